@@ -5,6 +5,7 @@ pub mod ports {
 pub mod config;
 pub mod email_client;
 pub mod error_counter;
+pub mod notifier;
 pub mod pairtrade;
 pub mod rate_limit_notifier;
 pub mod trade;
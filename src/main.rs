@@ -150,19 +150,35 @@ async fn run_batch(batch_file: &str) -> std::io::Result<()> {
         return Ok(());
     }
 
-    // Load replay data once.
-    let backtest_file = env::var("BACKTEST_FILE").map_err(|_| {
-        std::io::Error::new(
-            std::io::ErrorKind::InvalidInput,
-            "BACKTEST_FILE must be set for batch mode",
-        )
-    })?;
+    // Load replay data once. BACKTEST_FILES (comma-separated, `*` allowed
+    // in file names) takes precedence over BACKTEST_FILE.
+    let backtest_files: Vec<String> = env::var("BACKTEST_FILES")
+        .unwrap_or_default()
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect();
+    let backtest_file = if backtest_files.is_empty() {
+        env::var("BACKTEST_FILE").map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "BACKTEST_FILE or BACKTEST_FILES must be set for batch mode",
+            )
+        })?
+    } else {
+        backtest_files.join(",")
+    };
     eprintln!(
         "[BATCH] Loading data from {} ({} param sets)...",
         backtest_file,
         param_sets.len()
     );
-    let replay = Arc::new(ReplayConnector::new(&backtest_file).map_err(|e| {
+    let loaded = if backtest_files.is_empty() {
+        ReplayConnector::new(&backtest_file)
+    } else {
+        ReplayConnector::from_files(&backtest_files)
+    };
+    let replay = Arc::new(loaded.map_err(|e| {
         std::io::Error::new(
             std::io::ErrorKind::Other,
             format!("failed to load replay data: {}", e),
@@ -0,0 +1,175 @@
+//! Alert routing. Every operator alert goes through a `Notifier`, so the
+//! channel is a deployment choice: a chat webhook when `ALERT_WEBHOOK_URL`
+//! is set, otherwise email via `EmailClient` (the historical default).
+//! `ALERT_MIN_SEVERITY` (`info` | `warning` | `critical`, default `info`)
+//! drops alerts below the threshold.
+
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::email_client::EmailClient;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "INFO",
+            Severity::Warning => "WARNING",
+            Severity::Critical => "CRITICAL",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "info" => Ok(Severity::Info),
+            "warning" | "warn" => Ok(Severity::Warning),
+            "critical" | "crit" => Ok(Severity::Critical),
+            _ => Err(()),
+        }
+    }
+}
+
+pub trait Notifier: Send + Sync {
+    fn notify(&self, severity: Severity, subject: &str, body: &str);
+}
+
+impl Notifier for EmailClient {
+    fn notify(&self, severity: Severity, subject: &str, body: &str) {
+        self.send(&format!("[{}] {}", severity, subject), body);
+    }
+}
+
+/// Slack/Discord-compatible incoming webhook. The payload carries the
+/// message under both `text` (Slack) and `content` (Discord); each service
+/// ignores the other key.
+pub struct WebhookNotifier {
+    url: String,
+    agent: ureq::Agent,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: String) -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(10))
+            .build();
+        Self { url, agent }
+    }
+
+    fn payload(severity: Severity, subject: &str, body: &str) -> serde_json::Value {
+        let text = format!("[{}] {}\n{}", severity, subject, body);
+        serde_json::json!({ "text": text, "content": text })
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn notify(&self, severity: Severity, subject: &str, body: &str) {
+        let payload = Self::payload(severity, subject, body);
+        let result = self
+            .agent
+            .post(&self.url)
+            .set("Content-Type", "application/json")
+            .send_string(&payload.to_string());
+        if let Err(e) = result {
+            log::warn!("Failed to send webhook alert: {:?}", e);
+        }
+    }
+}
+
+/// Drops alerts below `min` before handing them to `inner`.
+pub struct SeverityFilter {
+    inner: Box<dyn Notifier>,
+    min: Severity,
+}
+
+impl Notifier for SeverityFilter {
+    fn notify(&self, severity: Severity, subject: &str, body: &str) {
+        if severity < self.min {
+            log::debug!(
+                "[ALERT] suppressed {} alert below {}: {}",
+                severity,
+                self.min,
+                subject
+            );
+            return;
+        }
+        self.inner.notify(severity, subject, body);
+    }
+}
+
+/// Build the configured notifier: webhook when `ALERT_WEBHOOK_URL` is set,
+/// email otherwise, filtered by `ALERT_MIN_SEVERITY`.
+pub fn from_env() -> Box<dyn Notifier> {
+    let inner: Box<dyn Notifier> = match env::var("ALERT_WEBHOOK_URL")
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+    {
+        Some(url) => Box::new(WebhookNotifier::new(url.trim().to_string())),
+        None => Box::new(EmailClient::new()),
+    };
+    let min = env::var("ALERT_MIN_SEVERITY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(Severity::Info);
+    Box::new(SeverityFilter { inner, min })
+}
+
+/// Send one alert through the configured channel.
+pub fn notify(severity: Severity, subject: &str, body: &str) {
+    from_env().notify(severity, subject, body);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    struct Recorder(Arc<Mutex<Vec<Severity>>>);
+
+    impl Notifier for Recorder {
+        fn notify(&self, severity: Severity, _subject: &str, _body: &str) {
+            self.0.lock().unwrap().push(severity);
+        }
+    }
+
+    #[test]
+    fn severity_filter_drops_below_threshold() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let filter = SeverityFilter {
+            inner: Box::new(Recorder(seen.clone())),
+            min: "warning".parse().unwrap(),
+        };
+        filter.notify(Severity::Info, "a", "");
+        filter.notify(Severity::Warning, "b", "");
+        filter.notify(Severity::Critical, "c", "");
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![Severity::Warning, Severity::Critical]
+        );
+    }
+
+    #[test]
+    fn webhook_payload_is_slack_and_discord_compatible() {
+        let payload =
+            WebhookNotifier::payload(Severity::Critical, "Startup close failed", "BTC open");
+        let expected = "[CRITICAL] Startup close failed\nBTC open";
+        assert_eq!(payload["text"], expected);
+        assert_eq!(payload["content"], expected);
+    }
+}
@@ -38,9 +38,11 @@ pub(super) async fn create_connector(
     Option<Arc<ReplayConnector>>,
 )> {
     if cfg.backtest_mode {
-        let replay = Arc::new(ReplayConnector::new(
-            cfg.backtest_file.as_ref().unwrap().as_str(),
-        )?);
+        let replay = Arc::new(if cfg.backtest_files.is_empty() {
+            ReplayConnector::new(cfg.backtest_file.as_ref().unwrap().as_str())?
+        } else {
+            ReplayConnector::from_files(&cfg.backtest_files)?
+        });
         let primary: Arc<dyn DexConnector + Send + Sync> = replay.clone();
         let n = cfg.strategies.len().max(1);
         let instance_connectors = std::iter::repeat(primary.clone()).take(n).collect();
@@ -71,7 +73,8 @@ pub(super) async fn create_connector(
             None,
         )
         .await
-        .context("failed to initialize connector")?;
+        .context("failed to initialize connector")?
+        .with_order_rate_limit(cfg.max_orders_per_symbol_per_min);
         conn.start()
             .await
             .context("failed to start connector")?;
@@ -113,7 +116,8 @@ pub(super) async fn create_connector(
                 Some(strategy.id.as_str()),
             )
             .await
-            .with_context(|| format!("failed to initialize connector for {}", strategy.id))?;
+            .with_context(|| format!("failed to initialize connector for {}", strategy.id))?
+            .with_order_rate_limit(cfg.max_orders_per_symbol_per_min);
             conn.start()
                 .await
                 .with_context(|| format!("failed to start connector for {}", strategy.id))?;
@@ -22,6 +22,9 @@ pub(super) struct BarBuilder {
     /// earliest tick of the bucket. See pairtrade#4.
     close_ts: Option<i64>,
     open_ts: Option<i64>,
+    /// Longest bar-start-to-closing-tick distance still emitted as a bar;
+    /// beyond it the bar straddled a data gap and is dropped. 0 = no limit.
+    max_span_secs: i64,
 }
 
 impl BarBuilder {
@@ -35,9 +38,21 @@ impl BarBuilder {
             close: Decimal::ZERO,
             close_ts: None,
             open_ts: None,
+            max_span_secs: 0,
         }
     }
 
+    /// Drop bars whose closing tick arrives more than `mult` windows after
+    /// the bar start (`max_bar_span_mult`). `mult <= 0` keeps every bar.
+    pub(super) fn with_max_span_mult(mut self, mult: f64) -> Self {
+        self.max_span_secs = if mult > 0.0 {
+            (self.window_secs as f64 * mult).round() as i64
+        } else {
+            0
+        };
+        self
+    }
+
     /// Align a timestamp down to the wall-clock bucket boundary.
     ///
     /// Buckets are anchored to the Unix epoch (`floor(ts / window) * window`),
@@ -78,6 +93,17 @@ impl BarBuilder {
                     self.close = price;
                     self.close_ts = Some(ts);
                     self.open_ts = Some(ts);
+                    let span = ts.saturating_sub(start);
+                    if self.max_span_secs > 0 && span > self.max_span_secs {
+                        log::debug!(
+                            "[BAR] dropped bar starting {} closed {}s later (> {}s); restarting at {}",
+                            start,
+                            span,
+                            self.max_span_secs,
+                            current_bucket
+                        );
+                        return None;
+                    }
                     Some((prev_close, bar_close_ts))
                 } else {
                     // Within the same bucket: pick the tick with the largest
@@ -114,3 +140,27 @@ impl BarBuilder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_bar_straddling_a_gap() {
+        let p = |v: i64| Decimal::from(v);
+        let mut plain = BarBuilder::new(60);
+        let mut capped = BarBuilder::new(60).with_max_span_mult(3.0);
+        for builder in [&mut plain, &mut capped] {
+            assert_eq!(builder.push(0, p(1)), None);
+            assert_eq!(builder.push(30, p(2)), None);
+        }
+        // Next bucket: both emit the bar.
+        assert_eq!(plain.push(70, p(3)), Some((p(2), 60)));
+        assert_eq!(capped.push(70, p(3)), Some((p(2), 60)));
+
+        // An hour of silence: only the uncapped builder emits the stale bar.
+        assert_eq!(plain.push(3_660, p(9)), Some((p(3), 120)));
+        assert_eq!(capped.push(3_660, p(9)), None);
+        assert_eq!(capped.push(3_720, p(10)), Some((p(9), 3_720)));
+    }
+}
@@ -0,0 +1,52 @@
+//! Monotonic time source for the engine. Pending-order timeouts, cooldowns,
+//! caches and log rate limits read the time through `Clock` so tests can
+//! drive them with `ManualClock` instead of real sleeps. Replay-aware paths
+//! keep using the bar timestamp (`now_ts`), not this clock.
+
+use std::time::{Duration, Instant};
+
+pub(super) trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Time since `since`, saturating at zero.
+    fn elapsed(&self, since: Instant) -> Duration {
+        self.now().saturating_duration_since(since)
+    }
+}
+
+/// Wall-clock implementation used outside tests.
+pub(super) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Test clock that only moves when `advance` is called.
+#[cfg(test)]
+pub(super) struct ManualClock {
+    base: Instant,
+    offset: std::sync::Mutex<Duration>,
+}
+
+#[cfg(test)]
+impl ManualClock {
+    pub(super) fn new() -> Self {
+        Self {
+            base: Instant::now(),
+            offset: std::sync::Mutex::new(Duration::ZERO),
+        }
+    }
+
+    pub(super) fn advance(&self, by: Duration) {
+        *self.offset.lock().unwrap() += by;
+    }
+}
+
+#[cfg(test)]
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.base + *self.offset.lock().unwrap()
+    }
+}
@@ -11,6 +11,7 @@ use anyhow::{anyhow, Context, Result};
 use serde::Deserialize;
 
 use super::defaults::*;
+use super::schedule::{self, NoTradeWindow};
 
 /// Resolved per-pair parameters (global defaults merged with any pair-specific overrides).
 #[derive(Debug, Clone, Default)]
@@ -22,6 +23,9 @@ pub struct PairParams {
     pub stop_loss_z: f64,
     pub force_close_secs: u64,
     pub cooldown_secs: u64,
+    /// Cooldown applied instead of `cooldown_secs` after a `stop_loss_z` exit.
+    /// `None` = same as `cooldown_secs`.
+    pub stop_loss_cooldown_secs: Option<u64>,
     pub max_loss_r_mult: f64,
     pub half_life_max_hours: f64,
     pub adf_p_threshold: f64,
@@ -52,6 +56,15 @@ pub struct PairParams {
     pub std_collapse_window_bars: usize,
     pub std_collapse_min_ratio: f64,
     pub std_collapse_observe_only: bool,
+    /// Spread std (spread units) below which the pair is untradeable: z is
+    /// not evaluated for entries, since trivial moves of a near-constant
+    /// spread blow it up. Per pair, as spread scales differ. 0 = disabled.
+    pub min_spread_std: f64,
+    /// Fixed leg-A notional (USD) replacing the equity/risk-based sizing for
+    /// this pair; vol targeting, the notional cap and `max_leverage` (gross
+    /// of both legs against equity) still apply. Set from a structured
+    /// `universe` entry or `pair_overrides`. None = risk-based.
+    pub notional_per_leg_usd: Option<f64>,
 }
 
 #[derive(Debug, Clone)]
@@ -76,24 +89,39 @@ pub(super) fn parse_pairs_vec(pairs: &[String]) -> Result<Vec<PairSpec>> {
     parse_pairs_list(&joined)
 }
 
-pub(super) fn parse_symbols_vec(symbols: &[String]) -> Result<Vec<PairSpec>> {
-    let syms: Vec<String> = symbols
-        .iter()
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty())
-        .collect();
-    if syms.is_empty() {
-        return Err(anyhow!("UNIVERSE_SYMBOLS produced no valid pairs"));
-    }
+/// `preserve_symbol_order`: the env var wins over the yaml value, so the
+/// universe resolved from yaml and the config field always agree.
+fn preserve_symbol_order_from(yaml_value: Option<bool>) -> bool {
+    env_bool("PRESERVE_SYMBOL_ORDER")
+        .or(yaml_value)
+        .unwrap_or(DEFAULT_PRESERVE_SYMBOL_ORDER)
+}
+
+/// Every pair of `syms`, alphabetized within each pair unless
+/// `preserve_order`, in which case the earlier-listed symbol is the base.
+fn symbol_pairs(syms: &[String], preserve_order: bool) -> Vec<PairSpec> {
     let mut pairs = Vec::new();
     for i in 0..syms.len() {
         for j in (i + 1)..syms.len() {
             let a = syms[i].clone();
             let b = syms[j].clone();
-            let (base, quote) = if a < b { (a, b) } else { (b, a) };
+            let (base, quote) = if preserve_order || a < b { (a, b) } else { (b, a) };
             pairs.push(PairSpec { base, quote });
         }
     }
+    pairs
+}
+
+pub(super) fn parse_symbols_vec(symbols: &[String], preserve_order: bool) -> Result<Vec<PairSpec>> {
+    let syms: Vec<String> = symbols
+        .iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    if syms.is_empty() {
+        return Err(anyhow!("UNIVERSE_SYMBOLS produced no valid pairs"));
+    }
+    let pairs = symbol_pairs(&syms, preserve_order);
     if pairs.is_empty() {
         return Err(anyhow!("UNIVERSE_SYMBOLS produced no valid pairs"));
     }
@@ -101,28 +129,78 @@ pub(super) fn parse_symbols_vec(symbols: &[String]) -> Result<Vec<PairSpec>> {
 }
 
 pub(super) fn resolve_universe_from_yaml(yaml: &PairTradeYaml) -> Result<Vec<PairSpec>> {
+    let preserve_order = preserve_symbol_order_from(yaml.preserve_symbol_order);
     if env_has_universe_override() {
-        return parse_universe_pairs();
+        return parse_universe_pairs(preserve_order);
     }
-    if let Some(pairs) = yaml.universe_pairs.clone() {
+    let entries = yaml.universe.as_deref().unwrap_or_default();
+    let mut pairs = if let Some(pairs) = yaml.universe_pairs.clone() {
         let pairs = pairs.into_vec();
         if pairs.is_empty() {
             return Err(anyhow!("universe_pairs produced no valid pairs"));
         }
-        return parse_pairs_vec(&pairs);
-    }
-    if let Some(symbols) = yaml.universe_symbols.clone() {
+        parse_pairs_vec(&pairs)?
+    } else if let Some(symbols) = yaml.universe_symbols.clone() {
         let symbols = symbols.into_vec();
         if symbols.is_empty() {
             return Err(anyhow!("universe_symbols produced no valid pairs"));
         }
-        return parse_symbols_vec(&symbols);
+        parse_symbols_vec(&symbols, preserve_order)?
+    } else if entries.is_empty() {
+        let raw = "BTC/ETH,BTC/SOL,ETH/SOL".to_string();
+        parse_pairs_list(&raw)?
+    } else {
+        Vec::new()
+    };
+    // Structured entries extend the flat list; a disabled entry also takes
+    // its pair out of it, so a pair can be toggled without editing the list.
+    for entry in entries {
+        let same = |p: &PairSpec| p.base == entry.base && p.quote == entry.quote;
+        if entry.is_enabled() {
+            if !pairs.iter().any(same) {
+                pairs.push(PairSpec {
+                    base: entry.base.clone(),
+                    quote: entry.quote.clone(),
+                });
+            }
+        } else {
+            log::info!("[UNIVERSE] {}/{} disabled in config", entry.base, entry.quote);
+            pairs.retain(|p| !same(p));
+        }
     }
-    let raw = "BTC/ETH,BTC/SOL,ETH/SOL".to_string();
-    parse_pairs_list(&raw)
+    if pairs.is_empty() {
+        return Err(anyhow!("universe has no enabled pairs"));
+    }
+    Ok(pairs)
+}
+
+/// `pair_overrides` with the per-pair settings of enabled structured
+/// `universe` entries merged in; an entry's fields win over the same
+/// pair's `pair_overrides` fields.
+fn merged_pair_overrides(yaml: &PairTradeYaml) -> Option<HashMap<String, PairOverrideYaml>> {
+    let entries = yaml.universe.as_deref().unwrap_or_default();
+    if entries.is_empty() {
+        return yaml.pair_overrides.clone();
+    }
+    let mut merged = yaml.pair_overrides.clone().unwrap_or_default();
+    for entry in entries.iter().filter(|e| e.is_enabled()) {
+        if entry.entry_z.is_none() && entry.notional_per_leg_usd.is_none() {
+            continue;
+        }
+        let ovr = merged
+            .entry(format!("{}/{}", entry.base, entry.quote))
+            .or_default();
+        if entry.entry_z.is_some() {
+            ovr.entry_z_score_base = entry.entry_z;
+        }
+        if entry.notional_per_leg_usd.is_some() {
+            ovr.notional_per_leg_usd = entry.notional_per_leg_usd;
+        }
+    }
+    Some(merged)
 }
 
-pub(super) fn parse_universe_pairs() -> Result<Vec<PairSpec>> {
+pub(super) fn parse_universe_pairs(preserve_order: bool) -> Result<Vec<PairSpec>> {
     if let Ok(raw_pairs) = env::var("UNIVERSE_PAIRS") {
         if !raw_pairs.trim().is_empty() {
             return parse_pairs_list(&raw_pairs);
@@ -135,23 +213,15 @@ pub(super) fn parse_universe_pairs() -> Result<Vec<PairSpec>> {
                 .map(|s| s.trim().to_string())
                 .filter(|s| !s.is_empty())
                 .collect();
-            let mut pairs = Vec::new();
-            if syms.len() == 1 {
+            let pairs = if syms.len() == 1 {
                 // Single-symbol mode: create a self-pair for data-dump collection
-                pairs.push(PairSpec {
+                vec![PairSpec {
                     base: syms[0].clone(),
                     quote: syms[0].clone(),
-                });
+                }]
             } else {
-                for i in 0..syms.len() {
-                    for j in (i + 1)..syms.len() {
-                        let a = syms[i].clone();
-                        let b = syms[j].clone();
-                        let (base, quote) = if a < b { (a, b) } else { (b, a) };
-                        pairs.push(PairSpec { base, quote });
-                    }
-                }
-            }
+                symbol_pairs(&syms, preserve_order)
+            };
             if pairs.is_empty() {
                 return Err(anyhow!("UNIVERSE_SYMBOLS produced no valid pairs"));
             }
@@ -194,6 +264,25 @@ pub(super) enum StringOrVec {
     Vec(Vec<String>),
 }
 
+/// Structured `universe` entry: a pair that can be switched off with
+/// `enabled: false` and tuned in place (`entry_z` sets the pair's
+/// `entry_z_score_base`).
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub(super) struct UniverseEntryYaml {
+    pub(super) base: String,
+    pub(super) quote: String,
+    pub(super) enabled: Option<bool>,
+    pub(super) notional_per_leg_usd: Option<f64>,
+    pub(super) entry_z: Option<f64>,
+}
+
+impl UniverseEntryYaml {
+    fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+}
+
 impl StringOrVec {
     pub(super) fn into_vec(self) -> Vec<String> {
         match self {
@@ -229,6 +318,7 @@ pub(super) struct PairTradeYaml {
     pub(super) stop_loss_z_score: Option<f64>,
     pub(super) force_close_time_secs: Option<u64>,
     pub(super) cooldown_secs: Option<u64>,
+    pub(super) stop_loss_cooldown_secs: Option<u64>,
     pub(super) net_funding_min_per_hour: Option<f64>,
     pub(super) spread_velocity_max_sigma_per_min: Option<f64>,
     pub(super) notional_per_leg_usd: Option<f64>,
@@ -237,6 +327,7 @@ pub(super) struct PairTradeYaml {
     pub(super) equity_usd_reference: Option<f64>,
     pub(super) universe_pairs: Option<StringOrVec>,
     pub(super) universe_symbols: Option<StringOrVec>,
+    pub(super) universe: Option<Vec<UniverseEntryYaml>>,
     pub(super) pair_selection_lookback_hours_short: Option<u64>,
     pub(super) pair_selection_lookback_hours_long: Option<u64>,
     pub(super) half_life_max_hours: Option<f64>,
@@ -264,6 +355,7 @@ pub(super) struct PairTradeYaml {
     pub(super) history_archive_retention_days: Option<u32>,
     pub(super) backtest_mode: Option<bool>,
     pub(super) backtest_file: Option<String>,
+    pub(super) backtest_files: Option<StringOrVec>,
     pub(super) spread_trend_max_slope_sigma: Option<f64>,
     pub(super) beta_divergence_max: Option<f64>,
     pub(super) beta_min: Option<f64>,
@@ -284,6 +376,7 @@ pub(super) struct PairTradeYaml {
     pub(super) std_collapse_window_bars: Option<usize>,
     pub(super) std_collapse_min_ratio: Option<f64>,
     pub(super) std_collapse_observe_only: Option<bool>,
+    pub(super) min_spread_std: Option<f64>,
     pub(super) pair_overrides: Option<HashMap<String, PairOverrideYaml>>,
     /// Graceful shutdown: max seconds to wait for natural exit on SIGTERM before
     /// force-closing both legs. 0 = immediate force close (legacy behavior).
@@ -307,6 +400,124 @@ pub(super) struct PairTradeYaml {
     pub(super) regime_reference_symbol: Option<String>,
     // Daily drawdown limit (bot-strategy#185 Phase 2)
     pub(super) risk: Option<RiskYaml>,
+    // Equity guard: block entries on low equity / repeated balance failures
+    pub(super) min_equity_usd: Option<f64>,
+    pub(super) max_balance_fetch_failures: Option<u32>,
+    pub(super) align_bar_clock: Option<bool>,
+    pub(super) history_price_format: Option<String>,
+    pub(super) avoid_funding_window_secs: Option<u64>,
+    pub(super) funding_interval_secs: Option<u64>,
+    pub(super) close_profitable_before_funding: Option<bool>,
+    pub(super) vol_target_sigma: Option<f64>,
+    pub(super) vol_target_min_notional: Option<f64>,
+    pub(super) vol_target_max_notional: Option<f64>,
+    pub(super) reconcile_concurrency: Option<usize>,
+    pub(super) data_dump_compress: Option<bool>,
+    pub(super) trade_stats_reset: Option<String>,
+    pub(super) spread_smoothing_bars: Option<usize>,
+    pub(super) max_spread_gap_bars: Option<usize>,
+    pub(super) dust_sweep: Option<bool>,
+    pub(super) beta_on_returns: Option<bool>,
+    pub(super) orderbook_depth: Option<usize>,
+    pub(super) orderbook_depth_band_bps: Option<f64>,
+    pub(super) exit_z_require_profit: Option<bool>,
+    pub(super) startup_force_close_backoff_mult: Option<f64>,
+    pub(super) startup_force_close_max_wait_secs: Option<u64>,
+    pub(super) startup_force_close_jitter_pct: Option<f64>,
+    pub(super) startup_cancel_wait_secs: Option<u64>,
+    pub(super) observe_simulate_pnl: Option<bool>,
+    pub(super) auto_flatten_mismatched_legs: Option<bool>,
+    pub(super) z_price_source: Option<String>,
+    pub(super) no_trade_windows: Option<Vec<String>>,
+    pub(super) forensic_dump_dir: Option<String>,
+    pub(super) forensic_dump_keep: Option<usize>,
+    pub(super) min_book_notional_usd: Option<f64>,
+    pub(super) hedge_ratio_tolerance_pct: Option<f64>,
+    pub(super) status_price_dp: Option<u32>,
+    pub(super) status_pnl_dp: Option<u32>,
+    pub(super) ticker_auth_retries: Option<u32>,
+    pub(super) ticker_auth_retry_ms: Option<u64>,
+    pub(super) normalize_legs: Option<bool>,
+    pub(super) max_reissues_per_pair: Option<u32>,
+    pub(super) max_reissued_legs_per_cycle: Option<usize>,
+    pub(super) bar_source: Option<String>,
+    pub(super) pending_max_age_secs: Option<u64>,
+    pub(super) equity_baseline_samples: Option<usize>,
+    pub(super) history_backend: Option<String>,
+    pub(super) allow_negative_beta: Option<bool>,
+    pub(super) pair_idle_alert_secs: Option<u64>,
+    pub(super) pair_idle_notify: Option<bool>,
+    pub(super) max_min_order_oversizing_factor: Option<f64>,
+    pub(super) position_audit_interval_secs: Option<u64>,
+    pub(super) position_audit_force_close: Option<bool>,
+    pub(super) exit_post_only_wait_reasons: Option<StringOrVec>,
+    pub(super) seed_new_pairs_from_history: Option<bool>,
+    pub(super) max_abs_funding_per_hour: Option<f64>,
+    pub(super) matching_model: Option<String>,
+    pub(super) max_orders_per_symbol_per_min: Option<u32>,
+    pub(super) backtest_apply_costs: Option<bool>,
+    pub(super) pair_eval_interval_secs: Option<u64>,
+    pub(super) strict_universe_validation: Option<bool>,
+    pub(super) skip_unchanged_bars: Option<bool>,
+    pub(super) max_realized_slippage_bps: Option<f64>,
+    pub(super) realized_slippage_block_secs: Option<u64>,
+    pub(super) persist_trade_stats: Option<bool>,
+    pub(super) backtest_full_sync: Option<bool>,
+    pub(super) log_price_snapshot_every_n_ticks: Option<u64>,
+    pub(super) log_zcheck_every_n_ticks: Option<u64>,
+    pub(super) log_order_params_every_n_ticks: Option<u64>,
+    pub(super) beta_drift_exit_pct: Option<f64>,
+    pub(super) beta_drift_action: Option<String>,
+    pub(super) z_stat_mode: Option<String>,
+    pub(super) max_open_orders: Option<usize>,
+    pub(super) entry_max_book_spread_bps: Option<f64>,
+    pub(super) eligibility_max_age_secs: Option<u64>,
+    pub(super) reconcile_fill_pnl: Option<bool>,
+    pub(super) dust_notional_usd: Option<f64>,
+    pub(super) dust_notional_usd_by_symbol: Option<HashMap<String, f64>>,
+    pub(super) exit_market_reasons: Option<StringOrVec>,
+    pub(super) position_age_warn_secs: Option<u64>,
+    pub(super) directional_eligibility: Option<bool>,
+    pub(super) max_bar_span_mult: Option<f64>,
+    pub(super) min_interval_secs: Option<u64>,
+    pub(super) max_interval_secs: Option<u64>,
+    pub(super) min_hold_secs: Option<u64>,
+    pub(super) eval_history_file: Option<String>,
+    pub(super) partial_take_profit_fraction: Option<f64>,
+    pub(super) partial_take_profit_r: Option<f64>,
+    pub(super) status_size_adjustments: Option<bool>,
+    pub(super) max_equity_fallback_cycles: Option<u32>,
+    pub(super) min_spread_history_points: Option<usize>,
+    pub(super) min_symbol_history_bars: Option<usize>,
+    pub(super) shared_symbol_policy: Option<String>,
+    pub(super) max_net_symbol_exposure_usd: Option<f64>,
+    pub(super) metrics_log_interval_secs: Option<u64>,
+    pub(super) metrics_log_format: Option<String>,
+    pub(super) metrics_log_detail: Option<bool>,
+    pub(super) portfolio_funding_aware: Option<bool>,
+    pub(super) preserve_symbol_order: Option<bool>,
+    pub(super) single_position_mode: Option<bool>,
+    pub(super) reject_crossed_book: Option<bool>,
+    pub(super) equity_history_detailed: Option<bool>,
+    pub(super) auto_restart_after_failures: Option<u32>,
+    pub(super) auto_restart_cooldown_secs: Option<u64>,
+    pub(super) maker_fee_bps_by_symbol: Option<HashMap<String, f64>>,
+    pub(super) taker_fee_bps_by_symbol: Option<HashMap<String, f64>>,
+    pub(super) max_bar_log_return: Option<f64>,
+    pub(super) scale_in_enabled: Option<bool>,
+    pub(super) scale_in_add_z: Option<f64>,
+    pub(super) scale_in_fraction: Option<f64>,
+    pub(super) non_finite_stats_block_secs: Option<u64>,
+    pub(super) entry_market_mode: Option<bool>,
+    pub(super) rejected_entries_file: Option<String>,
+    pub(super) min_eligible_pairs: Option<usize>,
+    pub(super) plan_trace_file: Option<String>,
+    pub(super) loss_reentry_block_ticks: Option<u64>,
+    pub(super) missed_tick_behavior: Option<String>,
+    pub(super) delta_rebalance_enabled: Option<bool>,
+    pub(super) rebalance_threshold_usd: Option<f64>,
+    pub(super) delta_rebalance_interval_secs: Option<u64>,
+    pub(super) recover_entry_time_from_risk_state: Option<bool>,
 }
 
 /// `risk:` YAML block for cross-session safety limits. Phase 2 covers
@@ -374,6 +585,7 @@ pub(super) struct PairOverrideYaml {
     pub(super) stop_loss_z_score: Option<f64>,
     pub(super) force_close_time_secs: Option<u64>,
     pub(super) cooldown_secs: Option<u64>,
+    pub(super) stop_loss_cooldown_secs: Option<u64>,
     pub(super) max_loss_r_mult: Option<f64>,
     pub(super) half_life_max_hours: Option<f64>,
     pub(super) adf_p_threshold: Option<f64>,
@@ -393,6 +605,8 @@ pub(super) struct PairOverrideYaml {
     pub(super) circuit_breaker_tier2_losses: Option<u32>,
     pub(super) circuit_breaker_tier2_cooldown_secs: Option<u64>,
     pub(super) entry_post_only_timeout_secs: Option<u64>,
+    pub(super) min_spread_std: Option<f64>,
+    pub(super) notional_per_leg_usd: Option<f64>,
 }
 
 #[derive(Debug, Clone)]
@@ -422,16 +636,27 @@ pub struct PairTradeConfig {
     pub force_close_on_startup: bool,
     // For data dump feature
     pub enable_data_dump: bool,
+    /// A `.gz` suffix enables gzip (one new segment per run, see
+    /// `data_dump_compress`).
     pub data_dump_file: Option<String>,
     // Safety guard to avoid real orders while observing market data
     pub observe_only: bool,
     pub disable_history_persist: bool,
+    /// A `.gz` suffix writes the snapshot gzip-compressed; loading detects
+    /// gzip by content, so either form is readable.
     pub history_file: String,
     pub history_archive_dir: Option<String>,
     pub history_archive_retention_days: u32,
     // For backtest feature
     pub backtest_mode: bool,
     pub backtest_file: Option<String>,
+    /// Replay inputs read back to back as one stream, e.g. monthly dumps.
+    /// Entries may use `*` in the file name (`dumps/2025-*.bin`); matches
+    /// are taken in name order. Files must be chronological; gaps and
+    /// overlaps between consecutive files are logged and overlapping
+    /// records dropped. Takes precedence over `backtest_file`.
+    /// Env: BACKTEST_FILES, comma-separated (default empty).
+    pub backtest_files: Vec<String>,
     /// Path to a history snapshot file for BT warm-start. When set,
     /// the replay loads price history from this file before the first
     /// tick, giving the BT an identical starting state to a live bot.
@@ -499,6 +724,567 @@ pub struct PairTradeConfig {
     pub regime_reference_symbol: String,
     // Daily drawdown limit (bot-strategy#185 Phase 2)
     pub risk: RiskConfig,
+    /// Block new entries while the last fetched account equity is below
+    /// this many USD. 0.0 disables the check (default).
+    pub min_equity_usd: f64,
+    /// Block new entries once this many consecutive `get_balance` calls
+    /// have failed, instead of sizing against the stale cached equity.
+    /// 0 disables the check (default).
+    pub max_balance_fetch_failures: u32,
+    /// Bucket every symbol's bar by the engine's wall-clock `now_ts`
+    /// instead of the per-symbol exchange ts, so both legs of a pair close
+    /// on the same tick and evaluation is never delayed by a one-sided
+    /// close. Env: ALIGN_BAR_CLOCK (default false).
+    pub align_bar_clock: bool,
+    /// Encoding of the persisted price history. Env: HISTORY_PRICE_FORMAT
+    /// (`log_f64` default, or `decimal`). Files of either version load
+    /// regardless of this setting.
+    pub history_price_format: HistoryPriceFormat,
+    /// Block new entries this many seconds before the next funding time.
+    /// 0 disables (default). Env: AVOID_FUNDING_WINDOW_SECS.
+    pub avoid_funding_window_secs: u64,
+    /// Venue funding cadence; funding happens at epoch-aligned multiples.
+    /// The funding window uses the venue's own schedule when `dex_name` is
+    /// a known venue and falls back to this otherwise; 0 = schedule
+    /// unknown, which turns the window into a no-op.
+    /// Env: FUNDING_INTERVAL_SECS.
+    pub funding_interval_secs: u64,
+    /// Inside the funding window, also close positions that are currently
+    /// in profit. Env: CLOSE_PROFITABLE_BEFORE_FUNDING (default false).
+    pub close_profitable_before_funding: bool,
+    /// Reference spread std for vol-targeted sizing. When > 0, leg
+    /// notional is multiplied by `vol_target_sigma / spread_std` so quiet
+    /// and wild pairs carry a similar dollar-vol. 0.0 disables (default).
+    /// Env: VOL_TARGET_SIGMA.
+    pub vol_target_sigma: f64,
+    /// Lower / upper bounds on the vol-scaled leg notional (USD). An upper
+    /// bound of 0.0 caps the scaling at 4x the unscaled notional; the
+    /// result is also held within `max_leverage`. Env:
+    /// VOL_TARGET_MIN_NOTIONAL, VOL_TARGET_MAX_NOTIONAL.
+    pub vol_target_min_notional: f64,
+    pub vol_target_max_notional: f64,
+    /// Max pairs whose pending-order status is fetched concurrently before
+    /// the serial reconcile pass. 1 keeps the sequential per-pair fetch
+    /// (default). Env: RECONCILE_CONCURRENCY.
+    pub reconcile_concurrency: usize,
+    /// Gzip the data dump even when `data_dump_file` does not end in `.gz`.
+    /// Env: DATA_DUMP_COMPRESS (default false). Compressed dumps open a new
+    /// `.gz` segment per run instead of appending; see `data_dump.rs`.
+    pub data_dump_compress: bool,
+    /// Reset cadence of the per-pair trade stats. Env: TRADE_STATS_RESET
+    /// (`session` default, or `daily` at `daily_reset_utc_hour`).
+    pub trade_stats_reset: TradeStatsReset,
+    /// Feed `spread_history` with the simple moving average of the last N
+    /// raw spreads instead of the raw value, so z/mean/std run on the
+    /// smoothed series. 0 or 1 disables (default). Env: SPREAD_SMOOTHING_BARS.
+    pub spread_smoothing_bars: usize,
+    /// Block entries while the spread window contains a timestamp gap of
+    /// more than this many missing bars (e.g. after downtime), since z would
+    /// mix discontinuous periods. 0 disables (default). Env:
+    /// MAX_SPREAD_GAP_BARS.
+    pub max_spread_gap_bars: usize,
+    /// When a pair is left holding only a sub-`min_order` remnant on one leg,
+    /// try one reduce-only close of it (ceiling-quantized); if that is still
+    /// below the exchange minimum or the close fails, accept the dust and
+    /// keep the pair flat. Env: DUST_SWEEP (default false).
+    pub dust_sweep: bool,
+    /// Estimate the hedge beta by OLS on first-differenced log prices
+    /// (returns) instead of log levels, so a shared trend does not inflate
+    /// it. The spread is still built from levels. Env: BETA_ON_RETURNS
+    /// (default false).
+    pub beta_on_returns: bool,
+    /// Order-book levels fetched per symbol each tick. 1 = top-of-book only
+    /// (legacy). Deeper books feed `liquidity_score` and the fill-price
+    /// estimate used for sizing. Env: ORDERBOOK_DEPTH (default 1).
+    pub orderbook_depth: usize,
+    /// Band around the touch, in bps, whose cumulative size counts as
+    /// available depth. Env: ORDERBOOK_DEPTH_BAND_BPS (default 10).
+    pub orderbook_depth_band_bps: f64,
+    /// Only take the `exit_z` reversion exit when the position's PnL net of
+    /// estimated round-trip fees and slippage is positive; otherwise hold
+    /// for stop_loss / force_close. Env: EXIT_Z_REQUIRE_PROFIT (default false).
+    pub exit_z_require_profit: bool,
+    /// Growth factor applied to `startup_force_close_wait_secs` after each
+    /// startup close attempt. 1.0 keeps the fixed wait. Env:
+    /// STARTUP_FORCE_CLOSE_BACKOFF_MULT (default 1.0).
+    pub startup_force_close_backoff_mult: f64,
+    /// Upper bound on the backed-off wait (0 = unbounded). Env:
+    /// STARTUP_FORCE_CLOSE_MAX_WAIT_SECS (default 60).
+    pub startup_force_close_max_wait_secs: u64,
+    /// Random ± spread on each wait, as a fraction (0.2 = ±20%). Env:
+    /// STARTUP_FORCE_CLOSE_JITTER_PCT (default 0).
+    pub startup_force_close_jitter_pct: f64,
+    /// Pause between `cancel_all_orders` and the first close attempt. Env:
+    /// STARTUP_CANCEL_WAIT_SECS (default 0).
+    pub startup_cancel_wait_secs: u64,
+    /// Within `observe_only`, simulate paper fills and PnL against the live
+    /// feed through the dry-run path instead of just logging signals. No
+    /// orders are placed. Env: OBSERVE_SIMULATE_PNL (default false).
+    pub observe_simulate_pnl: bool,
+    /// Flatten both legs with reduce-only market orders when the exchange
+    /// reports them with the same sign (a net directional position, not a
+    /// spread) instead of guarding the pair indefinitely. Env:
+    /// AUTO_FLATTEN_MISMATCHED_LEGS (default false).
+    pub auto_flatten_mismatched_legs: bool,
+    /// Price fed into the bar builders, and so into the spread / z pipeline:
+    /// `last` (ticker price), `mid`, `bid` or `ask`. Order logging and
+    /// execution references keep using the ticker price. Env: Z_PRICE_SOURCE
+    /// (default last).
+    pub z_price_source: PriceSource,
+    /// UTC windows in which new entries are blocked (exits still run), e.g.
+    /// `sat-sun` or `mon-fri 22:00-02:00`; see `schedule`. Empty = always
+    /// trade. Env: NO_TRADE_WINDOWS, `;`-separated.
+    pub no_trade_windows: Vec<NoTradeWindow>,
+    /// Directory for JSON forensic snapshots written on every
+    /// `Inconsistent state` error. None = disabled. Env: FORENSIC_DUMP_DIR.
+    pub forensic_dump_dir: Option<String>,
+    /// Number of forensic snapshots kept; older ones are deleted. Env:
+    /// FORENSIC_DUMP_KEEP (default 20).
+    pub forensic_dump_keep: usize,
+    /// Minimum USD notional on the thinner side of each leg's book (top of
+    /// book, or the in-band depth when `orderbook_depth > 1`). Entry
+    /// candidates below it on either leg are skipped. 0 = disabled. Env:
+    /// MIN_BOOK_NOTIONAL_USD (default 0).
+    pub min_book_notional_usd: f64,
+    /// Tolerance, in percent, on the realized hedge ratio (leg-B notional /
+    /// leg-A notional vs |beta|) of a synced position. Within it the legs
+    /// are accepted as hedged; beyond it leg B is resized to |beta| with one
+    /// market order, at most once per 5 minutes per pair. 0 = no check.
+    /// Env: HEDGE_RATIO_TOLERANCE_PCT (default 0).
+    pub hedge_ratio_tolerance_pct: f64,
+    /// Decimal places for prices written to status.json and the PnL log.
+    /// None keeps full precision. Position sizes always use the venue's
+    /// `size_decimals` instead. Env: STATUS_PRICE_DP.
+    pub status_price_dp: Option<u32>,
+    /// Decimal places for PnL values written to status.json and the PnL
+    /// log. None keeps full precision. Env: STATUS_PNL_DP.
+    pub status_pnl_dp: Option<u32>,
+    /// In-cycle retries of a ticker fetch that failed with an auth-classified
+    /// error (403 / forbidden / undeserializable body) before the symbol is
+    /// skipped for the cycle. Env: TICKER_AUTH_RETRIES (default 0).
+    pub ticker_auth_retries: u32,
+    /// Delay between those retries. Env: TICKER_AUTH_RETRY_MS (default 250).
+    pub ticker_auth_retry_ms: u64,
+    /// Standardize each leg's log price (subtract the window mean, divide by
+    /// the window std) before forming the spread, so legs of very different
+    /// price level / volatility contribute on the same scale. Beta is then
+    /// fitted on, and the legs standardized over, `metrics_window`; it relates
+    /// standardized legs, so sizing scales it back by the legs' log-price std
+    /// ratio (std_a / std_b) first. Env: NORMALIZE_LEGS (default false).
+    pub normalize_legs: bool,
+    /// Cap on partial-fill reissues of one pending entry. Once exceeded the
+    /// remaining legs are cancelled, the filled part is market-closed and the
+    /// pending entry is cleared. Env: MAX_REISSUES_PER_PAIR (default 20).
+    pub max_reissues_per_pair: u32,
+    /// Legs `reissue_partial_legs` may re-place per cycle across all pairs;
+    /// further reissues keep their leg pending until the next tick.
+    /// Env: MAX_REISSUED_LEGS_PER_CYCLE (default 8).
+    pub max_reissued_legs_per_cycle: usize,
+    /// What drives the bar builders: `snapshot` (one sampled price per
+    /// symbol per cycle, default) or `trades` (every trade returned by
+    /// `get_last_trades` this cycle is folded in, so high/low/close reflect
+    /// actual prints). Trades carry no timestamp, so each cycle's batch is
+    /// stamped with the cycle's tick ts and the connector's trade buffer is
+    /// cleared once read; a failed or empty fetch falls back to the
+    /// snapshot price. Env: BAR_SOURCE (default snapshot).
+    pub bar_source: BarSource,
+    /// Backstop on how long a pair may keep pending orders, measured across
+    /// reissues. Past it the orders are cancelled, the exposure they leave is
+    /// market-closed and the pending state cleared, whatever the retry
+    /// counters say. 0 = disabled. Env: PENDING_MAX_AGE_SECS (default 1800).
+    pub pending_max_age_secs: u64,
+    /// Equity readings collected at the start of a UTC day before the daily
+    /// PnL baseline is fixed; the baseline is their median, so one glitched
+    /// balance read cannot skew the whole day's `pnl_today`. 1 = first
+    /// reading (previous behavior). Env: EQUITY_BASELINE_SAMPLES (default 1).
+    pub equity_baseline_samples: usize,
+    /// Where the log-price history is persisted: `json` (the whole snapshot
+    /// rewritten to `history_file` each bar, default) or `mmap` (one
+    /// fixed-size memory-mapped ring per symbol under `<history_file>.rings/`,
+    /// appended in O(1); the JSON file then only carries spread histories).
+    /// Ring files assume a single writer, so A/B/C bots sharing one history
+    /// file should stay on `json`. Env: HISTORY_BACKEND (default json).
+    pub history_backend: HistoryBackend,
+    /// Let the hedge beta go negative for inversely correlated pairs. Beta
+    /// is then clamped to ±[0.1, 10.0] by magnitude (keeping its sign)
+    /// instead of [0.1, 10.0], and a negative-beta pair trades both legs on
+    /// the same side: the spread `a − β·b` rises with b, so the hedge leg
+    /// flips. Env: ALLOW_NEGATIVE_BETA (default false).
+    pub allow_negative_beta: bool,
+    /// Warn once (`[IDLE]`) when a pair has gone this long without an
+    /// entry, counted from its last entry or from when it was first seen.
+    /// Re-armed by the next entry. 0 disables. Env: PAIR_IDLE_ALERT_SECS
+    /// (default 0).
+    pub pair_idle_alert_secs: u64,
+    /// Also send the `[IDLE]` alert through the notifier (Warning).
+    /// Env: PAIR_IDLE_NOTIFY (default false).
+    pub pair_idle_notify: bool,
+    /// Skip an entry when a leg's min-order notional is more than this many
+    /// times the intended leg notional, instead of flooring the leg up to
+    /// min_order (a $5 target leg becoming a $200 order on a small account).
+    /// 0 disables the check. Env: MAX_MIN_ORDER_OVERSIZING_FACTOR (default 0).
+    pub max_min_order_oversizing_factor: f64,
+    /// Every this many seconds compare the legs the engine tracks (open
+    /// positions, pending orders and guarded pairs across all instances)
+    /// with the exchange's non-dust positions and log a `[AUDIT]` report on
+    /// a mismatch. Two mismatching audits in a row count as persistent. 0
+    /// disables. Env: POSITION_AUDIT_INTERVAL_SECS (default 0).
+    pub position_audit_interval_secs: u64,
+    /// On a persistent audit mismatch, reduce-only market close exchange
+    /// positions the engine does not track. Env: POSITION_AUDIT_FORCE_CLOSE
+    /// (default false).
+    pub position_audit_force_close: bool,
+    /// Exit reasons (`exit_z`, `expected_value`, `ineligible`, ...) whose
+    /// post-only close does not fall back to a taker order once the
+    /// post-only attempts are exhausted; the exit is retried post-only on the
+    /// next cycle instead. `all` covers every reason. Stop-loss style exits
+    /// (`stop_loss_z`, `max_loss_r`, `force_close`) always fall back.
+    /// Env: EXIT_POST_ONLY_WAIT_REASONS, comma-separated (default empty).
+    pub exit_post_only_wait_reasons: Vec<String>,
+    /// When a universe reload adds a pair whose legs already have bar
+    /// history (shared with other pairs), backfill its `spread_history`
+    /// from those bars at the current beta so it can trade without waiting
+    /// a full window. Env: SEED_NEW_PAIRS_FROM_HISTORY (default false).
+    pub seed_new_pairs_from_history: bool,
+    /// Per-leg funding cap: entries are blocked while either leg's absolute
+    /// funding per hour (normalized by `funding_interval_secs`) exceeds it,
+    /// whatever the net carry. 0 disables. Env: MAX_ABS_FUNDING_PER_HOUR
+    /// (default 0).
+    pub max_abs_funding_per_hour: f64,
+    /// Fill model for dry-run / paper entries: `full` (whole order fills,
+    /// default), `price_time` (FIFO behind the displayed queue) or
+    /// `pro_rata` (size-proportional share of contra size). Partial fills
+    /// shrink both legs to the smaller fill ratio so the hedge is kept; a
+    /// leg with no simulated fill skips the entry. Paper exits always fill
+    /// in full. Env: MATCHING_MODEL (default full).
+    pub matching_model: MatchingModel,
+    /// Per-symbol token bucket in the live connector over opening
+    /// `create_order` calls; excess calls fail locally with an order error
+    /// instead of reaching the venue. Cancels and reduce-only orders are
+    /// exempt so exits are never throttled. 0 disables.
+    /// Env: MAX_ORDERS_PER_SYMBOL_PER_MIN (default 120).
+    pub max_orders_per_symbol_per_min: u32,
+    /// Backtest paper entries record a cost-adjusted fill instead of the raw
+    /// snapshot price (see `backtest_round_trip_cost_bps`), so `compute_pnl`
+    /// at exit is net of slippage and fees. Ignored outside backtest mode.
+    /// Env: BACKTEST_APPLY_COSTS (default true).
+    pub backtest_apply_costs: bool,
+    /// Periodic `evaluate_pair` cadence (beta / ADF / half-life refresh)
+    /// when no jump, velocity or vol-spike trigger fires. Clamped to >= 1.
+    /// Env: PAIR_EVAL_INTERVAL_SECS (default 3600).
+    pub pair_eval_interval_secs: u64,
+    /// Startup universe validation (positive price, known min_order /
+    /// min_tick per symbol): abort on any invalid symbol instead of
+    /// dropping the pairs that use it. Env: STRICT_UNIVERSE_VALIDATION
+    /// (default false).
+    pub strict_universe_validation: bool,
+    /// Skip spread / z / `evaluate_pair` work for a flat pair when neither
+    /// leg's new bar changed its close. The unchanged bar is then not pushed
+    /// into `spread_history`. Pairs with a position or pending orders are
+    /// always evaluated. Env: SKIP_UNCHANGED_BARS (default false).
+    pub skip_unchanged_bars: bool,
+    /// Block new entries on a symbol while the mean adverse slippage of its
+    /// last fully filled legs (fill price vs posted limit, or vs the price
+    /// when a market leg was placed) exceeds this. 0 disables.
+    /// Env: MAX_REALIZED_SLIPPAGE_BPS (default 0).
+    pub max_realized_slippage_bps: f64,
+    /// A `max_realized_slippage_bps` block lapses after this long and the
+    /// symbol's slippage window starts over; a blocked symbol takes no new
+    /// fills to recover on. 0 keeps the block until the window recovers.
+    /// Env: REALIZED_SLIPPAGE_BLOCK_SECS (default 3600).
+    pub realized_slippage_block_secs: u64,
+    /// Mirror the per-pair trade stats to `trade_stats.json` next to
+    /// `risk_state.json` after every trade and reload them on start, so an
+    /// intraday restart keeps the day's counts and PnL. Live only.
+    /// Env: PERSIST_TRADE_STATS (default true).
+    pub persist_trade_stats: bool,
+    /// Backtest only: run the live position sync every step against the
+    /// engine's paper positions mirrored into the replay connector, instead
+    /// of short-circuiting it. Each pair is synced against its own paper
+    /// legs, so pairs sharing a symbol don't net out. Starts with positions
+    /// not ready.
+    /// Env: BACKTEST_FULL_SYNC (default false).
+    pub backtest_full_sync: bool,
+    /// Emit `[PRICE_SNAPSHOT]` for a symbol on one fetch in N (1 = every
+    /// fetch, 0 treated as 1).
+    /// Env: LOG_PRICE_SNAPSHOT_EVERY_N_TICKS (default 1).
+    pub log_price_snapshot_every_n_ticks: u64,
+    /// Emit the `[ZCHECK]` and `[ENTRY_THRESHOLD]` lines for a pair on one
+    /// evaluation in N (1 = every tick, 0 treated as 1).
+    /// Env: LOG_ZCHECK_EVERY_N_TICKS (default 1).
+    pub log_zcheck_every_n_ticks: u64,
+    /// Emit the per-order `[ORDER_PARAMS]` parameter line for a pair on one
+    /// submission in N (1 = every order, 0 treated as 1). Exchange size
+    /// adjustments are always logged.
+    /// Env: LOG_ORDER_PARAMS_EVERY_N_TICKS (default 1).
+    pub log_order_params_every_n_ticks: u64,
+    /// Act on an open position once the pair's current beta differs from
+    /// the beta it was entered at by more than this percentage of the
+    /// entry beta (e.g. after a jump-triggered re-evaluation), since the
+    /// spread is then mis-hedged. What happens is `beta_drift_action`.
+    /// 0 disables. Env: BETA_DRIFT_EXIT_PCT (default 0).
+    pub beta_drift_exit_pct: f64,
+    /// Response to a `beta_drift_exit_pct` breach: `exit` closes the
+    /// position (reason `beta_drift`); `rehedge` resizes leg B to the
+    /// current beta and keeps the position, falling back to an exit when
+    /// the beta changed sign. Env: BETA_DRIFT_ACTION (default exit).
+    pub beta_drift_action: BetaDriftAction,
+    /// Center and scale of the spread z over `spread_history`: `simple`
+    /// (mean / std) or `robust` (median / MAD scaled by 1.4826 to be
+    /// std-consistent), which a single spike bar barely moves. Also applied
+    /// to existing pairs on a SIGHUP reload. Env: Z_STAT_MODE (default
+    /// simple).
+    pub z_stat_mode: ZStatMode,
+    /// Safety valve against order leaks: every
+    /// `OPEN_ORDER_AUDIT_INTERVAL_SECS` each instance tallies its open orders
+    /// over the universe symbols, and above this count it stops new entries
+    /// and cancels every open order not tracked as a pending leg. The tally
+    /// shares the /account REST spacing and is put off while another
+    /// account call is recent. 0 disables.
+    /// Env: MAX_OPEN_ORDERS (default 50).
+    pub max_open_orders: usize,
+    /// Entries are blocked while either leg's top-of-book spread
+    /// `(ask - bid) / mid` exceeds this many bps: a wide book makes the
+    /// round trip structurally expensive. Legs without both quotes are not
+    /// checked. 0 disables. Env: ENTRY_MAX_BOOK_SPREAD_BPS (default 0).
+    pub entry_max_book_spread_bps: f64,
+    /// Entries need an `evaluate_pair` result at most this old: a pair
+    /// whose last successful evaluation is staler is not eligible for entry
+    /// (open positions are untouched) until it is re-evaluated. Set it
+    /// above the eval cadence, including `bt_eval_timestamps` gaps in a
+    /// replay. 0 disables. Env: ELIGIBILITY_MAX_AGE_SECS (default 0).
+    pub eligibility_max_age_secs: u64,
+    /// Book exit PnL from the venue-reported `filled_value`/`filled_fee` of
+    /// the entry and exit fills (net of fees) instead of `compute_pnl` on
+    /// snapshot prices. Falls back to `compute_pnl` when any leg lacks fill
+    /// data or the exit size does not match the entry.
+    /// Env: RECONCILE_FILL_PNL (default false).
+    pub reconcile_fill_pnl: bool,
+    /// Positions worth less than this (USD at the snapshot price) are dust,
+    /// on top of anything below the exchange minimum order size. 0 keeps
+    /// the exchange minimum as the only test.
+    /// Env: DUST_NOTIONAL_USD (default 0).
+    pub dust_notional_usd: f64,
+    /// Per-symbol `dust_notional_usd`, for symbols whose typical size makes
+    /// the global floor too coarse. Symbols not listed use the global.
+    /// Env: DUST_NOTIONAL_USD_BY_SYMBOL as `SYM=usd,SYM=usd` (default empty).
+    pub dust_notional_usd_by_symbol: HashMap<String, f64>,
+    /// Exit reasons closed with market orders outright instead of the
+    /// post-only attempts (the default sends `force_close` and the loss
+    /// stops to market, while `exit_z` stays passive with its taker
+    /// fallback). `all` covers every reason; an empty list leaves only
+    /// `universe_removed` at market. Unknown reason names fail config load.
+    /// Env: EXIT_MARKET_REASONS, comma-separated
+    /// (default `force_close,stop_loss_z,max_loss_r`).
+    pub exit_market_reasons: Vec<String>,
+    /// Positions held at least this long get a rate-limited `[POSITION]
+    /// aging` warning and are listed under `aging` in status.json, ahead of
+    /// the pair's `force_close_secs` (e.g. ~75% of it). Must be shorter than
+    /// the shortest `force_close_secs`. 0 disables.
+    /// Env: POSITION_AGE_WARN_SECS (default 0).
+    pub position_age_warn_secs: u64,
+    /// Track eligibility per spread direction (the pair is eligible and
+    /// that direction's net carry is ≥ max(0, `net_funding_min_per_hour`))
+    /// and only enter in the direction the current z implies when it is
+    /// eligible. Transitions are logged under `[ELIGIBILITY]`.
+    /// Env: DIRECTIONAL_ELIGIBILITY (default false).
+    pub directional_eligibility: bool,
+    /// Drop a bar instead of emitting it when the tick that closes it
+    /// arrives more than this many `trading_period_secs` after the bar's
+    /// start (a data gap or downtime), and restart the builder on the new
+    /// bucket. 0 always emits; otherwise at least 2.
+    /// Env: MAX_BAR_SPAN_MULT (default 0).
+    pub max_bar_span_mult: f64,
+    /// Floor for the live loop interval; `interval_secs` below it is
+    /// raised. Env: MIN_INTERVAL_SECS (default 0).
+    pub min_interval_secs: u64,
+    /// Ceiling the live loop interval backs off to on repeated rate-limit
+    /// responses (doubling per new hit, halving back after a clean
+    /// period). 0 keeps the fixed `interval_secs`.
+    /// Env: MAX_INTERVAL_SECS (default 0).
+    pub max_interval_secs: u64,
+    /// Positions younger than this only exit on `stop_loss_z`,
+    /// `max_loss_r` or `force_close`; every other exit reason waits, so
+    /// z noise around the thresholds cannot churn a fresh entry.
+    /// Env: MIN_HOLD_SECS (default 0).
+    pub min_hold_secs: u64,
+    /// Jsonl file that every `evaluate_pair` run appends its diagnostics
+    /// (betas, half-life, ADF p-value, score) to. None = disabled. Env:
+    /// EVAL_HISTORY_FILE.
+    pub eval_history_file: Option<String>,
+    /// Share of each leg closed (reduce-only, at market) once unrealized
+    /// PnL reaches `partial_take_profit_r` times the per-trade risk budget.
+    /// The rest runs to the usual exits. Taken at most once per position;
+    /// 0 disables. Env: PARTIAL_TAKE_PROFIT_FRACTION (default 0).
+    pub partial_take_profit_fraction: f64,
+    /// Profit target, in multiples of `risk_pct_per_trade` of equity, for
+    /// `partial_take_profit_fraction`. Env: PARTIAL_TAKE_PROFIT_R
+    /// (default 0 = disabled).
+    pub partial_take_profit_r: f64,
+    /// Also publish the per-symbol tally of exchange-adjusted order sizes
+    /// (always in `[METRICS]`) in status.json.
+    /// Env: STATUS_SIZE_ADJUSTMENTS (default false).
+    pub status_size_adjustments: bool,
+    /// Block new entries and send a critical alert once this many
+    /// consecutive cycles have run on the fallback equity (the cached or
+    /// `equity_usd_reference` value kept after a failed `get_balance`).
+    /// Reset by the next successful fetch; 0 disables.
+    /// Env: MAX_EQUITY_FALLBACK_CYCLES (default 30).
+    pub max_equity_fallback_cycles: u32,
+    /// Spread-history points a pair needs before z-based entries run;
+    /// exits of an open position do not wait on it. 0 = half of
+    /// `metrics_window`, at least 10.
+    /// Env: MIN_SPREAD_HISTORY_POINTS (default 0).
+    pub min_spread_history_points: usize,
+    /// Bars each leg's price history needs on top of that. 0 = the
+    /// `evaluate_pair` warm-up (`warm_start_mode`). See `pair_warm_check`.
+    /// Env: MIN_SYMBOL_HISTORY_BARS (default 0).
+    pub min_symbol_history_bars: usize,
+    /// Entries on a pair sharing a symbol with an active pair: `block`
+    /// (default) skips them; `net_exposure` allows them while the net
+    /// signed USD exposure of every shared symbol stays within
+    /// `max_net_symbol_exposure_usd`. Env: SHARED_SYMBOL_POLICY.
+    pub shared_symbol_policy: SharedSymbolPolicy,
+    /// Cap on |net signed notional| per shared symbol for
+    /// `shared_symbol_policy: net_exposure`.
+    /// Env: MAX_NET_SYMBOL_EXPOSURE_USD (default 0).
+    pub max_net_symbol_exposure_usd: f64,
+    /// Seconds between periodic `[METRICS]` logs; 0 disables them.
+    /// Env: METRICS_LOG_INTERVAL_SECS (default 300).
+    pub metrics_log_interval_secs: u64,
+    /// `text` (default): the human-readable `[METRICS]` lines. `json`: one
+    /// `[METRICS]` line carrying a single JSON object. Env: METRICS_LOG_FORMAT.
+    pub metrics_log_format: MetricsLogFormat,
+    /// Add per-pair velocity, entry threshold, position and pending state,
+    /// plus open/pending counts, to the metrics log.
+    /// Env: METRICS_LOG_DETAIL (default false).
+    pub metrics_log_detail: bool,
+    /// Rank entry candidates by the change in the whole book's funding
+    /// carry (USD per hour, on the sized legs netted against current
+    /// positions) instead of each pair's own funding rate.
+    /// Env: PORTFOLIO_FUNDING_AWARE (default false).
+    pub portfolio_funding_aware: bool,
+    /// Pairs generated from `universe_symbols` / `UNIVERSE_SYMBOLS` keep the
+    /// listed order (the earlier symbol is base, leg A) instead of being
+    /// alphabetized. This flips pair keys (`ETH/BTC` vs `BTC/ETH`), so
+    /// `pair_overrides`, persisted spread histories and state files must use
+    /// the same order, and `long_spread` means long the listed-first symbol.
+    /// Explicit `universe_pairs` are always taken as written.
+    /// Env: PRESERVE_SYMBOL_ORDER (default false).
+    pub preserve_symbol_order: bool,
+    /// Block every new entry while any exchange position is open. Turn off
+    /// to let the shared-symbol overlap checks and `max_active_pairs`, which
+    /// then also caps pairs holding or entering a position, decide how many
+    /// pairs run at once.
+    /// Env: SINGLE_POSITION_MODE (default true).
+    pub single_position_mode: bool,
+    /// Skip a new entry when either leg's top of book is crossed or locked
+    /// (bid >= ask). When off, pricing falls back to the ticker price for
+    /// such snapshots; exits always take the fallback.
+    /// Env: REJECT_CROSSED_BOOK (default false).
+    pub reject_crossed_book: bool,
+    /// Add the instance's gross notional exposure (USD, marked at the latest
+    /// bar close) and open pair count to each of its `equity_history.jsonl`
+    /// points.
+    /// Env: EQUITY_HISTORY_DETAILED (default false).
+    pub equity_history_detailed: bool,
+    /// Consecutive price-fetch or position-sync failures after which the
+    /// connector is restarted (`DexConnector::restart`). 0 disables.
+    /// Env: AUTO_RESTART_AFTER_FAILURES (default 0).
+    pub auto_restart_after_failures: u32,
+    /// Minimum spacing between automatic connector restarts.
+    /// Env: AUTO_RESTART_COOLDOWN_SECS (default 600).
+    pub auto_restart_cooldown_secs: u64,
+    /// Per-symbol maker and taker fee tiers in bps; symbols not listed use
+    /// `fee_bps`. The cost gates and net PnL charge each leg its own maker
+    /// rate on post-only venues and its taker rate otherwise
+    /// (`leg_fee_bps`); market exits and taker fallbacks pay the taker rate
+    /// (`exit_fee_bps`).
+    /// Env: MAKER_FEE_BPS_BY_SYMBOL / TAKER_FEE_BPS_BY_SYMBOL as
+    /// `SYM=bps,SYM=bps` (default empty).
+    pub maker_fee_bps_by_symbol: HashMap<String, f64>,
+    pub taker_fee_bps_by_symbol: HashMap<String, f64>,
+    /// Sanity bound on a closed bar's absolute log return against the
+    /// previous bar in `history`; a bar beyond it is dropped as a bad tick
+    /// and the prior value kept. After `OUTLIER_BARS_BEFORE_ACCEPT` such
+    /// bars in a row the move is taken as real and the bar is kept. 0
+    /// disables; 0.5 is about a 40%/65% move.
+    /// Env: MAX_BAR_LOG_RETURN (default 0.5).
+    pub max_bar_log_return: f64,
+    /// Two-phase entries: open a scout of `scale_in_fraction` of the usual
+    /// size, then add the rest once |z| has extended `scale_in_add_z` past
+    /// the entry z (still short of `stop_loss_z`), averaging the entry
+    /// prices. One add per position, capped by
+    /// `risk.max_notional_usd_per_leg`.
+    /// Env: SCALE_IN_ENABLED (default false).
+    pub scale_in_enabled: bool,
+    /// Env: SCALE_IN_ADD_Z (default 0.5).
+    pub scale_in_add_z: f64,
+    /// Scout share of the full size, in (0, 1).
+    /// Env: SCALE_IN_FRACTION (default 0.5).
+    pub scale_in_fraction: f64,
+    /// How long a pair stays untradeable after one of its statistics (pair
+    /// evaluation or z-score) comes out NaN/inf. The bad result is always
+    /// discarded; 0 skips only the tick it occurred on.
+    /// Env: NON_FINITE_STATS_BLOCK_SECS (default 900).
+    pub non_finite_stats_block_secs: u64,
+    /// Place both entry legs as market orders in one shot, skipping the
+    /// post-only retries and the hybrid timeout. Trades fee savings for
+    /// fill certainty; a failed leg B still rolls leg A back.
+    /// Env: ENTRY_MARKET_MODE (default false).
+    pub entry_market_mode: bool,
+    /// Jsonl file that every entry candidate dropped at selection (overlap,
+    /// thin book, `max_active_pairs`, `min_eligible_pairs`, shortlist, lost
+    /// to `best_entry`, or blocked at sizing) is appended to with its z,
+    /// beta, funding, liquidity, score and reason. Pairs whose z cleared
+    /// the entry threshold but a per-pair gate (kill switch, daily loss,
+    /// cooldowns, ...) held back are recorded with the gate as reason.
+    /// None = disabled. Env: REJECTED_ENTRIES_FILE.
+    pub rejected_entries_file: Option<String>,
+    /// Portfolio breadth guard: new entries are blocked unless at least this
+    /// many pairs are currently eligible. 1 = no restriction.
+    /// Env: MIN_ELIGIBLE_PAIRS (default 1).
+    pub min_eligible_pairs: usize,
+    /// Jsonl file that each step appends every pair's planned action
+    /// (open/close/none with z, beta, funding, liquidity), the selected
+    /// entry and the queued exits to, stamped with the replay-aware tick
+    /// time. Heavy; meant for backtest analysis. None = disabled. Env:
+    /// PLAN_TRACE_FILE.
+    pub plan_trace_file: Option<String>,
+    /// Block re-entry on a pair, in either direction, for this many of its
+    /// evaluations after an exit that lost money (any exit reason, unlike
+    /// the stop-loss cooldown). A flat or profitable exit clears it.
+    /// 0 = disabled.
+    /// Env: LOSS_REENTRY_BLOCK_TICKS (default 0).
+    pub loss_reentry_block_ticks: u64,
+    /// What the live loop does when a step runs past the next tick
+    /// boundary: `skip` waits for the next wall-clock boundary, dropping
+    /// the missed ones; `delay` runs the next step a full interval after
+    /// the slow one finished; `burst` runs it immediately.
+    /// Env: MISSED_TICK_BEHAVIOR (default skip).
+    pub missed_tick_behavior: MissedTickBehavior,
+    /// Portfolio-level hedge overlay: every `delta_rebalance_interval_secs`
+    /// each symbol's exposure on an instance's account is compared with
+    /// what that instance's open pairs call for (leg A as booked, leg B at
+    /// the size the entry beta and entry prices imply, summed across pairs
+    /// sharing the symbol), and a gap above `rebalance_threshold_usd` is
+    /// traded back, reduce-only when it shrinks the position. Orders that
+    /// add exposure wait while the kill switch or a loss guard blocks
+    /// entries. Symbols with pending orders or a guarded pair are left alone.
+    /// Env: DELTA_REBALANCE_ENABLED (default false).
+    pub delta_rebalance_enabled: bool,
+    /// Per-symbol USD gap that triggers a rebalancing order.
+    /// Env: REBALANCE_THRESHOLD_USD (default 50).
+    pub rebalance_threshold_usd: f64,
+    /// Env: DELTA_REBALANCE_INTERVAL_SECS (default 300).
+    pub delta_rebalance_interval_secs: u64,
+    /// When the exchange reports a hedged pair the engine holds no
+    /// position for (a crash between placing and confirming an entry),
+    /// restore its entry time from the one persisted in the risk state
+    /// file at placement, so `force_close_secs` counts from the real
+    /// entry. Off: the holding clock starts at adoption.
+    /// Env: RECOVER_ENTRY_TIME_FROM_RISK_STATE (default false).
+    pub recover_entry_time_from_risk_state: bool,
 }
 
 /// Resolved `risk:` block. See `RiskYaml` for field meanings.
@@ -618,6 +1404,25 @@ pub struct StrategyConfig {
     pub mtf_z_min: Option<f64>,
 }
 
+impl StrategyConfig {
+    /// Overlay this strategy's exit thresholds and optional per-strategy
+    /// overrides on top of an engine-wide `PairParams`.
+    pub(super) fn overlay_pair_params(&self, pp: &mut PairParams) {
+        pp.exit_z = self.exit_z;
+        pp.stop_loss_z = self.stop_loss_z;
+        pp.max_loss_r_mult = self.max_loss_r_mult;
+        if let Some(fc) = self.force_close_time_secs {
+            pp.force_close_secs = fc;
+        }
+        if let Some(ref w) = self.mtf_windows {
+            pp.mtf_windows = w.clone();
+        }
+        if let Some(z) = self.mtf_z_min {
+            pp.mtf_z_min = z;
+        }
+    }
+}
+
 impl PairTradeConfig {
     pub fn params_for(&self, pair_key: &str) -> &PairParams {
         self.pair_params
@@ -642,12 +1447,100 @@ impl PairTradeConfig {
         m
     }
 
+    /// Shortest `force_close_secs` any pair or strategy can run with.
+    fn min_force_close_secs(&self) -> u64 {
+        std::iter::once(self.default_pair_params.force_close_secs)
+            .chain(self.pair_params.values().map(|p| p.force_close_secs))
+            .chain(self.strategies.iter().filter_map(|s| s.force_close_time_secs))
+            .filter(|secs| *secs > 0)
+            .min()
+            .unwrap_or(0)
+    }
+
     /// Assert that `shutdown_grace_secs` covers the longest per-strategy /
     /// per-pair `force_close_secs` plus a small buffer. Catches config drift
     /// like bot-strategy#50, where a strategy's `force_close_time_secs` was
-    /// extended without raising the global shutdown grace.
+    /// extended without raising the global shutdown grace. Also rejects
+    /// negative dust thresholds, a partial take-profit fraction outside
+    /// [0, 1), a `max_bar_span_mult` between 0 and 2, a per-pair
+    /// `notional_per_leg_usd` that is not a positive amount and, with
+    /// scale-in on, a scout fraction outside (0, 1).
     fn validate(&self) -> Result<()> {
         const BUFFER_SECS: u64 = 60;
+        if self.dust_notional_usd < 0.0 || !self.dust_notional_usd.is_finite() {
+            return Err(anyhow!(
+                "dust_notional_usd must be ≥ 0 and finite (got {})",
+                self.dust_notional_usd
+            ));
+        }
+        for (symbol, usd) in &self.dust_notional_usd_by_symbol {
+            if *usd < 0.0 || !usd.is_finite() {
+                return Err(anyhow!(
+                    "dust_notional_usd_by_symbol.{} must be ≥ 0 and finite (got {})",
+                    symbol,
+                    usd
+                ));
+            }
+        }
+        if !(0.0..1.0).contains(&self.partial_take_profit_fraction) {
+            return Err(anyhow!(
+                "partial_take_profit_fraction must be in [0, 1) (got {})",
+                self.partial_take_profit_fraction
+            ));
+        }
+        let pair_params = std::iter::once(("default", &self.default_pair_params))
+            .chain(self.pair_params.iter().map(|(key, pp)| (key.as_str(), pp)));
+        for (key, pp) in pair_params {
+            if let Some(usd) = pp.notional_per_leg_usd {
+                if !(usd > 0.0 && usd.is_finite()) {
+                    return Err(anyhow!(
+                        "{}: notional_per_leg_usd must be > 0 and finite (got {})",
+                        key,
+                        usd
+                    ));
+                }
+            }
+        }
+        // A bar's closing tick lands in the next bucket, so a normal bar
+        // already spans one window and a single late tick nearly two.
+        if !(self.max_bar_span_mult == 0.0 || self.max_bar_span_mult >= 2.0) {
+            return Err(anyhow!(
+                "max_bar_span_mult must be 0 (off) or ≥ 2 (got {})",
+                self.max_bar_span_mult
+            ));
+        }
+        if self.scale_in_enabled && !(self.scale_in_fraction > 0.0 && self.scale_in_fraction < 1.0) {
+            return Err(anyhow!(
+                "scale_in_fraction must be in (0, 1) (got {})",
+                self.scale_in_fraction
+            ));
+        }
+        if let Some(unknown) = self
+            .exit_market_reasons
+            .iter()
+            .find(|r| *r != "all" && !EXIT_REASONS.contains(&r.as_str()))
+        {
+            return Err(anyhow!(
+                "exit_market_reasons: unknown exit reason {:?} (known: all, {})",
+                unknown,
+                EXIT_REASONS.join(", ")
+            ));
+        }
+        let min_fc = self.min_force_close_secs();
+        if self.position_age_warn_secs > 0 && min_fc > 0 && self.position_age_warn_secs >= min_fc {
+            return Err(anyhow!(
+                "position_age_warn_secs ({}) must be shorter than the shortest force_close_secs ({}); \
+                 positions would be force-closed before the aging warning fires",
+                self.position_age_warn_secs,
+                min_fc
+            ));
+        }
+        if self.delta_rebalance_enabled && self.rebalance_threshold_usd <= 0.0 {
+            return Err(anyhow!(
+                "rebalance_threshold_usd must be > 0 when delta_rebalance_enabled (got {})",
+                self.rebalance_threshold_usd
+            ));
+        }
         // 0 = legacy immediate force-close on SIGTERM; no grace window to
         // validate.
         if self.shutdown_grace_secs == 0 {
@@ -701,6 +1594,7 @@ impl PairTradeConfig {
         let warm_start_min_from_yaml = yaml.warm_start_min_bars.is_some();
 
         let universe = resolve_universe_from_yaml(&yaml)?;
+        let pair_overrides = merged_pair_overrides(&yaml);
         let metrics_window = yaml.metrics_window_length.unwrap_or(DEFAULT_METRICS_WINDOW);
         let warm_start_mode = yaml
             .warm_start_mode
@@ -775,6 +1669,10 @@ impl PairTradeConfig {
             history_archive_retention_days: yaml.history_archive_retention_days.unwrap_or(360),
             backtest_mode: yaml.backtest_mode.unwrap_or(false),
             backtest_file: yaml.backtest_file,
+            backtest_files: yaml
+                .backtest_files
+                .map(StringOrVec::into_vec)
+                .unwrap_or_default(),
             bt_warm_start_snapshot: None, // env-only, not in YAML
             bt_eval_timestamps: None,     // env-only, not in YAML
             bt_restart_timestamps: None,  // env-only, not in YAML
@@ -805,14 +1703,358 @@ impl PairTradeConfig {
                 .clone()
                 .unwrap_or_else(|| DEFAULT_REGIME_REFERENCE_SYMBOL.to_string()),
             risk: resolve_risk_config(yaml.risk.as_ref())?,
+            min_equity_usd: yaml.min_equity_usd.unwrap_or(DEFAULT_MIN_EQUITY_USD),
+            max_balance_fetch_failures: yaml
+                .max_balance_fetch_failures
+                .unwrap_or(DEFAULT_MAX_BALANCE_FETCH_FAILURES),
+            align_bar_clock: yaml.align_bar_clock.unwrap_or(DEFAULT_ALIGN_BAR_CLOCK),
+            history_price_format: parse_history_price_format(
+                yaml.history_price_format
+                    .as_deref()
+                    .unwrap_or(DEFAULT_HISTORY_PRICE_FORMAT),
+            )?,
+            avoid_funding_window_secs: yaml
+                .avoid_funding_window_secs
+                .unwrap_or(DEFAULT_AVOID_FUNDING_WINDOW_SECS),
+            funding_interval_secs: yaml
+                .funding_interval_secs
+                .unwrap_or(DEFAULT_FUNDING_INTERVAL_SECS),
+            close_profitable_before_funding: yaml
+                .close_profitable_before_funding
+                .unwrap_or(DEFAULT_CLOSE_PROFITABLE_BEFORE_FUNDING),
+            vol_target_sigma: yaml.vol_target_sigma.unwrap_or(DEFAULT_VOL_TARGET_SIGMA),
+            vol_target_min_notional: yaml
+                .vol_target_min_notional
+                .unwrap_or(DEFAULT_VOL_TARGET_MIN_NOTIONAL),
+            vol_target_max_notional: yaml
+                .vol_target_max_notional
+                .unwrap_or(DEFAULT_VOL_TARGET_MAX_NOTIONAL),
+            reconcile_concurrency: yaml
+                .reconcile_concurrency
+                .unwrap_or(DEFAULT_RECONCILE_CONCURRENCY),
+            data_dump_compress: yaml.data_dump_compress.unwrap_or(DEFAULT_DATA_DUMP_COMPRESS),
+            trade_stats_reset: yaml
+                .trade_stats_reset
+                .as_deref()
+                .unwrap_or(DEFAULT_TRADE_STATS_RESET)
+                .parse()
+                .unwrap_or(TradeStatsReset::Session),
+            spread_smoothing_bars: yaml
+                .spread_smoothing_bars
+                .unwrap_or(DEFAULT_SPREAD_SMOOTHING_BARS),
+            max_spread_gap_bars: yaml
+                .max_spread_gap_bars
+                .unwrap_or(DEFAULT_MAX_SPREAD_GAP_BARS),
+            dust_sweep: yaml.dust_sweep.unwrap_or(DEFAULT_DUST_SWEEP),
+            beta_on_returns: yaml.beta_on_returns.unwrap_or(DEFAULT_BETA_ON_RETURNS),
+            orderbook_depth: yaml
+                .orderbook_depth
+                .unwrap_or(DEFAULT_ORDERBOOK_DEPTH)
+                .max(1),
+            orderbook_depth_band_bps: yaml
+                .orderbook_depth_band_bps
+                .unwrap_or(DEFAULT_ORDERBOOK_DEPTH_BAND_BPS),
+            exit_z_require_profit: yaml
+                .exit_z_require_profit
+                .unwrap_or(DEFAULT_EXIT_Z_REQUIRE_PROFIT),
+            startup_force_close_backoff_mult: yaml
+                .startup_force_close_backoff_mult
+                .unwrap_or(DEFAULT_STARTUP_FORCE_CLOSE_BACKOFF_MULT),
+            startup_force_close_max_wait_secs: yaml
+                .startup_force_close_max_wait_secs
+                .unwrap_or(DEFAULT_STARTUP_FORCE_CLOSE_MAX_WAIT_SECS),
+            startup_force_close_jitter_pct: yaml
+                .startup_force_close_jitter_pct
+                .unwrap_or(DEFAULT_STARTUP_FORCE_CLOSE_JITTER_PCT),
+            startup_cancel_wait_secs: yaml
+                .startup_cancel_wait_secs
+                .unwrap_or(DEFAULT_STARTUP_CANCEL_WAIT_SECS),
+            observe_simulate_pnl: yaml
+                .observe_simulate_pnl
+                .unwrap_or(DEFAULT_OBSERVE_SIMULATE_PNL),
+            auto_flatten_mismatched_legs: yaml
+                .auto_flatten_mismatched_legs
+                .unwrap_or(DEFAULT_AUTO_FLATTEN_MISMATCHED_LEGS),
+            z_price_source: yaml
+                .z_price_source
+                .as_deref()
+                .unwrap_or(DEFAULT_Z_PRICE_SOURCE)
+                .parse()
+                .unwrap_or(PriceSource::Last),
+            no_trade_windows: schedule::parse_windows(yaml.no_trade_windows.as_deref().unwrap_or_default())
+                .map_err(|e| anyhow!(e))?,
+            forensic_dump_dir: yaml.forensic_dump_dir.filter(|v| !v.trim().is_empty()),
+            forensic_dump_keep: yaml
+                .forensic_dump_keep
+                .unwrap_or(DEFAULT_FORENSIC_DUMP_KEEP),
+            min_book_notional_usd: yaml
+                .min_book_notional_usd
+                .unwrap_or(DEFAULT_MIN_BOOK_NOTIONAL_USD),
+            hedge_ratio_tolerance_pct: yaml
+                .hedge_ratio_tolerance_pct
+                .unwrap_or(DEFAULT_HEDGE_RATIO_TOLERANCE_PCT),
+            status_price_dp: yaml.status_price_dp,
+            status_pnl_dp: yaml.status_pnl_dp,
+            ticker_auth_retries: yaml
+                .ticker_auth_retries
+                .unwrap_or(DEFAULT_TICKER_AUTH_RETRIES),
+            ticker_auth_retry_ms: yaml
+                .ticker_auth_retry_ms
+                .unwrap_or(DEFAULT_TICKER_AUTH_RETRY_MS),
+            normalize_legs: yaml.normalize_legs.unwrap_or(DEFAULT_NORMALIZE_LEGS),
+            max_reissues_per_pair: yaml
+                .max_reissues_per_pair
+                .unwrap_or(DEFAULT_MAX_REISSUES_PER_PAIR),
+            max_reissued_legs_per_cycle: yaml
+                .max_reissued_legs_per_cycle
+                .unwrap_or(DEFAULT_MAX_REISSUED_LEGS_PER_CYCLE),
+            bar_source: yaml
+                .bar_source
+                .as_deref()
+                .unwrap_or(DEFAULT_BAR_SOURCE)
+                .parse()
+                .unwrap_or(BarSource::Snapshot),
+            pending_max_age_secs: yaml
+                .pending_max_age_secs
+                .unwrap_or(DEFAULT_PENDING_MAX_AGE_SECS),
+            equity_baseline_samples: yaml
+                .equity_baseline_samples
+                .unwrap_or(DEFAULT_EQUITY_BASELINE_SAMPLES)
+                .max(1),
+            history_backend: yaml
+                .history_backend
+                .as_deref()
+                .unwrap_or(DEFAULT_HISTORY_BACKEND)
+                .parse()
+                .unwrap_or(HistoryBackend::Json),
+            allow_negative_beta: yaml
+                .allow_negative_beta
+                .unwrap_or(DEFAULT_ALLOW_NEGATIVE_BETA),
+            pair_idle_alert_secs: yaml
+                .pair_idle_alert_secs
+                .unwrap_or(DEFAULT_PAIR_IDLE_ALERT_SECS),
+            pair_idle_notify: yaml.pair_idle_notify.unwrap_or(DEFAULT_PAIR_IDLE_NOTIFY),
+            max_min_order_oversizing_factor: yaml
+                .max_min_order_oversizing_factor
+                .unwrap_or(DEFAULT_MAX_MIN_ORDER_OVERSIZING_FACTOR),
+            position_audit_interval_secs: yaml
+                .position_audit_interval_secs
+                .unwrap_or(DEFAULT_POSITION_AUDIT_INTERVAL_SECS),
+            position_audit_force_close: yaml
+                .position_audit_force_close
+                .unwrap_or(DEFAULT_POSITION_AUDIT_FORCE_CLOSE),
+            exit_post_only_wait_reasons: yaml
+                .exit_post_only_wait_reasons
+                .map(StringOrVec::into_vec)
+                .unwrap_or_default(),
+            seed_new_pairs_from_history: yaml
+                .seed_new_pairs_from_history
+                .unwrap_or(DEFAULT_SEED_NEW_PAIRS_FROM_HISTORY),
+            max_abs_funding_per_hour: yaml
+                .max_abs_funding_per_hour
+                .unwrap_or(DEFAULT_MAX_ABS_FUNDING_PER_HOUR),
+            matching_model: yaml
+                .matching_model
+                .as_deref()
+                .unwrap_or(DEFAULT_MATCHING_MODEL)
+                .parse()
+                .unwrap_or(MatchingModel::Full),
+            max_orders_per_symbol_per_min: yaml
+                .max_orders_per_symbol_per_min
+                .unwrap_or(DEFAULT_MAX_ORDERS_PER_SYMBOL_PER_MIN),
+            backtest_apply_costs: yaml
+                .backtest_apply_costs
+                .unwrap_or(DEFAULT_BACKTEST_APPLY_COSTS),
+            pair_eval_interval_secs: yaml
+                .pair_eval_interval_secs
+                .unwrap_or(DEFAULT_PAIR_EVAL_INTERVAL_SECS)
+                .max(1),
+            strict_universe_validation: yaml
+                .strict_universe_validation
+                .unwrap_or(DEFAULT_STRICT_UNIVERSE_VALIDATION),
+            skip_unchanged_bars: yaml
+                .skip_unchanged_bars
+                .unwrap_or(DEFAULT_SKIP_UNCHANGED_BARS),
+            max_realized_slippage_bps: yaml
+                .max_realized_slippage_bps
+                .unwrap_or(DEFAULT_MAX_REALIZED_SLIPPAGE_BPS),
+            realized_slippage_block_secs: yaml
+                .realized_slippage_block_secs
+                .unwrap_or(DEFAULT_REALIZED_SLIPPAGE_BLOCK_SECS),
+            persist_trade_stats: yaml
+                .persist_trade_stats
+                .unwrap_or(DEFAULT_PERSIST_TRADE_STATS),
+            backtest_full_sync: yaml
+                .backtest_full_sync
+                .unwrap_or(DEFAULT_BACKTEST_FULL_SYNC),
+            log_price_snapshot_every_n_ticks: yaml
+                .log_price_snapshot_every_n_ticks
+                .unwrap_or(DEFAULT_LOG_PRICE_SNAPSHOT_EVERY_N_TICKS),
+            log_zcheck_every_n_ticks: yaml
+                .log_zcheck_every_n_ticks
+                .unwrap_or(DEFAULT_LOG_ZCHECK_EVERY_N_TICKS),
+            log_order_params_every_n_ticks: yaml
+                .log_order_params_every_n_ticks
+                .unwrap_or(DEFAULT_LOG_ORDER_PARAMS_EVERY_N_TICKS),
+            beta_drift_exit_pct: yaml
+                .beta_drift_exit_pct
+                .unwrap_or(DEFAULT_BETA_DRIFT_EXIT_PCT),
+            beta_drift_action: yaml
+                .beta_drift_action
+                .as_deref()
+                .unwrap_or(DEFAULT_BETA_DRIFT_ACTION)
+                .parse()
+                .unwrap_or(BetaDriftAction::Exit),
+            z_stat_mode: yaml
+                .z_stat_mode
+                .as_deref()
+                .unwrap_or(DEFAULT_Z_STAT_MODE)
+                .parse()
+                .unwrap_or(ZStatMode::Simple),
+            max_open_orders: yaml
+                .max_open_orders
+                .unwrap_or(DEFAULT_MAX_OPEN_ORDERS),
+            entry_max_book_spread_bps: yaml
+                .entry_max_book_spread_bps
+                .unwrap_or(DEFAULT_ENTRY_MAX_BOOK_SPREAD_BPS),
+            eligibility_max_age_secs: yaml
+                .eligibility_max_age_secs
+                .unwrap_or(DEFAULT_ELIGIBILITY_MAX_AGE_SECS),
+            reconcile_fill_pnl: yaml
+                .reconcile_fill_pnl
+                .unwrap_or(DEFAULT_RECONCILE_FILL_PNL),
+            dust_notional_usd: yaml
+                .dust_notional_usd
+                .unwrap_or(DEFAULT_DUST_NOTIONAL_USD),
+            dust_notional_usd_by_symbol: yaml
+                .dust_notional_usd_by_symbol
+                .clone()
+                .unwrap_or_default(),
+            exit_market_reasons: yaml
+                .exit_market_reasons
+                .unwrap_or_else(|| StringOrVec::String(DEFAULT_EXIT_MARKET_REASONS.to_string()))
+                .into_vec(),
+            position_age_warn_secs: yaml
+                .position_age_warn_secs
+                .unwrap_or(DEFAULT_POSITION_AGE_WARN_SECS),
+            directional_eligibility: yaml
+                .directional_eligibility
+                .unwrap_or(DEFAULT_DIRECTIONAL_ELIGIBILITY),
+            max_bar_span_mult: yaml
+                .max_bar_span_mult
+                .unwrap_or(DEFAULT_MAX_BAR_SPAN_MULT),
+            min_interval_secs: yaml
+                .min_interval_secs
+                .unwrap_or(DEFAULT_MIN_INTERVAL_SECS),
+            max_interval_secs: yaml
+                .max_interval_secs
+                .unwrap_or(DEFAULT_MAX_INTERVAL_SECS),
+            min_hold_secs: yaml.min_hold_secs.unwrap_or(DEFAULT_MIN_HOLD_SECS),
+            eval_history_file: yaml.eval_history_file.filter(|v| !v.trim().is_empty()),
+            partial_take_profit_fraction: yaml
+                .partial_take_profit_fraction
+                .unwrap_or(DEFAULT_PARTIAL_TAKE_PROFIT_FRACTION),
+            partial_take_profit_r: yaml.partial_take_profit_r.unwrap_or(DEFAULT_PARTIAL_TAKE_PROFIT_R),
+            status_size_adjustments: yaml
+                .status_size_adjustments
+                .unwrap_or(DEFAULT_STATUS_SIZE_ADJUSTMENTS),
+            max_equity_fallback_cycles: yaml
+                .max_equity_fallback_cycles
+                .unwrap_or(DEFAULT_MAX_EQUITY_FALLBACK_CYCLES),
+            min_spread_history_points: yaml
+                .min_spread_history_points
+                .unwrap_or(DEFAULT_MIN_SPREAD_HISTORY_POINTS),
+            min_symbol_history_bars: yaml
+                .min_symbol_history_bars
+                .unwrap_or(DEFAULT_MIN_SYMBOL_HISTORY_BARS),
+            shared_symbol_policy: yaml
+                .shared_symbol_policy
+                .as_deref()
+                .unwrap_or(DEFAULT_SHARED_SYMBOL_POLICY)
+                .parse()
+                .unwrap_or(SharedSymbolPolicy::Block),
+            max_net_symbol_exposure_usd: yaml
+                .max_net_symbol_exposure_usd
+                .unwrap_or(DEFAULT_MAX_NET_SYMBOL_EXPOSURE_USD),
+            metrics_log_interval_secs: yaml
+                .metrics_log_interval_secs
+                .unwrap_or(DEFAULT_METRICS_LOG_INTERVAL_SECS),
+            metrics_log_format: yaml
+                .metrics_log_format
+                .as_deref()
+                .unwrap_or(DEFAULT_METRICS_LOG_FORMAT)
+                .parse()
+                .unwrap_or(MetricsLogFormat::Text),
+            metrics_log_detail: yaml.metrics_log_detail.unwrap_or(DEFAULT_METRICS_LOG_DETAIL),
+            portfolio_funding_aware: yaml
+                .portfolio_funding_aware
+                .unwrap_or(DEFAULT_PORTFOLIO_FUNDING_AWARE),
+            preserve_symbol_order: preserve_symbol_order_from(yaml.preserve_symbol_order),
+            single_position_mode: yaml
+                .single_position_mode
+                .unwrap_or(DEFAULT_SINGLE_POSITION_MODE),
+            reject_crossed_book: yaml
+                .reject_crossed_book
+                .unwrap_or(DEFAULT_REJECT_CROSSED_BOOK),
+            equity_history_detailed: yaml
+                .equity_history_detailed
+                .unwrap_or(DEFAULT_EQUITY_HISTORY_DETAILED),
+            auto_restart_after_failures: yaml
+                .auto_restart_after_failures
+                .unwrap_or(DEFAULT_AUTO_RESTART_AFTER_FAILURES),
+            auto_restart_cooldown_secs: yaml
+                .auto_restart_cooldown_secs
+                .unwrap_or(DEFAULT_AUTO_RESTART_COOLDOWN_SECS),
+            maker_fee_bps_by_symbol: yaml
+                .maker_fee_bps_by_symbol
+                .clone()
+                .unwrap_or_default(),
+            taker_fee_bps_by_symbol: yaml
+                .taker_fee_bps_by_symbol
+                .clone()
+                .unwrap_or_default(),
+            max_bar_log_return: yaml
+                .max_bar_log_return
+                .unwrap_or(DEFAULT_MAX_BAR_LOG_RETURN),
+            scale_in_enabled: yaml.scale_in_enabled.unwrap_or(DEFAULT_SCALE_IN_ENABLED),
+            scale_in_add_z: yaml.scale_in_add_z.unwrap_or(DEFAULT_SCALE_IN_ADD_Z),
+            scale_in_fraction: yaml.scale_in_fraction.unwrap_or(DEFAULT_SCALE_IN_FRACTION),
+            non_finite_stats_block_secs: yaml
+                .non_finite_stats_block_secs
+                .unwrap_or(DEFAULT_NON_FINITE_STATS_BLOCK_SECS),
+            entry_market_mode: yaml.entry_market_mode.unwrap_or(DEFAULT_ENTRY_MARKET_MODE),
+            rejected_entries_file: yaml.rejected_entries_file.filter(|v| !v.trim().is_empty()),
+            min_eligible_pairs: yaml.min_eligible_pairs.unwrap_or(DEFAULT_MIN_ELIGIBLE_PAIRS),
+            plan_trace_file: yaml.plan_trace_file.filter(|v| !v.trim().is_empty()),
+            loss_reentry_block_ticks: yaml
+                .loss_reentry_block_ticks
+                .unwrap_or(DEFAULT_LOSS_REENTRY_BLOCK_TICKS),
+            missed_tick_behavior: yaml
+                .missed_tick_behavior
+                .as_deref()
+                .unwrap_or(DEFAULT_MISSED_TICK_BEHAVIOR)
+                .parse()
+                .unwrap_or(MissedTickBehavior::Skip),
+            delta_rebalance_enabled: yaml
+                .delta_rebalance_enabled
+                .unwrap_or(DEFAULT_DELTA_REBALANCE_ENABLED),
+            rebalance_threshold_usd: yaml
+                .rebalance_threshold_usd
+                .unwrap_or(DEFAULT_REBALANCE_THRESHOLD_USD),
+            delta_rebalance_interval_secs: yaml
+                .delta_rebalance_interval_secs
+                .unwrap_or(DEFAULT_DELTA_REBALANCE_INTERVAL_SECS),
+            recover_entry_time_from_risk_state: yaml
+                .recover_entry_time_from_risk_state
+                .unwrap_or(DEFAULT_RECOVER_ENTRY_TIME_FROM_RISK_STATE),
         };
 
-        cfg.pair_params = cfg.build_pair_params_map(&yaml.pair_overrides);
+        cfg.pair_params = cfg.build_pair_params_map(&pair_overrides);
         cfg.apply_env_overrides(history_file_from_yaml, warm_start_min_from_yaml)?;
         cfg.strategies = resolve_strategies(&cfg, yaml.strategies.as_deref());
         // apply_env_overrides mutates cfg.default_pair_params in place; re-merge
         // pair-specific overrides on top so YAML pair_overrides still win.
-        let pair_params_rebuilt = cfg.build_pair_params_map(&yaml.pair_overrides);
+        let pair_params_rebuilt = cfg.build_pair_params_map(&pair_overrides);
         if !pair_params_rebuilt.is_empty() {
             cfg.pair_params = pair_params_rebuilt;
         }
@@ -859,7 +2101,7 @@ impl PairTradeConfig {
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(DEFAULT_EQUITY_USD);
-        let universe = parse_universe_pairs()?;
+        let universe = parse_universe_pairs(preserve_symbol_order_from(None))?;
         let slippage_bps = env::var("SLIPPAGE_BPS")
             .ok()
             .and_then(|v| v.parse::<i32>().ok())
@@ -898,13 +2140,8 @@ impl PairTradeConfig {
             .ok()
             .and_then(|v| v.parse::<u64>().ok())
             .unwrap_or(DEFAULT_STARTUP_FORCE_CLOSE_WAIT_SECS);
-        let force_close_on_startup = env::var("FORCE_CLOSE_ON_STARTUP")
-            .ok()
-            .map(|v| {
-                let v = v.trim().to_ascii_lowercase();
-                v == "1" || v == "true" || v == "yes"
-            })
-            .unwrap_or(DEFAULT_FORCE_CLOSE_ON_STARTUP);
+        let force_close_on_startup =
+            env_bool("FORCE_CLOSE_ON_STARTUP").unwrap_or(DEFAULT_FORCE_CLOSE_ON_STARTUP);
 
         let enable_data_dump = env::var("ENABLE_DATA_DUMP")
             .unwrap_or_else(|_| "false".to_string())
@@ -921,13 +2158,7 @@ impl PairTradeConfig {
             .to_lowercase()
             == "true"
             || enable_data_dump;
-        let disable_history_persist = env::var("DISABLE_HISTORY_PERSIST")
-            .ok()
-            .map(|v| {
-                let v = v.trim().to_ascii_lowercase();
-                v == "1" || v == "true" || v == "yes"
-            })
-            .unwrap_or(false);
+        let disable_history_persist = env_bool("DISABLE_HISTORY_PERSIST").unwrap_or(false);
         let history_file = env::var("PAIRTRADE_HISTORY_FILE")
             .ok()
             .map(|v| v.trim().to_string())
@@ -939,9 +2170,13 @@ impl PairTradeConfig {
             .to_lowercase()
             == "true";
         let backtest_file = env::var("BACKTEST_FILE").ok();
-        if backtest_mode && backtest_file.is_none() {
+        let backtest_files = env::var("BACKTEST_FILES")
+            .ok()
+            .map(|v| StringOrVec::String(v).into_vec())
+            .unwrap_or_default();
+        if backtest_mode && backtest_file.is_none() && backtest_files.is_empty() {
             return Err(anyhow!(
-                "BACKTEST_FILE must be set if BACKTEST_MODE is true"
+                "BACKTEST_FILE or BACKTEST_FILES must be set if BACKTEST_MODE is true"
             ));
         }
 
@@ -981,6 +2216,7 @@ impl PairTradeConfig {
                 .unwrap_or(360),
             backtest_mode,
             backtest_file,
+            backtest_files,
             bt_warm_start_snapshot: env::var("BT_WARM_START_SNAPSHOT").ok().filter(|v| !v.trim().is_empty()),
             bt_eval_timestamps: load_bt_eval_timestamps(),
             bt_restart_timestamps: load_bt_restart_timestamps(),
@@ -1021,6 +2257,330 @@ impl PairTradeConfig {
                 .filter(|v| !v.trim().is_empty())
                 .unwrap_or_else(|| DEFAULT_REGIME_REFERENCE_SYMBOL.to_string()),
             risk: RiskConfig::default(),
+            min_equity_usd: env_parse("MIN_EQUITY_USD", DEFAULT_MIN_EQUITY_USD),
+            max_balance_fetch_failures: env_parse(
+                "MAX_BALANCE_FETCH_FAILURES",
+                DEFAULT_MAX_BALANCE_FETCH_FAILURES,
+            ),
+            align_bar_clock: env_bool("ALIGN_BAR_CLOCK").unwrap_or(DEFAULT_ALIGN_BAR_CLOCK),
+            history_price_format: parse_history_price_format(
+                &env::var("HISTORY_PRICE_FORMAT")
+                    .unwrap_or_else(|_| DEFAULT_HISTORY_PRICE_FORMAT.to_string()),
+            )?,
+            avoid_funding_window_secs: env_parse(
+                "AVOID_FUNDING_WINDOW_SECS",
+                DEFAULT_AVOID_FUNDING_WINDOW_SECS,
+            ),
+            funding_interval_secs: env_parse(
+                "FUNDING_INTERVAL_SECS",
+                DEFAULT_FUNDING_INTERVAL_SECS,
+            ),
+            close_profitable_before_funding: env_bool("CLOSE_PROFITABLE_BEFORE_FUNDING")
+                .unwrap_or(DEFAULT_CLOSE_PROFITABLE_BEFORE_FUNDING),
+            vol_target_sigma: env_parse("VOL_TARGET_SIGMA", DEFAULT_VOL_TARGET_SIGMA),
+            vol_target_min_notional: env_parse(
+                "VOL_TARGET_MIN_NOTIONAL",
+                DEFAULT_VOL_TARGET_MIN_NOTIONAL,
+            ),
+            vol_target_max_notional: env_parse(
+                "VOL_TARGET_MAX_NOTIONAL",
+                DEFAULT_VOL_TARGET_MAX_NOTIONAL,
+            ),
+            reconcile_concurrency: env_parse(
+                "RECONCILE_CONCURRENCY",
+                DEFAULT_RECONCILE_CONCURRENCY,
+            ),
+            data_dump_compress: env_bool("DATA_DUMP_COMPRESS")
+                .unwrap_or(DEFAULT_DATA_DUMP_COMPRESS),
+            trade_stats_reset: env::var("TRADE_STATS_RESET")
+                .ok()
+                .unwrap_or_else(|| DEFAULT_TRADE_STATS_RESET.to_string())
+                .parse()
+                .unwrap_or(TradeStatsReset::Session),
+            spread_smoothing_bars: env_parse("SPREAD_SMOOTHING_BARS", DEFAULT_SPREAD_SMOOTHING_BARS),
+            max_spread_gap_bars: env_parse("MAX_SPREAD_GAP_BARS", DEFAULT_MAX_SPREAD_GAP_BARS),
+            dust_sweep: env_bool("DUST_SWEEP").unwrap_or(DEFAULT_DUST_SWEEP),
+            beta_on_returns: env_bool("BETA_ON_RETURNS").unwrap_or(DEFAULT_BETA_ON_RETURNS),
+            orderbook_depth: env_parse("ORDERBOOK_DEPTH", DEFAULT_ORDERBOOK_DEPTH).max(1),
+            orderbook_depth_band_bps: env_parse(
+                "ORDERBOOK_DEPTH_BAND_BPS",
+                DEFAULT_ORDERBOOK_DEPTH_BAND_BPS,
+            ),
+            exit_z_require_profit: env_bool("EXIT_Z_REQUIRE_PROFIT")
+                .unwrap_or(DEFAULT_EXIT_Z_REQUIRE_PROFIT),
+            startup_force_close_backoff_mult: env_parse(
+                "STARTUP_FORCE_CLOSE_BACKOFF_MULT",
+                DEFAULT_STARTUP_FORCE_CLOSE_BACKOFF_MULT,
+            ),
+            startup_force_close_max_wait_secs: env_parse(
+                "STARTUP_FORCE_CLOSE_MAX_WAIT_SECS",
+                DEFAULT_STARTUP_FORCE_CLOSE_MAX_WAIT_SECS,
+            ),
+            startup_force_close_jitter_pct: env_parse(
+                "STARTUP_FORCE_CLOSE_JITTER_PCT",
+                DEFAULT_STARTUP_FORCE_CLOSE_JITTER_PCT,
+            ),
+            startup_cancel_wait_secs: env_parse("STARTUP_CANCEL_WAIT_SECS", DEFAULT_STARTUP_CANCEL_WAIT_SECS),
+            observe_simulate_pnl: env_bool("OBSERVE_SIMULATE_PNL")
+                .unwrap_or(DEFAULT_OBSERVE_SIMULATE_PNL),
+            auto_flatten_mismatched_legs: env_bool("AUTO_FLATTEN_MISMATCHED_LEGS")
+                .unwrap_or(DEFAULT_AUTO_FLATTEN_MISMATCHED_LEGS),
+            z_price_source: env::var("Z_PRICE_SOURCE")
+                .ok()
+                .unwrap_or_else(|| DEFAULT_Z_PRICE_SOURCE.to_string())
+                .parse()
+                .unwrap_or(PriceSource::Last),
+            no_trade_windows: schedule::parse_windows(
+                &env::var("NO_TRADE_WINDOWS").unwrap_or_default().split(';').collect::<Vec<_>>(),
+            )
+            .map_err(|e| anyhow!(e))?,
+            forensic_dump_dir: env::var("FORENSIC_DUMP_DIR")
+                .ok()
+                .filter(|v| !v.trim().is_empty()),
+            forensic_dump_keep: env_parse("FORENSIC_DUMP_KEEP", DEFAULT_FORENSIC_DUMP_KEEP),
+            min_book_notional_usd: env_parse("MIN_BOOK_NOTIONAL_USD", DEFAULT_MIN_BOOK_NOTIONAL_USD),
+            hedge_ratio_tolerance_pct: env_parse(
+                "HEDGE_RATIO_TOLERANCE_PCT",
+                DEFAULT_HEDGE_RATIO_TOLERANCE_PCT,
+            ),
+            status_price_dp: env::var("STATUS_PRICE_DP")
+                .ok()
+                .and_then(|v| v.trim().parse().ok()),
+            status_pnl_dp: env::var("STATUS_PNL_DP")
+                .ok()
+                .and_then(|v| v.trim().parse().ok()),
+            ticker_auth_retries: env_parse("TICKER_AUTH_RETRIES", DEFAULT_TICKER_AUTH_RETRIES),
+            ticker_auth_retry_ms: env_parse("TICKER_AUTH_RETRY_MS", DEFAULT_TICKER_AUTH_RETRY_MS),
+            normalize_legs: env_bool("NORMALIZE_LEGS").unwrap_or(DEFAULT_NORMALIZE_LEGS),
+            max_reissues_per_pair: env_parse("MAX_REISSUES_PER_PAIR", DEFAULT_MAX_REISSUES_PER_PAIR),
+            max_reissued_legs_per_cycle: env_parse(
+                "MAX_REISSUED_LEGS_PER_CYCLE",
+                DEFAULT_MAX_REISSUED_LEGS_PER_CYCLE,
+            ),
+            bar_source: env::var("BAR_SOURCE")
+                .ok()
+                .unwrap_or_else(|| DEFAULT_BAR_SOURCE.to_string())
+                .parse()
+                .unwrap_or(BarSource::Snapshot),
+            pending_max_age_secs: env_parse("PENDING_MAX_AGE_SECS", DEFAULT_PENDING_MAX_AGE_SECS),
+            equity_baseline_samples: env_parse(
+                "EQUITY_BASELINE_SAMPLES",
+                DEFAULT_EQUITY_BASELINE_SAMPLES,
+            )
+            .max(1),
+            history_backend: env::var("HISTORY_BACKEND")
+                .ok()
+                .unwrap_or_else(|| DEFAULT_HISTORY_BACKEND.to_string())
+                .parse()
+                .unwrap_or(HistoryBackend::Json),
+            allow_negative_beta: env_bool("ALLOW_NEGATIVE_BETA")
+                .unwrap_or(DEFAULT_ALLOW_NEGATIVE_BETA),
+            pair_idle_alert_secs: env_parse("PAIR_IDLE_ALERT_SECS", DEFAULT_PAIR_IDLE_ALERT_SECS),
+            pair_idle_notify: env_bool("PAIR_IDLE_NOTIFY").unwrap_or(DEFAULT_PAIR_IDLE_NOTIFY),
+            max_min_order_oversizing_factor: env_parse(
+                "MAX_MIN_ORDER_OVERSIZING_FACTOR",
+                DEFAULT_MAX_MIN_ORDER_OVERSIZING_FACTOR,
+            ),
+            position_audit_interval_secs: env_parse(
+                "POSITION_AUDIT_INTERVAL_SECS",
+                DEFAULT_POSITION_AUDIT_INTERVAL_SECS,
+            ),
+            position_audit_force_close: env_bool("POSITION_AUDIT_FORCE_CLOSE")
+                .unwrap_or(DEFAULT_POSITION_AUDIT_FORCE_CLOSE),
+            exit_post_only_wait_reasons: env::var("EXIT_POST_ONLY_WAIT_REASONS")
+                .ok()
+                .map(|v| StringOrVec::String(v).into_vec())
+                .unwrap_or_default(),
+            seed_new_pairs_from_history: env_bool("SEED_NEW_PAIRS_FROM_HISTORY")
+                .unwrap_or(DEFAULT_SEED_NEW_PAIRS_FROM_HISTORY),
+            max_abs_funding_per_hour: env_parse(
+                "MAX_ABS_FUNDING_PER_HOUR",
+                DEFAULT_MAX_ABS_FUNDING_PER_HOUR,
+            ),
+            matching_model: env::var("MATCHING_MODEL")
+                .ok()
+                .unwrap_or_else(|| DEFAULT_MATCHING_MODEL.to_string())
+                .parse()
+                .unwrap_or(MatchingModel::Full),
+            max_orders_per_symbol_per_min: env_parse(
+                "MAX_ORDERS_PER_SYMBOL_PER_MIN",
+                DEFAULT_MAX_ORDERS_PER_SYMBOL_PER_MIN,
+            ),
+            backtest_apply_costs: env_bool("BACKTEST_APPLY_COSTS")
+                .unwrap_or(DEFAULT_BACKTEST_APPLY_COSTS),
+            pair_eval_interval_secs: env_parse(
+                "PAIR_EVAL_INTERVAL_SECS",
+                DEFAULT_PAIR_EVAL_INTERVAL_SECS,
+            )
+            .max(1),
+            strict_universe_validation: env_bool("STRICT_UNIVERSE_VALIDATION")
+                .unwrap_or(DEFAULT_STRICT_UNIVERSE_VALIDATION),
+            skip_unchanged_bars: env_bool("SKIP_UNCHANGED_BARS")
+                .unwrap_or(DEFAULT_SKIP_UNCHANGED_BARS),
+            max_realized_slippage_bps: env_parse(
+                "MAX_REALIZED_SLIPPAGE_BPS",
+                DEFAULT_MAX_REALIZED_SLIPPAGE_BPS,
+            ),
+            realized_slippage_block_secs: env_parse(
+                "REALIZED_SLIPPAGE_BLOCK_SECS",
+                DEFAULT_REALIZED_SLIPPAGE_BLOCK_SECS,
+            ),
+            persist_trade_stats: env_bool("PERSIST_TRADE_STATS")
+                .unwrap_or(DEFAULT_PERSIST_TRADE_STATS),
+            backtest_full_sync: env_bool("BACKTEST_FULL_SYNC")
+                .unwrap_or(DEFAULT_BACKTEST_FULL_SYNC),
+            log_price_snapshot_every_n_ticks: env_parse(
+                "LOG_PRICE_SNAPSHOT_EVERY_N_TICKS",
+                DEFAULT_LOG_PRICE_SNAPSHOT_EVERY_N_TICKS,
+            ),
+            log_zcheck_every_n_ticks: env_parse(
+                "LOG_ZCHECK_EVERY_N_TICKS",
+                DEFAULT_LOG_ZCHECK_EVERY_N_TICKS,
+            ),
+            log_order_params_every_n_ticks: env_parse(
+                "LOG_ORDER_PARAMS_EVERY_N_TICKS",
+                DEFAULT_LOG_ORDER_PARAMS_EVERY_N_TICKS,
+            ),
+            beta_drift_exit_pct: env_parse("BETA_DRIFT_EXIT_PCT", DEFAULT_BETA_DRIFT_EXIT_PCT),
+            beta_drift_action: env::var("BETA_DRIFT_ACTION")
+                .ok()
+                .unwrap_or_else(|| DEFAULT_BETA_DRIFT_ACTION.to_string())
+                .parse()
+                .unwrap_or(BetaDriftAction::Exit),
+            z_stat_mode: env::var("Z_STAT_MODE")
+                .ok()
+                .unwrap_or_else(|| DEFAULT_Z_STAT_MODE.to_string())
+                .parse()
+                .unwrap_or(ZStatMode::Simple),
+            max_open_orders: env_parse("MAX_OPEN_ORDERS", DEFAULT_MAX_OPEN_ORDERS),
+            entry_max_book_spread_bps: env_parse(
+                "ENTRY_MAX_BOOK_SPREAD_BPS",
+                DEFAULT_ENTRY_MAX_BOOK_SPREAD_BPS,
+            ),
+            eligibility_max_age_secs: env_parse(
+                "ELIGIBILITY_MAX_AGE_SECS",
+                DEFAULT_ELIGIBILITY_MAX_AGE_SECS,
+            ),
+            reconcile_fill_pnl: env_bool("RECONCILE_FILL_PNL")
+                .unwrap_or(DEFAULT_RECONCILE_FILL_PNL),
+            dust_notional_usd: env_parse("DUST_NOTIONAL_USD", DEFAULT_DUST_NOTIONAL_USD),
+            dust_notional_usd_by_symbol: env::var("DUST_NOTIONAL_USD_BY_SYMBOL")
+                .map(|v| parse_symbol_values(&v))
+                .unwrap_or_default(),
+            exit_market_reasons: StringOrVec::String(
+                env::var("EXIT_MARKET_REASONS")
+                    .unwrap_or_else(|_| DEFAULT_EXIT_MARKET_REASONS.to_string()),
+            )
+            .into_vec(),
+            position_age_warn_secs: env_parse(
+                "POSITION_AGE_WARN_SECS",
+                DEFAULT_POSITION_AGE_WARN_SECS,
+            ),
+            directional_eligibility: env_bool("DIRECTIONAL_ELIGIBILITY")
+                .unwrap_or(DEFAULT_DIRECTIONAL_ELIGIBILITY),
+            max_bar_span_mult: env_parse("MAX_BAR_SPAN_MULT", DEFAULT_MAX_BAR_SPAN_MULT),
+            min_interval_secs: env_parse("MIN_INTERVAL_SECS", DEFAULT_MIN_INTERVAL_SECS),
+            max_interval_secs: env_parse("MAX_INTERVAL_SECS", DEFAULT_MAX_INTERVAL_SECS),
+            min_hold_secs: env_parse("MIN_HOLD_SECS", DEFAULT_MIN_HOLD_SECS),
+            eval_history_file: env::var("EVAL_HISTORY_FILE")
+                .ok()
+                .filter(|v| !v.trim().is_empty()),
+            partial_take_profit_fraction: env_parse(
+                "PARTIAL_TAKE_PROFIT_FRACTION",
+                DEFAULT_PARTIAL_TAKE_PROFIT_FRACTION,
+            ),
+            partial_take_profit_r: env_parse("PARTIAL_TAKE_PROFIT_R", DEFAULT_PARTIAL_TAKE_PROFIT_R),
+            status_size_adjustments: env_bool("STATUS_SIZE_ADJUSTMENTS")
+                .unwrap_or(DEFAULT_STATUS_SIZE_ADJUSTMENTS),
+            max_equity_fallback_cycles: env_parse(
+                "MAX_EQUITY_FALLBACK_CYCLES",
+                DEFAULT_MAX_EQUITY_FALLBACK_CYCLES,
+            ),
+            min_spread_history_points: env_parse(
+                "MIN_SPREAD_HISTORY_POINTS",
+                DEFAULT_MIN_SPREAD_HISTORY_POINTS,
+            ),
+            min_symbol_history_bars: env_parse("MIN_SYMBOL_HISTORY_BARS", DEFAULT_MIN_SYMBOL_HISTORY_BARS),
+            shared_symbol_policy: env::var("SHARED_SYMBOL_POLICY")
+                .ok()
+                .unwrap_or_else(|| DEFAULT_SHARED_SYMBOL_POLICY.to_string())
+                .parse()
+                .unwrap_or(SharedSymbolPolicy::Block),
+            max_net_symbol_exposure_usd: env_parse(
+                "MAX_NET_SYMBOL_EXPOSURE_USD",
+                DEFAULT_MAX_NET_SYMBOL_EXPOSURE_USD,
+            ),
+            metrics_log_interval_secs: env_parse(
+                "METRICS_LOG_INTERVAL_SECS",
+                DEFAULT_METRICS_LOG_INTERVAL_SECS,
+            ),
+            metrics_log_format: env::var("METRICS_LOG_FORMAT")
+                .ok()
+                .unwrap_or_else(|| DEFAULT_METRICS_LOG_FORMAT.to_string())
+                .parse()
+                .unwrap_or(MetricsLogFormat::Text),
+            metrics_log_detail: env_bool("METRICS_LOG_DETAIL")
+                .unwrap_or(DEFAULT_METRICS_LOG_DETAIL),
+            portfolio_funding_aware: env_bool("PORTFOLIO_FUNDING_AWARE")
+                .unwrap_or(DEFAULT_PORTFOLIO_FUNDING_AWARE),
+            preserve_symbol_order: preserve_symbol_order_from(None),
+            single_position_mode: env_bool("SINGLE_POSITION_MODE")
+                .unwrap_or(DEFAULT_SINGLE_POSITION_MODE),
+            reject_crossed_book: env_bool("REJECT_CROSSED_BOOK")
+                .unwrap_or(DEFAULT_REJECT_CROSSED_BOOK),
+            equity_history_detailed: env_bool("EQUITY_HISTORY_DETAILED")
+                .unwrap_or(DEFAULT_EQUITY_HISTORY_DETAILED),
+            auto_restart_after_failures: env_parse(
+                "AUTO_RESTART_AFTER_FAILURES",
+                DEFAULT_AUTO_RESTART_AFTER_FAILURES,
+            ),
+            auto_restart_cooldown_secs: env_parse(
+                "AUTO_RESTART_COOLDOWN_SECS",
+                DEFAULT_AUTO_RESTART_COOLDOWN_SECS,
+            ),
+            maker_fee_bps_by_symbol: env::var("MAKER_FEE_BPS_BY_SYMBOL")
+                .map(|v| parse_symbol_values(&v))
+                .unwrap_or_default(),
+            taker_fee_bps_by_symbol: env::var("TAKER_FEE_BPS_BY_SYMBOL")
+                .map(|v| parse_symbol_values(&v))
+                .unwrap_or_default(),
+            max_bar_log_return: env_parse("MAX_BAR_LOG_RETURN", DEFAULT_MAX_BAR_LOG_RETURN),
+            scale_in_enabled: env_bool("SCALE_IN_ENABLED").unwrap_or(DEFAULT_SCALE_IN_ENABLED),
+            scale_in_add_z: env_parse("SCALE_IN_ADD_Z", DEFAULT_SCALE_IN_ADD_Z),
+            scale_in_fraction: env_parse("SCALE_IN_FRACTION", DEFAULT_SCALE_IN_FRACTION),
+            non_finite_stats_block_secs: env_parse(
+                "NON_FINITE_STATS_BLOCK_SECS",
+                DEFAULT_NON_FINITE_STATS_BLOCK_SECS,
+            ),
+            entry_market_mode: env_bool("ENTRY_MARKET_MODE").unwrap_or(DEFAULT_ENTRY_MARKET_MODE),
+            rejected_entries_file: env::var("REJECTED_ENTRIES_FILE")
+                .ok()
+                .filter(|v| !v.trim().is_empty()),
+            min_eligible_pairs: env_parse("MIN_ELIGIBLE_PAIRS", DEFAULT_MIN_ELIGIBLE_PAIRS),
+            plan_trace_file: env::var("PLAN_TRACE_FILE")
+                .ok()
+                .filter(|v| !v.trim().is_empty()),
+            loss_reentry_block_ticks: env_parse(
+                "LOSS_REENTRY_BLOCK_TICKS",
+                DEFAULT_LOSS_REENTRY_BLOCK_TICKS,
+            ),
+            missed_tick_behavior: env::var("MISSED_TICK_BEHAVIOR")
+                .ok()
+                .unwrap_or_else(|| DEFAULT_MISSED_TICK_BEHAVIOR.to_string())
+                .parse()
+                .unwrap_or(MissedTickBehavior::Skip),
+            delta_rebalance_enabled: env_bool("DELTA_REBALANCE_ENABLED")
+                .unwrap_or(DEFAULT_DELTA_REBALANCE_ENABLED),
+            rebalance_threshold_usd: env_parse(
+                "REBALANCE_THRESHOLD_USD",
+                DEFAULT_REBALANCE_THRESHOLD_USD,
+            ),
+            delta_rebalance_interval_secs: env_parse(
+                "DELTA_REBALANCE_INTERVAL_SECS",
+                DEFAULT_DELTA_REBALANCE_INTERVAL_SECS,
+            ),
+            recover_entry_time_from_risk_state: env_bool("RECOVER_ENTRY_TIME_FROM_RISK_STATE")
+                .unwrap_or(DEFAULT_RECOVER_ENTRY_TIME_FROM_RISK_STATE),
         };
         cfg.default_pair_params = default_pair_params_from_env();
         if cfg.default_pair_params.warm_start_min_bars == 0 {
@@ -1071,6 +2631,11 @@ impl PairTradeConfig {
         env_override("STOP_LOSS_Z_SCORE", &mut self.default_pair_params.stop_loss_z);
         env_override("FORCE_CLOSE_TIME_SECS", &mut self.default_pair_params.force_close_secs);
         env_override("COOLDOWN_SECS", &mut self.default_pair_params.cooldown_secs);
+        if let Ok(value) = env::var("STOP_LOSS_COOLDOWN_SECS") {
+            if let Ok(parsed) = value.parse() {
+                self.default_pair_params.stop_loss_cooldown_secs = Some(parsed);
+            }
+        }
         env_override("NET_FUNDING_MIN_PER_HOUR", &mut self.net_funding_min_per_hour);
         env_override("SPREAD_VELOCITY_MAX_SIGMA_PER_MIN", &mut self.default_pair_params.spread_velocity_max_sigma_per_min);
         env_override("NOTIONAL_PER_LEG_USD", &mut self.notional_per_leg);
@@ -1121,9 +2686,8 @@ impl PairTradeConfig {
                 self.startup_force_close_wait_secs = parsed;
             }
         }
-        if let Ok(value) = env::var("FORCE_CLOSE_ON_STARTUP") {
-            let lower = value.trim().to_ascii_lowercase();
-            self.force_close_on_startup = lower == "1" || lower == "true" || lower == "yes";
+        if let Some(value) = env_bool("FORCE_CLOSE_ON_STARTUP") {
+            self.force_close_on_startup = value;
         }
 
         let env_pairs = env::var("UNIVERSE_PAIRS")
@@ -1135,8 +2699,9 @@ impl PairTradeConfig {
             .map(|value| value.trim().to_string())
             .filter(|value| !value.is_empty());
         let universe_overridden = env_pairs.is_some() || env_symbols.is_some();
+        self.preserve_symbol_order = preserve_symbol_order_from(Some(self.preserve_symbol_order));
         if universe_overridden {
-            self.universe = parse_universe_pairs()?;
+            self.universe = parse_universe_pairs(self.preserve_symbol_order)?;
         }
 
         if let Ok(value) = env::var("ENABLE_DATA_DUMP") {
@@ -1162,9 +2727,8 @@ impl PairTradeConfig {
         // collects data and trades live (e.g. debot-pair-btceth running
         // as the data collector while the A leg of an A/B test).
 
-        if let Ok(value) = env::var("DISABLE_HISTORY_PERSIST") {
-            let lower = value.trim().to_ascii_lowercase();
-            self.disable_history_persist = lower == "1" || lower == "true" || lower == "yes";
+        if let Some(value) = env_bool("DISABLE_HISTORY_PERSIST") {
+            self.disable_history_persist = value;
         }
         if let Ok(value) = env::var("PAIRTRADE_HISTORY_FILE") {
             if !value.trim().is_empty() {
@@ -1182,9 +2746,12 @@ impl PairTradeConfig {
                 self.backtest_file = Some(value);
             }
         }
-        if self.backtest_mode && self.backtest_file.is_none() {
+        if let Ok(value) = env::var("BACKTEST_FILES") {
+            self.backtest_files = StringOrVec::String(value).into_vec();
+        }
+        if self.backtest_mode && self.backtest_file.is_none() && self.backtest_files.is_empty() {
             return Err(anyhow!(
-                "BACKTEST_FILE must be set if BACKTEST_MODE is true"
+                "BACKTEST_FILE or BACKTEST_FILES must be set if BACKTEST_MODE is true"
             ));
         }
         if let Ok(value) = env::var("BT_WARM_START_SNAPSHOT") {
@@ -1229,11 +2796,10 @@ impl PairTradeConfig {
             "STD_COLLAPSE_MIN_RATIO",
             &mut self.default_pair_params.std_collapse_min_ratio,
         );
-        if let Ok(value) = env::var("STD_COLLAPSE_OBSERVE_ONLY") {
-            let lower = value.trim().to_ascii_lowercase();
-            self.default_pair_params.std_collapse_observe_only =
-                matches!(lower.as_str(), "1" | "true" | "yes");
+        if let Some(value) = env_bool("STD_COLLAPSE_OBSERVE_ONLY") {
+            self.default_pair_params.std_collapse_observe_only = value;
         }
+        env_override("MIN_SPREAD_STD", &mut self.default_pair_params.min_spread_std);
 
         // Kalman filter
         if let Ok(value) = env::var("USE_KALMAN_BETA") {
@@ -1255,6 +2821,275 @@ impl PairTradeConfig {
             }
         }
 
+        // Equity guard
+        env_override("MIN_EQUITY_USD", &mut self.min_equity_usd);
+        env_override("MAX_BALANCE_FETCH_FAILURES", &mut self.max_balance_fetch_failures);
+
+        if let Some(value) = env_bool("ALIGN_BAR_CLOCK") {
+            self.align_bar_clock = value;
+        }
+        if let Ok(value) = env::var("HISTORY_PRICE_FORMAT") {
+            self.history_price_format = parse_history_price_format(&value)?;
+        }
+        // Funding window
+        env_override("AVOID_FUNDING_WINDOW_SECS", &mut self.avoid_funding_window_secs);
+        env_override("FUNDING_INTERVAL_SECS", &mut self.funding_interval_secs);
+        if let Some(value) = env_bool("CLOSE_PROFITABLE_BEFORE_FUNDING") {
+            self.close_profitable_before_funding = value;
+        }
+        // Vol-targeted sizing
+        env_override("VOL_TARGET_SIGMA", &mut self.vol_target_sigma);
+        env_override("VOL_TARGET_MIN_NOTIONAL", &mut self.vol_target_min_notional);
+        env_override("VOL_TARGET_MAX_NOTIONAL", &mut self.vol_target_max_notional);
+        env_override("RECONCILE_CONCURRENCY", &mut self.reconcile_concurrency);
+        if let Some(value) = env_bool("DATA_DUMP_COMPRESS") {
+            self.data_dump_compress = value;
+        }
+        env_override("TRADE_STATS_RESET", &mut self.trade_stats_reset);
+        env_override("SPREAD_SMOOTHING_BARS", &mut self.spread_smoothing_bars);
+        env_override("MAX_SPREAD_GAP_BARS", &mut self.max_spread_gap_bars);
+        if let Some(value) = env_bool("DUST_SWEEP") {
+            self.dust_sweep = value;
+        }
+        if let Some(value) = env_bool("BETA_ON_RETURNS") {
+            self.beta_on_returns = value;
+        }
+        env_override("ORDERBOOK_DEPTH", &mut self.orderbook_depth);
+        self.orderbook_depth = self.orderbook_depth.max(1);
+        env_override("ORDERBOOK_DEPTH_BAND_BPS", &mut self.orderbook_depth_band_bps);
+        if let Some(value) = env_bool("EXIT_Z_REQUIRE_PROFIT") {
+            self.exit_z_require_profit = value;
+        }
+        env_override(
+            "STARTUP_FORCE_CLOSE_BACKOFF_MULT",
+            &mut self.startup_force_close_backoff_mult,
+        );
+        env_override(
+            "STARTUP_FORCE_CLOSE_MAX_WAIT_SECS",
+            &mut self.startup_force_close_max_wait_secs,
+        );
+        env_override(
+            "STARTUP_FORCE_CLOSE_JITTER_PCT",
+            &mut self.startup_force_close_jitter_pct,
+        );
+        env_override("STARTUP_CANCEL_WAIT_SECS", &mut self.startup_cancel_wait_secs);
+        if let Some(value) = env_bool("OBSERVE_SIMULATE_PNL") {
+            self.observe_simulate_pnl = value;
+        }
+        if let Some(value) = env_bool("AUTO_FLATTEN_MISMATCHED_LEGS") {
+            self.auto_flatten_mismatched_legs = value;
+        }
+        env_override("Z_PRICE_SOURCE", &mut self.z_price_source);
+        if let Ok(value) = env::var("NO_TRADE_WINDOWS") {
+            self.no_trade_windows = schedule::parse_windows(&value.split(';').collect::<Vec<_>>())
+                .map_err(|e| anyhow!(e))?;
+        }
+        if let Ok(value) = env::var("FORENSIC_DUMP_DIR") {
+            if !value.trim().is_empty() {
+                self.forensic_dump_dir = Some(value);
+            }
+        }
+        env_override("FORENSIC_DUMP_KEEP", &mut self.forensic_dump_keep);
+        env_override("MIN_BOOK_NOTIONAL_USD", &mut self.min_book_notional_usd);
+        env_override("HEDGE_RATIO_TOLERANCE_PCT", &mut self.hedge_ratio_tolerance_pct);
+        if let Some(dp) = env::var("STATUS_PRICE_DP").ok().and_then(|v| v.trim().parse().ok()) {
+            self.status_price_dp = Some(dp);
+        }
+        if let Some(dp) = env::var("STATUS_PNL_DP").ok().and_then(|v| v.trim().parse().ok()) {
+            self.status_pnl_dp = Some(dp);
+        }
+        env_override("TICKER_AUTH_RETRIES", &mut self.ticker_auth_retries);
+        env_override("TICKER_AUTH_RETRY_MS", &mut self.ticker_auth_retry_ms);
+        if let Some(value) = env_bool("NORMALIZE_LEGS") {
+            self.normalize_legs = value;
+        }
+        env_override("MAX_REISSUES_PER_PAIR", &mut self.max_reissues_per_pair);
+        env_override(
+            "MAX_REISSUED_LEGS_PER_CYCLE",
+            &mut self.max_reissued_legs_per_cycle,
+        );
+        env_override("BAR_SOURCE", &mut self.bar_source);
+        env_override("PENDING_MAX_AGE_SECS", &mut self.pending_max_age_secs);
+        env_override("EQUITY_BASELINE_SAMPLES", &mut self.equity_baseline_samples);
+        self.equity_baseline_samples = self.equity_baseline_samples.max(1);
+        env_override("HISTORY_BACKEND", &mut self.history_backend);
+        if let Some(value) = env_bool("ALLOW_NEGATIVE_BETA") {
+            self.allow_negative_beta = value;
+        }
+        env_override("PAIR_IDLE_ALERT_SECS", &mut self.pair_idle_alert_secs);
+        if let Some(value) = env_bool("PAIR_IDLE_NOTIFY") {
+            self.pair_idle_notify = value;
+        }
+        env_override(
+            "MAX_MIN_ORDER_OVERSIZING_FACTOR",
+            &mut self.max_min_order_oversizing_factor,
+        );
+        env_override(
+            "POSITION_AUDIT_INTERVAL_SECS",
+            &mut self.position_audit_interval_secs,
+        );
+        if let Some(value) = env_bool("POSITION_AUDIT_FORCE_CLOSE") {
+            self.position_audit_force_close = value;
+        }
+        if let Ok(value) = env::var("EXIT_POST_ONLY_WAIT_REASONS") {
+            self.exit_post_only_wait_reasons = StringOrVec::String(value).into_vec();
+        }
+        if let Some(value) = env_bool("SEED_NEW_PAIRS_FROM_HISTORY") {
+            self.seed_new_pairs_from_history = value;
+        }
+        env_override(
+            "MAX_ABS_FUNDING_PER_HOUR",
+            &mut self.max_abs_funding_per_hour,
+        );
+        env_override("MATCHING_MODEL", &mut self.matching_model);
+        env_override(
+            "MAX_ORDERS_PER_SYMBOL_PER_MIN",
+            &mut self.max_orders_per_symbol_per_min,
+        );
+        if let Some(value) = env_bool("BACKTEST_APPLY_COSTS") {
+            self.backtest_apply_costs = value;
+        }
+        env_override(
+            "PAIR_EVAL_INTERVAL_SECS",
+            &mut self.pair_eval_interval_secs,
+        );
+        self.pair_eval_interval_secs = self.pair_eval_interval_secs.max(1);
+        if let Some(value) = env_bool("STRICT_UNIVERSE_VALIDATION") {
+            self.strict_universe_validation = value;
+        }
+        if let Some(value) = env_bool("SKIP_UNCHANGED_BARS") {
+            self.skip_unchanged_bars = value;
+        }
+        env_override(
+            "MAX_REALIZED_SLIPPAGE_BPS",
+            &mut self.max_realized_slippage_bps,
+        );
+        env_override(
+            "REALIZED_SLIPPAGE_BLOCK_SECS",
+            &mut self.realized_slippage_block_secs,
+        );
+        if let Some(value) = env_bool("PERSIST_TRADE_STATS") {
+            self.persist_trade_stats = value;
+        }
+        if let Some(value) = env_bool("BACKTEST_FULL_SYNC") {
+            self.backtest_full_sync = value;
+        }
+        env_override(
+            "LOG_PRICE_SNAPSHOT_EVERY_N_TICKS",
+            &mut self.log_price_snapshot_every_n_ticks,
+        );
+        env_override(
+            "LOG_ZCHECK_EVERY_N_TICKS",
+            &mut self.log_zcheck_every_n_ticks,
+        );
+        env_override(
+            "LOG_ORDER_PARAMS_EVERY_N_TICKS",
+            &mut self.log_order_params_every_n_ticks,
+        );
+        env_override("BETA_DRIFT_EXIT_PCT", &mut self.beta_drift_exit_pct);
+        env_override("BETA_DRIFT_ACTION", &mut self.beta_drift_action);
+        env_override("Z_STAT_MODE", &mut self.z_stat_mode);
+        env_override("MAX_OPEN_ORDERS", &mut self.max_open_orders);
+        env_override(
+            "ENTRY_MAX_BOOK_SPREAD_BPS",
+            &mut self.entry_max_book_spread_bps,
+        );
+        env_override(
+            "ELIGIBILITY_MAX_AGE_SECS",
+            &mut self.eligibility_max_age_secs,
+        );
+        if let Some(value) = env_bool("RECONCILE_FILL_PNL") {
+            self.reconcile_fill_pnl = value;
+        }
+        env_override("DUST_NOTIONAL_USD", &mut self.dust_notional_usd);
+        if let Ok(value) = env::var("DUST_NOTIONAL_USD_BY_SYMBOL") {
+            self.dust_notional_usd_by_symbol = parse_symbol_values(&value);
+        }
+        if let Ok(value) = env::var("EXIT_MARKET_REASONS") {
+            self.exit_market_reasons = StringOrVec::String(value).into_vec();
+        }
+        env_override("POSITION_AGE_WARN_SECS", &mut self.position_age_warn_secs);
+        if let Some(value) = env_bool("DIRECTIONAL_ELIGIBILITY") {
+            self.directional_eligibility = value;
+        }
+        env_override("MAX_BAR_SPAN_MULT", &mut self.max_bar_span_mult);
+        env_override("MIN_INTERVAL_SECS", &mut self.min_interval_secs);
+        env_override("MAX_INTERVAL_SECS", &mut self.max_interval_secs);
+        env_override("MIN_HOLD_SECS", &mut self.min_hold_secs);
+        if let Ok(value) = env::var("EVAL_HISTORY_FILE") {
+            if !value.trim().is_empty() {
+                self.eval_history_file = Some(value);
+            }
+        }
+        env_override("PARTIAL_TAKE_PROFIT_FRACTION", &mut self.partial_take_profit_fraction);
+        env_override("PARTIAL_TAKE_PROFIT_R", &mut self.partial_take_profit_r);
+        if let Some(value) = env_bool("STATUS_SIZE_ADJUSTMENTS") {
+            self.status_size_adjustments = value;
+        }
+        env_override("MAX_EQUITY_FALLBACK_CYCLES", &mut self.max_equity_fallback_cycles);
+        env_override("MIN_SPREAD_HISTORY_POINTS", &mut self.min_spread_history_points);
+        env_override("MIN_SYMBOL_HISTORY_BARS", &mut self.min_symbol_history_bars);
+        env_override("SHARED_SYMBOL_POLICY", &mut self.shared_symbol_policy);
+        env_override("MAX_NET_SYMBOL_EXPOSURE_USD", &mut self.max_net_symbol_exposure_usd);
+        env_override("METRICS_LOG_INTERVAL_SECS", &mut self.metrics_log_interval_secs);
+        env_override("METRICS_LOG_FORMAT", &mut self.metrics_log_format);
+        if let Some(value) = env_bool("METRICS_LOG_DETAIL") {
+            self.metrics_log_detail = value;
+        }
+        if let Some(value) = env_bool("PORTFOLIO_FUNDING_AWARE") {
+            self.portfolio_funding_aware = value;
+        }
+        if let Some(value) = env_bool("SINGLE_POSITION_MODE") {
+            self.single_position_mode = value;
+        }
+        if let Some(value) = env_bool("REJECT_CROSSED_BOOK") {
+            self.reject_crossed_book = value;
+        }
+        if let Some(value) = env_bool("EQUITY_HISTORY_DETAILED") {
+            self.equity_history_detailed = value;
+        }
+        env_override("AUTO_RESTART_AFTER_FAILURES", &mut self.auto_restart_after_failures);
+        env_override("AUTO_RESTART_COOLDOWN_SECS", &mut self.auto_restart_cooldown_secs);
+        if let Ok(value) = env::var("MAKER_FEE_BPS_BY_SYMBOL") {
+            self.maker_fee_bps_by_symbol = parse_symbol_values(&value);
+        }
+        if let Ok(value) = env::var("TAKER_FEE_BPS_BY_SYMBOL") {
+            self.taker_fee_bps_by_symbol = parse_symbol_values(&value);
+        }
+        env_override("MAX_BAR_LOG_RETURN", &mut self.max_bar_log_return);
+        if let Some(value) = env_bool("SCALE_IN_ENABLED") {
+            self.scale_in_enabled = value;
+        }
+        env_override("SCALE_IN_ADD_Z", &mut self.scale_in_add_z);
+        env_override("SCALE_IN_FRACTION", &mut self.scale_in_fraction);
+        env_override("NON_FINITE_STATS_BLOCK_SECS", &mut self.non_finite_stats_block_secs);
+        if let Some(value) = env_bool("ENTRY_MARKET_MODE") {
+            self.entry_market_mode = value;
+        }
+        if let Ok(value) = env::var("REJECTED_ENTRIES_FILE") {
+            if !value.trim().is_empty() {
+                self.rejected_entries_file = Some(value);
+            }
+        }
+        env_override("MIN_ELIGIBLE_PAIRS", &mut self.min_eligible_pairs);
+        if let Ok(value) = env::var("PLAN_TRACE_FILE") {
+            if !value.trim().is_empty() {
+                self.plan_trace_file = Some(value);
+            }
+        }
+        env_override("LOSS_REENTRY_BLOCK_TICKS", &mut self.loss_reentry_block_ticks);
+        env_override("MISSED_TICK_BEHAVIOR", &mut self.missed_tick_behavior);
+        if let Some(value) = env_bool("DELTA_REBALANCE_ENABLED") {
+            self.delta_rebalance_enabled = value;
+        }
+        env_override("REBALANCE_THRESHOLD_USD", &mut self.rebalance_threshold_usd);
+        env_override(
+            "DELTA_REBALANCE_INTERVAL_SECS",
+            &mut self.delta_rebalance_interval_secs,
+        );
+        if let Some(value) = env_bool("RECOVER_ENTRY_TIME_FROM_RISK_STATE") {
+            self.recover_entry_time_from_risk_state = value;
+        }
         Ok(())
     }
 
@@ -1262,6 +3097,147 @@ impl PairTradeConfig {
         self.slippage_bps.max(0) as f64
     }
 
+    /// Paper entry prices already carry the round-trip cost
+    /// (`backtest_apply_costs` in a backtest), so PnL off them is net.
+    pub(super) fn fills_include_costs(&self) -> bool {
+        self.backtest_mode && self.backtest_apply_costs
+    }
+
+    /// Per-leg cost folded into a backtest paper entry price when
+    /// `backtest_apply_costs` is set. Paper exits book at the raw snapshot
+    /// price, so the entry basis carries the whole round trip: slippage on
+    /// both fills, the entry fee, and the taker fee of the exit, which
+    /// fills at once like a market close.
+    pub(super) fn backtest_round_trip_cost_bps(&self, symbol: &str) -> f64 {
+        2.0 * self.slippage_cost_bps()
+            + self.leg_fee_bps(symbol).max(0.0)
+            + self.fee_bps_for(symbol, false).max(0.0)
+    }
+
+    /// Venues where orders can be sent post-only.
+    pub(super) fn post_only_supported(&self) -> bool {
+        let dex = self.dex_name.to_ascii_lowercase();
+        dex.contains("lighter") || dex.contains("extended")
+    }
+
+    pub(super) fn should_post_only(&self) -> bool {
+        self.fee_bps > 0.0 && self.post_only_supported()
+    }
+
+    /// Fee of one fill on `symbol` in bps: its maker or taker entry in
+    /// `*_fee_bps_by_symbol`, else the global `fee_bps`.
+    pub(super) fn fee_bps_for(&self, symbol: &str, maker: bool) -> f64 {
+        let by_symbol = if maker {
+            &self.maker_fee_bps_by_symbol
+        } else {
+            &self.taker_fee_bps_by_symbol
+        };
+        by_symbol.get(symbol).copied().unwrap_or(self.fee_bps)
+    }
+
+    /// Fee the cost estimates assume for a regular fill on `symbol`: maker
+    /// when orders go out post-only, taker otherwise.
+    pub(super) fn leg_fee_bps(&self, symbol: &str) -> f64 {
+        self.fee_bps_for(symbol, self.should_post_only())
+    }
+
+    /// Fee the cost estimates assume for the closing fill on `symbol` of an
+    /// exit tagged `reason`. The maker rate applies only to a post-only
+    /// close that waits rather than fall back to taker; `exit_market_reasons`
+    /// closes, `universe_removed` and taker fallbacks pay the taker rate.
+    pub(super) fn exit_fee_bps(&self, symbol: &str, reason: &str) -> f64 {
+        let maker = self.should_post_only()
+            && reason != "universe_removed"
+            && !self.exit_uses_market(reason)
+            && !self.exit_taker_fallback_allowed(reason);
+        self.fee_bps_for(symbol, maker)
+    }
+
+    /// Estimated cost of one fill on each leg of `pair_key` ("BASE/QUOTE"),
+    /// fees plus slippage, in bps. A key without `/` is charged as if both
+    /// legs were `pair_key`.
+    pub(super) fn pair_cost_bps(&self, pair_key: &str) -> f64 {
+        let (base, quote) = pair_key.split_once('/').unwrap_or((pair_key, pair_key));
+        self.leg_fee_bps(base) + self.leg_fee_bps(quote) + self.slippage_cost_bps() * 2.0
+    }
+
+    /// `pair_cost_bps` for the closing fills of an exit tagged `reason`,
+    /// each leg at its `exit_fee_bps`.
+    pub(super) fn pair_exit_cost_bps(&self, pair_key: &str, reason: &str) -> f64 {
+        let (base, quote) = pair_key.split_once('/').unwrap_or((pair_key, pair_key));
+        self.exit_fee_bps(base, reason)
+            + self.exit_fee_bps(quote, reason)
+            + self.slippage_cost_bps() * 2.0
+    }
+
+    /// The pair's last successful evaluation is older than
+    /// `eligibility_max_age_secs`. Never stale with the check disabled or
+    /// before the first evaluation, which entries already wait for.
+    pub(super) fn eligibility_stale(&self, last_evaluated_ts: Option<i64>, now_ts: i64) -> bool {
+        self.eligibility_max_age_secs > 0
+            && last_evaluated_ts.is_some_and(|ts| {
+                now_ts.saturating_sub(ts) > self.eligibility_max_age_secs as i64
+            })
+    }
+
+    /// Dust notional floor for `symbol`: its `dust_notional_usd_by_symbol`
+    /// entry, else the global `dust_notional_usd`.
+    pub(super) fn dust_notional_for(&self, symbol: &str) -> f64 {
+        self.dust_notional_usd_by_symbol
+            .get(symbol)
+            .copied()
+            .unwrap_or(self.dust_notional_usd)
+    }
+
+    /// Entries and exits are simulated as paper fills at the snapshot price:
+    /// dry-run, or observe_only with `observe_simulate_pnl`.
+    pub(super) fn paper_fills(&self) -> bool {
+        self.dry_run || self.observe_paper_fills()
+    }
+
+    /// observe_only forward-testing with paper PnL. The exchange account is
+    /// never traded, so paper positions are the book of record.
+    pub(super) fn observe_paper_fills(&self) -> bool {
+        self.observe_only && self.observe_simulate_pnl
+    }
+
+    /// Wait after startup force-close attempt `attempt` (1-based), before
+    /// jitter: `startup_force_close_wait_secs` grown by
+    /// `startup_force_close_backoff_mult` per attempt, capped at
+    /// `startup_force_close_max_wait_secs` when that is non-zero.
+    pub(super) fn startup_force_close_wait_for(&self, attempt: u32) -> Duration {
+        let base = self.startup_force_close_wait_secs as f64;
+        let mult = self.startup_force_close_backoff_mult.max(1.0);
+        let mut secs = base * mult.powi(attempt.saturating_sub(1) as i32);
+        if self.startup_force_close_max_wait_secs > 0 {
+            secs = secs.min(self.startup_force_close_max_wait_secs.max(base as u64) as f64);
+        }
+        Duration::from_secs_f64(secs.max(0.0))
+    }
+
+    /// Whether a post-only close for `reason` may fall back to taker after
+    /// its attempts are exhausted (`exit_post_only_wait_reasons`).
+    pub(super) fn exit_taker_fallback_allowed(&self, reason: &str) -> bool {
+        if CRITICAL_EXIT_REASONS.contains(&reason) {
+            return true;
+        }
+        !self
+            .exit_post_only_wait_reasons
+            .iter()
+            .any(|r| r == reason || r == "all")
+    }
+
+    /// Whether an exit for `reason` goes straight to market orders
+    /// (`exit_market_reasons`). A `force_close` always does: the hold limit
+    /// is a hard stop, not something to wait on a maker fill for.
+    pub(super) fn exit_uses_market(&self, reason: &str) -> bool {
+        reason == "force_close"
+            || self
+                .exit_market_reasons
+                .iter()
+                .any(|r| r == reason || r == "all")
+    }
+
     pub(super) fn circuit_breaker_cooldown_for(&self, losses: u32) -> Option<Duration> {
         let dpp = &self.default_pair_params;
         // Graduated tiers (check tier2 first as higher threshold)
@@ -1298,6 +3274,257 @@ impl std::str::FromStr for WarmStartMode {
     }
 }
 
+/// On-disk encoding of the persisted log-price history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryPriceFormat {
+    /// `(log_price: f64, ts)` tuples (snapshot v2). Default; readable by
+    /// every existing build.
+    LogF64,
+    /// `(close: Decimal string, ts)` tuples (snapshot v3). The log is
+    /// recomputed on load exactly as the live bar path computes it, so a
+    /// persist/reload cycle cannot drift the samples that feed beta.
+    Decimal,
+}
+
+impl std::str::FromStr for HistoryPriceFormat {
+    type Err = ();
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "log_f64" | "f64" => Ok(HistoryPriceFormat::LogF64),
+            "decimal" => Ok(HistoryPriceFormat::Decimal),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Storage behind the persisted log-price history; see `history_store`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryBackend {
+    /// Whole snapshot rewritten as JSON each bar. Default.
+    Json,
+    /// Per-symbol memory-mapped ring files with O(1) append.
+    Mmap,
+}
+
+impl std::str::FromStr for HistoryBackend {
+    type Err = ();
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" | "memory" => Ok(HistoryBackend::Json),
+            "mmap" => Ok(HistoryBackend::Mmap),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How dry-run / paper entries translate top-of-book size into a fill;
+/// see `market::simulated_fill_qty` for the assumptions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchingModel {
+    /// Every paper order fills in full at the reference price. Default.
+    Full,
+    /// FIFO queue: our order rests behind the whole displayed size on its
+    /// side and only fills from contra size left over after that queue.
+    PriceTime,
+    /// Contra size is allocated across resting orders in proportion to
+    /// their size, so our share is `qty / (qty + queue)`.
+    ProRata,
+}
+
+impl std::str::FromStr for MatchingModel {
+    type Err = ();
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "full" => Ok(MatchingModel::Full),
+            "price_time" | "fifo" => Ok(MatchingModel::PriceTime),
+            "pro_rata" => Ok(MatchingModel::ProRata),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Statistic the spread z is centered and scaled by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZStatMode {
+    /// Mean and standard deviation. Default.
+    Simple,
+    /// Median and scaled median absolute deviation.
+    Robust,
+}
+
+impl std::str::FromStr for ZStatMode {
+    type Err = ();
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "simple" => Ok(ZStatMode::Simple),
+            "robust" | "median_mad" => Ok(ZStatMode::Robust),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What to do with an open position whose beta drifted past
+/// `beta_drift_exit_pct`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BetaDriftAction {
+    /// Close the position with reason `beta_drift`. Default.
+    Exit,
+    /// Resize leg B to the current beta and keep the position open.
+    Rehedge,
+}
+
+impl std::str::FromStr for BetaDriftAction {
+    type Err = ();
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "exit" => Ok(BetaDriftAction::Exit),
+            "rehedge" | "re_hedge" => Ok(BetaDriftAction::Rehedge),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How entry selection treats a candidate sharing a symbol with an active
+/// pair (`shared_symbol_policy`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SharedSymbolPolicy {
+    /// Skip the candidate. Default.
+    Block,
+    /// Allow it while each shared symbol's net signed exposure stays
+    /// within `max_net_symbol_exposure_usd`.
+    NetExposure,
+}
+
+impl std::str::FromStr for SharedSymbolPolicy {
+    type Err = ();
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "block" => Ok(SharedSymbolPolicy::Block),
+            "net_exposure" | "netexposure" => Ok(SharedSymbolPolicy::NetExposure),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Layout of the periodic `[METRICS]` log (`metrics_log_format`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsLogFormat {
+    /// Several human-readable lines. Default.
+    Text,
+    /// One line holding a JSON object.
+    Json,
+}
+
+impl std::str::FromStr for MetricsLogFormat {
+    type Err = ();
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(MetricsLogFormat::Text),
+            "json" => Ok(MetricsLogFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+/// When the per-pair trade stats (`PairTradeEngine::stats`) start over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeStatsReset {
+    /// Accumulate for the life of the process. Default.
+    Session,
+    /// Clear at the daily rollover (`daily_reset_utc_hour`).
+    Daily,
+}
+
+impl std::str::FromStr for TradeStatsReset {
+    type Err = ();
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "session" => Ok(TradeStatsReset::Session),
+            "daily" => Ok(TradeStatsReset::Daily),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Which quote of a `SymbolSnapshot` to sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceSource {
+    /// Ticker price (last trade). Default.
+    Last,
+    /// Midpoint of best bid and ask.
+    Mid,
+    Bid,
+    Ask,
+}
+
+impl std::str::FromStr for PriceSource {
+    type Err = ();
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "last" => Ok(PriceSource::Last),
+            "mid" => Ok(PriceSource::Mid),
+            "bid" => Ok(PriceSource::Bid),
+            "ask" => Ok(PriceSource::Ask),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What feeds the bar builders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarSource {
+    /// One sampled snapshot price per symbol per cycle. Default.
+    Snapshot,
+    /// Recent trade prints from `get_last_trades`.
+    Trades,
+}
+
+impl std::str::FromStr for BarSource {
+    type Err = ();
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "snapshot" => Ok(BarSource::Snapshot),
+            "trades" => Ok(BarSource::Trades),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How the live loop schedules the next step after one overran its tick
+/// (`missed_tick_behavior`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissedTickBehavior {
+    /// Wait for the next wall-clock boundary. Default.
+    Skip,
+    /// Wait one full interval from the end of the slow step.
+    Delay,
+    /// Run the missed step right away.
+    Burst,
+}
+
+impl std::str::FromStr for MissedTickBehavior {
+    type Err = ();
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "skip" => Ok(MissedTickBehavior::Skip),
+            "delay" => Ok(MissedTickBehavior::Delay),
+            "burst" => Ok(MissedTickBehavior::Burst),
+            _ => Err(()),
+        }
+    }
+}
+
+/// `history_price_format`; an unknown value is a load error rather than a
+/// silent fall back to `log_f64`.
+fn parse_history_price_format(raw: &str) -> Result<HistoryPriceFormat> {
+    raw.trim().parse().map_err(|_| {
+        anyhow!(
+            "history_price_format: unknown value '{}' (expected log_f64 or decimal)",
+            raw
+        )
+    })
+}
+
 fn default_history_file(universe: &[PairSpec], _agent_name: Option<&str>) -> String {
     let mut symbols: Vec<String> = universe
         .iter()
@@ -1336,6 +3563,31 @@ fn sanitize_symbol_for_filename(symbol: &str) -> String {
     out
 }
 
+/// Parse `SYM=value,SYM=value` into a per-symbol map. Entries without `=`
+/// or with an unparsable value are skipped.
+fn parse_symbol_values(raw: &str) -> HashMap<String, f64> {
+    raw.split(',')
+        .filter_map(|part| {
+            let (symbol, value) = part.split_once('=')?;
+            let symbol = symbol.trim();
+            if symbol.is_empty() {
+                return None;
+            }
+            Some((symbol.to_string(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// `1` / `true` / `yes` (any case, surrounding whitespace ignored).
+fn parse_bool_flag(value: &str) -> bool {
+    matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes")
+}
+
+/// `key` as a boolean flag; `None` when unset.
+fn env_bool(key: &str) -> Option<bool> {
+    env::var(key).ok().map(|v| parse_bool_flag(&v))
+}
+
 fn env_parse<T: std::str::FromStr>(key: &str, fallback: T) -> T {
     env::var(key)
         .ok()
@@ -1402,6 +3654,9 @@ pub(super) fn default_pair_params_from_env() -> PairParams {
         stop_loss_z: env_parse("STOP_LOSS_Z_SCORE", DEFAULT_STOP_LOSS_Z),
         force_close_secs: env_parse("FORCE_CLOSE_TIME_SECS", DEFAULT_FORCE_CLOSE_SECS),
         cooldown_secs: env_parse("COOLDOWN_SECS", DEFAULT_COOLDOWN_SECS),
+        stop_loss_cooldown_secs: env::var("STOP_LOSS_COOLDOWN_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok()),
         max_loss_r_mult: env_parse("MAX_LOSS_R_MULT", DEFAULT_MAX_LOSS_R_MULT),
         half_life_max_hours: env_parse("HALF_LIFE_MAX_HOURS", DEFAULT_HALF_LIFE_MAX_HOURS),
         adf_p_threshold: env_parse("ADF_P_THRESHOLD", DEFAULT_ADF_P_THRESHOLD),
@@ -1463,10 +3718,10 @@ pub(super) fn default_pair_params_from_env() -> PairParams {
             "STD_COLLAPSE_MIN_RATIO",
             DEFAULT_STD_COLLAPSE_MIN_RATIO,
         ),
-        std_collapse_observe_only: env::var("STD_COLLAPSE_OBSERVE_ONLY")
-            .ok()
-            .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+        std_collapse_observe_only: env_bool("STD_COLLAPSE_OBSERVE_ONLY")
             .unwrap_or(DEFAULT_STD_COLLAPSE_OBSERVE_ONLY),
+        min_spread_std: env_parse("MIN_SPREAD_STD", DEFAULT_MIN_SPREAD_STD),
+        notional_per_leg_usd: None,
     }
 }
 
@@ -1567,6 +3822,7 @@ pub(super) fn default_pair_params_from_yaml(yaml: &PairTradeYaml) -> PairParams
             .force_close_time_secs
             .unwrap_or(DEFAULT_FORCE_CLOSE_SECS),
         cooldown_secs: yaml.cooldown_secs.unwrap_or(DEFAULT_COOLDOWN_SECS),
+        stop_loss_cooldown_secs: yaml.stop_loss_cooldown_secs,
         max_loss_r_mult: yaml.max_loss_r_mult.unwrap_or(DEFAULT_MAX_LOSS_R_MULT),
         half_life_max_hours: yaml
             .half_life_max_hours
@@ -1630,6 +3886,8 @@ pub(super) fn default_pair_params_from_yaml(yaml: &PairTradeYaml) -> PairParams
         std_collapse_observe_only: yaml
             .std_collapse_observe_only
             .unwrap_or(DEFAULT_STD_COLLAPSE_OBSERVE_ONLY),
+        min_spread_std: yaml.min_spread_std.unwrap_or(DEFAULT_MIN_SPREAD_STD),
+        notional_per_leg_usd: None,
     }
 }
 
@@ -1653,6 +3911,9 @@ fn apply_pair_overrides(
             stop_loss_z: ovr.stop_loss_z_score.unwrap_or(default.stop_loss_z),
             force_close_secs: ovr.force_close_time_secs.unwrap_or(default.force_close_secs),
             cooldown_secs: ovr.cooldown_secs.unwrap_or(default.cooldown_secs),
+            stop_loss_cooldown_secs: ovr
+                .stop_loss_cooldown_secs
+                .or(default.stop_loss_cooldown_secs),
             max_loss_r_mult: ovr.max_loss_r_mult.unwrap_or(default.max_loss_r_mult),
             half_life_max_hours: ovr
                 .half_life_max_hours
@@ -1710,6 +3971,8 @@ fn apply_pair_overrides(
             std_collapse_window_bars: default.std_collapse_window_bars,
             std_collapse_min_ratio: default.std_collapse_min_ratio,
             std_collapse_observe_only: default.std_collapse_observe_only,
+            min_spread_std: ovr.min_spread_std.unwrap_or(default.min_spread_std),
+            notional_per_leg_usd: ovr.notional_per_leg_usd.or(default.notional_per_leg_usd),
         };
         map.insert(pair_key.clone(), pp);
     }
@@ -1720,6 +3983,23 @@ fn apply_pair_overrides(
 mod tests {
     use super::*;
 
+    #[test]
+    fn history_price_format_rejects_unknown_values() {
+        assert_eq!(parse_history_price_format("Decimal").unwrap(), HistoryPriceFormat::Decimal);
+        assert_eq!(parse_history_price_format(" f64 ").unwrap(), HistoryPriceFormat::LogF64);
+        assert!(parse_history_price_format("decimals").is_err());
+    }
+
+    #[test]
+    fn bool_flags_parse_case_and_whitespace_insensitively() {
+        assert!(parse_bool_flag("1"));
+        assert!(parse_bool_flag(" TRUE "));
+        assert!(parse_bool_flag("Yes"));
+        assert!(!parse_bool_flag("0"));
+        assert!(!parse_bool_flag("off"));
+        assert!(!parse_bool_flag(""));
+    }
+
     #[test]
     fn risk_config_defaults_when_block_absent() {
         let cfg = resolve_risk_config(None).unwrap();
@@ -1754,6 +4034,14 @@ mod tests {
         assert!(resolve_risk_config(Some(&yaml)).is_err());
     }
 
+    #[test]
+    fn parses_per_symbol_dust_thresholds() {
+        let map = parse_symbol_values(" BTC=5, PEPE = 0.5,bad,ETH=x,=1");
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("BTC"), Some(&5.0));
+        assert_eq!(map.get("PEPE"), Some(&0.5));
+    }
+
     #[test]
     fn risk_config_rejects_zero_sample_cadence() {
         let yaml = RiskYaml {
@@ -1788,8 +4076,8 @@ mod tests {
     #[test]
     fn per_strategy_equity_env_override() {
         use std::io::Write;
-        let dir = std::env::temp_dir();
-        let path = dir.join("pairtrade_per_strategy_equity_env.yaml");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pairtrade_per_strategy_equity_env.yaml");
         let yaml = r#"
 dex_name: lighter
 rest_endpoint: https://example
@@ -1846,6 +4134,202 @@ strategies:
         if let Some(v) = prev_c {
             std::env::set_var("EQUITY_REFERENCE_USD_C", v);
         }
-        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn structured_universe_toggles_and_tunes_pairs() {
+        use std::io::Write;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("structured_universe.yaml");
+        let yaml = r#"
+dex_name: lighter
+rest_endpoint: https://example
+web_socket_endpoint: wss://example
+dry_run: true
+universe_pairs:
+- BTC/ETH
+- BTC/SOL
+universe:
+  - base: BTC
+    quote: SOL
+    enabled: false
+  - base: ETH
+    quote: SOL
+    entry_z: 2.5
+    notional_per_leg_usd: 300
+  - base: DOGE
+    quote: PEPE
+    enabled: false
+    entry_z: 9.0
+"#;
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(yaml.as_bytes())
+            .unwrap();
+        let cfg = PairTradeConfig::from_yaml_path(&path).expect("yaml load");
+        let keys: Vec<String> = cfg
+            .universe
+            .iter()
+            .map(|p| format!("{}/{}", p.base, p.quote))
+            .collect();
+        assert_eq!(keys, vec!["BTC/ETH", "ETH/SOL"]);
+        let pp = &cfg.pair_params["ETH/SOL"];
+        assert!((pp.entry_z_base - 2.5).abs() < 1e-9);
+        assert_eq!(pp.notional_per_leg_usd, Some(300.0));
+        assert!(!cfg.pair_params.contains_key("DOGE/PEPE"));
+    }
+
+    #[test]
+    fn pair_notional_must_be_a_positive_amount() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        let mut pp = cfg.default_pair_params.clone();
+        pp.notional_per_leg_usd = Some(300.0);
+        cfg.pair_params.insert("ETH/SOL".to_string(), pp.clone());
+        assert!(cfg.validate().is_ok());
+        for bad in [0.0, -50.0, f64::NAN, f64::INFINITY] {
+            pp.notional_per_leg_usd = Some(bad);
+            cfg.pair_params.insert("ETH/SOL".to_string(), pp.clone());
+            assert!(cfg.validate().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn symbol_pairs_alphabetize_unless_order_preserved() {
+        let syms: Vec<String> = ["SOL", "BTC", "ETH"].iter().map(|s| s.to_string()).collect();
+        let keys = |preserve: bool| -> Vec<String> {
+            parse_symbols_vec(&syms, preserve)
+                .unwrap()
+                .iter()
+                .map(|p| format!("{}/{}", p.base, p.quote))
+                .collect()
+        };
+        assert_eq!(keys(false), vec!["BTC/SOL", "ETH/SOL", "BTC/ETH"]);
+        assert_eq!(keys(true), vec!["SOL/BTC", "SOL/ETH", "BTC/ETH"]);
+    }
+
+    #[test]
+    fn startup_force_close_wait_backs_off_and_caps() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        cfg.startup_force_close_wait_secs = 3;
+        cfg.startup_force_close_backoff_mult = 1.0;
+        cfg.startup_force_close_max_wait_secs = 10;
+        let waits = |cfg: &PairTradeConfig| {
+            (1..=4)
+                .map(|a| cfg.startup_force_close_wait_for(a).as_secs())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(waits(&cfg), vec![3, 3, 3, 3], "default stays linear");
+
+        cfg.startup_force_close_backoff_mult = 2.0;
+        assert_eq!(waits(&cfg), vec![3, 6, 10, 10]);
+
+        cfg.startup_force_close_max_wait_secs = 0;
+        assert_eq!(waits(&cfg), vec![3, 6, 12, 24]);
+    }
+
+    #[test]
+    fn eligibility_age_check_is_off_unless_set() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        cfg.eligibility_max_age_secs = 0;
+        // A long gap between replayed evaluations does not block entries.
+        assert!(!cfg.eligibility_stale(Some(0), 86_400));
+
+        cfg.eligibility_max_age_secs = 3_600;
+        assert!(!cfg.eligibility_stale(Some(0), 3_600));
+        assert!(cfg.eligibility_stale(Some(0), 3_601));
+        assert!(!cfg.eligibility_stale(None, 3_601));
+    }
+
+    #[test]
+    fn position_age_warning_must_precede_force_close() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        cfg.default_pair_params.force_close_secs = 7_200;
+        cfg.shutdown_grace_secs = 0;
+        cfg.position_age_warn_secs = 5_400;
+        assert!(cfg.validate().is_ok());
+
+        cfg.position_age_warn_secs = 7_200;
+        assert!(cfg.validate().is_err());
+
+        cfg.position_age_warn_secs = 0;
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn max_bar_span_mult_is_off_or_at_least_two_windows() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        for ok in [0.0, 2.0, 3.5] {
+            cfg.max_bar_span_mult = ok;
+            assert!(cfg.validate().is_ok(), "{}", ok);
+        }
+        for bad in [-1.0, 0.5, 1.0, 1.9, f64::NAN] {
+            cfg.max_bar_span_mult = bad;
+            assert!(cfg.validate().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn exit_wait_reasons_never_block_critical_exits() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        cfg.exit_post_only_wait_reasons = Vec::new();
+        assert!(cfg.exit_taker_fallback_allowed("exit_z"));
+
+        cfg.exit_post_only_wait_reasons = StringOrVec::String("exit_z, ineligible".into()).into_vec();
+        assert!(!cfg.exit_taker_fallback_allowed("exit_z"));
+        assert!(!cfg.exit_taker_fallback_allowed("ineligible"));
+        assert!(cfg.exit_taker_fallback_allowed("expected_value"));
+
+        cfg.exit_post_only_wait_reasons = vec!["all".to_string()];
+        assert!(!cfg.exit_taker_fallback_allowed("expected_value"));
+        assert!(cfg.exit_taker_fallback_allowed("stop_loss_z"));
+        assert!(cfg.exit_taker_fallback_allowed("force_close"));
+    }
+
+    #[test]
+    fn exit_market_reasons_select_aggressive_exits() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        cfg.exit_market_reasons = Vec::new();
+        assert!(!cfg.exit_uses_market("stop_loss_z"));
+
+        cfg.exit_market_reasons = StringOrVec::String("stop_loss_z, max_loss_r".into()).into_vec();
+        assert!(cfg.exit_uses_market("stop_loss_z"));
+        assert!(cfg.exit_uses_market("max_loss_r"));
+        assert!(!cfg.exit_uses_market("exit_z"));
+
+        cfg.exit_market_reasons = vec!["all".to_string()];
+        assert!(cfg.exit_uses_market("exit_z"));
+        assert!(cfg.validate().is_ok());
+
+        cfg.exit_market_reasons = vec!["stop_loss".to_string()];
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn exit_fee_pays_taker_unless_the_close_waits_post_only() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        cfg.dex_name = "lighter".to_string();
+        cfg.fee_bps = 2.0;
+        cfg.maker_fee_bps_by_symbol = HashMap::from([("ETH".to_string(), 0.0)]);
+        cfg.taker_fee_bps_by_symbol = HashMap::from([("ETH".to_string(), 5.0)]);
+        cfg.exit_post_only_wait_reasons = vec!["exit_z".to_string()];
+
+        assert_eq!(cfg.exit_fee_bps("ETH", "exit_z"), 0.0);
+        // Market reasons and taker fallbacks.
+        assert_eq!(cfg.exit_fee_bps("ETH", "stop_loss_z"), 5.0);
+        assert_eq!(cfg.exit_fee_bps("ETH", "ineligible"), 5.0);
+        assert_eq!(cfg.exit_fee_bps("ETH", "universe_removed"), 5.0);
+        // A taker venue always pays taker.
+        cfg.dex_name = "hyperliquid".to_string();
+        assert_eq!(cfg.exit_fee_bps("ETH", "exit_z"), 5.0);
+    }
+
+    #[test]
+    fn default_exit_market_reasons_cover_force_close_and_loss_stops() {
+        let cfg = PairTradeConfig::from_env().unwrap();
+        assert!(cfg.exit_uses_market("force_close"));
+        assert!(cfg.exit_uses_market("stop_loss_z"));
+        assert!(cfg.exit_uses_market("max_loss_r"));
+        assert!(!cfg.exit_uses_market("exit_z"));
+        assert!(cfg.validate().is_ok());
     }
 }
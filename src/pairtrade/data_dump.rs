@@ -4,38 +4,88 @@
 //! rotating to a new file at UTC midnight. Replaces the previous approach
 //! of writing to a single file rotated by logrotate with `copytruncate`,
 //! which caused data loss during the copy window.
+//!
+//! Gzip: when the configured path ends in `.gz` (or `data_dump_compress` is
+//! set) each file is gzip-compressed. Appending to a finished gzip stream
+//! is not safe across crashes, so compressed dumps never reopen an existing
+//! file: every run (and every UTC-midnight rotation) starts a new segment
+//! named `{base}_YYYYMMDD_HHMMSS{ext}.gz`. Concatenate a day's segments in
+//! name order to get its full dump; `zcat` and the replay loader both read
+//! multi-member gzip transparently.
 
 use std::fs::{File, OpenOptions};
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 
 use chrono::{NaiveDate, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+const GZ_EXT: &str = ".gz";
+
+enum DumpSink {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl DumpSink {
+    /// Flush and, for gzip, write the stream trailer so the segment is a
+    /// complete member.
+    fn finish(self) -> std::io::Result<()> {
+        match self {
+            DumpSink::Plain(mut w) => w.flush(),
+            DumpSink::Gzip(enc) => enc.finish()?.flush(),
+        }
+    }
+}
+
+impl Write for DumpSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            DumpSink::Plain(w) => w.write(buf),
+            DumpSink::Gzip(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            DumpSink::Plain(w) => w.flush(),
+            DumpSink::Gzip(w) => w.flush(),
+        }
+    }
+}
 
 pub(super) struct RotatingDumpWriter {
     /// Base path without extension, e.g. `/opt/debot/market_data_btceth`
     base: PathBuf,
-    /// Extension, e.g. `.jsonl`
+    /// Extension, e.g. `.jsonl` (without the `.gz` suffix)
     ext: String,
-    writer: BufWriter<File>,
+    compress: bool,
+    writer: Option<DumpSink>,
     current_date: NaiveDate,
 }
 
 impl RotatingDumpWriter {
     /// Create a new rotating writer. Opens (or creates) the file for today's
-    /// date in append mode.
-    pub(super) fn new(configured_path: &str) -> std::io::Result<Self> {
-        let path = Path::new(configured_path);
+    /// date in append mode; compressed dumps start a fresh segment instead.
+    pub(super) fn new(configured_path: &str, compress: bool) -> std::io::Result<Self> {
+        let (path, gz_suffix) = match configured_path.strip_suffix(GZ_EXT) {
+            Some(stripped) => (Path::new(stripped), true),
+            None => (Path::new(configured_path), false),
+        };
+        let compress = compress || gz_suffix;
         let ext = path
             .extension()
             .map(|e| format!(".{}", e.to_string_lossy()))
             .unwrap_or_default();
         let base = path.with_extension("");
         let today = Utc::now().date_naive();
-        let file = Self::open_file(&base, &ext, today)?;
+        let writer = Self::open_sink(&base, &ext, compress, today)?;
         Ok(Self {
             base,
             ext,
-            writer: BufWriter::new(file),
+            compress,
+            writer: Some(writer),
             current_date: today,
         })
     }
@@ -44,17 +94,42 @@ impl RotatingDumpWriter {
     pub(super) fn write_line(&mut self, line: &str) -> std::io::Result<()> {
         let today = Utc::now().date_naive();
         if today != self.current_date {
-            // Flush the old file before switching
-            self.writer.flush()?;
-            let file = Self::open_file(&self.base, &self.ext, today)?;
-            self.writer = BufWriter::new(file);
+            // Flush (and for gzip, terminate) the old file before switching
+            if let Some(old) = self.writer.take() {
+                old.finish()?;
+            }
+            self.writer = Some(Self::open_sink(&self.base, &self.ext, self.compress, today)?);
             self.current_date = today;
-            log::info!(
-                "[DataDump] Rotated to {}",
-                Self::file_path(&self.base, &self.ext, today).display()
-            );
+            if !self.compress {
+                log::info!(
+                    "[DataDump] Rotated to {}",
+                    Self::file_path(&self.base, &self.ext, today).display()
+                );
+            }
+        }
+        match self.writer.as_mut() {
+            Some(writer) => writeln!(writer, "{}", line),
+            None => Err(std::io::Error::other("data dump writer closed")),
         }
-        writeln!(self.writer, "{}", line)
+    }
+
+    fn open_sink(
+        base: &Path,
+        ext: &str,
+        compress: bool,
+        date: NaiveDate,
+    ) -> std::io::Result<DumpSink> {
+        if !compress {
+            let file = Self::open_file(&Self::file_path(base, ext, date))?;
+            return Ok(DumpSink::Plain(BufWriter::new(file)));
+        }
+        let path = Self::segment_path(base, ext, date, &Utc::now().format("%H%M%S").to_string());
+        let file = Self::open_file(&path)?;
+        log::info!("[DataDump] Writing gzip segment {}", path.display());
+        Ok(DumpSink::Gzip(GzEncoder::new(
+            BufWriter::new(file),
+            Compression::default(),
+        )))
     }
 
     fn file_path(base: &Path, ext: &str, date: NaiveDate) -> PathBuf {
@@ -70,15 +145,31 @@ impl RotatingDumpWriter {
         base.with_file_name(filename)
     }
 
-    fn open_file(base: &Path, ext: &str, date: NaiveDate) -> std::io::Result<File> {
-        let path = Self::file_path(base, ext, date);
+    /// Per-run gzip segment: `{base}_YYYYMMDD_HHMMSS{ext}.gz`.
+    fn segment_path(base: &Path, ext: &str, date: NaiveDate, time_tag: &str) -> PathBuf {
+        let mut path = Self::file_path(base, "", date).into_os_string();
+        path.push(format!("_{}{}{}", time_tag, ext, GZ_EXT));
+        PathBuf::from(path)
+    }
+
+    fn open_file(path: &Path) -> std::io::Result<File> {
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
         OpenOptions::new()
             .create(true)
             .append(true)
-            .open(&path)
+            .open(path)
+    }
+}
+
+impl Drop for RotatingDumpWriter {
+    fn drop(&mut self) {
+        if let Some(writer) = self.writer.take() {
+            if let Err(e) = writer.finish() {
+                log::error!("[DataDump] Failed to finalize dump file: {}", e);
+            }
+        }
     }
 }
 
@@ -86,6 +177,7 @@ impl std::fmt::Debug for RotatingDumpWriter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RotatingDumpWriter")
             .field("base", &self.base)
+            .field("compress", &self.compress)
             .field("current_date", &self.current_date)
             .finish()
     }
@@ -113,9 +205,9 @@ mod tests {
     fn writes_to_dated_file() {
         let dir = TempDir::new().unwrap();
         let base_path = dir.path().join("dump.jsonl");
-        let mut writer = RotatingDumpWriter::new(base_path.to_str().unwrap()).unwrap();
+        let mut writer = RotatingDumpWriter::new(base_path.to_str().unwrap(), false).unwrap();
         writer.write_line(r#"{"test": 1}"#).unwrap();
-        writer.writer.flush().unwrap();
+        writer.writer.as_mut().unwrap().flush().unwrap();
 
         let today = Utc::now().date_naive().format("%Y%m%d").to_string();
         let expected_file = dir.path().join(format!("dump_{}.jsonl", today));
@@ -128,4 +220,36 @@ mod tests {
             .unwrap();
         assert!(content.contains(r#"{"test": 1}"#));
     }
+
+    #[test]
+    fn segment_path_format() {
+        let base = Path::new("/opt/debot/market_data_btceth");
+        let date = NaiveDate::from_ymd_opt(2026, 4, 12).unwrap();
+        let path = RotatingDumpWriter::segment_path(base, ".jsonl", date, "093000");
+        assert_eq!(
+            path.to_string_lossy(),
+            "/opt/debot/market_data_btceth_20260412_093000.jsonl.gz"
+        );
+    }
+
+    #[test]
+    fn gz_suffix_writes_readable_segment() {
+        let dir = TempDir::new().unwrap();
+        let base_path = dir.path().join("dump.jsonl.gz");
+        let mut writer = RotatingDumpWriter::new(base_path.to_str().unwrap(), false).unwrap();
+        writer.write_line(r#"{"test": 2}"#).unwrap();
+        drop(writer);
+
+        let segment = std::fs::read_dir(dir.path())
+            .unwrap()
+            .flatten()
+            .map(|e| e.path())
+            .find(|p| p.to_string_lossy().ends_with(".jsonl.gz"))
+            .expect("gzip segment should exist");
+        let mut content = String::new();
+        flate2::read::MultiGzDecoder::new(File::open(&segment).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "{\"test\": 2}\n");
+    }
 }
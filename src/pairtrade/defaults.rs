@@ -13,6 +13,7 @@ pub(super) const DEFAULT_FORCE_CLOSE_SECS: u64 = 3600;
 pub(super) const DEFAULT_SHUTDOWN_GRACE_SECS: u64 = 3660; // DEFAULT_FORCE_CLOSE_SECS + 60s buffer
 pub(super) const DEFAULT_COOLDOWN_SECS: u64 = 30;
 pub(super) const MAX_EXIT_RETRIES: u32 = 3;
+pub(super) const LEG_B_ATTEMPTS: u32 = 2; // partial take-profit / scale-in leg-B tries before undoing leg A
 pub(super) const DEFAULT_NET_FUNDING_MIN_PER_HOUR: f64 = -0.005;
 pub(super) const DEFAULT_SPREAD_VELOCITY_MAX_SIGMA_PER_MIN: f64 = 0.1;
 pub(super) const DEFAULT_NOTIONAL_PER_LEG: f64 = 100.0;
@@ -23,7 +24,7 @@ pub(super) const DEFAULT_LOOKBACK_HOURS_SHORT: u64 = 4;
 pub(super) const DEFAULT_LOOKBACK_HOURS_LONG: u64 = 24;
 pub(super) const DEFAULT_HALF_LIFE_MAX_HOURS: f64 = 1.5;
 pub(super) const DEFAULT_ADF_P_THRESHOLD: f64 = 0.05;
-pub(super) const PAIR_SELECTION_INTERVAL_SECS: u64 = 3600;
+pub(super) const DEFAULT_PAIR_EVAL_INTERVAL_SECS: u64 = 3600;
 pub(super) const DEFAULT_ENTRY_VOL_LOOKBACK_HOURS: u64 = 24;
 pub(super) const DEFAULT_SLIPPAGE_BPS: i32 = 0;
 pub(super) const DEFAULT_FEE_BPS: f64 = 0.0;
@@ -97,3 +98,357 @@ pub(super) const DEFAULT_MAX_SESSION_LOSS_BPS: u32 = 0;
 pub(super) const DEFAULT_SESSION_DD_LOOKBACK_SECS: u64 = 30 * 24 * 60 * 60;
 pub(super) const DEFAULT_SESSION_DD_SAMPLE_SECS: u64 = 3600;
 pub(super) const DEFAULT_MAX_NOTIONAL_USD_PER_LEG: f64 = 0.0;
+
+// Equity guard — disabled by default. When `min_equity_usd > 0`, new entries
+// are blocked while the last fetched account equity is below it. When
+// `max_balance_fetch_failures > 0`, new entries are also blocked once that
+// many consecutive `get_balance` calls have failed, so the bot never sizes
+// trades against a stale fallback equity.
+pub(super) const DEFAULT_MIN_EQUITY_USD: f64 = 0.0;
+pub(super) const DEFAULT_MAX_BALANCE_FETCH_FAILURES: u32 = 0;
+
+// Bar clock alignment — disabled by default. When true, every symbol's tick
+// is bucketed by the engine's wall-clock `now_ts` instead of its own
+// exchange ts, so both legs of a pair close their bars on the same tick.
+pub(super) const DEFAULT_ALIGN_BAR_CLOCK: bool = false;
+
+// History persistence encoding: "log_f64" (snapshot v2, default) or "decimal"
+// (snapshot v3, raw close prices as Decimal strings).
+pub(super) const DEFAULT_HISTORY_PRICE_FORMAT: &str = "log_f64";
+
+// Funding window avoidance — disabled by default. Within
+// `avoid_funding_window_secs` of the next funding time, new entries are
+// blocked and (optionally) profitable positions are closed early. Funding
+// times are epoch-aligned multiples of `funding_interval_secs`; 0 means the
+// venue's schedule is unknown and the avoidance is a no-op.
+pub(super) const DEFAULT_AVOID_FUNDING_WINDOW_SECS: u64 = 0;
+pub(super) const DEFAULT_FUNDING_INTERVAL_SECS: u64 = 0;
+pub(super) const DEFAULT_CLOSE_PROFITABLE_BEFORE_FUNDING: bool = false;
+
+// Vol-targeted sizing — disabled by default (sigma 0.0). When set, leg
+// notional is scaled by `vol_target_sigma / spread_std` and clamped to
+// [min, max] notional (max 0.0 = at most 4x the unscaled notional).
+pub(super) const DEFAULT_VOL_TARGET_SIGMA: f64 = 0.0;
+pub(super) const DEFAULT_VOL_TARGET_MIN_NOTIONAL: f64 = 10.0;
+pub(super) const DEFAULT_VOL_TARGET_MAX_NOTIONAL: f64 = 0.0;
+
+// Order rejections: after an entry is rejected for insufficient margin, new
+// entries on that instance are blocked for this long.
+pub(super) const INSUFFICIENT_MARGIN_BLOCK_SECS: u64 = 300;
+
+// Pending-order reconciliation concurrency. 1 = legacy sequential per-pair
+// status fetches.
+pub(super) const DEFAULT_RECONCILE_CONCURRENCY: usize = 1;
+
+// Gzip data dumps even without a `.gz` suffix on `data_dump_file`.
+pub(super) const DEFAULT_DATA_DUMP_COMPRESS: bool = false;
+
+// Per-pair trade stats reset cadence: "session" (process lifetime) or
+// "daily" (cleared at `daily_reset_utc_hour`).
+pub(super) const DEFAULT_TRADE_STATS_RESET: &str = "session";
+
+// Spread input smoothing — disabled by default (0 bars). When > 1, each bar's
+// spread is replaced by the SMA of the last N raw spreads before it enters
+// `spread_history`.
+pub(super) const DEFAULT_SPREAD_SMOOTHING_BARS: usize = 0;
+
+// Spread-history gap tolerance — disabled by default (0). When > 0, entries
+// are skipped while the spread window holds a gap wider than this many
+// missing bars.
+pub(super) const DEFAULT_MAX_SPREAD_GAP_BARS: usize = 0;
+
+// Dust sweep after partial exits — disabled by default. Lone sub-min_order
+// legs are otherwise just filtered out of `open_positions` and left on the
+// exchange.
+pub(super) const DEFAULT_DUST_SWEEP: bool = false;
+
+// Hedge-ratio regression input: false = log levels (legacy), true = log
+// returns.
+pub(super) const DEFAULT_BETA_ON_RETURNS: bool = false;
+
+// Order-book depth — top-of-book only by default. With more levels, sizes
+// within `orderbook_depth_band_bps` of the touch count toward liquidity.
+pub(super) const DEFAULT_ORDERBOOK_DEPTH: usize = 1;
+pub(super) const DEFAULT_ORDERBOOK_DEPTH_BAND_BPS: f64 = 10.0;
+
+// exit_z profit requirement — disabled by default. When true, the exit_z
+// reversion exit waits until the PnL clears estimated round-trip costs.
+pub(super) const DEFAULT_EXIT_Z_REQUIRE_PROFIT: bool = false;
+
+// Startup force-close pacing. The defaults keep the fixed
+// `startup_force_close_wait_secs` between attempts (backoff 1.0, no jitter)
+// and go straight from cancel_all_orders to the first close.
+pub(super) const DEFAULT_STARTUP_FORCE_CLOSE_BACKOFF_MULT: f64 = 1.0;
+pub(super) const DEFAULT_STARTUP_FORCE_CLOSE_MAX_WAIT_SECS: u64 = 60;
+pub(super) const DEFAULT_STARTUP_FORCE_CLOSE_JITTER_PCT: f64 = 0.0;
+pub(super) const DEFAULT_STARTUP_CANCEL_WAIT_SECS: u64 = 0;
+
+// observe_only paper trading — disabled by default, so observe_only stays a
+// pure signal observer with no simulated fills.
+pub(super) const DEFAULT_OBSERVE_SIMULATE_PNL: bool = false;
+
+// Same-sign leg resolution — disabled by default: the pair stays guarded
+// until an operator flattens it.
+pub(super) const DEFAULT_AUTO_FLATTEN_MISMATCHED_LEGS: bool = false;
+
+// Price feeding the spread / z pipeline: "last" (ticker price, default),
+// "mid", "bid" or "ask".
+pub(super) const DEFAULT_Z_PRICE_SOURCE: &str = "last";
+
+// Forensic snapshots on inconsistent state — written only when
+// `forensic_dump_dir` is set; this many of the newest are kept.
+pub(super) const DEFAULT_FORENSIC_DUMP_KEEP: usize = 20;
+
+// Entry liquidity floor — disabled by default (0.0). Per-leg USD notional
+// resting on the thinner side of the book.
+pub(super) const DEFAULT_MIN_BOOK_NOTIONAL_USD: f64 = 0.0;
+
+// Realized hedge-ratio tolerance for synced positions — disabled by default
+// (0.0): opposite-sign legs are accepted as hedged whatever their sizes.
+pub(super) const DEFAULT_HEDGE_RATIO_TOLERANCE_PCT: f64 = 0.0;
+
+// Ticker auth-error retries — disabled by default (0): an auth-classified
+// ticker failure skips the symbol for the cycle straight away.
+pub(super) const DEFAULT_TICKER_AUTH_RETRIES: u32 = 0;
+pub(super) const DEFAULT_TICKER_AUTH_RETRY_MS: u64 = 250;
+
+// Leg standardization before the spread — disabled by default: the spread
+// is formed from raw log prices.
+pub(super) const DEFAULT_NORMALIZE_LEGS: bool = false;
+
+// Partial-fill reissue bounds. The per-pair cap escalates a pending entry to
+// a market close of its filled part; the per-cycle cap defers further leg
+// reissues to the next tick across all pairs.
+pub(super) const DEFAULT_MAX_REISSUES_PER_PAIR: u32 = 20;
+pub(super) const DEFAULT_MAX_REISSUED_LEGS_PER_CYCLE: usize = 8;
+
+// Bar input: "snapshot" (sampled price per cycle, default) or "trades"
+// (fold `get_last_trades` prints into the bar).
+pub(super) const DEFAULT_BAR_SOURCE: &str = "snapshot";
+
+// Pending-order age backstop — 30 minutes, far above order_timeout_secs and
+// the retry ladders, so it only fires on orders the exchange never resolves.
+pub(super) const DEFAULT_PENDING_MAX_AGE_SECS: u64 = 1800;
+
+// Daily PnL baseline — fixed from the first equity reading of the day (1).
+// Larger values take the median of that many readings.
+pub(super) const DEFAULT_EQUITY_BASELINE_SAMPLES: usize = 1;
+
+// History persistence backend: "json" (whole-snapshot rewrite, default) or
+// "mmap" (per-symbol memory-mapped ring files).
+pub(super) const DEFAULT_HISTORY_BACKEND: &str = "json";
+
+// Hedge beta sign — positive-only (false) unless a pair is known to be
+// inversely correlated.
+pub(super) const DEFAULT_ALLOW_NEGATIVE_BETA: bool = false;
+
+// Idle-pair watchdog — off (0). When set, a pair with no entry for this many
+// seconds gets a one-time [IDLE] warning; notification is opt-in.
+pub(super) const DEFAULT_PAIR_IDLE_ALERT_SECS: u64 = 0;
+pub(super) const DEFAULT_PAIR_IDLE_NOTIFY: bool = false;
+
+// Min-order oversizing guard — off (0.0); legs below min_order are floored
+// up to it as before.
+pub(super) const DEFAULT_MAX_MIN_ORDER_OVERSIZING_FACTOR: f64 = 0.0;
+
+// Engine-vs-exchange position audit — off (0). Reports only unless
+// force-close is enabled.
+pub(super) const DEFAULT_POSITION_AUDIT_INTERVAL_SECS: u64 = 0;
+pub(super) const DEFAULT_POSITION_AUDIT_FORCE_CLOSE: bool = false;
+
+// Exit reasons that must leave the market even at taker fees; never subject
+// to `exit_post_only_wait_reasons`.
+pub(super) const CRITICAL_EXIT_REASONS: &[&str] = &["stop_loss_z", "max_loss_r", "force_close"];
+
+// Every reason tag a close can carry; `exit_market_reasons` entries must be
+// one of these (or `all`).
+pub(super) const EXIT_REASONS: &[&str] = &[
+    "exit_z",
+    "stop_loss_z",
+    "force_close",
+    "max_loss_r",
+    "risk_budget",
+    "expected_value",
+    "beta_drift",
+    "funding_window",
+    "ineligible",
+    "universe_removed",
+];
+
+// Universe-reload seeding — off; added pairs accumulate their own spread
+// window as before.
+pub(super) const DEFAULT_SEED_NEW_PAIRS_FROM_HISTORY: bool = false;
+
+// Extreme per-leg funding guard — disabled (0.0). Independent of
+// `net_funding_min_per_hour`: one leg paying or receiving an outsized rate
+// usually means a dislocated market even when the net carry looks fine.
+pub(super) const DEFAULT_MAX_ABS_FUNDING_PER_HOUR: f64 = 0.0;
+
+// Dry-run fill model — `full`: paper entries fill completely, as before.
+pub(super) const DEFAULT_MATCHING_MODEL: &str = "full";
+
+// Local per-symbol order rate cap — generous: two create/cancel calls a
+// second sustained per symbol. Only a runaway reissue or chase loop should
+// ever reach it.
+pub(super) const DEFAULT_MAX_ORDERS_PER_SYMBOL_PER_MIN: u32 = 120;
+
+// Backtest cost model — on: paper entry prices in backtests carry the
+// round-trip slippage + fees so replay PnL is net, not gross.
+pub(super) const DEFAULT_BACKTEST_APPLY_COSTS: bool = true;
+
+// Startup universe validation — lenient: invalid symbols drop their pairs
+// with a warning instead of aborting startup.
+pub(super) const DEFAULT_STRICT_UNIVERSE_VALIDATION: bool = false;
+
+// Unchanged-bar short-circuit — off: every closed bar feeds the spread
+// window, even when neither leg moved.
+pub(super) const DEFAULT_SKIP_UNCHANGED_BARS: bool = false;
+
+// Realized-slippage guard — disabled (0.0). Slippage is averaged over the
+// last REALIZED_SLIPPAGE_WINDOW filled legs per symbol, and only judged once
+// REALIZED_SLIPPAGE_MIN_SAMPLES are in so one bad fill cannot block a symbol.
+pub(super) const DEFAULT_MAX_REALIZED_SLIPPAGE_BPS: f64 = 0.0;
+// A slippage block lapses after an hour, then the symbol is judged afresh.
+pub(super) const DEFAULT_REALIZED_SLIPPAGE_BLOCK_SECS: u64 = 3600;
+pub(super) const REALIZED_SLIPPAGE_WINDOW: usize = 20;
+pub(super) const REALIZED_SLIPPAGE_MIN_SAMPLES: usize = 3;
+
+// Trade-stats sidecar — on: intraday restarts keep the per-pair books.
+pub(super) const DEFAULT_PERSIST_TRADE_STATS: bool = true;
+
+// Backtest position sync — off: replay keeps the positions-ready shortcut.
+pub(super) const DEFAULT_BACKTEST_FULL_SYNC: bool = false;
+
+// High-frequency debug line sampling — 1: every tick, as before.
+pub(super) const DEFAULT_LOG_PRICE_SNAPSHOT_EVERY_N_TICKS: u64 = 1;
+pub(super) const DEFAULT_LOG_ZCHECK_EVERY_N_TICKS: u64 = 1;
+pub(super) const DEFAULT_LOG_ORDER_PARAMS_EVERY_N_TICKS: u64 = 1;
+
+// Beta drift on open positions — off; `exit` when enabled.
+pub(super) const DEFAULT_BETA_DRIFT_EXIT_PCT: f64 = 0.0;
+pub(super) const DEFAULT_BETA_DRIFT_ACTION: &str = "exit";
+
+// Spread z statistic — `simple`: mean/std, as before.
+pub(super) const DEFAULT_Z_STAT_MODE: &str = "simple";
+
+// Account-wide open order cap: well above the two legs a pair keeps working,
+// so it only trips on a leak. The audit is one REST call per universe symbol.
+pub(super) const DEFAULT_MAX_OPEN_ORDERS: usize = 50;
+pub(super) const OPEN_ORDER_AUDIT_INTERVAL_SECS: u64 = 60;
+
+// Flat-spread floor on the spread std — off; set per pair in spread units.
+pub(super) const DEFAULT_MIN_SPREAD_STD: f64 = 0.0;
+
+// Wide-book entry guard — off.
+pub(super) const DEFAULT_ENTRY_MAX_BOOK_SPREAD_BPS: f64 = 0.0;
+
+// Eligibility freshness for entries — 0: no freshness check.
+pub(super) const DEFAULT_ELIGIBILITY_MAX_AGE_SECS: u64 = 0;
+
+// Fill-reconciled exit PnL — off (price-based compute_pnl).
+pub(super) const DEFAULT_RECONCILE_FILL_PNL: bool = false;
+
+// Notional dust floor — off (exchange minimum order size only).
+pub(super) const DEFAULT_DUST_NOTIONAL_USD: f64 = 0.0;
+
+// Position aging warning — off.
+pub(super) const DEFAULT_POSITION_AGE_WARN_SECS: u64 = 0;
+
+// Per-direction eligibility gate — off (symmetric `eligible`).
+pub(super) const DEFAULT_DIRECTIONAL_ELIGIBILITY: bool = false;
+
+// Gap-straddling bar discard — off; every bar is emitted.
+pub(super) const DEFAULT_MAX_BAR_SPAN_MULT: f64 = 0.0;
+
+// Rate-limit-aware loop interval — no floor, no back-off ceiling (fixed
+// `interval_secs`).
+pub(super) const DEFAULT_MIN_INTERVAL_SECS: u64 = 0;
+pub(super) const DEFAULT_MAX_INTERVAL_SECS: u64 = 0;
+
+// Minimum hold before non-stop exits — off.
+pub(super) const DEFAULT_MIN_HOLD_SECS: u64 = 0;
+
+// Partial take-profit — off; both must be set to scale out.
+pub(super) const DEFAULT_PARTIAL_TAKE_PROFIT_FRACTION: f64 = 0.0;
+pub(super) const DEFAULT_PARTIAL_TAKE_PROFIT_R: f64 = 0.0;
+
+// Size-adjustment tally in status.json — off ([METRICS] only).
+pub(super) const DEFAULT_STATUS_SIZE_ADJUSTMENTS: bool = false;
+
+// Cycles sized on fallback equity before entries are blocked and an alert
+// goes out — about 10 minutes at the default 20s interval, long enough to
+// ride out a brief balance-endpoint outage.
+pub(super) const DEFAULT_MAX_EQUITY_FALLBACK_CYCLES: u32 = 30;
+
+// Pair warm-up minimums — 0 keeps the derived defaults (see
+// `pair_warm_check`).
+pub(super) const DEFAULT_MIN_SPREAD_HISTORY_POINTS: usize = 0;
+pub(super) const DEFAULT_MIN_SYMBOL_HISTORY_BARS: usize = 0;
+
+// Exit reasons closed at market outright: force_close and the loss stops.
+pub(super) const DEFAULT_EXIT_MARKET_REASONS: &str = "force_close,stop_loss_z,max_loss_r";
+
+// Pairs sharing a symbol with an active pair are skipped; the net-exposure
+// cap only applies under `shared_symbol_policy: net_exposure`.
+pub(super) const DEFAULT_SHARED_SYMBOL_POLICY: &str = "block";
+pub(super) const DEFAULT_MAX_NET_SYMBOL_EXPOSURE_USD: f64 = 0.0;
+
+// Periodic [METRICS] log — every 5 min, human-readable, compact.
+pub(super) const DEFAULT_METRICS_LOG_INTERVAL_SECS: u64 = 300;
+pub(super) const DEFAULT_METRICS_LOG_FORMAT: &str = "text";
+pub(super) const DEFAULT_METRICS_LOG_DETAIL: bool = false;
+
+// Entry selection ranks on each candidate's own funding rate.
+pub(super) const DEFAULT_PORTFOLIO_FUNDING_AWARE: bool = false;
+
+// Symbol-generated pairs are alphabetized (base < quote).
+pub(super) const DEFAULT_PRESERVE_SYMBOL_ORDER: bool = false;
+
+// Any open position blocks new entries.
+pub(super) const DEFAULT_SINGLE_POSITION_MODE: bool = true;
+
+// Crossed books fall back to the ticker price rather than blocking entries.
+pub(super) const DEFAULT_REJECT_CROSSED_BOOK: bool = false;
+
+// Equity history points carry only `ts` and `equity`.
+pub(super) const DEFAULT_EQUITY_HISTORY_DETAILED: bool = false;
+
+// Automatic connector restarts are off; when enabled, at most one per 10 min.
+pub(super) const DEFAULT_AUTO_RESTART_AFTER_FAILURES: u32 = 0;
+pub(super) const DEFAULT_AUTO_RESTART_COOLDOWN_SECS: u64 = 600;
+
+// Bar outlier filter: a bar whose ln-return from the previous bar exceeds
+// 0.5 (about -40%/+65%) is dropped as a bad tick, until
+// OUTLIER_BARS_BEFORE_ACCEPT such bars in a row mark a real level shift.
+pub(super) const DEFAULT_MAX_BAR_LOG_RETURN: f64 = 0.5;
+pub(super) const OUTLIER_BARS_BEFORE_ACCEPT: u32 = 3;
+
+// Single-phase entries; when scale-in is on, a half-size scout adds the rest
+// 0.5 sigma further out.
+pub(super) const DEFAULT_SCALE_IN_ENABLED: bool = false;
+pub(super) const DEFAULT_SCALE_IN_ADD_Z: f64 = 0.5;
+pub(super) const DEFAULT_SCALE_IN_FRACTION: f64 = 0.5;
+
+// Sit out 15 minutes after a NaN/inf statistic.
+pub(super) const DEFAULT_NON_FINITE_STATS_BLOCK_SECS: u64 = 900;
+
+// Entries go through the post-only/limit path unless market mode is on.
+pub(super) const DEFAULT_ENTRY_MARKET_MODE: bool = false;
+
+// Any single eligible pair may trade.
+pub(super) const DEFAULT_MIN_ELIGIBLE_PAIRS: usize = 1;
+
+// Evaluations a pair stays closed to entries after a losing exit. 0 = off.
+pub(super) const DEFAULT_LOSS_REENTRY_BLOCK_TICKS: u64 = 0;
+
+// Overrunning steps drop the missed tick boundaries instead of catching up.
+pub(super) const DEFAULT_MISSED_TICK_BEHAVIOR: &str = "skip";
+
+// Portfolio delta rebalancing — off; $50 per-symbol gap, checked every 5 min.
+pub(super) const DEFAULT_DELTA_REBALANCE_ENABLED: bool = false;
+pub(super) const DEFAULT_REBALANCE_THRESHOLD_USD: f64 = 50.0;
+pub(super) const DEFAULT_DELTA_REBALANCE_INTERVAL_SECS: u64 = 300;
+
+// A pair adopted from the exchange without local state starts its holding
+// clock at adoption unless recovery is on.
+pub(super) const DEFAULT_RECOVER_ENTRY_TIME_FROM_RISK_STATE: bool = false;
@@ -4,7 +4,11 @@
 use std::collections::VecDeque;
 
 use super::config::{PairParams, PairTradeConfig};
-use super::state::PairState;
+use super::market::{
+    book_spread_bps, funding_per_hour, net_funding_for_direction, venue_funding_interval_secs,
+    SymbolSnapshot,
+};
+use super::state::{PairState, PositionDirection};
 use super::stats::spread_slope_sigma;
 use super::util::tail_std;
 
@@ -59,7 +63,9 @@ const ENTRY_Z_SCALE_MAX: f64 = 2.0;
 /// is layered on top.
 const FUNDING_CARRY_ENTRY_DISCOUNT: f64 = 0.9;
 
-pub(super) fn entry_z_for_pair(
+/// Vol-ratio factor on `entry_z_base`: the pair's recent spread vol against
+/// the universe median, clamped to the entry-z scale bounds.
+pub(super) fn entry_z_alpha(
     cfg: &PairTradeConfig,
     pp: &PairParams,
     state: &PairState,
@@ -68,13 +74,107 @@ pub(super) fn entry_z_for_pair(
     let entry_vol_len =
         ((pp.entry_vol_lookback_hours * 3600) / cfg.trading_period_secs).max(1) as usize;
     let vol_pair = tail_std(&state.spread_history, entry_vol_len).unwrap_or(1.0);
-    let alpha = (vol_pair / vol_median).clamp(ENTRY_Z_SCALE_MIN, ENTRY_Z_SCALE_MAX);
-    let z = pp.entry_z_base * alpha;
+    (vol_pair / vol_median).clamp(ENTRY_Z_SCALE_MIN, ENTRY_Z_SCALE_MAX)
+}
+
+pub(super) fn entry_z_for_pair(
+    cfg: &PairTradeConfig,
+    pp: &PairParams,
+    state: &PairState,
+    vol_median: f64,
+) -> f64 {
+    let z = pp.entry_z_base * entry_z_alpha(cfg, pp, state, vol_median);
     z.clamp(pp.entry_z_min, pp.entry_z_max)
 }
 
+/// The factors `should_enter` stacks on the pair's clamped `z_entry`; |z|
+/// must reach `required()` for an entry. Logged as `[ENTRY_THRESHOLD]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct EntryThreshold {
+    pub(super) z_entry: f64,
+    /// Carry discount times the `funding_entry_z_scale` adjustment.
+    pub(super) funding_factor: f64,
+    /// `1 + beta_gap_entry_z_scale * beta_gap`.
+    pub(super) beta_gap_factor: f64,
+    pub(super) cost_in_sigma: f64,
+}
+
+impl EntryThreshold {
+    /// Threshold before costs.
+    pub(super) fn threshold(&self) -> f64 {
+        self.z_entry * self.funding_factor * self.beta_gap_factor
+    }
+
+    pub(super) fn required(&self) -> f64 {
+        self.threshold() + self.cost_in_sigma
+    }
+}
+
+pub(super) fn entry_threshold(
+    cfg: &PairTradeConfig,
+    key: &str,
+    pp: &PairParams,
+    state: &PairState,
+    std: f64,
+    net_funding: f64,
+) -> EntryThreshold {
+    // prefer positive carry by easing the required entry slightly
+    let mut funding_factor = if net_funding > 0.0 {
+        FUNDING_CARRY_ENTRY_DISCOUNT
+    } else {
+        1.0
+    };
+    // --- Phase 2 filter: funding rate continuous scaling ---
+    // Scale entry_z based on funding magnitude (beyond the simple discount
+    // above). funding_entry_z_scale > 0: entry_z *= 1.0 - scale * net_funding
+    //   positive funding → lower threshold (easier entry)
+    //   negative funding → higher threshold (harder entry)
+    // Disabled when funding_entry_z_scale == 0.0.
+    if pp.funding_entry_z_scale > 0.0 {
+        let adjustment = 1.0 - pp.funding_entry_z_scale * net_funding;
+        funding_factor *= adjustment.clamp(ENTRY_Z_SCALE_MIN, ENTRY_Z_SCALE_MAX);
+    }
+    // --- Phase 2 filter: beta gap dynamic adjustment ---
+    // Raise entry threshold when beta_s and beta_l diverge (hedge unreliable).
+    // entry_z *= 1.0 + scale * beta_gap
+    // Disabled when beta_gap_entry_z_scale == 0.0.
+    let beta_gap_factor = if pp.beta_gap_entry_z_scale > 0.0 {
+        1.0 + pp.beta_gap_entry_z_scale * state.beta_gap
+    } else {
+        1.0
+    };
+    EntryThreshold {
+        z_entry: state.z_entry,
+        funding_factor,
+        beta_gap_factor,
+        // Account for estimated cost (fees + slippage) in sigma units
+        cost_in_sigma: cost_in_sigma(cfg, key, std),
+    }
+}
+
+/// Re-entry cooldown after the pair's last exit. A stop-loss exit means the
+/// model misread the regime, so it gets `stop_loss_cooldown_secs` instead of
+/// the uniform `cooldown_secs`.
+fn entry_cooldown_secs(pp: &PairParams, state: &PairState) -> u64 {
+    match state.last_exit_reason {
+        Some("stop_loss_z") => pp.stop_loss_cooldown_secs.unwrap_or(pp.cooldown_secs),
+        _ => pp.cooldown_secs,
+    }
+}
+
+/// Estimated cost of one fill per leg (`pair_cost_bps`) in units of the
+/// spread's sigma; 0 for a degenerate `std`.
+pub(super) fn cost_in_sigma(cfg: &PairTradeConfig, key: &str, std: f64) -> f64 {
+    if std <= 1e-9 {
+        return 0.0;
+    }
+    cfg.pair_cost_bps(key) / 10_000.0 / std
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(super) fn should_enter(
     cfg: &PairTradeConfig,
+    key: &str,
     pp: &PairParams,
     state: &PairState,
     z: f64,
@@ -83,7 +183,7 @@ pub(super) fn should_enter(
     now_ts: i64,
 ) -> bool {
     if let Some(last_exit_ts) = state.last_exit_ts {
-        if now_ts.saturating_sub(last_exit_ts) < pp.cooldown_secs as i64 {
+        if now_ts.saturating_sub(last_exit_ts) < entry_cooldown_secs(pp, state) as i64 {
             return false;
         }
     }
@@ -133,31 +233,7 @@ pub(super) fn should_enter(
         }
     }
 
-    let mut entry_threshold = if net_funding > 0.0 {
-        // prefer positive carry by easing the required entry slightly
-        state.z_entry * FUNDING_CARRY_ENTRY_DISCOUNT
-    } else {
-        state.z_entry
-    };
-
-    // --- Phase 2 filter: funding rate continuous scaling ---
-    // Scale entry_z based on funding magnitude (beyond the simple discount
-    // above). funding_entry_z_scale > 0: entry_z *= 1.0 - scale * net_funding
-    //   positive funding → lower threshold (easier entry)
-    //   negative funding → higher threshold (harder entry)
-    // Disabled when funding_entry_z_scale == 0.0.
-    if pp.funding_entry_z_scale > 0.0 {
-        let adjustment = 1.0 - pp.funding_entry_z_scale * net_funding;
-        entry_threshold *= adjustment.clamp(ENTRY_Z_SCALE_MIN, ENTRY_Z_SCALE_MAX);
-    }
-
-    // --- Phase 2 filter: beta gap dynamic adjustment ---
-    // Raise entry threshold when beta_s and beta_l diverge (hedge unreliable).
-    // entry_z *= 1.0 + scale * beta_gap
-    // Disabled when beta_gap_entry_z_scale == 0.0.
-    if pp.beta_gap_entry_z_scale > 0.0 {
-        entry_threshold *= 1.0 + pp.beta_gap_entry_z_scale * state.beta_gap;
-    }
+    let threshold = entry_threshold(cfg, key, pp, state, std, net_funding);
 
     // Avoid entering when the current z already triggers stop-loss exit.
     if z.abs() >= pp.stop_loss_z {
@@ -174,14 +250,10 @@ pub(super) fn should_enter(
         return false;
     }
     // Beta minimum filter: block entry if beta is too low (hedge leg too small)
-    if pp.beta_min > 0.0 && state.beta < pp.beta_min {
+    if pp.beta_min > 0.0 && state.beta.abs() < pp.beta_min {
         return false;
     }
-    // Account for estimated cost (fees + slippage) in sigma units
-    let total_cost_bps = cfg.fee_bps * 2.0 + cfg.slippage_cost_bps() * 2.0; // two legs
-    let cost_ratio = total_cost_bps / 10_000.0;
-    let cost_in_sigma = if std <= 1e-9 { 0.0 } else { cost_ratio / std };
-    if z.abs() < entry_threshold {
+    if z.abs() < threshold.threshold() {
         return false;
     }
 
@@ -200,7 +272,94 @@ pub(super) fn should_enter(
         }
     }
 
-    z.abs() >= entry_threshold + cost_in_sigma && net_funding >= cfg.net_funding_min_per_hour
+    z.abs() >= threshold.required() && net_funding >= cfg.net_funding_min_per_hour
+}
+
+/// Direction an entry at `z` takes: short the spread above its mean, long
+/// below.
+pub(super) fn spread_direction_for_z(z: f64) -> PositionDirection {
+    if z > 0.0 {
+        PositionDirection::ShortSpread
+    } else {
+        PositionDirection::LongSpread
+    }
+}
+
+/// Per-direction eligibility `(long_spread, short_spread)`: the pair is
+/// `eligible` and the direction's own net carry is non-negative and at
+/// least `net_funding_min_per_hour`.
+pub(super) fn directional_eligibility(
+    cfg: &PairTradeConfig,
+    eligible: bool,
+    p1: &SymbolSnapshot,
+    p2: &SymbolSnapshot,
+) -> (bool, bool) {
+    let floor = cfg.net_funding_min_per_hour.max(0.0);
+    // net_funding_for_direction keys on the sign of z: z < 0 enters a long
+    // spread, z > 0 a short spread.
+    let ok = |z: f64| eligible && net_funding_for_direction(z, p1, p2) >= floor;
+    (ok(-1.0), ok(1.0))
+}
+
+/// `max_abs_funding_per_hour` guard: blocks an otherwise valid entry when
+/// either leg's absolute hourly funding exceeds the cap, independent of the
+/// net carry checked in `should_enter`, with rates spread over the venue's
+/// funding schedule like `funding_window_active`. Logs the block.
+pub(super) fn extreme_funding_blocks(
+    cfg: &PairTradeConfig,
+    key: &str,
+    z: f64,
+    p1: &SymbolSnapshot,
+    p2: &SymbolSnapshot,
+) -> bool {
+    let cap = cfg.max_abs_funding_per_hour;
+    if cap <= 0.0 {
+        return false;
+    }
+    let interval = venue_funding_interval_secs(&cfg.dex_name).unwrap_or(cfg.funding_interval_secs);
+    let funding_a = funding_per_hour(p1, interval);
+    let funding_b = funding_per_hour(p2, interval);
+    if funding_a.abs() <= cap && funding_b.abs() <= cap {
+        return false;
+    }
+    log::info!(
+        "[ENTRY] blocked extreme_funding {} z={:.2} funding_a={:.6}/h funding_b={:.6}/h cap={:.6}/h",
+        key,
+        z,
+        funding_a,
+        funding_b,
+        cap
+    );
+    true
+}
+
+/// `entry_max_book_spread_bps` guard: blocks an entry while either leg's
+/// top-of-book spread is wider than the cap. Logs the block.
+pub(super) fn wide_book_blocks(
+    cfg: &PairTradeConfig,
+    key: &str,
+    z: f64,
+    p1: &SymbolSnapshot,
+    p2: &SymbolSnapshot,
+) -> bool {
+    let cap = cfg.entry_max_book_spread_bps;
+    if cap <= 0.0 {
+        return false;
+    }
+    let spread_a = book_spread_bps(p1);
+    let spread_b = book_spread_bps(p2);
+    if spread_a.unwrap_or(0.0) <= cap && spread_b.unwrap_or(0.0) <= cap {
+        return false;
+    }
+    log::info!(
+        "[ENTRY] blocked wide_book {} z={:.2} spread_a={:.1}bps spread_b={:.1}bps cap={:.1}bps",
+        key,
+        z,
+        spread_a.unwrap_or(0.0),
+        spread_b.unwrap_or(0.0),
+        cap
+    );
+    true
 }
 
 #[cfg(test)]
@@ -268,4 +427,111 @@ mod tests {
         assert_eq!(median_of(&even), Some(2.5));
         assert_eq!(median_of(&VecDeque::<f64>::new()), None);
     }
+
+    #[test]
+    fn directional_eligibility_follows_each_direction_carry() {
+        use rust_decimal::Decimal;
+        use std::str::FromStr;
+        let snap = |funding: &str| SymbolSnapshot {
+            funding_rate: Decimal::from_str(funding).unwrap(),
+            ..SymbolSnapshot::test_at(Decimal::ONE)
+        };
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        cfg.net_funding_min_per_hour = -1.0;
+        // Quote funding above base: only the short spread has positive
+        // carry under net_funding_for_direction.
+        let (p1, p2) = (snap("0.0"), snap("0.0024"));
+        assert_eq!(directional_eligibility(&cfg, true, &p1, &p2), (false, true));
+        assert_eq!(directional_eligibility(&cfg, false, &p1, &p2), (false, false));
+        // Flat funding is acceptable in both directions.
+        let (p1, p2) = (snap("0.0"), snap("0.0"));
+        assert_eq!(directional_eligibility(&cfg, true, &p1, &p2), (true, true));
+    }
+
+    #[test]
+    fn extreme_funding_uses_the_venue_schedule() {
+        use rust_decimal::Decimal;
+        use std::str::FromStr;
+        let snap = |funding: &str| SymbolSnapshot {
+            funding_rate: Decimal::from_str(funding).unwrap(),
+            ..SymbolSnapshot::test_at(Decimal::ONE)
+        };
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        cfg.max_abs_funding_per_hour = 0.0005;
+        cfg.funding_interval_secs = 8 * 3600;
+        let (p1, p2) = (snap("0.001"), snap("0.0"));
+        // Spread over the configured 8h interval the rate is under the cap.
+        cfg.dex_name = "test".to_string();
+        assert!(!extreme_funding_blocks(&cfg, "AAA/BBB", 2.0, &p1, &p2));
+        // An hourly venue pays it every hour.
+        cfg.dex_name = "hyperliquid".to_string();
+        assert!(extreme_funding_blocks(&cfg, "AAA/BBB", 2.0, &p1, &p2));
+    }
+
+    #[test]
+    fn cost_in_sigma_charges_each_leg_its_own_fee() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        cfg.dex_name = "hyperliquid".to_string();
+        cfg.fee_bps = 2.0;
+        cfg.slippage_bps = 0;
+        cfg.maker_fee_bps_by_symbol = std::collections::HashMap::from([("ETH".to_string(), 0.0)]);
+        cfg.taker_fee_bps_by_symbol = std::collections::HashMap::from([("ETH".to_string(), 5.0)]);
+
+        // Taker venue: BTC at the global 2 bps, ETH at its 5 bps tier.
+        assert!((cost_in_sigma(&cfg, "BTC/SOL", 0.01) - 0.04).abs() < 1e-12);
+        assert!((cost_in_sigma(&cfg, "BTC/ETH", 0.01) - 0.07).abs() < 1e-12);
+        // Post-only venue charges the maker tier instead.
+        cfg.dex_name = "lighter".to_string();
+        assert!((cost_in_sigma(&cfg, "BTC/ETH", 0.01) - 0.02).abs() < 1e-12);
+        assert_eq!(cost_in_sigma(&cfg, "BTC/ETH", 0.0), 0.0);
+    }
+
+    #[test]
+    fn entry_threshold_stacks_funding_beta_gap_and_cost() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        cfg.dex_name = "hyperliquid".to_string();
+        cfg.fee_bps = 2.0;
+        cfg.slippage_bps = 0;
+        let pp = PairParams {
+            funding_entry_z_scale: 100.0,
+            beta_gap_entry_z_scale: 2.0,
+            ..Default::default()
+        };
+        let mut state = PairState::new(10, 2.0);
+        state.beta_gap = 0.1;
+
+        let t = entry_threshold(&cfg, "BTC/ETH", &pp, &state, 0.01, 0.001);
+        // Carry discount 0.9 times (1 - 100 * 0.001) = 0.81.
+        assert!((t.funding_factor - 0.81).abs() < 1e-12);
+        assert!((t.beta_gap_factor - 1.2).abs() < 1e-12);
+        assert!((t.cost_in_sigma - 0.04).abs() < 1e-12);
+        assert!((t.threshold() - 2.0 * 0.81 * 1.2).abs() < 1e-12);
+        assert!((t.required() - (2.0 * 0.81 * 1.2 + 0.04)).abs() < 1e-12);
+
+        // Negative carry neither discounts nor, with the scale off, raises.
+        let pp = PairParams::default();
+        let t = entry_threshold(&cfg, "BTC/ETH", &pp, &state, 0.01, -0.001);
+        assert_eq!((t.funding_factor, t.beta_gap_factor), (1.0, 1.0));
+    }
+
+    #[test]
+    fn stop_loss_exit_uses_stop_loss_cooldown() {
+        let pp = PairParams {
+            cooldown_secs: 30,
+            stop_loss_cooldown_secs: Some(600),
+            ..Default::default()
+        };
+        let mut state = PairState::new(10, 2.0);
+        state.last_exit_reason = Some("exit_z");
+        assert_eq!(entry_cooldown_secs(&pp, &state), 30);
+        state.last_exit_reason = Some("stop_loss_z");
+        assert_eq!(entry_cooldown_secs(&pp, &state), 600);
+
+        // Unset falls back to the normal cooldown.
+        let pp = PairParams {
+            cooldown_secs: 30,
+            ..Default::default()
+        };
+        assert_eq!(entry_cooldown_secs(&pp, &state), 30);
+    }
 }
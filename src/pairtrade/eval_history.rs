@@ -0,0 +1,59 @@
+//! Append-only jsonl time series of `evaluate_pair` diagnostics, written
+//! when `eval_history_file` is set. One record per evaluation, so the
+//! file shows how betas, half-life and the ADF p-value drifted ahead of
+//! an eligibility change. Independent of the PnL and data dump logs.
+
+use std::io;
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::jsonl;
+use super::pair_eval::PairEvaluation;
+
+#[derive(Serialize)]
+struct EvalHistoryRecord<'a> {
+    ts: i64,
+    pair: &'a str,
+    #[serde(flatten)]
+    eval: &'a PairEvaluation,
+}
+
+/// Append one evaluation for `pair` at `ts` (unix seconds) to `path`.
+pub(super) fn append(path: &Path, ts: i64, pair: &str, eval: &PairEvaluation) -> io::Result<()> {
+    jsonl::append(path, &[EvalHistoryRecord { ts, pair, eval }])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn appends_one_record_per_evaluation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("eval_history.jsonl");
+        let eval = PairEvaluation {
+            beta_short: 1.1,
+            beta_long: 1.0,
+            beta_eff: 1.05,
+            half_life_hours: 6.0,
+            adf_p_value: 0.02,
+            eligible: true,
+            score: 0.8,
+            beta_gap: 0.1,
+        };
+        append(&path, 100, "BTC/ETH", &eval).unwrap();
+        append(&path, 160, "BTC/ETH", &eval).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["ts"], 160);
+        assert_eq!(lines[0]["pair"], "BTC/ETH");
+        assert_eq!(lines[0]["adf_p_value"], 0.02);
+        assert_eq!(lines[0]["eligible"], true);
+    }
+}
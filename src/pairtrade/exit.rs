@@ -3,12 +3,14 @@
 use rust_decimal::prelude::FromPrimitive;
 use rust_decimal::Decimal;
 
-use super::config::{PairParams, PairTradeConfig};
+use super::config::{BetaDriftAction, PairParams, PairTradeConfig};
 use super::state::{PairState, Position, PositionDirection};
 use super::market::SymbolSnapshot;
 
+#[allow(clippy::too_many_arguments)]
 pub(super) fn exit_reason(
     cfg: &PairTradeConfig,
+    key: &str,
     pp: &PairParams,
     state: &PairState,
     z: f64,
@@ -25,25 +27,47 @@ pub(super) fn exit_reason(
     if now_ts.saturating_sub(pos.entered_ts) >= pp.force_close_secs as i64 {
         return Some("force_close");
     }
-    if pp.exit_z > 0.0 && z.abs() <= pp.exit_z {
-        return Some("exit_z");
-    }
     let pnl = compute_pnl(pos, p1.price, p2.price);
-    if let Some(pnl) = pnl {
-        let risk_budget = equity_base * cfg.risk_pct_per_trade;
-        if let Some(target) = Decimal::from_f64(risk_budget) {
-            if target > Decimal::ZERO {
-                if pp.max_loss_r_mult > 0.0 {
-                    let loss_mult = Decimal::from_f64(pp.max_loss_r_mult).unwrap_or(Decimal::ONE);
-                    let max_loss = -target * loss_mult;
-                    if pnl <= max_loss {
-                        return Some("max_loss_r");
-                    }
-                }
-                if pnl >= target {
-                    return Some("risk_budget");
-                }
+    let risk_budget = Decimal::from_f64(equity_base * cfg.risk_pct_per_trade)
+        .filter(|target| *target > Decimal::ZERO);
+    let max_loss_hit = match (pnl, risk_budget) {
+        (Some(pnl), Some(target)) if pp.max_loss_r_mult > 0.0 => {
+            let loss_mult = Decimal::from_f64(pp.max_loss_r_mult).unwrap_or(Decimal::ONE);
+            pnl <= -target * loss_mult
+        }
+        _ => false,
+    };
+    if cfg.min_hold_secs > 0 && now_ts.saturating_sub(pos.entered_ts) < cfg.min_hold_secs as i64 {
+        // Inside min_hold_secs only the loss stop may close the position.
+        return max_loss_hit.then_some("max_loss_r");
+    }
+    // A sign flip cannot be re-hedged by resizing leg B.
+    if beta_drift_breached(cfg, pos, state.beta)
+        && (cfg.beta_drift_action == BetaDriftAction::Exit
+            || (state.beta < 0.0) != pos.hedge_inverted)
+    {
+        return Some("beta_drift");
+    }
+    if pp.exit_z > 0.0 && z.abs() <= pp.exit_z {
+        if !cfg.exit_z_require_profit {
+            return Some("exit_z");
+        }
+        match net_pnl_after_costs(cfg, key, "exit_z", pos, p1, p2) {
+            Some(net) if net <= Decimal::ZERO => {
+                log::debug!(
+                    "[EXIT] exit_z held: net pnl {} after estimated costs not positive",
+                    net
+                );
             }
+            _ => return Some("exit_z"),
+        }
+    }
+    if max_loss_hit {
+        return Some("max_loss_r");
+    }
+    if let (Some(pnl), Some(target)) = (pnl, risk_budget) {
+        if pnl >= target {
+            return Some("risk_budget");
         }
     }
     if std > 1e-9 {
@@ -58,10 +82,9 @@ pub(super) fn exit_reason(
                         let k = (2.0_f64).ln() / half_life_secs;
                         let decay = (-k * remaining_secs).exp();
                         let expected_improvement = z.abs() * (1.0 - decay);
-                        let total_cost_bps = cfg.fee_bps * 2.0 + cfg.slippage_cost_bps() * 2.0;
-                        let cost_ratio = total_cost_bps / 10_000.0;
-                        let cost_in_sigma = cost_ratio / std;
-                        if expected_improvement <= cost_in_sigma {
+                        let exit_cost = cfg.pair_exit_cost_bps(key, "expected_value") / 10_000.0;
+                        let exit_cost_sigma = if std <= 1e-9 { 0.0 } else { exit_cost / std };
+                        if expected_improvement <= exit_cost_sigma {
                             return Some("expected_value");
                         }
                     }
@@ -72,6 +95,103 @@ pub(super) fn exit_reason(
     None
 }
 
+/// Whether `partial_take_profit_*` is enabled, the position has not been
+/// scaled out yet, and its PnL reached `partial_take_profit_r` times the
+/// per-trade risk budget. Targets at or above 1R never fire: the full
+/// `risk_budget` exit closes the position first.
+pub(super) fn partial_take_profit_hit(
+    cfg: &PairTradeConfig,
+    pos: &Position,
+    p1: &SymbolSnapshot,
+    p2: &SymbolSnapshot,
+    equity_base: f64,
+) -> bool {
+    if pos.partial_taken || cfg.partial_take_profit_fraction <= 0.0 || cfg.partial_take_profit_r <= 0.0 {
+        return false;
+    }
+    let target = Decimal::from_f64(equity_base * cfg.risk_pct_per_trade * cfg.partial_take_profit_r)
+        .filter(|target| *target > Decimal::ZERO);
+    match (compute_pnl(pos, p1.price, p2.price), target) {
+        (Some(pnl), Some(target)) => pnl >= target,
+        _ => false,
+    }
+}
+
+/// Whether `scale_in_enabled` is on, the scout has not been added to yet,
+/// and z has moved at least `scale_in_add_z` further out than the entry z on
+/// the same side while staying short of `stop_loss_z`.
+pub(super) fn scale_in_due(cfg: &PairTradeConfig, pp: &PairParams, pos: &Position, z: f64) -> bool {
+    if !cfg.scale_in_enabled || pos.scaled_in || cfg.scale_in_add_z <= 0.0 {
+        return false;
+    }
+    let Some(entry_z) = pos.entry_z.filter(|e| e.abs() > 0.0) else {
+        return false;
+    };
+    entry_z.signum() == z.signum()
+        && z.abs() >= entry_z.abs() + cfg.scale_in_add_z
+        && z.abs() < pp.stop_loss_z
+}
+
+/// The `scale_in_*` remainder for a scout entered with `scout` leg sizes:
+/// the scout is `scale_in_fraction` of the target, so this is the rest of
+/// the target. `None` when scale-in is off.
+pub(super) fn scale_in_remainder(
+    cfg: &PairTradeConfig,
+    scout: (Decimal, Decimal),
+) -> Option<(Decimal, Decimal)> {
+    if !cfg.scale_in_enabled {
+        return None;
+    }
+    let fraction = cfg.scale_in_fraction;
+    let ratio = Decimal::from_f64((1.0 - fraction) / fraction)?;
+    Some((scout.0 * ratio, scout.1 * ratio))
+}
+
+/// Change of `beta` against the position's entry beta, in percent of the
+/// entry beta. `None` without a known, non-zero entry beta.
+pub(super) fn beta_drift_pct(pos: &Position, beta: f64) -> Option<f64> {
+    let entry = pos.entry_beta.filter(|b| b.abs() > 1e-12)?;
+    Some((beta - entry).abs() / entry.abs() * 100.0)
+}
+
+/// Whether `beta_drift_exit_pct` is enabled and `beta` has drifted past it.
+pub(super) fn beta_drift_breached(cfg: &PairTradeConfig, pos: &Position, beta: f64) -> bool {
+    cfg.beta_drift_exit_pct > 0.0
+        && beta_drift_pct(pos, beta).is_some_and(|pct| pct > cfg.beta_drift_exit_pct)
+}
+
+/// `compute_pnl` minus estimated fees and slippage on all four fills (entry
+/// and exit of both legs): the entries at each leg's `leg_fee_bps`, the
+/// exits at its `exit_fee_bps` for `reason`. Backtest fills that already
+/// include the costs are taken as is. `None` when the PnL cannot be
+/// computed.
+pub(super) fn net_pnl_after_costs(
+    cfg: &PairTradeConfig,
+    key: &str,
+    reason: &str,
+    pos: &Position,
+    p1: &SymbolSnapshot,
+    p2: &SymbolSnapshot,
+) -> Option<Decimal> {
+    let pnl = compute_pnl(pos, p1.price, p2.price)?;
+    if cfg.fills_include_costs() {
+        return Some(pnl);
+    }
+    let size_a = pos.entry_size_a?;
+    let size_b = pos.entry_size_b?;
+    let (base, quote) = key.split_once('/').unwrap_or((key, key));
+    let ratio = |fee_bps: f64| {
+        Decimal::from_f64((fee_bps + cfg.slippage_cost_bps()) / 10_000.0).unwrap_or(Decimal::ZERO)
+    };
+    let leg_cost = |symbol: &str, size: Decimal, entry: Decimal, exit: Decimal| {
+        (size * entry).abs() * ratio(cfg.leg_fee_bps(symbol))
+            + (size * exit).abs() * ratio(cfg.exit_fee_bps(symbol, reason))
+    };
+    let cost_a = leg_cost(base, size_a, pos.entry_price_a?, p1.price);
+    let cost_b = leg_cost(quote, size_b, pos.entry_price_b?, p2.price);
+    Some(pnl - cost_a - cost_b)
+}
+
 pub(super) fn compute_pnl(
     pos: &Position,
     exit_price_a: Decimal,
@@ -91,5 +211,233 @@ pub(super) fn compute_pnl(
             (exit_price_b - entry_price_b) * entry_size_b,
         ),
     };
+    let pnl_b = if pos.hedge_inverted { -pnl_b } else { pnl_b };
     Some(pnl_a + pnl_b)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn dec(v: &str) -> Decimal {
+        v.parse().unwrap()
+    }
+
+    #[test]
+    fn net_pnl_subtracts_round_trip_costs() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        cfg.fee_bps = 5.0;
+        cfg.slippage_bps = 0;
+        let pos = Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts: 0,
+            entry_price_a: Some(dec("100")),
+            entry_price_b: Some(dec("50")),
+            entry_size_a: Some(dec("1")),
+            entry_size_b: Some(dec("2")),
+            entry_z: None,
+            entry_beta: None,
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        };
+        let snap = |price: &str| SymbolSnapshot::test_at(dec(price));
+        // Gross +0.1 on leg A; 400.1 traded notional at 5 bps = 0.20005 cost.
+        let (p1, p2) = (snap("100.1"), snap("50"));
+        assert_eq!(compute_pnl(&pos, p1.price, p2.price), Some(dec("0.1")));
+        assert_eq!(net_pnl_after_costs(&cfg, "A/B", "exit_z", &pos, &p1, &p2), Some(dec("-0.10005")));
+
+        let (p1, p2) = (snap("101"), snap("50"));
+        assert!(net_pnl_after_costs(&cfg, "A/B", "exit_z", &pos, &p1, &p2).unwrap() > Decimal::ZERO);
+
+        // A cheaper fee tier on leg B only lowers B's 200 traded notional.
+        cfg.taker_fee_bps_by_symbol = std::collections::HashMap::from([("B".to_string(), 1.0)]);
+        let (p1, p2) = (snap("100.1"), snap("50"));
+        assert_eq!(net_pnl_after_costs(&cfg, "A/B", "exit_z", &pos, &p1, &p2), Some(dec("-0.02005")));
+
+        // Cost-adjusted backtest fills are not charged a second time.
+        cfg.backtest_mode = true;
+        cfg.backtest_apply_costs = true;
+        assert_eq!(net_pnl_after_costs(&cfg, "A/B", "exit_z", &pos, &p1, &p2), Some(dec("0.1")));
+    }
+
+    #[test]
+    fn inverted_hedge_pnl_flips_leg_b() {
+        // Long spread on a negative beta buys both legs, so B rising pays.
+        let pos = Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts: 0,
+            entry_price_a: Some(dec("100")),
+            entry_price_b: Some(dec("50")),
+            entry_size_a: Some(dec("1")),
+            entry_size_b: Some(dec("2")),
+            entry_z: None,
+            entry_beta: None,
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: true,
+        };
+        assert_eq!(compute_pnl(&pos, dec("101"), dec("51")), Some(dec("3")));
+        assert_eq!(
+            PositionDirection::ShortSpread.entry_sides(true),
+            (
+                dex_connector::OrderSide::Short,
+                dex_connector::OrderSide::Short
+            )
+        );
+    }
+
+    #[test]
+    fn beta_drift_measured_against_entry_beta() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        let mut pos = Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts: 0,
+            entry_price_a: None,
+            entry_price_b: None,
+            entry_size_a: None,
+            entry_size_b: None,
+            entry_z: None,
+            entry_beta: Some(0.8),
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        };
+        assert!((beta_drift_pct(&pos, 1.0).unwrap() - 25.0).abs() < 1e-9);
+        cfg.beta_drift_exit_pct = 0.0;
+        assert!(!beta_drift_breached(&cfg, &pos, 1.0));
+        cfg.beta_drift_exit_pct = 20.0;
+        assert!(beta_drift_breached(&cfg, &pos, 1.0));
+        assert!(!beta_drift_breached(&cfg, &pos, 0.9));
+        pos.entry_beta = None;
+        assert!(!beta_drift_breached(&cfg, &pos, 1.0));
+    }
+
+    #[test]
+    fn min_hold_defers_all_but_loss_stops() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        cfg.min_hold_secs = 60;
+        cfg.exit_z_require_profit = false;
+        cfg.risk_pct_per_trade = 0.01;
+        let pp = PairParams {
+            exit_z: 0.5,
+            stop_loss_z: 4.0,
+            force_close_secs: 3_600,
+            max_loss_r_mult: 1.0,
+            ..Default::default()
+        };
+        let mut state = PairState::new(10, 2.0);
+        state.position = Some(Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts: 0,
+            entry_price_a: Some(dec("100")),
+            entry_price_b: Some(dec("50")),
+            entry_size_a: Some(dec("1")),
+            entry_size_b: Some(dec("2")),
+            entry_z: None,
+            entry_beta: None,
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        });
+        let snap = |price: &str| SymbolSnapshot::test_at(dec(price));
+        let reason = |z: f64, price_a: &str, now_ts: i64| {
+            exit_reason(
+                &cfg,
+                "A/B",
+                &pp,
+                &state,
+                z,
+                0.0,
+                &snap(price_a),
+                &snap("50"),
+                1_000.0,
+                now_ts,
+            )
+        };
+        assert_eq!(reason(0.1, "100", 30), None);
+        assert_eq!(reason(5.0, "100", 30), Some("stop_loss_z"));
+        // Loss of 20 against a 10 risk budget.
+        assert_eq!(reason(0.1, "80", 30), Some("max_loss_r"));
+        assert_eq!(reason(0.1, "100", 60), Some("exit_z"));
+    }
+
+    #[test]
+    fn partial_take_profit_fires_once_at_target() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        cfg.risk_pct_per_trade = 0.01;
+        cfg.partial_take_profit_r = 0.5;
+        let mut pos = Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts: 0,
+            entry_price_a: Some(dec("100")),
+            entry_price_b: Some(dec("50")),
+            entry_size_a: Some(dec("1")),
+            entry_size_b: Some(dec("2")),
+            entry_z: None,
+            entry_beta: None,
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        };
+        let snap = |price: &str| SymbolSnapshot::test_at(dec(price));
+        // Target is 0.5 * 1% of 1000 = 5.
+        assert!(!partial_take_profit_hit(&cfg, &pos, &snap("106"), &snap("50"), 1_000.0));
+        cfg.partial_take_profit_fraction = 0.5;
+        assert!(!partial_take_profit_hit(&cfg, &pos, &snap("104"), &snap("50"), 1_000.0));
+        assert!(partial_take_profit_hit(&cfg, &pos, &snap("106"), &snap("50"), 1_000.0));
+        pos.partial_taken = true;
+        assert!(!partial_take_profit_hit(&cfg, &pos, &snap("106"), &snap("50"), 1_000.0));
+    }
+
+    #[test]
+    fn scale_in_due_between_add_z_and_stop() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        cfg.scale_in_add_z = 0.5;
+        let pp = PairParams {
+            stop_loss_z: 4.0,
+            ..Default::default()
+        };
+        let mut pos = Position {
+            direction: PositionDirection::ShortSpread,
+            entered_at: Instant::now(),
+            entered_ts: 0,
+            entry_price_a: None,
+            entry_price_b: None,
+            entry_size_a: None,
+            entry_size_b: None,
+            entry_z: Some(2.0),
+            entry_beta: None,
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        };
+        assert!(!scale_in_due(&cfg, &pp, &pos, 2.6));
+        cfg.scale_in_enabled = true;
+        assert!(!scale_in_due(&cfg, &pp, &pos, 2.4));
+        assert!(scale_in_due(&cfg, &pp, &pos, 2.6));
+        // Past the stop, or flipped to the other side, never adds.
+        assert!(!scale_in_due(&cfg, &pp, &pos, 4.0));
+        assert!(!scale_in_due(&cfg, &pp, &pos, -2.6));
+        pos.scaled_in = true;
+        assert!(!scale_in_due(&cfg, &pp, &pos, 2.6));
+    }
+}
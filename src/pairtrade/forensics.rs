@@ -0,0 +1,85 @@
+//! Forensic snapshots written on `Inconsistent state` errors when
+//! `forensic_dump_dir` is set. The engine state types hold `Instant`s and
+//! are not `Serialize`, so per-pair state is captured as its `Debug`
+//! rendering inside an otherwise structured JSON document. Files are named
+//! `forensic_<UTC timestamp>.json`; only the newest `forensic_dump_keep`
+//! are retained.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+
+const FILE_PREFIX: &str = "forensic_";
+
+/// Write `snapshot` into `dir` (tmpfile + rename) and prune old dumps.
+pub(super) fn write_snapshot(
+    dir: &Path,
+    keep: usize,
+    snapshot: &serde_json::Value,
+) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let name = format!(
+        "{}{}.json",
+        FILE_PREFIX,
+        Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+    );
+    let path = dir.join(name);
+    let tmp_path = path.with_extension("json.tmp");
+    let payload = serde_json::to_vec_pretty(snapshot).map_err(io::Error::other)?;
+    fs::write(&tmp_path, payload)?;
+    fs::rename(&tmp_path, &path)?;
+    prune(dir, keep)?;
+    Ok(path)
+}
+
+/// Delete all but the newest `keep` dumps. Names sort chronologically.
+fn prune(dir: &Path, keep: usize) -> io::Result<()> {
+    let mut dumps: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(FILE_PREFIX) && n.ends_with(".json"))
+        })
+        .collect();
+    if dumps.len() <= keep.max(1) {
+        return Ok(());
+    }
+    dumps.sort();
+    let excess = dumps.len() - keep.max(1);
+    for path in dumps.into_iter().take(excess) {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_only_newest_dumps() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        for name in [
+            "forensic_20260101T000000.000Z.json",
+            "forensic_20260102T000000.000Z.json",
+        ] {
+            fs::write(dir.join(name), "{}").unwrap();
+        }
+        fs::write(dir.join("unrelated.json"), "{}").unwrap();
+
+        let written = write_snapshot(dir, 2, &serde_json::json!({ "reason": "test" })).unwrap();
+        let mut left: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left.len(), 3);
+        assert_eq!(left[0], "forensic_20260102T000000.000Z.json");
+        assert!(written.exists());
+        assert!(left.contains(&"unrelated.json".to_string()));
+    }
+}
@@ -3,13 +3,19 @@
 
 use std::collections::{HashMap, VecDeque};
 use std::fs;
+use std::io::{Read, Write};
 use std::path::Path;
 use std::time::{Duration, SystemTime};
 
 use chrono::Utc;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
-use super::config::PairTradeConfig;
+use super::config::{HistoryPriceFormat, PairTradeConfig};
 use super::stats::PriceSample;
 
 /// On-disk snapshot schema used by the live bot. Version 2 adds
@@ -24,11 +30,24 @@ use super::stats::PriceSample;
 /// Version 1 (no `_v` field) was a bare `HashMap<String,
 /// Vec<(f64, i64)>>`. The loader parses v2 first and falls back to
 /// v1 on failure, so pre-existing history files keep working.
+///
+/// Version 3 (`history_price_format: decimal`) leaves `prices` empty and
+/// stores raw bar closes in `prices_dec` instead; the log is recomputed on
+/// load. The same struct parses all three versions.
+///
+/// `migrate_snapshot` is the single entry point for reading any of them;
+/// a file tagged with a version newer than `SNAPSHOT_VERSION_MAX` is
+/// rejected with a warning rather than misread.
 #[derive(Serialize, Deserialize, Default)]
 struct SnapshotV2 {
     #[serde(rename = "_v")]
     version: u32,
+    #[serde(default)]
     prices: HashMap<String, Vec<(f64, i64)>>,
+    /// Symbol → `(close, ts)` with `close` serialized as a Decimal string.
+    /// Only populated by v3 writers.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    prices_dec: HashMap<String, Vec<(Decimal, i64)>>,
     /// Pair key (e.g. "BTC/ETH") → the live engine's
     /// `state.spread_history` as a plain `Vec<f64>`. Missing in older
     /// files; defaulted to empty by `#[serde(default)]`.
@@ -36,6 +55,128 @@ struct SnapshotV2 {
     spread_histories: HashMap<String, Vec<f64>>,
 }
 
+/// Newest snapshot version this build can read.
+const SNAPSHOT_VERSION_MAX: u32 = 3;
+
+impl SnapshotV2 {
+    fn build(
+        format: HistoryPriceFormat,
+        history: &HashMap<String, VecDeque<PriceSample>>,
+        spread_histories: &HashMap<String, VecDeque<f64>>,
+    ) -> Self {
+        let spread_histories: HashMap<String, Vec<f64>> = spread_histories
+            .iter()
+            .map(|(k, deque)| (k.clone(), deque.iter().copied().collect()))
+            .collect();
+        match format {
+            HistoryPriceFormat::LogF64 => {
+                let prices: HashMap<String, Vec<(f64, i64)>> = history
+                    .iter()
+                    .map(|(sym, deque)| {
+                        let v: Vec<(f64, i64)> =
+                            deque.iter().map(|p| (p.log_price, p.ts)).collect();
+                        (sym.clone(), v)
+                    })
+                    .collect();
+                SnapshotV2 {
+                    version: 2,
+                    prices,
+                    prices_dec: HashMap::new(),
+                    spread_histories,
+                }
+            }
+            HistoryPriceFormat::Decimal => {
+                // Samples loaded from an f64 file carry no raw close; those
+                // are approximated once via exp(log) and are exact from then
+                // on.
+                let prices_dec: HashMap<String, Vec<(Decimal, i64)>> = history
+                    .iter()
+                    .map(|(sym, deque)| {
+                        let v: Vec<(Decimal, i64)> = deque
+                            .iter()
+                            .filter_map(|p| {
+                                p.close
+                                    .or_else(|| Decimal::from_f64(p.log_price.exp()))
+                                    .map(|c| (c, p.ts))
+                            })
+                            .collect();
+                        (sym.clone(), v)
+                    })
+                    .collect();
+                SnapshotV2 {
+                    version: 3,
+                    prices: HashMap::new(),
+                    prices_dec,
+                    spread_histories,
+                }
+            }
+        }
+    }
+
+    fn into_samples(self) -> HashMap<String, Vec<PriceSample>> {
+        let mut out: HashMap<String, Vec<PriceSample>> = self
+            .prices
+            .into_iter()
+            .map(|(sym, entries)| {
+                let v = entries
+                    .into_iter()
+                    .map(|(log_price, ts)| PriceSample {
+                        log_price,
+                        ts,
+                        close: None,
+                    })
+                    .collect();
+                (sym, v)
+            })
+            .collect();
+        for (sym, entries) in self.prices_dec {
+            let v = entries
+                .into_iter()
+                .filter_map(|(close, ts)| {
+                    let log_price = close.to_f64().filter(|p| *p > 0.0)?.ln();
+                    Some(PriceSample {
+                        log_price,
+                        ts,
+                        close: Some(close),
+                    })
+                })
+                .collect();
+            out.insert(sym, v);
+        }
+        out
+    }
+}
+
+/// A `history_file` ending in `.gz` is written gzip-compressed. The
+/// snapshot is rewritten whole on every persist (tmpfile + rename), so
+/// unlike the data dump there is no append/rotation concern.
+fn is_gzip_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
+fn encode_snapshot(path: &Path, json: String) -> std::io::Result<Vec<u8>> {
+    if !is_gzip_path(path) {
+        return Ok(json.into_bytes());
+    }
+    let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+    enc.write_all(json.as_bytes())?;
+    enc.finish()
+}
+
+/// Read a snapshot file, transparently gunzipping it when it starts with
+/// the gzip magic bytes (regardless of extension).
+fn read_snapshot_text(path: &Path) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut content = String::new();
+        MultiGzDecoder::new(bytes.as_slice())
+            .read_to_string(&mut content)
+            .ok()?;
+        return Some(content);
+    }
+    String::from_utf8(bytes).ok()
+}
+
 pub(super) fn persist_history_to_disk(
     cfg: &PairTradeConfig,
     history: &HashMap<String, VecDeque<PriceSample>>,
@@ -53,22 +194,7 @@ pub(super) fn persist_history_to_disk(
     if cfg.backtest_mode {
         return;
     }
-    let prices: HashMap<String, Vec<(f64, i64)>> = history
-        .iter()
-        .map(|(sym, deque)| {
-            let v: Vec<(f64, i64)> = deque.iter().map(|p| (p.log_price, p.ts)).collect();
-            (sym.clone(), v)
-        })
-        .collect();
-    let spread_histories: HashMap<String, Vec<f64>> = spread_histories
-        .iter()
-        .map(|(k, deque)| (k.clone(), deque.iter().copied().collect()))
-        .collect();
-    let snapshot = SnapshotV2 {
-        version: 2,
-        prices,
-        spread_histories,
-    };
+    let snapshot = SnapshotV2::build(cfg.history_price_format, history, spread_histories);
     if let Ok(json) = serde_json::to_string(&snapshot) {
         // Atomic write: tmpfile in the same directory + rename. Multiple
         // bots may be writing this shared file concurrently (pairtrade#4);
@@ -80,7 +206,14 @@ pub(super) fn persist_history_to_disk(
             .map(|s| s.to_string_lossy().into_owned())
             .unwrap_or_else(|| "pairtrade_history.json".to_string());
         let tmp = dir.join(format!(".{}.tmp.{}", file_name, std::process::id()));
-        if let Err(e) = fs::write(&tmp, json) {
+        let bytes = match encode_snapshot(path, json) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::debug!("persist history gzip encode failed: {:?}", e);
+                return;
+            }
+        };
+        if let Err(e) = fs::write(&tmp, bytes) {
             log::debug!("persist history tmp write failed: {:?}", e);
             return;
         }
@@ -102,12 +235,14 @@ fn archive_snapshot_hourly(cfg: &PairTradeConfig, history_path: &Path) {
         log::debug!("archive dir create failed: {:?}", e);
         return;
     }
-    let stem = history_path
-        .file_stem()
-        .unwrap_or_default()
-        .to_string_lossy();
+    let (stem_path, ext) = if is_gzip_path(history_path) {
+        (history_path.with_extension(""), "json.gz")
+    } else {
+        (history_path.to_path_buf(), "json")
+    };
+    let stem = stem_path.file_stem().unwrap_or_default().to_string_lossy();
     let hour_tag = Utc::now().format("%Y%m%dT%H00Z");
-    let archive_path = archive_dir.join(format!("{}.{}.json", stem, hour_tag));
+    let archive_path = archive_dir.join(format!("{}.{}.{}", stem, hour_tag, ext));
     if archive_path.exists() {
         return;
     }
@@ -142,25 +277,69 @@ fn cleanup_old_archives(dir: &Path, retention_days: u32) {
     }
 }
 
-/// Parse the persisted history file, accepting both v2 (explicit
-/// `SnapshotV2` struct) and legacy v1 (bare per-symbol map). Returns
+/// Read snapshot text of any supported version into the current struct.
+/// Untagged files are the legacy v1 bare per-symbol map and come back as
+/// `version: 1` with no spread histories; tagged files are checked against
+/// `SNAPSHOT_VERSION_MAX` before being deserialized.
+fn migrate_snapshot(content: &str) -> Result<SnapshotV2, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(content).map_err(|e| format!("invalid JSON: {}", e))?;
+    let Some(tag) = value.get("_v") else {
+        let prices: HashMap<String, Vec<(f64, i64)>> = serde_json::from_value(value)
+            .map_err(|e| format!("unversioned file is not a v1 price map: {}", e))?;
+        return Ok(SnapshotV2 {
+            version: 1,
+            prices,
+            ..Default::default()
+        });
+    };
+    let version = tag
+        .as_u64()
+        .and_then(|v| u32::try_from(v).ok())
+        .ok_or_else(|| format!("unreadable version tag {}", tag))?;
+    if version > SNAPSHOT_VERSION_MAX {
+        return Err(format!(
+            "version {} is newer than supported v{}",
+            version, SNAPSHOT_VERSION_MAX
+        ));
+    }
+    serde_json::from_value(value).map_err(|e| format!("v{} schema mismatch: {}", version, e))
+}
+
+/// Parse the persisted history file via `migrate_snapshot`. Returns
 /// (prices, spread_histories) where `spread_histories` is empty for v1.
+/// A missing file is silent; a file that exists but cannot be read as any
+/// supported version is logged, since it means a cold start.
 fn parse_snapshot_file(
     path: &std::path::Path,
-) -> Option<(
-    HashMap<String, Vec<(f64, i64)>>,
-    HashMap<String, Vec<f64>>,
-)> {
-    let content = fs::read_to_string(path).ok()?;
-    // Try v2 first (has explicit schema with `_v` and `prices`).
-    if let Ok(v2) = serde_json::from_str::<SnapshotV2>(&content) {
-        if v2.version >= 2 {
-            return Some((v2.prices, v2.spread_histories));
+) -> Option<(HashMap<String, Vec<PriceSample>>, HashMap<String, Vec<f64>>)> {
+    if !path.exists() {
+        return None;
+    }
+    let Some(content) = read_snapshot_text(path) else {
+        log::warn!("[HISTORY] could not read {}", path.display());
+        return None;
+    };
+    let mut snapshot = match migrate_snapshot(&content) {
+        Ok(snapshot) => snapshot,
+        Err(reason) => {
+            log::warn!(
+                "[HISTORY] ignoring {}: {}; history will cold-start",
+                path.display(),
+                reason
+            );
+            return None;
         }
+    };
+    if snapshot.version < 2 {
+        log::info!(
+            "[HISTORY] migrated legacy v{} snapshot {}; it is rewritten in the current format on the next persist",
+            snapshot.version,
+            path.display()
+        );
     }
-    // Fall back to v1 (bare `HashMap<String, Vec<(f64, i64)>>`).
-    let prices: HashMap<String, Vec<(f64, i64)>> = serde_json::from_str(&content).ok()?;
-    Some((prices, HashMap::new()))
+    let spreads = std::mem::take(&mut snapshot.spread_histories);
+    Some((snapshot.into_samples(), spreads))
 }
 
 /// Load a history snapshot for backtest warm-start. Unlike
@@ -186,12 +365,12 @@ pub(super) fn load_history_snapshot_for_bt(
         if entries.is_empty() {
             continue;
         }
-        let newest_ts = entries.iter().map(|(_, ts)| *ts).max().unwrap_or(0);
+        let newest_ts = entries.iter().map(|s| s.ts).max().unwrap_or(0);
         let max_age = (max_history_len as i64) * 60; // assume 60s bars
         let mut deque = VecDeque::new();
-        for (log_price, ts) in entries {
-            if newest_ts.saturating_sub(ts) <= max_age {
-                deque.push_back(PriceSample { log_price, ts });
+        for sample in entries {
+            if newest_ts.saturating_sub(sample.ts) <= max_age {
+                deque.push_back(sample);
             }
         }
         if !deque.is_empty() {
@@ -240,6 +419,37 @@ pub(super) fn load_history_from_disk(
     let Some((prices, spreads)) = parse_snapshot_file(history_path) else {
         return;
     };
+    apply_loaded_history(
+        cfg,
+        history,
+        spread_histories_out,
+        prices,
+        spreads,
+        now_ts,
+        max_history_len,
+    );
+}
+
+/// Spread histories from the snapshot file alone, for backends that keep
+/// log prices elsewhere.
+pub(super) fn load_spread_histories(history_path: &Path) -> HashMap<String, Vec<f64>> {
+    parse_snapshot_file(history_path)
+        .map(|(_, spreads)| spreads)
+        .unwrap_or_default()
+}
+
+/// Merge loaded samples into `history`, dropping symbols whose newest sample
+/// is stale and samples older than `max_history_len` bars. Spread histories
+/// are only restored when no symbol was stale.
+pub(super) fn apply_loaded_history(
+    cfg: &PairTradeConfig,
+    history: &mut HashMap<String, VecDeque<PriceSample>>,
+    spread_histories_out: &mut HashMap<String, VecDeque<f64>>,
+    prices: HashMap<String, Vec<PriceSample>>,
+    spreads: HashMap<String, Vec<f64>>,
+    now_ts: i64,
+    max_history_len: usize,
+) {
     let max_age_secs =
         (max_history_len as i64).saturating_mul(cfg.trading_period_secs as i64);
     // Stale-history guard (pairtrade#4): if the newest sample for a symbol
@@ -249,7 +459,7 @@ pub(super) fn load_history_from_disk(
     let stale_threshold_secs = (cfg.trading_period_secs as i64).saturating_mul(5).max(60);
     let mut any_stale = false;
     for (sym, entries) in prices {
-        let newest_ts = entries.iter().map(|(_, ts)| *ts).max().unwrap_or(0);
+        let newest_ts = entries.iter().map(|s| s.ts).max().unwrap_or(0);
         if now_ts.saturating_sub(newest_ts) > stale_threshold_secs {
             log::debug!(
                 "discarding stale persisted history for {}: newest sample {}s old",
@@ -260,11 +470,11 @@ pub(super) fn load_history_from_disk(
             continue;
         }
         let mut deque = VecDeque::new();
-        for (log_price, ts) in entries {
-            if now_ts.saturating_sub(ts) > max_age_secs {
+        for sample in entries {
+            if now_ts.saturating_sub(sample.ts) > max_age_secs {
                 continue;
             }
-            deque.push_back(PriceSample { log_price, ts });
+            deque.push_back(sample);
         }
         if !deque.is_empty() {
             history.insert(sym, deque);
@@ -285,3 +495,127 @@ pub(super) fn load_history_from_disk(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn sample_history() -> HashMap<String, VecDeque<PriceSample>> {
+        let closes = ["64123.45", "64130.1", "64099.999"];
+        let deque = closes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let close = Decimal::from_str(c).unwrap();
+                PriceSample {
+                    log_price: close.to_f64().unwrap().ln(),
+                    ts: 1_700_000_000 + i as i64 * 60,
+                    close: Some(close),
+                }
+            })
+            .collect();
+        HashMap::from([("BTC".to_string(), deque)])
+    }
+
+    fn round_trip(format: HistoryPriceFormat) -> HashMap<String, Vec<PriceSample>> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        let spreads = HashMap::from([("BTC/ETH".to_string(), VecDeque::from(vec![0.1, -0.2]))]);
+        let snapshot = SnapshotV2::build(format, &sample_history(), &spreads);
+        fs::write(&path, serde_json::to_string(&snapshot).unwrap()).unwrap();
+        let (prices, spread_histories) = parse_snapshot_file(&path).unwrap();
+        assert_eq!(spread_histories["BTC/ETH"], vec![0.1, -0.2]);
+        prices
+    }
+
+    #[test]
+    fn gz_history_file_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.json.gz");
+        let snapshot =
+            SnapshotV2::build(HistoryPriceFormat::LogF64, &sample_history(), &HashMap::new());
+        let bytes = encode_snapshot(&path, serde_json::to_string(&snapshot).unwrap()).unwrap();
+        assert!(bytes.starts_with(&[0x1f, 0x8b]));
+        fs::write(&path, bytes).unwrap();
+        let (prices, _) = parse_snapshot_file(&path).unwrap();
+        assert_eq!(prices["BTC"].len(), 3);
+    }
+
+    #[test]
+    fn decimal_format_round_trips_closes_and_logs_exactly() {
+        let original = sample_history();
+        let loaded = round_trip(HistoryPriceFormat::Decimal);
+        let btc = &loaded["BTC"];
+        assert_eq!(btc.len(), 3);
+        for (got, want) in btc.iter().zip(original["BTC"].iter()) {
+            assert_eq!(got.ts, want.ts);
+            assert_eq!(got.close, want.close);
+            assert_eq!(got.log_price.to_bits(), want.log_price.to_bits());
+        }
+    }
+
+    #[test]
+    fn log_f64_format_still_loads_without_closes() {
+        let loaded = round_trip(HistoryPriceFormat::LogF64);
+        let btc = &loaded["BTC"];
+        assert_eq!(btc.len(), 3);
+        assert!(btc.iter().all(|s| s.close.is_none()));
+        assert!((btc[0].log_price - 64123.45f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn legacy_v1_file_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        fs::write(&path, r#"{"ETH":[[7.9,1700000000],[7.91,1700000060]]}"#).unwrap();
+        let (prices, spreads) = parse_snapshot_file(&path).unwrap();
+        assert_eq!(prices["ETH"].len(), 2);
+        assert_eq!(prices["ETH"][1].ts, 1_700_000_060);
+        assert!(spreads.is_empty());
+    }
+
+    #[test]
+    fn legacy_v1_migrates_into_current_schema() {
+        let v1 = migrate_snapshot(r#"{"ETH":[[7.9,1700000000]]}"#).unwrap();
+        assert_eq!(v1.version, 1);
+        assert_eq!(v1.prices["ETH"], vec![(7.9, 1_700_000_000)]);
+
+        // Re-persisting a migrated snapshot writes the tagged format.
+        let samples: HashMap<String, VecDeque<PriceSample>> = v1
+            .into_samples()
+            .into_iter()
+            .map(|(sym, v)| (sym, v.into_iter().collect()))
+            .collect();
+        let upgraded = SnapshotV2::build(HistoryPriceFormat::LogF64, &samples, &HashMap::new());
+        let reread = migrate_snapshot(&serde_json::to_string(&upgraded).unwrap()).unwrap();
+        assert_eq!(reread.version, 2);
+        assert_eq!(reread.prices["ETH"], vec![(7.9, 1_700_000_000)]);
+    }
+
+    #[test]
+    fn tagged_v2_file_loads_with_spreads() {
+        let v2 = migrate_snapshot(
+            r#"{"_v":2,"prices":{"ETH":[[7.9,1700000000]]},"spread_histories":{"BTC/ETH":[0.5]}}"#,
+        )
+        .unwrap();
+        assert_eq!(v2.version, 2);
+        assert_eq!(v2.prices["ETH"].len(), 1);
+        assert_eq!(v2.spread_histories["BTC/ETH"], vec![0.5]);
+    }
+
+    #[test]
+    fn newer_or_malformed_snapshots_are_rejected() {
+        let err = migrate_snapshot(r#"{"_v":9,"prices":{}}"#).err().unwrap();
+        assert!(err.contains("newer"), "{}", err);
+        assert!(migrate_snapshot(r#"{"_v":"x"}"#).is_err());
+        assert!(migrate_snapshot(r#"{"ETH":"oops"}"#).is_err());
+        assert!(migrate_snapshot("not json").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        assert!(parse_snapshot_file(&path).is_none());
+        fs::write(&path, r#"{"_v":9,"prices":{}}"#).unwrap();
+        assert!(parse_snapshot_file(&path).is_none());
+    }
+}
@@ -0,0 +1,431 @@
+//! Persistence backends for the shared log-price history
+//! (`history_backend`). The engine always works on the in-memory
+//! `VecDeque<PriceSample>` window; a `HistoryStore` only decides how that
+//! window reaches disk and comes back after a restart.
+//!
+//! `JsonHistoryStore` is the historical path: the whole snapshot (prices
+//! and spread histories) rewritten to `history_file` every bar.
+//! `MmapHistoryStore` appends each new bar to a fixed-size memory-mapped
+//! ring per symbol, so a persist costs O(new bars) instead of a full
+//! rewrite; spread histories still go to `history_file` (with no prices).
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use memmap2::MmapMut;
+
+use super::config::{HistoryBackend, PairTradeConfig};
+use super::history_io;
+use super::stats::PriceSample;
+
+pub(super) trait HistoryStore: Send + Sync {
+    fn persist(
+        &mut self,
+        cfg: &PairTradeConfig,
+        history: &HashMap<String, VecDeque<PriceSample>>,
+        spread_histories: &HashMap<String, VecDeque<f64>>,
+    );
+
+    fn load(
+        &mut self,
+        cfg: &PairTradeConfig,
+        history: &mut HashMap<String, VecDeque<PriceSample>>,
+        spread_histories_out: &mut HashMap<String, VecDeque<f64>>,
+        now_ts: i64,
+        max_history_len: usize,
+    );
+}
+
+/// Build the store selected by `history_backend`. `capacity` is the ring
+/// size in bars for the mmap backend (the engine's max history length).
+pub(super) fn from_config(
+    cfg: &PairTradeConfig,
+    history_path: &Path,
+    capacity: usize,
+) -> Box<dyn HistoryStore> {
+    match cfg.history_backend {
+        HistoryBackend::Json => Box::new(JsonHistoryStore {
+            path: history_path.to_path_buf(),
+        }),
+        HistoryBackend::Mmap => Box::new(MmapHistoryStore::new(history_path, capacity)),
+    }
+}
+
+pub(super) struct JsonHistoryStore {
+    path: PathBuf,
+}
+
+impl HistoryStore for JsonHistoryStore {
+    fn persist(
+        &mut self,
+        cfg: &PairTradeConfig,
+        history: &HashMap<String, VecDeque<PriceSample>>,
+        spread_histories: &HashMap<String, VecDeque<f64>>,
+    ) {
+        history_io::persist_history_to_disk(cfg, history, spread_histories, &self.path);
+    }
+
+    fn load(
+        &mut self,
+        cfg: &PairTradeConfig,
+        history: &mut HashMap<String, VecDeque<PriceSample>>,
+        spread_histories_out: &mut HashMap<String, VecDeque<f64>>,
+        now_ts: i64,
+        max_history_len: usize,
+    ) {
+        history_io::load_history_from_disk(
+            cfg,
+            history,
+            spread_histories_out,
+            &self.path,
+            now_ts,
+            max_history_len,
+        );
+    }
+}
+
+pub(super) struct MmapHistoryStore {
+    snapshot_path: PathBuf,
+    ring_dir: PathBuf,
+    capacity: usize,
+    rings: HashMap<String, RingFile>,
+}
+
+impl MmapHistoryStore {
+    fn new(history_path: &Path, capacity: usize) -> Self {
+        let mut dir = history_path.as_os_str().to_owned();
+        dir.push(".rings");
+        Self {
+            snapshot_path: history_path.to_path_buf(),
+            ring_dir: PathBuf::from(dir),
+            capacity: capacity.max(1),
+            rings: HashMap::new(),
+        }
+    }
+
+    fn ring(&mut self, symbol: &str) -> io::Result<&mut RingFile> {
+        if !self.rings.contains_key(symbol) {
+            fs::create_dir_all(&self.ring_dir)?;
+            let path = self
+                .ring_dir
+                .join(format!("{}.ring", ring_file_stem(symbol)));
+            let ring = RingFile::open(&path, symbol, self.capacity)?;
+            self.rings.insert(symbol.to_string(), ring);
+        }
+        Ok(self.rings.get_mut(symbol).expect("ring inserted above"))
+    }
+}
+
+impl HistoryStore for MmapHistoryStore {
+    fn persist(
+        &mut self,
+        cfg: &PairTradeConfig,
+        history: &HashMap<String, VecDeque<PriceSample>>,
+        spread_histories: &HashMap<String, VecDeque<f64>>,
+    ) {
+        if cfg.disable_history_persist || cfg.backtest_mode {
+            return;
+        }
+        for (symbol, samples) in history {
+            let ring = match self.ring(symbol) {
+                Ok(ring) => ring,
+                Err(e) => {
+                    log::debug!("persist history ring {} open failed: {:?}", symbol, e);
+                    continue;
+                }
+            };
+            let last_ts = ring.last_ts();
+            let mut appended = false;
+            for sample in samples.iter().filter(|s| last_ts.is_none_or(|t| s.ts > t)) {
+                ring.append(sample.log_price, sample.ts);
+                appended = true;
+            }
+            if !appended {
+                continue;
+            }
+            if let Err(e) = ring.flush() {
+                log::debug!("persist history ring {} flush failed: {:?}", symbol, e);
+            }
+        }
+        history_io::persist_history_to_disk(
+            cfg,
+            &HashMap::new(),
+            spread_histories,
+            &self.snapshot_path,
+        );
+    }
+
+    fn load(
+        &mut self,
+        cfg: &PairTradeConfig,
+        history: &mut HashMap<String, VecDeque<PriceSample>>,
+        spread_histories_out: &mut HashMap<String, VecDeque<f64>>,
+        now_ts: i64,
+        max_history_len: usize,
+    ) {
+        if cfg.disable_history_persist || cfg.backtest_mode {
+            return;
+        }
+        let Ok(entries) = fs::read_dir(&self.ring_dir) else {
+            return;
+        };
+        let mut prices = HashMap::new();
+        for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
+            if path.extension().and_then(|e| e.to_str()) != Some("ring") {
+                continue;
+            }
+            match RingFile::read_records(&path) {
+                Ok((symbol, records)) => {
+                    let samples = records
+                        .into_iter()
+                        .map(|(log_price, ts)| PriceSample {
+                            log_price,
+                            ts,
+                            close: None,
+                        })
+                        .collect();
+                    prices.insert(symbol, samples);
+                }
+                Err(e) => log::debug!("load history ring {} failed: {:?}", path.display(), e),
+            }
+        }
+        let spreads = history_io::load_spread_histories(&self.snapshot_path);
+        history_io::apply_loaded_history(
+            cfg,
+            history,
+            spread_histories_out,
+            prices,
+            spreads,
+            now_ts,
+            max_history_len,
+        );
+    }
+}
+
+/// File name for `symbol`'s ring. Characters unsafe in a file name become
+/// `_`; when that changes the name, an FNV-1a hash of the full symbol is
+/// appended so distinct symbols never share a file. The header keeps the
+/// exact symbol, so loading does not depend on the stem.
+fn ring_file_stem(symbol: &str) -> String {
+    let stem: String = symbol
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if stem == symbol {
+        return stem;
+    }
+    let hash = symbol.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{}-{:016x}", stem, hash)
+}
+
+const RING_MAGIC: &[u8; 8] = b"PTRING2\0";
+/// magic, capacity (u64), records written (u64), symbol length (u64);
+/// the symbol's bytes follow, padded to 8, then the records.
+const FIXED_HEADER_LEN: usize = 8 + 8 + 8 + 8;
+/// log_price (f64) + ts (i64), little-endian.
+const RECORD_LEN: usize = 16;
+
+fn header_len(symbol_len: usize) -> usize {
+    FIXED_HEADER_LEN + symbol_len.next_multiple_of(8)
+}
+
+/// Fixed-capacity ring of `(log_price, ts)` records in one mapped file.
+/// A record is written before the header's write counter is bumped, so a
+/// process crash mid-append loses at most that record; `flush` syncs the
+/// map, so appends flushed before a machine crash survive it too.
+pub(super) struct RingFile {
+    map: MmapMut,
+    capacity: usize,
+    header_len: usize,
+}
+
+impl RingFile {
+    /// Open (or create) a ring of `capacity` records. A file with another
+    /// capacity is rebuilt, keeping its newest records.
+    pub(super) fn open(path: &Path, symbol: &str, capacity: usize) -> io::Result<Self> {
+        let capacity = capacity.max(1);
+        let header_len = header_len(symbol.len());
+        let reuse = Self::layout_of(path)? == Some((capacity, header_len));
+        let previous = if reuse {
+            Vec::new()
+        } else {
+            Self::read_records(path).map(|(_, r)| r).unwrap_or_default()
+        };
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(!reuse)
+            .open(path)?;
+        file.set_len((header_len + capacity * RECORD_LEN) as u64)?;
+        // SAFETY: ring files assume a single writer (see `history_backend`),
+        // and the file is sized above before mapping.
+        let map = unsafe { MmapMut::map_mut(&file)? };
+        let mut ring = Self {
+            map,
+            capacity,
+            header_len,
+        };
+        if !reuse {
+            let name = symbol.as_bytes();
+            ring.map[..8].copy_from_slice(RING_MAGIC);
+            ring.map[8..16].copy_from_slice(&(capacity as u64).to_le_bytes());
+            ring.set_written(0);
+            ring.map[24..32].copy_from_slice(&(name.len() as u64).to_le_bytes());
+            ring.map[FIXED_HEADER_LEN..FIXED_HEADER_LEN + name.len()].copy_from_slice(name);
+            let skip = previous.len().saturating_sub(capacity);
+            for (log_price, ts) in previous.into_iter().skip(skip) {
+                ring.append(log_price, ts);
+            }
+        }
+        Ok(ring)
+    }
+
+    /// `(capacity, header length)` of an existing ring; `None` when the
+    /// file is missing or not a ring of this format.
+    fn layout_of(path: &Path) -> io::Result<Option<(usize, usize)>> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        if bytes.len() < FIXED_HEADER_LEN || bytes[..8] != RING_MAGIC[..] {
+            return Ok(None);
+        }
+        let capacity = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) as usize;
+        let symbol_len = u64::from_le_bytes(bytes[24..32].try_into().unwrap()) as usize;
+        if symbol_len > bytes.len() {
+            return Ok(None);
+        }
+        Ok(Some((capacity, header_len(symbol_len))))
+    }
+
+    /// Records in append order plus the stored symbol, without mapping.
+    pub(super) fn read_records(path: &Path) -> io::Result<(String, Vec<(f64, i64)>)> {
+        let bytes = fs::read(path)?;
+        if bytes.len() < FIXED_HEADER_LEN || bytes[..8] != RING_MAGIC[..] {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a history ring",
+            ));
+        }
+        let capacity = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) as usize;
+        let written = u64::from_le_bytes(bytes[16..24].try_into().unwrap()) as usize;
+        let symbol_len = u64::from_le_bytes(bytes[24..32].try_into().unwrap()) as usize;
+        let header_len = header_len(symbol_len.min(bytes.len()));
+        if capacity == 0
+            || symbol_len > bytes.len()
+            || bytes.len() < header_len + capacity.saturating_mul(RECORD_LEN)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "truncated history ring",
+            ));
+        }
+        let symbol_bytes = &bytes[FIXED_HEADER_LEN..FIXED_HEADER_LEN + symbol_len];
+        let symbol = String::from_utf8_lossy(symbol_bytes).into_owned();
+        let count = written.min(capacity);
+        let records = (written - count..written)
+            .map(|i| {
+                let at = header_len + (i % capacity) * RECORD_LEN;
+                let log_price = f64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+                let ts = i64::from_le_bytes(bytes[at + 8..at + 16].try_into().unwrap());
+                (log_price, ts)
+            })
+            .collect();
+        Ok((symbol, records))
+    }
+
+    fn written(&self) -> usize {
+        u64::from_le_bytes(self.map[16..24].try_into().unwrap()) as usize
+    }
+
+    fn set_written(&mut self, written: usize) {
+        self.map[16..24].copy_from_slice(&(written as u64).to_le_bytes());
+    }
+
+    pub(super) fn last_ts(&self) -> Option<i64> {
+        let written = self.written();
+        if written == 0 {
+            return None;
+        }
+        let at = self.header_len + ((written - 1) % self.capacity) * RECORD_LEN;
+        Some(i64::from_le_bytes(
+            self.map[at + 8..at + 16].try_into().unwrap(),
+        ))
+    }
+
+    pub(super) fn append(&mut self, log_price: f64, ts: i64) {
+        let written = self.written();
+        let at = self.header_len + (written % self.capacity) * RECORD_LEN;
+        self.map[at..at + 8].copy_from_slice(&log_price.to_le_bytes());
+        self.map[at + 8..at + 16].copy_from_slice(&ts.to_le_bytes());
+        self.set_written(written + 1);
+    }
+
+    /// Write the mapped pages back and wait for them to reach disk.
+    pub(super) fn flush(&self) -> io::Result<()> {
+        self.map.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_wraps_and_survives_reopen_and_resize() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("BTC.ring");
+
+        let mut ring = RingFile::open(&path, "BTC", 3).unwrap();
+        assert_eq!(ring.last_ts(), None);
+        for ts in 1..=5 {
+            ring.append(ts as f64 * 0.5, ts);
+        }
+        ring.flush().unwrap();
+        assert_eq!(ring.last_ts(), Some(5));
+        drop(ring);
+
+        let (symbol, records) = RingFile::read_records(&path).unwrap();
+        assert_eq!(symbol, "BTC");
+        assert_eq!(records, vec![(1.5, 3), (2.0, 4), (2.5, 5)]);
+
+        // Reopening with the same capacity keeps the data; a smaller one
+        // keeps only the newest records.
+        assert_eq!(RingFile::open(&path, "BTC", 3).unwrap().last_ts(), Some(5));
+        drop(RingFile::open(&path, "BTC", 2).unwrap());
+        assert_eq!(
+            RingFile::read_records(&path).unwrap().1,
+            vec![(2.0, 4), (2.5, 5)]
+        );
+    }
+
+    #[test]
+    fn ring_keeps_full_symbol_and_distinct_stems() {
+        let dir = tempfile::tempdir().unwrap();
+        let symbol = "1000PEPE-USD-PERP/very-long-venue-market-name";
+        let path = dir.path().join(format!("{}.ring", ring_file_stem(symbol)));
+        let mut ring = RingFile::open(&path, symbol, 2).unwrap();
+        ring.append(1.0, 7);
+        drop(ring);
+
+        let (stored, records) = RingFile::read_records(&path).unwrap();
+        assert_eq!(stored, symbol);
+        assert_eq!(records, vec![(1.0, 7)]);
+        assert_eq!(RingFile::open(&path, symbol, 2).unwrap().last_ts(), Some(7));
+
+        assert_eq!(ring_file_stem("BTC-USD"), "BTC-USD");
+        assert_ne!(ring_file_stem("BTC/USD"), ring_file_stem("BTC_USD"));
+    }
+}
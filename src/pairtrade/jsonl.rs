@@ -0,0 +1,23 @@
+//! Shared append helper for the jsonl side files (eval history, PnL log,
+//! plan trace, rejected entries).
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+
+use serde::Serialize;
+
+/// Append `records` to `path`, one json line each, in a single write so a
+/// batch is never interleaved with another writer's lines.
+pub(super) fn append<T: Serialize>(path: &Path, records: &[T]) -> io::Result<()> {
+    if records.is_empty() {
+        return Ok(());
+    }
+    let mut lines = String::new();
+    for record in records {
+        lines.push_str(&serde_json::to_string(record).map_err(io::Error::other)?);
+        lines.push('\n');
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lines.as_bytes())
+}
@@ -1,3 +1,5 @@
+use super::stats::clamp_beta;
+
 /// Scalar Kalman filter for online hedge-ratio (beta) estimation.
 ///
 /// Model
@@ -14,6 +16,7 @@ pub(super) struct KalmanBeta {
     q: f64,            // process noise
     r: f64,            // observation noise
     updates: u64,
+    allow_negative: bool,
 }
 
 impl KalmanBeta {
@@ -24,9 +27,16 @@ impl KalmanBeta {
             q,
             r,
             updates: 0,
+            allow_negative: false,
         }
     }
 
+    /// Keep the estimate's sign when it crosses zero (`allow_negative_beta`).
+    pub(super) fn with_negative_beta(mut self, allow: bool) -> Self {
+        self.allow_negative = allow;
+        self
+    }
+
     /// Feed one observation pair (dx, dy) where
     ///   dx = log(quote_t) - log(quote_{t-1})
     ///   dy = log(base_t)  - log(base_{t-1})
@@ -47,7 +57,7 @@ impl KalmanBeta {
         let k = p_pred * dx / s;
         self.beta += k * innovation;
         self.p = (1.0 - k * dx) * p_pred;
-        self.beta = self.beta.clamp(0.1, 10.0);
+        self.beta = clamp_beta(self.beta, self.allow_negative);
         self.updates += 1;
         self.beta
     }
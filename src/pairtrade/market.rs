@@ -1,9 +1,14 @@
 //! Market data snapshot type and small per-snapshot helpers.
 
-use rust_decimal::prelude::ToPrimitive;
+use std::collections::HashMap;
+
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use dex_connector::{OrderBookLevel, OrderBookSnapshot, OrderSide};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
+use super::config::{MatchingModel, PriceSource};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(super) struct SymbolSnapshot {
     pub(super) price: Decimal,
@@ -21,6 +26,149 @@ pub(super) struct SymbolSnapshot {
     /// processes (pairtrade#4).
     #[serde(default)]
     pub(super) exchange_ts: Option<i64>,
+    /// Book depth beyond top-of-book. Empty unless `orderbook_depth > 1`.
+    #[serde(default)]
+    pub(super) depth: BookDepth,
+}
+
+/// Aggregated order-book depth for one symbol. `bid_size` / `ask_size` are
+/// the cumulative sizes within `orderbook_depth_band_bps` of the touch; the
+/// raw levels (best first) are kept for fill-price estimation but not dumped.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(super) struct BookDepth {
+    pub(super) bid_size: Decimal,
+    pub(super) ask_size: Decimal,
+    #[serde(skip)]
+    pub(super) bids: Vec<(Decimal, Decimal)>,
+    #[serde(skip)]
+    pub(super) asks: Vec<(Decimal, Decimal)>,
+}
+
+impl BookDepth {
+    /// Depth for a fetched book. Empty for top-of-book fetches
+    /// (`orderbook_depth <= 1`), where `bid_size` / `ask_size` already say it all.
+    pub(super) fn from_book(book: &OrderBookSnapshot, orderbook_depth: usize, band_bps: f64) -> Self {
+        if orderbook_depth <= 1 {
+            return Self::default();
+        }
+        let levels = |side: &[OrderBookLevel]| -> Vec<(Decimal, Decimal)> {
+            side.iter()
+                .take(orderbook_depth)
+                .map(|l| (l.price, l.size))
+                .collect()
+        };
+        Self::from_levels(levels(&book.bids), levels(&book.asks), band_bps)
+    }
+
+    pub(super) fn from_levels(
+        bids: Vec<(Decimal, Decimal)>,
+        asks: Vec<(Decimal, Decimal)>,
+        band_bps: f64,
+    ) -> Self {
+        Self {
+            bid_size: size_within_bps(&bids, band_bps),
+            ask_size: size_within_bps(&asks, band_bps),
+            bids,
+            asks,
+        }
+    }
+
+    /// Estimated average fill for a taker order of `notional`: buys walk the
+    /// asks, sells the bids. `None` without fetched depth.
+    pub(super) fn fill_price(&self, is_buy: bool, notional: Decimal) -> Option<Decimal> {
+        let levels = if is_buy { &self.asks } else { &self.bids };
+        estimate_fill_price(levels, notional)
+    }
+}
+
+/// Cumulative size of the levels priced within `band_bps` of the first
+/// (best) level. The best level always counts.
+pub(super) fn size_within_bps(levels: &[(Decimal, Decimal)], band_bps: f64) -> Decimal {
+    let Some(&(best, _)) = levels.first() else {
+        return Decimal::ZERO;
+    };
+    let band = Decimal::from_f64(band_bps.max(0.0) / 10_000.0).unwrap_or(Decimal::ZERO);
+    let tolerance = best.abs() * band;
+    levels
+        .iter()
+        .take_while(|(price, _)| (*price - best).abs() <= tolerance)
+        .map(|(_, size)| *size)
+        .sum()
+}
+
+/// Volume-weighted price for spending `notional` against `levels` (best
+/// first). Any notional left once the book is exhausted is assumed to fill at
+/// the last level. `None` when the side is empty or `notional` is not positive.
+pub(super) fn estimate_fill_price(levels: &[(Decimal, Decimal)], notional: Decimal) -> Option<Decimal> {
+    let last_price = levels.last()?.0;
+    if notional <= Decimal::ZERO || last_price <= Decimal::ZERO {
+        return None;
+    }
+    let mut remaining = notional;
+    let mut qty = Decimal::ZERO;
+    for &(price, size) in levels {
+        if remaining <= Decimal::ZERO {
+            break;
+        }
+        if price <= Decimal::ZERO {
+            continue;
+        }
+        let take = (size * price).min(remaining);
+        qty += take / price;
+        remaining -= take;
+    }
+    if remaining > Decimal::ZERO {
+        qty += remaining / last_price;
+    }
+    if qty <= Decimal::ZERO {
+        return None;
+    }
+    Some(notional / qty)
+}
+
+impl SymbolSnapshot {
+    /// Top of book is crossed or locked (bid >= ask), i.e. a momentarily
+    /// broken snapshot whose quotes should not be priced off.
+    pub(super) fn book_crossed(&self) -> bool {
+        matches!((self.bid_price, self.ask_price), (Some(bid), Some(ask)) if bid >= ask)
+    }
+
+    /// The requested quote, falling back to the ticker price when that side
+    /// of the book is missing or the book is crossed.
+    pub(super) fn price_from(&self, source: PriceSource) -> Decimal {
+        if self.book_crossed() {
+            return self.price;
+        }
+        let quote = match source {
+            PriceSource::Last => None,
+            PriceSource::Mid => self
+                .bid_price
+                .zip(self.ask_price)
+                .map(|(bid, ask)| (bid + ask) / Decimal::TWO),
+            PriceSource::Bid => self.bid_price,
+            PriceSource::Ask => self.ask_price,
+        };
+        quote.filter(|p| *p > Decimal::ZERO).unwrap_or(self.price)
+    }
+
+    /// Bare snapshot at `price` (no book, funding or venue limits) for
+    /// tests; set other fields with struct update syntax.
+    #[cfg(test)]
+    pub(super) fn test_at(price: Decimal) -> Self {
+        Self {
+            price,
+            funding_rate: Decimal::ZERO,
+            bid_price: None,
+            ask_price: None,
+            bid_size: Decimal::ZERO,
+            ask_size: Decimal::ZERO,
+            min_order: None,
+            min_tick: None,
+            size_decimals: None,
+            exchange_ts: None,
+            depth: BookDepth::default(),
+        }
+    }
 }
 
 pub(super) fn net_funding_for_direction(
@@ -37,8 +185,223 @@ pub(super) fn net_funding_for_direction(
     }
 }
 
+/// Funding carry per hour, in USD, of signed notional exposures by symbol,
+/// with the `net_funding_for_direction` sign (long legs count `+rate`,
+/// short legs `-rate`, rates treated as daily). Symbols without a snapshot
+/// contribute nothing.
+pub(super) fn book_funding_per_hour(
+    exposure: &HashMap<String, f64>,
+    prices: &HashMap<String, SymbolSnapshot>,
+) -> f64 {
+    exposure
+        .iter()
+        .filter_map(|(symbol, usd)| {
+            let rate = prices.get(symbol)?.funding_rate.to_f64()?;
+            Some(usd * rate / 24.0)
+        })
+        .sum()
+}
+
+/// One leg's funding rate per hour. With a known schedule the rate is
+/// spread over `funding_interval_secs`; otherwise it is treated as daily,
+/// matching `net_funding_for_direction`.
+pub(super) fn funding_per_hour(p: &SymbolSnapshot, funding_interval_secs: u64) -> f64 {
+    let rate = p.funding_rate.to_f64().unwrap_or(0.0);
+    if funding_interval_secs > 0 {
+        rate * 3600.0 / funding_interval_secs as f64
+    } else {
+        rate / 24.0
+    }
+}
+
+/// Funding cadence of the venue behind `dex_name`. The connector trait
+/// does not report a schedule, so it is keyed by venue: Hyperliquid,
+/// Lighter and Extended all settle hourly on the hour. `None` for other
+/// venues, which fall back to `funding_interval_secs`.
+pub(super) fn venue_funding_interval_secs(dex_name: &str) -> Option<u64> {
+    let dex = dex_name.to_ascii_lowercase();
+    ["hyperliquid", "lighter", "extended"]
+        .iter()
+        .any(|venue| dex.contains(venue))
+        .then_some(3_600)
+}
+
+/// Top-of-book spread `(ask - bid) / mid` in bps. `None` without both
+/// quotes or on a crossed / non-positive book.
+pub(super) fn book_spread_bps(p: &SymbolSnapshot) -> Option<f64> {
+    let (bid, ask) = p.bid_price.zip(p.ask_price)?;
+    if bid <= Decimal::ZERO || ask < bid {
+        return None;
+    }
+    let mid = (bid + ask) / Decimal::TWO;
+    ((ask - bid) / mid).to_f64().map(|ratio| ratio * 10_000.0)
+}
+
+/// Simulated paper fill for a passive order of `qty` on `side` within one
+/// tick. Assumptions, kept deliberately simple:
+/// - the displayed size on the order's own side of the book (bid for a buy,
+///   ask for a sell) is the resting queue the order competes with;
+/// - the contra touch size is the flow that trades against that price level
+///   during the tick;
+/// - `PriceTime` puts the order at the back of the queue, so it only gets
+///   contra size left after the queue is cleared; `ProRata` gives it a
+///   `qty / (qty + queue)` share of the contra size.
+///
+/// Depth beyond the touch is ignored. The result is capped at `qty`, and
+/// `Full` always returns `qty`.
+pub(super) fn simulated_fill_qty(
+    model: MatchingModel,
+    p: &SymbolSnapshot,
+    side: OrderSide,
+    qty: Decimal,
+) -> Decimal {
+    let (queue, contra) = match side {
+        OrderSide::Long => (p.bid_size, p.ask_size),
+        OrderSide::Short => (p.ask_size, p.bid_size),
+    };
+    let filled = match model {
+        MatchingModel::Full => qty,
+        MatchingModel::PriceTime => (contra - queue).max(Decimal::ZERO),
+        MatchingModel::ProRata => {
+            if qty + queue <= Decimal::ZERO {
+                Decimal::ZERO
+            } else {
+                contra * qty / (qty + queue)
+            }
+        }
+    };
+    filled.min(qty).max(Decimal::ZERO)
+}
+
 pub(super) fn liquidity_score(p1: &SymbolSnapshot, p2: &SymbolSnapshot) -> f64 {
-    let s1 = p1.bid_size.min(p1.ask_size).to_f64().unwrap_or(0.0);
-    let s2 = p2.bid_size.min(p2.ask_size).to_f64().unwrap_or(0.0);
-    (s1 + s2).max(0.0)
+    (side_liquidity(p1) + side_liquidity(p2)).max(0.0)
+}
+
+/// USD notional resting on the thinner side of the book.
+pub(super) fn book_notional_usd(p: &SymbolSnapshot) -> f64 {
+    side_liquidity(p) * p.price.to_f64().unwrap_or(0.0)
+}
+
+/// Thinner side of the book, using the in-band depth when it was fetched.
+fn side_liquidity(p: &SymbolSnapshot) -> f64 {
+    let bid = p.bid_size.max(p.depth.bid_size);
+    let ask = p.ask_size.max(p.depth.ask_size);
+    bid.min(ask).to_f64().unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(v: &str) -> Decimal {
+        v.parse().unwrap()
+    }
+
+    #[test]
+    fn known_venues_report_an_hourly_funding_schedule() {
+        assert_eq!(venue_funding_interval_secs("hyperliquid"), Some(3_600));
+        assert_eq!(venue_funding_interval_secs("Lighter"), Some(3_600));
+        assert_eq!(venue_funding_interval_secs("extended_testnet"), Some(3_600));
+        assert_eq!(venue_funding_interval_secs("test"), None);
+    }
+
+    #[test]
+    fn book_funding_nets_opposite_legs() {
+        let snap = |rate: &str| SymbolSnapshot {
+            funding_rate: d(rate),
+            ..SymbolSnapshot::test_at(d("100"))
+        };
+        let prices: HashMap<String, SymbolSnapshot> =
+            [("A".to_string(), snap("0.024")), ("B".to_string(), snap("0.048"))].into();
+        let mut exposure: HashMap<String, f64> =
+            [("A".to_string(), 1000.0), ("B".to_string(), -500.0)].into();
+        assert!((book_funding_per_hour(&exposure, &prices) - 0.0).abs() < 1e-12);
+        // A long leg offsetting an existing short drops B's carry entirely.
+        *exposure.get_mut("B").unwrap() += 500.0;
+        assert!((book_funding_per_hour(&exposure, &prices) - 1.0).abs() < 1e-12);
+        exposure.insert("C".to_string(), 1e6);
+        assert!((book_funding_per_hour(&exposure, &prices) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn price_from_picks_quote_and_falls_back_to_last() {
+        let mut snap = SymbolSnapshot {
+            bid_price: Some(d("99")),
+            ask_price: Some(d("101")),
+            ..SymbolSnapshot::test_at(d("100.5"))
+        };
+        assert_eq!(snap.price_from(PriceSource::Last), d("100.5"));
+        assert_eq!(snap.price_from(PriceSource::Mid), d("100"));
+        assert_eq!(snap.price_from(PriceSource::Bid), d("99"));
+        assert_eq!(snap.price_from(PriceSource::Ask), d("101"));
+
+        snap.bid_price = Some(d("101"));
+        assert!(snap.book_crossed());
+        assert_eq!(snap.price_from(PriceSource::Bid), d("100.5"));
+        assert_eq!(snap.price_from(PriceSource::Mid), d("100.5"));
+        snap.bid_price = Some(d("99"));
+
+        snap.ask_price = None;
+        assert!(!snap.book_crossed());
+        assert_eq!(snap.price_from(PriceSource::Mid), d("100.5"));
+        assert_eq!(snap.price_from(PriceSource::Ask), d("100.5"));
+
+        // Thinner side × price; in-band depth counts when larger.
+        snap.price = d("100");
+        snap.bid_size = d("2");
+        snap.ask_size = d("0.5");
+        assert!((book_notional_usd(&snap) - 50.0).abs() < 1e-9);
+        snap.depth.ask_size = d("3");
+        assert!((book_notional_usd(&snap) - 200.0).abs() < 1e-9);
+
+        // 8h schedule: 0.0008 per interval → 0.0001 per hour.
+        snap.funding_rate = d("-0.0008");
+        assert!((funding_per_hour(&snap, 8 * 3600) + 0.0001).abs() < 1e-12);
+        assert!((funding_per_hour(&snap, 0) + 0.0008 / 24.0).abs() < 1e-12);
+
+        // 99 / 101 around a 100 mid → 200 bps; one-sided book → None.
+        snap.ask_price = Some(d("101"));
+        assert!((book_spread_bps(&snap).unwrap() - 200.0).abs() < 1e-9);
+        snap.bid_price = None;
+        assert_eq!(book_spread_bps(&snap), None);
+    }
+
+    #[test]
+    fn simulated_fill_follows_matching_model() {
+        let snap = SymbolSnapshot {
+            bid_price: Some(d("99")),
+            ask_price: Some(d("101")),
+            bid_size: d("3"),
+            ask_size: d("5"),
+            ..SymbolSnapshot::test_at(d("100"))
+        };
+        let fill = |model, side, qty| simulated_fill_qty(model, &snap, side, d(qty));
+        assert_eq!(fill(MatchingModel::Full, OrderSide::Long, "10"), d("10"));
+        // Buy queues behind 3 on the bid; 5 contra leaves 2.
+        assert_eq!(fill(MatchingModel::PriceTime, OrderSide::Long, "10"), d("2"));
+        assert_eq!(fill(MatchingModel::PriceTime, OrderSide::Long, "1"), d("1"));
+        // Sell queues behind 5 on the ask; only 3 contra → nothing.
+        assert_eq!(fill(MatchingModel::PriceTime, OrderSide::Short, "1"), d("0"));
+        // Pro-rata: 2 / (2 + 3) of 5 contra.
+        assert_eq!(fill(MatchingModel::ProRata, OrderSide::Long, "2"), d("2"));
+        assert_eq!(fill(MatchingModel::ProRata, OrderSide::Short, "5"), d("1.5"));
+    }
+
+    #[test]
+    fn depth_band_and_fill_estimate_walk_the_book() {
+        let asks = vec![(d("100"), d("1")), (d("100.05"), d("2")), (d("101"), d("5"))];
+        // 10 bps of 100 = 0.1 → first two levels only.
+        assert_eq!(size_within_bps(&asks, 10.0), d("3"));
+        assert_eq!(size_within_bps(&asks, 0.0), d("1"));
+
+        // Within the best level: fills at the touch.
+        assert_eq!(estimate_fill_price(&asks, d("50")), Some(d("100")));
+        // 100 at 100 + 200.1 at 100.05 → fill between the two levels.
+        let vwap = estimate_fill_price(&asks, d("300.1")).unwrap();
+        assert!(vwap > d("100") && vwap < d("100.05"));
+        // Past the book: remainder assumed at the last level.
+        let deep = estimate_fill_price(&asks, d("10000")).unwrap();
+        assert!(deep > d("100.05") && deep <= d("101"));
+        assert_eq!(estimate_fill_price(&[], d("10")), None);
+    }
 }
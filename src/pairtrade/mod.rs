@@ -1,47 +1,81 @@
 use anyhow::{anyhow, Context, Result};
 use chrono::Utc;
-use dex_connector::{DexConnector, DexError, PositionSnapshot};
-use rust_decimal::prelude::ToPrimitive;
+use dex_connector::{DexConnector, DexError, PositionSnapshot, TickerResponse};
+use rand::Rng;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
 use rust_decimal::Decimal;
 use serde::Serialize;
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::time::{sleep, Duration};
 
-use crate::email_client::EmailClient;
+use crate::notifier::{self, Severity};
 use crate::ports::replay_dex::ReplayConnector;
+use crate::rate_limit_notifier::rate_limit_hit_count;
 
 mod backtest;
 mod bar;
+mod clock;
 mod config;
 mod data_dump;
 mod defaults;
 mod entry;
+mod eval_history;
 mod exit;
+mod forensics;
 mod history_io;
+mod history_store;
+mod jsonl;
 mod kalman;
 mod market;
 mod order_pricing;
+mod pacing;
 mod pair_eval;
+mod plan_trace;
 mod pnl_log;
 mod regime;
+mod rejected_entries;
+mod rejection;
 mod risk_io;
+mod schedule;
 mod sizing;
 mod state;
 mod stats;
 mod status;
+mod trade_stats;
 mod util;
 use bar::BarBuilder;
-use entry::{entry_z_for_pair, should_enter};
-use exit::{compute_pnl, exit_reason};
-use market::{liquidity_score, net_funding_for_direction, SymbolSnapshot};
+use clock::{Clock, SystemClock};
+use entry::{
+    directional_eligibility, entry_threshold, entry_z_alpha, entry_z_for_pair,
+    extreme_funding_blocks, should_enter, spread_direction_for_z, wide_book_blocks,
+};
+use exit::{
+    beta_drift_breached, compute_pnl, exit_reason, partial_take_profit_hit, scale_in_due,
+    scale_in_remainder,
+};
+use history_store::HistoryStore;
+use market::{
+    book_funding_per_hour, book_notional_usd, liquidity_score, net_funding_for_direction,
+    venue_funding_interval_secs, BookDepth, SymbolSnapshot,
+};
 use pair_eval::PairEvaluation;
 use pnl_log::{PnlLogRecord, PnlLogger};
-use stats::{regression_beta, spread_slope_sigma, tail_samples, PriceSample};
-pub use config::{PairTradeConfig, WarmStartMode};
+use rejection::{classify_rejection, is_unknown_symbol_error, RejectionKind};
+use stats::{
+    hedge_beta, outlier_log_return, spread_slope_sigma, standardize, standardize_scale,
+    standardized_latest, tail_samples, PriceSample,
+};
+pub use trade_stats::{TradeStats, TradeStatsBook};
+pub use config::{
+    BarSource, BetaDriftAction, HistoryBackend, HistoryPriceFormat, MatchingModel, PairTradeConfig,
+    MetricsLogFormat, MissedTickBehavior, PriceSource, SharedSymbolPolicy, TradeStatsReset,
+    WarmStartMode, ZStatMode,
+};
+pub use schedule::NoTradeWindow;
 use config::PairParams;
 use config::PairSpec;
 use defaults::*;
@@ -50,9 +84,10 @@ use state::{
     PendingStatus, Position, PositionDirection,
 };
 use status::{
-    PairTradeStats, ShutdownPosition, ShutdownStatus, StatusReporter,
+    AgingPosition, PairTradeStats, ShutdownPosition, ShutdownStatus, SizeAdjustment, StartupCloseStatus,
+    StatusReporter,
 };
-use util::{enforce_post_only_passive, round_price_by_tick, tail_std};
+use util::{enforce_post_only_passive, first_non_finite, round_price_by_tick, tail_std};
 
 /// Max age of the per-instance equity cache before `refresh_equity_if_needed`
 /// fetches a fresh value from the exchange. Now a low-frequency dashboard tick:
@@ -62,6 +97,23 @@ use util::{enforce_post_only_passive, round_price_by_tick, tail_std};
 /// the entry branch of `step()`), independent of this cache. See
 /// bot-strategy#156.
 const EQUITY_REFRESH_CACHE_SECS: u64 = 1800;
+const SELECT_LOG_INTERVAL_SECS: u64 = 300;
+/// `max_retries` passed to `DexConnector::restart` by the automatic
+/// restart (`auto_restart_after_failures`).
+const CONNECTOR_RESTART_MAX_RETRIES: i32 = 3;
+/// Minimum spacing between /account REST calls across all instances.
+/// Lighter enforces a per-IP short-window rate-limit on /account the
+/// sidecar can't see; empirically ~1 call per 5s survives. Shared
+/// across instances so step() only waits when a recent call exists,
+/// not unconditionally on every inst_idx > 0. See bot-strategy#122.
+const MIN_ACCOUNT_SPACING: Duration = Duration::from_millis(5_500);
+/// Ticker attempts per symbol in `validate_universe` before a transient
+/// error is given up on, and the first backoff (doubled per retry).
+const UNIVERSE_TICKER_ATTEMPTS: u32 = 3;
+#[cfg(not(test))]
+const UNIVERSE_TICKER_BACKOFF_MS: u64 = 500;
+#[cfg(test)]
+const UNIVERSE_TICKER_BACKOFF_MS: u64 = 1;
 
 /// Sentinel file that, when present, blocks all new entries without
 /// requiring `systemctl stop`. Existing positions still exit normally.
@@ -90,6 +142,30 @@ struct StrategyInstance {
     /// Per-instance live equity from the instance's connector.
     equity_cache: f64,
     last_equity_fetch: Option<Instant>,
+    /// Consecutive `get_balance` failures since the last successful fetch.
+    /// Reset to zero on success; compared against
+    /// `max_balance_fetch_failures` by `equity_guard_blocks`.
+    balance_fetch_failures: u32,
+    /// Consecutive cycles run while the last balance fetch had failed, i.e.
+    /// on fallback equity. Compared against `max_equity_fallback_cycles`.
+    equity_fallback_cycles: u32,
+    /// True while the equity guard blocks new entries. Used for
+    /// transition logging only; the gate itself is recomputed every tick.
+    equity_guard_halted: bool,
+    /// Set when an entry order is rejected for insufficient margin; new
+    /// entries stay blocked until this timestamp.
+    margin_block_until_ts: Option<i64>,
+    /// Open orders on the instance's account at the last open-order audit,
+    /// and whether that count exceeded `max_open_orders` (new entries stay
+    /// blocked until an audit comes back under the cap).
+    open_order_count: usize,
+    open_orders_blocked: bool,
+    last_open_order_audit: Option<Instant>,
+    /// Last `delta_rebalance_enabled` check.
+    last_delta_rebalance: Option<Instant>,
+    /// Delta-rebalance orders not yet confirmed filled, each with the
+    /// `(pair key, leg B size)` bookings it settles.
+    delta_rebalance_orders: Vec<(PendingLeg, Vec<(String, Decimal)>)>,
     /// Per-strategy fixed equity reference from the YAML
     /// `equity_usd_reference`. Used as the base for risk thresholds
     /// (daily DD, exit risk_budget) AND position sizing so each
@@ -129,11 +205,20 @@ struct StrategyInstance {
     session_halted: bool,
     session_halt_reason: Option<String>,
     session_halt_ts: Option<i64>,
+    /// Entry time per pair as read back from `risk_state.json`; consulted
+    /// under `recover_entry_time_from_risk_state` when the exchange reports a
+    /// hedged pair the engine holds no position for.
+    persisted_entry_ts: HashMap<String, i64>,
     total_trades: u64,
     total_wins: u64,
     total_pnl: f64,
     peak_pnl: f64,
     max_dd: f64,
+    /// Per-pair / aggregate trade stats, reset per `trade_stats_reset`.
+    trade_stats: TradeStatsBook,
+    /// Candidate/winner signature of the last `[SELECT]` log and when it was
+    /// emitted; the line repeats only on change or after `SELECT_LOG_INTERVAL_SECS`.
+    last_select_log: Option<(String, Instant)>,
     /// Per-instance pair parameter overrides. Built at `new_inner` time by
     /// overlaying the strategy's `exit_z` / `stop_loss_z` / `max_loss_r_mult`
     /// on top of the engine-wide defaults. Look up via
@@ -152,6 +237,15 @@ pub struct PairTradeEngine {
     last_ob_warn: HashMap<String, Instant>,
     last_ticker_warn: HashMap<String, Instant>,
     last_position_warn: HashMap<String, Instant>,
+    /// Pairs whose last tick closed only one leg's bar; `[EVAL]` logs the
+    /// one-sided close once per streak.
+    one_sided_bar_pairs: HashSet<String>,
+    /// Consecutive closed bars per symbol dropped by `max_bar_log_return`;
+    /// the `OUTLIER_BARS_BEFORE_ACCEPT`th is taken as a real level shift.
+    outlier_bar_streaks: HashMap<String, u32>,
+    // Per (log category, symbol or pair) emission counters behind the
+    // log_*_every_n_ticks sampling knobs.
+    log_sample_counts: HashMap<(&'static str, String), u64>,
     min_order_warned: HashSet<String>,
     min_tick_warned: HashSet<String>,
     positions_ready: bool,
@@ -162,10 +256,19 @@ pub struct PairTradeEngine {
     /// step() for the full span even when no recent call had been made.
     /// See bot-strategy#122.
     last_account_rest_call: Option<Instant>,
-    history_path: PathBuf,
+    /// Consecutive price-fetch / position-sync failures and the last
+    /// automatic connector restart (`auto_restart_after_failures`).
+    connector_failures: u32,
+    last_connector_restart: Option<Instant>,
+    /// Persistence backend for `history` (`history_backend`).
+    history_store: Box<dyn HistoryStore>,
     /// Path for the risk-state persistence file (circuit breaker counters
-    /// + cool-down deadline). Sibling of `history_path`. See bot-strategy#185.
+    /// + cool-down deadline). Sibling of `history_file`. See bot-strategy#185.
     risk_state_path: PathBuf,
+    /// Per-engine directory holding `risk_state_path` in tests; removed
+    /// when the engine drops.
+    #[cfg(test)]
+    _test_state_dir: Option<tempfile::TempDir>,
     /// Cached result of the most recent `KILL_SWITCH_PATH` existence check.
     /// Refreshed at the top of every `step_shared` tick. True blocks new
     /// entries across all instances.
@@ -177,6 +280,63 @@ pub struct PairTradeEngine {
     ///   - existing exit logic (exit_z / stop_loss_z / force_close_secs) runs normally
     ///   - live loop exits as soon as open_positions is empty, or after shutdown_grace_secs
     shutdown_pending: bool,
+    /// Pair keys dropped from the universe by a SIGHUP reload while some
+    /// instance still held a position or pending order on them. They stay
+    /// in `cfg.universe` so the normal exit path can flatten them, are
+    /// blocked from new entries, and are removed by `retire_flat_pairs`
+    /// once flat everywhere.
+    retiring_pairs: HashSet<String>,
+    /// Symbols of the startup universe, i.e. the token list the connectors
+    /// were built with. A reload cannot add pairs outside it.
+    connector_symbols: HashSet<String>,
+    /// Set once the unknown-funding-schedule warning has been logged.
+    funding_schedule_warned: bool,
+    /// Symbols whose dust remnant has already been swept (or accepted).
+    /// Cleared once the remnant no longer shows up, so each remnant gets a
+    /// single attempt.
+    dust_sweep_attempted: HashSet<String>,
+    /// Spec of the `no_trade_windows` entry currently blocking entries, so
+    /// `[SCHEDULE]` logs only on transitions.
+    no_trade_window: Option<String>,
+    /// Time source for order timeouts, cooldowns, caches and log rate
+    /// limits. `SystemClock` outside tests.
+    clock: Arc<dyn Clock>,
+    /// Legs re-placed by `reissue_partial_legs` during the current `step`;
+    /// capped at `max_reissued_legs_per_cycle`.
+    reissued_legs_this_cycle: usize,
+    /// `position_audit_interval_secs` bookkeeping: last audit time and the
+    /// number of consecutive audits that found a mismatch.
+    last_position_audit: Option<Instant>,
+    position_audit_strikes: u32,
+    /// Post-only orders that fell back to taker since startup, by exit
+    /// reason. Reported with `[METRICS]`.
+    taker_fallbacks: BTreeMap<&'static str, u64>,
+    /// Orders whose connector-reported size differed from the requested
+    /// one, per symbol, since startup. Reported with `[METRICS]` and, with
+    /// `status_size_adjustments`, in status.json.
+    size_adjustments: BTreeMap<String, SizeAdjustment>,
+    /// Adverse slippage (bps) of the last `REALIZED_SLIPPAGE_WINDOW` fully
+    /// filled legs per symbol, and the symbols currently over
+    /// `max_realized_slippage_bps` with when they went over.
+    realized_slippage: HashMap<String, VecDeque<f64>>,
+    slippage_blocked: HashMap<String, Instant>,
+}
+
+/// Position column of the detailed metrics log.
+fn metrics_position_label(state: &PairState) -> &'static str {
+    state
+        .position
+        .as_ref()
+        .map_or("flat", |pos| pnl_log::direction_label(pos.direction))
+}
+
+/// Pending-order column of the detailed metrics log.
+fn metrics_pending_label(state: &PairState) -> &'static str {
+    match (&state.pending_entry, &state.pending_exit) {
+        (Some(_), _) => "entry",
+        (None, Some(_)) => "exit",
+        (None, None) => "none",
+    }
 }
 
 struct PlannedAction {
@@ -235,24 +395,31 @@ impl PairTradeEngine {
         for pair in &cfg.universe {
             history.insert(pair.base.clone(), VecDeque::new());
             history.insert(pair.quote.clone(), VecDeque::new());
-            bar_builders.insert(pair.base.clone(), BarBuilder::new(cfg.trading_period_secs));
-            bar_builders.insert(pair.quote.clone(), BarBuilder::new(cfg.trading_period_secs));
+            for sym in [&pair.base, &pair.quote] {
+                bar_builders.insert(
+                    sym.clone(),
+                    BarBuilder::new(cfg.trading_period_secs).with_max_span_mult(cfg.max_bar_span_mult),
+                );
+            }
         }
 
         let history_path = PathBuf::from(cfg.history_file.as_str());
+        let history_store =
+            history_store::from_config(&cfg, &history_path, max_history_len_for(&cfg));
         let risk_state_path = risk_state_path_for(&history_path);
 
         let min_order_warned = HashSet::new();
         let min_tick_warned = HashSet::new();
         let data_dump_writer = if cfg.enable_data_dump {
             let file_path = cfg.data_dump_file.as_ref().unwrap(); // is_none checked in from_env
-            Some(data_dump::RotatingDumpWriter::new(file_path)?)
+            Some(data_dump::RotatingDumpWriter::new(file_path, cfg.data_dump_compress)?)
         } else {
             None
         };
 
-        let backtest_mode = cfg.backtest_mode;
-        let _ = backtest_mode;
+        // Backtest skips the position sync unless `backtest_full_sync` asks
+        // for the live path, which starts out not ready.
+        let backtest_mode = cfg.backtest_mode && !cfg.backtest_full_sync;
         let multi_instance = cfg.strategies.len() > 1;
 
         // Build one StrategyInstance per entry in cfg.strategies. For legacy
@@ -268,34 +435,12 @@ impl PairTradeEngine {
             // top of the engine's default_pair_params and per-pair overrides
             // so each variant evaluates z-exits at its own thresholds.
             let mut inst_default = cfg.default_pair_params.clone();
-            inst_default.exit_z = strategy.exit_z;
-            inst_default.stop_loss_z = strategy.stop_loss_z;
-            inst_default.max_loss_r_mult = strategy.max_loss_r_mult;
-            if let Some(fc) = strategy.force_close_time_secs {
-                inst_default.force_close_secs = fc;
-            }
-            if let Some(ref w) = strategy.mtf_windows {
-                inst_default.mtf_windows = w.clone();
-            }
-            if let Some(z) = strategy.mtf_z_min {
-                inst_default.mtf_z_min = z;
-            }
+            strategy.overlay_pair_params(&mut inst_default);
 
             let mut inst_pair_params: HashMap<String, PairParams> = HashMap::new();
             for (k, v) in cfg.pair_params.iter() {
                 let mut pp = v.clone();
-                pp.exit_z = strategy.exit_z;
-                pp.stop_loss_z = strategy.stop_loss_z;
-                pp.max_loss_r_mult = strategy.max_loss_r_mult;
-                if let Some(fc) = strategy.force_close_time_secs {
-                    pp.force_close_secs = fc;
-                }
-                if let Some(ref w) = strategy.mtf_windows {
-                    pp.mtf_windows = w.clone();
-                }
-                if let Some(z) = strategy.mtf_z_min {
-                    pp.mtf_z_min = z;
-                }
+                strategy.overlay_pair_params(&mut pp);
                 inst_pair_params.insert(k.clone(), pp);
             }
 
@@ -305,16 +450,7 @@ impl PairTradeEngine {
                 let pp = inst_pair_params
                     .get(&pair_key)
                     .unwrap_or(&inst_default);
-                let mut ps = PairState::new(cfg.metrics_window, pp.entry_z_base);
-                if cfg.use_kalman_beta {
-                    ps.kalman = Some(kalman::KalmanBeta::new(
-                        1.0,
-                        cfg.kalman_initial_p,
-                        cfg.kalman_q,
-                        cfg.kalman_r,
-                    ));
-                }
-                states.insert(pair_key, ps);
+                states.insert(pair_key, new_pair_state(&cfg, pp));
             }
 
             let instance_connector = instance_connectors
@@ -356,6 +492,15 @@ impl PairTradeEngine {
                 connector: instance_connector,
                 equity_cache: strategy.equity_reference_usd,
                 last_equity_fetch,
+                balance_fetch_failures: 0,
+                equity_fallback_cycles: 0,
+                equity_guard_halted: false,
+                margin_block_until_ts: None,
+                open_order_count: 0,
+                open_orders_blocked: false,
+                last_open_order_audit: None,
+                last_delta_rebalance: None,
+                delta_rebalance_orders: Vec::new(),
                 equity_reference_usd: strategy.equity_reference_usd,
                 states,
                 pnl_logger,
@@ -371,16 +516,24 @@ impl PairTradeEngine {
                 session_halted: false,
                 session_halt_reason: None,
                 session_halt_ts: None,
+                persisted_entry_ts: HashMap::new(),
                 total_trades: 0,
                 total_wins: 0,
                 total_pnl: 0.0,
                 peak_pnl: 0.0,
                 max_dd: 0.0,
+                trade_stats: TradeStatsBook::default(),
+                last_select_log: None,
                 pair_params: inst_pair_params,
                 default_pair_params: inst_default,
             });
         }
 
+        let connector_symbols: HashSet<String> = cfg
+            .universe
+            .iter()
+            .flat_map(|p| [p.base.clone(), p.quote.clone()])
+            .collect();
         Ok(Self {
             cfg,
             connector,
@@ -392,16 +545,36 @@ impl PairTradeEngine {
             last_ob_warn: HashMap::new(),
             last_ticker_warn: HashMap::new(),
             last_position_warn: HashMap::new(),
+            one_sided_bar_pairs: HashSet::new(),
+            outlier_bar_streaks: HashMap::new(),
+            log_sample_counts: HashMap::new(),
             min_order_warned,
             min_tick_warned,
             positions_ready: backtest_mode,
             open_positions: HashMap::new(),
             last_account_rest_call: None,
-            history_path,
+            connector_failures: 0,
+            last_connector_restart: None,
+            history_store,
             risk_state_path,
+            #[cfg(test)]
+            _test_state_dir: None,
             kill_switch_active: false,
             data_dump_writer,
             shutdown_pending: false,
+            retiring_pairs: HashSet::new(),
+            connector_symbols,
+            funding_schedule_warned: false,
+            dust_sweep_attempted: HashSet::new(),
+            no_trade_window: None,
+            clock: Arc::new(SystemClock),
+            reissued_legs_this_cycle: 0,
+            last_position_audit: None,
+            position_audit_strikes: 0,
+            taker_fallbacks: BTreeMap::new(),
+            size_adjustments: BTreeMap::new(),
+            realized_slippage: HashMap::new(),
+            slippage_blocked: HashMap::new(),
         })
     }
 
@@ -462,6 +635,10 @@ impl PairTradeEngine {
         if dd > self.instances[inst_idx].max_dd {
             self.instances[inst_idx].max_dd = dd;
         }
+        self.instances[inst_idx]
+            .trade_stats
+            .record(&record.pair, record.pnl, record.hold_secs);
+        self.persist_trade_stats();
 
         // Update status reporter
         let inst = &mut self.instances[inst_idx];
@@ -479,6 +656,7 @@ impl PairTradeEngine {
         }
 
         if let Some(logger) = &mut self.instances[inst_idx].pnl_logger {
+            let record = record.rounded(self.cfg.status_price_dp, self.cfg.status_pnl_dp);
             if let Err(err) = logger.log(record) {
                 log::warn!("[PNL] failed to write pnl log: {:?}", err);
             }
@@ -519,23 +697,94 @@ impl PairTradeEngine {
         }
 
         // Log what the exchange reports for positions
-        match self.connector.get_positions().await {
+        let exchange_positions = match self.connector.get_positions().await {
             Ok(pos) => {
                 let filtered: Vec<_> = pos
                     .into_iter()
                     .filter(|p| p.sign != 0 && p.size > Decimal::ZERO)
                     .collect();
                 log::error!("[DEBUG][EXCHANGE_POSITIONS] {:?}", filtered);
+                serde_json::json!(filtered.iter().map(|p| format!("{:?}", p)).collect::<Vec<_>>())
             }
             Err(get_err) => {
                 log::error!(
                     "[DEBUG][EXCHANGE_POSITIONS] failed to fetch positions: {:?}",
                     get_err
                 );
+                serde_json::json!({ "error": format!("{:?}", get_err) })
+            }
+        };
+
+        if let Some(dir) = self.cfg.forensic_dump_dir.clone() {
+            let snapshot = self.forensic_snapshot(err, exchange_positions);
+            match forensics::write_snapshot(Path::new(&dir), self.cfg.forensic_dump_keep, &snapshot) {
+                Ok(path) => log::error!("[FORENSIC] snapshot written to {}", path.display()),
+                Err(write_err) => log::warn!("[FORENSIC] failed to write snapshot: {:?}", write_err),
             }
         }
     }
 
+    /// Full engine state for `forensic_dump_dir`: every pair state (all
+    /// fields, including pending orders, as `Debug`), cached and exchange
+    /// positions, and the last bars per symbol.
+    fn forensic_snapshot(
+        &self,
+        err: &anyhow::Error,
+        exchange_positions: serde_json::Value,
+    ) -> serde_json::Value {
+        const RECENT_BARS: usize = 20;
+        let instances: Vec<serde_json::Value> = self
+            .instances
+            .iter()
+            .map(|inst| {
+                let states: serde_json::Map<String, serde_json::Value> = inst
+                    .states
+                    .iter()
+                    .map(|(key, state)| (key.clone(), serde_json::json!(format!("{:#?}", state))))
+                    .collect();
+                serde_json::json!({
+                    "id": inst.id,
+                    "consecutive_losses": inst.consecutive_losses,
+                    "session_halted": inst.session_halted,
+                    "states": states,
+                })
+            })
+            .collect();
+        let open_positions: serde_json::Map<String, serde_json::Value> = self
+            .open_positions
+            .iter()
+            .map(|(symbol, pos)| (symbol.clone(), serde_json::json!(format!("{:?}", pos))))
+            .collect();
+        let recent_bars: serde_json::Map<String, serde_json::Value> = self
+            .history
+            .iter()
+            .map(|(symbol, samples)| {
+                let bars: Vec<serde_json::Value> = samples
+                    .iter()
+                    .skip(samples.len().saturating_sub(RECENT_BARS))
+                    .map(|s| {
+                        serde_json::json!({
+                            "ts": s.ts,
+                            "close": s.close.map(|c| c.to_string()),
+                            "log_price": s.log_price,
+                        })
+                    })
+                    .collect();
+                (symbol.clone(), serde_json::json!(bars))
+            })
+            .collect();
+        serde_json::json!({
+            "ts": self.current_now_ts(),
+            "error": format!("{:#}", err),
+            "dex": self.cfg.dex_name,
+            "positions_ready": self.positions_ready,
+            "instances": instances,
+            "open_positions": open_positions,
+            "exchange_positions": exchange_positions,
+            "recent_bars": recent_bars,
+        })
+    }
+
     pub async fn run(&mut self) -> Result<()> {
         log::info!("[CONFIG] DEX_NAME is: {}", self.cfg.dex_name);
         log::info!(
@@ -545,8 +794,35 @@ impl PairTradeEngine {
             self.post_only_supported(),
             self.should_post_only()
         );
+        if self.cfg.spread_smoothing_bars > 1 {
+            log::info!(
+                "[CONFIG] spread smoothing active: SMA over {} raw spreads feeds spread_history",
+                self.cfg.spread_smoothing_bars
+            );
+        }
+        if self.cfg.z_price_source != PriceSource::Last {
+            log::info!(
+                "[CONFIG] z price source: {:?} (bars feeding spread/z; execution keeps ticker price)",
+                self.cfg.z_price_source
+            );
+        }
+        if self.cfg.bar_source == BarSource::Trades {
+            log::info!("[CONFIG] bar source: trades (get_last_trades folded into bars each cycle)");
+        }
+        log::info!(
+            "[CONFIG] z statistic: {}",
+            match self.cfg.z_stat_mode {
+                ZStatMode::Simple => "mean/std",
+                ZStatMode::Robust => "median/MAD",
+            }
+        );
+        log::info!(
+            "[CONFIG] pair eval interval: {}s (jump/velocity/vol-spike triggers still re-evaluate early)",
+            self.cfg.pair_eval_interval_secs
+        );
         self.load_history_from_disk();
         self.load_risk_state();
+        self.load_trade_stats();
         // BT warm-start: load a live history snapshot so the replay starts
         // with an identical spread_history / beta to the live bot, instead
         // of building from scratch over the first 4 hours of data.
@@ -563,8 +839,7 @@ impl PairTradeEngine {
                 for inst in &mut self.instances {
                     for (pair_key, spreads) in &loaded_spreads {
                         if let Some(state) = inst.states.get_mut(pair_key) {
-                            state.last_spread = spreads.back().copied();
-                            state.spread_history = spreads.clone();
+                            state.restore_spread_history(spreads.clone());
                         }
                     }
                 }
@@ -604,6 +879,7 @@ impl PairTradeEngine {
             // occasionally 429'd during the multi-instance startup burst).
             // bot-strategy#143.
             sleep(Duration::from_secs(5)).await;
+            self.validate_universe().await?;
             if self.cfg.force_close_on_startup {
                 self.force_close_on_startup().await?;
             }
@@ -613,7 +889,16 @@ impl PairTradeEngine {
             // aligning the tick phase itself, two bots would sample the last tick of a 60s bucket
             // at different wall-clock seconds and therefore see slightly different close prices,
             // which cascades into divergent beta/mean/std/z.
-            let interval_secs = self.cfg.interval_secs.max(1);
+            // With `max_interval_secs` the interval backs off on rate-limit
+            // hits; boundaries stay wall-clock aligned to whatever it is.
+            let mut pacing = pacing::AdaptiveInterval::new(
+                self.cfg.interval_secs,
+                self.cfg.min_interval_secs,
+                self.cfg.max_interval_secs,
+                rate_limit_hit_count(),
+                Instant::now(),
+            );
+            let mut interval_secs = pacing.current_secs();
             fn next_wall_clock_boundary(interval_secs: u64) -> tokio::time::Instant {
                 use std::time::{SystemTime, UNIX_EPOCH};
                 let now_unix_ms = SystemTime::now()
@@ -634,6 +919,10 @@ impl PairTradeEngine {
                 tokio::signal::unix::SignalKind::interrupt(),
             )
             .expect("failed to register SIGINT handler");
+            let mut sighup = tokio::signal::unix::signal(
+                tokio::signal::unix::SignalKind::hangup(),
+            )
+            .expect("failed to register SIGHUP handler");
 
             let grace = Duration::from_secs(self.cfg.shutdown_grace_secs);
             let mut shutdown_deadline: Option<Instant> = None;
@@ -660,6 +949,7 @@ impl PairTradeEngine {
 
                 tokio::select! {
                     _ = tokio::time::sleep_until(next_tick) => {
+                        interval_secs = pacing.observe(rate_limit_hit_count(), Instant::now());
                         next_tick = next_wall_clock_boundary(interval_secs);
                         // Monitor step() execution time. If it exceeds interval_secs,
                         // the next wall-clock boundary will be skipped, causing tick
@@ -672,6 +962,16 @@ impl PairTradeEngine {
                         }
                         let step_elapsed = step_start.elapsed();
                         let interval = Duration::from_secs(interval_secs);
+                        let step_end = tokio::time::Instant::now();
+                        if step_end >= next_tick {
+                            next_tick = pacing::next_tick_after_overrun(
+                                self.cfg.missed_tick_behavior,
+                                next_tick,
+                                step_end,
+                                interval,
+                                || next_wall_clock_boundary(interval_secs),
+                            );
+                        }
                         // Warn only on critical overrun (>=1.5x interval), where a
                         // wall-clock tick is genuinely skipped and A/B/C bars drift.
                         // Mild overruns (just past the boundary) are logged at info
@@ -680,16 +980,18 @@ impl PairTradeEngine {
                         if step_elapsed >= critical {
                             log::warn!(
                                 "[STEP_OVERRUN] step() took {:.2}s >= {:.2}s (1.5x interval_secs={}); \
-                                 wall-clock tick skipped",
+                                 wall-clock tick missed, missed_tick_behavior={:?}",
                                 step_elapsed.as_secs_f64(),
                                 critical.as_secs_f64(),
-                                interval_secs
+                                interval_secs,
+                                self.cfg.missed_tick_behavior
                             );
                         } else if step_elapsed >= interval {
                             log::info!(
-                                "[STEP_OVERRUN] step() took {:.2}s >= interval_secs={} (mild)",
+                                "[STEP_OVERRUN] step() took {:.2}s >= interval_secs={} (mild, {:?})",
                                 step_elapsed.as_secs_f64(),
-                                interval_secs
+                                interval_secs,
+                                self.cfg.missed_tick_behavior
                             );
                         }
                     }
@@ -781,6 +1083,14 @@ impl PairTradeEngine {
                             break;
                         }
                     }
+                    _ = sighup.recv() => {
+                        if self.shutdown_pending {
+                            log::info!("[UNIVERSE] SIGHUP ignored during graceful shutdown");
+                        } else {
+                            log::info!("[UNIVERSE] SIGHUP received, reloading universe");
+                            self.reload_universe();
+                        }
+                    }
                     _ = sigint.recv() => {
                         if self.shutdown_pending {
                             log::info!("[PAIR] SIGINT received during graceful shutdown, force-closing");
@@ -887,6 +1197,19 @@ impl PairTradeEngine {
                 new_legs.push(kept);
                 continue;
             }
+            if self.reissued_legs_this_cycle >= self.cfg.max_reissued_legs_per_cycle {
+                log::warn!(
+                    "[ORDER] {} leg {} reissue deferred: {} legs already reissued this cycle",
+                    stage,
+                    leg.symbol,
+                    self.reissued_legs_this_cycle
+                );
+                let mut kept = leg.clone();
+                kept.filled = filled;
+                new_legs.push(kept);
+                continue;
+            }
+            self.reissued_legs_this_cycle += 1;
             let spread = self.order_spread_param(limit, false);
             match self
                 .connector
@@ -917,6 +1240,7 @@ impl PairTradeEngine {
                             filled,
                             side: leg.side,
                             limit_price: None,
+                            placed_price: leg.placed_price,
                         });
                     }
                     new_legs.push(PendingLeg {
@@ -927,6 +1251,7 @@ impl PairTradeEngine {
                         filled: Decimal::ZERO,
                         side: leg.side,
                         limit_price: None,
+                        placed_price: price_map.get(&leg.symbol).map(|s| s.price),
                     });
                 }
                 Err(e) => {
@@ -972,12 +1297,69 @@ impl PairTradeEngine {
         Ok(Some(PendingOrders {
             legs: new_legs,
             direction: pending.direction,
-            placed_at: Instant::now(),
+            placed_at: self.clock.now(),
             hedge_retry_count: retry_count,
             post_only_hybrid: false,
+            partial_exit: pending.partial_exit,
+            scale_in: pending.scale_in,
         }))
     }
 
+    /// Reduce-only market close of the exposure a pending order leaves
+    /// behind: the filled part of each entry leg, or the unfilled remainder
+    /// of each exit leg. Orders must already be cancelled.
+    async fn close_pending_exposure(
+        &mut self,
+        key: &str,
+        pending: &PendingOrders,
+        filled_qtys: &HashMap<String, Decimal>,
+        is_exit: bool,
+        reason: &str,
+    ) {
+        let stage = if is_exit { "exit" } else { "entry" };
+        for leg in &pending.legs {
+            let filled = filled_qtys
+                .get(&leg.order_id)
+                .cloned()
+                .unwrap_or(Decimal::ZERO)
+                .max(leg.filled)
+                .min(leg.target);
+            let (size, side) = if is_exit {
+                (leg.target - filled, leg.side)
+            } else {
+                let close_side = match leg.side {
+                    dex_connector::OrderSide::Long => dex_connector::OrderSide::Short,
+                    dex_connector::OrderSide::Short => dex_connector::OrderSide::Long,
+                };
+                (filled, close_side)
+            };
+            if size <= Decimal::ZERO {
+                continue;
+            }
+            match self
+                .connector
+                .create_order(&leg.symbol, size, side, None, None, true, None)
+                .await
+            {
+                Ok(_) => log::warn!(
+                    "[ORDER] {} market-closed {} leg {} size={} ({})",
+                    key,
+                    stage,
+                    leg.symbol,
+                    size,
+                    reason
+                ),
+                Err(e) => log::error!(
+                    "[ORDER] {} failed to market-close {} leg {}: {:?}",
+                    key,
+                    stage,
+                    leg.symbol,
+                    e
+                ),
+            }
+        }
+    }
+
     async fn reissue_entry_as_taker(
         &mut self,
         key: &str,
@@ -1023,6 +1405,7 @@ impl PairTradeEngine {
                         filled: Decimal::ZERO,
                         side: leg.side,
                         limit_price: None,
+                        placed_price: price_map.get(&leg.symbol).map(|s| s.price),
                     });
                 }
                 Err(e) => {
@@ -1041,9 +1424,11 @@ impl PairTradeEngine {
         Ok(Some(PendingOrders {
             legs: new_legs,
             direction: pending.direction,
-            placed_at: Instant::now(),
+            placed_at: self.clock.now(),
             hedge_retry_count: 0,
             post_only_hybrid: false,
+            partial_exit: false,
+            scale_in: false,
         }))
     }
 
@@ -1067,7 +1452,31 @@ impl PairTradeEngine {
         parts.join(", ")
     }
 
-    async fn force_close_on_startup(&self) -> Result<()> {
+    /// Publish startup force-close progress to every instance's status file;
+    /// `None` clears it once the close has completed.
+    fn report_startup_close(&mut self, status: Option<StartupCloseStatus>) {
+        for inst in self.instances.iter_mut() {
+            if let Some(reporter) = &mut inst.status_reporter {
+                reporter.set_startup_close(status.clone());
+                if let Err(err) = reporter.write_snapshot(&self.open_positions, false) {
+                    log::debug!("[Startup] failed to write status snapshot: {:?}", err);
+                }
+            }
+        }
+    }
+
+    /// `startup_force_close_wait_for(attempt)` with the configured ± jitter.
+    fn startup_force_close_wait(&self, attempt: u32) -> Duration {
+        let wait = self.cfg.startup_force_close_wait_for(attempt);
+        let jitter = self.cfg.startup_force_close_jitter_pct.clamp(0.0, 1.0);
+        if jitter <= 0.0 || wait.is_zero() {
+            return wait;
+        }
+        let factor = 1.0 + rand::thread_rng().gen_range(-jitter..=jitter);
+        wait.mul_f64(factor)
+    }
+
+    async fn force_close_on_startup(&mut self) -> Result<()> {
         if self.cfg.dry_run || self.cfg.observe_only {
             log::info!(
                 "[Startup] DRY RUN/OBSERVE ONLY: Would cancel all orders and close all positions"
@@ -1075,17 +1484,32 @@ impl PairTradeEngine {
             return Ok(());
         }
         let attempts = self.cfg.startup_force_close_attempts.max(1);
-        let wait_secs = self.cfg.startup_force_close_wait_secs;
         log::info!(
-            "[Startup] Force closing any existing orders/positions (attempts={}, wait_secs={})",
+            "[Startup] Force closing any existing orders/positions (attempts={}, wait_secs={}, backoff={}, max_wait_secs={}, jitter={})",
             attempts,
-            wait_secs
+            self.cfg.startup_force_close_wait_secs,
+            self.cfg.startup_force_close_backoff_mult,
+            self.cfg.startup_force_close_max_wait_secs,
+            self.cfg.startup_force_close_jitter_pct
         );
         if let Err(err) = self.connector.cancel_all_orders(None).await {
             log::warn!("[Startup] cancel_all_orders failed: {:?}", err);
         }
+        if self.cfg.startup_cancel_wait_secs > 0 {
+            sleep(Duration::from_secs(self.cfg.startup_cancel_wait_secs)).await;
+        }
         for attempt in 1..=attempts {
             let positions_result = self.connector.get_positions().await;
+            if matches!(&positions_result, Ok(p) if p.is_empty()) {
+                self.report_startup_close(None);
+            } else {
+                self.report_startup_close(Some(StartupCloseStatus {
+                    attempt,
+                    attempts,
+                    remaining_positions: positions_result.as_ref().ok().map(|p| p.len()),
+                    done: false,
+                }));
+            }
             match positions_result {
                 Ok(positions) if positions.is_empty() => {
                     if attempt == 1 {
@@ -1122,15 +1546,35 @@ impl PairTradeEngine {
                 }
             }
 
-            if attempt < attempts && wait_secs > 0 {
-                sleep(Duration::from_secs(wait_secs)).await;
+            let wait = self.startup_force_close_wait(attempt);
+            if attempt < attempts && !wait.is_zero() {
+                log::info!(
+                    "[Startup] waiting {:.1}s before close attempt {}/{}",
+                    wait.as_secs_f64(),
+                    attempt + 1,
+                    attempts
+                );
+                sleep(wait).await;
             }
         }
 
-        if wait_secs > 0 {
-            sleep(Duration::from_secs(wait_secs)).await;
+        let wait = self.startup_force_close_wait(attempts);
+        if !wait.is_zero() {
+            sleep(wait).await;
         }
-        match self.connector.get_positions().await {
+        let final_result = self.connector.get_positions().await;
+        if matches!(&final_result, Ok(p) if p.is_empty()) {
+            self.report_startup_close(None);
+        } else {
+            // Left in the status file so a supervisor sees the stuck close.
+            self.report_startup_close(Some(StartupCloseStatus {
+                attempt: attempts,
+                attempts,
+                remaining_positions: final_result.as_ref().ok().map(|p| p.len()),
+                done: true,
+            }));
+        }
+        match final_result {
             Ok(positions) if positions.is_empty() => {
                 log::info!("[Startup] All positions closed");
             }
@@ -1152,7 +1596,7 @@ impl PairTradeEngine {
                     "Startup force close failed after {} attempts.\nOpen positions: {}",
                     attempts, summary
                 );
-                EmailClient::new().send(&subject, &body);
+                notifier::notify(Severity::Critical, &subject, &body);
             }
             Err(err) => {
                 log::error!(
@@ -1212,123 +1656,583 @@ impl PairTradeEngine {
         if !self.instances.is_empty() {
             self.connector = self.instances[0].connector.clone();
         }
+        self.reissued_legs_this_cycle = 0;
         let Some((price_map, updated)) = self.step_shared().await? else {
             return Ok(());
         };
+        self.roll_trade_stats();
         for inst_idx in 0..self.instances.len() {
             self.connector = self.instances[inst_idx].connector.clone();
             self.step_for_instance(inst_idx, &price_map, &updated).await?;
         }
+        self.retire_flat_pairs();
         Ok(())
     }
 
-    /// Shared phase: run once per outer step. Fetches the canonical price
-    /// tick, advances the ReplayConnector clock exactly once, updates the
-    /// engine-wide history + bar builders, and returns the `(price_map,
-    /// updated)` pair for the per-instance phase. Returns `Ok(None)` when a
-    /// host-shared cooldown is active and every instance should skip.
-    async fn step_shared(
-        &mut self,
-    ) -> Result<Option<(HashMap<String, SymbolSnapshot>, HashSet<String>)>> {
-        // Lighter WAF cooldown is host-shared. Any REST call we make here
-        // would be rejected anyway and would refresh the rolling window,
-        // turning a 60s cooldown into a permanent block. Skip silently.
-        // dex-connector logs once on engagement; the email goes out via
-        // report_rate_limit. See bot-strategy#35.
-        #[cfg(feature = "lighter-sdk")]
-        if dex_connector::lighter_waf_cooldown::cooldown_remaining().is_some() {
-            return Ok(None);
+    /// Per-instance trade stats (per pair and aggregate), keyed by strategy
+    /// id.
+    pub fn stats(&self) -> HashMap<String, TradeStatsBook> {
+        self.instances
+            .iter()
+            .map(|inst| (inst.id.clone(), inst.trade_stats.clone()))
+            .collect()
+    }
+
+    fn roll_trade_stats(&mut self) {
+        let now_ts = self.current_now_ts();
+        let mode = self.cfg.trade_stats_reset;
+        let reset_hour = self.cfg.risk.daily_reset_utc_hour;
+        let mut rolled = false;
+        for inst in &mut self.instances {
+            let prev = inst.trade_stats.aggregate.clone();
+            if inst.trade_stats.roll(mode, now_ts, reset_hour) {
+                log::info!(
+                    "[TRADE_STATS] {} daily reset: prev trades={} wins={} pnl={:.4}",
+                    inst.id,
+                    prev.trades,
+                    prev.wins,
+                    prev.gross_pnl
+                );
+                rolled = true;
+            }
+        }
+        if rolled {
+            self.persist_trade_stats();
         }
+    }
 
-        self.update_kill_switch_state();
-        self.consume_risk_ack();
-        self.refresh_daily_session();
+    fn trade_stats_path(&self) -> PathBuf {
+        self.risk_state_path.with_file_name("trade_stats.json")
+    }
 
-        let price_map = self.fetch_latest_prices().await?;
+    fn persist_trade_stats(&self) {
+        if !self.cfg.persist_trade_stats || self.cfg.backtest_mode {
+            return;
+        }
+        trade_stats::persist_trade_stats(&self.trade_stats_path(), self.stats());
+    }
 
-        if let Some(writer) = &mut self.data_dump_writer {
-            let dump_entry = DataDumpEntry {
-                timestamp: Utc::now().timestamp_millis(),
-                prices: &price_map,
-            };
-            if let Ok(json_string) = serde_json::to_string(&dump_entry) {
-                if writer.write_line(&json_string).is_err() {
-                    log::error!("[DataDump] Failed to write to dump file");
-                }
+    /// Reload the books saved by `persist_trade_stats`. A book from an
+    /// earlier session day is cleared by the next `roll_trade_stats` when
+    /// `trade_stats_reset` is daily.
+    fn load_trade_stats(&mut self) {
+        if !self.cfg.persist_trade_stats || self.cfg.backtest_mode {
+            return;
+        }
+        let mut loaded = trade_stats::load_trade_stats(&self.trade_stats_path());
+        for inst in &mut self.instances {
+            if let Some(book) = loaded.remove(&inst.id) {
+                log::info!(
+                    "[TRADE_STATS] {} restored: trades={} pnl={:.4} since_ts={}",
+                    inst.id,
+                    book.aggregate.trades,
+                    book.aggregate.gross_pnl,
+                    book.since_ts
+                );
+                inst.trade_stats = book;
             }
         }
+    }
 
-        // Bar build + history update is engine-wide: all instances read
-        // from the same `self.history`, so we must do it exactly once per
-        // outer tick before any per-instance decision logic runs.
-        let max_history_len = self.max_history_len();
-        let now_ts = self.current_now_ts();
-        self.load_history_from_disk();
+    /// SIGHUP handler: re-read the config and apply its universe and
+    /// `z_stat_mode`. Every other knob still requires a restart.
+    fn reload_universe(&mut self) {
+        match PairTradeConfig::from_env_or_yaml() {
+            Ok(new_cfg) => self.apply_universe_reload(&new_cfg),
+            Err(e) => log::error!(
+                "[UNIVERSE] reload failed, keeping current universe: {:?}",
+                e
+            ),
+        }
+    }
 
-        // BT restart simulation (bot-strategy#27 comment 2026-04-16): when
-        // the replay crosses a timestamp listed in
-        // `BT_RESTART_TIMESTAMPS_FILE`, re-run `warm_start_states_from_history`
-        // to mirror what the live bot does at each systemd restart —
-        // re-compute `state.beta` from OLS and re-seed `spread_history`
-        // with 240 single-beta spreads. That seeded low-variance history
-        // is the mechanism behind the 2026-04-15 06:02 UTC "std collapse"
-        // (bot-strategy#62 — now known to be a restart artifact, not a
-        // regime break). We fire on crossing, not exact match, because
-        // the live dump has a gap (WS down) around the restart second, so
-        // the exact `restart_ts` often has no replay record. Each matched
-        // ts is removed from the set, so each restart fires at most once.
-        let restart_passed = self
-            .cfg
-            .bt_restart_timestamps
-            .as_mut()
-            .map(|set| {
-                let passed: Vec<i64> = set.iter().filter(|&&t| t <= now_ts).copied().collect();
-                for t in &passed {
-                    set.remove(t);
-                }
-                !passed.is_empty()
-            })
-            .unwrap_or(false);
-        if restart_passed {
-            log::warn!(
-                "[BT_RESTART] simulating live service restart (now_ts={})",
-                now_ts
+    /// Diff `new_cfg.universe` against the running one. Added pairs get
+    /// fresh `states` / `history` / `bar_builders` (symbols already shared
+    /// with another pair keep their warm history); removed pairs are
+    /// dropped immediately when flat, or parked in `retiring_pairs` until
+    /// their position is closed. The connectors were built with the
+    /// startup token list, so a pair with a symbol outside
+    /// `connector_symbols` is rejected until a restart. A changed
+    /// `z_stat_mode` is applied to every existing pair state as well.
+    fn apply_universe_reload(&mut self, new_cfg: &PairTradeConfig) {
+        if new_cfg.z_stat_mode != self.cfg.z_stat_mode {
+            log::info!(
+                "[CONFIG] z statistic reloaded: {:?} -> {:?}",
+                self.cfg.z_stat_mode,
+                new_cfg.z_stat_mode
             );
-            self.warm_start_states_from_history();
-        }
-        let mut updated = HashSet::new();
-        for (symbol, snapshot) in price_map.iter() {
-            if let Some(builder) = self.bar_builders.get_mut(symbol) {
-                let tick_ts = snapshot.exchange_ts.unwrap_or(now_ts);
-                if let Some((close_price, close_ts)) = builder.push(tick_ts, snapshot.price) {
-                    let entry = self
-                        .history
-                        .entry(symbol.clone())
-                        .or_insert_with(VecDeque::new);
-                    let log_price = close_price
-                        .to_f64()
-                        .ok_or_else(|| anyhow!("invalid price for {}", symbol))?
-                        .ln();
-                    if entry.back().map(|s| s.ts) != Some(close_ts) {
-                        if entry.len() >= max_history_len {
-                            entry.pop_front();
-                        }
-                        entry.push_back(PriceSample {
-                            log_price,
-                            ts: close_ts,
-                        });
-                    }
-                    updated.insert(symbol.clone());
+            self.cfg.z_stat_mode = new_cfg.z_stat_mode;
+            for inst in &mut self.instances {
+                for state in inst.states.values_mut() {
+                    state.z_stat_mode = new_cfg.z_stat_mode;
                 }
-            } else {
-                log::debug!("no bar builder for {}", symbol);
             }
         }
-        self.persist_history_to_disk();
-
-        Ok(Some((price_map, updated)))
-    }
-
+        let current: HashSet<String> = self
+            .cfg
+            .universe
+            .iter()
+            .map(|p| format!("{}/{}", p.base, p.quote))
+            .collect();
+        let wanted: HashSet<String> = new_cfg
+            .universe
+            .iter()
+            .map(|p| format!("{}/{}", p.base, p.quote))
+            .collect();
+        let trading_period_secs = self.cfg.trading_period_secs;
+        let max_bar_span_mult = self.cfg.max_bar_span_mult;
+
+        for pair in &new_cfg.universe {
+            let key = format!("{}/{}", pair.base, pair.quote);
+            if self.retiring_pairs.remove(&key) {
+                log::info!("[UNIVERSE] {} re-added while retiring; keeping it", key);
+                continue;
+            }
+            if current.contains(&key) {
+                continue;
+            }
+            let unregistered: Vec<&str> = [&pair.base, &pair.quote]
+                .into_iter()
+                .filter(|sym| !self.connector_symbols.contains(*sym))
+                .map(String::as_str)
+                .collect();
+            if !unregistered.is_empty() {
+                log::warn!(
+                    "[UNIVERSE] {} not added: {} not registered with the connector; \
+                     restart to trade it",
+                    key,
+                    unregistered.join(", ")
+                );
+                continue;
+            }
+            self.cfg.universe.push(pair.clone());
+            for sym in [&pair.base, &pair.quote] {
+                self.history.entry(sym.clone()).or_default();
+                self.bar_builders
+                    .entry(sym.clone())
+                    .or_insert_with(|| {
+                        BarBuilder::new(trading_period_secs).with_max_span_mult(max_bar_span_mult)
+                    });
+            }
+            let override_pp = new_cfg.pair_params.get(&key);
+            if let Some(pp) = override_pp {
+                self.cfg.pair_params.insert(key.clone(), pp.clone());
+            }
+            for (inst_idx, inst) in self.instances.iter_mut().enumerate() {
+                let pp = match override_pp {
+                    Some(pp) => {
+                        let mut pp = pp.clone();
+                        if let Some(strategy) = self.cfg.strategies.get(inst_idx) {
+                            strategy.overlay_pair_params(&mut pp);
+                        }
+                        inst.pair_params.insert(key.clone(), pp.clone());
+                        pp
+                    }
+                    None => inst.default_pair_params.clone(),
+                };
+                inst.states.insert(key.clone(), new_pair_state(&self.cfg, &pp));
+            }
+            log::info!("[UNIVERSE] added {}", key);
+            if self.cfg.seed_new_pairs_from_history {
+                for inst_idx in 0..self.instances.len() {
+                    if self.seed_spread_history_from_bars(inst_idx, pair) == Some(true) {
+                        let state = &self.instances[inst_idx].states[&key];
+                        log::info!(
+                            "[UNIVERSE] {} seeded spread_history len={} beta={:.4} from shared bars",
+                            key,
+                            state.spread_history.len(),
+                            state.beta
+                        );
+                    }
+                }
+            }
+        }
+
+        for key in current.difference(&wanted) {
+            if self.retiring_pairs.contains(key) {
+                continue;
+            }
+            if self.pair_has_exposure(key) {
+                log::warn!(
+                    "[UNIVERSE] {} removed from config with an open position or pending order; \
+                     flattening before retiring",
+                    key
+                );
+                self.retiring_pairs.insert(key.clone());
+            } else {
+                self.remove_pair(key);
+            }
+        }
+    }
+
+    /// True when `now_ts` is within `avoid_funding_window_secs` of the next
+    /// funding time. The schedule is the venue's, falling back to
+    /// `funding_interval_secs`; without either this is always false and a
+    /// warning is logged once.
+    fn funding_window_active(&mut self, now_ts: i64) -> bool {
+        let window = self.cfg.avoid_funding_window_secs;
+        if window == 0 {
+            return false;
+        }
+        let interval = venue_funding_interval_secs(&self.cfg.dex_name)
+            .unwrap_or(self.cfg.funding_interval_secs) as i64;
+        if interval <= 0 {
+            if !self.funding_schedule_warned {
+                log::warn!(
+                    "[FUNDING] avoid_funding_window_secs={} set but funding schedule is unknown \
+                     (funding_interval_secs=0); funding window disabled",
+                    window
+                );
+                self.funding_schedule_warned = true;
+            }
+            return false;
+        }
+        let secs_to_funding = interval - now_ts.rem_euclid(interval);
+        secs_to_funding <= window as i64
+    }
+
+    /// True while `now_ts` falls in one of the `no_trade_windows`. Entering
+    /// and leaving a window is logged once each.
+    fn no_trade_window_active(&mut self, now_ts: i64) -> bool {
+        let active = schedule::active_window(&self.cfg.no_trade_windows, now_ts).map(|w| w.to_string());
+        if active != self.no_trade_window {
+            match &active {
+                Some(spec) => log::info!("[SCHEDULE] entries blocked (window '{}')", spec),
+                None => log::info!("[SCHEDULE] no-trade window ended; entries resumed"),
+            }
+            self.no_trade_window = active;
+        }
+        self.no_trade_window.is_some()
+    }
+
+    fn pair_has_exposure(&self, key: &str) -> bool {
+        self.instances.iter().any(|inst| {
+            inst.states.get(key).is_some_and(|s| {
+                s.position.is_some() || s.pending_entry.is_some() || s.pending_exit.is_some()
+            })
+        })
+    }
+
+    /// Drop retiring pairs whose positions and orders are gone in every
+    /// instance.
+    fn retire_flat_pairs(&mut self) {
+        if self.retiring_pairs.is_empty() {
+            return;
+        }
+        let flat: Vec<String> = self
+            .retiring_pairs
+            .iter()
+            .filter(|key| !self.pair_has_exposure(key))
+            .cloned()
+            .collect();
+        for key in flat {
+            self.retiring_pairs.remove(&key);
+            self.remove_pair(&key);
+        }
+    }
+
+    /// Startup check that every universe symbol has a positive ticker price
+    /// and a known `min_order` / `min_tick`. A typo'd or delisted symbol
+    /// would otherwise feed degenerate bars until its first order fails.
+    /// With `strict_universe_validation` any bad symbol aborts startup;
+    /// otherwise pairs touching one are dropped with a warning. Ticker
+    /// errors are retried with backoff; only an "unknown symbol" answer
+    /// counts as bad, while one still failing transiently (429, timeout)
+    /// keeps its pairs with a warning. A universe with no valid pair left
+    /// always aborts rather than running empty.
+    async fn validate_universe(&mut self) -> Result<()> {
+        let symbols: BTreeSet<String> = self
+            .cfg
+            .universe
+            .iter()
+            .flat_map(|p| [p.base.clone(), p.quote.clone()])
+            .collect();
+        let mut invalid: BTreeMap<String, String> = BTreeMap::new();
+        for symbol in &symbols {
+            let problem = match self.fetch_universe_ticker(symbol).await {
+                Err(err) if is_unknown_symbol_error(&err) => {
+                    Some(format!("ticker fetch failed: {:?}", err))
+                }
+                Err(err) => {
+                    log::warn!(
+                        "[UNIVERSE] {} not validated after {} attempts, keeping it: {:?}",
+                        symbol,
+                        UNIVERSE_TICKER_ATTEMPTS,
+                        err
+                    );
+                    None
+                }
+                Ok(t) if t.price <= Decimal::ZERO => Some(format!("price {}", t.price)),
+                Ok(t) if t.min_order.is_none() || t.min_tick.is_none() => Some(format!(
+                    "min_order={:?} min_tick={:?}",
+                    t.min_order, t.min_tick
+                )),
+                Ok(_) => None,
+            };
+            if let Some(problem) = problem {
+                invalid.insert(symbol.clone(), problem);
+            }
+        }
+        if invalid.is_empty() {
+            log::info!("[UNIVERSE] validated {} symbols", symbols.len());
+            return Ok(());
+        }
+        for (symbol, problem) in &invalid {
+            log::warn!("[UNIVERSE] invalid symbol {}: {}", symbol, problem);
+        }
+        if self.cfg.strict_universe_validation {
+            return Err(anyhow!(
+                "strict_universe_validation: invalid symbols {:?}",
+                invalid.keys().collect::<Vec<_>>()
+            ));
+        }
+        let bad_pairs: Vec<String> = self
+            .cfg
+            .universe
+            .iter()
+            .filter(|p| invalid.contains_key(&p.base) || invalid.contains_key(&p.quote))
+            .map(|p| format!("{}/{}", p.base, p.quote))
+            .collect();
+        if bad_pairs.len() == self.cfg.universe.len() {
+            return Err(anyhow!("universe validation left no tradable pairs"));
+        }
+        for key in bad_pairs {
+            log::warn!("[UNIVERSE] dropping {} at startup (invalid leg)", key);
+            self.remove_pair(&key);
+        }
+        Ok(())
+    }
+
+    async fn fetch_universe_ticker(&self, symbol: &str) -> Result<TickerResponse, DexError> {
+        let mut backoff = Duration::from_millis(UNIVERSE_TICKER_BACKOFF_MS);
+        let mut attempt = 1;
+        loop {
+            match self.connector.get_ticker(symbol, None).await {
+                Err(err)
+                    if attempt < UNIVERSE_TICKER_ATTEMPTS && !is_unknown_symbol_error(&err) =>
+                {
+                    log::debug!(
+                        "[UNIVERSE] {} ticker attempt {} failed: {:?}; retrying in {:?}",
+                        symbol,
+                        attempt,
+                        err,
+                        backoff
+                    );
+                    sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn remove_pair(&mut self, key: &str) {
+        self.cfg
+            .universe
+            .retain(|p| format!("{}/{}", p.base, p.quote) != key);
+        self.cfg.pair_params.remove(key);
+        for inst in &mut self.instances {
+            inst.states.remove(key);
+            inst.pair_params.remove(key);
+        }
+        let still_used: HashSet<&String> = self
+            .cfg
+            .universe
+            .iter()
+            .flat_map(|p| [&p.base, &p.quote])
+            .collect();
+        self.history.retain(|sym, _| still_used.contains(sym));
+        self.bar_builders.retain(|sym, _| still_used.contains(sym));
+        log::info!("[UNIVERSE] retired {}", key);
+    }
+
+    /// Shared phase: run once per outer step. Fetches the canonical price
+    /// tick, advances the ReplayConnector clock exactly once, updates the
+    /// engine-wide history + bar builders, and returns the `(price_map,
+    /// updated)` pair for the per-instance phase. Returns `Ok(None)` when a
+    /// host-shared cooldown is active and every instance should skip.
+    async fn step_shared(
+        &mut self,
+    ) -> Result<Option<(HashMap<String, SymbolSnapshot>, HashSet<String>)>> {
+        // Lighter WAF cooldown is host-shared. Any REST call we make here
+        // would be rejected anyway and would refresh the rolling window,
+        // turning a 60s cooldown into a permanent block. Skip silently.
+        // dex-connector logs once on engagement; the email goes out via
+        // report_rate_limit. See bot-strategy#35.
+        #[cfg(feature = "lighter-sdk")]
+        if dex_connector::lighter_waf_cooldown::cooldown_remaining().is_some() {
+            return Ok(None);
+        }
+
+        self.update_kill_switch_state();
+        self.consume_risk_ack();
+        self.refresh_daily_session();
+
+        let price_map = match self.fetch_latest_prices().await {
+            Ok(map) => {
+                self.connector_failures = 0;
+                map
+            }
+            Err(err) => {
+                self.note_connector_failure("price fetch").await;
+                return Err(err);
+            }
+        };
+
+        if let Some(writer) = &mut self.data_dump_writer {
+            let dump_entry = DataDumpEntry {
+                timestamp: Utc::now().timestamp_millis(),
+                prices: &price_map,
+            };
+            if let Ok(json_string) = serde_json::to_string(&dump_entry) {
+                if writer.write_line(&json_string).is_err() {
+                    log::error!("[DataDump] Failed to write to dump file");
+                }
+            }
+        }
+
+        // Bar build + history update is engine-wide: all instances read
+        // from the same `self.history`, so we must do it exactly once per
+        // outer tick before any per-instance decision logic runs.
+        let max_history_len = self.max_history_len();
+        let now_ts = self.current_now_ts();
+        self.load_history_from_disk();
+
+        // BT restart simulation (bot-strategy#27 comment 2026-04-16): when
+        // the replay crosses a timestamp listed in
+        // `BT_RESTART_TIMESTAMPS_FILE`, re-run `warm_start_states_from_history`
+        // to mirror what the live bot does at each systemd restart —
+        // re-compute `state.beta` from OLS and re-seed `spread_history`
+        // with 240 single-beta spreads. That seeded low-variance history
+        // is the mechanism behind the 2026-04-15 06:02 UTC "std collapse"
+        // (bot-strategy#62 — now known to be a restart artifact, not a
+        // regime break). We fire on crossing, not exact match, because
+        // the live dump has a gap (WS down) around the restart second, so
+        // the exact `restart_ts` often has no replay record. Each matched
+        // ts is removed from the set, so each restart fires at most once.
+        let restart_passed = self
+            .cfg
+            .bt_restart_timestamps
+            .as_mut()
+            .map(|set| {
+                let passed: Vec<i64> = set.iter().filter(|&&t| t <= now_ts).copied().collect();
+                for t in &passed {
+                    set.remove(t);
+                }
+                !passed.is_empty()
+            })
+            .unwrap_or(false);
+        if restart_passed {
+            log::warn!(
+                "[BT_RESTART] simulating live service restart (now_ts={})",
+                now_ts
+            );
+            self.warm_start_states_from_history();
+        }
+        let trade_ticks = if self.cfg.bar_source == BarSource::Trades {
+            self.fetch_trade_ticks(price_map.keys()).await
+        } else {
+            HashMap::new()
+        };
+        let mut updated = HashSet::new();
+        let align_bar_clock = self.cfg.align_bar_clock;
+        let z_price_source = self.cfg.z_price_source;
+        for (symbol, snapshot) in price_map.iter() {
+            if let Some(builder) = self.bar_builders.get_mut(symbol) {
+                // With `align_bar_clock` all symbols share the engine's
+                // wall-clock ts, so both legs of a pair cross a bucket
+                // boundary on the same tick.
+                let tick_ts = if align_bar_clock {
+                    now_ts
+                } else {
+                    snapshot.exchange_ts.unwrap_or(now_ts)
+                };
+                let closed = match trade_ticks.get(symbol) {
+                    // Only the first push of a cycle can cross a bucket
+                    // boundary; the rest share its tick ts.
+                    Some(prices) => prices
+                        .iter()
+                        .fold(None, |closed, price| closed.or(builder.push(tick_ts, *price))),
+                    None => builder.push(tick_ts, snapshot.price_from(z_price_source)),
+                };
+                if let Some((close_price, close_ts)) = closed {
+                    let log_price = close_price
+                        .to_f64()
+                        .ok_or_else(|| anyhow!("invalid price for {}", symbol))?
+                        .ln();
+                    if self.reject_outlier_bar(symbol, log_price, close_price, close_ts) {
+                        continue;
+                    }
+                    let entry = self
+                        .history
+                        .entry(symbol.clone())
+                        .or_insert_with(VecDeque::new);
+                    if entry.back().map(|s| s.ts) != Some(close_ts) {
+                        if entry.len() >= max_history_len {
+                            entry.pop_front();
+                        }
+                        entry.push_back(PriceSample {
+                            log_price,
+                            ts: close_ts,
+                            close: Some(close_price),
+                        });
+                    }
+                    updated.insert(symbol.clone());
+                }
+            } else {
+                log::debug!("no bar builder for {}", symbol);
+            }
+        }
+        self.persist_history_to_disk();
+
+        Ok(Some((price_map, updated)))
+    }
+
+    /// `max_bar_log_return`: whether a closed bar is dropped as a bad tick.
+    /// The `OUTLIER_BARS_BEFORE_ACCEPT`th outlier in a row is kept instead,
+    /// so a real level shift reaches `history` after a short delay.
+    fn reject_outlier_bar(
+        &mut self,
+        symbol: &str,
+        log_price: f64,
+        close_price: Decimal,
+        close_ts: i64,
+    ) -> bool {
+        let bound = self.cfg.max_bar_log_return;
+        let Some(ret) = self
+            .history
+            .get(symbol)
+            .and_then(|h| outlier_log_return(h, log_price, bound))
+        else {
+            self.outlier_bar_streaks.remove(symbol);
+            return false;
+        };
+        let streak = self.outlier_bar_streaks.entry(symbol.to_string()).or_insert(0);
+        *streak += 1;
+        if *streak < OUTLIER_BARS_BEFORE_ACCEPT {
+            log::warn!(
+                "[HISTORY] rejected outlier {} close={} ts={} log_return={:.4} (max {})",
+                symbol,
+                close_price,
+                close_ts,
+                ret,
+                bound
+            );
+            return true;
+        }
+        log::warn!(
+            "[HISTORY] accepting {} close={} ts={} after {} outlier bars in a row (log_return={:.4})",
+            symbol,
+            close_price,
+            close_ts,
+            streak,
+            ret
+        );
+        self.outlier_bar_streaks.remove(symbol);
+        false
+    }
+
     async fn step_for_instance(
         &mut self,
         inst_idx: usize,
@@ -1365,6 +2269,9 @@ impl PairTradeEngine {
         self.update_equity_sample(inst_idx);
         self.evaluate_session_dd(inst_idx).await;
         self.sync_positions_from_exchange(inst_idx, price_map).await?;
+        self.audit_position_count(price_map).await;
+        self.rebalance_net_delta(inst_idx, price_map).await;
+        self.audit_open_orders(inst_idx).await;
 
         let vol_median = self.compute_vol_median(inst_idx);
 
@@ -1396,7 +2303,9 @@ impl PairTradeEngine {
             }
         }
 
-        let positions_clear = self.open_positions.is_empty();
+        // Outside `single_position_mode` open positions only count through
+        // `max_active_pairs` and the overlap filter.
+        let positions_clear = !self.cfg.single_position_mode || self.open_positions.is_empty();
         let has_pending_orders = self
             .instances[inst_idx]
             .states
@@ -1408,10 +2317,16 @@ impl PairTradeEngine {
                 self.open_positions.len()
             );
             self.last_position_warn
-                .insert("entry_block".to_string(), Instant::now());
+                .insert("entry_block".to_string(), self.clock.now());
         }
         let mut planned: Vec<PlannedAction> = Vec::new();
+        // Entries whose z cleared the threshold but a per-pair gate held
+        // back, kept for `rejected_entries_file`.
+        let mut gated_entries: Vec<(PlannedAction, &'static str)> = Vec::new();
         let now_ts = self.current_now_ts();
+        let funding_window_active = self.funding_window_active(now_ts);
+        let no_trade_window_active = self.no_trade_window_active(now_ts);
+        let mut prefetched_status = self.prefetch_pending_statuses(inst_idx).await;
 
         let universe = self.cfg.universe.clone();
         for pair in &universe {
@@ -1420,9 +2335,20 @@ impl PairTradeEngine {
                 (Some(a), Some(b)) => (a, b),
                 _ => continue,
             };
-            if !(updated.contains(&pair.base) && updated.contains(&pair.quote)) {
+            let base_updated = updated.contains(&pair.base);
+            let quote_updated = updated.contains(&pair.quote);
+            if !(base_updated && quote_updated) {
+                if base_updated != quote_updated && self.one_sided_bar_pairs.insert(key.clone()) {
+                    log::info!(
+                        "[EVAL] {} skipped: one-sided bar close (base={} quote={})",
+                        key,
+                        base_updated,
+                        quote_updated
+                    );
+                }
                 continue;
             }
+            self.one_sided_bar_pairs.remove(&key);
 
             // Resolve BT deferred exits whose fill delay has elapsed
             // (bot-strategy#69). Must run before reconcile so the position
@@ -1436,7 +2362,7 @@ impl PairTradeEngine {
                                 key, self.cfg.bt_fill_delay_secs, now_ts
                             );
                             state.position = None;
-                            state.last_exit_at = Some(Instant::now());
+                            state.last_exit_at = Some(self.clock.now());
                             state.last_exit_ts = Some(now_ts);
                             state.bt_deferred_exit = None;
                         }
@@ -1445,19 +2371,57 @@ impl PairTradeEngine {
             }
 
             // First, reconcile any pending entry/exit orders for this pair
-            self.reconcile_pending_orders(inst_idx, &key, price_map).await?;
-
+            self.reconcile_pending_orders(inst_idx, &key, price_map, &mut prefetched_status)
+                .await?;
+            if let Some(state) = self.instances[inst_idx].states.get_mut(&key) {
+                if state.pending_entry.is_none() && state.pending_exit.is_none() {
+                    state.pending_since = None;
+                }
+            }
+
+            if self.cfg.skip_unchanged_bars
+                && self.bar_unchanged(&pair.base, updated)
+                && self.bar_unchanged(&pair.quote, updated)
+            {
+                // Flat pairs only: with a position or an order in flight the
+                // pair keeps running so force_close and the exit checks still
+                // fire on flat bars.
+                let idle = self.instances[inst_idx].states.get(&key).is_some_and(|s| {
+                    s.position.is_none()
+                        && s.pending_entry.is_none()
+                        && s.pending_exit.is_none()
+                        && s.bt_deferred_exit.is_none()
+                });
+                if idle {
+                    log::debug!("[EVAL] {} skipped: neither leg's close changed", key);
+                    continue;
+                }
+            }
+
             let mut action = TradeAction::None;
+            let mut close_reason: Option<&'static str> = None;
+            let mut entry_gate: Option<&'static str> = None;
+            let mut gated_open: Option<TradeAction> = None;
+            let mut rehedge_beta: Option<f64> = None;
+            let mut take_partial_profit = false;
+            let mut scale_in = false;
             let log_a = self
                 .latest_log_price(&pair.base)
                 .ok_or_else(|| anyhow!("no bar for {}", pair.base))?;
             let log_b = self
                 .latest_log_price(&pair.quote)
                 .ok_or_else(|| anyhow!("no bar for {}", pair.quote))?;
+            let bar_ts = self
+                .history
+                .get(&pair.base)
+                .and_then(|h| h.back())
+                .map(|s| s.ts)
+                .unwrap_or(now_ts);
 
             let (
                 prev_eligible,
                 z_snapshot,
+                spread_gap_bars,
                 last_eval_ts,
                 z_entry_copy,
                 spread_len,
@@ -1488,11 +2452,24 @@ impl PairTradeEngine {
                         }
                     }
                 }
-                let spread = log_a - state.beta * log_b;
-                state.push_spread(spread, self.cfg.metrics_window, &self.cfg);
+                let (leg_a, leg_b) = match (
+                    self.cfg.normalize_legs,
+                    self.history.get(&pair.base),
+                    self.history.get(&pair.quote),
+                ) {
+                    (true, Some(hist_a), Some(hist_b)) => (
+                        standardized_latest(hist_a, self.cfg.metrics_window).unwrap_or(log_a),
+                        standardized_latest(hist_b, self.cfg.metrics_window).unwrap_or(log_b),
+                    ),
+                    _ => (log_a, log_b),
+                };
+                let spread = leg_a - state.beta * leg_b;
+                state.push_spread(spread, bar_ts, self.cfg.metrics_window, &self.cfg);
+                state.eval_ticks += 1;
                 (
                     prev_eligible,
                     state.z_score_details(),
+                    state.max_spread_gap_bars(self.cfg.trading_period_secs),
                     state.last_evaluated_ts,
                     state.z_entry,
                     state.spread_history.len(),
@@ -1503,15 +2480,27 @@ impl PairTradeEngine {
                     state.beta_long,
                 )
             };
+            let non_finite_z = z_snapshot.and_then(|(z, std, mean, spread)| {
+                first_non_finite(&[("z", z), ("std", std), ("mean", mean), ("spread", spread)])
+            });
+            let z_snapshot = match non_finite_z {
+                Some((stat, value)) => {
+                    self.block_non_finite_stats(inst_idx, &key, stat, value, now_ts);
+                    None
+                }
+                None => z_snapshot,
+            };
 
             // [ZCHECK] Per-step alignment audit log. Designed for side-by-side
             // comparison across A/B/C bots running the same pair: if buckets are
             // properly aligned, identical bucket_ts rows should show identical
             // close/beta/mean/std/z values across processes. See pairtrade#4.
+            let log_zcheck =
+                self.log_sampled("zcheck", &key, self.cfg.log_zcheck_every_n_ticks);
             let base_bar = self.history.get(&pair.base).and_then(|h| h.back()).cloned();
             let quote_bar = self.history.get(&pair.quote).and_then(|h| h.back()).cloned();
             if let (Some(ba), Some(bq)) = (base_bar, quote_bar) {
-                if let Some((z, std, mean, latest)) = z_snapshot {
+                if let Some((z, std, mean, latest)) = z_snapshot.filter(|_| log_zcheck) {
                     log::info!(
                         "[ZCHECK] {} bucket_ts={} close_a={:.6} close_b={:.6} \
                          beta_eff={:.4} beta_s={:.4} beta_l={:.4} mean={:.6} std={:.6} \
@@ -1557,6 +2546,18 @@ impl PairTradeEngine {
             if force_close_due {
                 if let Some(pos) = &position_state {
                     log::info!("[EXIT_CHECK] {} reason=force_close", key);
+                    close_reason = Some("force_close");
+                    action = TradeAction::Close {
+                        direction: pos.direction,
+                        z: 0.0,
+                        beta: beta_eff,
+                        force: true,
+                    };
+                }
+            } else if self.retiring_pairs.contains(&key) {
+                if let Some(pos) = &position_state {
+                    log::info!("[EXIT_CHECK] {} reason=universe_removed", key);
+                    close_reason = Some("universe_removed");
                     action = TradeAction::Close {
                         direction: pos.direction,
                         z: 0.0,
@@ -1564,6 +2565,26 @@ impl PairTradeEngine {
                         force: true,
                     };
                 }
+            } else if funding_window_active && self.cfg.close_profitable_before_funding {
+                if let Some(pos) = &position_state {
+                    let pnl = compute_pnl(pos, p1.price, p2.price)
+                        .and_then(|p| p.to_f64())
+                        .unwrap_or(0.0);
+                    if pnl > 0.0 {
+                        log::info!(
+                            "[EXIT_CHECK] {} reason=funding_window pnl={:.4}",
+                            key,
+                            pnl
+                        );
+                        close_reason = Some("funding_window");
+                        action = TradeAction::Close {
+                            direction: pos.direction,
+                            z: 0.0,
+                            beta: beta_eff,
+                            force: false,
+                        };
+                    }
+                }
             }
 
             if self.instances[inst_idx].states[&key].pending_entry.is_some()
@@ -1582,14 +2603,14 @@ impl PairTradeEngine {
                             "[POSITION] {} in unhedged/mismatch state; skipping new actions",
                             key
                         );
-                        self.last_position_warn.insert(key.clone(), Instant::now());
+                        self.last_position_warn.insert(key.clone(), self.clock.now());
                     }
                     continue;
                 }
             }
 
             let needs_eval_interval = last_eval_ts
-                .map(|t| now_ts.saturating_sub(t) >= PAIR_SELECTION_INTERVAL_SECS as i64)
+                .map(|t| now_ts.saturating_sub(t) >= self.cfg.pair_eval_interval_secs as i64)
                 .unwrap_or(true);
             let needs_eval_jump = z_snapshot
                 .map(|(z, _, _, _)| z.abs() >= z_entry_copy * pp.reeval_jump_z_mult)
@@ -1642,7 +2663,14 @@ impl PairTradeEngine {
             };
             let eval = if should_eval
             {
-                let res = self.evaluate_pair(pair);
+                let mut res = self.evaluate_pair(pair);
+                let non_finite = res.as_ref().and_then(|e| e.non_finite_stat());
+                if let Some((stat, value)) = non_finite {
+                    // Keep the previous beta and eligibility rather than
+                    // adopting a garbage evaluation.
+                    self.block_non_finite_stats(inst_idx, &key, stat, value, now_ts);
+                    res = None;
+                }
                 if let Some(ref e) = res {
                     log::info!(
                         "[EVAL] {} beta_s={:.3} beta_l={:.3} beta={:.3} hl={:.2}h p={:.3} eligible={} score={:.3}",
@@ -1655,33 +2683,27 @@ impl PairTradeEngine {
                         e.eligible,
                         e.score
                     );
-                } else {
+                    if let Some(path) = self.cfg.eval_history_file.as_deref() {
+                        if let Err(err) = eval_history::append(Path::new(path), now_ts, &key, e) {
+                            log::warn!("[EVAL] failed to append eval history to {}: {:?}", path, err);
+                        }
+                    }
+                } else if non_finite.is_none() {
                     let (avail_a, avail_b) = (
                         self.history.get(&pair.base).map(|h| h.len()).unwrap_or(0),
                         self.history.get(&pair.quote).map(|h| h.len()).unwrap_or(0),
                     );
+                    let needed = pair_eval::warmup_bars_needed(&self.cfg, &key);
+                    let warmth_pct = avail_a.min(avail_b) as f64 / needed as f64 * 100.0;
                     log::debug!(
-                        "[EVAL] {} insufficient history ({}:{}, need long/short (strict) {} / {}, mode={:?})",
+                        "[EVAL] {} insufficient history ({}:{} {}:{}, need {}, warmth={:.0}%, mode={:?})",
                         key,
                         pair.base,
                         avail_a,
-                        pp.lookback_hours_long
-                            .max(pp.lookback_hours_short)
-                            * 3600
-                            / self.cfg.trading_period_secs,
-                        (pp.lookback_hours_short * 3600) / self.cfg.trading_period_secs,
-                        self.cfg.warm_start_mode
-                    );
-                    log::debug!(
-                        "[EVAL] {} insufficient history ({}:{}, need long/short (strict) {} / {}, mode={:?})",
-                        key,
                         pair.quote,
                         avail_b,
-                        pp.lookback_hours_long
-                            .max(pp.lookback_hours_short)
-                            * 3600
-                            / self.cfg.trading_period_secs,
-                        (pp.lookback_hours_short * 3600) / self.cfg.trading_period_secs,
+                        needed,
+                        warmth_pct.min(100.0),
                         self.cfg.warm_start_mode
                     );
                 }
@@ -1694,9 +2716,38 @@ impl PairTradeEngine {
             let circuit_breaker_until_ts_snapshot = self.instances[inst_idx].circuit_breaker_until_ts;
             let kill_switch_active_snapshot = self.kill_switch_active;
             let daily_loss_blocks_snapshot = self.daily_loss_blocks(&self.instances[inst_idx]);
+            let equity_guard_blocks_snapshot = self.equity_guard_blocks(&self.instances[inst_idx]);
+            let margin_block_snapshot = self.instances[inst_idx]
+                .margin_block_until_ts
+                .is_some_and(|until| now_ts < until);
+            // Non-short-circuiting so both legs' blocks get to lapse.
+            let slippage_blocked =
+                self.slippage_block_active(&pair.base) | self.slippage_block_active(&pair.quote);
             let session_halted_snapshot = self.instances[inst_idx].session_halted;
             let consecutive_losses_snapshot = self.instances[inst_idx].consecutive_losses;
             let equity_reference_snapshot = self.instances[inst_idx].equity_reference_usd;
+            // A scale-in adds exposure, so it answers to the account-level
+            // entry gates too.
+            let scale_in_block = [
+                (kill_switch_active_snapshot, "kill_switch"),
+                (session_halted_snapshot, "session_dd"),
+                (daily_loss_blocks_snapshot, "daily_loss"),
+                (equity_guard_blocks_snapshot, "equity_guard"),
+                (
+                    circuit_breaker_until_ts_snapshot.is_some_and(|until| now_ts < until),
+                    "circuit_breaker",
+                ),
+                (no_trade_window_active, "no_trade_window"),
+            ]
+            .into_iter()
+            .find_map(|(blocked, gate)| blocked.then_some(gate));
+            let warm = pair_eval::pair_warm_check(
+                &self.cfg,
+                &key,
+                spread_len,
+                self.history.get(&pair.base).map_or(0, |h| h.len()),
+                self.history.get(&pair.quote).map_or(0, |h| h.len()),
+            );
             {
                 let state = self
                     .instances[inst_idx]
@@ -1724,7 +2775,7 @@ impl PairTradeEngine {
                     state.eligible = eval.eligible;
                     state.p_value_weighted_score = eval.score;
                     state.beta_gap = eval.beta_gap;
-                    state.last_evaluated = Some(Instant::now());
+                    state.last_evaluated = Some(self.clock.now());
                     state.last_evaluated_ts = Some(now_ts);
                 }
                 if prev_eligible != state.eligible {
@@ -1737,105 +2788,260 @@ impl PairTradeEngine {
                         (state.beta_short - state.beta_long).abs()
                     );
                 }
+                let (long_ok, short_ok) = directional_eligibility(&self.cfg, state.eligible, p1, p2);
+                if self.cfg.directional_eligibility
+                    && (long_ok, short_ok) != (state.eligible_long_spread, state.eligible_short_spread)
+                {
+                    log::info!(
+                        "[ELIGIBILITY] {} directional long_spread={} short_spread={}",
+                        key,
+                        long_ok,
+                        short_ok
+                    );
+                }
+                state.eligible_long_spread = long_ok;
+                state.eligible_short_spread = short_ok;
 
                 let z_entry = entry_z_for_pair(&self.cfg, pp, state, vol_median);
                 state.z_entry = z_entry;
 
-                let min_points = (self.cfg.metrics_window / 2).max(10);
                 if matches!(action, TradeAction::None) {
-                    if state.eligible && spread_len >= min_points {
+                    // The warm check gates entries only; an open position
+                    // keeps its exits and stops.
+                    if state.eligible && (warm.is_ok() || state.position.is_some()) {
                         if let Some((z, std, mean, latest_spread)) = z_snapshot {
                             let net_funding = net_funding_for_direction(z, p1, p2);
                             if let Some(pos) = &state.position {
                                 let equity_base = equity_reference_snapshot;
                                 if let Some(reason) =
-                                    exit_reason(&self.cfg, pp, state, z, std, p1, p2, equity_base, now_ts)
+                                    exit_reason(&self.cfg, &key, pp, state, z, std, p1, p2, equity_base, now_ts)
                                 {
                                     log::info!(
-                                    "[EXIT_CHECK] {} reason={} z={:.2} exit_z={:.2} stop_z={:.2} vel={:.3} max_vel={:.3}",
-                                    key,
-                                    reason,
-                                    z,
-                                    pp.exit_z,
-                                    pp.stop_loss_z,
-                                    state.last_velocity_sigma_per_min,
-                                    pp.spread_velocity_max_sigma_per_min
-                                );
+                                        "[EXIT_CHECK] {} reason={} z={:.2} exit_z={:.2} stop_z={:.2} vel={:.3} max_vel={:.3}",
+                                        key,
+                                        reason,
+                                        z,
+                                        pp.exit_z,
+                                        pp.stop_loss_z,
+                                        state.last_velocity_sigma_per_min,
+                                        pp.spread_velocity_max_sigma_per_min
+                                    );
+                                    close_reason = Some(reason);
                                     action = TradeAction::Close {
                                         direction: pos.direction,
                                         z,
                                         beta: state.beta,
-                                        force: false,
+                                        force: self.cfg.exit_uses_market(reason),
                                     };
+                                } else if partial_take_profit_hit(&self.cfg, pos, p1, p2, equity_base) {
+                                    take_partial_profit = true;
+                                } else if scale_in_due(&self.cfg, pp, pos, z)
+                                    && match scale_in_block {
+                                        Some(gate) => {
+                                            log::debug!("[SCALE_IN] {} blocked by {}", key, gate);
+                                            false
+                                        }
+                                        None => true,
+                                    }
+                                {
+                                    scale_in = true;
+                                } else if self.cfg.beta_drift_action == BetaDriftAction::Rehedge
+                                    && beta_drift_breached(&self.cfg, pos, state.beta)
+                                {
+                                    rehedge_beta = Some(state.beta);
+                                }
+                            } else if let Err(unmet) = &warm {
+                                if log_zcheck {
+                                    log::debug!("[ZCHECK] {} skipped (not warm: {})", key, unmet);
                                 }
                             } else if !self.positions_ready {
+                                entry_gate = Some("positions_not_ready");
                                 log_positions_not_ready = true;
+                            } else if state.stats_blocked(now_ts) {
+                                entry_gate = Some("non_finite_stats");
+                                // entry blocked after a NaN/inf statistic;
+                                // logged as [NON_FINITE] on detection
+                            } else if state.loss_reentry_blocked(self.cfg.loss_reentry_block_ticks) {
+                                entry_gate = Some("loss_reentry");
+                                if log_zcheck {
+                                    log::debug!(
+                                        "[ENTRY] {} blocked loss_reentry: losing exit {} ticks ago (<= {})",
+                                        key,
+                                        state.eval_ticks - state.last_loss_exit_tick.unwrap_or_default(),
+                                        self.cfg.loss_reentry_block_ticks
+                                    );
+                                }
                             } else if kill_switch_active_snapshot {
+                                entry_gate = Some("kill_switch");
                                 // entry blocked by KILL_SWITCH sentinel file;
                                 // engagement/release is logged in step_shared
                             } else if session_halted_snapshot {
+                                entry_gate = Some("session_dd");
                                 // entry blocked by Phase 3-1 session-DD halt.
                                 // The trip + flatten was logged in
                                 // evaluate_session_dd; clearing requires a
                                 // manual ack at /opt/debot/RISK_ACK.
                             } else if daily_loss_blocks_snapshot {
+                                entry_gate = Some("daily_loss");
                                 // entry blocked by daily DD threshold; the
                                 // exact PnL / bps is surfaced in status.json
                                 // and re-logged on session rollover via
                                 // [DAILY_DD]. Existing positions still exit
                                 // through the usual exit_reason paths.
+                            } else if equity_guard_blocks_snapshot {
+                                entry_gate = Some("equity_guard");
+                                // entry blocked by min_equity_usd / repeated
+                                // balance-fetch failures; transitions are
+                                // logged via [EQUITY_GUARD].
+                            } else if funding_window_active {
+                                entry_gate = Some("funding_window");
+                                // entry blocked: too close to the next
+                                // funding time (avoid_funding_window_secs).
+                            } else if no_trade_window_active {
+                                entry_gate = Some("no_trade_window");
+                                // entry blocked by a no_trade_windows entry;
+                                // transitions are logged via [SCHEDULE].
+                            } else if margin_block_snapshot {
+                                entry_gate = Some("insufficient_margin");
+                                // entry blocked after an insufficient-margin
+                                // reject; logged via [ORDER_REJECT].
+                            } else if slippage_blocked {
+                                entry_gate = Some("slippage");
+                                // entry blocked: a leg's rolling realized
+                                // slippage exceeds max_realized_slippage_bps;
+                                // transitions are logged via [SLIPPAGE].
+                            } else if pp.min_spread_std > 0.0 && std < pp.min_spread_std {
+                                entry_gate = Some("flat_spread");
+                                // Near-constant spread: z is meaningless, so
+                                // the pair is untradeable until std recovers.
+                                if log_zcheck {
+                                    log::debug!(
+                                        "[ZCHECK] {} skipped flat (std {:.6} < min_spread_std {:.6})",
+                                        key,
+                                        std,
+                                        pp.min_spread_std
+                                    );
+                                }
+                            } else if self.cfg.max_spread_gap_bars > 0
+                                && spread_gap_bars > self.cfg.max_spread_gap_bars
+                            {
+                                // z mixes bars from both sides of a downtime
+                                // gap; exits still use it, entries wait until
+                                // the gap rolls out of the window.
+                                entry_gate = Some("spread_gap");
+                                if log_zcheck {
+                                    log::debug!(
+                                        "[ZCHECK] {} skipped (gap: {} missing bars > {})",
+                                        key,
+                                        spread_gap_bars,
+                                        self.cfg.max_spread_gap_bars
+                                    );
+                                }
                             } else if circuit_breaker_until_ts_snapshot
                                 .map_or(false, |until| now_ts < until)
                             {
                                 // entry blocked by circuit breaker; logged via ZCHECK
+                                entry_gate = Some("circuit_breaker");
                             } else if last_eval_ts.is_none() {
                                 // Block entry until first evaluate_pair() completes,
                                 // because beta is still at its initial value (1.0).
+                                entry_gate = Some("not_evaluated");
+                            } else if self.cfg.eligibility_stale(state.last_evaluated_ts, now_ts) {
+                                // Eligibility rests on a stale evaluation;
+                                // wait for the next evaluate_pair().
+                                entry_gate = Some("stale_evaluation");
+                                log::debug!(
+                                    "[ENTRY] {} blocked: evaluation older than {}s",
+                                    key,
+                                    self.cfg.eligibility_max_age_secs
+                                );
+                            } else if self.cfg.directional_eligibility
+                                && !(if z > 0.0 {
+                                    state.eligible_short_spread
+                                } else {
+                                    state.eligible_long_spread
+                                })
+                            {
+                                // The direction z points to fails its own
+                                // carry check; the other side may still be
+                                // eligible.
+                                entry_gate = Some("direction_not_eligible");
+                                if log_zcheck {
+                                    log::debug!(
+                                        "[ZCHECK] {} skipped (direction not eligible: z={:.2} funding={:.5})",
+                                        key,
+                                        z,
+                                        net_funding
+                                    );
+                                }
                             } else if !regime_ok {
                                 // entry blocked by regime filter
-                            } else if should_enter(&self.cfg, pp, state, z, std, net_funding, now_ts) {
-                                let direction = if z > 0.0 {
-                                    PositionDirection::ShortSpread
+                                entry_gate = Some("regime");
+                            } else if should_enter(&self.cfg, &key, pp, state, z, std, net_funding, now_ts) {
+                                if extreme_funding_blocks(&self.cfg, &key, z, p1, p2) {
+                                    entry_gate = Some("extreme_funding");
+                                } else if wide_book_blocks(&self.cfg, &key, z, p1, p2) {
+                                    entry_gate = Some("wide_book");
                                 } else {
-                                    PositionDirection::LongSpread
-                                };
-                                action = TradeAction::Open {
-                                    direction,
+                                    action = TradeAction::Open {
+                                        direction: spread_direction_for_z(z),
+                                        z,
+                                        beta: state.beta,
+                                    };
+                                }
+                            }
+                            if entry_gate.is_some()
+                                && self.cfg.rejected_entries_file.is_some()
+                                && z.abs()
+                                    >= entry_threshold(&self.cfg, &key, pp, state, std, net_funding).required()
+                            {
+                                gated_open = Some(TradeAction::Open {
+                                    direction: spread_direction_for_z(z),
                                     z,
                                     beta: state.beta,
-                                };
+                                });
+                            }
+                            if log_zcheck {
+                                let slope_sig =
+                                    spread_slope_sigma(&state.spread_history, self.cfg.metrics_window);
+                                log::debug!(
+                                    "[ZCHECK] {} z={:.2} entry={:.2} std={:.4} mean={:.4} spread={:.4} hist={} beta_s={:.3} beta_l={:.3} funding={:.5} eligible={} beta_gap={:.3} slope_sigma={:.3} consec_loss={}",
+                                    key,
+                                    z,
+                                    state.z_entry,
+                                    std,
+                                    mean,
+                                    latest_spread,
+                                    spread_len,
+                                    beta_short,
+                                    beta_long,
+                                    net_funding,
+                                    state.eligible,
+                                    state.beta_gap,
+                                    slope_sig.unwrap_or(0.0),
+                                    consecutive_losses_snapshot
+                                );
+                                let threshold =
+                                    entry_threshold(&self.cfg, &key, pp, state, std, net_funding);
+                                log::debug!(
+                                    "[ENTRY_THRESHOLD] {} z={:.2} base_z={:.2} alpha={:.3} z_entry={:.2} funding_x={:.3} beta_gap_x={:.3} cost_sigma={:.3} required={:.2}",
+                                    key,
+                                    z,
+                                    pp.entry_z_base,
+                                    entry_z_alpha(&self.cfg, pp, state, vol_median),
+                                    threshold.z_entry,
+                                    threshold.funding_factor,
+                                    threshold.beta_gap_factor,
+                                    threshold.cost_in_sigma,
+                                    threshold.required()
+                                );
                             }
-                            let slope_sig =
-                                spread_slope_sigma(&state.spread_history, self.cfg.metrics_window);
-                            log::debug!(
-                            "[ZCHECK] {} z={:.2} entry={:.2} std={:.4} mean={:.4} spread={:.4} hist={} beta_s={:.3} beta_l={:.3} funding={:.5} eligible={} beta_gap={:.3} slope_sigma={:.3} consec_loss={}",
-                            key,
-                            z,
-                            state.z_entry,
-                            std,
-                            mean,
-                            latest_spread,
-                            spread_len,
-                            beta_short,
-                            beta_long,
-                            net_funding,
-                            state.eligible,
-                            state.beta_gap,
-                            slope_sig.unwrap_or(0.0),
-                            consecutive_losses_snapshot
-                        );
                         }
-                    } else if state.eligible && spread_len < min_points {
-                        log::debug!(
-                            "[ZCHECK] {} skipped (spread history too short: {} < {})",
-                            key,
-                            spread_len,
-                            min_points
-                        );
                     } else if position_state.is_some() && !state.eligible {
                         // If pair falls out of eligibility, flatten
                         if let Some(pos) = &state.position {
                             log::info!("[EXIT_CHECK] {} reason=ineligible", key);
+                            close_reason = Some("ineligible");
                             action = TradeAction::Close {
                                 direction: pos.direction,
                                 z: 0.0,
@@ -1846,22 +3052,67 @@ impl PairTradeEngine {
                     }
                 }
             }
-            if !positions_clear && matches!(action, TradeAction::Open { .. }) {
+            let late_gate = if !matches!(action, TradeAction::Open { .. }) {
+                None
+            } else if !positions_clear {
                 log::debug!("[ENTRY] blocked due to open positions; key={}", key);
-                action = TradeAction::None;
-            }
-            if maintenance_block_entries && matches!(action, TradeAction::Open { .. }) {
-                action = TradeAction::None;
-            }
-            if self.shutdown_pending && matches!(action, TradeAction::Open { .. }) {
+                Some("open_positions")
+            } else if maintenance_block_entries {
+                Some("maintenance")
+            } else if self.shutdown_pending {
                 log::debug!("[ENTRY] blocked by graceful shutdown; key={}", key);
-                action = TradeAction::None;
+                Some("shutdown")
+            } else if self.retiring_pairs.contains(&key) {
+                log::debug!("[ENTRY] blocked: pair is retiring; key={}", key);
+                Some("retiring")
+            } else if self.instances[inst_idx].open_orders_blocked {
+                log::debug!("[ENTRY] blocked: open orders over max_open_orders; key={}", key);
+                Some("max_open_orders")
+            } else {
+                None
+            };
+            if let Some(gate) = late_gate {
+                entry_gate = Some(gate);
+                gated_open = Some(std::mem::replace(&mut action, TradeAction::None));
+            }
+            if let (Some(gate), Some(TradeAction::Open { direction, z, beta })) =
+                (entry_gate, gated_open)
+            {
+                gated_entries.push((
+                    PlannedAction {
+                        pair: pair.clone(),
+                        key: key.clone(),
+                        action: TradeAction::Open { direction, z, beta },
+                        net_funding_per_hour: net_funding_for_direction(z, p1, p2),
+                        abs_z: z.abs(),
+                        liquidity_score: liquidity_score(p1, p2),
+                        p1: p1.clone(),
+                        p2: p2.clone(),
+                    },
+                    gate,
+                ));
             }
 
             if log_positions_not_ready && self.should_log_position_warn(&self.cfg.dex_name) {
                 log::warn!("[POSITION] positions not synced yet; skipping entry");
                 self.last_position_warn
-                    .insert(self.cfg.dex_name.clone(), Instant::now());
+                    .insert(self.cfg.dex_name.clone(), self.clock.now());
+            }
+
+            if let (TradeAction::Close { .. }, Some(reason)) = (&action, close_reason) {
+                if let Some(state) = self.instances[inst_idx].states.get_mut(&key) {
+                    state.last_exit_reason = Some(reason);
+                }
+            }
+            if let Some(beta) = rehedge_beta {
+                self.rehedge_position(inst_idx, &key, pair, beta, price_map, "[BETA_DRIFT]")
+                    .await;
+            }
+            if take_partial_profit {
+                self.take_partial_profit(inst_idx, &key, pair, price_map).await;
+            }
+            if scale_in {
+                self.scale_in(inst_idx, &key, pair, price_map).await;
             }
 
             if !matches!(action, TradeAction::None) {
@@ -1892,6 +3143,7 @@ impl PairTradeEngine {
         }
 
         self.maybe_log_metrics(inst_idx);
+        self.check_idle_pairs(inst_idx, now_ts);
         // Process exits first
         for plan in planned.iter() {
             if let TradeAction::Close {
@@ -1902,7 +3154,9 @@ impl PairTradeEngine {
             } = plan.action
             {
                 let qtys = self
-                    .exit_sizes_for_pair(inst_idx, &plan.key, &plan.pair, beta, &plan.p1, &plan.p2)
+                    .exit_sizes_for_pair(
+                        inst_idx, &plan.key, &plan.pair, direction, beta, &plan.p1, &plan.p2,
+                    )
                     .context("exit_sizes_for_pair")?;
                 if qtys.0 <= Decimal::ZERO && qtys.1 <= Decimal::ZERO {
                     log::warn!(
@@ -1913,7 +3167,7 @@ impl PairTradeEngine {
                         state.position = None;
                         state.pending_exit = None;
                         state.position_guard = false;
-                        state.last_exit_at = Some(Instant::now());
+                        state.last_exit_at = Some(self.clock.now());
                         state.last_exit_ts = Some(now_ts);
                     }
                     continue;
@@ -1926,7 +3180,7 @@ impl PairTradeEngine {
                         qtys.1
                     );
                 }
-                if self.cfg.dry_run {
+                if self.cfg.paper_fills() {
                     let price_a = price_map
                         .get(&plan.pair.base)
                         .map(|s| s.price)
@@ -1959,7 +3213,7 @@ impl PairTradeEngine {
                                 direction,
                                 pnl_value,
                                 now_ts,
-                                "exit_dry_run",
+                                if self.cfg.dry_run { "exit_dry_run" } else { "exit_observe_paper" },
                             ).with_trade_details(
                                 entry_a, entry_b,
                                 price_a.to_f64(), price_b.to_f64(),
@@ -1967,8 +3221,14 @@ impl PairTradeEngine {
                                 self.instances[inst_idx].states.get(&plan.key)
                                     .and_then(|s| s.last_spread.map(|_| z)),
                                 hold_secs,
+                            ).with_exit_reason(
+                                self.instances[inst_idx].states.get(&plan.key)
+                                    .and_then(|s| s.last_exit_reason),
                             );
                             self.write_pnl_record(inst_idx, record);
+                            if let Some(state) = self.instances[inst_idx].states.get_mut(&plan.key) {
+                                state.record_exit_pnl(pnl_value);
+                            }
                             self.instances[inst_idx].realized_pnl_today += pnl_value;
                             let mut risk_state_dirty = pnl_value != 0.0;
                             if pnl_value < 0.0 {
@@ -1978,7 +3238,7 @@ impl PairTradeEngine {
                                     .cfg
                                     .circuit_breaker_cooldown_for(self.instances[inst_idx].consecutive_losses)
                                 {
-                                    self.instances[inst_idx].circuit_breaker_until = Some(Instant::now() + cooldown);
+                                    self.instances[inst_idx].circuit_breaker_until = Some(self.clock.now() + cooldown);
                                     self.instances[inst_idx].circuit_breaker_until_ts =
                                         Some(now_ts + cooldown.as_secs() as i64);
                                     log::warn!(
@@ -2039,7 +3299,7 @@ impl PairTradeEngine {
                             });
                         } else {
                             state.position = None;
-                            state.last_exit_at = Some(Instant::now());
+                            state.last_exit_at = Some(self.clock.now());
                             state.last_exit_ts = Some(now_ts);
                         }
                     }
@@ -2050,8 +3310,20 @@ impl PairTradeEngine {
                         plan.pair.quote
                     );
                 } else {
+                    let exit_state = self.instances[inst_idx].states.get(&plan.key);
+                    let hedge_inverted = exit_state
+                        .and_then(|s| s.position.as_ref())
+                        .is_some_and(|p| p.hedge_inverted);
+                    let reason = exit_state.and_then(|s| s.last_exit_reason).unwrap_or("exit");
                     let legs = match self
-                        .close_pair_orders(&plan.pair, direction, qtys, price_map, force)
+                        .close_pair_orders(
+                            &plan.pair,
+                            direction.exit_sides(hedge_inverted),
+                            qtys,
+                            price_map,
+                            force,
+                            reason,
+                        )
                         .await
                     {
                         Ok(legs) => legs,
@@ -2064,9 +3336,11 @@ impl PairTradeEngine {
                         state.pending_exit = Some(PendingOrders {
                             legs,
                             direction,
-                            placed_at: Instant::now(),
+                            placed_at: self.clock.now(),
                             hedge_retry_count: 0,
                             post_only_hybrid: false,
+                            partial_exit: false,
+                            scale_in: false,
                         });
                     }
                 }
@@ -2101,17 +3375,26 @@ impl PairTradeEngine {
                 active_symbols.insert(symbol.clone());
             }
         }
+        let symbol_exposure = match self.cfg.shared_symbol_policy {
+            SharedSymbolPolicy::Block => None,
+            SharedSymbolPolicy::NetExposure => Some(self.symbol_exposure_usd(inst_idx, price_map)),
+        };
 
         // Among entry candidates, shortlist by model score then pick best by funding->score->liquidity->|z|
-        let mut entry_candidates: Vec<&PlannedAction> = planned
+        let mut rejected: Vec<(&PlannedAction, &'static str)> =
+            gated_entries.iter().map(|(plan, gate)| (plan, *gate)).collect();
+        let (entry_candidates, overlapping): (Vec<&PlannedAction>, Vec<&PlannedAction>) = planned
             .iter()
             .filter(|p| matches!(p.action, TradeAction::Open { .. }))
-            .filter(|p| {
+            .partition(|p| {
                 if active_symbols.is_empty() {
                     return true;
                 }
                 let overlaps =
                     active_symbols.contains(&p.pair.base) || active_symbols.contains(&p.pair.quote);
+                if let (true, Some((exposure, unknown))) = (overlaps, &symbol_exposure) {
+                    return self.shared_symbol_entry_allowed(inst_idx, p, exposure, unknown);
+                }
                 if overlaps {
                     log::debug!(
                         "[OVERLAP] skipping {}/{} due to active symbol overlap",
@@ -2120,20 +3403,90 @@ impl PairTradeEngine {
                     );
                 }
                 !overlaps
-            })
-            .collect();
+            });
+        rejected.extend(overlapping.into_iter().map(|p| (p, "symbol_overlap")));
+        let min_notional = self.cfg.min_book_notional_usd;
+        let (mut entry_candidates, thin): (Vec<&PlannedAction>, Vec<&PlannedAction>) =
+            entry_candidates.into_iter().partition(|p| {
+                min_notional <= 0.0
+                    || (book_notional_usd(&p.p1) >= min_notional
+                        && book_notional_usd(&p.p2) >= min_notional)
+            });
+        for p in &thin {
+            // A thin book stays thin for a while; repeat the line at most
+            // every position-warn interval per pair.
+            let warn_key = format!("thin_book:{}:{}", self.instances[inst_idx].id, p.key);
+            if self.should_log_position_warn(&warn_key) {
+                log::info!(
+                    "[ENTRY] blocked liquidity {}/{}: book notional {:.2} / {:.2} < {:.2}",
+                    p.pair.base,
+                    p.pair.quote,
+                    book_notional_usd(&p.p1),
+                    book_notional_usd(&p.p2),
+                    min_notional
+                );
+                self.last_position_warn.insert(warn_key, self.clock.now());
+            }
+        }
+        rejected.extend(thin.into_iter().map(|p| (p, "thin_book")));
+        if !self.cfg.single_position_mode && !entry_candidates.is_empty() {
+            let active_pairs = self.instances[inst_idx]
+                .states
+                .values()
+                .filter(|s| s.position.is_some() || s.pending_entry.is_some())
+                .count();
+            if active_pairs >= self.cfg.max_active_pairs.max(1) {
+                log::debug!(
+                    "[ENTRY] blocked: {} active pairs >= max_active_pairs {}",
+                    active_pairs,
+                    self.cfg.max_active_pairs
+                );
+                rejected.extend(entry_candidates.drain(..).map(|p| (p, "max_active_pairs")));
+            }
+        }
+        if self.cfg.min_eligible_pairs > 1 && !entry_candidates.is_empty() {
+            let eligible_pairs = self.instances[inst_idx]
+                .states
+                .values()
+                .filter(|s| s.eligible)
+                .count();
+            if eligible_pairs < self.cfg.min_eligible_pairs {
+                // Breadth changes slowly; repeat the line at most every
+                // position-warn interval.
+                let warn_key = format!("insufficient_breadth:{}", self.instances[inst_idx].id);
+                if self.should_log_position_warn(&warn_key) {
+                    log::info!(
+                        "[ENTRY] blocked insufficient_breadth: {} eligible pairs < min_eligible_pairs {}",
+                        eligible_pairs,
+                        self.cfg.min_eligible_pairs
+                    );
+                    self.last_position_warn.insert(warn_key, self.clock.now());
+                }
+                rejected.extend(entry_candidates.drain(..).map(|p| (p, "insufficient_breadth")));
+            }
+        }
         entry_candidates.sort_by(|a, b| {
             self.state_score(inst_idx, &b.key)
                 .partial_cmp(&self.state_score(inst_idx, &a.key))
                 .unwrap_or(Ordering::Equal)
         });
-        let shortlisted: Vec<&PlannedAction> = entry_candidates
-            .into_iter()
-            .take(self.cfg.max_active_pairs.max(1))
-            .collect();
-        let best_entry = shortlisted.into_iter().max_by(|a, b| {
-            a.net_funding_per_hour
-                .partial_cmp(&b.net_funding_per_hour)
+        let shortlist_len = entry_candidates.len().min(self.cfg.max_active_pairs.max(1));
+        rejected.extend(entry_candidates.drain(shortlist_len..).map(|p| (p, "not_shortlisted")));
+        let shortlisted: Vec<&PlannedAction> = entry_candidates;
+        let portfolio_funding = if self.cfg.portfolio_funding_aware {
+            self.portfolio_funding_scores(inst_idx, &shortlisted, price_map)
+        } else {
+            HashMap::new()
+        };
+        let funding_of = |p: &PlannedAction| {
+            portfolio_funding
+                .get(&p.key)
+                .copied()
+                .unwrap_or(p.net_funding_per_hour)
+        };
+        let best_entry = shortlisted.iter().copied().max_by(|a, b| {
+            funding_of(a)
+                .partial_cmp(&funding_of(b))
                 .unwrap_or(Ordering::Equal)
                 .then_with(|| {
                     self.state_score(inst_idx, &a.key)
@@ -2147,6 +3500,17 @@ impl PairTradeEngine {
                 })
                 .then_with(|| a.abs_z.partial_cmp(&b.abs_z).unwrap_or(Ordering::Equal))
         });
+        if let Some(winner) = best_entry {
+            self.log_entry_selection(inst_idx, &shortlisted, &winner.key);
+            rejected.extend(
+                shortlisted
+                    .iter()
+                    .filter(|p| p.key != winner.key)
+                    .map(|p| (*p, "not_selected")),
+            );
+        }
+        self.record_rejected_entries(inst_idx, &rejected, now_ts);
+        self.record_plan_trace(inst_idx, &planned, best_entry, now_ts);
         if let Some(plan) = best_entry {
             if let TradeAction::Open { direction, z, beta } = plan.action {
                 // Force-fresh equity immediately before sizing: entries happen
@@ -2155,9 +3519,12 @@ impl PairTradeEngine {
                 // 30-min cache used for dashboard / R-budget. See
                 // bot-strategy#156.
                 self.fetch_equity_rest(inst_idx).await;
+                let equity_guard_blocked = self.equity_guard_blocks(&self.instances[inst_idx]);
                 let qtys = self
-                    .hedged_sizes(inst_idx, &plan.pair, beta, &plan.p1, &plan.p2)
+                    .hedged_sizes(inst_idx, &plan.pair, direction, beta, &plan.p1, &plan.p2)
                     .context("hedged_sizes")?;
+                let qtys = self.scout_sizes(&plan.pair, qtys, price_map);
+                let scale_in_add = scale_in_remainder(&self.cfg, qtys);
                 let price_a = price_map
                     .get(&plan.pair.base)
                     .map(|s| s.price)
@@ -2166,7 +3533,70 @@ impl PairTradeEngine {
                     .get(&plan.pair.quote)
                     .map(|s| s.price)
                     .unwrap_or_default();
-                if self.cfg.dry_run {
+                let qtys = if self.cfg.paper_fills() {
+                    let filled = sizing::paper_entry_qtys(
+                        self.cfg.matching_model,
+                        direction,
+                        beta < 0.0,
+                        qtys,
+                        &plan.p1,
+                        &plan.p2,
+                    );
+                    if filled != qtys {
+                        log::info!(
+                            "[PAPER] {}/{} {:?} fill size_a={}->{} size_b={}->{}",
+                            plan.pair.base,
+                            plan.pair.quote,
+                            self.cfg.matching_model,
+                            qtys.0,
+                            filled.0,
+                            qtys.1,
+                            filled.1
+                        );
+                    }
+                    filled
+                } else {
+                    qtys
+                };
+                if equity_guard_blocked {
+                    log::warn!(
+                        "[ENTRY] {}/{} skipped: equity guard engaged after pre-sizing balance fetch",
+                        plan.pair.base,
+                        plan.pair.quote
+                    );
+                    self.record_rejected_entries(inst_idx, &[(plan, "equity_guard")], now_ts);
+                } else if qtys.0 <= Decimal::ZERO || qtys.1 <= Decimal::ZERO {
+                    log::warn!(
+                        "[ENTRY] {}/{} skipped: sized to zero (size_a={} size_b={})",
+                        plan.pair.base,
+                        plan.pair.quote,
+                        qtys.0,
+                        qtys.1
+                    );
+                    self.record_rejected_entries(inst_idx, &[(plan, "sized_to_zero")], now_ts);
+                } else if self.cfg.paper_fills() {
+                    let (price_a, price_b) = if self.cfg.fills_include_costs() {
+                        let cost_a = self.cfg.backtest_round_trip_cost_bps(&plan.pair.base);
+                        let cost_b = self.cfg.backtest_round_trip_cost_bps(&plan.pair.quote);
+                        let (side_a, side_b) = direction.entry_sides(beta < 0.0);
+                        let fill_a = order_pricing::cost_adjusted_fill(price_a, side_a, cost_a);
+                        let fill_b = order_pricing::cost_adjusted_fill(price_b, side_b, cost_b);
+                        log::debug!(
+                            "[BT_COST] {}/{} cost_bps={:.2}/{:.2} (slippage={}, round trip) price_a={}->{} price_b={}->{}",
+                            plan.pair.base,
+                            plan.pair.quote,
+                            cost_a,
+                            cost_b,
+                            self.cfg.slippage_cost_bps(),
+                            price_a,
+                            fill_a,
+                            price_b,
+                            fill_b
+                        );
+                        (fill_a, fill_b)
+                    } else {
+                        (price_a, price_b)
+                    };
                     log::info!(
                             "[ENTRY] pair={}/{} direction={:?} size_a={} price_a={} size_b={} price_b={} z={:.2} beta={:.2} carry={:.4} ts={}",
                             plan.pair.base,
@@ -2184,14 +3614,21 @@ impl PairTradeEngine {
                     if let Some(state) = self.instances[inst_idx].states.get_mut(&plan.key) {
                         state.position = Some(Position {
                             direction,
-                            entered_at: Instant::now(),
+                            entered_at: self.clock.now(),
                             entered_ts: now_ts,
                             entry_price_a: Some(price_a),
                             entry_price_b: Some(price_b),
                             entry_size_a: Some(qtys.0),
                             entry_size_b: Some(qtys.1),
                             entry_z: Some(z),
+                            entry_beta: Some(beta),
+                            entry_fill_cash: None,
+                            partial_taken: false,
+                            scaled_in: false,
+                            scale_in_add,
+                            hedge_inverted: beta < 0.0,
                         });
+                        state.mark_entry(now_ts);
                     }
                 } else if self.cfg.observe_only {
                     log::info!(
@@ -2215,7 +3652,14 @@ impl PairTradeEngine {
                         now_ts
                     );
                     let legs = match self
-                        .place_pair_orders(inst_idx, &plan.pair, direction, qtys, price_map)
+                        .place_pair_orders(
+                            inst_idx,
+                            &plan.pair,
+                            direction,
+                            beta < 0.0,
+                            qtys,
+                            price_map,
+                        )
                         .await
                     {
                         Ok(legs) => legs,
@@ -2232,17 +3676,24 @@ impl PairTradeEngine {
                     if !legs.is_empty() {
                         let entry_pp = self.pair_params_for(inst_idx, &plan.key).clone();
                         let entry_pp = &entry_pp;
-                        let hybrid = entry_pp.entry_post_only_timeout_secs > 0
+                        let hybrid = !self.cfg.entry_market_mode
+                            && entry_pp.entry_post_only_timeout_secs > 0
                             && self.post_only_supported();
                         if let Some(state) = self.instances[inst_idx].states.get_mut(&plan.key) {
                             state.pending_entry = Some(PendingOrders {
                                 legs,
                                 direction,
-                                placed_at: Instant::now(),
+                                placed_at: self.clock.now(),
                                 hedge_retry_count: 0,
                                 post_only_hybrid: hybrid,
+                                partial_exit: false,
+                                scale_in: false,
                             });
+                            state.mark_entry(now_ts);
                         }
+                        // Record the placement so a crash before the fills
+                        // are confirmed can still recover the entry time.
+                        self.persist_risk_state();
                     }
                 }
             }
@@ -2251,9 +3702,23 @@ impl PairTradeEngine {
         {
             let risk = self.daily_risk_snapshot(inst_idx);
             let session_risk = self.session_risk_snapshot(inst_idx);
+            let pair_stats = self.instances[inst_idx].trade_stats.clone();
+            let aging = self.aging_positions(inst_idx, now_ts);
+            let size_adjustments = if self.cfg.status_size_adjustments {
+                self.size_adjustments.clone()
+            } else {
+                BTreeMap::new()
+            };
+            let size_decimals = price_map
+                .iter()
+                .filter_map(|(symbol, snap)| Some((symbol.clone(), snap.size_decimals?)));
             if let Some(reporter) = &mut self.instances[inst_idx].status_reporter {
+                reporter.update_size_decimals(size_decimals);
                 reporter.set_daily_risk(risk);
                 reporter.set_session_risk(session_risk);
+                reporter.set_pair_stats(Some(pair_stats));
+                reporter.set_aging(aging);
+                reporter.set_size_adjustments(size_adjustments);
                 if let Err(err) =
                     reporter.write_snapshot_if_due(&self.open_positions, self.positions_ready)
                 {
@@ -2264,6 +3729,49 @@ impl PairTradeEngine {
         Ok(())
     }
 
+    /// Positions held at least `position_age_warn_secs`, oldest first, with
+    /// a `[POSITION] aging` warning per pair at most every 5 minutes.
+    fn aging_positions(&mut self, inst_idx: usize, now_ts: i64) -> Vec<AgingPosition> {
+        let warn_secs = self.cfg.position_age_warn_secs;
+        if warn_secs == 0 {
+            return Vec::new();
+        }
+        let inst = &self.instances[inst_idx];
+        let mut aging: Vec<AgingPosition> = inst
+            .states
+            .iter()
+            .filter_map(|(key, state)| {
+                let pos = state.position.as_ref()?;
+                let age_secs = now_ts.saturating_sub(pos.entered_ts).max(0);
+                if age_secs < warn_secs as i64 {
+                    return None;
+                }
+                let pp = inst.pair_params.get(key).unwrap_or(&inst.default_pair_params);
+                Some(AgingPosition {
+                    key: key.clone(),
+                    entered_ts: pos.entered_ts,
+                    age_secs,
+                    force_close_eta_ts: pos.entered_ts + pp.force_close_secs as i64,
+                })
+            })
+            .collect();
+        aging.sort_by_key(|a| a.entered_ts);
+        for a in &aging {
+            let warn_key = format!("aging:{}", a.key);
+            if self.should_log_position_warn(&warn_key) {
+                log::warn!(
+                    "[POSITION] aging {} held={}s warn_after={}s force_close_in={}s",
+                    a.key,
+                    a.age_secs,
+                    warn_secs,
+                    a.force_close_eta_ts.saturating_sub(now_ts).max(0)
+                );
+                self.last_position_warn.insert(warn_key, self.clock.now());
+            }
+        }
+        aging
+    }
+
     /// Build a `DailyRiskSnapshot` for the dashboard. Returns `None` when
     /// the daily-DD threshold is disabled (no point surfacing data nobody
     /// acts on) or the session hasn't been initialised yet. See
@@ -2327,112 +3835,394 @@ impl PairTradeEngine {
             .map(|p| p.log_price)
     }
 
+    /// `symbol` closed a bar this tick (it is in `updated`) and that bar has
+    /// the same close as the one before it.
+    fn bar_unchanged(&self, symbol: &str, updated: &HashSet<String>) -> bool {
+        updated.contains(symbol) && self.history.get(symbol).is_some_and(|h| {
+            h.len() >= 2 && h[h.len() - 1].log_price == h[h.len() - 2].log_price
+        })
+    }
+
     async fn refresh_equity_if_needed(&mut self, inst_idx: usize) -> Result<()> {
         const CACHE_SECS: u64 = EQUITY_REFRESH_CACHE_SECS;
         if self.instances[inst_idx]
             .last_equity_fetch
-            .map(|t| t.elapsed() < Duration::from_secs(CACHE_SECS))
+            .map(|t| self.clock.elapsed(t) < Duration::from_secs(CACHE_SECS))
             .unwrap_or(false)
         {
+            self.count_equity_fallback_cycle(inst_idx);
             return Ok(());
         }
         self.fetch_equity_rest(inst_idx).await;
+        self.count_equity_fallback_cycle(inst_idx);
         Ok(())
     }
 
+    /// `max_equity_fallback_cycles`: count cycles that run while the last
+    /// balance fetch failed, and alert once when the count reaches the cap.
+    /// `equity_guard_blocks` holds new entries from then on until a fetch
+    /// succeeds.
+    fn count_equity_fallback_cycle(&mut self, inst_idx: usize) {
+        let max_cycles = self.cfg.max_equity_fallback_cycles;
+        let inst = &mut self.instances[inst_idx];
+        if inst.balance_fetch_failures == 0 {
+            inst.equity_fallback_cycles = 0;
+            return;
+        }
+        inst.equity_fallback_cycles = inst.equity_fallback_cycles.saturating_add(1);
+        if max_cycles == 0 || inst.equity_fallback_cycles != max_cycles {
+            return;
+        }
+        let body = format!(
+            "{} has run {} cycles on fallback equity {:.2} ({} consecutive get_balance failures); new entries are blocked until a balance fetch succeeds.",
+            inst.id, max_cycles, inst.equity_cache, inst.balance_fetch_failures
+        );
+        log::error!("[EQUITY_GUARD] {}", body);
+        let subject = match self.cfg.agent_name.as_deref() {
+            Some(name) => format!("[{}] Equity fallback", name),
+            None => format!("[EQUITY_GUARD] Trading on fallback equity (dex={})", self.cfg.dex_name),
+        };
+        notifier::notify(Severity::Critical, &subject, &body);
+        self.update_equity_guard_state(inst_idx);
+    }
+
     async fn fetch_equity_rest(&mut self, inst_idx: usize) {
-        // Minimum spacing between /account REST calls across all instances.
-        // Lighter enforces a per-IP short-window rate-limit on /account the
-        // sidecar can't see; empirically ~1 call per 5s survives. Shared
-        // across instances so step() only waits when a recent call exists,
-        // not unconditionally on every inst_idx > 0. See bot-strategy#122.
-        const MIN_ACCOUNT_SPACING: Duration = Duration::from_millis(5_500);
         if let Some(last) = self.last_account_rest_call {
-            let elapsed = last.elapsed();
+            let elapsed = self.clock.elapsed(last);
             if elapsed < MIN_ACCOUNT_SPACING {
                 tokio::time::sleep(MIN_ACCOUNT_SPACING - elapsed).await;
             }
         }
-        self.last_account_rest_call = Some(Instant::now());
+        self.last_account_rest_call = Some(self.clock.now());
+        let exposure = self
+            .cfg
+            .equity_history_detailed
+            .then(|| self.instance_exposure_usd(inst_idx));
         match self.connector.get_balance(None).await {
             Ok(resp) => {
                 if let Some(eq) = resp.equity.to_f64() {
                     let inst = &mut self.instances[inst_idx];
                     inst.equity_cache = eq.max(0.0);
-                    inst.last_equity_fetch = Some(Instant::now());
+                    inst.last_equity_fetch = Some(self.clock.now());
+                    inst.balance_fetch_failures = 0;
+                    inst.equity_fallback_cycles = 0;
                     if let Some(reporter) = &mut inst.status_reporter {
+                        reporter.set_exposure(exposure);
                         reporter.update_equity(inst.equity_cache);
                     }
+                } else {
+                    let inst = &mut self.instances[inst_idx];
+                    inst.balance_fetch_failures = inst.balance_fetch_failures.saturating_add(1);
+                    log::warn!(
+                        "equity refresh for {} returned unparseable equity {} (failures={})",
+                        inst.id,
+                        resp.equity,
+                        inst.balance_fetch_failures
+                    );
                 }
             }
             Err(err) => {
-                log::warn!("equity refresh failed for {}: {:?}", self.instances[inst_idx].id, err);
-                self.instances[inst_idx].last_equity_fetch = Some(Instant::now());
+                let inst = &mut self.instances[inst_idx];
+                inst.balance_fetch_failures = inst.balance_fetch_failures.saturating_add(1);
+                log::warn!(
+                    "equity refresh failed for {} (failures={}): {:?}",
+                    inst.id,
+                    inst.balance_fetch_failures,
+                    err
+                );
+                inst.last_equity_fetch = Some(self.clock.now());
             }
         }
+        self.update_equity_guard_state(inst_idx);
     }
 
-    async fn sync_positions_from_exchange(
-        &mut self,
-        inst_idx: usize,
-        prices: &HashMap<String, SymbolSnapshot>,
-    ) -> Result<()> {
-        if self.replay_connector.is_some() {
-            return Ok(());
+    /// Whether the equity guard blocks new entries for `inst`: either the
+    /// last `max_balance_fetch_failures` balance fetches all failed, the
+    /// last `max_equity_fallback_cycles` cycles ran on fallback equity, or
+    /// the cached equity is below `min_equity_usd`. Each check is disabled
+    /// at zero.
+    fn equity_guard_blocks(&self, inst: &StrategyInstance) -> bool {
+        let max_failures = self.cfg.max_balance_fetch_failures;
+        if max_failures > 0 && inst.balance_fetch_failures >= max_failures {
+            return true;
         }
-        let now_ts = self.current_now_ts();
-        let positions = match self.connector.get_positions().await {
-            Ok(v) => v,
-            Err(err) => {
-                let err_msg = err.to_string();
-                if err_msg.contains("positions not ready from websocket") {
-                    let stale_clear_secs = self.cfg.order_timeout_secs.max(1).saturating_mul(6);
-                    self.clear_stale_pending(inst_idx, Duration::from_secs(stale_clear_secs), "ws_not_ready");
-                    // Startup transient: the Lighter WS hasn't pushed the
-                    // initial position snapshot yet. Resolves within seconds
-                    // of the first WS push. Log at INFO so it does not
-                    // inflate error_summary and trigger the error-watch
-                    // workflow (bot-strategy#49) on every restart. Other
-                    // get_positions failures keep WARN below.
-                    if self.should_log_position_warn(&self.cfg.dex_name) {
-                        log::info!(
-                            "[POSITION] waiting for initial WS positions on {}",
-                            self.cfg.dex_name
-                        );
-                        self.last_position_warn
-                            .insert(self.cfg.dex_name.clone(), Instant::now());
-                    }
-                    self.positions_ready = false;
-                    return Ok(());
-                }
-                if self.should_log_position_warn(&self.cfg.dex_name) {
-                    log::warn!(
-                        "[POSITION] get_positions not available for {}: {:?}",
-                        self.cfg.dex_name,
-                        err
-                    );
-                    self.last_position_warn
-                        .insert(self.cfg.dex_name.clone(), Instant::now());
-                }
-                return Ok(());
+        let max_cycles = self.cfg.max_equity_fallback_cycles;
+        if max_cycles > 0 && inst.equity_fallback_cycles >= max_cycles {
+            return true;
+        }
+        self.cfg.min_equity_usd > 0.0 && inst.equity_cache < self.cfg.min_equity_usd
+    }
+
+    /// Log equity-guard transitions so the journal shows when entries were
+    /// blocked / resumed without repeating the warning every tick.
+    fn update_equity_guard_state(&mut self, inst_idx: usize) {
+        let blocks = self.equity_guard_blocks(&self.instances[inst_idx]);
+        let inst = &mut self.instances[inst_idx];
+        if blocks && !inst.equity_guard_halted {
+            log::warn!(
+                "[EQUITY_GUARD] {} blocking new entries: equity={:.2} min_equity_usd={:.2} balance_failures={} (max={}) fallback_cycles={} (max={})",
+                inst.id,
+                inst.equity_cache,
+                self.cfg.min_equity_usd,
+                inst.balance_fetch_failures,
+                self.cfg.max_balance_fetch_failures,
+                inst.equity_fallback_cycles,
+                self.cfg.max_equity_fallback_cycles
+            );
+            inst.equity_guard_halted = true;
+        } else if !blocks && inst.equity_guard_halted {
+            log::warn!(
+                "[EQUITY_GUARD] {} cleared: equity={:.2} balance_failures={}",
+                inst.id,
+                inst.equity_cache,
+                inst.balance_fetch_failures
+            );
+            inst.equity_guard_halted = false;
+        }
+    }
+
+    /// Exchange positions, or the paper book under paper fills.
+    fn position_book(&self) -> Vec<PositionSnapshot> {
+        if self.cfg.paper_fills() {
+            self.paper_position_book()
+        } else {
+            self.open_positions.values().cloned().collect()
+        }
+    }
+
+    /// Gross USD notional of `inst_idx`'s own pair positions, each leg
+    /// marked at its symbol's latest bar close (entry price when there is
+    /// no history), and the number of open pairs. Feeds
+    /// `equity_history_detailed`, so each instance's equity points carry
+    /// only the exposure behind that equity.
+    fn instance_exposure_usd(&self, inst_idx: usize) -> (f64, usize) {
+        let states = &self.instances[inst_idx].states;
+        let mut gross = 0.0;
+        let mut count = 0;
+        for pair in &self.cfg.universe {
+            let key = format!("{}/{}", pair.base, pair.quote);
+            let Some(position) = states.get(&key).and_then(|s| s.position.as_ref()) else {
+                continue;
+            };
+            count += 1;
+            for (symbol, signed, entry_price) in paper_legs(pair, position) {
+                let mark = self
+                    .history
+                    .get(symbol)
+                    .and_then(|h| h.back())
+                    .map(|s| s.log_price.exp())
+                    .or_else(|| entry_price.and_then(|p| p.to_f64()))
+                    .unwrap_or(0.0);
+                gross += signed.abs().to_f64().unwrap_or(0.0) * mark;
+            }
+        }
+        (gross, count)
+    }
+
+    /// Net per-symbol positions implied by every instance's paper
+    /// positions, in `get_positions` form.
+    fn paper_position_book(&self) -> Vec<PositionSnapshot> {
+        let mut book: HashMap<String, (Decimal, Option<Decimal>)> = HashMap::new();
+        for inst in &self.instances {
+            for pair in &self.cfg.universe {
+                let key = format!("{}/{}", pair.base, pair.quote);
+                let Some(position) = inst.states.get(&key).and_then(|s| s.position.as_ref()) else {
+                    continue;
+                };
+                for (symbol, signed, price) in paper_legs(pair, position) {
+                    let entry = book.entry(symbol.clone()).or_insert((Decimal::ZERO, None));
+                    entry.0 += signed;
+                    entry.1 = entry.1.or(price);
+                }
+            }
+        }
+        book.into_iter()
+            .filter_map(|(symbol, (net, entry_price))| paper_snapshot(symbol, net, entry_price))
+            .collect()
+    }
+
+    /// One pair's paper legs on one instance, in `get_positions` form.
+    /// `backtest_full_sync` serves these from the replay connector a pair at
+    /// a time, so pairs sharing a leg are not netted against each other.
+    fn pair_paper_book(&self, inst_idx: usize, pair: &PairSpec) -> Vec<PositionSnapshot> {
+        let key = format!("{}/{}", pair.base, pair.quote);
+        let Some(position) = self.instances[inst_idx]
+            .states
+            .get(&key)
+            .and_then(|s| s.position.as_ref())
+        else {
+            return Vec::new();
+        };
+        paper_legs(pair, position)
+            .into_iter()
+            .filter_map(|(symbol, signed, price)| paper_snapshot(symbol.clone(), signed, price))
+            .collect()
+    }
+
+    /// Count a consecutive price-fetch or position-sync failure and, once
+    /// `auto_restart_after_failures` is reached, restart the connector at
+    /// most once per `auto_restart_cooldown_secs`. A successful restart
+    /// resets the count; a failed one leaves it so the next failure after
+    /// the cooldown retries. The shared connector and every instance's own
+    /// connector are restarted, each once.
+    async fn note_connector_failure(&mut self, what: &str) {
+        let threshold = self.cfg.auto_restart_after_failures;
+        if threshold == 0 {
+            return;
+        }
+        self.connector_failures = self.connector_failures.saturating_add(1);
+        if self.connector_failures < threshold {
+            return;
+        }
+        let cooldown = Duration::from_secs(self.cfg.auto_restart_cooldown_secs);
+        if let Some(last) = self.last_connector_restart {
+            if self.clock.elapsed(last) < cooldown {
+                log::debug!(
+                    "[CONNECTOR] {} consecutive failures; restart cooling down",
+                    self.connector_failures
+                );
+                return;
+            }
+        }
+        log::warn!(
+            "[CONNECTOR] {} consecutive failures on {} (last: {}); restarting connector",
+            self.connector_failures,
+            self.cfg.dex_name,
+            what
+        );
+        self.last_connector_restart = Some(self.clock.now());
+        let mut connectors: Vec<Arc<dyn DexConnector + Send + Sync>> = vec![self.connector.clone()];
+        for inst in &self.instances {
+            if !connectors.iter().any(|c| Arc::ptr_eq(c, &inst.connector)) {
+                connectors.push(inst.connector.clone());
+            }
+        }
+        let mut all_ok = true;
+        for (i, connector) in connectors.iter().enumerate() {
+            match connector.restart(CONNECTOR_RESTART_MAX_RETRIES).await {
+                Ok(()) => log::warn!(
+                    "[CONNECTOR] restart of {} connector {}/{} succeeded",
+                    self.cfg.dex_name,
+                    i + 1,
+                    connectors.len()
+                ),
+                Err(err) => {
+                    all_ok = false;
+                    log::error!(
+                        "[CONNECTOR] restart of {} connector {}/{} failed: {:?}",
+                        self.cfg.dex_name,
+                        i + 1,
+                        connectors.len(),
+                        err
+                    );
+                }
+            }
+        }
+        if all_ok {
+            self.connector_failures = 0;
+        }
+    }
+
+    async fn sync_positions_from_exchange(
+        &mut self,
+        inst_idx: usize,
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) -> Result<()> {
+        // `backtest_full_sync`: each pair is reconciled against its own paper
+        // legs, read back through the replay connector.
+        let mut pair_books: Option<HashMap<String, HashMap<String, PositionSnapshot>>> = None;
+        if let Some(replay) = self.replay_connector.clone() {
+            if !self.cfg.backtest_full_sync {
+                return Ok(());
+            }
+            let mut books = HashMap::new();
+            for pair in &self.cfg.universe {
+                replay.set_paper_positions(self.pair_paper_book(inst_idx, pair));
+                let legs = replay.get_positions().await.unwrap_or_default();
+                books.insert(
+                    format!("{}/{}", pair.base, pair.quote),
+                    legs.into_iter()
+                        .map(|leg| (leg.symbol.clone(), leg))
+                        .collect::<HashMap<_, _>>(),
+                );
+            }
+            pair_books = Some(books);
+            replay.set_paper_positions(self.paper_position_book());
+        }
+        if self.cfg.observe_paper_fills() {
+            // Paper positions would be wiped by the (always flat) exchange
+            // snapshot; they are the book of record in this mode.
+            self.positions_ready = true;
+            return Ok(());
+        }
+        let now_ts = self.current_now_ts();
+        let positions = match self.connector.get_positions().await {
+            Ok(v) => v,
+            Err(err) => {
+                let err_msg = err.to_string();
+                if err_msg.contains("positions not ready from websocket") {
+                    let stale_clear_secs = self.cfg.order_timeout_secs.max(1).saturating_mul(6);
+                    self.clear_stale_pending(inst_idx, Duration::from_secs(stale_clear_secs), "ws_not_ready");
+                    // Startup transient: the Lighter WS hasn't pushed the
+                    // initial position snapshot yet. Resolves within seconds
+                    // of the first WS push. Log at INFO so it does not
+                    // inflate error_summary and trigger the error-watch
+                    // workflow (bot-strategy#49) on every restart. Other
+                    // get_positions failures keep WARN below.
+                    if self.should_log_position_warn(&self.cfg.dex_name) {
+                        log::info!(
+                            "[POSITION] waiting for initial WS positions on {}",
+                            self.cfg.dex_name
+                        );
+                        self.last_position_warn
+                            .insert(self.cfg.dex_name.clone(), self.clock.now());
+                    }
+                    self.positions_ready = false;
+                    self.note_connector_failure("position sync").await;
+                    return Ok(());
+                }
+                if self.should_log_position_warn(&self.cfg.dex_name) {
+                    log::warn!(
+                        "[POSITION] get_positions not available for {}: {:?}",
+                        self.cfg.dex_name,
+                        err
+                    );
+                    self.last_position_warn
+                        .insert(self.cfg.dex_name.clone(), self.clock.now());
+                }
+                self.note_connector_failure("position sync").await;
+                return Ok(());
             }
         };
         self.positions_ready = true;
+        self.connector_failures = 0;
 
         let mut snapshots: HashMap<String, PositionSnapshot> = HashMap::new();
+        let mut dust_legs: HashMap<String, PositionSnapshot> = HashMap::new();
         for snapshot in positions {
             if snapshot.sign == 0 || snapshot.size <= Decimal::ZERO {
                 continue;
             }
             if self.is_dust_position(&snapshot, prices) {
+                dust_legs.insert(snapshot.symbol.clone(), snapshot);
                 continue;
             }
             snapshots.insert(snapshot.symbol.clone(), snapshot);
         }
         self.open_positions = snapshots.clone();
+        self.dust_sweep_attempted
+            .retain(|symbol| dust_legs.contains_key(symbol));
+        let mut dust_sweeps: Vec<(String, String, i32, Decimal)> = Vec::new();
+        let mut mismatched_flattens: Vec<(String, [PositionSnapshot; 2])> = Vec::new();
+        let mut hedge_corrections: Vec<(String, PairSpec, f64)> = Vec::new();
 
         let mut unhedged_attempted: HashSet<String> = HashSet::new();
         let mut unhedged_closures: Vec<(String, String, i32, Decimal)> = Vec::new();
+        let recovered_entry_ts = if self.cfg.recover_entry_time_from_risk_state {
+            self.instances[inst_idx].persisted_entry_ts.clone()
+        } else {
+            HashMap::new()
+        };
         for pair in &self.cfg.universe {
             let key = format!("{}/{}", pair.base, pair.quote);
             let log_warn = self.should_log_position_warn(&key);
@@ -2441,8 +4231,10 @@ impl PairTradeEngine {
                 continue;
             };
 
-            let base = snapshots.get(&pair.base);
-            let quote = snapshots.get(&pair.quote);
+            let (base, quote) = match pair_books.as_ref().and_then(|books| books.get(&key)) {
+                Some(book) => (book.get(&pair.base), book.get(&pair.quote)),
+                None => (snapshots.get(&pair.base), snapshots.get(&pair.quote)),
+            };
 
             if state.pending_entry.is_some() || state.pending_exit.is_some() {
                 // Keep pending orders; reconciliation handles timeouts/hedging.
@@ -2456,9 +4248,26 @@ impl PairTradeEngine {
                     }
                     state.position = None;
                     state.position_guard = false;
+                    if self.cfg.dust_sweep {
+                        for symbol in [&pair.base, &pair.quote] {
+                            if let Some(dust) = dust_legs.get(symbol) {
+                                if self.dust_sweep_attempted.insert(symbol.clone()) {
+                                    dust_sweeps.push((
+                                        key.clone(),
+                                        symbol.clone(),
+                                        dust.sign,
+                                        dust.size,
+                                    ));
+                                }
+                            }
+                        }
+                    }
                 }
                 (Some(b), Some(q)) => {
-                    if b.sign * q.sign >= 0 {
+                    // Same-sign legs are only a valid hedge for a
+                    // negative-beta pair.
+                    let hedge_inverted = b.sign * q.sign > 0;
+                    if b.sign * q.sign == 0 || (hedge_inverted && !self.cfg.allow_negative_beta) {
                         if log_warn {
                             log::warn!(
                                 "[POSITION] {} has mismatched legs (signs {} / {})",
@@ -2468,10 +4277,13 @@ impl PairTradeEngine {
                             );
                         }
                         if log_warn {
-                            self.last_position_warn.insert(key.clone(), Instant::now());
+                            self.last_position_warn.insert(key.clone(), self.clock.now());
                         }
                         state.position = None;
                         state.position_guard = true;
+                        if self.cfg.auto_flatten_mismatched_legs {
+                            mismatched_flattens.push((key.clone(), [b.clone(), q.clone()]));
+                        }
                         continue;
                     }
 
@@ -2480,12 +4292,80 @@ impl PairTradeEngine {
                     } else {
                         PositionDirection::ShortSpread
                     };
-                    let (entered_at, entered_ts) = state
+                    // A pair adopted without local state (crash after both
+                    // legs filled) would otherwise start its holding clock
+                    // now and delay force_close.
+                    let recovered_ts = recovered_entry_ts.get(&key).copied();
+                    if state.position.is_none() {
+                        match recovered_ts {
+                            Some(ts) => log::warn!(
+                                "[POSITION] {} adopted from exchange with no local state; entry_ts={} recovered from risk state",
+                                key,
+                                ts
+                            ),
+                            None => log::warn!(
+                                "[POSITION] {} adopted from exchange with no local state; holding clock starts now",
+                                key
+                            ),
+                        }
+                    }
+                    let (entered_at, entered_ts) = match (&state.position, recovered_ts) {
+                        (Some(p), _) => (p.entered_at, p.entered_ts),
+                        (None, Some(ts)) => {
+                            let age = Duration::from_secs(now_ts.saturating_sub(ts).max(0) as u64);
+                            let now = self.clock.now();
+                            (now.checked_sub(age).unwrap_or(now), ts)
+                        }
+                        (None, None) => (self.clock.now(), now_ts),
+                    };
+                    let prev_entry_z = state.position.as_ref().and_then(|p| p.entry_z);
+                    let prev_entry_beta = state.position.as_ref().and_then(|p| p.entry_beta);
+                    let prev_partial_taken = state.position.as_ref().is_some_and(|p| p.partial_taken);
+                    let prev_scaled_in = state.position.as_ref().is_some_and(|p| p.scaled_in);
+                    let prev_scale_in_add = state.position.as_ref().and_then(|p| p.scale_in_add);
+                    // Fill cash only describes the position it was booked for.
+                    let prev_entry_fill_cash = state
                         .position
                         .as_ref()
-                        .map(|p| (p.entered_at, p.entered_ts))
-                        .unwrap_or((Instant::now(), now_ts));
-                    let prev_entry_z = state.position.as_ref().and_then(|p| p.entry_z);
+                        .filter(|p| p.entry_size_a == Some(b.size) && p.entry_size_b == Some(q.size))
+                        .and_then(|p| p.entry_fill_cash);
+                    if self.cfg.hedge_ratio_tolerance_pct > 0.0 {
+                        let mark = |symbol: &str, snap: &PositionSnapshot| {
+                            prices
+                                .get(symbol)
+                                .map(|s| s.price)
+                                .or(snap.entry_price)
+                                .unwrap_or(Decimal::ZERO)
+                        };
+                        let deviation = sizing::realized_hedge_deviation(
+                            b.size,
+                            mark(&pair.base, b),
+                            q.size,
+                            mark(&pair.quote, q),
+                            sizing_beta(&self.cfg, &self.history, &pair.base, &pair.quote, state.beta),
+                        );
+                        // Within tolerance the legs are hedged as far as the
+                        // engine is concerned; rounding drift is not chased.
+                        // Beyond it leg B is re-hedged, at most once per
+                        // position-warn interval so fills can land.
+                        if let Some(dev) = deviation
+                            .filter(|dev| *dev * 100.0 > self.cfg.hedge_ratio_tolerance_pct)
+                        {
+                            if log_warn {
+                                log::warn!(
+                                    "[POSITION] {} hedge ratio off by {:.1}% (> {:.1}% tolerance, beta={:.3}, sizes {} / {}); re-hedging",
+                                    key,
+                                    dev * 100.0,
+                                    self.cfg.hedge_ratio_tolerance_pct,
+                                    state.beta,
+                                    b.size,
+                                    q.size
+                                );
+                                self.last_position_warn.insert(key.clone(), self.clock.now());
+                                hedge_corrections.push((key.clone(), pair.clone(), state.beta));
+                            }
+                        }
+                    }
                     state.position = Some(Position {
                         direction,
                         entered_at,
@@ -2495,6 +4375,12 @@ impl PairTradeEngine {
                         entry_size_a: Some(b.size),
                         entry_size_b: Some(q.size),
                         entry_z: prev_entry_z,
+                        entry_beta: prev_entry_beta,
+                        entry_fill_cash: prev_entry_fill_cash,
+                        partial_taken: prev_partial_taken,
+                        scaled_in: prev_scaled_in,
+                        scale_in_add: prev_scale_in_add,
+                        hedge_inverted,
                     });
                     state.position_guard = false;
                 }
@@ -2524,7 +4410,7 @@ impl PairTradeEngine {
                             base.is_some(),
                             quote.is_some()
                         );
-                        self.last_position_warn.insert(key.clone(), Instant::now());
+                        self.last_position_warn.insert(key.clone(), self.clock.now());
                         state.position_guard = true;
                     } else {
                         state.position_guard = false;
@@ -2540,3153 +4426,8346 @@ impl PairTradeEngine {
             self.try_close_unhedged_leg(inst_idx, &key, &symbol, sign, size, prices)
                 .await;
         }
+        for (key, symbol, sign, size) in dust_sweeps {
+            self.sweep_dust_leg(&key, &symbol, sign, size, prices).await;
+        }
+        for (key, legs) in mismatched_flattens {
+            self.flatten_mismatched_legs(inst_idx, &key, &legs, prices)
+                .await;
+        }
+        for (key, pair, beta) in hedge_corrections {
+            self.rehedge_position(inst_idx, &key, &pair, beta, prices, "[HEDGE_RATIO]")
+                .await;
+        }
 
         Ok(())
     }
 
-    async fn try_close_unhedged_leg(
-        &mut self,
-        inst_idx: usize,
-        key: &str,
-        symbol: &str,
-        sign: i32,
-        size: Decimal,
-        prices: &HashMap<String, SymbolSnapshot>,
-    ) {
-        let now_ts = self.current_now_ts();
-        if self.cfg.dry_run || self.cfg.observe_only {
-            log::warn!(
-                "[UNHEDGED] {} close skipped (mode) symbol={} size={}",
-                key,
-                symbol,
-                size
-            );
+    /// Periodic tally of the instance's open orders over the universe
+    /// symbols against `max_open_orders`. Over the cap, new entries are
+    /// blocked and every open order that is not a tracked pending leg is
+    /// cancelled; the block lifts once a later tally is back under it. The
+    /// tally is a burst of account REST calls, so it is put off to a later
+    /// step (not slept for) while the last one is within
+    /// `MIN_ACCOUNT_SPACING`.
+    async fn audit_open_orders(&mut self, inst_idx: usize) {
+        let max = self.cfg.max_open_orders;
+        if max == 0 || self.replay_connector.is_some() || self.cfg.dry_run || self.cfg.observe_only {
+            return;
+        }
+        let interval = Duration::from_secs(OPEN_ORDER_AUDIT_INTERVAL_SECS);
+        if self.instances[inst_idx]
+            .last_open_order_audit
+            .is_some_and(|t| self.clock.elapsed(t) < interval)
+        {
             return;
         }
+        if self
+            .last_account_rest_call
+            .is_some_and(|t| self.clock.elapsed(t) < MIN_ACCOUNT_SPACING)
+        {
+            return;
+        }
+        self.instances[inst_idx].last_open_order_audit = Some(self.clock.now());
+        self.last_account_rest_call = Some(self.clock.now());
 
-        const UNHEDGED_CLOSE_COOLDOWN_SECS: u64 = 30;
-        let last_exit = self.instances[inst_idx].states.get(key).and_then(|state| state.last_exit_at);
-        if let Some(last_exit) = last_exit {
-            if last_exit.elapsed() < Duration::from_secs(UNHEDGED_CLOSE_COOLDOWN_SECS) {
-                return;
+        let symbols: BTreeSet<String> = self
+            .cfg
+            .universe
+            .iter()
+            .flat_map(|p| [p.base.clone(), p.quote.clone()])
+            .collect();
+        let tracked: HashSet<String> = self.instances[inst_idx]
+            .states
+            .values()
+            .flat_map(|s| s.pending_entry.iter().chain(s.pending_exit.iter()))
+            .flat_map(|p| p.legs.iter())
+            .flat_map(|leg| std::iter::once(leg.order_id.clone()).chain(leg.exchange_order_id.clone()))
+            .collect();
+        let mut total = 0usize;
+        let mut untracked: Vec<(String, Vec<String>)> = Vec::new();
+        for symbol in symbols {
+            let open = match self.connector.get_open_orders(&symbol).await {
+                Ok(open) => open,
+                Err(err) => {
+                    log::warn!("[ORDERS] open order audit skipped: {} failed: {:?}", symbol, err);
+                    return;
+                }
+            };
+            total += open.orders.len();
+            let ids: Vec<String> = open
+                .orders
+                .into_iter()
+                .map(|o| o.order_id)
+                .filter(|id| !tracked.contains(id))
+                .collect();
+            if !ids.is_empty() {
+                untracked.push((symbol, ids));
             }
         }
-
-        let side = if sign >= 0 {
-            dex_connector::OrderSide::Short
-        } else {
-            dex_connector::OrderSide::Long
-        };
-        let qty = self.quantize_order_size_close(symbol, size, prices);
-        if qty <= Decimal::ZERO {
-            log::warn!(
-                "[UNHEDGED] {} close skipped (qty=0) symbol={} size={}",
-                key,
-                symbol,
-                size
-            );
+        let inst = &mut self.instances[inst_idx];
+        inst.open_order_count = total;
+        log::debug!("[ORDERS] {} open orders: {} (max {})", inst.id, total, max);
+        if total <= max {
+            if inst.open_orders_blocked {
+                log::info!("[ORDERS] {} open orders back under cap: {} <= {}", inst.id, total, max);
+                inst.open_orders_blocked = false;
+            }
             return;
         }
-
+        inst.open_orders_blocked = true;
         log::warn!(
-            "[UNHEDGED] {} closing lone leg symbol={} sign={} size={} qty={} side={:?}",
-            key,
-            symbol,
-            sign,
-            size,
-            qty,
-            side
+            "[ORDERS] {} open orders {} > max_open_orders {}: blocking entries, cancelling {} untracked",
+            inst.id,
+            total,
+            max,
+            untracked.iter().map(|(_, ids)| ids.len()).sum::<usize>()
         );
+        for (symbol, ids) in untracked {
+            if let Err(err) = self.connector.cancel_orders(Some(symbol.clone()), ids).await {
+                log::warn!("[ORDERS] cancel sweep failed for {}: {:?}", symbol, err);
+            }
+        }
+    }
 
-        let res = self
-            .connector
-            .create_order(symbol, qty, side, None, None, true, None)
-            .await;
+    /// Periodic `position_audit_interval_secs` check that every non-dust
+    /// exchange position belongs to a pair some instance tracks (position,
+    /// pending order or guard) and vice versa. The per-pair sync only looks
+    /// at universe pairs, so a position in an unexpected symbol is otherwise
+    /// never noticed.
+    async fn audit_position_count(&mut self, prices: &HashMap<String, SymbolSnapshot>) {
+        let interval = self.cfg.position_audit_interval_secs;
+        if interval == 0
+            || self.replay_connector.is_some()
+            || self.cfg.observe_paper_fills()
+            || !self.positions_ready
+        {
+            return;
+        }
+        if self
+            .last_position_audit
+            .is_some_and(|t| self.clock.elapsed(t) < Duration::from_secs(interval))
+        {
+            return;
+        }
+        self.last_position_audit = Some(self.clock.now());
 
-        match res {
-            Ok(res) => {
-                log::info!(
-                    "[UNHEDGED] {} close submitted symbol={} order_id={}",
-                    key,
-                    symbol,
-                    res.order_id
-                );
-                if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
-                    state.last_exit_at = Some(Instant::now());
-                    state.last_exit_ts = Some(now_ts);
+        let mut tracked: HashSet<String> = HashSet::new();
+        let mut missing: Vec<String> = Vec::new();
+        let mut engine_positions = 0usize;
+        for inst in &self.instances {
+            for (key, state) in &inst.states {
+                let active = state.position.is_some()
+                    || state.pending_entry.is_some()
+                    || state.pending_exit.is_some()
+                    || state.position_guard;
+                if !active {
+                    continue;
                 }
-            }
-            Err(err) => {
-                if Self::is_reduce_only_position_missing_error(&err)
-                    && self.confirm_reduce_only_position_missing(symbol).await
-                {
-                    log::info!(
-                        "[UNHEDGED] {} close skipped; position already closed symbol={}",
-                        key,
-                        symbol
-                    );
-                    if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
-                        state.last_exit_at = Some(Instant::now());
-                        state.last_exit_ts = Some(now_ts);
+                let Some((base, quote)) = key.split_once('/') else {
+                    continue;
+                };
+                tracked.insert(base.to_string());
+                tracked.insert(quote.to_string());
+                if state.position.is_some() {
+                    engine_positions += 1;
+                    if state.pending_exit.is_none() {
+                        missing.extend(
+                            [base, quote]
+                                .into_iter()
+                                .filter(|s| !self.open_positions.contains_key(*s))
+                                .map(str::to_string),
+                        );
                     }
-                } else {
-                    log::error!(
-                        "[UNHEDGED] {} close failed symbol={} err={:?}",
-                        key,
-                        symbol,
-                        err
-                    );
                 }
             }
         }
-    }
+        let mut untracked: Vec<&PositionSnapshot> = self
+            .open_positions
+            .values()
+            .filter(|p| !tracked.contains(&p.symbol))
+            .collect();
+        untracked.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        missing.sort();
+        missing.dedup();
 
-    fn clear_stale_pending(&mut self, inst_idx: usize, max_age: Duration, reason: &str) {
-        let now_ts = self.current_now_ts();
-        for (key, state) in self.instances[inst_idx].states.iter_mut() {
-            let entry_age = state.pending_entry.as_ref().map(|p| p.placed_at.elapsed());
-            let exit_age = state.pending_exit.as_ref().map(|p| p.placed_at.elapsed());
-            let age = match (entry_age, exit_age) {
-                (Some(a), Some(b)) => Some(a.max(b)),
-                (Some(a), None) => Some(a),
-                (None, Some(b)) => Some(b),
-                (None, None) => None,
-            };
-            if let Some(age) = age {
-                if age >= max_age {
-                    log::warn!(
-                        "[POSITION] {} pending cleared (reason={}, age={}s)",
-                        key,
-                        reason,
-                        age.as_secs()
-                    );
-                    state.pending_entry = None;
-                    state.pending_exit = None;
-                    state.position = None;
-                    state.position_guard = false;
-                    state.last_exit_at = Some(Instant::now());
-                    state.last_exit_ts = Some(now_ts);
-                }
+        if untracked.is_empty() && missing.is_empty() {
+            if self.position_audit_strikes > 0 {
+                log::info!("[AUDIT] positions reconciled with exchange");
             }
+            self.position_audit_strikes = 0;
+            return;
         }
-    }
-
-    fn compute_vol_median(&self, inst_idx: usize) -> f64 {
-        let tail_len = self.entry_vol_window();
-        let mut vols: Vec<f64> = self
-            .instances[inst_idx]
-            .states
-            .values()
-            .filter_map(|s| tail_std(&s.spread_history, tail_len))
+        self.position_audit_strikes += 1;
+        let persistent = self.position_audit_strikes >= 2;
+        let untracked_summary: Vec<String> = untracked
+            .iter()
+            .map(|p| format!("{}(sign={} size={})", p.symbol, p.sign, p.size))
             .collect();
-        if vols.is_empty() {
-            return 1.0;
+        let report = format!(
+            "engine positions={} exchange legs={} untracked=[{}] missing_on_exchange=[{}] strikes={}",
+            engine_positions,
+            self.open_positions.len(),
+            untracked_summary.join(", "),
+            missing.join(", "),
+            self.position_audit_strikes
+        );
+        if !persistent {
+            log::warn!("[AUDIT] position mismatch: {}", report);
+            return;
+        }
+        log::error!("[AUDIT] persistent position mismatch: {}", report);
+        if !self.cfg.position_audit_force_close || self.cfg.dry_run || self.cfg.observe_only {
+            return;
+        }
+        let closes: Vec<(String, i32, Decimal)> = untracked
+            .iter()
+            .map(|p| (p.symbol.clone(), p.sign, p.size))
+            .collect();
+        for (symbol, sign, size) in closes {
+            let side = if sign >= 0 {
+                dex_connector::OrderSide::Short
+            } else {
+                dex_connector::OrderSide::Long
+            };
+            let qty = self.quantize_order_size_close(&symbol, size, prices);
+            if qty <= Decimal::ZERO {
+                continue;
+            }
+            match self
+                .connector
+                .create_order(&symbol, qty, side, None, None, true, None)
+                .await
+            {
+                Ok(res) => log::warn!(
+                    "[AUDIT] closing untracked position symbol={} qty={} side={:?} order_id={}",
+                    symbol,
+                    qty,
+                    side,
+                    res.order_id
+                ),
+                Err(err) => log::error!(
+                    "[AUDIT] close of untracked position {} failed: {:?}",
+                    symbol,
+                    err
+                ),
+            }
         }
-        vols.sort_by(|a, b| a.partial_cmp(b).unwrap());
-        vols[vols.len() / 2].max(1e-9)
     }
 
-    fn maybe_log_metrics(&mut self, inst_idx: usize) {
-        const LOG_INTERVAL: u64 = 300;
-        if self
-            .last_metrics_log
-            .map(|t| t.elapsed() < Duration::from_secs(LOG_INTERVAL))
-            .unwrap_or(false)
+    /// `delta_rebalance_enabled`: trade each symbol's exposure on the
+    /// instance's account back towards what the instance's open pairs call
+    /// for. Leg A is taken as booked and leg B at the entry-implied
+    /// `size_a * |entry beta| * entry_price_a / entry_price_b`, so legs that
+    /// filled at different ratios (or pairs sharing a symbol) leave a
+    /// per-symbol gap; gaps above `rebalance_threshold_usd` at current
+    /// prices get a market order, reduce-only when it only shrinks the
+    /// position. Orders that add exposure wait out the entry risk gates.
+    /// Leg B sizes are booked once an order is confirmed filled; no new
+    /// pass runs while one is outstanding.
+    async fn rebalance_net_delta(
+        &mut self,
+        inst_idx: usize,
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) {
+        if !self.cfg.delta_rebalance_enabled
+            || self.replay_connector.is_some()
+            || self.cfg.paper_fills()
+            || !self.positions_ready
         {
             return;
         }
-        let mut lines = Vec::new();
-        for (k, s) in &self.instances[inst_idx].states {
-            let z = s.z_score().map(|(z, _)| z).unwrap_or(0.0);
-            lines.push(format!(
-                "{} elig={} z={:.2} beta={:.2} hl={:.2}h p={:.3}",
-                k, s.eligible, z, s.beta, s.half_life_hours, s.adf_p_value
-            ));
+        if !self.instances[inst_idx].delta_rebalance_orders.is_empty() {
+            self.confirm_delta_rebalance(inst_idx).await;
+            if !self.instances[inst_idx].delta_rebalance_orders.is_empty() {
+                return;
+            }
         }
-        lines.sort();
-        if !lines.is_empty() {
-            log::info!("[METRICS] {}", lines.join(" | "));
+        let interval = Duration::from_secs(self.cfg.delta_rebalance_interval_secs);
+        if self.instances[inst_idx]
+            .last_delta_rebalance
+            .is_some_and(|t| self.clock.elapsed(t) < interval)
+        {
+            return;
         }
-        self.last_metrics_log = Some(Instant::now());
-    }
-
-    fn state_score(&self, inst_idx: usize, key: &str) -> f64 {
-        self.instances[inst_idx].states
-            .get(key)
-            .map(|s| s.p_value_weighted_score)
-            .unwrap_or(0.0)
-    }
-
-    fn should_log_ob_warn(&self, symbol: &str) -> bool {
-        const WARN_INTERVAL: u64 = 300;
-        self.last_ob_warn
-            .get(symbol)
-            .map(|t| t.elapsed() >= Duration::from_secs(WARN_INTERVAL))
-            .unwrap_or(true)
-    }
-
-    fn should_log_ticker_warn(&self, symbol: &str) -> bool {
-        const WARN_INTERVAL: u64 = 300;
-        self.last_ticker_warn
-            .get(symbol)
-            .map(|t| t.elapsed() >= Duration::from_secs(WARN_INTERVAL))
-            .unwrap_or(true)
-    }
-
-    fn should_log_position_warn(&self, key: &str) -> bool {
-        const WARN_INTERVAL: u64 = 300;
-        self.last_position_warn
-            .get(key)
-            .map(|t| t.elapsed() >= Duration::from_secs(WARN_INTERVAL))
-            .unwrap_or(true)
-    }
-
-    fn is_dust_position(
-        &self,
-        snapshot: &PositionSnapshot,
-        prices: &HashMap<String, SymbolSnapshot>,
-    ) -> bool {
-        let Some(symbol_snapshot) = prices.get(&snapshot.symbol) else {
-            return false;
-        };
-        let Some(min_order) = symbol_snapshot.min_order else {
-            return false;
-        };
-        snapshot.size < min_order
-    }
-
-    fn is_ticker_auth_error(msg: &str) -> bool {
-        let lower = msg.to_ascii_lowercase();
-        lower.contains("403")
-            || lower.contains("forbidden")
-            || lower.contains("failed to deserialize response")
-            || lower.contains("expected value at line 1 column 1")
-    }
+        self.instances[inst_idx].last_delta_rebalance = Some(self.clock.now());
 
-    fn is_reduce_only_position_missing_error(err: &DexError) -> bool {
-        let msg = match err {
-            DexError::ServerResponse(message) | DexError::Other(message) => message,
-            _ => return false,
+        let price = |symbol: &str| prices.get(symbol).and_then(|s| s.price.to_f64());
+        let signed = |side: dex_connector::OrderSide, qty: f64| match side {
+            dex_connector::OrderSide::Long => qty,
+            dex_connector::OrderSide::Short => -qty,
         };
-        let lower = msg.to_ascii_lowercase();
-        lower.contains("position is missing for reduce-only order")
-            || lower.contains("position is missing for reduce only order")
-    }
-
-    async fn confirm_reduce_only_position_missing(&mut self, symbol: &str) -> bool {
-        let cached_has_position = self
+        // Signed target quantity per symbol, and each pair's entry-implied
+        // leg B size to book once its symbol is traded back.
+        let mut target_qty: HashMap<String, f64> = HashMap::new();
+        let mut implied_b: Vec<(String, String, f64)> = Vec::new();
+        let mut skipped: HashSet<String> = HashSet::new();
+        for (key, state) in &self.instances[inst_idx].states {
+            let Some((base, quote)) = key.split_once('/') else {
+                continue;
+            };
+            let busy =
+                state.pending_entry.is_some() || state.pending_exit.is_some() || state.position_guard;
+            let Some(pos) = &state.position else {
+                if busy {
+                    skipped.extend([base.to_string(), quote.to_string()]);
+                }
+                continue;
+            };
+            let entry_prices = pos
+                .entry_price_a
+                .zip(pos.entry_price_b)
+                .and_then(|(a, b)| Some((a.to_f64()?, b.to_f64()?)))
+                .filter(|(a, b)| *a > 0.0 && *b > 0.0);
+            let Some((size_a, (entry_a, entry_b))) = pos
+                .entry_size_a
+                .and_then(|size| size.abs().to_f64())
+                .zip(entry_prices)
+                .filter(|_| !busy && price(base).is_some() && price(quote).is_some())
+            else {
+                skipped.extend([base.to_string(), quote.to_string()]);
+                continue;
+            };
+            let beta = pos.entry_beta.unwrap_or(state.beta);
+            let beta = sizing_beta(&self.cfg, &self.history, base, quote, beta).abs();
+            let size_b = size_a * beta * entry_a / entry_b;
+            let (side_a, side_b) = pos.direction.entry_sides(pos.hedge_inverted);
+            *target_qty.entry(base.to_string()).or_default() += signed(side_a, size_a);
+            *target_qty.entry(quote.to_string()).or_default() += signed(side_b, size_b);
+            implied_b.push((key.clone(), quote.to_string(), size_b));
+        }
+        target_qty.retain(|symbol, _| !skipped.contains(symbol));
+        if target_qty.is_empty() {
+            return;
+        }
+        let target: HashMap<String, f64> = target_qty
+            .iter()
+            .map(|(symbol, qty)| (symbol.clone(), qty * price(symbol).unwrap_or(0.0)))
+            .collect();
+        // `open_positions` holds this instance's account, synced just before.
+        let actual: HashMap<String, f64> = self
             .open_positions
-            .get(symbol)
-            .map(|p| p.sign != 0 && p.size > Decimal::ZERO)
-            .unwrap_or(false);
-        if !cached_has_position && self.positions_ready {
-            return true;
+            .values()
+            .map(|p| {
+                let usd = p.size.abs().to_f64().unwrap_or(0.0) * price(&p.symbol).unwrap_or(0.0);
+                (p.symbol.clone(), f64::from(p.sign.signum()) * usd)
+            })
+            .collect();
+        let add_block = [
+            (self.kill_switch_active, "kill_switch"),
+            (self.instances[inst_idx].session_halted, "session_dd"),
+            (self.daily_loss_blocks(&self.instances[inst_idx]), "daily_loss"),
+            (self.equity_guard_blocks(&self.instances[inst_idx]), "equity_guard"),
+        ]
+        .into_iter()
+        .find_map(|(blocked, gate)| blocked.then_some(gate));
+        let gaps = sizing::delta_rebalance_gaps(&actual, &target, self.cfg.rebalance_threshold_usd);
+        if gaps.is_empty() {
+            return;
         }
-
-        match self.connector.get_positions().await {
-            Ok(positions) => {
-                let has_position = positions
-                    .iter()
-                    .any(|p| p.symbol == symbol && p.sign != 0 && p.size > Decimal::ZERO);
-                if !has_position {
-                    self.open_positions.remove(symbol);
-                    return true;
-                }
+        let net_delta: f64 = actual.values().sum();
+        let summary: Vec<String> = gaps
+            .iter()
+            .map(|(symbol, gap)| {
+                format!(
+                    "{}(actual={:.2} target={:.2} gap={:.2})",
+                    symbol,
+                    actual.get(symbol).copied().unwrap_or(0.0),
+                    target[symbol],
+                    gap
+                )
+            })
+            .collect();
+        log::info!(
+            "[DELTA_REBALANCE] net delta {:.2} USD; off target: {}",
+            net_delta,
+            summary.join(", ")
+        );
+        for (symbol, gap) in gaps {
+            let Some(raw_qty) = price(&symbol)
+                .filter(|p| *p > 0.0)
+                .and_then(|p| Decimal::from_f64(gap.abs() / p))
+            else {
+                continue;
+            };
+            let side = if gap > 0.0 {
+                dex_connector::OrderSide::Short
+            } else {
+                dex_connector::OrderSide::Long
+            };
+            let qty = self.quantize_order_size(&symbol, raw_qty, prices);
+            let min_order = prices.get(&symbol).and_then(|s| s.min_order);
+            if qty <= Decimal::ZERO || min_order.is_some_and(|min| qty < min) {
+                log::debug!(
+                    "[DELTA_REBALANCE] {} gap {:.2} USD below the minimum order; skipped",
+                    symbol,
+                    gap
+                );
+                continue;
             }
-            Err(err) => {
-                log::warn!(
-                    "[ORDER] reduce-only missing check failed for {}: {:?}",
+            // Reduce-only when the order trims the current position without
+            // flipping it; otherwise it adds to (or opens) the leg.
+            let held = actual.get(&symbol).copied().unwrap_or(0.0);
+            let reduce_only = held * gap > 0.0 && gap.abs() <= held.abs();
+            if let (false, Some(gate)) = (reduce_only, add_block) {
+                log::info!(
+                    "[DELTA_REBALANCE] {} gap {:.2} USD needs an adding order; blocked by {}",
                     symbol,
-                    err
+                    gap,
+                    gate
+                );
+                continue;
+            }
+            if self.cfg.dry_run || self.cfg.observe_only {
+                log::info!(
+                    "[DELTA_REBALANCE] would place {} qty={} side={:?} reduce_only={}",
+                    symbol,
+                    qty,
+                    side,
+                    reduce_only
                 );
+                continue;
             }
+            let connector = self.instances[inst_idx].connector.clone();
+            let res = match connector
+                .create_order(&symbol, qty, side, None, None, reduce_only, None)
+                .await
+            {
+                Ok(res) => res,
+                Err(err) => {
+                    log::error!("[DELTA_REBALANCE] order for {} failed: {:?}", symbol, err);
+                    continue;
+                }
+            };
+            log::info!(
+                "[DELTA_REBALANCE] {} qty={} side={:?} reduce_only={} gap={:.2} order_id={}",
+                symbol,
+                qty,
+                side,
+                reduce_only,
+                gap,
+                res.order_id
+            );
+            // Once filled the symbol sits at its target: each hedge leg on
+            // it is then booked at its entry-implied size.
+            let bookings = implied_b
+                .iter()
+                .filter(|(_, quote, _)| *quote == symbol)
+                .filter_map(|(key, _, size_b)| {
+                    let size = Decimal::from_f64(*size_b)?;
+                    Some((key.clone(), self.quantize_order_size(&symbol, size, prices)))
+                })
+                .collect();
+            let leg = PendingLeg {
+                symbol: symbol.clone(),
+                order_id: res.order_id,
+                exchange_order_id: res.exchange_order_id,
+                target: qty,
+                filled: Decimal::ZERO,
+                side,
+                limit_price: None,
+                placed_price: prices.get(&symbol).map(|s| s.price),
+            };
+            self.instances[inst_idx]
+                .delta_rebalance_orders
+                .push((leg, bookings));
         }
-        false
     }
 
-    fn persist_history_to_disk(&self) {
-        // Persist the engine's shared log-price history plus the first
-        // instance's per-pair `spread_history`. We pick instance 0 as
-        // the representative: A/B/C instances drift ≤0.3% per the
-        // existing TODO near evaluate_pair, which on reload converges
-        // back to whatever was persisted. Persisting per-instance would
-        // require an instance ID in the schema — over-engineered for
-        // the single-bot-per-process setup this field currently supports.
-        let spread_histories: HashMap<String, VecDeque<f64>> = self
-            .instances
-            .first()
-            .map(|inst| {
-                inst.states
-                    .iter()
-                    .map(|(k, s)| (k.clone(), s.spread_history.clone()))
-                    .collect()
-            })
-            .unwrap_or_default();
-        history_io::persist_history_to_disk(
-            &self.cfg,
-            &self.history,
-            &spread_histories,
-            &self.history_path,
-        );
+    /// Settle outstanding delta-rebalance orders: a filled order books its
+    /// leg B sizes; one still short of its size `order_timeout_secs` after
+    /// the pass is cancelled and dropped unbooked so the next pass
+    /// re-measures the gap. Orders whose status can't be fetched are kept
+    /// for the next call.
+    async fn confirm_delta_rebalance(&mut self, inst_idx: usize) {
+        let connector = self.instances[inst_idx].connector.clone();
+        let orders = std::mem::take(&mut self.instances[inst_idx].delta_rebalance_orders);
+        let legs: Vec<PendingLeg> = orders.iter().map(|(leg, _)| leg.clone()).collect();
+        let status = match fetch_pending_status(&connector, &legs).await {
+            Ok(status) => status,
+            Err(err) => {
+                log::warn!("[DELTA_REBALANCE] order status unavailable: {:?}", err);
+                self.instances[inst_idx].delta_rebalance_orders = orders;
+                return;
+            }
+        };
+        let timed_out = self.instances[inst_idx]
+            .last_delta_rebalance
+            .is_none_or(|t| {
+                self.clock.elapsed(t) >= Duration::from_secs(self.cfg.order_timeout_secs.max(1))
+            });
+        for (leg, bookings) in orders {
+            let filled = self.filled_for_leg(&leg, &status.fills);
+            let open = status.open_ids.contains(&leg.order_id);
+            if filled >= leg.target {
+                for (key, size_b) in bookings {
+                    if let Some(pos) = self.instances[inst_idx]
+                        .states
+                        .get_mut(&key)
+                        .and_then(|s| s.position.as_mut())
+                    {
+                        pos.entry_size_b = Some(size_b);
+                    }
+                }
+                continue;
+            }
+            if !timed_out {
+                self.instances[inst_idx]
+                    .delta_rebalance_orders
+                    .push((leg, bookings));
+                continue;
+            }
+            log::warn!(
+                "[DELTA_REBALANCE] {} order {} filled {} of {}; not booked",
+                leg.symbol,
+                leg.order_id,
+                filled,
+                leg.target
+            );
+            if open {
+                if let Err(err) = connector
+                    .cancel_orders(Some(leg.symbol.clone()), vec![leg.order_id.clone()])
+                    .await
+                {
+                    log::error!("[DELTA_REBALANCE] cancel of {} failed: {:?}", leg.order_id, err);
+                }
+            }
+        }
     }
 
-    fn load_history_from_disk(&mut self) {
-        let now = self.current_now_ts();
-        let max_len = self.max_history_len();
-        let mut loaded_spreads: HashMap<String, VecDeque<f64>> = HashMap::new();
-        history_io::load_history_from_disk(
-            &self.cfg,
-            &mut self.history,
-            &mut loaded_spreads,
-            &self.history_path,
-            now,
-            max_len,
-        );
-        if loaded_spreads.is_empty() {
+    /// Reduce-only market close of both legs of a same-sign "pair". The pair
+    /// stays guarded until a later exchange snapshot shows it flat.
+    async fn flatten_mismatched_legs(
+        &mut self,
+        inst_idx: usize,
+        key: &str,
+        legs: &[PositionSnapshot],
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) {
+        let summary = legs
+            .iter()
+            .map(|leg| format!("{} sign={} size={}", leg.symbol, leg.sign, leg.size))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if self.cfg.dry_run || self.cfg.observe_only {
+            log::warn!("[MISMATCH] {} flatten skipped (mode): {}", key, summary);
             return;
         }
-        // Apply the persisted spread_history only when the instance's own
-        // spread_history is still empty — i.e. on the initial post-restart
-        // load, before any ticks have pushed a live spread. Subsequent
-        // per-tick loads must NOT clobber the instance's accumulating
-        // series, otherwise every step would silently revert the
-        // previous step's push (in single-bot mode) or import another
-        // bot's beta trajectory (in multi-bot mode, which is not the
-        // intended sharing axis — peer bots coordinate on log_prices,
-        // not on state.beta-dependent derived series).
-        for inst in &mut self.instances {
-            for (pair_key, spreads) in &loaded_spreads {
-                if let Some(state) = inst.states.get_mut(pair_key) {
-                    if state.spread_history.is_empty() {
-                        state.last_spread = spreads.back().copied();
-                        state.spread_history = spreads.clone();
-                    }
-                }
-            }
-        }
-    }
 
-    /// Restore circuit-breaker state from disk so a crash or `systemctl
-    /// restart` during an active cool-down does not silently clear the
-    /// consecutive-loss counter. See bot-strategy#185 Phase 1-3, extended
-    /// in Phase 2 to restore `session_start_*` / `realized_pnl_today`.
-    fn load_risk_state(&mut self) {
-        if self.cfg.backtest_mode {
-            return;
-        }
-        let loaded = risk_io::load_risk_state(&self.risk_state_path);
-        if loaded.is_empty() {
+        const MISMATCH_FLATTEN_COOLDOWN_SECS: u64 = 30;
+        let last_flatten = self.instances[inst_idx]
+            .states
+            .get(key)
+            .and_then(|state| state.last_mismatch_flatten_at);
+        let cooldown = Duration::from_secs(MISMATCH_FLATTEN_COOLDOWN_SECS);
+        if last_flatten.is_some_and(|t| self.clock.elapsed(t) < cooldown) {
             return;
         }
-        let now_ts = self.current_now_ts();
-        for inst in &mut self.instances {
-            let Some(state) = loaded.get(&inst.id) else { continue };
-            inst.consecutive_losses = state.consecutive_losses;
-            inst.session_start_equity = state.session_start_equity;
-            inst.session_start_ts = state.session_start_ts;
-            inst.realized_pnl_today = state.realized_pnl_today;
-            inst.equity_samples = state.equity_samples.clone();
-            inst.session_halted = state.session_halted;
-            inst.session_halt_reason = state.session_halt_reason.clone();
-            inst.session_halt_ts = state.session_halt_ts;
-            if inst.session_halted {
+
+        log::warn!("[MISMATCH] {} same-sign legs detected, flattening: {}", key, summary);
+        let mut submitted = 0;
+        for leg in legs {
+            let (symbol, size) = (&leg.symbol, leg.size);
+            let side = if leg.sign >= 0 {
+                dex_connector::OrderSide::Short
+            } else {
+                dex_connector::OrderSide::Long
+            };
+            let qty = self.quantize_order_size_close(symbol, size, prices);
+            if qty <= Decimal::ZERO {
                 log::warn!(
-                    "[SESSION_DD] {} restored halt: reason={} since_ts={} (waiting for {} ack)",
-                    inst.id,
-                    inst.session_halt_reason.as_deref().unwrap_or("unknown"),
-                    inst.session_halt_ts.unwrap_or(0),
-                    RISK_ACK_PATH
+                    "[MISMATCH] {} close skipped (qty=0) symbol={} size={}",
+                    key,
+                    symbol,
+                    size
                 );
+                continue;
             }
-            match state.circuit_breaker_until_ts {
-                Some(until_ts) if until_ts > now_ts => {
-                    inst.circuit_breaker_until_ts = Some(until_ts);
-                    let remaining_secs = (until_ts - now_ts).max(0) as u64;
-                    inst.circuit_breaker_until =
-                        Some(Instant::now() + Duration::from_secs(remaining_secs));
-                    log::warn!(
-                        "[RISK_STATE] {} restored: consecutive_losses={}, cool-down {}s remaining, realized_pnl_today={:.4}",
-                        inst.id, inst.consecutive_losses, remaining_secs, inst.realized_pnl_today
+            match self
+                .connector
+                .create_order(symbol, qty, side, None, None, true, None)
+                .await
+            {
+                Ok(res) => {
+                    submitted += 1;
+                    log::info!(
+                        "[MISMATCH] {} close submitted symbol={} qty={} side={:?} order_id={}",
+                        key,
+                        symbol,
+                        qty,
+                        side,
+                        res.order_id
                     );
                 }
-                Some(_) => {
-                    if inst.consecutive_losses > 0 || inst.realized_pnl_today != 0.0 {
-                        log::info!(
-                            "[RISK_STATE] {} restored: consecutive_losses={}, prior cool-down expired, realized_pnl_today={:.4}",
-                            inst.id, inst.consecutive_losses, inst.realized_pnl_today
-                        );
-                    }
-                }
-                None => {
-                    if inst.consecutive_losses > 0 || inst.realized_pnl_today != 0.0 {
-                        log::info!(
-                            "[RISK_STATE] {} restored: consecutive_losses={}, realized_pnl_today={:.4}",
-                            inst.id, inst.consecutive_losses, inst.realized_pnl_today
-                        );
-                    }
-                }
+                Err(err) => log::error!(
+                    "[MISMATCH] {} close failed symbol={} qty={} err={:?}",
+                    key,
+                    symbol,
+                    qty,
+                    err
+                ),
+            }
+        }
+        if submitted > 0 {
+            if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                state.last_mismatch_flatten_at = Some(self.clock.now());
             }
         }
     }
 
-    fn persist_risk_state(&self) {
-        if self.cfg.backtest_mode {
+    /// One reduce-only close of a lone dust remnant. The pair's state is
+    /// already flat; whatever happens here the remnant is not guarded.
+    async fn sweep_dust_leg(
+        &mut self,
+        key: &str,
+        symbol: &str,
+        sign: i32,
+        size: Decimal,
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) {
+        if self.cfg.dry_run || self.cfg.observe_only {
+            log::info!(
+                "[DUST_SWEEP] {} skipped (mode) symbol={} size={}",
+                key,
+                symbol,
+                size
+            );
             return;
         }
-        let instances: HashMap<String, risk_io::InstanceRiskState> = self
-            .instances
-            .iter()
-            .map(|inst| {
-                (
-                    inst.id.clone(),
-                    risk_io::InstanceRiskState {
-                        consecutive_losses: inst.consecutive_losses,
-                        circuit_breaker_until_ts: inst.circuit_breaker_until_ts,
-                        session_start_equity: inst.session_start_equity,
-                        session_start_ts: inst.session_start_ts,
-                        realized_pnl_today: inst.realized_pnl_today,
-                        equity_samples: inst.equity_samples.clone(),
-                        session_halted: inst.session_halted,
-                        session_halt_reason: inst.session_halt_reason.clone(),
-                        session_halt_ts: inst.session_halt_ts,
-                    },
-                )
-            })
-            .collect();
-        risk_io::persist_risk_state(&self.risk_state_path, &instances);
+        let Some(qty) = order_pricing::dust_sweep_qty(symbol, size, prices) else {
+            log::info!(
+                "[DUST_SWEEP] {} accepted symbol={} size={} (below exchange min)",
+                key,
+                symbol,
+                size
+            );
+            return;
+        };
+        let side = if sign >= 0 {
+            dex_connector::OrderSide::Short
+        } else {
+            dex_connector::OrderSide::Long
+        };
+        match self
+            .connector
+            .create_order(symbol, qty, side, None, None, true, None)
+            .await
+        {
+            Ok(res) => log::info!(
+                "[DUST_SWEEP] {} closed symbol={} size={} qty={} order_id={}",
+                key,
+                symbol,
+                size,
+                qty,
+                res.order_id
+            ),
+            Err(err) => log::warn!(
+                "[DUST_SWEEP] {} accepted symbol={} size={} (close failed: {:?})",
+                key,
+                symbol,
+                size,
+                err
+            ),
+        }
     }
 
-    /// Cross the UTC session boundary when `now_ts` lands in a different
-    /// day bucket from the persisted `session_start_ts`. Called once per
-    /// `step_shared` tick. On rollover, realized_pnl_today is zeroed and
-    /// `session_start_equity` is reset to the fixed `equity_reference_usd`
-    /// so the daily-DD denominator stays constant within (and across)
-    /// sessions, regardless of intra-session live equity drift. Also
-    /// initialises state on the very first tick (when
-    /// `session_start_ts == 0`). See bot-strategy#185 Phase 2 and
-    /// bot-strategy#222 (semantic switch from live floor to fixed
-    /// reference).
-    fn refresh_daily_session(&mut self) {
-        if self.cfg.backtest_mode {
+    /// `beta_drift_action: rehedge` (and `hedge_ratio_tolerance_pct`):
+    /// resize leg B of `key`'s open position to `beta` at the current leg-A
+    /// size with one market order, then make `beta` the position's entry
+    /// beta. Paper positions are resized in place. A failed order leaves the
+    /// position as is for the next tick. `tag` prefixes the log lines.
+    async fn rehedge_position(
+        &mut self,
+        inst_idx: usize,
+        key: &str,
+        pair: &PairSpec,
+        beta: f64,
+        prices: &HashMap<String, SymbolSnapshot>,
+        tag: &str,
+    ) {
+        let Some(pos) = self.instances[inst_idx]
+            .states
+            .get(key)
+            .and_then(|s| s.position.clone())
+        else {
             return;
-        }
-        let reset_hour = self.cfg.risk.daily_reset_utc_hour;
-        let threshold_bps = self.cfg.risk.max_daily_loss_bps;
-        let now_ts = self.current_now_ts();
-        let current_day = session_day(now_ts, reset_hour);
-        let mut dirty = false;
-        for inst in &mut self.instances {
-            let prior_day = if inst.session_start_ts > 0 {
-                Some(session_day(inst.session_start_ts, reset_hour))
+        };
+        let (Some(qty_a), Some(qty_b), Some(p_a), Some(p_b)) = (
+            pos.entry_size_a,
+            pos.entry_size_b,
+            prices.get(&pair.base).map(|s| s.price),
+            prices.get(&pair.quote).map(|s| s.price),
+        ) else {
+            return;
+        };
+        let price_beta = sizing_beta(&self.cfg, &self.history, &pair.base, &pair.quote, beta);
+        let Some(target_b) = sizing::rehedge_qty_b(qty_a, p_a, p_b, price_beta) else {
+            return;
+        };
+        let grow = target_b > qty_b;
+        let qty = self.quantize_order_size_close(&pair.quote, (target_b - qty_b).abs(), prices);
+        log::info!(
+            "{} {} rehedge beta {:.3} -> {:.3}: size_b {} -> {} (order {})",
+            tag,
+            key,
+            pos.entry_beta.unwrap_or(0.0),
+            beta,
+            qty_b,
+            target_b,
+            qty
+        );
+        if qty > Decimal::ZERO && !(self.cfg.dry_run || self.cfg.observe_only) {
+            let side = if grow {
+                pos.direction.entry_sides(pos.hedge_inverted).1
             } else {
-                None
+                pos.direction.exit_sides(pos.hedge_inverted).1
             };
-            let needs_rollover = match prior_day {
-                None => true,
-                Some(prev) => prev != current_day,
-            };
-            if needs_rollover {
-                let equity_base = inst.equity_reference_usd;
-                let prev_pnl = inst.realized_pnl_today;
-                inst.session_start_ts = now_ts;
-                inst.session_start_equity = equity_base;
-                inst.realized_pnl_today = 0.0;
-                if inst.daily_loss_halted {
-                    log::warn!(
-                        "[DAILY_DD] {} halt cleared by session rollover", inst.id
-                    );
-                }
-                inst.daily_loss_halted = false;
-                dirty = true;
-                if prior_day.is_some() {
-                    log::info!(
-                        "[DAILY_DD] {} session rolled: prev_pnl={:.4} -> reset; new session_start_equity={:.2}",
-                        inst.id, prev_pnl, equity_base
-                    );
-                } else {
-                    log::info!(
-                        "[DAILY_DD] {} session initialised: session_start_equity={:.2}",
-                        inst.id, equity_base
-                    );
-                }
+            if let Err(err) = self
+                .connector
+                .create_order(&pair.quote, qty, side, None, None, !grow, None)
+                .await
+            {
+                log::warn!("{} {} rehedge order failed: {:?}", tag, key, err);
+                return;
             }
+        }
+        let Some(state) = self.instances[inst_idx].states.get_mut(key) else {
+            return;
+        };
+        if let Some(position) = state.position.as_mut() {
+            let new_b = if grow { qty_b + qty } else { (qty_b - qty).max(Decimal::ZERO) };
+            // Added size averages into the entry price; trimmed size keeps it.
+            if grow && new_b > Decimal::ZERO {
+                position.entry_price_b = position
+                    .entry_price_b
+                    .map(|ep| (ep * qty_b + p_b * qty) / new_b);
+            }
+            position.entry_size_b = Some(new_b);
+            position.entry_beta = Some(beta);
+            position.entry_fill_cash = None;
+        }
+    }
 
-            // Transition logging for the halt gate. Recomputed each tick so
-            // we catch both the activation (when realized_pnl_today crosses
-            // the threshold after a losing close) and any clear (should be
-            // rare: Phase 2 doesn't expose a manual recovery path, so this
-            // branch fires only after a rollover reset above or a manual
-            // `risk_state.json` edit).
-            let currently_blocks = threshold_bps > 0
-                && inst.session_start_equity > 0.0
-                && inst.realized_pnl_today < 0.0
-                && {
-                    let loss_bps =
-                        (-inst.realized_pnl_today / inst.session_start_equity) * 10_000.0;
-                    loss_bps >= threshold_bps as f64
-                };
-            if currently_blocks && !inst.daily_loss_halted {
-                let loss_bps =
-                    (-inst.realized_pnl_today / inst.session_start_equity) * 10_000.0;
-                log::warn!(
-                    "[DAILY_DD] {} halted: realized_pnl_today={:.4} loss_bps={:.1} threshold={}bps (new entries blocked until UTC {:02}:00)",
-                    inst.id, inst.realized_pnl_today, loss_bps, threshold_bps, reset_hour
-                );
-                inst.daily_loss_halted = true;
-                dirty = true;
-            } else if !currently_blocks && inst.daily_loss_halted {
-                // Usually unreachable in Phase 2; kept so manual edits
-                // to risk_state.json don't leave a stale halt flag.
-                log::warn!("[DAILY_DD] {} halt cleared", inst.id);
-                inst.daily_loss_halted = false;
-                dirty = true;
-            }
-        }
-        if dirty {
-            self.persist_risk_state();
-        }
-    }
-
-    /// Whether `realized_pnl_today` has breached `max_daily_loss_bps`
-    /// against `session_start_equity`. Returns false when the threshold
-    /// is disabled (0 bps), the equity baseline is still zero (pre-first
-    /// rollover), or the running PnL is non-negative.
-    fn daily_loss_blocks(&self, inst: &StrategyInstance) -> bool {
-        let threshold_bps = self.cfg.risk.max_daily_loss_bps;
-        if threshold_bps == 0 || inst.session_start_equity <= 0.0 {
-            return false;
+    /// `partial_take_profit_fraction`: close that share of both legs of
+    /// `key`'s position with reduce-only market orders, leg B scaled to
+    /// the quantized leg-A quantity so the remainder keeps its hedge ratio,
+    /// and book the closed slice's PnL. Paper positions are resized in
+    /// place; live orders are tracked as a `partial_exit` pending exit and
+    /// booked by `reconcile_pending_orders` once their fills are confirmed.
+    /// A failed leg-A order leaves the position for the next tick; a leg B
+    /// that still fails after `LEG_B_ATTEMPTS` re-opens leg A so the pair
+    /// stays hedged, and the slice is retried later.
+    async fn take_partial_profit(
+        &mut self,
+        inst_idx: usize,
+        key: &str,
+        pair: &PairSpec,
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) {
+        let Some(pos) = self.instances[inst_idx]
+            .states
+            .get(key)
+            .and_then(|s| s.position.clone())
+        else {
+            return;
+        };
+        let (Some(size_a), Some(size_b), Some(p_a), Some(p_b)) = (
+            pos.entry_size_a,
+            pos.entry_size_b,
+            prices.get(&pair.base).map(|s| s.price),
+            prices.get(&pair.quote).map(|s| s.price),
+        ) else {
+            return;
+        };
+        if size_a <= Decimal::ZERO {
+            return;
         }
-        if inst.realized_pnl_today >= 0.0 {
-            return false;
+        let fraction = Decimal::from_f64(self.cfg.partial_take_profit_fraction).unwrap_or(Decimal::ZERO);
+        let qty_a = self.quantize_order_size_close(&pair.base, size_a * fraction, prices);
+        let qty_b = self.quantize_order_size_close(&pair.quote, size_b * qty_a / size_a, prices);
+        if qty_a <= Decimal::ZERO || qty_b <= Decimal::ZERO || qty_a >= size_a || qty_b >= size_b {
+            // Too small to split at the venue's lot size; run it whole.
+            log::info!(
+                "[PARTIAL_TP] {} skipped: {} of sizes {} / {} rounds to {} / {}",
+                key,
+                fraction,
+                size_a,
+                size_b,
+                qty_a,
+                qty_b
+            );
+            if let Some(position) = self.instances[inst_idx]
+                .states
+                .get_mut(key)
+                .and_then(|s| s.position.as_mut())
+            {
+                position.partial_taken = true;
+            }
+            return;
         }
-        let loss_bps = (-inst.realized_pnl_today / inst.session_start_equity) * 10_000.0;
-        loss_bps >= threshold_bps as f64
-    }
-
-    /// Refresh `kill_switch_active` from the sentinel file. Called at the
-    /// top of every `step_shared` tick. Logs on state transitions so the
-    /// journal shows exactly when entries were blocked / resumed without
-    /// spamming the log on every check. See bot-strategy#185 Phase 1-2.
-    fn update_kill_switch_state(&mut self) {
-        if self.cfg.backtest_mode {
+        log::info!(
+            "[PARTIAL_TP] {} closing {} / {} of {} / {}",
+            key,
+            qty_a,
+            qty_b,
+            size_a,
+            size_b
+        );
+        if !(self.cfg.dry_run || self.cfg.observe_only) {
+            let (side_a, side_b) = pos.direction.exit_sides(pos.hedge_inverted);
+            let resp_a = match self
+                .connector
+                .create_order(&pair.base, qty_a, side_a, None, None, true, None)
+                .await
+            {
+                Ok(resp) => resp,
+                Err(err) => {
+                    log::warn!("[PARTIAL_TP] {} leg A order failed: {:?}", key, err);
+                    return;
+                }
+            };
+            let mut resp_b = None;
+            for attempt in 1..=LEG_B_ATTEMPTS {
+                match self
+                    .connector
+                    .create_order(&pair.quote, qty_b, side_b, None, None, true, None)
+                    .await
+                {
+                    Ok(resp) => {
+                        resp_b = Some(resp);
+                        break;
+                    }
+                    Err(err) => log::warn!(
+                        "[PARTIAL_TP] {} leg B order failed (attempt {}/{}): {:?}",
+                        key,
+                        attempt,
+                        LEG_B_ATTEMPTS,
+                        err
+                    ),
+                }
+            }
+            let Some(resp_b) = resp_b else {
+                let (restore_side, _) = pos.direction.entry_sides(pos.hedge_inverted);
+                match self
+                    .connector
+                    .create_order(&pair.base, qty_a, restore_side, None, None, false, None)
+                    .await
+                {
+                    Ok(_) => log::error!(
+                        "[PARTIAL_TP] {} leg B could not be reduced; restored leg A {}",
+                        key,
+                        qty_a
+                    ),
+                    Err(err) => {
+                        // Leave both legs as they were: position sync adopts
+                        // the exchange sizes and the hedge-ratio check
+                        // rebalances leg B. Don't try the slice again.
+                        log::error!(
+                            "[PARTIAL_TP] {} leg B could not be reduced and leg A restore failed: {:?}",
+                            key,
+                            err
+                        );
+                        if let Some(position) = self.instances[inst_idx]
+                            .states
+                            .get_mut(key)
+                            .and_then(|s| s.position.as_mut())
+                        {
+                            position.partial_taken = true;
+                        }
+                    }
+                }
+                return;
+            };
+            let pending = PendingOrders {
+                legs: vec![
+                    PendingLeg {
+                        symbol: pair.base.clone(),
+                        order_id: resp_a.order_id,
+                        exchange_order_id: resp_a.exchange_order_id,
+                        target: qty_a,
+                        filled: Decimal::ZERO,
+                        side: side_a,
+                        limit_price: None,
+                        placed_price: Some(p_a),
+                    },
+                    PendingLeg {
+                        symbol: pair.quote.clone(),
+                        order_id: resp_b.order_id,
+                        exchange_order_id: resp_b.exchange_order_id,
+                        target: qty_b,
+                        filled: Decimal::ZERO,
+                        side: side_b,
+                        limit_price: None,
+                        placed_price: Some(p_b),
+                    },
+                ],
+                direction: pos.direction,
+                placed_at: self.clock.now(),
+                hedge_retry_count: 0,
+                post_only_hybrid: false,
+                partial_exit: true,
+                scale_in: false,
+            };
+            if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                if let Some(position) = state.position.as_mut() {
+                    position.partial_taken = true;
+                }
+                state.pending_exit = Some(pending);
+            }
             return;
         }
-        let present = std::path::Path::new(KILL_SWITCH_PATH).exists();
-        if present && !self.kill_switch_active {
-            log::warn!(
-                "[KILL_SWITCH] activated: {} detected; new entries blocked, existing positions will exit normally",
-                KILL_SWITCH_PATH
-            );
-            self.kill_switch_active = true;
-        } else if !present && self.kill_switch_active {
-            log::warn!(
-                "[KILL_SWITCH] cleared: {} removed; new entries resumed",
-                KILL_SWITCH_PATH
-            );
-            self.kill_switch_active = false;
+        self.book_partial_exit(inst_idx, key, qty_a, qty_b, p_a, p_b);
+        if let Some(position) = self.instances[inst_idx]
+            .states
+            .get_mut(key)
+            .and_then(|s| s.position.as_mut())
+        {
+            position.partial_taken = true;
         }
     }
 
-    /// Consume `/opt/debot/RISK_ACK` if present and clear `session_halted`
-    /// across all instances. The file is unconditionally removed so a
-    /// stale ack from a prior incident never silently re-arms. See
-    /// bot-strategy#185 Phase 3-2.
-    fn consume_risk_ack(&mut self) {
-        if self.cfg.backtest_mode {
+    /// Shrink both legs of `key`'s position by a closed `qty_a` / `qty_b`
+    /// slice and book that slice's PnL at `p_a` / `p_b`.
+    fn book_partial_exit(
+        &mut self,
+        inst_idx: usize,
+        key: &str,
+        qty_a: Decimal,
+        qty_b: Decimal,
+        p_a: Decimal,
+        p_b: Decimal,
+    ) {
+        let now_ts = self.current_now_ts();
+        let Some((base, quote)) = key.split_once('/') else {
             return;
-        }
-        let path = std::path::Path::new(RISK_ACK_PATH);
-        if !path.exists() {
+        };
+        let Some(state) = self.instances[inst_idx].states.get_mut(key) else {
             return;
-        }
-        // Read the file (best-effort) so the ack reason makes it into the
-        // journal — useful when chasing why a halt cleared days later.
-        let payload = std::fs::read_to_string(path).unwrap_or_default();
-        let trimmed = payload.trim();
-        let mut cleared_any = false;
-        for inst in &mut self.instances {
-            if inst.session_halted {
-                log::warn!(
-                    "[SESSION_DD] {} halt cleared by ack at {} (reason was: {}, ack payload: {:?})",
-                    inst.id,
-                    RISK_ACK_PATH,
-                    inst.session_halt_reason.as_deref().unwrap_or("unknown"),
-                    trimmed
-                );
-                inst.session_halted = false;
-                inst.session_halt_reason = None;
-                inst.session_halt_ts = None;
-                cleared_any = true;
-            }
-        }
-        if let Err(e) = std::fs::remove_file(path) {
-            log::warn!("[SESSION_DD] failed to remove {} after ack: {:?}", RISK_ACK_PATH, e);
-        } else {
-            log::info!("[SESSION_DD] {} consumed", RISK_ACK_PATH);
-        }
-        if cleared_any {
-            self.persist_risk_state();
-        }
+        };
+        let Some(pos) = state.position.clone() else {
+            return;
+        };
+        let z_exit = state.z_score().map(|(z, _)| z);
+        let beta = state.beta;
+        if let Some(position) = state.position.as_mut() {
+            position.entry_size_a = pos.entry_size_a.map(|s| (s - qty_a).max(Decimal::ZERO));
+            position.entry_size_b = pos.entry_size_b.map(|s| (s - qty_b).max(Decimal::ZERO));
+            position.entry_fill_cash = None;
+        }
+        let closed = Position {
+            entry_size_a: Some(qty_a),
+            entry_size_b: Some(qty_b),
+            ..pos.clone()
+        };
+        let Some(pnl) = compute_pnl(&closed, p_a, p_b).and_then(|p| p.to_f64()) else {
+            return;
+        };
+        let record = PnlLogRecord::new(
+            base,
+            quote,
+            pos.direction,
+            pnl,
+            now_ts,
+            "partial_take_profit",
+        )
+        .with_trade_details(
+            pos.entry_price_a.and_then(|v| v.to_f64()),
+            pos.entry_price_b.and_then(|v| v.to_f64()),
+            p_a.to_f64(),
+            p_b.to_f64(),
+            Some(beta),
+            pos.entry_z,
+            z_exit,
+            Some(now_ts.saturating_sub(pos.entered_ts).max(0) as f64),
+        )
+        .with_exit_reason(Some("partial_take_profit"));
+        self.write_pnl_record(inst_idx, record);
+        self.instances[inst_idx].realized_pnl_today += pnl;
     }
 
-    /// Append the current equity to `equity_samples`, prune entries
-    /// outside the rolling window, and update the cached peak. Called
-    /// after a successful equity refresh. No-op when the threshold is
-    /// disabled (0 bps) so disabled instances don't grow disk state.
-    fn update_equity_sample(&mut self, inst_idx: usize) {
-        if self.cfg.backtest_mode {
+    /// With `scale_in_enabled`, shrink the full entry sizes to the
+    /// `scale_in_fraction` scout; `scale_in` adds the remainder later.
+    fn scout_sizes(
+        &self,
+        pair: &PairSpec,
+        qtys: (Decimal, Decimal),
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) -> (Decimal, Decimal) {
+        if !self.cfg.scale_in_enabled {
+            return qtys;
+        }
+        let fraction = Decimal::from_f64(self.cfg.scale_in_fraction).unwrap_or(Decimal::ONE);
+        (
+            self.quantize_order_size(&pair.base, qtys.0 * fraction, prices),
+            self.quantize_order_size(&pair.quote, qtys.1 * fraction, prices),
+        )
+    }
+
+    /// `scale_in_*`: add the remainder fixed at entry (`scale_in_add`) to a
+    /// scout position and average the entry prices. Paper positions are
+    /// resized in place; live orders are tracked as a `scale_in` pending
+    /// entry and booked by `reconcile_pending_orders` as they fill. A leg B
+    /// that still fails after `LEG_B_ATTEMPTS` has leg A's add unwound
+    /// reduce-only, leaving the scout as it was.
+    async fn scale_in(
+        &mut self,
+        inst_idx: usize,
+        key: &str,
+        pair: &PairSpec,
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) {
+        let Some(pos) = self.instances[inst_idx]
+            .states
+            .get(key)
+            .and_then(|s| s.position.clone())
+        else {
+            return;
+        };
+        let mark_scaled_in = |engine: &mut Self| {
+            if let Some(position) = engine.instances[inst_idx]
+                .states
+                .get_mut(key)
+                .and_then(|s| s.position.as_mut())
+            {
+                position.scaled_in = true;
+            }
+        };
+        let Some((mut add_a, mut add_b)) = pos.scale_in_add else {
+            log::info!("[SCALE_IN] {} skipped: no scout size recorded at entry", key);
+            mark_scaled_in(self);
+            return;
+        };
+        let (Some(size_a), Some(size_b), Some(p_a), Some(p_b)) = (
+            pos.entry_size_a,
+            pos.entry_size_b,
+            prices.get(&pair.base).map(|s| s.price),
+            prices.get(&pair.quote).map(|s| s.price),
+        ) else {
             return;
+        };
+        // Keep the combined size within the per-leg notional cap, scaling
+        // both legs together so the hedge ratio survives.
+        if let Some(cap) = Decimal::from_f64(self.cfg.risk.max_notional_usd_per_leg)
+            .filter(|cap| *cap > Decimal::ZERO)
+        {
+            let headroom = |size: Decimal, add: Decimal, price: Decimal| {
+                if add <= Decimal::ZERO || price <= Decimal::ZERO {
+                    return Decimal::ONE;
+                }
+                ((cap / price - size) / add).clamp(Decimal::ZERO, Decimal::ONE)
+            };
+            let scale = headroom(size_a, add_a, p_a).min(headroom(size_b, add_b, p_b));
+            add_a *= scale;
+            add_b *= scale;
         }
-        let threshold_bps = self.cfg.risk.max_session_loss_bps;
-        if threshold_bps == 0 {
+        let add_a = self.quantize_order_size(&pair.base, add_a, prices);
+        let add_b = self.quantize_order_size(&pair.quote, add_b, prices);
+        if add_a <= Decimal::ZERO || add_b <= Decimal::ZERO {
+            log::info!(
+                "[SCALE_IN] {} skipped: add to sizes {} / {} rounds to {} / {}",
+                key,
+                size_a,
+                size_b,
+                add_a,
+                add_b
+            );
+            mark_scaled_in(self);
             return;
         }
-        let lookback = self.cfg.risk.session_dd_lookback_secs as i64;
-        let sample_secs = self.cfg.risk.session_dd_sample_secs as i64;
-        let now_ts = self.current_now_ts();
-        let cutoff = now_ts.saturating_sub(lookback);
-        let inst = &mut self.instances[inst_idx];
-        let equity = inst.equity_cache;
-        if equity <= 0.0 {
+        log::info!(
+            "[SCALE_IN] {} adding {} / {} to {} / {}",
+            key,
+            add_a,
+            add_b,
+            size_a,
+            size_b
+        );
+        if self.cfg.dry_run || self.cfg.observe_only {
+            self.book_scale_in(inst_idx, key, add_a, add_b, Some(p_a), Some(p_b));
+            mark_scaled_in(self);
             return;
         }
-        // Keep at most one sample per `sample_secs` window so 30 d at 1 h
-        // cadence ≤ 720 entries (≈ 17 KB JSON). Newer sample replaces an
-        // entry within the same bucket so peak stays current without
-        // unbounded growth.
-        let bucket_start = (now_ts / sample_secs) * sample_secs;
-        let mut replaced = false;
-        if let Some(last) = inst.equity_samples.last_mut() {
-            if last.ts >= bucket_start {
-                last.equity = equity;
-                last.ts = now_ts;
-                replaced = true;
+        let (side_a, side_b) = pos.direction.entry_sides(pos.hedge_inverted);
+        let resp_a = match self
+            .connector
+            .create_order(&pair.base, add_a, side_a, None, None, false, None)
+            .await
+        {
+            Ok(resp) => resp,
+            Err(err) => {
+                log::warn!("[SCALE_IN] {} leg A order failed: {:?}", key, err);
+                return;
+            }
+        };
+        let mut resp_b = None;
+        for attempt in 1..=LEG_B_ATTEMPTS {
+            match self
+                .connector
+                .create_order(&pair.quote, add_b, side_b, None, None, false, None)
+                .await
+            {
+                Ok(resp) => {
+                    resp_b = Some(resp);
+                    break;
+                }
+                Err(err) => log::warn!(
+                    "[SCALE_IN] {} leg B order failed (attempt {}/{}): {:?}",
+                    key,
+                    attempt,
+                    LEG_B_ATTEMPTS,
+                    err
+                ),
             }
         }
-        if !replaced {
-            inst.equity_samples.push(risk_io::EquitySample {
-                ts: now_ts,
-                equity,
-            });
-        }
-        let pre_len = inst.equity_samples.len();
-        inst.equity_samples.retain(|s| s.ts >= cutoff);
-        let pruned = pre_len.saturating_sub(inst.equity_samples.len());
-        if pruned > 0 {
-            log::debug!(
-                "[SESSION_DD] {} pruned {} expired equity samples (window {}s)",
-                inst.id, pruned, lookback
-            );
+        let Some(resp_b) = resp_b else {
+            let (unwind_side, _) = pos.direction.exit_sides(pos.hedge_inverted);
+            match self
+                .connector
+                .create_order(&pair.base, add_a, unwind_side, None, None, true, None)
+                .await
+            {
+                Ok(_) => log::error!(
+                    "[SCALE_IN] {} leg B could not be added; unwound leg A {}",
+                    key,
+                    add_a
+                ),
+                Err(err) => {
+                    // Position sync adopts the exchange sizes and the
+                    // hedge-ratio check rebalances leg B; don't add again.
+                    log::error!(
+                        "[SCALE_IN] {} leg B could not be added and leg A unwind failed: {:?}",
+                        key,
+                        err
+                    );
+                    mark_scaled_in(self);
+                }
+            }
+            return;
+        };
+        let pending = PendingOrders {
+            legs: vec![
+                PendingLeg {
+                    symbol: pair.base.clone(),
+                    order_id: resp_a.order_id,
+                    exchange_order_id: resp_a.exchange_order_id,
+                    target: add_a,
+                    filled: Decimal::ZERO,
+                    side: side_a,
+                    limit_price: None,
+                    placed_price: Some(p_a),
+                },
+                PendingLeg {
+                    symbol: pair.quote.clone(),
+                    order_id: resp_b.order_id,
+                    exchange_order_id: resp_b.exchange_order_id,
+                    target: add_b,
+                    filled: Decimal::ZERO,
+                    side: side_b,
+                    limit_price: None,
+                    placed_price: Some(p_b),
+                },
+            ],
+            direction: pos.direction,
+            placed_at: self.clock.now(),
+            hedge_retry_count: 0,
+            post_only_hybrid: false,
+            partial_exit: false,
+            scale_in: true,
+        };
+        mark_scaled_in(self);
+        if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+            state.pending_entry = Some(pending);
         }
-        // Persistence is cheap (atomic rename of a small JSON file) but
-        // we don't write on every tick — only when something changed
-        // materially. Sampling refreshes cadence; `step_shared` calls
-        // `evaluate_session_dd` immediately after, which triggers
-        // persistence on halt transitions.
     }
 
-    /// Compute the rolling peak from `equity_samples` and return the
-    /// peak / current ratio. Returns `None` when there are no samples
-    /// or the peak is non-positive (cannot define a percentage DD).
-    fn rolling_peak(samples: &[risk_io::EquitySample], current: f64) -> Option<(f64, f64)> {
-        let mut peak = current;
-        for s in samples {
-            if s.equity > peak {
-                peak = s.equity;
+    /// Grow both legs of `key`'s position by an `add_a` / `add_b` scale-in
+    /// fill, averaging each entry price with its fill price. A leg without
+    /// a price keeps its entry price.
+    fn book_scale_in(
+        &mut self,
+        inst_idx: usize,
+        key: &str,
+        add_a: Decimal,
+        add_b: Decimal,
+        p_a: Option<Decimal>,
+        p_b: Option<Decimal>,
+    ) {
+        let Some(position) = self.instances[inst_idx]
+            .states
+            .get_mut(key)
+            .and_then(|s| s.position.as_mut())
+        else {
+            return;
+        };
+        let grow = |entry: &mut Option<Decimal>,
+                    size: &mut Option<Decimal>,
+                    add: Decimal,
+                    price: Option<Decimal>| {
+            let current = size.unwrap_or_default();
+            let total = current + add;
+            if total <= Decimal::ZERO {
+                return;
             }
-        }
-        if peak <= 0.0 {
-            return None;
-        }
-        let dd_bps = if current >= peak {
-            0.0
-        } else {
-            ((peak - current) / peak) * 10_000.0
+            if let (Some(ep), Some(price)) = (*entry, price) {
+                *entry = Some((ep * current + price * add) / total);
+            }
+            *size = Some(total);
         };
-        Some((peak, dd_bps))
+        grow(&mut position.entry_price_a, &mut position.entry_size_a, add_a, p_a);
+        grow(&mut position.entry_price_b, &mut position.entry_size_b, add_b, p_b);
+        position.entry_fill_cash = None;
     }
 
-    /// Check whether the rolling peak DD has breached the threshold and
-    /// engage the session halt + auto-flatten if so. Idempotent: a
-    /// repeated breach while already halted re-runs `close_all_positions`
-    /// at most once (the second call succeeds quickly if the account is
-    /// already flat). Returns true if the halt is currently active for
-    /// the instance after the check.
-    async fn evaluate_session_dd(&mut self, inst_idx: usize) -> bool {
-        if self.cfg.backtest_mode {
-            return false;
-        }
-        let threshold_bps = self.cfg.risk.max_session_loss_bps;
-        if threshold_bps == 0 {
-            return self.instances[inst_idx].session_halted;
-        }
-        let inst = &self.instances[inst_idx];
-        if inst.session_halted {
-            // Halt is sticky — once tripped, stay halted until ack'd.
-            // No need to re-flatten on every tick; one shot is enough,
-            // and `close_all_positions` was already invoked on trip.
-            return true;
-        }
-        let current = inst.equity_cache;
-        if current <= 0.0 {
-            return false;
-        }
-        let Some((peak, dd_bps)) = Self::rolling_peak(&inst.equity_samples, current) else {
-            return false;
-        };
-        if dd_bps < threshold_bps as f64 {
-            return false;
-        }
+    async fn try_close_unhedged_leg(
+        &mut self,
+        inst_idx: usize,
+        key: &str,
+        symbol: &str,
+        sign: i32,
+        size: Decimal,
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) {
         let now_ts = self.current_now_ts();
-        let reason = format!("session_dd_{}bps", threshold_bps);
-        log::error!(
-            "[SESSION_DD] {} breach: equity={:.2} peak={:.2} dd_bps={:.1} threshold={}bps; flattening positions and halting (ack via {})",
-            inst.id, current, peak, dd_bps, threshold_bps, RISK_ACK_PATH
-        );
-        {
-            let inst_mut = &mut self.instances[inst_idx];
-            inst_mut.session_halted = true;
-            inst_mut.session_halt_reason = Some(reason);
-            inst_mut.session_halt_ts = Some(now_ts);
+        if self.cfg.dry_run || self.cfg.observe_only {
+            log::warn!(
+                "[UNHEDGED] {} close skipped (mode) symbol={} size={}",
+                key,
+                symbol,
+                size
+            );
+            return;
         }
-        self.persist_risk_state();
-        // Flatten this instance's positions. `self.connector` was already
-        // pointed at `instances[inst_idx].connector` by the caller in
-        // `step()`, so close_all_positions hits the right sub-account.
-        if !self.cfg.dry_run && !self.cfg.observe_only {
-            if let Err(err) = self.connector.close_all_positions(None).await {
-                log::error!(
-                    "[SESSION_DD] {} close_all_positions failed: {:?}",
-                    self.instances[inst_idx].id,
-                    err
-                );
-            } else {
-                log::warn!(
-                    "[SESSION_DD] {} close_all_positions invoked",
-                    self.instances[inst_idx].id
-                );
+
+        const UNHEDGED_CLOSE_COOLDOWN_SECS: u64 = 30;
+        let last_exit = self.instances[inst_idx].states.get(key).and_then(|state| state.last_exit_at);
+        if let Some(last_exit) = last_exit {
+            if self.clock.elapsed(last_exit) < Duration::from_secs(UNHEDGED_CLOSE_COOLDOWN_SECS) {
+                return;
             }
         }
-        true
-    }
 
-    /// Rebuild each pair's beta and spread_history from the shared on-disk
-    /// price history so A/B/C bots have identical regression windows the
-    /// instant they start, instead of waiting metrics_window live bars to
-    /// converge (pairtrade#4). Computes beta directly from whatever bars
-    /// are available — does not go through evaluate_pair() because that
-    /// path enforces full lookback_hours_long under Strict warm-start and
-    /// would skip the seed when the loaded history is shorter than the
-    /// configured long window.
-    fn warm_start_states_from_history(&mut self) {
-        if self.cfg.disable_history_persist {
+        let side = if sign >= 0 {
+            dex_connector::OrderSide::Short
+        } else {
+            dex_connector::OrderSide::Long
+        };
+        let qty = self.quantize_order_size_close(symbol, size, prices);
+        if qty <= Decimal::ZERO {
+            log::warn!(
+                "[UNHEDGED] {} close skipped (qty=0) symbol={} size={}",
+                key,
+                symbol,
+                size
+            );
             return;
         }
-        for inst_idx in 0..self.instances.len() {
-            for pair in self.cfg.universe.clone() {
-                let key = format!("{}/{}", pair.base, pair.quote);
-                let (Some(hist_a), Some(hist_b)) =
-                    (self.history.get(&pair.base), self.history.get(&pair.quote))
-                else { continue };
-                let take = self.cfg.metrics_window.min(hist_a.len()).min(hist_b.len());
-                if take < 2 { continue }
-                let tail_a = tail_samples(hist_a, take);
-                let tail_b = tail_samples(hist_b, take);
-                let beta = regression_beta(&tail_b, &tail_a);
-                let Some(state) = self.instances[inst_idx].states.get_mut(&key) else { continue };
-                state.beta = beta;
-                state.beta_short = beta;
-                state.beta_long = beta;
-                // If `load_history_from_disk` / `load_history_snapshot_for_bt`
-                // has already restored the real persisted `spread_history`
-                // (v2 snapshot), keep it as-is. Synthesizing a
-                // single-OLS-beta series here would overwrite a 240-bar
-                // real series with one whose variance is artificially
-                // compressed — the mechanism behind the 2026-04-15 06:02
-                // UTC "std collapse" restart incident (bot-strategy#62).
-                // Only synthesize when the instance has no live spreads
-                // (fresh start with no persisted snapshot, or a v1
-                // snapshot from a pre-fix bot).
-                if state.spread_history.is_empty() {
-                    let spreads: VecDeque<f64> = tail_a
-                        .iter()
-                        .zip(tail_b.iter())
-                        .map(|(sa, sb)| sa.log_price - beta * sb.log_price)
-                        .collect();
-                    state.last_spread = spreads.back().copied();
-                    state.spread_history = spreads;
+
+        log::warn!(
+            "[UNHEDGED] {} closing lone leg symbol={} sign={} size={} qty={} side={:?}",
+            key,
+            symbol,
+            sign,
+            size,
+            qty,
+            side
+        );
+
+        let res = self
+            .connector
+            .create_order(symbol, qty, side, None, None, true, None)
+            .await;
+
+        match res {
+            Ok(res) => {
+                log::info!(
+                    "[UNHEDGED] {} close submitted symbol={} order_id={}",
+                    key,
+                    symbol,
+                    res.order_id
+                );
+                if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                    state.last_exit_at = Some(self.clock.now());
+                    state.last_exit_ts = Some(now_ts);
+                }
+            }
+            Err(err) => {
+                if Self::is_reduce_only_position_missing_error(&err)
+                    && self.confirm_reduce_only_position_missing(symbol).await
+                {
                     log::info!(
-                        "[WARM_START] {} synthesized spread_history len={} beta={:.4} (no persisted v2 series)",
-                        key, state.spread_history.len(), state.beta
+                        "[UNHEDGED] {} close skipped; position already closed symbol={}",
+                        key,
+                        symbol
                     );
+                    if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                        state.last_exit_at = Some(self.clock.now());
+                        state.last_exit_ts = Some(now_ts);
+                    }
                 } else {
-                    log::info!(
-                        "[WARM_START] {} kept persisted spread_history len={} beta={:.4} (no synthesis)",
-                        key, state.spread_history.len(), state.beta
+                    log::error!(
+                        "[UNHEDGED] {} close failed symbol={} err={:?}",
+                        key,
+                        symbol,
+                        err
                     );
                 }
             }
         }
     }
 
-    fn entry_vol_window(&self) -> usize {
-        ((self.cfg.default_pair_params.entry_vol_lookback_hours * 3600)
-            / self.cfg.trading_period_secs)
-            .max(1) as usize
-    }
-
-    /// Virtual clock used by all duration-based decisions. In live mode this
-    /// is the wall-clock UTC second; in backtest mode it tracks the replay
-    /// connector's logical timestamp so cooldown / force_close /
-    /// circuit_breaker / re-eval intervals fire correctly under replay.
-    fn current_now_ts(&self) -> i64 {
-        if self.cfg.backtest_mode {
-            self.replay_connector
-                .as_ref()
-                .and_then(|r| r.current_timestamp_secs())
-                .unwrap_or_else(|| chrono::Utc::now().timestamp())
-        } else {
-            chrono::Utc::now().timestamp()
-        }
+    fn clear_stale_pending(&mut self, inst_idx: usize, max_age: Duration, reason: &str) {
+        let now_ts = self.current_now_ts();
+        for (key, state) in self.instances[inst_idx].states.iter_mut() {
+            let entry_age = state.pending_entry.as_ref().map(|p| self.clock.elapsed(p.placed_at));
+            let exit_age = state.pending_exit.as_ref().map(|p| self.clock.elapsed(p.placed_at));
+            let age = match (entry_age, exit_age) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (Some(a), None) => Some(a),
+                (None, Some(b)) => Some(b),
+                (None, None) => None,
+            };
+            if let Some(age) = age {
+                if age >= max_age {
+                    log::warn!(
+                        "[POSITION] {} pending cleared (reason={}, age={}s)",
+                        key,
+                        reason,
+                        age.as_secs()
+                    );
+                    state.pending_entry = None;
+                    state.pending_exit = None;
+                    state.position = None;
+                    state.position_guard = false;
+                    state.last_exit_at = Some(self.clock.now());
+                    state.last_exit_ts = Some(now_ts);
+                }
+            }
+        }
     }
 
-    fn max_history_len(&self) -> usize {
-        let mut max_needed = 0usize;
-        // Consider all per-pair params and the default
-        let all_params =
-            std::iter::once(&self.cfg.default_pair_params).chain(self.cfg.pair_params.values());
-        for pp in all_params {
-            let max_hrs = pp.lookback_hours_long.max(pp.lookback_hours_short);
-            let needed = (max_hrs * 3600 / self.cfg.trading_period_secs) as usize;
-            let vol_needed = ((pp.entry_vol_lookback_hours * 3600) / self.cfg.trading_period_secs)
-                .max(1) as usize;
-            max_needed = max_needed.max(needed).max(vol_needed);
+    fn compute_vol_median(&self, inst_idx: usize) -> f64 {
+        let tail_len = self.entry_vol_window();
+        let mut vols: Vec<f64> = self
+            .instances[inst_idx]
+            .states
+            .values()
+            .filter_map(|s| tail_std(&s.spread_history, tail_len))
+            .collect();
+        if vols.is_empty() {
+            return 1.0;
         }
-        max_needed.max(self.cfg.metrics_window)
+        vols.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        vols[vols.len() / 2].max(1e-9)
     }
 
-    async fn reconcile_pending_orders(
-        &mut self,
+    /// Append entry candidates dropped by a per-pair gate or at selection
+    /// to `rejected_entries_file`, if set.
+    fn record_rejected_entries(
+        &self,
         inst_idx: usize,
-        key: &str,
-        price_map: &HashMap<String, SymbolSnapshot>,
-    ) -> Result<()> {
-        let timeout = Duration::from_secs(self.cfg.order_timeout_secs.max(1));
-        let now_ts = self.current_now_ts();
-        let (pending_entry, pending_exit) = {
-            let state = self
-                .instances[inst_idx]
-                .states
-                .get_mut(key)
-                .ok_or_else(|| anyhow!("missing state for {}", key))?;
-            (state.pending_entry.take(), state.pending_exit.take())
+        rejected: &[(&PlannedAction, &'static str)],
+        now_ts: i64,
+    ) {
+        let Some(path) = self.cfg.rejected_entries_file.as_deref() else {
+            return;
         };
+        let strategy = self.instances[inst_idx].id.as_str();
+        let entries: Vec<rejected_entries::RejectedEntry> = rejected
+            .iter()
+            .filter_map(|(plan, reason)| {
+                let TradeAction::Open { direction, z, beta } = plan.action else {
+                    return None;
+                };
+                Some(rejected_entries::RejectedEntry {
+                    ts: now_ts,
+                    strategy,
+                    pair: &plan.key,
+                    reason,
+                    direction: pnl_log::direction_label(direction),
+                    z,
+                    beta,
+                    net_funding_per_hour: plan.net_funding_per_hour,
+                    liquidity_score: plan.liquidity_score,
+                    score: self.state_score(inst_idx, &plan.key),
+                })
+            })
+            .collect();
+        if let Err(err) = rejected_entries::append(Path::new(path), &entries) {
+            log::warn!("[ENTRY] failed to append rejected entries to {}: {:?}", path, err);
+        }
+    }
 
-        if let Some(mut pending) = pending_entry {
-            let status = self.pending_status(&pending).await?;
-            self.update_pending_fills(&mut pending, &status.fills);
-            let filled_qtys = self.filled_by_leg(&pending, &status.fills);
-            if self.all_filled(&pending, &status.fills) {
-                if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
-                    let (mut ep_a, mut ep_b, mut es_a, mut es_b) = (None, None, None, None);
-                    if let Some((base, quote)) = key.split_once('/') {
-                        for leg in &pending.legs {
-                            if leg.symbol == base {
-                                ep_a = price_map.get(base).map(|s| s.price);
-                                es_a = Some(leg.target);
-                            } else if leg.symbol == quote {
-                                ep_b = price_map.get(quote).map(|s| s.price);
-                                es_b = Some(leg.target);
-                            }
-                        }
+    /// Append this tick's planned actions to `plan_trace_file`, if set.
+    fn record_plan_trace(
+        &self,
+        inst_idx: usize,
+        planned: &[PlannedAction],
+        selected: Option<&PlannedAction>,
+        now_ts: i64,
+    ) {
+        let Some(path) = self.cfg.plan_trace_file.as_deref() else {
+            return;
+        };
+        let actions = planned
+            .iter()
+            .map(|plan| {
+                let (action, direction, z, beta, force) = match plan.action {
+                    TradeAction::Open { direction, z, beta } => {
+                        ("open", Some(direction), Some(z), Some(beta), None)
                     }
-                    let z_at_entry = state.z_score().map(|(z, _)| z);
-                    state.position = Some(Position {
-                        direction: pending.direction,
-                        entered_at: Instant::now(),
-                        entered_ts: now_ts,
-                        entry_price_a: ep_a,
-                        entry_price_b: ep_b,
-                        entry_size_a: es_a,
-                        entry_size_b: es_b,
-                        entry_z: z_at_entry,
-                    });
-                    state.pending_entry = None;
-                }
-                log::info!("[ORDER] {} entry orders filled", key);
-            } else if filled_qtys.values().any(|qty| *qty > Decimal::ZERO) {
-                let next_retry = pending.hedge_retry_count.saturating_add(1);
-                let max_retries = self.cfg.entry_partial_fill_max_retries;
-                let use_market = max_retries > 0 && next_retry > max_retries;
-                if use_market {
-                    log::info!(
-                        "[ORDER] {} entry leg partially filled, retries exceeded ({} > {}); reissuing remaining legs as MARKET",
-                        key,
-                        next_retry,
-                        max_retries
-                    );
-                } else if max_retries > 0 {
-                    log::info!(
-                        "[ORDER] {} entry leg partially filled, reissuing remaining legs (retry {}/{})",
-                        key,
-                        next_retry,
-                        max_retries
-                    );
-                } else {
-                    log::warn!(
-                        "[ORDER] {} entry leg partially filled, reissuing remaining legs",
-                        key
-                    );
+                    TradeAction::Close {
+                        direction,
+                        z,
+                        beta,
+                        force,
+                    } => ("close", Some(direction), Some(z), Some(beta), Some(force)),
+                    TradeAction::None => ("none", None, None, None, None),
+                };
+                plan_trace::PlannedActionTrace {
+                    pair: &plan.key,
+                    action,
+                    direction: direction.map(pnl_log::direction_label),
+                    z,
+                    beta,
+                    force,
+                    net_funding_per_hour: plan.net_funding_per_hour,
+                    liquidity_score: plan.liquidity_score,
                 }
-                self.cancel_pending_orders(&pending).await?;
-                if let Some(new_pending) = self
-                    .reissue_partial_legs(
-                        &pending,
-                        &filled_qtys,
-                        price_map,
-                        false,
-                        use_market,
-                        next_retry,
+            })
+            .collect();
+        let trace = plan_trace::PlanTrace {
+            ts: now_ts,
+            strategy: &self.instances[inst_idx].id,
+            actions,
+            selected_entry: selected.map(|plan| plan.key.as_str()),
+            exits: planned
+                .iter()
+                .filter(|plan| matches!(plan.action, TradeAction::Close { .. }))
+                .map(|plan| plan.key.as_str())
+                .collect(),
+        };
+        if let Err(err) = plan_trace::append(Path::new(path), &trace) {
+            log::warn!("[PLAN_TRACE] failed to append to {}: {:?}", path, err);
+        }
+    }
+
+    /// `[SELECT]` line listing the shortlisted entry candidates with their
+    /// sort keys (funding → score → liquidity → |z|), winner marked with `*`.
+    /// Suppressed while the candidate set and winner are unchanged, except
+    /// for a reminder every `SELECT_LOG_INTERVAL_SECS`.
+    fn log_entry_selection(&mut self, inst_idx: usize, shortlisted: &[&PlannedAction], winner: &str) {
+        let signature = format!(
+            "{}>{}",
+            shortlisted
+                .iter()
+                .map(|p| p.key.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+            winner
+        );
+        let inst = &self.instances[inst_idx];
+        if inst.last_select_log.as_ref().is_some_and(|(last, at)| {
+            *last == signature && self.clock.elapsed(*at) < Duration::from_secs(SELECT_LOG_INTERVAL_SECS)
+        }) {
+            return;
+        }
+        let candidates: Vec<String> = shortlisted
+            .iter()
+            .map(|p| {
+                format!(
+                    "{}{} funding={:.6} score={:.4} liq={:.2} |z|={:.2}",
+                    if p.key == winner { "*" } else { "" },
+                    p.key,
+                    p.net_funding_per_hour,
+                    self.state_score(inst_idx, &p.key),
+                    p.liquidity_score,
+                    p.abs_z
+                )
+            })
+            .collect();
+        log::info!(
+            "[SELECT] [{}] {} candidate(s): {}",
+            inst.id,
+            shortlisted.len(),
+            candidates.join(" | ")
+        );
+        self.instances[inst_idx].last_select_log = Some((signature, self.clock.now()));
+    }
+
+    fn maybe_log_metrics(&mut self, inst_idx: usize) {
+        let interval = self.cfg.metrics_log_interval_secs;
+        if interval == 0
+            || self
+                .last_metrics_log
+                .map(|t| self.clock.elapsed(t) < Duration::from_secs(interval))
+                .unwrap_or(false)
+        {
+            return;
+        }
+        if self.cfg.metrics_log_format == MetricsLogFormat::Json {
+            log::info!("[METRICS] {}", self.metrics_json(inst_idx));
+            self.last_metrics_log = Some(self.clock.now());
+            return;
+        }
+        let detail = self.cfg.metrics_log_detail;
+        let mut lines = Vec::new();
+        for (k, s) in &self.instances[inst_idx].states {
+            let z = s.z_score().map(|(z, _)| z).unwrap_or(0.0);
+            let mut line = format!(
+                "{} elig={} z={:.2} beta={:.2} hl={:.2}h p={:.3}",
+                k, s.eligible, z, s.beta, s.half_life_hours, s.adf_p_value
+            );
+            if detail {
+                line.push_str(&format!(
+                    " vel={:.3} entry_z={:.2} pos={} pending={}",
+                    s.last_velocity_sigma_per_min,
+                    s.z_entry,
+                    metrics_position_label(s),
+                    metrics_pending_label(s)
+                ));
+            }
+            lines.push(line);
+        }
+        lines.sort();
+        if !lines.is_empty() {
+            log::info!("[METRICS] {}", lines.join(" | "));
+        }
+        if detail {
+            let (open, pending_entry, pending_exit) = self.metrics_counts(inst_idx);
+            log::info!(
+                "[METRICS] positions open={} pending_entry={} pending_exit={}",
+                open,
+                pending_entry,
+                pending_exit
+            );
+        }
+        if self.cfg.spread_smoothing_bars > 1 {
+            log::info!(
+                "[METRICS] spread smoothing active (SMA {} bars)",
+                self.cfg.spread_smoothing_bars
+            );
+        }
+        log::info!(
+            "[METRICS] trades {}",
+            self.instances[inst_idx].trade_stats.summary_line()
+        );
+        if !self.taker_fallbacks.is_empty() {
+            let counts: Vec<String> = self
+                .taker_fallbacks
+                .iter()
+                .map(|(reason, n)| format!("{}={}", reason, n))
+                .collect();
+            log::info!("[METRICS] taker fallbacks {}", counts.join(" "));
+        }
+        if self.size_adjustments.values().any(|adj| adj.adjusted > 0) {
+            let counts: Vec<String> = self
+                .size_adjustments
+                .iter()
+                .filter(|(_, adj)| adj.adjusted > 0)
+                .map(|(symbol, adj)| {
+                    format!(
+                        "{}={}/{} diff={:.6} max={:.2}%",
+                        symbol, adj.adjusted, adj.orders, adj.abs_diff, adj.max_diff_pct
                     )
-                    .await?
-                {
-                    if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
-                        state.pending_entry = Some(new_pending);
+                })
+                .collect();
+            log::info!("[METRICS] size adjustments {}", counts.join(" "));
+        }
+        self.last_metrics_log = Some(self.clock.now());
+    }
+
+    /// Open positions, pending entries and pending exits of `inst_idx`.
+    fn metrics_counts(&self, inst_idx: usize) -> (usize, usize, usize) {
+        let states = self.instances[inst_idx].states.values();
+        states.fold((0, 0, 0), |(open, entry, exit), s| {
+            (
+                open + usize::from(s.position.is_some()),
+                entry + usize::from(s.pending_entry.is_some()),
+                exit + usize::from(s.pending_exit.is_some()),
+            )
+        })
+    }
+
+    /// `metrics_log_format: json`: everything the text `[METRICS]` lines
+    /// carry, as one object.
+    fn metrics_json(&self, inst_idx: usize) -> serde_json::Value {
+        let inst = &self.instances[inst_idx];
+        let detail = self.cfg.metrics_log_detail;
+        let mut pairs: Vec<(&String, &PairState)> = inst.states.iter().collect();
+        pairs.sort_by(|a, b| a.0.cmp(b.0));
+        let pairs: Vec<serde_json::Value> = pairs
+            .into_iter()
+            .map(|(key, s)| {
+                let mut pair = serde_json::json!({
+                    "pair": key,
+                    "eligible": s.eligible,
+                    "z": s.z_score().map(|(z, _)| z),
+                    "beta": s.beta,
+                    "half_life_hours": s.half_life_hours,
+                    "adf_p_value": s.adf_p_value,
+                });
+                if detail {
+                    pair["velocity_sigma_per_min"] = s.last_velocity_sigma_per_min.into();
+                    pair["entry_z"] = s.z_entry.into();
+                    pair["position"] = metrics_position_label(s).into();
+                    pair["pending"] = metrics_pending_label(s).into();
+                }
+                pair
+            })
+            .collect();
+        let mut metrics = serde_json::json!({
+            "instance": inst.id,
+            "pairs": pairs,
+            "trades": inst.trade_stats,
+            "taker_fallbacks": self.taker_fallbacks,
+            "size_adjustments": self.size_adjustments,
+        });
+        if self.cfg.spread_smoothing_bars > 1 {
+            metrics["spread_smoothing_bars"] = self.cfg.spread_smoothing_bars.into();
+        }
+        if detail {
+            let (open, pending_entry, pending_exit) = self.metrics_counts(inst_idx);
+            metrics["positions"] = serde_json::json!({
+                "open": open,
+                "pending_entry": pending_entry,
+                "pending_exit": pending_exit,
+            });
+        }
+        metrics
+    }
+
+    /// Net signed USD notional per symbol across `inst_idx`'s positions and
+    /// pending entries, for `shared_symbol_policy: net_exposure`. The second
+    /// set holds symbols whose exposure is unknown (guarded pairs, positions
+    /// without sizes, exchange positions no pair accounts for); those still
+    /// block like `block`.
+    fn symbol_exposure_usd(
+        &self,
+        inst_idx: usize,
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) -> (HashMap<String, f64>, HashSet<String>) {
+        let mut exposure: HashMap<String, f64> = HashMap::new();
+        let mut unknown = HashSet::new();
+        let mut add = |symbol: &str, side: dex_connector::OrderSide, qty: Decimal| {
+            let price = prices.get(symbol).and_then(|s| s.price.to_f64()).unwrap_or(0.0);
+            let sign = match side {
+                dex_connector::OrderSide::Long => 1.0,
+                dex_connector::OrderSide::Short => -1.0,
+            };
+            *exposure.entry(symbol.to_string()).or_default() +=
+                sign * qty.to_f64().unwrap_or(0.0) * price;
+        };
+        let mut tracked = HashSet::new();
+        for (key, state) in &self.instances[inst_idx].states {
+            let Some((base, quote)) = key.split_once('/') else {
+                continue;
+            };
+            if let Some(pos) = &state.position {
+                tracked.extend([base.to_string(), quote.to_string()]);
+                let (side_a, side_b) = pos.direction.entry_sides(pos.hedge_inverted);
+                match (pos.entry_size_a, pos.entry_size_b) {
+                    (Some(size_a), Some(size_b)) if !state.position_guard => {
+                        add(base, side_a, size_a);
+                        add(quote, side_b, size_b);
                     }
-                } else if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
-                    state.pending_entry = None;
+                    _ => unknown.extend([base.to_string(), quote.to_string()]),
                 }
-                return Ok(());
-            } else if pending.post_only_hybrid {
-                let recon_pp = self.pair_params_for(inst_idx, key).clone();
-                let recon_pp = &recon_pp;
-                if recon_pp.entry_post_only_timeout_secs > 0
-                    && pending.placed_at.elapsed()
-                        >= Duration::from_secs(recon_pp.entry_post_only_timeout_secs)
-                {
-                    // Phase 0 instrumentation (bot-strategy#165): capture per-leg
-                    // fill status, posted limit vs current book, and z-movement
-                    // from entry to timeout so we can tell why the post-only
-                    // legs didn't fill before falling back to taker.
-                    let (z_entry, z_now) = {
-                        let state_ref = self.instances[inst_idx].states.get(key);
-                        let ze = state_ref.map(|s| s.z_entry).unwrap_or(0.0);
-                        let zn = state_ref
-                            .and_then(|s| s.z_score().map(|(z, _)| z))
-                            .unwrap_or(0.0);
-                        (ze, zn)
-                    };
-                    let leg_details: Vec<String> = pending
-                        .legs
-                        .iter()
-                        .map(|leg| {
-                            let filled = status
-                                .fills
-                                .get(&leg.order_id)
-                                .cloned()
-                                .unwrap_or(Decimal::ZERO);
-                            let open = status.open_ids.contains(&leg.order_id);
-                            let snap = price_map.get(&leg.symbol);
-                            let bid = snap.and_then(|s| s.bid_price);
-                            let ask = snap.and_then(|s| s.ask_price);
-                            let tick = snap.and_then(|s| s.min_tick);
-                            format!(
-                                "[{}|{:?}|tgt={}|filled={}|open={}|limit={}|bid={}|ask={}|tick={}]",
-                                leg.symbol,
-                                leg.side,
-                                leg.target,
-                                filled,
-                                open,
-                                leg.limit_price
-                                    .map(|d| d.to_string())
-                                    .unwrap_or_else(|| "none".into()),
-                                bid.map(|d| d.to_string())
-                                    .unwrap_or_else(|| "?".into()),
-                                ask.map(|d| d.to_string())
-                                    .unwrap_or_else(|| "?".into()),
-                                tick.map(|d| d.to_string())
-                                    .unwrap_or_else(|| "?".into()),
-                            )
-                        })
-                        .collect();
-                    log::info!(
-                        "[ORDER_FALLBACK_DETAIL] {} elapsed={}s dir={:?} z_entry={:.2} z_now={:.2} legs={}",
-                        key,
-                        pending.placed_at.elapsed().as_secs(),
-                        pending.direction,
-                        z_entry,
-                        z_now,
-                        leg_details.join(" ")
-                    );
+            } else if let Some(pending) = &state.pending_entry {
+                tracked.extend([base.to_string(), quote.to_string()]);
+                for leg in &pending.legs {
+                    add(&leg.symbol, leg.side, leg.target);
+                }
+            } else if state.position_guard {
+                unknown.extend([base.to_string(), quote.to_string()]);
+            }
+        }
+        unknown.extend(
+            self.open_positions
+                .keys()
+                .filter(|symbol| !tracked.contains(*symbol))
+                .cloned(),
+        );
+        (exposure, unknown)
+    }
 
-                    // Post-only entry timed out; cancel and reissue as taker
-                    log::info!(
-                        "[ORDER] {} post-only entry timeout ({}s), falling back to taker",
-                        key,
-                        recon_pp.entry_post_only_timeout_secs
-                    );
-                    self.cancel_pending_orders(&pending).await?;
-                    let new_pending = self
-                        .reissue_entry_as_taker(key, &pending, price_map)
-                        .await?;
-                    if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
-                        state.pending_entry = new_pending;
+    /// Signed USD notional of an entry candidate's two legs at its planned
+    /// sizes. `None` for non-entries or when sizing fails.
+    fn candidate_leg_exposure<'a>(
+        &self,
+        inst_idx: usize,
+        plan: &'a PlannedAction,
+    ) -> Option<[(&'a str, f64); 2]> {
+        let TradeAction::Open { direction, beta, .. } = plan.action else {
+            return None;
+        };
+        let (qty_a, qty_b) = self
+            .hedged_sizes(inst_idx, &plan.pair, direction, beta, &plan.p1, &plan.p2)
+            .ok()?;
+        let signed = |side: dex_connector::OrderSide, qty: Decimal, price: Decimal| {
+            let usd = (qty * price).to_f64().unwrap_or(0.0);
+            match side {
+                dex_connector::OrderSide::Long => usd,
+                dex_connector::OrderSide::Short => -usd,
+            }
+        };
+        let (side_a, side_b) = direction.entry_sides(beta < 0.0);
+        Some([
+            (plan.pair.base.as_str(), signed(side_a, qty_a, plan.p1.price)),
+            (plan.pair.quote.as_str(), signed(side_b, qty_b, plan.p2.price)),
+        ])
+    }
+
+    /// `portfolio_funding_aware`: change in the book's hourly funding carry
+    /// (USD) if each shortlisted candidate were entered, keyed by pair. The
+    /// book is the exchange positions, or the paper book under paper fills.
+    /// Candidates that cannot be sized rank last.
+    fn portfolio_funding_scores(
+        &self,
+        inst_idx: usize,
+        shortlisted: &[&PlannedAction],
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) -> HashMap<String, f64> {
+        let mut book: HashMap<String, f64> = HashMap::new();
+        for pos in &self.position_book() {
+            let price = prices.get(&pos.symbol).and_then(|s| s.price.to_f64()).unwrap_or(0.0);
+            *book.entry(pos.symbol.clone()).or_default() +=
+                f64::from(pos.sign.signum()) * pos.size.to_f64().unwrap_or(0.0) * price;
+        }
+        let before = book_funding_per_hour(&book, prices);
+        shortlisted
+            .iter()
+            .map(|plan| {
+                let delta = match self.candidate_leg_exposure(inst_idx, plan) {
+                    Some(legs) => {
+                        let mut after = book.clone();
+                        for (symbol, usd) in legs {
+                            *after.entry(symbol.to_string()).or_default() += usd;
+                        }
+                        book_funding_per_hour(&after, prices) - before
+                    }
+                    None => f64::NEG_INFINITY,
+                };
+                log::debug!(
+                    "[SELECT] {} portfolio funding delta={:.6}/h (book {:.6}/h)",
+                    plan.key,
+                    delta,
+                    before
+                );
+                (plan.key.clone(), delta)
+            })
+            .collect()
+    }
+
+    /// `shared_symbol_policy: net_exposure` verdict for an entry candidate
+    /// that shares a symbol with an active pair: allowed unless a shared
+    /// symbol's exposure is unknown or its net would exceed
+    /// `max_net_symbol_exposure_usd`.
+    fn shared_symbol_entry_allowed(
+        &self,
+        inst_idx: usize,
+        plan: &PlannedAction,
+        exposure: &HashMap<String, f64>,
+        unknown: &HashSet<String>,
+    ) -> bool {
+        let (base, quote) = (&plan.pair.base, &plan.pair.quote);
+        if unknown.contains(base) || unknown.contains(quote) {
+            log::debug!("[OVERLAP] skipping {}/{}: shared symbol exposure unknown", base, quote);
+            return false;
+        }
+        let Some(legs) = self.candidate_leg_exposure(inst_idx, plan) else {
+            return false;
+        };
+        let cap = self.cfg.max_net_symbol_exposure_usd;
+        match sizing::net_exposure_breach(exposure, &legs, cap) {
+            Some((symbol, net)) => {
+                log::debug!(
+                    "[OVERLAP] skipping {}/{}: net {} exposure {:.2} > {:.2}",
+                    base,
+                    quote,
+                    symbol,
+                    net,
+                    cap
+                );
+                false
+            }
+            None => {
+                log::debug!(
+                    "[OVERLAP] allowing {}/{}: shared symbol net exposure within {:.2}",
+                    base,
+                    quote,
+                    cap
+                );
+                true
+            }
+        }
+    }
+
+    /// Tally a placed order for `size_adjustments`. Connectors that do not
+    /// report the ordered size (0) are not counted.
+    fn note_ordered_size(&mut self, symbol: &str, requested: Decimal, ordered: Decimal) {
+        if ordered <= Decimal::ZERO {
+            return;
+        }
+        self.size_adjustments
+            .entry(symbol.to_string())
+            .or_default()
+            .record(requested.to_f64().unwrap_or(0.0), ordered.to_f64().unwrap_or(0.0));
+    }
+
+    /// `pair_idle_alert_secs` watchdog: warn once per idle stretch about a
+    /// pair with no entry, with its latest eligibility inputs so a data or
+    /// config problem can be told apart from a quiet market. A pair holding
+    /// a position or with an order in flight is not idle; its stretch
+    /// restarts once it is flat again.
+    fn check_idle_pairs(&mut self, inst_idx: usize, now_ts: i64) {
+        let idle_secs = self.cfg.pair_idle_alert_secs;
+        if idle_secs == 0 {
+            return;
+        }
+        let mut alerts = Vec::new();
+        for (key, state) in self.instances[inst_idx].states.iter_mut() {
+            if state.position.is_some()
+                || state.pending_entry.is_some()
+                || state.pending_exit.is_some()
+            {
+                state.last_entry_ts = Some(now_ts);
+                state.idle_alerted = false;
+                continue;
+            }
+            let since = *state.last_entry_ts.get_or_insert(now_ts);
+            let idle_for = now_ts.saturating_sub(since);
+            if state.idle_alerted || idle_for < idle_secs as i64 {
+                continue;
+            }
+            state.idle_alerted = true;
+            alerts.push(format!(
+                "{} no entry for {}s: elig={} score={:.3} hl={:.2}h p={:.3} beta_gap={:.3} history={}",
+                key,
+                idle_for,
+                state.eligible,
+                state.p_value_weighted_score,
+                state.half_life_hours,
+                state.adf_p_value,
+                state.beta_gap,
+                state.spread_history.len()
+            ));
+        }
+        alerts.sort();
+        for line in &alerts {
+            log::warn!("[IDLE] {}", line);
+        }
+        if self.cfg.pair_idle_notify && !alerts.is_empty() {
+            let subject = match self.cfg.agent_name.as_deref() {
+                Some(name) => format!("[{}] Idle pairs", name),
+                None => format!("[IDLE] Pairs without entries (dex={})", self.cfg.dex_name),
+            };
+            notifier::notify(Severity::Warning, &subject, &alerts.join("\n"));
+        }
+    }
+
+    fn state_score(&self, inst_idx: usize, key: &str) -> f64 {
+        self.instances[inst_idx].states
+            .get(key)
+            .map(|s| s.p_value_weighted_score)
+            .unwrap_or(0.0)
+    }
+
+    fn should_log_ob_warn(&self, symbol: &str) -> bool {
+        const WARN_INTERVAL: u64 = 300;
+        self.last_ob_warn
+            .get(symbol)
+            .map(|t| self.clock.elapsed(*t) >= Duration::from_secs(WARN_INTERVAL))
+            .unwrap_or(true)
+    }
+
+    fn should_log_ticker_warn(&self, symbol: &str) -> bool {
+        const WARN_INTERVAL: u64 = 300;
+        self.last_ticker_warn
+            .get(symbol)
+            .map(|t| self.clock.elapsed(*t) >= Duration::from_secs(WARN_INTERVAL))
+            .unwrap_or(true)
+    }
+
+    fn should_log_position_warn(&self, key: &str) -> bool {
+        const WARN_INTERVAL: u64 = 300;
+        self.last_position_warn
+            .get(key)
+            .map(|t| self.clock.elapsed(*t) >= Duration::from_secs(WARN_INTERVAL))
+            .unwrap_or(true)
+    }
+
+    /// Whether this occurrence of a sampled log line should be emitted:
+    /// the first and then every `every_n`-th per (category, key). With
+    /// `every_n <= 1` every line is emitted and nothing is tracked.
+    fn log_sampled(&mut self, category: &'static str, key: &str, every_n: u64) -> bool {
+        if every_n <= 1 {
+            return true;
+        }
+        let count = self
+            .log_sample_counts
+            .entry((category, key.to_string()))
+            .or_insert(0);
+        let emit = count.is_multiple_of(every_n);
+        *count += 1;
+        emit
+    }
+
+    fn is_dust_position(
+        &self,
+        snapshot: &PositionSnapshot,
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) -> bool {
+        let Some(symbol_snapshot) = prices.get(&snapshot.symbol) else {
+            return false;
+        };
+        if symbol_snapshot.min_order.is_some_and(|min_order| snapshot.size < min_order) {
+            return true;
+        }
+        let floor = self.cfg.dust_notional_for(&snapshot.symbol);
+        if floor <= 0.0 {
+            return false;
+        }
+        (snapshot.size * symbol_snapshot.price)
+            .to_f64()
+            .is_some_and(|notional| notional < floor)
+    }
+
+    fn is_ticker_auth_error(msg: &str) -> bool {
+        let lower = msg.to_ascii_lowercase();
+        lower.contains("403")
+            || lower.contains("forbidden")
+            || lower.contains("failed to deserialize response")
+            || lower.contains("expected value at line 1 column 1")
+    }
+
+    fn is_reduce_only_position_missing_error(err: &DexError) -> bool {
+        let msg = match err {
+            DexError::ServerResponse(message) | DexError::Other(message) => message,
+            _ => return false,
+        };
+        let lower = msg.to_ascii_lowercase();
+        lower.contains("position is missing for reduce-only order")
+            || lower.contains("position is missing for reduce only order")
+    }
+
+    async fn confirm_reduce_only_position_missing(&mut self, symbol: &str) -> bool {
+        let cached_has_position = self
+            .open_positions
+            .get(symbol)
+            .map(|p| p.sign != 0 && p.size > Decimal::ZERO)
+            .unwrap_or(false);
+        if !cached_has_position && self.positions_ready {
+            return true;
+        }
+
+        match self.connector.get_positions().await {
+            Ok(positions) => {
+                let has_position = positions
+                    .iter()
+                    .any(|p| p.symbol == symbol && p.sign != 0 && p.size > Decimal::ZERO);
+                if !has_position {
+                    self.open_positions.remove(symbol);
+                    return true;
+                }
+            }
+            Err(err) => {
+                log::warn!(
+                    "[ORDER] reduce-only missing check failed for {}: {:?}",
+                    symbol,
+                    err
+                );
+            }
+        }
+        false
+    }
+
+    fn persist_history_to_disk(&mut self) {
+        // Persist the engine's shared log-price history plus the first
+        // instance's per-pair `spread_history`. We pick instance 0 as
+        // the representative: A/B/C instances drift ≤0.3% per the
+        // existing TODO near evaluate_pair, which on reload converges
+        // back to whatever was persisted. Persisting per-instance would
+        // require an instance ID in the schema — over-engineered for
+        // the single-bot-per-process setup this field currently supports.
+        let spread_histories: HashMap<String, VecDeque<f64>> = self
+            .instances
+            .first()
+            .map(|inst| {
+                inst.states
+                    .iter()
+                    .map(|(k, s)| (k.clone(), s.spread_history.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.history_store
+            .persist(&self.cfg, &self.history, &spread_histories);
+    }
+
+    fn load_history_from_disk(&mut self) {
+        let now = self.current_now_ts();
+        let max_len = self.max_history_len();
+        let mut loaded_spreads: HashMap<String, VecDeque<f64>> = HashMap::new();
+        self.history_store.load(
+            &self.cfg,
+            &mut self.history,
+            &mut loaded_spreads,
+            now,
+            max_len,
+        );
+        if loaded_spreads.is_empty() {
+            return;
+        }
+        // Apply the persisted spread_history only when the instance's own
+        // spread_history is still empty — i.e. on the initial post-restart
+        // load, before any ticks have pushed a live spread. Subsequent
+        // per-tick loads must NOT clobber the instance's accumulating
+        // series, otherwise every step would silently revert the
+        // previous step's push (in single-bot mode) or import another
+        // bot's beta trajectory (in multi-bot mode, which is not the
+        // intended sharing axis — peer bots coordinate on log_prices,
+        // not on state.beta-dependent derived series).
+        for inst in &mut self.instances {
+            for (pair_key, spreads) in &loaded_spreads {
+                if let Some(state) = inst.states.get_mut(pair_key) {
+                    if state.spread_history.is_empty() {
+                        state.restore_spread_history(spreads.clone());
                     }
                 }
-            } else if pending.placed_at.elapsed() >= timeout {
-                // Partial fill or stuck orders; cancel and flatten any filled leg
-                if status.open_remaining > 0 {
-                    log::warn!(
-                        "[ORDER] {} entry orders stale ({}s), cancelling {} legs",
-                        key,
-                        pending.placed_at.elapsed().as_secs(),
-                        status.open_remaining
-                    );
-                    for leg in &pending.legs {
-                        let filled = filled_qtys
-                            .get(&leg.order_id)
-                            .cloned()
-                            .unwrap_or(Decimal::ZERO);
-                        let is_open = status.open_ids.contains(&leg.order_id);
+            }
+        }
+    }
+
+    /// Restore circuit-breaker state from disk so a crash or `systemctl
+    /// restart` during an active cool-down does not silently clear the
+    /// consecutive-loss counter. See bot-strategy#185 Phase 1-3, extended
+    /// in Phase 2 to restore `session_start_*` / `realized_pnl_today`.
+    fn load_risk_state(&mut self) {
+        if self.cfg.backtest_mode {
+            return;
+        }
+        let loaded = risk_io::load_risk_state(&self.risk_state_path);
+        if loaded.is_empty() {
+            return;
+        }
+        let now_ts = self.current_now_ts();
+        for inst in &mut self.instances {
+            let Some(state) = loaded.get(&inst.id) else { continue };
+            inst.consecutive_losses = state.consecutive_losses;
+            inst.session_start_equity = state.session_start_equity;
+            inst.session_start_ts = state.session_start_ts;
+            inst.realized_pnl_today = state.realized_pnl_today;
+            inst.equity_samples = state.equity_samples.clone();
+            inst.session_halted = state.session_halted;
+            inst.session_halt_reason = state.session_halt_reason.clone();
+            inst.session_halt_ts = state.session_halt_ts;
+            inst.persisted_entry_ts = state.entry_ts_by_pair.clone();
+            if inst.session_halted {
+                log::warn!(
+                    "[SESSION_DD] {} restored halt: reason={} since_ts={} (waiting for {} ack)",
+                    inst.id,
+                    inst.session_halt_reason.as_deref().unwrap_or("unknown"),
+                    inst.session_halt_ts.unwrap_or(0),
+                    RISK_ACK_PATH
+                );
+            }
+            match state.circuit_breaker_until_ts {
+                Some(until_ts) if until_ts > now_ts => {
+                    inst.circuit_breaker_until_ts = Some(until_ts);
+                    let remaining_secs = (until_ts - now_ts).max(0) as u64;
+                    inst.circuit_breaker_until =
+                        Some(self.clock.now() + Duration::from_secs(remaining_secs));
+                    log::warn!(
+                        "[RISK_STATE] {} restored: consecutive_losses={}, cool-down {}s remaining, realized_pnl_today={:.4}",
+                        inst.id, inst.consecutive_losses, remaining_secs, inst.realized_pnl_today
+                    );
+                }
+                Some(_) => {
+                    if inst.consecutive_losses > 0 || inst.realized_pnl_today != 0.0 {
+                        log::info!(
+                            "[RISK_STATE] {} restored: consecutive_losses={}, prior cool-down expired, realized_pnl_today={:.4}",
+                            inst.id, inst.consecutive_losses, inst.realized_pnl_today
+                        );
+                    }
+                }
+                None => {
+                    if inst.consecutive_losses > 0 || inst.realized_pnl_today != 0.0 {
+                        log::info!(
+                            "[RISK_STATE] {} restored: consecutive_losses={}, realized_pnl_today={:.4}",
+                            inst.id, inst.consecutive_losses, inst.realized_pnl_today
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn persist_risk_state(&self) {
+        if self.cfg.backtest_mode {
+            return;
+        }
+        let instances: HashMap<String, risk_io::InstanceRiskState> = self
+            .instances
+            .iter()
+            .map(|inst| {
+                (
+                    inst.id.clone(),
+                    risk_io::InstanceRiskState {
+                        consecutive_losses: inst.consecutive_losses,
+                        circuit_breaker_until_ts: inst.circuit_breaker_until_ts,
+                        session_start_equity: inst.session_start_equity,
+                        session_start_ts: inst.session_start_ts,
+                        realized_pnl_today: inst.realized_pnl_today,
+                        equity_samples: inst.equity_samples.clone(),
+                        session_halted: inst.session_halted,
+                        session_halt_reason: inst.session_halt_reason.clone(),
+                        session_halt_ts: inst.session_halt_ts,
+                        entry_ts_by_pair: inst
+                            .states
+                            .iter()
+                            .filter_map(|(key, state)| {
+                                let entered_ts = match (&state.position, &state.pending_entry) {
+                                    (Some(pos), _) => Some(pos.entered_ts),
+                                    (None, Some(_)) => state.last_entry_ts,
+                                    (None, None) => None,
+                                };
+                                entered_ts.map(|ts| (key.clone(), ts))
+                            })
+                            .collect(),
+                    },
+                )
+            })
+            .collect();
+        risk_io::persist_risk_state(&self.risk_state_path, &instances);
+    }
+
+    /// Cross the UTC session boundary when `now_ts` lands in a different
+    /// day bucket from the persisted `session_start_ts`. Called once per
+    /// `step_shared` tick. On rollover, realized_pnl_today is zeroed and
+    /// `session_start_equity` is reset to the fixed `equity_reference_usd`
+    /// so the daily-DD denominator stays constant within (and across)
+    /// sessions, regardless of intra-session live equity drift. Also
+    /// initialises state on the very first tick (when
+    /// `session_start_ts == 0`). See bot-strategy#185 Phase 2 and
+    /// bot-strategy#222 (semantic switch from live floor to fixed
+    /// reference).
+    fn refresh_daily_session(&mut self) {
+        if self.cfg.backtest_mode {
+            return;
+        }
+        let reset_hour = self.cfg.risk.daily_reset_utc_hour;
+        let threshold_bps = self.cfg.risk.max_daily_loss_bps;
+        let now_ts = self.current_now_ts();
+        let current_day = session_day(now_ts, reset_hour);
+        let mut dirty = false;
+        for inst in &mut self.instances {
+            let prior_day = if inst.session_start_ts > 0 {
+                Some(session_day(inst.session_start_ts, reset_hour))
+            } else {
+                None
+            };
+            let needs_rollover = match prior_day {
+                None => true,
+                Some(prev) => prev != current_day,
+            };
+            if needs_rollover {
+                let equity_base = inst.equity_reference_usd;
+                let prev_pnl = inst.realized_pnl_today;
+                inst.session_start_ts = now_ts;
+                inst.session_start_equity = equity_base;
+                inst.realized_pnl_today = 0.0;
+                if inst.daily_loss_halted {
+                    log::warn!(
+                        "[DAILY_DD] {} halt cleared by session rollover", inst.id
+                    );
+                }
+                inst.daily_loss_halted = false;
+                dirty = true;
+                if prior_day.is_some() {
+                    log::info!(
+                        "[DAILY_DD] {} session rolled: prev_pnl={:.4} -> reset; new session_start_equity={:.2}",
+                        inst.id, prev_pnl, equity_base
+                    );
+                } else {
+                    log::info!(
+                        "[DAILY_DD] {} session initialised: session_start_equity={:.2}",
+                        inst.id, equity_base
+                    );
+                }
+            }
+
+            // Transition logging for the halt gate. Recomputed each tick so
+            // we catch both the activation (when realized_pnl_today crosses
+            // the threshold after a losing close) and any clear (should be
+            // rare: Phase 2 doesn't expose a manual recovery path, so this
+            // branch fires only after a rollover reset above or a manual
+            // `risk_state.json` edit).
+            let currently_blocks = threshold_bps > 0
+                && inst.session_start_equity > 0.0
+                && inst.realized_pnl_today < 0.0
+                && {
+                    let loss_bps =
+                        (-inst.realized_pnl_today / inst.session_start_equity) * 10_000.0;
+                    loss_bps >= threshold_bps as f64
+                };
+            if currently_blocks && !inst.daily_loss_halted {
+                let loss_bps =
+                    (-inst.realized_pnl_today / inst.session_start_equity) * 10_000.0;
+                log::warn!(
+                    "[DAILY_DD] {} halted: realized_pnl_today={:.4} loss_bps={:.1} threshold={}bps (new entries blocked until UTC {:02}:00)",
+                    inst.id, inst.realized_pnl_today, loss_bps, threshold_bps, reset_hour
+                );
+                inst.daily_loss_halted = true;
+                dirty = true;
+            } else if !currently_blocks && inst.daily_loss_halted {
+                // Usually unreachable in Phase 2; kept so manual edits
+                // to risk_state.json don't leave a stale halt flag.
+                log::warn!("[DAILY_DD] {} halt cleared", inst.id);
+                inst.daily_loss_halted = false;
+                dirty = true;
+            }
+        }
+        if dirty {
+            self.persist_risk_state();
+        }
+    }
+
+    /// Whether `realized_pnl_today` has breached `max_daily_loss_bps`
+    /// against `session_start_equity`. Returns false when the threshold
+    /// is disabled (0 bps), the equity baseline is still zero (pre-first
+    /// rollover), or the running PnL is non-negative.
+    fn daily_loss_blocks(&self, inst: &StrategyInstance) -> bool {
+        let threshold_bps = self.cfg.risk.max_daily_loss_bps;
+        if threshold_bps == 0 || inst.session_start_equity <= 0.0 {
+            return false;
+        }
+        if inst.realized_pnl_today >= 0.0 {
+            return false;
+        }
+        let loss_bps = (-inst.realized_pnl_today / inst.session_start_equity) * 10_000.0;
+        loss_bps >= threshold_bps as f64
+    }
+
+    /// Refresh `kill_switch_active` from the sentinel file. Called at the
+    /// top of every `step_shared` tick. Logs on state transitions so the
+    /// journal shows exactly when entries were blocked / resumed without
+    /// spamming the log on every check. See bot-strategy#185 Phase 1-2.
+    fn update_kill_switch_state(&mut self) {
+        if self.cfg.backtest_mode {
+            return;
+        }
+        let present = std::path::Path::new(KILL_SWITCH_PATH).exists();
+        if present && !self.kill_switch_active {
+            log::warn!(
+                "[KILL_SWITCH] activated: {} detected; new entries blocked, existing positions will exit normally",
+                KILL_SWITCH_PATH
+            );
+            self.kill_switch_active = true;
+        } else if !present && self.kill_switch_active {
+            log::warn!(
+                "[KILL_SWITCH] cleared: {} removed; new entries resumed",
+                KILL_SWITCH_PATH
+            );
+            self.kill_switch_active = false;
+        }
+    }
+
+    /// Consume `/opt/debot/RISK_ACK` if present and clear `session_halted`
+    /// across all instances. The file is unconditionally removed so a
+    /// stale ack from a prior incident never silently re-arms. See
+    /// bot-strategy#185 Phase 3-2.
+    fn consume_risk_ack(&mut self) {
+        if self.cfg.backtest_mode {
+            return;
+        }
+        let path = std::path::Path::new(RISK_ACK_PATH);
+        if !path.exists() {
+            return;
+        }
+        // Read the file (best-effort) so the ack reason makes it into the
+        // journal — useful when chasing why a halt cleared days later.
+        let payload = std::fs::read_to_string(path).unwrap_or_default();
+        let trimmed = payload.trim();
+        let mut cleared_any = false;
+        for inst in &mut self.instances {
+            if inst.session_halted {
+                log::warn!(
+                    "[SESSION_DD] {} halt cleared by ack at {} (reason was: {}, ack payload: {:?})",
+                    inst.id,
+                    RISK_ACK_PATH,
+                    inst.session_halt_reason.as_deref().unwrap_or("unknown"),
+                    trimmed
+                );
+                inst.session_halted = false;
+                inst.session_halt_reason = None;
+                inst.session_halt_ts = None;
+                cleared_any = true;
+            }
+        }
+        if let Err(e) = std::fs::remove_file(path) {
+            log::warn!("[SESSION_DD] failed to remove {} after ack: {:?}", RISK_ACK_PATH, e);
+        } else {
+            log::info!("[SESSION_DD] {} consumed", RISK_ACK_PATH);
+        }
+        if cleared_any {
+            self.persist_risk_state();
+        }
+    }
+
+    /// Append the current equity to `equity_samples`, prune entries
+    /// outside the rolling window, and update the cached peak. Called
+    /// after a successful equity refresh. No-op when the threshold is
+    /// disabled (0 bps) so disabled instances don't grow disk state.
+    fn update_equity_sample(&mut self, inst_idx: usize) {
+        if self.cfg.backtest_mode {
+            return;
+        }
+        let threshold_bps = self.cfg.risk.max_session_loss_bps;
+        if threshold_bps == 0 {
+            return;
+        }
+        let lookback = self.cfg.risk.session_dd_lookback_secs as i64;
+        let sample_secs = self.cfg.risk.session_dd_sample_secs as i64;
+        let now_ts = self.current_now_ts();
+        let cutoff = now_ts.saturating_sub(lookback);
+        let inst = &mut self.instances[inst_idx];
+        let equity = inst.equity_cache;
+        if equity <= 0.0 {
+            return;
+        }
+        // Keep at most one sample per `sample_secs` window so 30 d at 1 h
+        // cadence ≤ 720 entries (≈ 17 KB JSON). Newer sample replaces an
+        // entry within the same bucket so peak stays current without
+        // unbounded growth.
+        let bucket_start = (now_ts / sample_secs) * sample_secs;
+        let mut replaced = false;
+        if let Some(last) = inst.equity_samples.last_mut() {
+            if last.ts >= bucket_start {
+                last.equity = equity;
+                last.ts = now_ts;
+                replaced = true;
+            }
+        }
+        if !replaced {
+            inst.equity_samples.push(risk_io::EquitySample {
+                ts: now_ts,
+                equity,
+            });
+        }
+        let pre_len = inst.equity_samples.len();
+        inst.equity_samples.retain(|s| s.ts >= cutoff);
+        let pruned = pre_len.saturating_sub(inst.equity_samples.len());
+        if pruned > 0 {
+            log::debug!(
+                "[SESSION_DD] {} pruned {} expired equity samples (window {}s)",
+                inst.id, pruned, lookback
+            );
+        }
+        // Persistence is cheap (atomic rename of a small JSON file) but
+        // we don't write on every tick — only when something changed
+        // materially. Sampling refreshes cadence; `step_shared` calls
+        // `evaluate_session_dd` immediately after, which triggers
+        // persistence on halt transitions.
+    }
+
+    /// Compute the rolling peak from `equity_samples` and return the
+    /// peak / current ratio. Returns `None` when there are no samples
+    /// or the peak is non-positive (cannot define a percentage DD).
+    fn rolling_peak(samples: &[risk_io::EquitySample], current: f64) -> Option<(f64, f64)> {
+        let mut peak = current;
+        for s in samples {
+            if s.equity > peak {
+                peak = s.equity;
+            }
+        }
+        if peak <= 0.0 {
+            return None;
+        }
+        let dd_bps = if current >= peak {
+            0.0
+        } else {
+            ((peak - current) / peak) * 10_000.0
+        };
+        Some((peak, dd_bps))
+    }
+
+    /// Check whether the rolling peak DD has breached the threshold and
+    /// engage the session halt + auto-flatten if so. Idempotent: a
+    /// repeated breach while already halted re-runs `close_all_positions`
+    /// at most once (the second call succeeds quickly if the account is
+    /// already flat). Returns true if the halt is currently active for
+    /// the instance after the check.
+    async fn evaluate_session_dd(&mut self, inst_idx: usize) -> bool {
+        if self.cfg.backtest_mode {
+            return false;
+        }
+        let threshold_bps = self.cfg.risk.max_session_loss_bps;
+        if threshold_bps == 0 {
+            return self.instances[inst_idx].session_halted;
+        }
+        let inst = &self.instances[inst_idx];
+        if inst.session_halted {
+            // Halt is sticky — once tripped, stay halted until ack'd.
+            // No need to re-flatten on every tick; one shot is enough,
+            // and `close_all_positions` was already invoked on trip.
+            return true;
+        }
+        let current = inst.equity_cache;
+        if current <= 0.0 {
+            return false;
+        }
+        let Some((peak, dd_bps)) = Self::rolling_peak(&inst.equity_samples, current) else {
+            return false;
+        };
+        if dd_bps < threshold_bps as f64 {
+            return false;
+        }
+        let now_ts = self.current_now_ts();
+        let reason = format!("session_dd_{}bps", threshold_bps);
+        log::error!(
+            "[SESSION_DD] {} breach: equity={:.2} peak={:.2} dd_bps={:.1} threshold={}bps; flattening positions and halting (ack via {})",
+            inst.id, current, peak, dd_bps, threshold_bps, RISK_ACK_PATH
+        );
+        {
+            let inst_mut = &mut self.instances[inst_idx];
+            inst_mut.session_halted = true;
+            inst_mut.session_halt_reason = Some(reason);
+            inst_mut.session_halt_ts = Some(now_ts);
+        }
+        self.persist_risk_state();
+        // Flatten this instance's positions. `self.connector` was already
+        // pointed at `instances[inst_idx].connector` by the caller in
+        // `step()`, so close_all_positions hits the right sub-account.
+        if !self.cfg.dry_run && !self.cfg.observe_only {
+            if let Err(err) = self.connector.close_all_positions(None).await {
+                log::error!(
+                    "[SESSION_DD] {} close_all_positions failed: {:?}",
+                    self.instances[inst_idx].id,
+                    err
+                );
+            } else {
+                log::warn!(
+                    "[SESSION_DD] {} close_all_positions invoked",
+                    self.instances[inst_idx].id
+                );
+            }
+        }
+        true
+    }
+
+    /// Rebuild each pair's beta and spread_history from the shared on-disk
+    /// price history so A/B/C bots have identical regression windows the
+    /// instant they start, instead of waiting metrics_window live bars to
+    /// converge (pairtrade#4). Computes beta directly from whatever bars
+    /// are available — does not go through evaluate_pair() because that
+    /// path enforces full lookback_hours_long under Strict warm-start and
+    /// would skip the seed when the loaded history is shorter than the
+    /// configured long window.
+    fn warm_start_states_from_history(&mut self) {
+        if self.cfg.disable_history_persist {
+            return;
+        }
+        for inst_idx in 0..self.instances.len() {
+            for pair in self.cfg.universe.clone() {
+                let key = format!("{}/{}", pair.base, pair.quote);
+                let Some(seeded) = self.seed_spread_history_from_bars(inst_idx, &pair) else {
+                    continue;
+                };
+                let state = &self.instances[inst_idx].states[&key];
+                if seeded {
+                    log::info!(
+                        "[WARM_START] {} synthesized spread_history len={} beta={:.4} (no persisted v2 series)",
+                        key, state.spread_history.len(), state.beta
+                    );
+                } else {
+                    log::info!(
+                        "[WARM_START] {} kept persisted spread_history len={} beta={:.4} (no synthesis)",
+                        key, state.spread_history.len(), state.beta
+                    );
+                }
+            }
+        }
+    }
+
+    /// Re-derive a pair's beta from the per-symbol `history` (last
+    /// `metrics_window` bars) and, when its `spread_history` is still empty,
+    /// backfill it with spreads recomputed at that beta. Legs that are
+    /// already warm through other pairs then let the pair trade without
+    /// re-accumulating a window. Returns `Some(true)` when spreads were
+    /// synthesized, `Some(false)` when an existing series was kept, and
+    /// `None` without enough shared history or pair state.
+    fn seed_spread_history_from_bars(&mut self, inst_idx: usize, pair: &PairSpec) -> Option<bool> {
+        let key = format!("{}/{}", pair.base, pair.quote);
+        let hist_a = self.history.get(&pair.base)?;
+        let hist_b = self.history.get(&pair.quote)?;
+        let take = self.cfg.metrics_window.min(hist_a.len()).min(hist_b.len());
+        if take < 2 {
+            return None;
+        }
+        let mut tail_a = tail_samples(hist_a, take);
+        let mut tail_b = tail_samples(hist_b, take);
+        if self.cfg.normalize_legs {
+            tail_a = standardize(&tail_a);
+            tail_b = standardize(&tail_b);
+        }
+        let beta = hedge_beta(
+            &tail_b,
+            &tail_a,
+            self.cfg.beta_on_returns,
+            self.cfg.allow_negative_beta,
+        );
+        let state = self.instances[inst_idx].states.get_mut(&key)?;
+        state.beta = beta;
+        state.beta_short = beta;
+        state.beta_long = beta;
+        // If `load_history_from_disk` / `load_history_snapshot_for_bt`
+        // has already restored the real persisted `spread_history`
+        // (v2 snapshot), keep it as-is. Synthesizing a
+        // single-OLS-beta series here would overwrite a 240-bar
+        // real series with one whose variance is artificially
+        // compressed — the mechanism behind the 2026-04-15 06:02
+        // UTC "std collapse" restart incident (bot-strategy#62).
+        // Only synthesize when the instance has no live spreads
+        // (fresh start with no persisted snapshot, or a v1
+        // snapshot from a pre-fix bot).
+        if !state.spread_history.is_empty() {
+            return Some(false);
+        }
+        let spreads: Vec<f64> = tail_a
+            .iter()
+            .zip(tail_b.iter())
+            .map(|(sa, sb)| sa.log_price - beta * sb.log_price)
+            .collect();
+        state.seed_spread_history(&spreads, &self.cfg);
+        Some(true)
+    }
+
+    fn entry_vol_window(&self) -> usize {
+        ((self.cfg.default_pair_params.entry_vol_lookback_hours * 3600)
+            / self.cfg.trading_period_secs)
+            .max(1) as usize
+    }
+
+    /// Virtual clock used by all duration-based decisions. In live mode this
+    /// is the wall-clock UTC second; in backtest mode it tracks the replay
+    /// connector's logical timestamp so cooldown / force_close /
+    /// circuit_breaker / re-eval intervals fire correctly under replay.
+    fn current_now_ts(&self) -> i64 {
+        if self.cfg.backtest_mode {
+            self.replay_connector
+                .as_ref()
+                .and_then(|r| r.current_timestamp_secs())
+                .unwrap_or_else(|| chrono::Utc::now().timestamp())
+        } else {
+            chrono::Utc::now().timestamp()
+        }
+    }
+
+    fn max_history_len(&self) -> usize {
+        max_history_len_for(&self.cfg)
+    }
+
+    async fn reconcile_pending_orders(
+        &mut self,
+        inst_idx: usize,
+        key: &str,
+        price_map: &HashMap<String, SymbolSnapshot>,
+        prefetched: &mut HashMap<(String, bool), PendingStatus>,
+    ) -> Result<()> {
+        let timeout = Duration::from_secs(self.cfg.order_timeout_secs.max(1));
+        let now_ts = self.current_now_ts();
+        let (pending_entry, pending_exit) = {
+            let state = self
+                .instances[inst_idx]
+                .states
+                .get_mut(key)
+                .ok_or_else(|| anyhow!("missing state for {}", key))?;
+            let has_pending = state.pending_entry.is_some() || state.pending_exit.is_some();
+            if has_pending && state.pending_since.is_none() {
+                state.pending_since = Some(self.clock.now());
+            }
+            (state.pending_entry.take(), state.pending_exit.take())
+        };
+
+        let max_age = Duration::from_secs(self.cfg.pending_max_age_secs);
+        let pending_age = self.instances[inst_idx]
+            .states
+            .get(key)
+            .and_then(|s| s.pending_since)
+            .map(|since| self.clock.elapsed(since));
+        if self.cfg.pending_max_age_secs > 0 && pending_age.is_some_and(|age| age >= max_age) {
+            log::error!(
+                "[ORDER] force-flatten stale pending {} (age={}s >= {}s)",
+                key,
+                pending_age.unwrap_or_default().as_secs(),
+                self.cfg.pending_max_age_secs
+            );
+            for (pending, is_exit) in [(pending_entry, false), (pending_exit, true)] {
+                let Some(pending) = pending else { continue };
+                let fills = match self.pending_status(&pending).await {
+                    Ok(status) => status.fills,
+                    Err(err) => {
+                        log::warn!("[ORDER] {} stale pending status unavailable: {:?}", key, err);
+                        HashMap::new()
+                    }
+                };
+                if let Err(err) = self.cancel_pending_orders(&pending).await {
+                    log::warn!("[ORDER] {} stale pending cancel failed: {:?}", key, err);
+                }
+                let filled_qtys = self.filled_by_leg(&pending, &fills);
+                self.close_pending_exposure(key, &pending, &filled_qtys, is_exit, "pending max age")
+                    .await;
+            }
+            if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                state.pending_since = None;
+                state.position = None;
+                state.position_guard = false;
+                state.last_exit_at = Some(self.clock.now());
+                state.last_exit_ts = Some(now_ts);
+            }
+            return Ok(());
+        }
+
+        if let Some(mut pending) = pending_entry {
+            let status = match prefetched.remove(&(key.to_string(), true)) {
+                Some(status) => status,
+                None => self.pending_status(&pending).await?,
+            };
+            self.update_pending_fills(&mut pending, &status.fills);
+            let filled_qtys = self.filled_by_leg(&pending, &status.fills);
+            if pending.scale_in {
+                let all_filled = self.all_filled(&pending, &status.fills);
+                if all_filled {
+                    self.record_realized_slippage(&pending, &status);
+                    self.book_scale_in_fills(inst_idx, key, &pending, &filled_qtys, price_map);
+                    log::info!("[SCALE_IN] {} add orders filled", key);
+                } else if self.clock.elapsed(pending.placed_at) >= timeout || status.open_remaining == 0 {
+                    // The scout stands on its own: cancel what's left of the
+                    // add and keep whatever filled.
+                    log::warn!(
+                        "[SCALE_IN] {} add incomplete ({} open); keeping what filled",
+                        key,
+                        status.open_remaining
+                    );
+                    if status.open_remaining > 0 {
+                        self.cancel_pending_orders(&pending).await?;
+                    }
+                    self.book_scale_in_fills(inst_idx, key, &pending, &filled_qtys, price_map);
+                } else if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                    state.pending_entry = Some(pending);
+                }
+            } else if self.all_filled(&pending, &status.fills) {
+                self.record_realized_slippage(&pending, &status);
+                let entry_fill_cash = self.fill_cash_flow(&pending, &status);
+                if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                    let (mut ep_a, mut ep_b, mut es_a, mut es_b) = (None, None, None, None);
+                    let (mut side_a, mut side_b) = (None, None);
+                    if let Some((base, quote)) = key.split_once('/') {
+                        for leg in &pending.legs {
+                            if leg.symbol == base {
+                                ep_a = price_map.get(base).map(|s| s.price);
+                                es_a = Some(leg.target);
+                                side_a = Some(leg.side);
+                            } else if leg.symbol == quote {
+                                ep_b = price_map.get(quote).map(|s| s.price);
+                                es_b = Some(leg.target);
+                                side_b = Some(leg.side);
+                            }
+                        }
+                    }
+                    let z_at_entry = state.z_score().map(|(z, _)| z);
+                    state.position = Some(Position {
+                        direction: pending.direction,
+                        entered_at: self.clock.now(),
+                        entered_ts: now_ts,
+                        entry_price_a: ep_a,
+                        entry_price_b: ep_b,
+                        entry_size_a: es_a,
+                        entry_size_b: es_b,
+                        entry_z: z_at_entry,
+                        entry_beta: Some(state.beta),
+                        entry_fill_cash,
+                        partial_taken: false,
+                        scaled_in: false,
+                        scale_in_add: es_a.zip(es_b).and_then(|scout| scale_in_remainder(&self.cfg, scout)),
+                        hedge_inverted: side_a.is_some() && side_a == side_b,
+                    });
+                    state.pending_entry = None;
+                }
+                log::info!("[ORDER] {} entry orders filled", key);
+            } else if filled_qtys.values().any(|qty| *qty > Decimal::ZERO) {
+                let next_retry = pending.hedge_retry_count.saturating_add(1);
+                if next_retry > self.cfg.max_reissues_per_pair {
+                    log::error!(
+                        "[ORDER] {} entry exceeded {} partial-fill reissues; closing filled legs",
+                        key,
+                        self.cfg.max_reissues_per_pair
+                    );
+                    self.cancel_pending_orders(&pending).await?;
+                    self.close_pending_exposure(key, &pending, &filled_qtys, false, "reissue cap")
+                        .await;
+                    if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                        state.pending_entry = None;
+                        state.position = None;
+                        state.last_exit_at = Some(self.clock.now());
+                        state.last_exit_ts = Some(now_ts);
+                    }
+                    return Ok(());
+                }
+                let max_retries = self.cfg.entry_partial_fill_max_retries;
+                let use_market = max_retries > 0 && next_retry > max_retries;
+                if use_market {
+                    log::info!(
+                        "[ORDER] {} entry leg partially filled, retries exceeded ({} > {}); reissuing remaining legs as MARKET",
+                        key,
+                        next_retry,
+                        max_retries
+                    );
+                } else if max_retries > 0 {
+                    log::info!(
+                        "[ORDER] {} entry leg partially filled, reissuing remaining legs (retry {}/{})",
+                        key,
+                        next_retry,
+                        max_retries
+                    );
+                } else {
+                    log::warn!(
+                        "[ORDER] {} entry leg partially filled, reissuing remaining legs",
+                        key
+                    );
+                }
+                self.cancel_pending_orders(&pending).await?;
+                if let Some(new_pending) = self
+                    .reissue_partial_legs(
+                        &pending,
+                        &filled_qtys,
+                        price_map,
+                        false,
+                        use_market,
+                        next_retry,
+                    )
+                    .await?
+                {
+                    if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                        state.pending_entry = Some(new_pending);
+                    }
+                } else if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                    state.pending_entry = None;
+                }
+                return Ok(());
+            } else if pending.post_only_hybrid {
+                let recon_pp = self.pair_params_for(inst_idx, key).clone();
+                let recon_pp = &recon_pp;
+                if recon_pp.entry_post_only_timeout_secs > 0
+                    && self.clock.elapsed(pending.placed_at)
+                        >= Duration::from_secs(recon_pp.entry_post_only_timeout_secs)
+                {
+                    // Phase 0 instrumentation (bot-strategy#165): capture per-leg
+                    // fill status, posted limit vs current book, and z-movement
+                    // from entry to timeout so we can tell why the post-only
+                    // legs didn't fill before falling back to taker.
+                    let (z_entry, z_now) = {
+                        let state_ref = self.instances[inst_idx].states.get(key);
+                        let ze = state_ref.map(|s| s.z_entry).unwrap_or(0.0);
+                        let zn = state_ref
+                            .and_then(|s| s.z_score().map(|(z, _)| z))
+                            .unwrap_or(0.0);
+                        (ze, zn)
+                    };
+                    let leg_details: Vec<String> = pending
+                        .legs
+                        .iter()
+                        .map(|leg| {
+                            let filled = status
+                                .fills
+                                .get(&leg.order_id)
+                                .cloned()
+                                .unwrap_or(Decimal::ZERO);
+                            let open = status.open_ids.contains(&leg.order_id);
+                            let snap = price_map.get(&leg.symbol);
+                            let bid = snap.and_then(|s| s.bid_price);
+                            let ask = snap.and_then(|s| s.ask_price);
+                            let tick = snap.and_then(|s| s.min_tick);
+                            format!(
+                                "[{}|{:?}|tgt={}|filled={}|open={}|limit={}|bid={}|ask={}|tick={}]",
+                                leg.symbol,
+                                leg.side,
+                                leg.target,
+                                filled,
+                                open,
+                                leg.limit_price
+                                    .map(|d| d.to_string())
+                                    .unwrap_or_else(|| "none".into()),
+                                bid.map(|d| d.to_string())
+                                    .unwrap_or_else(|| "?".into()),
+                                ask.map(|d| d.to_string())
+                                    .unwrap_or_else(|| "?".into()),
+                                tick.map(|d| d.to_string())
+                                    .unwrap_or_else(|| "?".into()),
+                            )
+                        })
+                        .collect();
+                    log::info!(
+                        "[ORDER_FALLBACK_DETAIL] {} elapsed={}s dir={:?} z_entry={:.2} z_now={:.2} legs={}",
+                        key,
+                        self.clock.elapsed(pending.placed_at).as_secs(),
+                        pending.direction,
+                        z_entry,
+                        z_now,
+                        leg_details.join(" ")
+                    );
+
+                    // Post-only entry timed out; cancel and reissue as taker
+                    log::info!(
+                        "[ORDER] {} post-only entry timeout ({}s), falling back to taker",
+                        key,
+                        recon_pp.entry_post_only_timeout_secs
+                    );
+                    self.cancel_pending_orders(&pending).await?;
+                    let new_pending = self
+                        .reissue_entry_as_taker(key, &pending, price_map)
+                        .await?;
+                    if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                        state.pending_entry = new_pending;
+                    }
+                }
+            } else if self.clock.elapsed(pending.placed_at) >= timeout {
+                // Partial fill or stuck orders; cancel and flatten any filled leg
+                if status.open_remaining > 0 {
+                    log::warn!(
+                        "[ORDER] {} entry orders stale ({}s), cancelling {} legs",
+                        key,
+                        self.clock.elapsed(pending.placed_at).as_secs(),
+                        status.open_remaining
+                    );
+                    for leg in &pending.legs {
+                        let filled = filled_qtys
+                            .get(&leg.order_id)
+                            .cloned()
+                            .unwrap_or(Decimal::ZERO);
+                        let is_open = status.open_ids.contains(&leg.order_id);
+                        log::debug!(
+                            "[ORDER] {} entry leg status symbol={} order_id={} target={} filled={} open={}",
+                            key,
+                            leg.symbol,
+                            leg.order_id,
+                            leg.target,
+                            filled,
+                            is_open
+                        );
+                    }
+                    self.cancel_pending_orders(&pending).await?;
+                }
+                let filled_qtys = self.filled_by_leg(&pending, &status.fills);
+                let mut flattened_any = false;
+                let mut hedge_failed = false;
+                let mut retry_count = pending.hedge_retry_count;
+                let max_retries = 3u32;
+                for leg in &pending.legs {
+                    let filled = filled_qtys
+                        .get(&leg.order_id)
+                        .cloned()
+                        .unwrap_or(Decimal::ZERO);
+                    if filled > Decimal::ZERO {
+                        if price_map.contains_key(&leg.symbol) {
+                            let hedge_side = match leg.side {
+                                dex_connector::OrderSide::Long => dex_connector::OrderSide::Short,
+                                dex_connector::OrderSide::Short => dex_connector::OrderSide::Long,
+                            };
+                            let use_market = retry_count + 1 >= max_retries;
+                            let limit = if use_market {
+                                None
+                            } else {
+                                self.limit_price_for(&leg.symbol, hedge_side, price_map)
+                            };
+                            if !use_market && limit.is_none() {
+                                log::warn!(
+                                    "[ORDER] Missing reference price for hedge {} leg {}",
+                                    leg.symbol,
+                                    leg.order_id
+                                );
+                                hedge_failed = true;
+                                continue;
+                            }
+                            let spread = self.order_spread_param(limit, false);
+                            if let Err(e) = self
+                                .connector
+                                .create_order(
+                                    &leg.symbol,
+                                    filled,
+                                    hedge_side,
+                                    limit,
+                                    spread,
+                                    true,
+                                    None,
+                                )
+                                .await
+                            {
+                                log::error!(
+                                    "[ORDER] Failed to hedge partial entry {} ({}): {:?}",
+                                    leg.symbol,
+                                    leg.order_id,
+                                    e
+                                );
+                                hedge_failed = true;
+                            } else {
+                                flattened_any = true;
+                                let mode = if use_market { "MARKET" } else { "LIMIT" };
+                                log::warn!(
+                                    "[ORDER] Hedged partial entry on {} size={} mode={} retries={}",
+                                    leg.symbol,
+                                    filled,
+                                    mode,
+                                    retry_count
+                                );
+                            }
+                        } else {
+                            log::warn!(
+                                "[ORDER] Missing price map entry for hedge {} leg {}",
+                                leg.symbol,
+                                leg.order_id
+                            );
+                            hedge_failed = true;
+                        }
+                    }
+                }
+                if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                    if hedge_failed {
+                        retry_count = retry_count.saturating_add(1);
+                        pending.hedge_retry_count = retry_count;
+                        log::warn!(
+                            "[ORDER] Hedge retry scheduled for {} (retry {} of {})",
+                            key,
+                            retry_count,
+                            max_retries
+                        );
+                        pending.placed_at = self.clock.now();
+                        state.pending_entry = Some(pending);
+                    } else {
+                        state.last_exit_at = Some(self.clock.now());
+                        state.last_exit_ts = Some(now_ts);
+                        state.pending_entry = None;
+                        if flattened_any {
+                            state.position = None;
+                        }
+                    }
+                }
+            } else if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                state.pending_entry = Some(pending);
+            }
+        }
+
+        if let Some(pending) = pending_exit {
+            let status = match prefetched.remove(&(key.to_string(), false)) {
+                Some(status) => status,
+                None => self.pending_status(&pending).await?,
+            };
+            let mut pending = pending;
+            self.update_pending_fills(&mut pending, &status.fills);
+            let filled_qtys = self.filled_by_leg(&pending, &status.fills);
+            let mut pnl_record: Option<(PnlLogRecord, f64)> = None;
+            if pending.partial_exit
+                && status.open_remaining == 0
+                && self.all_filled(&pending, &status.fills)
+            {
+                self.record_realized_slippage(&pending, &status);
+                self.book_partial_fills(inst_idx, key, &pending, &filled_qtys, price_map);
+                log::info!("[PARTIAL_TP] {} partial exit orders filled", key);
+            } else if pending.partial_exit {
+                if self.clock.elapsed(pending.placed_at) >= timeout || status.open_remaining == 0 {
+                    // Never escalate a take-profit slice to a full close:
+                    // cancel what's left and keep the rest of the position.
+                    log::warn!(
+                        "[PARTIAL_TP] {} partial exit incomplete ({} open); keeping the remainder",
+                        key,
+                        status.open_remaining
+                    );
+                    if status.open_remaining > 0 {
+                        self.cancel_pending_orders(&pending).await?;
+                    }
+                    self.book_partial_fills(inst_idx, key, &pending, &filled_qtys, price_map);
+                } else if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                    state.pending_exit = Some(pending);
+                }
+            } else if status.open_remaining == 0 && self.all_filled(&pending, &status.fills) {
+                self.record_realized_slippage(&pending, &status);
+                let exit_fill_cash = if self.cfg.reconcile_fill_pnl {
+                    self.fill_cash_flow(&pending, &status)
+                } else {
+                    None
+                };
+                let mut exit_qtys: HashMap<&str, Decimal> = HashMap::new();
+                for leg in &pending.legs {
+                    let qty = filled_qtys.get(&leg.order_id).copied().unwrap_or_default();
+                    *exit_qtys.entry(leg.symbol.as_str()).or_default() += qty;
+                }
+                if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                    if let Some(pos) = state.position.as_ref() {
+                        if let Some((base, quote)) = key.split_once('/') {
+                            if let (Some(p1), Some(p2)) =
+                                (price_map.get(base), price_map.get(quote))
+                            {
+                                let price_pnl = compute_pnl(pos, p1.price, p2.price);
+                                let sizes_match = pos.entry_size_a == exit_qtys.get(base).copied()
+                                    && pos.entry_size_b == exit_qtys.get(quote).copied();
+                                let fill_pnl = exit_fill_cash
+                                    .zip(pos.entry_fill_cash)
+                                    .filter(|_| sizes_match)
+                                    .map(|(exit, entry)| entry + exit);
+                                if let Some(fill) = fill_pnl {
+                                    log::info!(
+                                        "[PNL] {} fill-reconciled pnl={} (price-based {:?})",
+                                        key,
+                                        fill,
+                                        price_pnl
+                                    );
+                                } else if self.cfg.reconcile_fill_pnl {
+                                    log::debug!(
+                                        "[PNL] {} fill data incomplete; using price-based pnl",
+                                        key
+                                    );
+                                }
+                                if let Some(pnl) = fill_pnl.or(price_pnl).and_then(|p| p.to_f64())
+                                {
+                                    let hold_secs = Some(
+                                        now_ts.saturating_sub(pos.entered_ts).max(0) as f64,
+                                    );
+                                    let entry_a = pos.entry_price_a.and_then(|v| v.to_f64());
+                                    let entry_b = pos.entry_price_b.and_then(|v| v.to_f64());
+                                    let z_exit = state.z_score().map(|(z, _)| z);
+                                    let beta_val = Some(state.beta);
+                                    pnl_record = Some((
+                                        PnlLogRecord::new(
+                                            base,
+                                            quote,
+                                            pos.direction,
+                                            pnl,
+                                            now_ts,
+                                            "exit_fill",
+                                        ).with_trade_details(
+                                            entry_a, entry_b,
+                                            p1.price.to_f64(), p2.price.to_f64(),
+                                            beta_val,
+                                            pos.entry_z,
+                                            z_exit,
+                                            hold_secs,
+                                        ).with_exit_reason(state.last_exit_reason),
+                                        pnl,
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                    state.position = None;
+                    state.last_exit_at = Some(self.clock.now());
+                    state.last_exit_ts = Some(now_ts);
+                    state.pending_exit = None;
+                }
+                log::info!("[ORDER] {} exit orders filled", key);
+                if let Some((record, pnl_value)) = pnl_record {
+                    self.write_pnl_record(inst_idx, record);
+                    if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                        state.record_exit_pnl(pnl_value);
+                    }
+                    self.instances[inst_idx].realized_pnl_today += pnl_value;
+                    let mut risk_state_dirty = pnl_value != 0.0;
+                    if pnl_value < 0.0 {
+                        self.instances[inst_idx].consecutive_losses += 1;
+                        risk_state_dirty = true;
+                        if let Some(cooldown) = self
+                            .cfg
+                            .circuit_breaker_cooldown_for(self.instances[inst_idx].consecutive_losses)
+                        {
+                            self.instances[inst_idx].circuit_breaker_until = Some(self.clock.now() + cooldown);
+                            self.instances[inst_idx].circuit_breaker_until_ts =
+                                Some(now_ts + cooldown.as_secs() as i64);
+                            log::warn!(
+                                "[CIRCUIT_BREAKER] activated after {} consecutive losses, cooldown {}s",
+                                self.instances[inst_idx].consecutive_losses, cooldown.as_secs()
+                            );
+                        }
+                    } else if pnl_value > 0.0 {
+                        if self.instances[inst_idx].consecutive_losses > 0 {
+                            log::info!(
+                                "[CIRCUIT_BREAKER] reset after win (was {} consecutive losses)",
+                                self.instances[inst_idx].consecutive_losses
+                            );
+                            risk_state_dirty = true;
+                        }
+                        self.instances[inst_idx].consecutive_losses = 0;
+                        self.instances[inst_idx].circuit_breaker_until = None;
+                        self.instances[inst_idx].circuit_breaker_until_ts = None;
+                    }
+                    if risk_state_dirty {
+                        self.persist_risk_state();
+                    }
+                }
+            } else if filled_qtys.values().any(|qty| *qty > Decimal::ZERO) {
+                let next_retry = pending.hedge_retry_count.saturating_add(1);
+                if next_retry > MAX_EXIT_RETRIES {
+                    self.force_close_all_positions(key, "partial_fill").await;
+                    if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                        state.pending_exit = None;
+                    }
+                    return Ok(());
+                }
+                log::info!(
+                    "[ORDER] {} exit leg partially filled, reissuing remaining legs",
+                    key
+                );
+                self.cancel_pending_orders(&pending).await?;
+                if let Some(new_pending) = self
+                    .reissue_partial_legs(&pending, &filled_qtys, price_map, true, true, next_retry)
+                    .await?
+                {
+                    if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                        state.pending_exit = Some(new_pending);
+                    }
+                } else if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                    state.pending_exit = None;
+                }
+                return Ok(());
+            } else if self.clock.elapsed(pending.placed_at) >= timeout || status.open_remaining == 0 {
+                let next_retry = pending.hedge_retry_count.saturating_add(1);
+                if next_retry > MAX_EXIT_RETRIES {
+                    self.force_close_all_positions(key, "timeout").await;
+                    if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                        state.pending_exit = None;
+                    }
+                    return Ok(());
+                }
+                if status.open_remaining > 0 {
+                    log::warn!(
+                        "[ORDER] {} exit orders stale ({}s), cancelling {} legs",
+                        key,
+                        self.clock.elapsed(pending.placed_at).as_secs(),
+                        status.open_remaining
+                    );
+                    for leg in &pending.legs {
+                        let filled = filled_qtys
+                            .get(&leg.order_id)
+                            .cloned()
+                            .unwrap_or(Decimal::ZERO);
+                        let is_open = status.open_ids.contains(&leg.order_id);
+                        log::debug!(
+                            "[ORDER] {} exit leg status symbol={} order_id={} target={} filled={} open={}",
+                            key,
+                            leg.symbol,
+                            leg.order_id,
+                            leg.target,
+                            filled,
+                            is_open
+                        );
+                    }
+                    self.cancel_pending_orders(&pending).await?;
+                }
+                // Re-attempt closing missing legs based on filled qty
+                // reusing filled_qtys defined earlier
+                let mut new_legs = Vec::new();
+                for leg in &pending.legs {
+                    let filled = filled_qtys
+                        .get(&leg.order_id)
+                        .cloned()
+                        .unwrap_or(Decimal::ZERO);
+                    let remaining_qty = (leg.target - filled).max(Decimal::ZERO);
+                    if remaining_qty > Decimal::ZERO {
+                        let quantized =
+                            self.quantize_order_size_exit(&leg.symbol, remaining_qty, price_map);
+                        if quantized <= Decimal::ZERO {
+                            continue;
+                        }
+                        let limit = None;
+                        match self
+                            .connector
+                            .create_order(&leg.symbol, quantized, leg.side, limit, None, true, None)
+                            .await
+                        {
+                            Ok(resp) => {
+                                new_legs.push(PendingLeg {
+                                    symbol: leg.symbol.clone(),
+                                    order_id: resp.order_id,
+                                    exchange_order_id: resp.exchange_order_id,
+                                    target: quantized,
+                                    filled: Decimal::ZERO,
+                                    side: leg.side,
+                                    limit_price: None,
+                                    placed_price: price_map.get(&leg.symbol).map(|s| s.price),
+                                });
+                                log::warn!(
+                                    "[ORDER] Retrying exit leg {} size={} mode=MARKET",
+                                    leg.symbol,
+                                    quantized
+                                );
+                            }
+                            Err(e) => log::error!(
+                                "[ORDER] Failed to retry exit leg {}: {:?}",
+                                leg.symbol,
+                                e
+                            ),
+                        }
+                    }
+                }
+                if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                    if new_legs.is_empty() {
+                        state.pending_exit = None;
+                        // Keep position state unchanged; will retry next loop
+                    } else {
+                        state.pending_exit = Some(PendingOrders {
+                            legs: new_legs,
+                            direction: pending.direction,
+                            placed_at: self.clock.now(),
+                            hedge_retry_count: next_retry,
+                            post_only_hybrid: false,
+                            partial_exit: pending.partial_exit,
+                            scale_in: pending.scale_in,
+                        });
+                    }
+                }
+            } else if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                state.pending_exit = Some(pending);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Book whatever a `partial_exit` pending exit filled against the
+    /// position and clear it. Each leg shrinks by its own fill, so an
+    /// uneven slice is left for the hedge-ratio check to rebalance.
+    fn book_partial_fills(
+        &mut self,
+        inst_idx: usize,
+        key: &str,
+        pending: &PendingOrders,
+        filled_qtys: &HashMap<String, Decimal>,
+        price_map: &HashMap<String, SymbolSnapshot>,
+    ) {
+        if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+            state.pending_exit = None;
+        }
+        let Some((base, quote)) = key.split_once('/') else {
+            return;
+        };
+        let qty_a = filled_for_symbol(pending, filled_qtys, base);
+        let qty_b = filled_for_symbol(pending, filled_qtys, quote);
+        if qty_a <= Decimal::ZERO && qty_b <= Decimal::ZERO {
+            return;
+        }
+        let (Some(p_a), Some(p_b)) = (price_map.get(base), price_map.get(quote)) else {
+            log::warn!("[PARTIAL_TP] {} missing prices; slice resized without PnL", key);
+            if let Some(position) = self.instances[inst_idx]
+                .states
+                .get_mut(key)
+                .and_then(|s| s.position.as_mut())
+            {
+                position.entry_size_a = position.entry_size_a.map(|s| (s - qty_a).max(Decimal::ZERO));
+                position.entry_size_b = position.entry_size_b.map(|s| (s - qty_b).max(Decimal::ZERO));
+                position.entry_fill_cash = None;
+            }
+            return;
+        };
+        self.book_partial_exit(inst_idx, key, qty_a, qty_b, p_a.price, p_b.price);
+    }
+
+    /// Book whatever a `scale_in` pending entry filled onto the position
+    /// and clear it.
+    fn book_scale_in_fills(
+        &mut self,
+        inst_idx: usize,
+        key: &str,
+        pending: &PendingOrders,
+        filled_qtys: &HashMap<String, Decimal>,
+        price_map: &HashMap<String, SymbolSnapshot>,
+    ) {
+        if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+            state.pending_entry = None;
+        }
+        let Some((base, quote)) = key.split_once('/') else {
+            return;
+        };
+        let add_a = filled_for_symbol(pending, filled_qtys, base);
+        let add_b = filled_for_symbol(pending, filled_qtys, quote);
+        if add_a <= Decimal::ZERO && add_b <= Decimal::ZERO {
+            return;
+        }
+        let price = |symbol: &str| price_map.get(symbol).map(|s| s.price);
+        self.book_scale_in(inst_idx, key, add_a, add_b, price(base), price(quote));
+    }
+
+    async fn cancel_pending_orders(&self, pending: &PendingOrders) -> Result<()> {
+        let mut by_symbol: HashMap<String, Vec<String>> = HashMap::new();
+        for leg in &pending.legs {
+            by_symbol
+                .entry(leg.symbol.clone())
+                .or_default()
+                .push(leg.order_id.clone());
+        }
+        for (symbol, order_ids) in by_symbol {
+            if let Err(e) = self
+                .connector
+                .cancel_orders(Some(symbol.clone()), order_ids.clone())
+                .await
+            {
+                log::error!(
+                    "[ORDER] cancel failed for {} ({} ids): {:?}",
+                    symbol,
+                    order_ids.len(),
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn pending_status(&self, pending: &PendingOrders) -> Result<PendingStatus> {
+        fetch_pending_status(&self.connector, &pending.legs).await
+    }
+
+    /// Fetch `PendingStatus` for every pair of this instance with a pending
+    /// entry/exit, up to `reconcile_concurrency` at a time. The fetches are
+    /// read-only, so pairs sharing a leg symbol are safe to query in
+    /// parallel; all state mutation still happens serially in
+    /// `reconcile_pending_orders`. Returns an empty map when concurrency is
+    /// disabled (<= 1) or fewer than two orders are pending, and skips pairs
+    /// whose fetch failed so the serial path retries and surfaces the error.
+    async fn prefetch_pending_statuses(
+        &self,
+        inst_idx: usize,
+    ) -> HashMap<(String, bool), PendingStatus> {
+        let mut out = HashMap::new();
+        let limit = self.cfg.reconcile_concurrency;
+        if limit <= 1 {
+            return out;
+        }
+        let mut jobs: Vec<((String, bool), Vec<PendingLeg>)> = Vec::new();
+        for (key, state) in &self.instances[inst_idx].states {
+            if let Some(p) = &state.pending_entry {
+                jobs.push(((key.clone(), true), p.legs.clone()));
+            }
+            if let Some(p) = &state.pending_exit {
+                jobs.push(((key.clone(), false), p.legs.clone()));
+            }
+        }
+        if jobs.len() < 2 {
+            return out;
+        }
+        let mut join_set = tokio::task::JoinSet::new();
+        let mut jobs = jobs.into_iter();
+        loop {
+            while join_set.len() < limit {
+                let Some((job_key, legs)) = jobs.next() else { break };
+                let connector = self.connector.clone();
+                join_set.spawn(async move {
+                    let res = fetch_pending_status(&connector, &legs).await;
+                    (job_key, res)
+                });
+            }
+            let Some(joined) = join_set.join_next().await else { break };
+            match joined {
+                Ok((job_key, Ok(status))) => {
+                    out.insert(job_key, status);
+                }
+                Ok(((key, _), Err(e))) => {
+                    log::debug!("[ORDER] prefetch pending status {} failed: {:?}", key, e);
+                }
+                Err(e) => {
+                    log::debug!("[ORDER] prefetch pending status task failed: {:?}", e);
+                }
+            }
+        }
+        out
+    }
+
+    fn leg_fill_from_map(&self, leg: &PendingLeg, fills: &HashMap<String, Decimal>) -> Decimal {
+        fills
+            .get(&leg.order_id)
+            .cloned()
+            .or_else(|| {
+                leg.exchange_order_id
+                    .as_ref()
+                    .and_then(|id| fills.get(id).cloned())
+            })
+            .unwrap_or(Decimal::ZERO)
+    }
+
+    fn update_pending_fills(&self, pending: &mut PendingOrders, fills: &HashMap<String, Decimal>) {
+        for leg in &mut pending.legs {
+            let filled = self.leg_fill_from_map(leg, fills);
+            if filled > leg.filled {
+                leg.filled = filled.min(leg.target);
+            }
+        }
+    }
+
+    fn filled_for_leg(&self, leg: &PendingLeg, fills: &HashMap<String, Decimal>) -> Decimal {
+        let filled = self.leg_fill_from_map(leg, fills);
+        filled.max(leg.filled).min(leg.target)
+    }
+
+    fn filled_by_leg(
+        &self,
+        pending: &PendingOrders,
+        fills: &HashMap<String, Decimal>,
+    ) -> HashMap<String, Decimal> {
+        let mut map = HashMap::new();
+        for leg in &pending.legs {
+            let filled = self.filled_for_leg(leg, fills);
+            map.insert(leg.order_id.clone(), filled);
+        }
+        map
+    }
+
+    fn all_filled(&self, pending: &PendingOrders, fills: &HashMap<String, Decimal>) -> bool {
+        pending
+            .legs
+            .iter()
+            .all(|leg| self.filled_for_leg(leg, fills) >= leg.target)
+    }
+
+    /// Net cash flow of a fully filled pending order from venue fill data:
+    /// sell-side values in, buy-side values out, fees out. `None` when any
+    /// leg is missing a fill size or value; a missing fee counts as zero.
+    fn fill_cash_flow(&self, pending: &PendingOrders, status: &PendingStatus) -> Option<Decimal> {
+        let mut cash = Decimal::ZERO;
+        for leg in &pending.legs {
+            let qty = self.leg_fill_from_map(leg, &status.fills);
+            let value = self.leg_fill_from_map(leg, &status.fill_values);
+            if qty <= Decimal::ZERO || value <= Decimal::ZERO {
+                return None;
+            }
+            match leg.side {
+                dex_connector::OrderSide::Long => cash -= value,
+                dex_connector::OrderSide::Short => cash += value,
+            }
+            cash -= self.leg_fill_from_map(leg, &status.fill_fees);
+        }
+        Some(cash)
+    }
+
+    /// Fold each leg of a fully filled pending order into its symbol's
+    /// realized-slippage window: the venue-reported fill price against the
+    /// posted limit (or, for market legs, the price when it was placed),
+    /// signed so positive is worse than modeled. Legs without a reported
+    /// fill value or a reference price are skipped. Crossing
+    /// `max_realized_slippage_bps` in either direction is logged once.
+    fn record_realized_slippage(&mut self, pending: &PendingOrders, status: &PendingStatus) {
+        for leg in &pending.legs {
+            let qty = self.leg_fill_from_map(leg, &status.fills);
+            let value = self.leg_fill_from_map(leg, &status.fill_values);
+            let reference = leg
+                .limit_price
+                .or(leg.placed_price)
+                .filter(|p| *p > Decimal::ZERO);
+            let Some(reference) = reference else { continue };
+            if qty <= Decimal::ZERO || value <= Decimal::ZERO {
+                continue;
+            }
+            let ratio = ((value / qty) / reference).to_f64().unwrap_or(1.0);
+            let slippage_bps = match leg.side {
+                dex_connector::OrderSide::Long => (ratio - 1.0) * 10_000.0,
+                dex_connector::OrderSide::Short => (1.0 - ratio) * 10_000.0,
+            };
+            let window = self.realized_slippage.entry(leg.symbol.clone()).or_default();
+            if window.len() >= REALIZED_SLIPPAGE_WINDOW {
+                window.pop_front();
+            }
+            window.push_back(slippage_bps);
+            log::debug!(
+                "[SLIPPAGE] {} realized={:.2}bps rolling={:.2}bps n={}",
+                leg.symbol,
+                slippage_bps,
+                window.iter().sum::<f64>() / window.len() as f64,
+                window.len()
+            );
+        }
+        let cap = self.cfg.max_realized_slippage_bps;
+        if cap <= 0.0 {
+            return;
+        }
+        let symbols: HashSet<&String> = pending.legs.iter().map(|l| &l.symbol).collect();
+        for symbol in symbols {
+            let Some(window) = self.realized_slippage.get(symbol) else { continue };
+            if window.len() < REALIZED_SLIPPAGE_MIN_SAMPLES {
+                continue;
+            }
+            let rolling = window.iter().sum::<f64>() / window.len() as f64;
+            if rolling > cap {
+                if !self.slippage_blocked.contains_key(symbol) {
+                    self.slippage_blocked.insert(symbol.clone(), self.clock.now());
+                    log::warn!(
+                        "[SLIPPAGE] {} rolling realized slippage {:.2}bps > {:.2}bps over {} fills; blocking entries",
+                        symbol,
+                        rolling,
+                        cap,
+                        window.len()
+                    );
+                }
+            } else if self.slippage_blocked.remove(symbol).is_some() {
+                log::info!(
+                    "[SLIPPAGE] {} rolling realized slippage back to {:.2}bps; entries allowed",
+                    symbol,
+                    rolling
+                );
+            }
+        }
+    }
+
+    /// Whether `symbol` is blocked by `max_realized_slippage_bps`. A block
+    /// older than `realized_slippage_block_secs` lapses here and clears the
+    /// symbol's window, so the fills that set it can't re-block at once.
+    fn slippage_block_active(&mut self, symbol: &str) -> bool {
+        let Some(since) = self.slippage_blocked.get(symbol).copied() else {
+            return false;
+        };
+        let block_secs = self.cfg.realized_slippage_block_secs;
+        if block_secs == 0 || self.clock.elapsed(since) < Duration::from_secs(block_secs) {
+            return true;
+        }
+        self.slippage_blocked.remove(symbol);
+        self.realized_slippage.remove(symbol);
+        log::info!(
+            "[SLIPPAGE] {} block lapsed after {}s; entries allowed",
+            symbol,
+            block_secs
+        );
+        false
+    }
+
+    /// Drop a NaN/inf statistic for `key` and block its entries for
+    /// `non_finite_stats_block_secs`.
+    fn block_non_finite_stats(
+        &mut self,
+        inst_idx: usize,
+        key: &str,
+        stat: &str,
+        value: f64,
+        now_ts: i64,
+    ) {
+        let block_secs = self.cfg.non_finite_stats_block_secs;
+        log::warn!(
+            "[NON_FINITE] {} {}={} discarded; pair untradeable for {}s",
+            key,
+            stat,
+            value,
+            block_secs
+        );
+        if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+            state.stats_blocked_until_ts = Some(now_ts.saturating_add(block_secs as i64));
+        }
+    }
+
+    fn evaluate_pair(&self, pair: &PairSpec) -> Option<PairEvaluation> {
+        pair_eval::evaluate_pair(&self.cfg, &self.history, pair)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn exit_sizes_for_pair(
+        &self,
+        inst_idx: usize,
+        key: &str,
+        pair: &PairSpec,
+        direction: PositionDirection,
+        beta: f64,
+        p1: &SymbolSnapshot,
+        p2: &SymbolSnapshot,
+    ) -> Result<(Decimal, Decimal)> {
+        let base_snapshot = self.open_positions.get(&pair.base);
+        let quote_snapshot = self.open_positions.get(&pair.quote);
+        if base_snapshot.is_some() || quote_snapshot.is_some() {
+            let qty_a = base_snapshot.map(|p| p.size).unwrap_or(Decimal::ZERO);
+            let qty_b = quote_snapshot.map(|p| p.size).unwrap_or(Decimal::ZERO);
+            return Ok((qty_a, qty_b));
+        }
+
+        let mut qty_a = Decimal::ZERO;
+        let mut qty_b = Decimal::ZERO;
+        if let Some(state) = self.instances[inst_idx].states.get(key).and_then(|s| s.position.as_ref()) {
+            qty_a = state.entry_size_a.unwrap_or(Decimal::ZERO);
+            qty_b = state.entry_size_b.unwrap_or(Decimal::ZERO);
+        }
+
+        if qty_a <= Decimal::ZERO && qty_b <= Decimal::ZERO {
+            log::warn!(
+                "[EXIT] {} missing position sizes from exchange/state; falling back to hedge sizing",
+                key
+            );
+            return self.hedged_sizes(inst_idx, pair, direction, beta, p1, p2);
+        }
+
+        Ok((qty_a, qty_b))
+    }
+
+    fn hedged_sizes(
+        &self,
+        inst_idx: usize,
+        pair: &PairSpec,
+        direction: PositionDirection,
+        beta: f64,
+        p1: &SymbolSnapshot,
+        p2: &SymbolSnapshot,
+    ) -> Result<(Decimal, Decimal)> {
+        let inst = &self.instances[inst_idx];
+        let equity = inst.equity_reference_usd;
+        let key = format!("{}/{}", pair.base, pair.quote);
+        let spread_std = if self.cfg.vol_target_sigma > 0.0 {
+            inst.states
+                .get(&key)
+                .and_then(|state| tail_std(&state.spread_history, self.cfg.metrics_window))
+        } else {
+            None
+        };
+        let notional = self.pair_params_for(inst_idx, &key).notional_per_leg_usd;
+        let beta = sizing_beta(&self.cfg, &self.history, &pair.base, &pair.quote, beta);
+        sizing::hedged_sizes(&self.cfg, equity, direction, beta, spread_std, notional, p1, p2)
+    }
+
+    fn post_only_supported(&self) -> bool {
+        self.cfg.post_only_supported()
+    }
+
+    fn should_post_only(&self) -> bool {
+        self.cfg.should_post_only()
+    }
+
+    fn order_reference_price_from_snapshot(
+        &self,
+        symbol: &str,
+        side: dex_connector::OrderSide,
+        snapshot: &SymbolSnapshot,
+    ) -> Decimal {
+        let use_book = self.cfg.slippage_bps < 0 || self.should_post_only();
+        if use_book && snapshot.book_crossed() {
+            log::warn!(
+                "[ORDER] {} crossed book (bid={:?} ask={:?}); using ticker price",
+                symbol,
+                snapshot.bid_price,
+                snapshot.ask_price
+            );
+            return snapshot.price;
+        }
+        if use_book {
+            let side_price = match side {
+                dex_connector::OrderSide::Long => snapshot.ask_price,
+                dex_connector::OrderSide::Short => snapshot.bid_price,
+            };
+            if side_price.is_none() {
+                log::debug!(
+                    "[ORDER] {} missing top-of-book price; using ticker price",
+                    symbol
+                );
+            }
+            return side_price.unwrap_or(snapshot.price);
+        }
+        snapshot.price
+    }
+
+    fn order_reference_price(
+        &self,
+        symbol: &str,
+        side: dex_connector::OrderSide,
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) -> Option<Decimal> {
+        let snapshot = prices.get(symbol)?;
+        Some(self.order_reference_price_from_snapshot(symbol, side, snapshot))
+    }
+
+    fn limit_price_for(
+        &mut self,
+        symbol: &str,
+        side: dex_connector::OrderSide,
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) -> Option<Decimal> {
+        let snapshot = prices.get(symbol)?;
+        let reference = self.order_reference_price_from_snapshot(symbol, side, snapshot);
+        let adjusted = self.apply_slippage(Some(reference), side)?;
+        Some(self.quantize_order_price_with_snapshot(symbol, adjusted, side, snapshot))
+    }
+
+    fn limit_price_for_snapshot(
+        &mut self,
+        symbol: &str,
+        side: dex_connector::OrderSide,
+        snapshot: &SymbolSnapshot,
+    ) -> Option<Decimal> {
+        let reference = self.order_reference_price_from_snapshot(symbol, side, snapshot);
+        let adjusted = self.apply_slippage(Some(reference), side)?;
+        Some(self.quantize_order_price_with_snapshot(symbol, adjusted, side, snapshot))
+    }
+
+    async fn refreshed_limit_price(
+        &mut self,
+        symbol: &str,
+        side: dex_connector::OrderSide,
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) -> Option<Decimal> {
+        match self.refresh_symbol_snapshot(symbol).await {
+            Ok(snapshot) => self.limit_price_for_snapshot(symbol, side, &snapshot),
+            Err(err) => {
+                log::debug!(
+                    "[ORDER] Failed to refresh price snapshot for {}: {:?}",
+                    symbol,
+                    err
+                );
+                self.limit_price_for(symbol, side, prices)
+            }
+        }
+    }
+
+    async fn refresh_symbol_snapshot(&mut self, symbol: &str) -> Result<SymbolSnapshot> {
+        let ticker = self
+            .connector
+            .get_ticker(symbol, None)
+            .await
+            .with_context(|| format!("ticker {}", symbol))?;
+        let (bid_price, ask_price, bid_size, ask_size, depth) =
+            match self
+                .connector
+                .get_order_book(symbol, self.cfg.orderbook_depth)
+                .await
+            {
+                Ok(ob) => (
+                    ob.bids.first().map(|l| l.price),
+                    ob.asks.first().map(|l| l.price),
+                    ob.bids.first().map(|l| l.size).unwrap_or(Decimal::ZERO),
+                    ob.asks.first().map(|l| l.size).unwrap_or(Decimal::ZERO),
+                    BookDepth::from_book(
+                        &ob,
+                        self.cfg.orderbook_depth,
+                        self.cfg.orderbook_depth_band_bps,
+                    ),
+                ),
+                Err(err) => {
+                    log::debug!(
+                        "[ORDER] orderbook {} unavailable during retry: {:?}",
+                        symbol,
+                        err
+                    );
+                    (None, None, Decimal::ZERO, Decimal::ZERO, BookDepth::default())
+                }
+            };
+        Ok(SymbolSnapshot {
+            price: ticker.price,
+            funding_rate: ticker.funding_rate.unwrap_or(Decimal::ZERO),
+            bid_price,
+            ask_price,
+            bid_size,
+            ask_size,
+            min_order: ticker.min_order,
+            min_tick: ticker.min_tick,
+            size_decimals: ticker.size_decimals,
+            exchange_ts: ticker.exchange_ts.map(|v| v as i64),
+            depth,
+        })
+    }
+
+    fn order_spread_param(&self, limit: Option<Decimal>, allow_post_only: bool) -> Option<i64> {
+        if allow_post_only && limit.is_some() && self.should_post_only() {
+            Some(-2)
+        } else {
+            None
+        }
+    }
+
+    fn apply_slippage(
+        &self,
+        price: Option<Decimal>,
+        side: dex_connector::OrderSide,
+    ) -> Option<Decimal> {
+        order_pricing::apply_slippage(self.cfg.slippage_bps, price, side)
+    }
+
+    fn quantize_order_size(
+        &self,
+        symbol: &str,
+        size: Decimal,
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) -> Decimal {
+        order_pricing::quantize_order_size(symbol, size, prices)
+    }
+
+    fn quantize_order_size_exit(
+        &self,
+        symbol: &str,
+        size: Decimal,
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) -> Decimal {
+        order_pricing::quantize_order_size_exit(symbol, size, prices)
+    }
+
+    fn quantize_order_size_close(
+        &self,
+        symbol: &str,
+        size: Decimal,
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) -> Decimal {
+        order_pricing::quantize_order_size_close(symbol, size, prices)
+    }
+
+    fn quantize_order_price_with_snapshot(
+        &mut self,
+        symbol: &str,
+        price: Decimal,
+        side: dex_connector::OrderSide,
+        snapshot: &SymbolSnapshot,
+    ) -> Decimal {
+        let mut effective_tick_size = snapshot.min_tick;
+
+        // Extended occasionally returns markets without `min_tick` populated
+        // in the snapshot (dex-connector fills this from the markets cache,
+        // which may lag a reconnect). Fall back to tick=1 so we don't spam
+        // the "No min tick" warning every cycle.
+        if effective_tick_size.is_none() && self.cfg.dex_name.contains("extended") {
+            effective_tick_size = Some(Decimal::ONE);
+        }
+
+        let Some(tick_size) = effective_tick_size else {
+            if !self.min_tick_warned.contains(symbol) {
+                log::warn!(
+                    "[ORDER] No min tick for {}; price rounding disabled",
+                    symbol
+                );
+
+                self.min_tick_warned.insert(symbol.to_string());
+            }
+
+            return price;
+        };
+
+        if tick_size <= Decimal::ZERO {
+            return price;
+        }
+
+        let rounded = round_price_by_tick(price, tick_size, side);
+
+        // bot-strategy#216: tick rounding is a no-op when the touch price is
+        // already a tick multiple (Extended BTC tick=1 with integer prices),
+        // so post-only limits land at touch and get rejected/crossed.
+        if self.should_post_only() {
+            let touch = match side {
+                dex_connector::OrderSide::Long => snapshot.ask_price,
+                dex_connector::OrderSide::Short => snapshot.bid_price,
+            };
+            if let Some(touch) = touch {
+                return enforce_post_only_passive(rounded, touch, tick_size, side);
+            }
+        }
+
+        rounded
+    }
+
+    async fn create_order_with_post_only_retry(
+        &mut self,
+        symbol: &str,
+        size: Decimal,
+        side: dex_connector::OrderSide,
+        reduce_only: bool,
+        prices: &HashMap<String, SymbolSnapshot>,
+        allow_post_only: bool,
+        max_post_only_attempts: usize,
+        fallback_to_taker: Option<&'static str>,
+    ) -> Result<dex_connector::CreateOrderResponse, DexError> {
+        let use_post_only = allow_post_only && self.should_post_only();
+        let max_attempts = max_post_only_attempts.max(1);
+        let max_elapsed = Duration::from_millis(POST_ONLY_RETRY_MAX_ELAPSED_MS);
+        let start = Instant::now();
+        let mut attempt = 0usize;
+        // Each actionable rejection gets exactly one corrective retry.
+        let mut price_refresh_used = false;
+        let mut refresh_limit = false;
+
+        let last_err = loop {
+            attempt += 1;
+            let limit = if use_post_only || refresh_limit {
+                self.refreshed_limit_price(symbol, side, prices).await
+            } else {
+                self.limit_price_for(symbol, side, prices)
+            };
+            refresh_limit = false;
+            if use_post_only && limit.is_none() {
+                return Err(DexError::Other(format!(
+                    "[ORDER] Missing reference price for post-only {}",
+                    symbol
+                )));
+            }
+            let spread = self.order_spread_param(limit, use_post_only);
+            match self
+                .connector
+                .create_order(symbol, size, side, limit, spread, reduce_only, None)
+                .await
+            {
+                Ok(resp) => return Ok(resp),
+                Err(err) => {
+                    match classify_rejection(&err) {
+                        Some(RejectionKind::SizeTooSmall) => {
+                            // Bumping this leg alone to min_order would break
+                            // the beta hedge with the other leg; give up and
+                            // let the caller skip (or unwind) the entry.
+                            log::warn!(
+                                "[ORDER_REJECT] {} size_too_small at {}: not retrying ({:?})",
+                                symbol,
+                                size,
+                                err
+                            );
+                            return Err(err);
+                        }
+                        Some(RejectionKind::PriceOutOfBounds)
+                            if !price_refresh_used && limit.is_some() =>
+                        {
+                            log::warn!(
+                                "[ORDER_REJECT] {} price_out_of_bounds at {:?}: refreshing quote and retrying",
+                                symbol,
+                                limit
+                            );
+                            price_refresh_used = true;
+                            refresh_limit = true;
+                            continue;
+                        }
+                        Some(RejectionKind::InsufficientMargin) => {
+                            log::warn!("[ORDER_REJECT] {} insufficient_margin: {:?}", symbol, err);
+                            return Err(err);
+                        }
+                        Some(kind) => {
+                            log::warn!("[ORDER_REJECT] {} {}: {:?}", symbol, kind.as_str(), err);
+                        }
+                        None => {}
+                    }
+                    if !use_post_only {
+                        return Err(err);
+                    }
+                    if attempt >= max_attempts || start.elapsed() >= max_elapsed {
+                        break err;
+                    }
+                }
+            }
+
+            log::info!(
+                "[ORDER] {} post-only attempt {} failed; retrying",
+                symbol,
+                attempt
+            );
+            sleep(Duration::from_millis(POST_ONLY_RETRY_DELAY_MS)).await;
+        };
+
+        if let Some(reason) = fallback_to_taker.filter(|_| use_post_only) {
+            *self.taker_fallbacks.entry(reason).or_insert(0) += 1;
+            log::warn!(
+                "[ORDER] {} post-only attempts exhausted; falling back to taker (reason={})",
+                symbol,
+                reason
+            );
+            return self
+                .connector
+                .create_order(symbol, size, side, None, None, reduce_only, None)
+                .await;
+        }
+        if use_post_only && reduce_only {
+            log::info!(
+                "[ORDER] {} post-only close attempts exhausted; no taker fallback, retrying next cycle",
+                symbol
+            );
+        }
+
+        Err(last_err)
+    }
+
+    /// Block new entries on this instance for
+    /// `INSUFFICIENT_MARGIN_BLOCK_SECS` after an entry leg is rejected for
+    /// insufficient margin; retrying sooner only produces the same reject.
+    fn note_entry_rejection(&mut self, inst_idx: usize, err: &DexError) {
+        if classify_rejection(err) != Some(RejectionKind::InsufficientMargin) {
+            return;
+        }
+        let until = self.current_now_ts() + INSUFFICIENT_MARGIN_BLOCK_SECS as i64;
+        let inst = &mut self.instances[inst_idx];
+        log::warn!(
+            "[ORDER_REJECT] {} insufficient margin on entry; blocking new entries for {}s",
+            inst.id,
+            INSUFFICIENT_MARGIN_BLOCK_SECS
+        );
+        inst.margin_block_until_ts = Some(until);
+    }
+
+    async fn place_pair_orders(
+        &mut self,
+        inst_idx: usize,
+        pair: &PairSpec,
+        direction: PositionDirection,
+        hedge_inverted: bool,
+        qtys: (Decimal, Decimal),
+        prices: &HashMap<String, SymbolSnapshot>,
+    ) -> Result<Vec<PendingLeg>> {
+        let (side_a, side_b) = direction.entry_sides(hedge_inverted);
+        if self.cfg.reject_crossed_book {
+            let crossed = [&pair.base, &pair.quote]
+                .into_iter()
+                .find(|sym| prices.get(*sym).is_some_and(|s| s.book_crossed()));
+            if let Some(sym) = crossed {
+                log::warn!(
+                    "[ORDER_ADJUST][ENTRY] {}/{} BLOCKED: crossed book on {}",
+                    pair.base,
+                    pair.quote,
+                    sym
+                );
+                return Ok(Vec::new());
+            }
+        }
+        let ref_price_a = self.order_reference_price(&pair.base, side_a, prices);
+        let ref_price_b = self.order_reference_price(&pair.quote, side_b, prices);
+        // Pick the floor/ceiling combination per leg that best preserves the
+        // requested hedge ratio. With Extended's coarse ETH lot (0.01 ≈ $23) and a
+        // small Phase 3 budget (~$25/leg), a single rounding direction routinely
+        // produces 23-82% hedge deviation — searching the 4 combinations finds
+        // the round that keeps the ratio intact. See bot-strategy#211.
+        let qa_floor = self.quantize_order_size(&pair.base, qtys.0, prices);
+        let qa_ceil = self.quantize_order_size_exit(&pair.base, qtys.0, prices);
+        let qb_floor = self.quantize_order_size(&pair.quote, qtys.1, prices);
+        let qb_ceil = self.quantize_order_size_exit(&pair.quote, qtys.1, prices);
+        let (qty_a, qty_b, ratio_dev) = match order_pricing::pick_entry_quantize(
+            qtys, qa_floor, qa_ceil, qb_floor, qb_ceil,
+        ) {
+            Some(x) => x,
+            None => {
+                log::warn!(
+                    "[ORDER_ADJUST][ENTRY] {}/{} BLOCKED: zero qty after quantize (qty_a={}, qty_b={})",
+                    pair.base, pair.quote, qa_floor, qb_floor
+                );
+                return Ok(Vec::new());
+            }
+        };
+        if qty_a != qtys.0 {
+            log::debug!(
+                "[ORDER_ADJUST][ENTRY] {} settled qty_a {} -> {}",
+                pair.base,
+                qtys.0,
+                qty_a
+            );
+        }
+        if qty_b != qtys.1 {
+            log::debug!(
+                "[ORDER_ADJUST][ENTRY] {} settled qty_b {} -> {}",
+                pair.quote,
+                qtys.1,
+                qty_b
+            );
+        }
+        let pair_key_for_dev = format!("{}/{}", pair.base, pair.quote);
+        let pp_for_dev = self.pair_params_for(inst_idx, &pair_key_for_dev).clone();
+        let pp_for_dev = &pp_for_dev;
+        if pp_for_dev.hedge_ratio_max_deviation < 1.0
+            && ratio_dev > pp_for_dev.hedge_ratio_max_deviation
+        {
+            log::warn!(
+                "[ORDER_ADJUST][ENTRY] {}/{} BLOCKED: hedge ratio deviation {:.1}% exceeds limit {:.1}%",
+                pair.base, pair.quote, ratio_dev * 100.0, pp_for_dev.hedge_ratio_max_deviation * 100.0
+            );
+            return Ok(Vec::new());
+        }
+        let use_market = self.cfg.entry_market_mode;
+        let limit_a = if use_market {
+            None
+        } else {
+            self.limit_price_for(&pair.base, side_a, prices)
+        };
+        let limit_b = if use_market {
+            None
+        } else {
+            self.limit_price_for(&pair.quote, side_b, prices)
+        };
+        let pair_key_for_hybrid = format!("{}/{}", pair.base, pair.quote);
+        let pp_for_hybrid = self.pair_params_for(inst_idx, &pair_key_for_hybrid).clone();
+        let pp_for_hybrid = &pp_for_hybrid;
+        let hybrid_active = !use_market
+            && pp_for_hybrid.entry_post_only_timeout_secs > 0
+            && self.post_only_supported();
+        let post_only = !use_market && self.should_post_only();
+        let entry_attempts = if hybrid_active {
+            1
+        } else {
+            POST_ONLY_ENTRY_ATTEMPTS
+        };
+        let log_order_params = self.log_sampled(
+            "order_params",
+            &pair_key_for_hybrid,
+            self.cfg.log_order_params_every_n_ticks,
+        );
+        if log_order_params {
+            log::debug!(
+                "[ORDER_PARAMS][ENTRY] pair={}/{} side_a={:?} qty_a={} ref_price_a={} limit_a={:?} side_b={:?} qty_b={} ref_price_b={} limit_b={:?} post_only={} hybrid={}",
+                pair.base,
+                pair.quote,
+                side_a,
+                qty_a,
+                ref_price_a.unwrap_or(Decimal::ZERO),
+                limit_a,
+                side_b,
+                qty_b,
+                ref_price_b.unwrap_or(Decimal::ZERO),
+                limit_b,
+                post_only,
+                hybrid_active
+            );
+        }
+        let mut legs: Vec<PendingLeg> = Vec::new();
+        let res_a = if use_market {
+            self.connector
+                .create_order(&pair.base, qty_a, side_a, None, None, false, None)
+                .await
+        } else {
+            self.create_order_with_post_only_retry(
+                &pair.base,
+                qty_a,
+                side_a,
+                false,
+                prices,
+                true,
+                entry_attempts,
+                None,
+            )
+            .await
+        };
+        let res_a = match res_a {
+            Ok(res) => res,
+            Err(e) => {
+                self.note_entry_rejection(inst_idx, &e);
+                return Err(anyhow::Error::from(e).context("place leg A"));
+            }
+        };
+        self.note_ordered_size(&pair.base, qtys.0, res_a.ordered_size);
+        let target_a = if res_a.ordered_size > Decimal::ZERO {
+            if res_a.ordered_size != qtys.0 {
+                log::debug!(
+                    "[ORDER_PARAMS][ENTRY] size adjusted by exchange for {}: requested={} ordered={}",
+                    pair.base,
+                    qtys.0,
+                    res_a.ordered_size
+                );
+            }
+            res_a.ordered_size
+        } else {
+            qtys.0
+        };
+        legs.push(PendingLeg {
+            symbol: pair.base.clone(),
+            order_id: res_a.order_id.clone(),
+            exchange_order_id: res_a.exchange_order_id.clone(),
+            target: target_a,
+            filled: Decimal::ZERO,
+            side: side_a,
+            limit_price: limit_a,
+            placed_price: prices.get(&pair.base).map(|s| s.price),
+        });
+
+        let res_b = if use_market {
+            self.connector
+                .create_order(&pair.quote, qty_b, side_b, None, None, false, None)
+                .await
+        } else {
+            self.create_order_with_post_only_retry(
+                &pair.quote,
+                qty_b,
+                side_b,
+                false,
+                prices,
+                true,
+                entry_attempts,
+                None,
+            )
+            .await
+        };
+        let res_b = match res_b {
+            Ok(res) => res,
+            Err(e) => {
+                self.note_entry_rejection(inst_idx, &e);
+                self.recover_from_leg_b_failure(pair, &res_a, side_a, &e).await;
+                return Err(PartialOrderPlacementError::new(legs.clone(), e).into());
+            }
+        };
+        self.note_ordered_size(&pair.quote, qtys.1, res_b.ordered_size);
+        let target_b = if res_b.ordered_size > Decimal::ZERO {
+            if res_b.ordered_size != qtys.1 {
+                log::debug!(
+                    "[ORDER_PARAMS][ENTRY] size adjusted by exchange for {}: requested={} ordered={}",
+                    pair.quote,
+                    qtys.1,
+                    res_b.ordered_size
+                );
+            }
+            res_b.ordered_size
+        } else {
+            qtys.1
+        };
+        legs.push(PendingLeg {
+            symbol: pair.quote.clone(),
+            order_id: res_b.order_id.clone(),
+            exchange_order_id: res_b.exchange_order_id.clone(),
+            target: target_b,
+            filled: Decimal::ZERO,
+            side: side_b,
+            limit_price: limit_b,
+            placed_price: prices.get(&pair.quote).map(|s| s.price),
+        });
+        Ok(legs)
+    }
+
+    /// Recovery path when leg B placement fails after leg A succeeded:
+    /// cancel leg A, wait briefly, check whether the exchange filled it
+    /// anyway, and if so submit a market reduce-only order in the opposite
+    /// direction to neutralize the unhedged exposure. All errors here are
+    /// logged but not propagated — the caller still surfaces the original
+    /// leg-B failure.
+    async fn recover_from_leg_b_failure(
+        &self,
+        pair: &PairSpec,
+        res_a: &dex_connector::CreateOrderResponse,
+        side_a: dex_connector::OrderSide,
+        leg_b_err: &DexError,
+    ) {
+        log::error!(
+            "[ORDER] Failed to place leg B for {}/{} (leg A={}): {:?}",
+            pair.base,
+            pair.quote,
+            res_a.order_id,
+            leg_b_err
+        );
+
+        // Attempt to cancel leg A, but proceed even if it fails.
+        if let Err(cancel_err) = self
+            .connector
+            .cancel_order(&pair.base, &res_a.order_id)
+            .await
+        {
+            log::warn!(
+                "[SAFETY] Failed to cancel leg A {} after leg B failed: {:?}",
+                res_a.order_id,
+                cancel_err
+            );
+        } else {
+            log::info!(
+                "[SAFETY] Canceled leg A {} after leg B failed.",
+                res_a.order_id
+            );
+        }
+
+        // Give the exchange time to settle any concurrent fill.
+        sleep(Duration::from_secs(5)).await;
+
+        let filled_orders = match self.connector.get_filled_orders(&pair.base).await {
+            Ok(orders) => orders,
+            Err(e) => {
+                log::error!(
+                    "[SAFETY] Could not check for filled orders for {}: {:?}",
+                    pair.base,
+                    e
+                );
+                return;
+            }
+        };
+
+        let matches_order = |order_id: &str| {
+            order_id == res_a.order_id
+                || res_a
+                    .exchange_order_id
+                    .as_ref()
+                    .map_or(false, |id| order_id == id)
+        };
+        let Some(filled_order) = filled_orders
+            .orders
+            .iter()
+            .find(|o| matches_order(&o.order_id))
+        else {
+            return;
+        };
+        let filled_size = filled_order.filled_size.unwrap_or(Decimal::ZERO);
+        if filled_size <= Decimal::ZERO {
+            return;
+        }
+
+        log::warn!(
+            "[SAFETY] Leg A {} was filled for {}. Hedging immediately.",
+            res_a.order_id,
+            pair.base
+        );
+        let hedge_side = match side_a {
+            dex_connector::OrderSide::Long => dex_connector::OrderSide::Short,
+            dex_connector::OrderSide::Short => dex_connector::OrderSide::Long,
+        };
+        if let Err(hedge_err) = self
+            .connector
+            .create_order(&pair.base, filled_size, hedge_side, None, None, true, None)
+            .await
+        {
+            log::error!(
+                "[SAFETY] FAILED TO HEDGE partial fill for {}: {:?}",
+                pair.base,
+                hedge_err
+            );
+        } else {
+            log::info!(
+                "[SAFETY] Successfully submitted hedge order for partial fill on {}",
+                pair.base
+            );
+        }
+    }
+
+    async fn close_pair_orders(
+        &mut self,
+        pair: &PairSpec,
+        (side_a, side_b): (dex_connector::OrderSide, dex_connector::OrderSide),
+        qtys: (Decimal, Decimal),
+        prices: &HashMap<String, SymbolSnapshot>,
+        use_market: bool,
+        reason: &'static str,
+    ) -> Result<Vec<PendingLeg>> {
+        let use_market = use_market || self.cfg.exit_uses_market(reason);
+        let fallback = self.cfg.exit_taker_fallback_allowed(reason).then_some(reason);
+        let ref_price_a = self.order_reference_price(&pair.base, side_a, prices);
+        let ref_price_b = self.order_reference_price(&pair.quote, side_b, prices);
+        let qty_a = self.quantize_order_size_close(&pair.base, qtys.0, prices);
+        let qty_b = self.quantize_order_size_close(&pair.quote, qtys.1, prices);
+        if qty_a != qtys.0 {
+            log::debug!(
+                "[ORDER_ADJUST][EXIT] {} settled qty_a {} -> {}",
+                pair.base,
+                qtys.0,
+                qty_a
+            );
+        }
+        if qty_b != qtys.1 {
+            log::debug!(
+                "[ORDER_ADJUST][EXIT] {} settled qty_b {} -> {}",
+                pair.quote,
+                qtys.1,
+                qty_b
+            );
+        }
+        let limit_a = if use_market {
+            None
+        } else {
+            self.limit_price_for(&pair.base, side_a, prices)
+        };
+        let limit_b = if use_market {
+            None
+        } else {
+            self.limit_price_for(&pair.quote, side_b, prices)
+        };
+        let post_only = !use_market && self.should_post_only();
+        let log_order_params = self.log_sampled(
+            "order_params",
+            &format!("{}/{}", pair.base, pair.quote),
+            self.cfg.log_order_params_every_n_ticks,
+        );
+        if log_order_params {
+            log::debug!(
+                "[ORDER_PARAMS][EXIT] pair={}/{} reason={} side_a={:?} qty_a={} ref_price_a={} limit_a={:?} side_b={:?} qty_b={} ref_price_b={} limit_b={:?} post_only={}",
+                pair.base,
+                pair.quote,
+                reason,
+                side_a,
+                qty_a,
+                ref_price_a.unwrap_or(Decimal::ZERO),
+                limit_a,
+                side_b,
+                qty_b,
+                ref_price_b.unwrap_or(Decimal::ZERO),
+                limit_b,
+                post_only
+            );
+        }
+        let mut legs: Vec<PendingLeg> = Vec::new();
+        let mut res_a = None;
+        if qty_a > Decimal::ZERO {
+            let res = if use_market {
+                self.connector
+                    .create_order(&pair.base, qty_a, side_a, None, None, true, None)
+                    .await
+            } else {
+                self.create_order_with_post_only_retry(
+                    &pair.base,
+                    qty_a,
+                    side_a,
+                    true,
+                    prices,
+                    true,
+                    POST_ONLY_EXIT_ATTEMPTS,
+                    fallback,
+                )
+                .await
+            };
+            match res {
+                Ok(res) => {
+                    self.note_ordered_size(&pair.base, qty_a, res.ordered_size);
+                    if res.ordered_size > Decimal::ZERO && res.ordered_size != qty_a {
                         log::debug!(
-                            "[ORDER] {} entry leg status symbol={} order_id={} target={} filled={} open={}",
-                            key,
-                            leg.symbol,
-                            leg.order_id,
-                            leg.target,
-                            filled,
-                            is_open
+                            "[ORDER_PARAMS][EXIT] size adjusted by exchange for {}: requested={} ordered={}",
+                            pair.base,
+                            qty_a,
+                            res.ordered_size
                         );
                     }
-                    self.cancel_pending_orders(&pending).await?;
+                    legs.push(PendingLeg {
+                        symbol: pair.base.clone(),
+                        order_id: res.order_id.clone(),
+                        exchange_order_id: res.exchange_order_id.clone(),
+                        target: qty_a,
+                        filled: Decimal::ZERO,
+                        side: side_a,
+                        limit_price: None,
+                        placed_price: prices.get(&pair.base).map(|s| s.price),
+                    });
+                    res_a = Some(res);
                 }
-                let filled_qtys = self.filled_by_leg(&pending, &status.fills);
-                let mut flattened_any = false;
-                let mut hedge_failed = false;
-                let mut retry_count = pending.hedge_retry_count;
-                let max_retries = 3u32;
-                for leg in &pending.legs {
-                    let filled = filled_qtys
-                        .get(&leg.order_id)
-                        .cloned()
-                        .unwrap_or(Decimal::ZERO);
-                    if filled > Decimal::ZERO {
-                        if price_map.contains_key(&leg.symbol) {
-                            let hedge_side = match leg.side {
-                                dex_connector::OrderSide::Long => dex_connector::OrderSide::Short,
-                                dex_connector::OrderSide::Short => dex_connector::OrderSide::Long,
-                            };
-                            let use_market = retry_count + 1 >= max_retries;
-                            let limit = if use_market {
-                                None
-                            } else {
-                                self.limit_price_for(&leg.symbol, hedge_side, price_map)
-                            };
-                            if !use_market && limit.is_none() {
-                                log::warn!(
-                                    "[ORDER] Missing reference price for hedge {} leg {}",
-                                    leg.symbol,
-                                    leg.order_id
-                                );
-                                hedge_failed = true;
-                                continue;
-                            }
-                            let spread = self.order_spread_param(limit, false);
-                            if let Err(e) = self
-                                .connector
-                                .create_order(
-                                    &leg.symbol,
-                                    filled,
-                                    hedge_side,
-                                    limit,
-                                    spread,
-                                    true,
-                                    None,
-                                )
-                                .await
-                            {
-                                log::error!(
-                                    "[ORDER] Failed to hedge partial entry {} ({}): {:?}",
-                                    leg.symbol,
-                                    leg.order_id,
-                                    e
-                                );
-                                hedge_failed = true;
-                            } else {
-                                flattened_any = true;
-                                let mode = if use_market { "MARKET" } else { "LIMIT" };
-                                log::warn!(
-                                    "[ORDER] Hedged partial entry on {} size={} mode={} retries={}",
-                                    leg.symbol,
-                                    filled,
-                                    mode,
-                                    retry_count
-                                );
-                            }
-                        } else {
-                            log::warn!(
-                                "[ORDER] Missing price map entry for hedge {} leg {}",
-                                leg.symbol,
-                                leg.order_id
+                Err(err) => {
+                    if Self::is_reduce_only_position_missing_error(&err) {
+                        let symbol = pair.base.clone();
+                        if self.confirm_reduce_only_position_missing(&symbol).await {
+                            log::info!(
+                                "[ORDER] {} reduce-only close skipped; position already closed",
+                                symbol
                             );
-                            hedge_failed = true;
+                        } else {
+                            return Err(err).context("close leg A");
                         }
+                    } else {
+                        return Err(err).context("close leg A");
                     }
                 }
-                if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
-                    if hedge_failed {
-                        retry_count = retry_count.saturating_add(1);
-                        pending.hedge_retry_count = retry_count;
-                        log::warn!(
-                            "[ORDER] Hedge retry scheduled for {} (retry {} of {})",
-                            key,
-                            retry_count,
-                            max_retries
-                        );
-                        pending.placed_at = Instant::now();
-                        state.pending_entry = Some(pending);
+            }
+        }
+
+        if qty_b > Decimal::ZERO {
+            let res_b = if use_market {
+                self.connector
+                    .create_order(&pair.quote, qty_b, side_b, None, None, true, None)
+                    .await
+            } else {
+                self.create_order_with_post_only_retry(
+                    &pair.quote,
+                    qty_b,
+                    side_b,
+                    true,
+                    prices,
+                    true,
+                    POST_ONLY_EXIT_ATTEMPTS,
+                    fallback,
+                )
+                .await
+            };
+            let res_b = match res_b {
+                Ok(res) => {
+                    self.note_ordered_size(&pair.quote, qty_b, res.ordered_size);
+                    Some(res)
+                }
+                Err(e) => {
+                    let mut skip = false;
+                    if Self::is_reduce_only_position_missing_error(&e) {
+                        let symbol = pair.quote.clone();
+                        if self.confirm_reduce_only_position_missing(&symbol).await {
+                            log::info!(
+                                "[ORDER] {} reduce-only close skipped; position already closed",
+                                symbol
+                            );
+                            skip = true;
+                        }
+                    }
+                    if skip {
+                        None
                     } else {
-                        state.last_exit_at = Some(Instant::now());
-                        state.last_exit_ts = Some(now_ts);
-                        state.pending_entry = None;
-                        if flattened_any {
-                            state.position = None;
+                        if let Some(ref res_a) = res_a {
+                            self.recover_from_leg_b_failure(pair, res_a, side_a, &e).await;
+                        } else {
+                            log::error!(
+                                "[ORDER] Failed to close leg B for {}/{}: {:?}",
+                                pair.base,
+                                pair.quote,
+                                e
+                            );
                         }
+
+                        return Err(PartialOrderPlacementError::new(legs.clone(), e).into());
                     }
                 }
-            } else if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
-                state.pending_entry = Some(pending);
+            };
+            if let Some(res_b) = res_b {
+                if res_b.ordered_size > Decimal::ZERO && res_b.ordered_size != qty_b {
+                    log::debug!(
+                        "[ORDER_PARAMS][EXIT] size adjusted by exchange for {}: requested={} ordered={}",
+                        pair.quote,
+                        qty_b,
+                        res_b.ordered_size
+                    );
+                }
+                legs.push(PendingLeg {
+                    symbol: pair.quote.clone(),
+                    order_id: res_b.order_id.clone(),
+                    exchange_order_id: res_b.exchange_order_id.clone(),
+                    target: qty_b,
+                    filled: Decimal::ZERO,
+                    side: side_b,
+                    limit_price: None,
+                    placed_price: prices.get(&pair.quote).map(|s| s.price),
+                });
             }
         }
 
-        if let Some(pending) = pending_exit {
-            let status = self.pending_status(&pending).await?;
-            let mut pending = pending;
-            self.update_pending_fills(&mut pending, &status.fills);
-            let filled_qtys = self.filled_by_leg(&pending, &status.fills);
-            let mut pnl_record: Option<(PnlLogRecord, f64)> = None;
-            if status.open_remaining == 0 && self.all_filled(&pending, &status.fills) {
-                if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
-                    if let Some(pos) = state.position.as_ref() {
-                        if let Some((base, quote)) = key.split_once('/') {
-                            if let (Some(p1), Some(p2)) =
-                                (price_map.get(base), price_map.get(quote))
-                            {
-                                if let Some(pnl) =
-                                    compute_pnl(pos, p1.price, p2.price).and_then(|p| p.to_f64())
-                                {
-                                    let hold_secs = Some(
-                                        now_ts.saturating_sub(pos.entered_ts).max(0) as f64,
-                                    );
-                                    let entry_a = pos.entry_price_a.and_then(|v| v.to_f64());
-                                    let entry_b = pos.entry_price_b.and_then(|v| v.to_f64());
-                                    let z_exit = state.z_score().map(|(z, _)| z);
-                                    let beta_val = Some(state.beta);
-                                    pnl_record = Some((
-                                        PnlLogRecord::new(
-                                            base,
-                                            quote,
-                                            pos.direction,
-                                            pnl,
-                                            now_ts,
-                                            "exit_fill",
-                                        ).with_trade_details(
-                                            entry_a, entry_b,
-                                            p1.price.to_f64(), p2.price.to_f64(),
-                                            beta_val,
-                                            pos.entry_z,
-                                            z_exit,
-                                            hold_secs,
-                                        ),
-                                        pnl,
-                                    ));
-                                }
-                            }
-                        }
-                    }
-                    state.position = None;
-                    state.last_exit_at = Some(Instant::now());
-                    state.last_exit_ts = Some(now_ts);
-                    state.pending_exit = None;
+        if legs.is_empty() {
+            log::warn!(
+                "[ORDER] No exit legs placed for {}/{} (qty_a={}, qty_b={})",
+                pair.base,
+                pair.quote,
+                qty_a,
+                qty_b
+            );
+        }
+        Ok(legs)
+    }
+
+    fn register_partial_leg_failure(
+        &mut self,
+        inst_idx: usize,
+        key: &str,
+        direction: PositionDirection,
+        err: &anyhow::Error,
+        is_exit: bool,
+    ) {
+        if let Some(partial) = err.downcast_ref::<PartialOrderPlacementError>() {
+            if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
+                let pending = PendingOrders {
+                    legs: partial.legs().to_vec(),
+                    direction,
+                    placed_at: self.clock.now(),
+                    hedge_retry_count: 0,
+                    post_only_hybrid: false,
+                    partial_exit: false,
+                    scale_in: false,
+                };
+                if is_exit {
+                    state.pending_exit = Some(pending);
+                } else {
+                    state.pending_entry = Some(pending);
                 }
-                log::info!("[ORDER] {} exit orders filled", key);
-                if let Some((record, pnl_value)) = pnl_record {
-                    self.write_pnl_record(inst_idx, record);
-                    self.instances[inst_idx].realized_pnl_today += pnl_value;
-                    let mut risk_state_dirty = pnl_value != 0.0;
-                    if pnl_value < 0.0 {
-                        self.instances[inst_idx].consecutive_losses += 1;
-                        risk_state_dirty = true;
-                        if let Some(cooldown) = self
-                            .cfg
-                            .circuit_breaker_cooldown_for(self.instances[inst_idx].consecutive_losses)
-                        {
-                            self.instances[inst_idx].circuit_breaker_until = Some(Instant::now() + cooldown);
-                            self.instances[inst_idx].circuit_breaker_until_ts =
-                                Some(now_ts + cooldown.as_secs() as i64);
-                            log::warn!(
-                                "[CIRCUIT_BREAKER] activated after {} consecutive losses, cooldown {}s",
-                                self.instances[inst_idx].consecutive_losses, cooldown.as_secs()
-                            );
-                        }
-                    } else if pnl_value > 0.0 {
-                        if self.instances[inst_idx].consecutive_losses > 0 {
-                            log::info!(
-                                "[CIRCUIT_BREAKER] reset after win (was {} consecutive losses)",
-                                self.instances[inst_idx].consecutive_losses
-                            );
-                            risk_state_dirty = true;
-                        }
-                        self.instances[inst_idx].consecutive_losses = 0;
-                        self.instances[inst_idx].circuit_breaker_until = None;
-                        self.instances[inst_idx].circuit_breaker_until_ts = None;
-                    }
-                    if risk_state_dirty {
-                        self.persist_risk_state();
+            }
+        }
+    }
+
+    /// Trade prices per symbol for `bar_source = trades`, in the order the
+    /// venue returns them. Prints carry no timestamp or id, so each symbol's
+    /// connector buffer is drained with `clear_last_trades` once read; the
+    /// next cycle then only sees new prints instead of folding the same
+    /// ones again. Symbols whose fetch fails or returns no trades are left
+    /// out so the caller falls back to the snapshot price.
+    async fn fetch_trade_ticks<'a>(
+        &self,
+        symbols: impl Iterator<Item = &'a String>,
+    ) -> HashMap<String, Vec<Decimal>> {
+        let mut join_set = tokio::task::JoinSet::new();
+        for sym in symbols.cloned() {
+            let conn = self.connector.clone();
+            join_set.spawn(async move {
+                let res = conn.get_last_trades(&sym).await;
+                if res.as_ref().is_ok_and(|resp| !resp.trades.is_empty()) {
+                    if let Err(e) = conn.clear_last_trades(&sym).await {
+                        log::debug!("clear trades {} failed: {:?}", sym, e);
                     }
                 }
-            } else if filled_qtys.values().any(|qty| *qty > Decimal::ZERO) {
-                let next_retry = pending.hedge_retry_count.saturating_add(1);
-                if next_retry > MAX_EXIT_RETRIES {
-                    self.force_close_all_positions(key, "partial_fill").await;
-                    if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
-                        state.pending_exit = None;
+                (sym, res)
+            });
+        }
+        let mut ticks = HashMap::new();
+        while let Some(res) = join_set.join_next().await {
+            let (symbol, res) = match res {
+                Ok(joined) => joined,
+                Err(e) => {
+                    log::warn!("trade fetch task failed: {:?}", e);
+                    continue;
+                }
+            };
+            match res {
+                Ok(resp) => {
+                    let prices: Vec<Decimal> = resp
+                        .trades
+                        .iter()
+                        .map(|t| t.price)
+                        .filter(|p| *p > Decimal::ZERO)
+                        .collect();
+                    if !prices.is_empty() {
+                        ticks.insert(symbol, prices);
                     }
-                    return Ok(());
                 }
-                log::info!(
-                    "[ORDER] {} exit leg partially filled, reissuing remaining legs",
-                    key
+                Err(e) => log::debug!("trades {} unavailable: {:?}", symbol, e),
+            }
+        }
+        ticks
+    }
+
+    async fn fetch_latest_prices(&mut self) -> Result<HashMap<String, SymbolSnapshot>> {
+        let symbols: Vec<String> = self
+            .cfg
+            .universe
+            .iter()
+            .flat_map(|p| [p.base.clone(), p.quote.clone()])
+            .collect::<std::collections::HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        let connector = self.connector.clone();
+        let depth = self.cfg.orderbook_depth;
+        let auth_retries = self.cfg.ticker_auth_retries;
+        let auth_retry_delay = Duration::from_millis(self.cfg.ticker_auth_retry_ms);
+        let mut join_set = tokio::task::JoinSet::new();
+        for sym in symbols.iter().cloned() {
+            let conn = connector.clone();
+            join_set.spawn(async move {
+                let (mut ticker_res, ob_res) = tokio::join!(
+                    conn.get_ticker(&sym, None),
+                    conn.get_order_book(&sym, depth),
                 );
-                self.cancel_pending_orders(&pending).await?;
-                if let Some(new_pending) = self
-                    .reissue_partial_legs(&pending, &filled_qtys, price_map, true, true, next_retry)
-                    .await?
+                // Auth blips (token refresh) often clear on the next call.
+                let mut attempt = 0;
+                while attempt < auth_retries
+                    && ticker_res
+                        .as_ref()
+                        .err()
+                        .is_some_and(|e| Self::is_ticker_auth_error(&e.to_string()))
                 {
-                    if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
-                        state.pending_exit = Some(new_pending);
-                    }
-                } else if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
-                    state.pending_exit = None;
-                }
-                return Ok(());
-            } else if pending.placed_at.elapsed() >= timeout || status.open_remaining == 0 {
-                let next_retry = pending.hedge_retry_count.saturating_add(1);
-                if next_retry > MAX_EXIT_RETRIES {
-                    self.force_close_all_positions(key, "timeout").await;
-                    if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
-                        state.pending_exit = None;
-                    }
-                    return Ok(());
-                }
-                if status.open_remaining > 0 {
-                    log::warn!(
-                        "[ORDER] {} exit orders stale ({}s), cancelling {} legs",
-                        key,
-                        pending.placed_at.elapsed().as_secs(),
-                        status.open_remaining
-                    );
-                    for leg in &pending.legs {
-                        let filled = filled_qtys
-                            .get(&leg.order_id)
-                            .cloned()
-                            .unwrap_or(Decimal::ZERO);
-                        let is_open = status.open_ids.contains(&leg.order_id);
-                        log::debug!(
-                            "[ORDER] {} exit leg status symbol={} order_id={} target={} filled={} open={}",
-                            key,
-                            leg.symbol,
-                            leg.order_id,
-                            leg.target,
-                            filled,
-                            is_open
-                        );
-                    }
-                    self.cancel_pending_orders(&pending).await?;
+                    attempt += 1;
+                    log::debug!("ticker {} auth error, retry {}/{}", sym, attempt, auth_retries);
+                    sleep(auth_retry_delay).await;
+                    ticker_res = conn.get_ticker(&sym, None).await;
                 }
-                // Re-attempt closing missing legs based on filled qty
-                // reusing filled_qtys defined earlier
-                let mut new_legs = Vec::new();
-                for leg in &pending.legs {
-                    let filled = filled_qtys
-                        .get(&leg.order_id)
-                        .cloned()
-                        .unwrap_or(Decimal::ZERO);
-                    let remaining_qty = (leg.target - filled).max(Decimal::ZERO);
-                    if remaining_qty > Decimal::ZERO {
-                        let quantized =
-                            self.quantize_order_size_exit(&leg.symbol, remaining_qty, price_map);
-                        if quantized <= Decimal::ZERO {
-                            continue;
-                        }
-                        let limit = None;
-                        match self
-                            .connector
-                            .create_order(&leg.symbol, quantized, leg.side, limit, None, true, None)
-                            .await
-                        {
-                            Ok(resp) => {
-                                new_legs.push(PendingLeg {
-                                    symbol: leg.symbol.clone(),
-                                    order_id: resp.order_id,
-                                    exchange_order_id: resp.exchange_order_id,
-                                    target: quantized,
-                                    filled: Decimal::ZERO,
-                                    side: leg.side,
-                                    limit_price: None,
-                                });
-                                log::warn!(
-                                    "[ORDER] Retrying exit leg {} size={} mode=MARKET",
-                                    leg.symbol,
-                                    quantized
-                                );
-                            }
-                            Err(e) => log::error!(
-                                "[ORDER] Failed to retry exit leg {}: {:?}",
-                                leg.symbol,
-                                e
-                            ),
+                (sym, ticker_res, ob_res)
+            });
+        }
+        let mut results = Vec::new();
+        while let Some(res) = join_set.join_next().await {
+            results.push(res.expect("fetch task panicked"));
+        }
+
+        let mut map = HashMap::new();
+        for (symbol, ticker_res, ob_res) in results {
+            let ticker = match ticker_res {
+                Ok(ticker) => ticker,
+                Err(e) => {
+                    let msg = e.to_string();
+                    if Self::is_ticker_auth_error(&msg) {
+                        if self.should_log_ticker_warn(&symbol) {
+                            log::warn!("ticker {} unavailable: {}", symbol, msg);
+                            self.last_ticker_warn.insert(symbol.clone(), self.clock.now());
+                        } else {
+                            log::debug!("ticker {} unavailable: {}", symbol, msg);
                         }
+                        continue;
                     }
+                    return Err(e).with_context(|| format!("ticker {}", symbol));
                 }
-                if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
-                    if new_legs.is_empty() {
-                        state.pending_exit = None;
-                        // Keep position state unchanged; will retry next loop
+            };
+            let (top_bid_price, top_ask_price, top_bid_size, top_ask_size, book_depth) = match ob_res {
+                Ok(ob) => (
+                    ob.bids.first().map(|l| l.price),
+                    ob.asks.first().map(|l| l.price),
+                    ob.bids.first().map(|l| l.size).unwrap_or(Decimal::ZERO),
+                    ob.asks.first().map(|l| l.size).unwrap_or(Decimal::ZERO),
+                    BookDepth::from_book(&ob, depth, self.cfg.orderbook_depth_band_bps),
+                ),
+                Err(e) => {
+                    let msg = format!("{:?}", e);
+                    let is_stale = msg.contains("order book snapshot unavailable");
+                    if is_stale {
+                        log::debug!("orderbook {} unavailable: {}", symbol, msg);
+                    } else if self.should_log_ob_warn(&symbol) {
+                        log::warn!("orderbook {} unavailable: {}", symbol, msg);
+                        self.last_ob_warn.insert(symbol.clone(), self.clock.now());
                     } else {
-                        state.pending_exit = Some(PendingOrders {
-                            legs: new_legs,
-                            direction: pending.direction,
-                            placed_at: Instant::now(),
-                            hedge_retry_count: next_retry,
-                            post_only_hybrid: false,
-                        });
+                        log::debug!("orderbook {} unavailable: {}", symbol, msg);
                     }
+                    (None, None, Decimal::ZERO, Decimal::ZERO, BookDepth::default())
                 }
-            } else if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
-                state.pending_exit = Some(pending);
+            };
+            if ticker.min_order.is_none() && !self.min_order_warned.contains(&symbol) {
+                let size_decimals_desc = ticker
+                    .size_decimals
+                    .map(|d| d.to_string())
+                    .unwrap_or_else(|| "none".into());
+                log::warn!(
+                    "[TICKER] {} missing min_order (size_decimals={}); using fallback step",
+                    symbol,
+                    size_decimals_desc
+                );
+                self.min_order_warned.insert(symbol.clone());
+            }
+            if ticker.min_tick.is_none() && !self.min_tick_warned.contains(&symbol) {
+                let min_tick_desc = ticker
+                    .min_tick
+                    .map(|t| t.to_string())
+                    .unwrap_or_else(|| "none".into());
+                log::warn!(
+                    "[TICKER] {} missing min_tick (ticker reports {}); price will be rounded with fallback",
+                    symbol,
+                    min_tick_desc
+                );
+                self.min_tick_warned.insert(symbol.clone());
             }
-        }
-
-        Ok(())
-    }
-
-    async fn cancel_pending_orders(&self, pending: &PendingOrders) -> Result<()> {
-        let mut by_symbol: HashMap<String, Vec<String>> = HashMap::new();
-        for leg in &pending.legs {
-            by_symbol
-                .entry(leg.symbol.clone())
-                .or_default()
-                .push(leg.order_id.clone());
-        }
-        for (symbol, order_ids) in by_symbol {
-            if let Err(e) = self
-                .connector
-                .cancel_orders(Some(symbol.clone()), order_ids.clone())
-                .await
-            {
-                log::error!(
-                    "[ORDER] cancel failed for {} ({} ids): {:?}",
+            map.insert(
+                symbol.clone(),
+                SymbolSnapshot {
+                    price: ticker.price,
+                    funding_rate: ticker.funding_rate.unwrap_or(Decimal::ZERO),
+                    bid_price: top_bid_price,
+                    ask_price: top_ask_price,
+                    bid_size: top_bid_size,
+                    ask_size: top_ask_size,
+                    min_order: ticker.min_order,
+                    min_tick: ticker.min_tick,
+                    size_decimals: ticker.size_decimals,
+                    exchange_ts: ticker.exchange_ts.map(|v| v as i64),
+                    depth: book_depth,
+                },
+            );
+            if self.log_sampled(
+                "price_snapshot",
+                &symbol,
+                self.cfg.log_price_snapshot_every_n_ticks,
+            ) {
+                log::debug!(
+                    "[PRICE_SNAPSHOT] {} price={} bid={:?} ask={:?} bid_sz={} ask_sz={} min_order={:?} min_tick={:?}",
                     symbol,
-                    order_ids.len(),
-                    e
+                    ticker.price,
+                    top_bid_price,
+                    top_ask_price,
+                    top_bid_size,
+                    top_ask_size,
+                    ticker.min_order,
+                    ticker.min_tick
                 );
             }
         }
-        Ok(())
+        Ok(map)
     }
+}
 
-    async fn pending_status(&self, pending: &PendingOrders) -> Result<PendingStatus> {
-        let mut open_remaining = 0;
-        let mut fills: HashMap<String, Decimal> = HashMap::new();
-        let mut open_ids: HashSet<String> = HashSet::new();
-        let mut per_symbol_open: HashMap<String, HashSet<String>> = HashMap::new();
-        let mut per_symbol_fill: HashMap<String, HashSet<String>> = HashMap::new();
-        for leg in &pending.legs {
-            per_symbol_open
-                .entry(leg.symbol.clone())
-                .or_default()
-                .insert(leg.order_id.clone());
-            let fill_ids = per_symbol_fill.entry(leg.symbol.clone()).or_default();
-            fill_ids.insert(leg.order_id.clone());
-            if let Some(exchange_id) = &leg.exchange_order_id {
-                fill_ids.insert(exchange_id.clone());
-            }
-        }
-        for (symbol, open_ids_filter) in per_symbol_open.iter() {
-            let fill_ids_filter = per_symbol_fill.get(symbol).cloned().unwrap_or_default();
-            let open = self
-                .connector
-                .get_open_orders(symbol)
-                .await
-                .with_context(|| format!("open orders {}", symbol))?;
-            let mut open_count = 0;
-            for order in open
-                .orders
-                .iter()
-                .filter(|o| open_ids_filter.contains(&o.order_id))
-            {
-                open_ids.insert(order.order_id.clone());
-                open_count += 1;
-            }
-            open_remaining += open_count;
+#[cfg(test)]
+impl PairTradeEngine {
+    fn test_instance(connector: Arc<dyn DexConnector + Send + Sync>) -> Self {
+        let cfg = PairTradeConfig {
+            dex_name: "test".to_string(),
+            rest_endpoint: "http://localhost".to_string(),
+            web_socket_endpoint: "ws://localhost".to_string(),
+            dry_run: true,
+            agent_name: None,
+            interval_secs: 1,
+            trading_period_secs: 1,
+            metrics_window: 1,
+            net_funding_min_per_hour: 0.0,
+            notional_per_leg: 1.0,
+            risk_pct_per_trade: 0.01,
+            equity_reference_usd: DEFAULT_EQUITY_USD,
+            universe: vec![PairSpec {
+                base: "AAA".to_string(),
+                quote: "BBB".to_string(),
+            }],
+            slippage_bps: 0,
+            fee_bps: 0.0,
+            max_leverage: 1.0,
+            max_active_pairs: 1,
+            warm_start_mode: WarmStartMode::Strict,
+            order_timeout_secs: DEFAULT_ORDER_TIMEOUT_SECS,
+            entry_partial_fill_max_retries: DEFAULT_ENTRY_PARTIAL_FILL_MAX_RETRIES,
+            startup_force_close_attempts: DEFAULT_STARTUP_FORCE_CLOSE_ATTEMPTS,
+            startup_force_close_wait_secs: DEFAULT_STARTUP_FORCE_CLOSE_WAIT_SECS,
+            force_close_on_startup: false,
+            enable_data_dump: false,
+            data_dump_file: None,
+            observe_only: false,
+            disable_history_persist: true,
+            history_file: "test-history.json".to_string(),
+            history_archive_dir: None,
+            history_archive_retention_days: 14,
+            backtest_mode: false,
+            backtest_file: None,
+            backtest_files: Vec::new(),
+            bt_warm_start_snapshot: None,
+            bt_eval_timestamps: None,
+            bt_restart_timestamps: None,
+            circuit_breaker_consecutive_losses: DEFAULT_CIRCUIT_BREAKER_CONSECUTIVE_LOSSES,
+            circuit_breaker_cooldown_secs: DEFAULT_CIRCUIT_BREAKER_COOLDOWN_SECS,
+            shutdown_grace_secs: 0,
+            pair_params: HashMap::new(),
+            default_pair_params: PairParams {
+                entry_z_base: 2.0,
+                entry_z_min: 1.8,
+                entry_z_max: 2.3,
+                exit_z: 0.5,
+                stop_loss_z: 3.0,
+                force_close_secs: 60,
+                cooldown_secs: 1,
+                stop_loss_cooldown_secs: None,
+                max_loss_r_mult: 1.0,
+                half_life_max_hours: 1.0,
+                adf_p_threshold: 0.05,
+                spread_velocity_max_sigma_per_min: 0.1,
+                lookback_hours_short: 1,
+                lookback_hours_long: 1,
+                entry_vol_lookback_hours: 1,
+                warm_start_min_bars: 1,
+                hedge_ratio_max_deviation: 1.0,
+                ..PairParams::default()
+            },
+            strategies: Vec::new(),
+            use_kalman_beta: DEFAULT_USE_KALMAN_BETA,
+            kalman_q: DEFAULT_KALMAN_Q,
+            kalman_r: DEFAULT_KALMAN_R,
+            kalman_initial_p: DEFAULT_KALMAN_INITIAL_P,
+            kalman_min_updates: DEFAULT_KALMAN_MIN_UPDATES,
+            regime_vol_window: DEFAULT_REGIME_VOL_WINDOW,
+            regime_vol_max: DEFAULT_REGIME_VOL_MAX,
+            regime_trend_window: DEFAULT_REGIME_TREND_WINDOW,
+            regime_trend_max: DEFAULT_REGIME_TREND_MAX,
+            regime_reference_symbol: DEFAULT_REGIME_REFERENCE_SYMBOL.to_string(),
+            bt_fill_delay_secs: 0,
+            risk: config::RiskConfig::default(),
+            min_equity_usd: DEFAULT_MIN_EQUITY_USD,
+            max_balance_fetch_failures: DEFAULT_MAX_BALANCE_FETCH_FAILURES,
+            align_bar_clock: DEFAULT_ALIGN_BAR_CLOCK,
+            history_price_format: HistoryPriceFormat::LogF64,
+            avoid_funding_window_secs: DEFAULT_AVOID_FUNDING_WINDOW_SECS,
+            funding_interval_secs: DEFAULT_FUNDING_INTERVAL_SECS,
+            close_profitable_before_funding: DEFAULT_CLOSE_PROFITABLE_BEFORE_FUNDING,
+            vol_target_sigma: DEFAULT_VOL_TARGET_SIGMA,
+            vol_target_min_notional: DEFAULT_VOL_TARGET_MIN_NOTIONAL,
+            vol_target_max_notional: DEFAULT_VOL_TARGET_MAX_NOTIONAL,
+            reconcile_concurrency: DEFAULT_RECONCILE_CONCURRENCY,
+            data_dump_compress: false,
+            trade_stats_reset: TradeStatsReset::Session,
+            spread_smoothing_bars: 0,
+            max_spread_gap_bars: 0,
+            dust_sweep: false,
+            beta_on_returns: false,
+            orderbook_depth: 1,
+            orderbook_depth_band_bps: 10.0,
+            exit_z_require_profit: false,
+            startup_force_close_backoff_mult: 1.0,
+            startup_force_close_max_wait_secs: 60,
+            startup_force_close_jitter_pct: 0.0,
+            startup_cancel_wait_secs: 0,
+            observe_simulate_pnl: false,
+            auto_flatten_mismatched_legs: false,
+            z_price_source: PriceSource::Last,
+            no_trade_windows: Vec::new(),
+            forensic_dump_dir: None,
+            forensic_dump_keep: 20,
+            min_book_notional_usd: 0.0,
+            hedge_ratio_tolerance_pct: 0.0,
+            status_price_dp: None,
+            status_pnl_dp: None,
+            ticker_auth_retries: 0,
+            ticker_auth_retry_ms: 250,
+            normalize_legs: false,
+            max_reissues_per_pair: 20,
+            max_reissued_legs_per_cycle: DEFAULT_MAX_REISSUED_LEGS_PER_CYCLE,
+            bar_source: BarSource::Snapshot,
+            pending_max_age_secs: 1800,
+            equity_baseline_samples: 1,
+            history_backend: HistoryBackend::Json,
+            allow_negative_beta: false,
+            pair_idle_alert_secs: 0,
+            pair_idle_notify: false,
+            max_min_order_oversizing_factor: 0.0,
+            position_audit_interval_secs: 0,
+            position_audit_force_close: false,
+            exit_post_only_wait_reasons: Vec::new(),
+            seed_new_pairs_from_history: false,
+            max_abs_funding_per_hour: 0.0,
+            matching_model: MatchingModel::Full,
+            max_orders_per_symbol_per_min: DEFAULT_MAX_ORDERS_PER_SYMBOL_PER_MIN,
+            backtest_apply_costs: DEFAULT_BACKTEST_APPLY_COSTS,
+            pair_eval_interval_secs: DEFAULT_PAIR_EVAL_INTERVAL_SECS,
+            strict_universe_validation: DEFAULT_STRICT_UNIVERSE_VALIDATION,
+            skip_unchanged_bars: DEFAULT_SKIP_UNCHANGED_BARS,
+            max_realized_slippage_bps: DEFAULT_MAX_REALIZED_SLIPPAGE_BPS,
+            realized_slippage_block_secs: DEFAULT_REALIZED_SLIPPAGE_BLOCK_SECS,
+            persist_trade_stats: false,
+            backtest_full_sync: DEFAULT_BACKTEST_FULL_SYNC,
+            log_price_snapshot_every_n_ticks: DEFAULT_LOG_PRICE_SNAPSHOT_EVERY_N_TICKS,
+            log_zcheck_every_n_ticks: DEFAULT_LOG_ZCHECK_EVERY_N_TICKS,
+            log_order_params_every_n_ticks: DEFAULT_LOG_ORDER_PARAMS_EVERY_N_TICKS,
+            beta_drift_exit_pct: DEFAULT_BETA_DRIFT_EXIT_PCT,
+            beta_drift_action: BetaDriftAction::Exit,
+            z_stat_mode: ZStatMode::Simple,
+            max_open_orders: DEFAULT_MAX_OPEN_ORDERS,
+            entry_max_book_spread_bps: DEFAULT_ENTRY_MAX_BOOK_SPREAD_BPS,
+            eligibility_max_age_secs: DEFAULT_ELIGIBILITY_MAX_AGE_SECS,
+            reconcile_fill_pnl: DEFAULT_RECONCILE_FILL_PNL,
+            dust_notional_usd: DEFAULT_DUST_NOTIONAL_USD,
+            dust_notional_usd_by_symbol: HashMap::new(),
+            exit_market_reasons: DEFAULT_EXIT_MARKET_REASONS
+                .split(',')
+                .map(str::to_string)
+                .collect(),
+            position_age_warn_secs: DEFAULT_POSITION_AGE_WARN_SECS,
+            directional_eligibility: DEFAULT_DIRECTIONAL_ELIGIBILITY,
+            max_bar_span_mult: DEFAULT_MAX_BAR_SPAN_MULT,
+            min_interval_secs: DEFAULT_MIN_INTERVAL_SECS,
+            max_interval_secs: DEFAULT_MAX_INTERVAL_SECS,
+            min_hold_secs: DEFAULT_MIN_HOLD_SECS,
+            eval_history_file: None,
+            partial_take_profit_fraction: DEFAULT_PARTIAL_TAKE_PROFIT_FRACTION,
+            partial_take_profit_r: DEFAULT_PARTIAL_TAKE_PROFIT_R,
+            status_size_adjustments: DEFAULT_STATUS_SIZE_ADJUSTMENTS,
+            max_equity_fallback_cycles: DEFAULT_MAX_EQUITY_FALLBACK_CYCLES,
+            min_spread_history_points: DEFAULT_MIN_SPREAD_HISTORY_POINTS,
+            min_symbol_history_bars: DEFAULT_MIN_SYMBOL_HISTORY_BARS,
+            shared_symbol_policy: SharedSymbolPolicy::Block,
+            max_net_symbol_exposure_usd: DEFAULT_MAX_NET_SYMBOL_EXPOSURE_USD,
+            metrics_log_interval_secs: DEFAULT_METRICS_LOG_INTERVAL_SECS,
+            metrics_log_format: MetricsLogFormat::Text,
+            metrics_log_detail: DEFAULT_METRICS_LOG_DETAIL,
+            portfolio_funding_aware: DEFAULT_PORTFOLIO_FUNDING_AWARE,
+            preserve_symbol_order: DEFAULT_PRESERVE_SYMBOL_ORDER,
+            single_position_mode: DEFAULT_SINGLE_POSITION_MODE,
+            reject_crossed_book: DEFAULT_REJECT_CROSSED_BOOK,
+            equity_history_detailed: DEFAULT_EQUITY_HISTORY_DETAILED,
+            auto_restart_after_failures: DEFAULT_AUTO_RESTART_AFTER_FAILURES,
+            auto_restart_cooldown_secs: DEFAULT_AUTO_RESTART_COOLDOWN_SECS,
+            maker_fee_bps_by_symbol: HashMap::new(),
+            taker_fee_bps_by_symbol: HashMap::new(),
+            max_bar_log_return: DEFAULT_MAX_BAR_LOG_RETURN,
+            scale_in_enabled: DEFAULT_SCALE_IN_ENABLED,
+            scale_in_add_z: DEFAULT_SCALE_IN_ADD_Z,
+            scale_in_fraction: DEFAULT_SCALE_IN_FRACTION,
+            non_finite_stats_block_secs: DEFAULT_NON_FINITE_STATS_BLOCK_SECS,
+            entry_market_mode: DEFAULT_ENTRY_MARKET_MODE,
+            rejected_entries_file: None,
+            min_eligible_pairs: DEFAULT_MIN_ELIGIBLE_PAIRS,
+            plan_trace_file: None,
+            loss_reentry_block_ticks: DEFAULT_LOSS_REENTRY_BLOCK_TICKS,
+            missed_tick_behavior: MissedTickBehavior::Skip,
+            delta_rebalance_enabled: DEFAULT_DELTA_REBALANCE_ENABLED,
+            rebalance_threshold_usd: DEFAULT_REBALANCE_THRESHOLD_USD,
+            delta_rebalance_interval_secs: DEFAULT_DELTA_REBALANCE_INTERVAL_SECS,
+            recover_entry_time_from_risk_state: DEFAULT_RECOVER_ENTRY_TIME_FROM_RISK_STATE,
+        };
 
-            let filled = self
-                .connector
-                .get_filled_orders(symbol)
-                .await
-                .with_context(|| format!("filled orders {}", symbol))?;
-            for order in filled.orders {
-                if fill_ids_filter.contains(&order.order_id) {
-                    let sz = order.filled_size.unwrap_or(Decimal::ZERO);
-                    *fills.entry(order.order_id.clone()).or_default() += sz;
-                    log::debug!(
-                        "[ORDER][FILLED] symbol={} order_id={} side={:?} size={} value={:?} fee={:?} trade_id={}",
-                        symbol,
-                        order.order_id,
-                        order.filled_side,
-                        sz,
-                        order.filled_value,
-                        order.filled_fee,
-                        order.trade_id
-                    );
-                }
-            }
-            log::debug!(
-                "[ORDER][PENDING_STATUS] symbol={} open_orders={} tracked_orders={} filled_entries={}",
-                symbol,
-                open_count,
-                open_ids_filter.len(),
-                fills.len()
-            );
+        let history_path = PathBuf::from(cfg.history_file.as_str());
+        let history_store =
+            history_store::from_config(&cfg, &history_path, max_history_len_for(&cfg));
+        // Entries persist the risk state; keep each engine's file out of the
+        // tree and apart from other tests.
+        let test_state_dir = tempfile::tempdir().unwrap();
+        let risk_state_path = test_state_dir.path().join("risk_state.json");
+        let connector_symbols: HashSet<String> = cfg
+            .universe
+            .iter()
+            .flat_map(|p| [p.base.clone(), p.quote.clone()])
+            .collect();
+
+        Self {
+            cfg,
+            connector: connector.clone(),
+            instances: vec![StrategyInstance {
+                id: "default".to_string(),
+                connector,
+                equity_cache: DEFAULT_EQUITY_USD,
+                last_equity_fetch: None,
+                balance_fetch_failures: 0,
+                equity_fallback_cycles: 0,
+                equity_guard_halted: false,
+                margin_block_until_ts: None,
+                open_order_count: 0,
+                open_orders_blocked: false,
+                last_open_order_audit: None,
+                last_delta_rebalance: None,
+                delta_rebalance_orders: Vec::new(),
+                equity_reference_usd: DEFAULT_EQUITY_USD,
+                states: HashMap::new(),
+                pnl_logger: None,
+                status_reporter: None,
+                consecutive_losses: 0,
+                circuit_breaker_until: None,
+                circuit_breaker_until_ts: None,
+                session_start_equity: 0.0,
+                session_start_ts: 0,
+                realized_pnl_today: 0.0,
+                daily_loss_halted: false,
+                equity_samples: Vec::new(),
+                session_halted: false,
+                session_halt_reason: None,
+                session_halt_ts: None,
+                persisted_entry_ts: HashMap::new(),
+                total_trades: 0,
+                total_wins: 0,
+                total_pnl: 0.0,
+                peak_pnl: 0.0,
+                max_dd: 0.0,
+                trade_stats: TradeStatsBook::default(),
+                last_select_log: None,
+                pair_params: HashMap::new(),
+                default_pair_params: PairParams::default(),
+            }],
+            history: HashMap::new(),
+            bar_builders: HashMap::new(),
+            last_metrics_log: None,
+            last_ob_warn: HashMap::new(),
+            last_ticker_warn: HashMap::new(),
+            last_position_warn: HashMap::new(),
+            one_sided_bar_pairs: HashSet::new(),
+            outlier_bar_streaks: HashMap::new(),
+            log_sample_counts: HashMap::new(),
+            min_order_warned: HashSet::new(),
+            min_tick_warned: HashSet::new(),
+            positions_ready: false,
+            open_positions: HashMap::new(),
+            last_account_rest_call: None,
+            connector_failures: 0,
+            last_connector_restart: None,
+            history_store,
+            risk_state_path,
+            _test_state_dir: Some(test_state_dir),
+            kill_switch_active: false,
+            data_dump_writer: None,
+            replay_connector: None,
+            shutdown_pending: false,
+            retiring_pairs: HashSet::new(),
+            connector_symbols,
+            funding_schedule_warned: false,
+            dust_sweep_attempted: HashSet::new(),
+            no_trade_window: None,
+            clock: Arc::new(SystemClock),
+            reissued_legs_this_cycle: 0,
+            last_position_audit: None,
+            position_audit_strikes: 0,
+            taker_fallbacks: BTreeMap::new(),
+            size_adjustments: BTreeMap::new(),
+            realized_slippage: HashMap::new(),
+            slippage_blocked: HashMap::new(),
         }
-        Ok(PendingStatus {
-            open_remaining,
-            fills,
-            open_ids,
-        })
     }
+}
 
-    fn leg_fill_from_map(&self, leg: &PendingLeg, fills: &HashMap<String, Decimal>) -> Decimal {
-        fills
-            .get(&leg.order_id)
-            .cloned()
-            .or_else(|| {
-                leg.exchange_order_id
-                    .as_ref()
-                    .and_then(|id| fills.get(id).cloned())
-            })
-            .unwrap_or(Decimal::ZERO)
+/// Signed size and entry price of each leg of a paper position.
+fn paper_legs<'a>(
+    pair: &'a PairSpec,
+    position: &Position,
+) -> Vec<(&'a String, Decimal, Option<Decimal>)> {
+    let (side_a, side_b) = position.direction.entry_sides(position.hedge_inverted);
+    [
+        (&pair.base, side_a, position.entry_size_a, position.entry_price_a),
+        (&pair.quote, side_b, position.entry_size_b, position.entry_price_b),
+    ]
+    .into_iter()
+    .filter_map(|(symbol, side, size, price)| {
+        let size = size?;
+        let signed = match side {
+            dex_connector::OrderSide::Long => size,
+            dex_connector::OrderSide::Short => -size,
+        };
+        Some((symbol, signed, price))
+    })
+    .collect()
+}
+
+/// `beta` as a log-price hedge ratio for sizing. Under `normalize_legs` it
+/// relates legs standardized over `metrics_window`, so it is scaled back by
+/// the ratio of their `standardize` divisors over that window.
+fn sizing_beta(
+    cfg: &PairTradeConfig,
+    history: &HashMap<String, VecDeque<PriceSample>>,
+    base: &str,
+    quote: &str,
+    beta: f64,
+) -> f64 {
+    if !cfg.normalize_legs {
+        return beta;
+    }
+    let scale = |symbol: &str| {
+        history
+            .get(symbol)
+            .and_then(|h| standardize_scale(h, cfg.metrics_window))
+    };
+    match (scale(base), scale(quote)) {
+        (Some(scale_a), Some(scale_b)) => beta * scale_a / scale_b,
+        _ => beta,
+    }
+}
+
+/// What the legs of `pending` on `symbol` filled, per `filled_qtys`.
+fn filled_for_symbol(
+    pending: &PendingOrders,
+    filled_qtys: &HashMap<String, Decimal>,
+    symbol: &str,
+) -> Decimal {
+    pending
+        .legs
+        .iter()
+        .filter(|leg| leg.symbol == symbol)
+        .filter_map(|leg| filled_qtys.get(&leg.order_id))
+        .copied()
+        .sum()
+}
+
+fn paper_snapshot(
+    symbol: String,
+    signed: Decimal,
+    entry_price: Option<Decimal>,
+) -> Option<PositionSnapshot> {
+    if signed.is_zero() {
+        return None;
+    }
+    let mut snapshot = PositionSnapshot {
+        symbol,
+        size: signed.abs(),
+        sign: if signed > Decimal::ZERO { 1 } else { -1 },
+        ..Default::default()
+    };
+    snapshot.entry_price = entry_price;
+    Some(snapshot)
+}
+
+/// Bars of history kept per symbol: the longest lookback of any pair's
+/// params, and at least `metrics_window`. Free function so it can size
+/// the history before the engine exists.
+fn max_history_len_for(cfg: &PairTradeConfig) -> usize {
+    let mut max_needed = 0usize;
+    // Consider all per-pair params and the default
+    let all_params = std::iter::once(&cfg.default_pair_params).chain(cfg.pair_params.values());
+    for pp in all_params {
+        let max_hrs = pp.lookback_hours_long.max(pp.lookback_hours_short);
+        let needed = (max_hrs * 3600 / cfg.trading_period_secs) as usize;
+        let vol_needed =
+            ((pp.entry_vol_lookback_hours * 3600) / cfg.trading_period_secs).max(1) as usize;
+        max_needed = max_needed.max(needed).max(vol_needed);
+    }
+    max_needed.max(cfg.metrics_window)
+}
+
+/// Compute `risk_state.json` path as a sibling of the history file. When
+/// `history_path` is a bare filename (the production default), this falls
+/// back to a relative path resolved against CWD — which is `/opt/debot/`
+/// under the systemd unit.
+fn risk_state_path_for(history_path: &std::path::Path) -> PathBuf {
+    match history_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        Some(dir) => dir.join("risk_state.json"),
+        None => PathBuf::from("risk_state.json"),
+    }
+}
+
+/// Bucket a UNIX-seconds timestamp into a "session day" where day
+/// boundaries fall at `reset_hour:00` UTC instead of midnight. Two
+/// timestamps share a session day iff the function returns the same
+/// value for both. `div_euclid` keeps the math correct for non-zero
+/// reset hours near pre-epoch timestamps (impossible in practice but
+/// cheap to preserve). See bot-strategy#185 Phase 2.
+fn session_day(ts_secs: i64, reset_hour: u32) -> i64 {
+    let shift = (reset_hour as i64) * 3600;
+    (ts_secs - shift).div_euclid(86400)
+}
+
+/// Open-order count and per-order fills for a set of pending legs. Free
+/// function so `prefetch_pending_statuses` can run it on spawned tasks.
+async fn fetch_pending_status(
+    connector: &Arc<dyn DexConnector + Send + Sync>,
+    legs: &[PendingLeg],
+) -> Result<PendingStatus> {
+    let mut open_remaining = 0;
+    let mut fills: HashMap<String, Decimal> = HashMap::new();
+    let mut fill_values: HashMap<String, Decimal> = HashMap::new();
+    let mut fill_fees: HashMap<String, Decimal> = HashMap::new();
+    let mut open_ids: HashSet<String> = HashSet::new();
+    let mut per_symbol_open: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut per_symbol_fill: HashMap<String, HashSet<String>> = HashMap::new();
+    for leg in legs {
+        per_symbol_open
+            .entry(leg.symbol.clone())
+            .or_default()
+            .insert(leg.order_id.clone());
+        let fill_ids = per_symbol_fill.entry(leg.symbol.clone()).or_default();
+        fill_ids.insert(leg.order_id.clone());
+        if let Some(exchange_id) = &leg.exchange_order_id {
+            fill_ids.insert(exchange_id.clone());
+        }
     }
+    for (symbol, open_ids_filter) in per_symbol_open.iter() {
+        let fill_ids_filter = per_symbol_fill.get(symbol).cloned().unwrap_or_default();
+        let open = connector
+            .get_open_orders(symbol)
+            .await
+            .with_context(|| format!("open orders {}", symbol))?;
+        let mut open_count = 0;
+        for order in open
+            .orders
+            .iter()
+            .filter(|o| open_ids_filter.contains(&o.order_id))
+        {
+            open_ids.insert(order.order_id.clone());
+            open_count += 1;
+        }
+        open_remaining += open_count;
 
-    fn update_pending_fills(&self, pending: &mut PendingOrders, fills: &HashMap<String, Decimal>) {
-        for leg in &mut pending.legs {
-            let filled = self.leg_fill_from_map(leg, fills);
-            if filled > leg.filled {
-                leg.filled = filled.min(leg.target);
+        let filled = connector
+            .get_filled_orders(symbol)
+            .await
+            .with_context(|| format!("filled orders {}", symbol))?;
+        for order in filled.orders {
+            if fill_ids_filter.contains(&order.order_id) {
+                let sz = order.filled_size.unwrap_or(Decimal::ZERO);
+                *fills.entry(order.order_id.clone()).or_default() += sz;
+                if let Some(value) = order.filled_value {
+                    *fill_values.entry(order.order_id.clone()).or_default() += value;
+                }
+                if let Some(fee) = order.filled_fee {
+                    *fill_fees.entry(order.order_id.clone()).or_default() += fee;
+                }
+                log::debug!(
+                    "[ORDER][FILLED] symbol={} order_id={} side={:?} size={} value={:?} fee={:?} trade_id={}",
+                    symbol,
+                    order.order_id,
+                    order.filled_side,
+                    sz,
+                    order.filled_value,
+                    order.filled_fee,
+                    order.trade_id
+                );
             }
         }
+        log::debug!(
+            "[ORDER][PENDING_STATUS] symbol={} open_orders={} tracked_orders={} filled_entries={}",
+            symbol,
+            open_count,
+            open_ids_filter.len(),
+            fills.len()
+        );
     }
+    Ok(PendingStatus {
+        open_remaining,
+        fills,
+        fill_values,
+        fill_fees,
+        open_ids,
+    })
+}
 
-    fn filled_for_leg(&self, leg: &PendingLeg, fills: &HashMap<String, Decimal>) -> Decimal {
-        let filled = self.leg_fill_from_map(leg, fills);
-        filled.max(leg.filled).min(leg.target)
+/// Fresh per-pair state for a pair entering the universe, either at
+/// startup or via a SIGHUP universe reload.
+fn new_pair_state(cfg: &PairTradeConfig, pp: &PairParams) -> PairState {
+    let mut ps = PairState::new(cfg.metrics_window, pp.entry_z_base);
+    ps.z_stat_mode = cfg.z_stat_mode;
+    if cfg.use_kalman_beta {
+        ps.kalman = Some(kalman::KalmanBeta::new(
+            1.0,
+            cfg.kalman_initial_p,
+            cfg.kalman_q,
+            cfg.kalman_r,
+        )
+        .with_negative_beta(cfg.allow_negative_beta));
     }
+    ps
+}
 
-    fn filled_by_leg(
-        &self,
-        pending: &PendingOrders,
-        fills: &HashMap<String, Decimal>,
-    ) -> HashMap<String, Decimal> {
-        let mut map = HashMap::new();
-        for leg in &pending.legs {
-            let filled = self.filled_for_leg(leg, fills);
-            map.insert(leg.order_id.clone(), filled);
-        }
-        map
-    }
 
-    fn all_filled(&self, pending: &PendingOrders, fills: &HashMap<String, Decimal>) -> bool {
-        pending
-            .legs
-            .iter()
-            .all(|leg| self.filled_for_leg(leg, fills) >= leg.target)
+#[derive(Serialize)]
+struct DataDumpEntry<'a> {
+    timestamp: i64,
+    prices: &'a HashMap<String, SymbolSnapshot>,
+}
+
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::util::{enforce_post_only_passive, quantize_size_by_step, quantize_size_by_step_ceiling};
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
     }
 
-    fn evaluate_pair(&self, pair: &PairSpec) -> Option<PairEvaluation> {
-        pair_eval::evaluate_pair(&self.cfg, &self.history, pair)
+    #[test]
+    fn round_price_by_tick_rounds_long_down() {
+        let price = dec("100.123");
+        let step = dec("0.01");
+        let quantized = round_price_by_tick(price, step, dex_connector::OrderSide::Long);
+        assert_eq!(quantized, dec("100.12"));
     }
 
-    fn exit_sizes_for_pair(
-        &self,
-        inst_idx: usize,
-        key: &str,
-        pair: &PairSpec,
-        beta: f64,
-        p1: &SymbolSnapshot,
-        p2: &SymbolSnapshot,
-    ) -> Result<(Decimal, Decimal)> {
-        let base_snapshot = self.open_positions.get(&pair.base);
-        let quote_snapshot = self.open_positions.get(&pair.quote);
-        if base_snapshot.is_some() || quote_snapshot.is_some() {
-            let qty_a = base_snapshot.map(|p| p.size).unwrap_or(Decimal::ZERO);
-            let qty_b = quote_snapshot.map(|p| p.size).unwrap_or(Decimal::ZERO);
-            return Ok((qty_a, qty_b));
-        }
+    #[test]
+    fn round_price_by_tick_rounds_short_up() {
+        let price = dec("100.123");
+        let step = dec("0.01");
+        let quantized = round_price_by_tick(price, step, dex_connector::OrderSide::Short);
+        assert_eq!(quantized, dec("100.13"));
+    }
 
-        let mut qty_a = Decimal::ZERO;
-        let mut qty_b = Decimal::ZERO;
-        if let Some(state) = self.instances[inst_idx].states.get(key).and_then(|s| s.position.as_ref()) {
-            qty_a = state.entry_size_a.unwrap_or(Decimal::ZERO);
-            qty_b = state.entry_size_b.unwrap_or(Decimal::ZERO);
-        }
+    #[test]
+    fn round_price_by_tick_enforces_minimum_step() {
+        let price = dec("0.0001");
+        let step = dec("0.005");
+        let quantized = round_price_by_tick(price, step, dex_connector::OrderSide::Long);
+        assert_eq!(quantized, step);
+    }
 
-        if qty_a <= Decimal::ZERO && qty_b <= Decimal::ZERO {
-            log::warn!(
-                "[EXIT] {} missing position sizes from exchange/state; falling back to hedge sizing",
-                key
-            );
-            return self.hedged_sizes(inst_idx, pair, beta, p1, p2);
-        }
+    // bot-strategy#216: post-only passive enforcement
+    #[test]
+    fn post_only_passive_long_extended_btc_at_touch() {
+        // Extended BTC tick=1, ask=77641, ToNegativeInfinity rounding leaves
+        // limit at touch (no-op). Must shift to ask - 1 tick.
+        let rounded = dec("77641");
+        let touch = dec("77641");
+        let tick = dec("1");
+        let limit = enforce_post_only_passive(rounded, touch, tick, dex_connector::OrderSide::Long);
+        assert_eq!(limit, dec("77640"));
+    }
 
-        Ok((qty_a, qty_b))
+    #[test]
+    fn post_only_passive_short_extended_eth_at_touch() {
+        // Extended ETH tick=0.1, bid=2315.5, ToPositiveInfinity rounding
+        // leaves limit at touch. Must shift to bid + 1 tick.
+        let rounded = dec("2315.5");
+        let touch = dec("2315.5");
+        let tick = dec("0.1");
+        let limit = enforce_post_only_passive(rounded, touch, tick, dex_connector::OrderSide::Short);
+        assert_eq!(limit, dec("2315.6"));
     }
 
-    fn hedged_sizes(
-        &self,
-        inst_idx: usize,
-        _pair: &PairSpec,
-        beta: f64,
-        p1: &SymbolSnapshot,
-        p2: &SymbolSnapshot,
-    ) -> Result<(Decimal, Decimal)> {
-        let inst = &self.instances[inst_idx];
-        let equity = inst.equity_reference_usd;
-        sizing::hedged_sizes(&self.cfg, equity, beta, p1, p2)
+    #[test]
+    fn post_only_passive_long_already_inside_no_op() {
+        // Lighter passive-slippage path: rounded already below ask. Untouched.
+        let rounded = dec("77640.0");
+        let touch = dec("77640.5");
+        let tick = dec("0.1");
+        let limit = enforce_post_only_passive(rounded, touch, tick, dex_connector::OrderSide::Long);
+        assert_eq!(limit, dec("77640.0"));
     }
 
-    fn post_only_supported(&self) -> bool {
-        let dex = self.cfg.dex_name.to_ascii_lowercase();
-        dex.contains("lighter") || dex.contains("extended")
+    #[test]
+    fn post_only_passive_short_already_inside_no_op() {
+        let rounded = dec("2315.6");
+        let touch = dec("2315.5");
+        let tick = dec("0.1");
+        let limit = enforce_post_only_passive(rounded, touch, tick, dex_connector::OrderSide::Short);
+        assert_eq!(limit, dec("2315.6"));
     }
 
-    fn should_post_only(&self) -> bool {
-        self.cfg.fee_bps > 0.0 && self.post_only_supported()
+    #[test]
+    fn post_only_passive_long_above_touch_clamps() {
+        // Defensive: if upstream produced a rounded price above ask (e.g.
+        // aggressive slippage_bps>0 with should_post_only true), clamp it
+        // back inside.
+        let rounded = dec("77642");
+        let touch = dec("77641");
+        let tick = dec("1");
+        let limit = enforce_post_only_passive(rounded, touch, tick, dex_connector::OrderSide::Long);
+        assert_eq!(limit, dec("77640"));
     }
 
-    fn order_reference_price_from_snapshot(
-        &self,
-        symbol: &str,
-        side: dex_connector::OrderSide,
-        snapshot: &SymbolSnapshot,
-    ) -> Decimal {
-        let use_book = self.cfg.slippage_bps < 0 || self.should_post_only();
-        if use_book {
-            let side_price = match side {
-                dex_connector::OrderSide::Long => snapshot.ask_price,
-                dex_connector::OrderSide::Short => snapshot.bid_price,
-            };
-            if side_price.is_none() {
-                log::debug!(
-                    "[ORDER] {} missing top-of-book price; using ticker price",
-                    symbol
-                );
-            }
-            return side_price.unwrap_or(snapshot.price);
-        }
-        snapshot.price
+    #[test]
+    fn post_only_passive_zero_tick_returns_input() {
+        let rounded = dec("100");
+        let touch = dec("100");
+        let tick = dec("0");
+        let limit = enforce_post_only_passive(rounded, touch, tick, dex_connector::OrderSide::Long);
+        assert_eq!(limit, dec("100"));
     }
 
-    fn order_reference_price(
-        &self,
-        symbol: &str,
-        side: dex_connector::OrderSide,
-        prices: &HashMap<String, SymbolSnapshot>,
-    ) -> Option<Decimal> {
-        let snapshot = prices.get(symbol)?;
-        Some(self.order_reference_price_from_snapshot(symbol, side, snapshot))
+    #[test]
+    fn quantize_size_by_step_uses_size_decimals() {
+        let size = dec("0.0023");
+        let step = dec("0.001");
+        let quantized = quantize_size_by_step(size, step, None);
+        assert_eq!(quantized, dec("0.002"));
     }
 
-    fn limit_price_for(
-        &mut self,
-        symbol: &str,
-        side: dex_connector::OrderSide,
-        prices: &HashMap<String, SymbolSnapshot>,
-    ) -> Option<Decimal> {
-        let snapshot = prices.get(symbol)?;
-        let reference = self.order_reference_price_from_snapshot(symbol, side, snapshot);
-        let adjusted = self.apply_slippage(Some(reference), side)?;
-        Some(self.quantize_order_price_with_snapshot(symbol, adjusted, side, snapshot))
+    #[test]
+    fn quantize_size_by_step_respects_min_order_floor() {
+        let size = dec("0.0002");
+        let step = dec("0.0001");
+        let quantized = quantize_size_by_step(size, step, Some(dec("0.001")));
+        assert_eq!(quantized, dec("0.001"));
     }
 
-    fn limit_price_for_snapshot(
-        &mut self,
-        symbol: &str,
-        side: dex_connector::OrderSide,
-        snapshot: &SymbolSnapshot,
-    ) -> Option<Decimal> {
-        let reference = self.order_reference_price_from_snapshot(symbol, side, snapshot);
-        let adjusted = self.apply_slippage(Some(reference), side)?;
-        Some(self.quantize_order_price_with_snapshot(symbol, adjusted, side, snapshot))
+    #[test]
+    fn quantize_size_by_step_ceiling_rounds_up() {
+        let size = dec("0.0023");
+        let step = dec("0.001");
+        let quantized = quantize_size_by_step_ceiling(size, step, None);
+        assert_eq!(quantized, dec("0.003"));
     }
 
-    async fn refreshed_limit_price(
-        &mut self,
-        symbol: &str,
-        side: dex_connector::OrderSide,
-        prices: &HashMap<String, SymbolSnapshot>,
-    ) -> Option<Decimal> {
-        match self.refresh_symbol_snapshot(symbol).await {
-            Ok(snapshot) => self.limit_price_for_snapshot(symbol, side, &snapshot),
-            Err(err) => {
-                log::debug!(
-                    "[ORDER] Failed to refresh price snapshot for {}: {:?}",
-                    symbol,
-                    err
-                );
-                self.limit_price_for(symbol, side, prices)
-            }
-        }
+    // 2026-04-23 18:29:50 UTC — 1745432990 — Thu of day 20203 (UNIX/86400)
+    const TS_2026_04_23_18_29: i64 = 1_745_432_990;
+    // 2026-04-24 00:00:05 UTC — 1745452805 — Fri of day 20204
+    const TS_2026_04_24_00_00: i64 = 1_745_452_805;
+    // 2026-04-23 23:59:55 UTC — 1745452795 — still Thu of day 20203
+    const TS_2026_04_23_23_59: i64 = 1_745_452_795;
+
+    #[test]
+    fn session_day_midnight_reset_same_bucket_before_boundary() {
+        let a = session_day(TS_2026_04_23_18_29, 0);
+        let b = session_day(TS_2026_04_23_23_59, 0);
+        assert_eq!(a, b, "timestamps within the same UTC day should share bucket");
     }
 
-    async fn refresh_symbol_snapshot(&mut self, symbol: &str) -> Result<SymbolSnapshot> {
-        let ticker = self
-            .connector
-            .get_ticker(symbol, None)
-            .await
-            .with_context(|| format!("ticker {}", symbol))?;
-        let (bid_price, ask_price, bid_size, ask_size) =
-            match self.connector.get_order_book(symbol, 1).await {
-                Ok(ob) => (
-                    ob.bids.first().map(|l| l.price),
-                    ob.asks.first().map(|l| l.price),
-                    ob.bids.first().map(|l| l.size).unwrap_or(Decimal::ZERO),
-                    ob.asks.first().map(|l| l.size).unwrap_or(Decimal::ZERO),
-                ),
-                Err(err) => {
-                    log::debug!(
-                        "[ORDER] orderbook {} unavailable during retry: {:?}",
-                        symbol,
-                        err
-                    );
-                    (None, None, Decimal::ZERO, Decimal::ZERO)
-                }
-            };
-        Ok(SymbolSnapshot {
-            price: ticker.price,
-            funding_rate: ticker.funding_rate.unwrap_or(Decimal::ZERO),
-            bid_price,
-            ask_price,
-            bid_size,
-            ask_size,
-            min_order: ticker.min_order,
-            min_tick: ticker.min_tick,
-            size_decimals: ticker.size_decimals,
-            exchange_ts: ticker.exchange_ts.map(|v| v as i64),
-        })
+    #[test]
+    fn session_day_midnight_reset_boundary_crosses() {
+        let before = session_day(TS_2026_04_23_23_59, 0);
+        let after = session_day(TS_2026_04_24_00_00, 0);
+        assert_eq!(after - before, 1, "UTC midnight should advance bucket by exactly 1");
     }
 
-    fn order_spread_param(&self, limit: Option<Decimal>, allow_post_only: bool) -> Option<i64> {
-        if allow_post_only && limit.is_some() && self.should_post_only() {
-            Some(-2)
-        } else {
-            None
-        }
+    #[test]
+    fn session_day_custom_reset_hour_shifts_boundary() {
+        // reset_hour=6 ⇒ session boundary at UTC 06:00. A timestamp just
+        // before 06:00 and one just after should fall in adjacent buckets.
+        let ts_0400_utc = 1_745_467_200; // 2026-04-24 04:00 UTC
+        let ts_0700_utc = 1_745_478_000; // 2026-04-24 07:00 UTC
+        let bucket_0400 = session_day(ts_0400_utc, 6);
+        let bucket_0700 = session_day(ts_0700_utc, 6);
+        assert_eq!(bucket_0700 - bucket_0400, 1);
     }
 
-    fn apply_slippage(
-        &self,
-        price: Option<Decimal>,
-        side: dex_connector::OrderSide,
-    ) -> Option<Decimal> {
-        order_pricing::apply_slippage(self.cfg.slippage_bps, price, side)
+    // bot-strategy#185 Phase 3-1: rolling-peak DD calculations.
+    fn sample(ts: i64, equity: f64) -> risk_io::EquitySample {
+        risk_io::EquitySample { ts, equity }
     }
 
-    fn quantize_order_size(
-        &self,
-        symbol: &str,
-        size: Decimal,
-        prices: &HashMap<String, SymbolSnapshot>,
-    ) -> Decimal {
-        order_pricing::quantize_order_size(symbol, size, prices)
+    #[test]
+    fn rolling_peak_no_samples_uses_current_as_peak() {
+        // Bot just started: no samples yet, so peak = current and DD = 0.
+        let (peak, dd) = PairTradeEngine::rolling_peak(&[], 1_000.0).unwrap();
+        assert_eq!(peak, 1_000.0);
+        assert_eq!(dd, 0.0);
     }
 
-    fn quantize_order_size_exit(
-        &self,
-        symbol: &str,
-        size: Decimal,
-        prices: &HashMap<String, SymbolSnapshot>,
-    ) -> Decimal {
-        order_pricing::quantize_order_size_exit(symbol, size, prices)
+    #[test]
+    fn rolling_peak_picks_max_across_samples_and_current() {
+        let s = vec![sample(100, 1_000.0), sample(200, 1_500.0), sample(300, 1_200.0)];
+        let (peak, dd) = PairTradeEngine::rolling_peak(&s, 900.0).unwrap();
+        assert_eq!(peak, 1_500.0);
+        // (1500 - 900)/1500 * 10000 = 4000 bps
+        assert!((dd - 4_000.0).abs() < 1e-6);
     }
 
-    fn quantize_order_size_close(
-        &self,
-        symbol: &str,
-        size: Decimal,
-        prices: &HashMap<String, SymbolSnapshot>,
-    ) -> Decimal {
-        order_pricing::quantize_order_size_close(symbol, size, prices)
+    #[test]
+    fn rolling_peak_zero_dd_when_current_is_new_peak() {
+        let s = vec![sample(100, 1_000.0)];
+        let (peak, dd) = PairTradeEngine::rolling_peak(&s, 1_500.0).unwrap();
+        assert_eq!(peak, 1_500.0);
+        assert_eq!(dd, 0.0);
     }
 
-    fn quantize_order_price_with_snapshot(
-        &mut self,
-        symbol: &str,
-        price: Decimal,
-        side: dex_connector::OrderSide,
-        snapshot: &SymbolSnapshot,
-    ) -> Decimal {
-        let mut effective_tick_size = snapshot.min_tick;
+    #[test]
+    fn rolling_peak_returns_none_for_non_positive_equity() {
+        // Pre-funded account / connector hiccup → equity reads 0; can't
+        // define a percent DD, so caller treats it as "no signal".
+        assert!(PairTradeEngine::rolling_peak(&[], 0.0).is_none());
+        assert!(PairTradeEngine::rolling_peak(&[], -10.0).is_none());
+    }
 
-        // Extended occasionally returns markets without `min_tick` populated
-        // in the snapshot (dex-connector fills this from the markets cache,
-        // which may lag a reconnect). Fall back to tick=1 so we don't spam
-        // the "No min tick" warning every cycle.
-        if effective_tick_size.is_none() && self.cfg.dex_name.contains("extended") {
-            effective_tick_size = Some(Decimal::ONE);
-        }
+    #[test]
+    fn risk_state_path_falls_back_to_cwd_for_bare_filename() {
+        let p = risk_state_path_for(std::path::Path::new(
+            "pairtrade_history_BTC_ETH.json",
+        ));
+        assert_eq!(p, std::path::PathBuf::from("risk_state.json"));
+    }
 
-        let Some(tick_size) = effective_tick_size else {
-            if !self.min_tick_warned.contains(symbol) {
-                log::warn!(
-                    "[ORDER] No min tick for {}; price rounding disabled",
-                    symbol
-                );
+    #[test]
+    fn risk_state_path_joined_to_parent_dir_when_absolute() {
+        let p = risk_state_path_for(std::path::Path::new(
+            "/opt/debot/pairtrade_history_BTC_ETH.json",
+        ));
+        assert_eq!(p, std::path::PathBuf::from("/opt/debot/risk_state.json"));
+    }
+}
 
-                self.min_tick_warned.insert(symbol.to_string());
-            }
+#[cfg(test)]
+mod pending_tests {
+    use super::*;
+    use async_trait::async_trait;
+    use dex_connector::{
+        BalanceResponse, CanceledOrdersResponse, CreateOrderResponse, DexConnector, DexError,
+        FilledOrder, FilledOrdersResponse, LastTrade, LastTradesResponse, OpenOrder, OpenOrdersResponse,
+        OrderBookSnapshot,
+        OrderSide, PositionSnapshot, TickerResponse, TpSl, TriggerOrderStyle,
+    };
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
 
-            return price;
-        };
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
 
-        if tick_size <= Decimal::ZERO {
-            return price;
-        }
+    #[derive(Default)]
+    struct DummyConnector {
+        calls: Mutex<Vec<(String, Decimal, OrderSide, Option<Decimal>, bool)>>,
+        next_id: AtomicUsize,
+        balance_calls: AtomicUsize,
+        balance_equity: Mutex<Option<Decimal>>,
+        restart_calls: AtomicUsize,
+        open_order_calls: AtomicUsize,
+        /// Open orders `get_open_orders` reports for every symbol.
+        open_orders: Mutex<usize>,
+        /// Served by `get_positions`; `close_all_positions` empties it.
+        positions: Mutex<Vec<PositionSnapshot>>,
+        /// Trade prints by symbol; `clear_last_trades` drains a symbol.
+        trades: Mutex<HashMap<String, Vec<Decimal>>>,
+        /// `(price, min_order)` served by `get_ticker`; unknown symbols error.
+        tickers: Mutex<HashMap<String, (Decimal, Option<Decimal>)>>,
+        /// `get_ticker` answers this many calls with a 429 first.
+        ticker_failures: AtomicUsize,
+        /// `create_order` rejects this many orders per symbol first.
+        order_failures: Mutex<HashMap<String, usize>>,
+        /// Served by `get_filled_orders` for every symbol.
+        filled_orders: Mutex<Vec<FilledOrder>>,
+    }
 
-        let rounded = round_price_by_tick(price, tick_size, side);
+    #[async_trait]
+    impl DexConnector for DummyConnector {
+        async fn start(&self) -> Result<(), DexError> {
+            Ok(())
+        }
 
-        // bot-strategy#216: tick rounding is a no-op when the touch price is
-        // already a tick multiple (Extended BTC tick=1 with integer prices),
-        // so post-only limits land at touch and get rejected/crossed.
-        if self.should_post_only() {
-            let touch = match side {
-                dex_connector::OrderSide::Long => snapshot.ask_price,
-                dex_connector::OrderSide::Short => snapshot.bid_price,
-            };
-            if let Some(touch) = touch {
-                return enforce_post_only_passive(rounded, touch, tick_size, side);
-            }
+        async fn stop(&self) -> Result<(), DexError> {
+            Ok(())
         }
 
-        rounded
-    }
+        async fn restart(&self, _max_retries: i32) -> Result<(), DexError> {
+            self.restart_calls.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
 
-    async fn create_order_with_post_only_retry(
-        &mut self,
-        symbol: &str,
-        size: Decimal,
-        side: dex_connector::OrderSide,
-        reduce_only: bool,
-        prices: &HashMap<String, SymbolSnapshot>,
-        allow_post_only: bool,
-        max_post_only_attempts: usize,
-        fallback_to_taker: bool,
-    ) -> Result<dex_connector::CreateOrderResponse, DexError> {
-        let use_post_only = allow_post_only && self.should_post_only();
-        let max_attempts = max_post_only_attempts.max(1);
-        let max_elapsed = Duration::from_millis(POST_ONLY_RETRY_MAX_ELAPSED_MS);
-        let start = Instant::now();
-        let mut attempt = 0usize;
+        async fn set_leverage(&self, _symbol: &str, _leverage: u32) -> Result<(), DexError> {
+            Ok(())
+        }
 
-        let last_err = loop {
-            attempt += 1;
-            let limit = if use_post_only {
-                self.refreshed_limit_price(symbol, side, prices).await
-            } else {
-                self.limit_price_for(symbol, side, prices)
-            };
-            if use_post_only && limit.is_none() {
-                return Err(DexError::Other(format!(
-                    "[ORDER] Missing reference price for post-only {}",
-                    symbol
-                )));
-            }
-            let spread = self.order_spread_param(limit, use_post_only);
-            match self
-                .connector
-                .create_order(symbol, size, side, limit, spread, reduce_only, None)
-                .await
-            {
-                Ok(resp) => return Ok(resp),
-                Err(err) => {
-                    if !use_post_only {
-                        return Err(err);
-                    }
-                    if attempt >= max_attempts || start.elapsed() >= max_elapsed {
-                        break err;
-                    }
-                }
+        async fn get_ticker(
+            &self,
+            symbol: &str,
+            _test_price: Option<Decimal>,
+        ) -> Result<TickerResponse, DexError> {
+            let Some((price, min_order)) = self.tickers.lock().unwrap().get(symbol).copied() else {
+                return Err(DexError::Other(format!("Symbol '{}' not found", symbol)));
+            };
+            if self.ticker_failures.load(Ordering::SeqCst) > 0 {
+                self.ticker_failures.fetch_sub(1, Ordering::SeqCst);
+                return Err(DexError::ServerResponse("429 Too Many Requests".to_string()));
             }
+            Ok(TickerResponse {
+                symbol: symbol.to_string(),
+                price,
+                min_tick: Some(dec("0.01")),
+                min_order,
+                size_decimals: None,
+                volume: None,
+                num_trades: None,
+                open_interest: None,
+                funding_rate: None,
+                oracle_price: None,
+                exchange_ts: None,
+            })
+        }
 
-            log::info!(
-                "[ORDER] {} post-only attempt {} failed; retrying",
-                symbol,
-                attempt
-            );
-            sleep(Duration::from_millis(POST_ONLY_RETRY_DELAY_MS)).await;
-        };
+        async fn get_filled_orders(&self, _symbol: &str) -> Result<FilledOrdersResponse, DexError> {
+            let orders = self.filled_orders.lock().unwrap().clone();
+            Ok(FilledOrdersResponse { orders })
+        }
 
-        if use_post_only && fallback_to_taker {
-            log::warn!(
-                "[ORDER] {} post-only attempts exhausted; falling back to taker",
-                symbol
-            );
-            return self
-                .connector
-                .create_order(symbol, size, side, None, None, reduce_only, None)
-                .await;
+        async fn get_canceled_orders(
+            &self,
+            _symbol: &str,
+        ) -> Result<CanceledOrdersResponse, DexError> {
+            Ok(CanceledOrdersResponse::default())
         }
 
-        Err(last_err)
-    }
+        async fn get_open_orders(&self, _symbol: &str) -> Result<OpenOrdersResponse, DexError> {
+            self.open_order_calls.fetch_add(1, Ordering::SeqCst);
+            let orders = (0..*self.open_orders.lock().unwrap())
+                .map(|i| OpenOrder {
+                    order_id: format!("open-{}", i),
+                    ..Default::default()
+                })
+                .collect();
+            Ok(OpenOrdersResponse { orders })
+        }
 
-    async fn place_pair_orders(
-        &mut self,
-        inst_idx: usize,
-        pair: &PairSpec,
-        direction: PositionDirection,
-        qtys: (Decimal, Decimal),
-        prices: &HashMap<String, SymbolSnapshot>,
-    ) -> Result<Vec<PendingLeg>> {
-        let (side_a, side_b) = match direction {
-            PositionDirection::LongSpread => (
-                dex_connector::OrderSide::Long,
-                dex_connector::OrderSide::Short,
-            ),
-            PositionDirection::ShortSpread => (
-                dex_connector::OrderSide::Short,
-                dex_connector::OrderSide::Long,
-            ),
-        };
-        let ref_price_a = self.order_reference_price(&pair.base, side_a, prices);
-        let ref_price_b = self.order_reference_price(&pair.quote, side_b, prices);
-        // Pick the floor/ceiling combination per leg that best preserves the
-        // requested hedge ratio. With Extended's coarse ETH lot (0.01 ≈ $23) and a
-        // small Phase 3 budget (~$25/leg), a single rounding direction routinely
-        // produces 23-82% hedge deviation — searching the 4 combinations finds
-        // the round that keeps the ratio intact. See bot-strategy#211.
-        let qa_floor = self.quantize_order_size(&pair.base, qtys.0, prices);
-        let qa_ceil = self.quantize_order_size_exit(&pair.base, qtys.0, prices);
-        let qb_floor = self.quantize_order_size(&pair.quote, qtys.1, prices);
-        let qb_ceil = self.quantize_order_size_exit(&pair.quote, qtys.1, prices);
-        let (qty_a, qty_b, ratio_dev) = match order_pricing::pick_entry_quantize(
-            qtys, qa_floor, qa_ceil, qb_floor, qb_ceil,
-        ) {
-            Some(x) => x,
-            None => {
-                log::warn!(
-                    "[ORDER_ADJUST][ENTRY] {}/{} BLOCKED: zero qty after quantize (qty_a={}, qty_b={})",
-                    pair.base, pair.quote, qa_floor, qb_floor
-                );
-                return Ok(Vec::new());
-            }
-        };
-        if qty_a != qtys.0 {
-            log::debug!(
-                "[ORDER_ADJUST][ENTRY] {} settled qty_a {} -> {}",
-                pair.base,
-                qtys.0,
-                qty_a
-            );
+        async fn get_balance(&self, _symbol: Option<&str>) -> Result<BalanceResponse, DexError> {
+            self.balance_calls.fetch_add(1, Ordering::SeqCst);
+            let equity = self.balance_equity.lock().unwrap().unwrap_or_default();
+            Ok(BalanceResponse {
+                equity,
+                balance: equity,
+                position_entry_price: None,
+                position_sign: None,
+            })
         }
-        if qty_b != qtys.1 {
-            log::debug!(
-                "[ORDER_ADJUST][ENTRY] {} settled qty_b {} -> {}",
-                pair.quote,
-                qtys.1,
-                qty_b
-            );
+
+        async fn get_combined_balance(
+            &self,
+        ) -> Result<dex_connector::CombinedBalanceResponse, DexError> {
+            Ok(dex_connector::CombinedBalanceResponse::default())
         }
-        let pair_key_for_dev = format!("{}/{}", pair.base, pair.quote);
-        let pp_for_dev = self.pair_params_for(inst_idx, &pair_key_for_dev).clone();
-        let pp_for_dev = &pp_for_dev;
-        if pp_for_dev.hedge_ratio_max_deviation < 1.0
-            && ratio_dev > pp_for_dev.hedge_ratio_max_deviation
-        {
-            log::warn!(
-                "[ORDER_ADJUST][ENTRY] {}/{} BLOCKED: hedge ratio deviation {:.1}% exceeds limit {:.1}%",
-                pair.base, pair.quote, ratio_dev * 100.0, pp_for_dev.hedge_ratio_max_deviation * 100.0
-            );
-            return Ok(Vec::new());
+
+        async fn get_positions(&self) -> Result<Vec<PositionSnapshot>, DexError> {
+            Ok(self.positions.lock().unwrap().clone())
         }
-        let limit_a = self.limit_price_for(&pair.base, side_a, prices);
-        let limit_b = self.limit_price_for(&pair.quote, side_b, prices);
-        let pair_key_for_hybrid = format!("{}/{}", pair.base, pair.quote);
-        let pp_for_hybrid = self.pair_params_for(inst_idx, &pair_key_for_hybrid).clone();
-        let pp_for_hybrid = &pp_for_hybrid;
-        let hybrid_active =
-            pp_for_hybrid.entry_post_only_timeout_secs > 0 && self.post_only_supported();
-        let post_only = self.should_post_only();
-        let entry_attempts = if hybrid_active {
-            1
-        } else {
-            POST_ONLY_ENTRY_ATTEMPTS
-        };
-        log::debug!(
-            "[ORDER_PARAMS][ENTRY] pair={}/{} side_a={:?} qty_a={} ref_price_a={} limit_a={:?} side_b={:?} qty_b={} ref_price_b={} limit_b={:?} post_only={} hybrid={}",
-            pair.base,
-            pair.quote,
-            side_a,
-            qty_a,
-            ref_price_a.unwrap_or(Decimal::ZERO),
-            limit_a,
-            side_b,
-            qty_b,
-            ref_price_b.unwrap_or(Decimal::ZERO),
-            limit_b,
-            post_only,
-            hybrid_active
-        );
-        let mut legs: Vec<PendingLeg> = Vec::new();
-        let res_a = self
-            .create_order_with_post_only_retry(
-                &pair.base,
-                qty_a,
-                side_a,
-                false,
-                prices,
-                true,
-                entry_attempts,
-                false,
-            )
-            .await
-            .context("place leg A")?;
-        let target_a = if res_a.ordered_size > Decimal::ZERO {
-            if res_a.ordered_size != qtys.0 {
-                log::debug!(
-                    "[ORDER_PARAMS][ENTRY] size adjusted by exchange for {}: requested={} ordered={}",
-                    pair.base,
-                    qtys.0,
-                    res_a.ordered_size
-                );
-            }
-            res_a.ordered_size
-        } else {
-            qtys.0
-        };
-        legs.push(PendingLeg {
-            symbol: pair.base.clone(),
-            order_id: res_a.order_id.clone(),
-            exchange_order_id: res_a.exchange_order_id.clone(),
-            target: target_a,
-            filled: Decimal::ZERO,
-            side: side_a,
-            limit_price: limit_a,
-        });
 
-        let res_b = match self
-            .create_order_with_post_only_retry(
-                &pair.quote,
-                qty_b,
-                side_b,
-                false,
-                prices,
-                true,
-                entry_attempts,
-                false,
-            )
-            .await
-        {
-            Ok(res) => res,
-            Err(e) => {
-                self.recover_from_leg_b_failure(pair, &res_a, side_a, &e).await;
-                return Err(PartialOrderPlacementError::new(legs.clone(), e).into());
-            }
-        };
-        let target_b = if res_b.ordered_size > Decimal::ZERO {
-            if res_b.ordered_size != qtys.1 {
-                log::debug!(
-                    "[ORDER_PARAMS][ENTRY] size adjusted by exchange for {}: requested={} ordered={}",
-                    pair.quote,
-                    qtys.1,
-                    res_b.ordered_size
-                );
-            }
-            res_b.ordered_size
-        } else {
-            qtys.1
-        };
-        legs.push(PendingLeg {
-            symbol: pair.quote.clone(),
-            order_id: res_b.order_id.clone(),
-            exchange_order_id: res_b.exchange_order_id.clone(),
-            target: target_b,
-            filled: Decimal::ZERO,
-            side: side_b,
-            limit_price: limit_b,
-        });
-        Ok(legs)
-    }
+        async fn get_last_trades(&self, symbol: &str) -> Result<LastTradesResponse, DexError> {
+            let prices = self.trades.lock().unwrap().get(symbol).cloned().unwrap_or_default();
+            Ok(LastTradesResponse {
+                trades: prices
+                    .into_iter()
+                    .map(|price| LastTrade {
+                        price,
+                        ..Default::default()
+                    })
+                    .collect(),
+            })
+        }
 
-    /// Recovery path when leg B placement fails after leg A succeeded:
-    /// cancel leg A, wait briefly, check whether the exchange filled it
-    /// anyway, and if so submit a market reduce-only order in the opposite
-    /// direction to neutralize the unhedged exposure. All errors here are
-    /// logged but not propagated — the caller still surfaces the original
-    /// leg-B failure.
-    async fn recover_from_leg_b_failure(
-        &self,
-        pair: &PairSpec,
-        res_a: &dex_connector::CreateOrderResponse,
-        side_a: dex_connector::OrderSide,
-        leg_b_err: &DexError,
-    ) {
-        log::error!(
-            "[ORDER] Failed to place leg B for {}/{} (leg A={}): {:?}",
-            pair.base,
-            pair.quote,
-            res_a.order_id,
-            leg_b_err
-        );
+        async fn get_order_book(
+            &self,
+            _symbol: &str,
+            _depth: usize,
+        ) -> Result<OrderBookSnapshot, DexError> {
+            Ok(OrderBookSnapshot::default())
+        }
 
-        // Attempt to cancel leg A, but proceed even if it fails.
-        if let Err(cancel_err) = self
-            .connector
-            .cancel_order(&pair.base, &res_a.order_id)
-            .await
-        {
-            log::warn!(
-                "[SAFETY] Failed to cancel leg A {} after leg B failed: {:?}",
-                res_a.order_id,
-                cancel_err
-            );
-        } else {
-            log::info!(
-                "[SAFETY] Canceled leg A {} after leg B failed.",
-                res_a.order_id
-            );
+        async fn clear_filled_order(&self, _symbol: &str, _trade_id: &str) -> Result<(), DexError> {
+            Ok(())
         }
 
-        // Give the exchange time to settle any concurrent fill.
-        sleep(Duration::from_secs(5)).await;
+        async fn clear_all_filled_orders(&self) -> Result<(), DexError> {
+            Ok(())
+        }
+
+        async fn clear_canceled_order(
+            &self,
+            _symbol: &str,
+            _order_id: &str,
+        ) -> Result<(), DexError> {
+            Ok(())
+        }
+
+        async fn clear_all_canceled_orders(&self) -> Result<(), DexError> {
+            Ok(())
+        }
 
-        let filled_orders = match self.connector.get_filled_orders(&pair.base).await {
-            Ok(orders) => orders,
-            Err(e) => {
-                log::error!(
-                    "[SAFETY] Could not check for filled orders for {}: {:?}",
-                    pair.base,
-                    e
-                );
-                return;
+        async fn create_order(
+            &self,
+            symbol: &str,
+            size: Decimal,
+            side: OrderSide,
+            price: Option<Decimal>,
+            _spread: Option<i64>,
+            reduce_only: bool,
+            _expiry_secs: Option<u64>,
+        ) -> Result<CreateOrderResponse, DexError> {
+            if let Some(left) = self.order_failures.lock().unwrap().get_mut(symbol) {
+                if *left > 0 {
+                    *left -= 1;
+                    return Err(DexError::Other("order rejected".to_string()));
+                }
             }
-        };
+            let order_id = format!("test-{}", self.next_id.fetch_add(1, Ordering::SeqCst));
+            let ordered_price = price.unwrap_or_else(|| Decimal::ONE);
+            self.calls
+                .lock()
+                .unwrap()
+                .push((symbol.to_string(), size, side, price, reduce_only));
+            Ok(CreateOrderResponse {
+                order_id,
+                exchange_order_id: None,
+                ordered_price,
+                ordered_size: size,
+                client_order_id: None,
+            })
+        }
 
-        let matches_order = |order_id: &str| {
-            order_id == res_a.order_id
-                || res_a
-                    .exchange_order_id
-                    .as_ref()
-                    .map_or(false, |id| order_id == id)
-        };
-        let Some(filled_order) = filled_orders
-            .orders
-            .iter()
-            .find(|o| matches_order(&o.order_id))
-        else {
-            return;
-        };
-        let filled_size = filled_order.filled_size.unwrap_or(Decimal::ZERO);
-        if filled_size <= Decimal::ZERO {
-            return;
+        async fn create_advanced_trigger_order(
+            &self,
+            _symbol: &str,
+            _size: Decimal,
+            _side: OrderSide,
+            _trigger_px: Decimal,
+            _limit_px: Option<Decimal>,
+            _order_style: TriggerOrderStyle,
+            _slippage_bps: Option<u32>,
+            _tpsl: TpSl,
+            _reduce_only: bool,
+            _expiry_secs: Option<u64>,
+        ) -> Result<CreateOrderResponse, DexError> {
+            Err(DexError::Other("not used".to_string()))
         }
 
-        log::warn!(
-            "[SAFETY] Leg A {} was filled for {}. Hedging immediately.",
-            res_a.order_id,
-            pair.base
-        );
-        let hedge_side = match side_a {
-            dex_connector::OrderSide::Long => dex_connector::OrderSide::Short,
-            dex_connector::OrderSide::Short => dex_connector::OrderSide::Long,
-        };
-        if let Err(hedge_err) = self
-            .connector
-            .create_order(&pair.base, filled_size, hedge_side, None, None, true, None)
-            .await
-        {
-            log::error!(
-                "[SAFETY] FAILED TO HEDGE partial fill for {}: {:?}",
-                pair.base,
-                hedge_err
-            );
-        } else {
-            log::info!(
-                "[SAFETY] Successfully submitted hedge order for partial fill on {}",
-                pair.base
-            );
+        async fn cancel_order(&self, _symbol: &str, _order_id: &str) -> Result<(), DexError> {
+            Ok(())
         }
-    }
 
-    async fn close_pair_orders(
-        &mut self,
-        pair: &PairSpec,
-        direction: PositionDirection,
-        qtys: (Decimal, Decimal),
-        prices: &HashMap<String, SymbolSnapshot>,
-        use_market: bool,
-    ) -> Result<Vec<PendingLeg>> {
-        let (side_a, side_b) = match direction {
-            PositionDirection::LongSpread => (
-                dex_connector::OrderSide::Short,
-                dex_connector::OrderSide::Long,
-            ),
-            PositionDirection::ShortSpread => (
-                dex_connector::OrderSide::Long,
-                dex_connector::OrderSide::Short,
-            ),
-        };
-        let ref_price_a = self.order_reference_price(&pair.base, side_a, prices);
-        let ref_price_b = self.order_reference_price(&pair.quote, side_b, prices);
-        let qty_a = self.quantize_order_size_close(&pair.base, qtys.0, prices);
-        let qty_b = self.quantize_order_size_close(&pair.quote, qtys.1, prices);
-        if qty_a != qtys.0 {
-            log::debug!(
-                "[ORDER_ADJUST][EXIT] {} settled qty_a {} -> {}",
-                pair.base,
-                qtys.0,
-                qty_a
-            );
+        async fn cancel_all_orders(&self, _symbol: Option<String>) -> Result<(), DexError> {
+            Ok(())
         }
-        if qty_b != qtys.1 {
-            log::debug!(
-                "[ORDER_ADJUST][EXIT] {} settled qty_b {} -> {}",
-                pair.quote,
-                qtys.1,
-                qty_b
-            );
+
+        async fn cancel_orders(
+            &self,
+            _symbol: Option<String>,
+            _order_ids: Vec<String>,
+        ) -> Result<(), DexError> {
+            Ok(())
         }
-        let limit_a = if use_market {
-            None
-        } else {
-            self.limit_price_for(&pair.base, side_a, prices)
-        };
-        let limit_b = if use_market {
-            None
-        } else {
-            self.limit_price_for(&pair.quote, side_b, prices)
-        };
-        let post_only = !use_market && self.should_post_only();
-        log::debug!(
-            "[ORDER_PARAMS][EXIT] pair={}/{} side_a={:?} qty_a={} ref_price_a={} limit_a={:?} side_b={:?} qty_b={} ref_price_b={} limit_b={:?} post_only={}",
-            pair.base,
-            pair.quote,
-            side_a,
-            qty_a,
-            ref_price_a.unwrap_or(Decimal::ZERO),
-            limit_a,
-            side_b,
-            qty_b,
-            ref_price_b.unwrap_or(Decimal::ZERO),
-            limit_b,
-            post_only
-        );
-        let mut legs: Vec<PendingLeg> = Vec::new();
-        let mut res_a = None;
-        if qty_a > Decimal::ZERO {
-            let res = if use_market {
-                self.connector
-                    .create_order(&pair.base, qty_a, side_a, None, None, true, None)
-                    .await
-            } else {
-                self.create_order_with_post_only_retry(
-                    &pair.base,
-                    qty_a,
-                    side_a,
-                    true,
-                    prices,
-                    true,
-                    POST_ONLY_EXIT_ATTEMPTS,
-                    true,
-                )
-                .await
-            };
-            match res {
-                Ok(res) => {
-                    if res.ordered_size > Decimal::ZERO && res.ordered_size != qty_a {
-                        log::debug!(
-                            "[ORDER_PARAMS][EXIT] size adjusted by exchange for {}: requested={} ordered={}",
-                            pair.base,
-                            qty_a,
-                            res.ordered_size
-                        );
-                    }
-                    legs.push(PendingLeg {
-                        symbol: pair.base.clone(),
-                        order_id: res.order_id.clone(),
-                        exchange_order_id: res.exchange_order_id.clone(),
-                        target: qty_a,
-                        filled: Decimal::ZERO,
-                        side: side_a,
-                        limit_price: None,
-                    });
-                    res_a = Some(res);
-                }
-                Err(err) => {
-                    if Self::is_reduce_only_position_missing_error(&err) {
-                        let symbol = pair.base.clone();
-                        if self.confirm_reduce_only_position_missing(&symbol).await {
-                            log::info!(
-                                "[ORDER] {} reduce-only close skipped; position already closed",
-                                symbol
-                            );
-                        } else {
-                            return Err(err).context("close leg A");
-                        }
-                    } else {
-                        return Err(err).context("close leg A");
-                    }
-                }
-            }
+
+        async fn close_all_positions(&self, _symbol: Option<String>) -> Result<(), DexError> {
+            self.positions.lock().unwrap().clear();
+            Ok(())
         }
 
-        if qty_b > Decimal::ZERO {
-            let res_b = if use_market {
-                self.connector
-                    .create_order(&pair.quote, qty_b, side_b, None, None, true, None)
-                    .await
-            } else {
-                self.create_order_with_post_only_retry(
-                    &pair.quote,
-                    qty_b,
-                    side_b,
-                    true,
-                    prices,
-                    true,
-                    POST_ONLY_EXIT_ATTEMPTS,
-                    true,
-                )
-                .await
-            };
-            let res_b = match res_b {
-                Ok(res) => Some(res),
-                Err(e) => {
-                    let mut skip = false;
-                    if Self::is_reduce_only_position_missing_error(&e) {
-                        let symbol = pair.quote.clone();
-                        if self.confirm_reduce_only_position_missing(&symbol).await {
-                            log::info!(
-                                "[ORDER] {} reduce-only close skipped; position already closed",
-                                symbol
-                            );
-                            skip = true;
-                        }
-                    }
-                    if skip {
-                        None
-                    } else {
-                        if let Some(ref res_a) = res_a {
-                            self.recover_from_leg_b_failure(pair, res_a, side_a, &e).await;
-                        } else {
-                            log::error!(
-                                "[ORDER] Failed to close leg B for {}/{}: {:?}",
-                                pair.base,
-                                pair.quote,
-                                e
-                            );
-                        }
+        async fn clear_last_trades(&self, symbol: &str) -> Result<(), DexError> {
+            self.trades.lock().unwrap().remove(symbol);
+            Ok(())
+        }
 
-                        return Err(PartialOrderPlacementError::new(legs.clone(), e).into());
-                    }
-                }
-            };
-            if let Some(res_b) = res_b {
-                if res_b.ordered_size > Decimal::ZERO && res_b.ordered_size != qty_b {
-                    log::debug!(
-                        "[ORDER_PARAMS][EXIT] size adjusted by exchange for {}: requested={} ordered={}",
-                        pair.quote,
-                        qty_b,
-                        res_b.ordered_size
-                    );
-                }
-                legs.push(PendingLeg {
-                    symbol: pair.quote.clone(),
-                    order_id: res_b.order_id.clone(),
-                    exchange_order_id: res_b.exchange_order_id.clone(),
-                    target: qty_b,
-                    filled: Decimal::ZERO,
-                    side: side_b,
-                    limit_price: None,
-                });
-            }
+        async fn is_upcoming_maintenance(&self, _hours_ahead: i64) -> bool {
+            false
         }
 
-        if legs.is_empty() {
-            log::warn!(
-                "[ORDER] No exit legs placed for {}/{} (qty_a={}, qty_b={})",
-                pair.base,
-                pair.quote,
-                qty_a,
-                qty_b
-            );
+        async fn sign_evm_65b(&self, _message: &str) -> Result<String, DexError> {
+            Ok("signed".to_string())
         }
-        Ok(legs)
-    }
 
-    fn register_partial_leg_failure(
-        &mut self,
-        inst_idx: usize,
-        key: &str,
-        direction: PositionDirection,
-        err: &anyhow::Error,
-        is_exit: bool,
-    ) {
-        if let Some(partial) = err.downcast_ref::<PartialOrderPlacementError>() {
-            if let Some(state) = self.instances[inst_idx].states.get_mut(key) {
-                let pending = PendingOrders {
-                    legs: partial.legs().to_vec(),
-                    direction,
-                    placed_at: Instant::now(),
-                    hedge_retry_count: 0,
-                    post_only_hybrid: false,
-                };
-                if is_exit {
-                    state.pending_exit = Some(pending);
-                } else {
-                    state.pending_entry = Some(pending);
-                }
-            }
+        async fn sign_evm_65b_with_eip191(&self, _message: &str) -> Result<String, DexError> {
+            Ok("signed".to_string())
         }
     }
 
-    async fn fetch_latest_prices(&mut self) -> Result<HashMap<String, SymbolSnapshot>> {
-        let symbols: Vec<String> = self
-            .cfg
-            .universe
+    #[tokio::test]
+    async fn reissue_partial_entry_leg_reorders_remaining() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        let pending = PendingOrders {
+            legs: vec![PendingLeg {
+                symbol: "AAA".to_string(),
+                order_id: "leg1".to_string(),
+                exchange_order_id: None,
+                target: dec("0.05"),
+                filled: Decimal::ZERO,
+                side: OrderSide::Long,
+                limit_price: None,
+                placed_price: None,
+            }],
+            direction: PositionDirection::LongSpread,
+            placed_at: Instant::now(),
+            hedge_retry_count: 0,
+            post_only_hybrid: false,
+            partial_exit: false,
+            scale_in: false,
+        };
+        let mut price_map = HashMap::new();
+        price_map.insert(
+            "AAA".to_string(),
+            SymbolSnapshot {
+                price: dec("100.0"),
+                funding_rate: Decimal::ZERO,
+                bid_price: None,
+                ask_price: None,
+                bid_size: Decimal::ZERO,
+                ask_size: Decimal::ZERO,
+                min_order: Some(dec("0.001")),
+                min_tick: Some(dec("0.001")),
+                size_decimals: Some(3),
+                exchange_ts: None,
+                depth: Default::default(),
+            },
+        );
+        let filled_qtys = HashMap::from([(pending.legs[0].order_id.clone(), dec("0.02"))]);
+
+        let result = engine
+            .reissue_partial_legs(&pending, &filled_qtys, &price_map, false, false, 0)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.legs.len(), 2);
+        assert!(result
+            .legs
             .iter()
-            .flat_map(|p| [p.base.clone(), p.quote.clone()])
-            .collect::<std::collections::HashSet<_>>()
-            .into_iter()
-            .collect();
+            .any(|leg| leg.target == dec("0.02") && leg.filled == dec("0.02")));
+        assert!(result
+            .legs
+            .iter()
+            .any(|leg| leg.target == dec("0.03") && leg.filled == Decimal::ZERO));
+        {
+            let calls = connector.calls.lock().unwrap();
+            assert_eq!(calls.len(), 1);
+            assert_eq!(calls[0].0, "AAA");
+            assert_eq!(calls[0].3, Some(dec("100.0")));
+            assert!(!calls[0].4);
+        }
 
-        let connector = self.connector.clone();
-        let mut join_set = tokio::task::JoinSet::new();
-        for sym in symbols.iter().cloned() {
-            let conn = connector.clone();
-            join_set.spawn(async move {
-                let (ticker_res, ob_res) = tokio::join!(
-                    conn.get_ticker(&sym, None),
-                    conn.get_order_book(&sym, 1),
-                );
-                (sym, ticker_res, ob_res)
+        // Per-cycle cap reached: the leg is kept for the next tick, not re-placed.
+        engine.reissued_legs_this_cycle = engine.cfg.max_reissued_legs_per_cycle;
+        let deferred = engine
+            .reissue_partial_legs(&pending, &filled_qtys, &price_map, false, false, 0)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(deferred.legs.len(), 1);
+        assert_eq!(deferred.legs[0].order_id, "leg1");
+        assert_eq!(deferred.legs[0].filled, dec("0.02"));
+        assert_eq!(connector.calls.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn entry_past_the_reissue_cap_closes_its_filled_legs() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        engine.cfg.dry_run = false;
+        engine.cfg.max_reissues_per_pair = 2;
+        let leg = |symbol: &str, side| PendingLeg {
+            symbol: symbol.to_string(),
+            order_id: format!("{}-entry", symbol),
+            exchange_order_id: None,
+            target: dec("0.05"),
+            filled: Decimal::ZERO,
+            side,
+            limit_price: Some(dec("100")),
+            placed_price: None,
+        };
+        let mut state = PairState::new(10, 2.0);
+        state.pending_entry = Some(PendingOrders {
+            legs: vec![leg("AAA", OrderSide::Long), leg("BBB", OrderSide::Short)],
+            direction: PositionDirection::LongSpread,
+            placed_at: Instant::now(),
+            hedge_retry_count: 2,
+            post_only_hybrid: false,
+            partial_exit: false,
+            scale_in: false,
+        });
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
+        let mut prefetched = HashMap::from([(
+            ("AAA/BBB".to_string(), true),
+            PendingStatus {
+                open_remaining: 1,
+                fills: HashMap::from([("AAA-entry".to_string(), dec("0.02"))]),
+                fill_values: HashMap::new(),
+                fill_fees: HashMap::new(),
+                open_ids: HashSet::from(["BBB-entry".to_string()]),
+            },
+        )]);
+
+        engine
+            .reconcile_pending_orders(0, "AAA/BBB", &HashMap::new(), &mut prefetched)
+            .await
+            .unwrap();
+        let calls = connector.calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        // The filled part of leg A goes back at market, reduce-only.
+        assert_eq!((calls[0].0.as_str(), calls[0].1), ("AAA", dec("0.02")));
+        assert_eq!(calls[0].2, OrderSide::Short);
+        assert!(calls[0].3.is_none() && calls[0].4);
+        let state = &engine.instances[0].states["AAA/BBB"];
+        assert!(state.pending_entry.is_none());
+        assert!(state.position.is_none());
+    }
+
+    #[tokio::test]
+    async fn prefetch_pending_statuses_respects_reconcile_concurrency() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        for (key, symbol) in [("AAA/BBB", "AAA"), ("CCC/BBB", "CCC")] {
+            let mut state = PairState::new(10, 2.0);
+            state.pending_entry = Some(PendingOrders {
+                legs: vec![PendingLeg {
+                    symbol: symbol.to_string(),
+                    order_id: format!("{}-entry", symbol),
+                    exchange_order_id: None,
+                    target: dec("0.05"),
+                    filled: Decimal::ZERO,
+                    side: OrderSide::Long,
+                    limit_price: None,
+                    placed_price: None,
+                }],
+                direction: PositionDirection::LongSpread,
+                placed_at: Instant::now(),
+                hedge_retry_count: 0,
+                post_only_hybrid: false,
+                partial_exit: false,
+                scale_in: false,
             });
-        }
-        let mut results = Vec::new();
-        while let Some(res) = join_set.join_next().await {
-            results.push(res.expect("fetch task panicked"));
+            engine.instances[0].states.insert(key.to_string(), state);
         }
 
-        let mut map = HashMap::new();
-        for (symbol, ticker_res, ob_res) in results {
-            let ticker = match ticker_res {
-                Ok(ticker) => ticker,
-                Err(e) => {
-                    let msg = e.to_string();
-                    if Self::is_ticker_auth_error(&msg) {
-                        if self.should_log_ticker_warn(&symbol) {
-                            log::warn!("ticker {} unavailable: {}", symbol, msg);
-                            self.last_ticker_warn.insert(symbol.clone(), Instant::now());
-                        } else {
-                            log::debug!("ticker {} unavailable: {}", symbol, msg);
-                        }
-                        continue;
-                    }
-                    return Err(e).with_context(|| format!("ticker {}", symbol));
-                }
-            };
-            let (top_bid_price, top_ask_price, top_bid_size, top_ask_size) = match ob_res {
-                Ok(ob) => (
-                    ob.bids.first().map(|l| l.price),
-                    ob.asks.first().map(|l| l.price),
-                    ob.bids.first().map(|l| l.size).unwrap_or(Decimal::ZERO),
-                    ob.asks.first().map(|l| l.size).unwrap_or(Decimal::ZERO),
-                ),
-                Err(e) => {
-                    let msg = format!("{:?}", e);
-                    let is_stale = msg.contains("order book snapshot unavailable");
-                    if is_stale {
-                        log::debug!("orderbook {} unavailable: {}", symbol, msg);
-                    } else if self.should_log_ob_warn(&symbol) {
-                        log::warn!("orderbook {} unavailable: {}", symbol, msg);
-                        self.last_ob_warn.insert(symbol.clone(), Instant::now());
-                    } else {
-                        log::debug!("orderbook {} unavailable: {}", symbol, msg);
-                    }
-                    (None, None, Decimal::ZERO, Decimal::ZERO)
-                }
-            };
-            if ticker.min_order.is_none() && !self.min_order_warned.contains(&symbol) {
-                let size_decimals_desc = ticker
-                    .size_decimals
-                    .map(|d| d.to_string())
-                    .unwrap_or_else(|| "none".into());
-                log::warn!(
-                    "[TICKER] {} missing min_order (size_decimals={}); using fallback step",
-                    symbol,
-                    size_decimals_desc
-                );
-                self.min_order_warned.insert(symbol.clone());
-            }
-            if ticker.min_tick.is_none() && !self.min_tick_warned.contains(&symbol) {
-                let min_tick_desc = ticker
-                    .min_tick
-                    .map(|t| t.to_string())
-                    .unwrap_or_else(|| "none".into());
-                log::warn!(
-                    "[TICKER] {} missing min_tick (ticker reports {}); price will be rounded with fallback",
-                    symbol,
-                    min_tick_desc
-                );
-                self.min_tick_warned.insert(symbol.clone());
-            }
-            map.insert(
-                symbol.clone(),
-                SymbolSnapshot {
-                    price: ticker.price,
-                    funding_rate: ticker.funding_rate.unwrap_or(Decimal::ZERO),
-                    bid_price: top_bid_price,
-                    ask_price: top_ask_price,
-                    bid_size: top_bid_size,
-                    ask_size: top_ask_size,
-                    min_order: ticker.min_order,
-                    min_tick: ticker.min_tick,
-                    size_decimals: ticker.size_decimals,
-                    exchange_ts: ticker.exchange_ts.map(|v| v as i64),
-                },
-            );
-            log::debug!(
-                "[PRICE_SNAPSHOT] {} price={} bid={:?} ask={:?} bid_sz={} ask_sz={} min_order={:?} min_tick={:?}",
-                symbol,
-                ticker.price,
-                top_bid_price,
-                top_ask_price,
-                top_bid_size,
-                top_ask_size,
-                ticker.min_order,
-                ticker.min_tick
-            );
-        }
-        Ok(map)
+        // Sequential (default): nothing prefetched, no connector calls.
+        engine.cfg.reconcile_concurrency = 1;
+        assert!(engine.prefetch_pending_statuses(0).await.is_empty());
+        assert_eq!(connector.open_order_calls.load(Ordering::SeqCst), 0);
+
+        engine.cfg.reconcile_concurrency = 4;
+        let prefetched = engine.prefetch_pending_statuses(0).await;
+        assert_eq!(prefetched.len(), 2);
+        assert!(prefetched.contains_key(&("AAA/BBB".to_string(), true)));
+        assert!(prefetched.contains_key(&("CCC/BBB".to_string(), true)));
+        assert_eq!(connector.open_order_calls.load(Ordering::SeqCst), 2);
+
+        // The serial pass consumes the prefetched status instead of refetching.
+        let mut prefetched = prefetched;
+        engine
+            .reconcile_pending_orders(0, "AAA/BBB", &HashMap::new(), &mut prefetched)
+            .await
+            .unwrap();
+        assert!(!prefetched.contains_key(&("AAA/BBB".to_string(), true)));
+        assert_eq!(connector.open_order_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn startup_close_status_clears_once_positions_are_closed() {
+        let connector = Arc::new(DummyConnector::default());
+        connector.positions.lock().unwrap().push(PositionSnapshot {
+            symbol: "AAA".to_string(),
+            size: dec("1"),
+            sign: 1,
+            ..Default::default()
+        });
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        engine.cfg.dry_run = false;
+        engine.cfg.startup_force_close_attempts = 3;
+        engine.cfg.startup_force_close_wait_secs = 0;
+        let dir = tempfile::tempdir().unwrap();
+        let reporter = StatusReporter::new(&engine.cfg, None, dir.path().join("status.json"));
+        engine.instances[0].status_reporter = Some(reporter);
+
+        // Attempt 1 publishes progress and closes; attempt 2 sees a flat
+        // book and clears the status instead of leaving it behind.
+        engine.force_close_on_startup().await.unwrap();
+        assert!(connector.positions.lock().unwrap().is_empty());
+        let reporter = engine.instances[0].status_reporter.as_ref().unwrap();
+        assert!(reporter.startup_close.is_none());
+    }
+
+    #[tokio::test]
+    async fn mismatched_leg_flatten_is_spaced_by_its_own_timestamp() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        let clock = Arc::new(clock::ManualClock::new());
+        engine.clock = clock.clone();
+        engine.cfg.dry_run = false;
+        engine.instances[0]
+            .states
+            .insert("AAA/BBB".to_string(), PairState::new(10, 2.0));
+        let leg = |symbol: &str| PositionSnapshot {
+            symbol: symbol.to_string(),
+            size: dec("1"),
+            sign: 1,
+            ..Default::default()
+        };
+        let legs = [leg("AAA"), leg("BBB")];
+        let prices = HashMap::new();
+
+        engine.flatten_mismatched_legs(0, "AAA/BBB", &legs, &prices).await;
+        assert_eq!(connector.calls.lock().unwrap().len(), 2);
+        assert!(connector.calls.lock().unwrap().iter().all(|c| c.4));
+        // Not an exit: the entry cooldown clock is left alone.
+        let state = &engine.instances[0].states["AAA/BBB"];
+        assert!(state.last_exit_at.is_none() && state.last_exit_ts.is_none());
+        assert!(state.last_mismatch_flatten_at.is_some());
+
+        engine.flatten_mismatched_legs(0, "AAA/BBB", &legs, &prices).await;
+        assert_eq!(connector.calls.lock().unwrap().len(), 2);
+        clock.advance(Duration::from_secs(31));
+        engine.flatten_mismatched_legs(0, "AAA/BBB", &legs, &prices).await;
+        assert_eq!(connector.calls.lock().unwrap().len(), 4);
     }
-}
 
-#[cfg(test)]
-impl PairTradeEngine {
-    fn test_instance(connector: Arc<dyn DexConnector + Send + Sync>) -> Self {
-        let cfg = PairTradeConfig {
-            dex_name: "test".to_string(),
-            rest_endpoint: "http://localhost".to_string(),
-            web_socket_endpoint: "ws://localhost".to_string(),
-            dry_run: true,
-            agent_name: None,
-            interval_secs: 1,
-            trading_period_secs: 1,
-            metrics_window: 1,
-            net_funding_min_per_hour: 0.0,
-            notional_per_leg: 1.0,
-            risk_pct_per_trade: 0.01,
-            equity_reference_usd: DEFAULT_EQUITY_USD,
-            universe: vec![PairSpec {
-                base: "AAA".to_string(),
-                quote: "BBB".to_string(),
-            }],
-            slippage_bps: 0,
-            fee_bps: 0.0,
-            max_leverage: 1.0,
-            max_active_pairs: 1,
-            warm_start_mode: WarmStartMode::Strict,
-            order_timeout_secs: DEFAULT_ORDER_TIMEOUT_SECS,
-            entry_partial_fill_max_retries: DEFAULT_ENTRY_PARTIAL_FILL_MAX_RETRIES,
-            startup_force_close_attempts: DEFAULT_STARTUP_FORCE_CLOSE_ATTEMPTS,
-            startup_force_close_wait_secs: DEFAULT_STARTUP_FORCE_CLOSE_WAIT_SECS,
-            force_close_on_startup: false,
-            enable_data_dump: false,
-            data_dump_file: None,
-            observe_only: false,
-            disable_history_persist: true,
-            history_file: "test-history.json".to_string(),
-            history_archive_dir: None,
-            history_archive_retention_days: 14,
-            backtest_mode: false,
-            backtest_file: None,
-            bt_warm_start_snapshot: None,
-            bt_eval_timestamps: None,
-            bt_restart_timestamps: None,
-            circuit_breaker_consecutive_losses: DEFAULT_CIRCUIT_BREAKER_CONSECUTIVE_LOSSES,
-            circuit_breaker_cooldown_secs: DEFAULT_CIRCUIT_BREAKER_COOLDOWN_SECS,
-            shutdown_grace_secs: 0,
-            pair_params: HashMap::new(),
-            default_pair_params: PairParams {
-                entry_z_base: 2.0,
-                entry_z_min: 1.8,
-                entry_z_max: 2.3,
-                exit_z: 0.5,
-                stop_loss_z: 3.0,
-                force_close_secs: 60,
-                cooldown_secs: 1,
-                max_loss_r_mult: 1.0,
-                half_life_max_hours: 1.0,
-                adf_p_threshold: 0.05,
-                spread_velocity_max_sigma_per_min: 0.1,
-                lookback_hours_short: 1,
-                lookback_hours_long: 1,
-                entry_vol_lookback_hours: 1,
-                warm_start_min_bars: 1,
-                hedge_ratio_max_deviation: 1.0,
-                ..PairParams::default()
-            },
-            strategies: Vec::new(),
-            use_kalman_beta: DEFAULT_USE_KALMAN_BETA,
-            kalman_q: DEFAULT_KALMAN_Q,
-            kalman_r: DEFAULT_KALMAN_R,
-            kalman_initial_p: DEFAULT_KALMAN_INITIAL_P,
-            kalman_min_updates: DEFAULT_KALMAN_MIN_UPDATES,
-            regime_vol_window: DEFAULT_REGIME_VOL_WINDOW,
-            regime_vol_max: DEFAULT_REGIME_VOL_MAX,
-            regime_trend_window: DEFAULT_REGIME_TREND_WINDOW,
-            regime_trend_max: DEFAULT_REGIME_TREND_MAX,
-            regime_reference_symbol: DEFAULT_REGIME_REFERENCE_SYMBOL.to_string(),
-            bt_fill_delay_secs: 0,
-            risk: config::RiskConfig::default(),
+    #[tokio::test]
+    async fn beta_drift_rehedge_resizes_leg_b_to_the_new_beta() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        engine.cfg.dry_run = false;
+        let mut state = PairState::new(10, 2.0);
+        state.position = Some(Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts: 0,
+            entry_price_a: Some(dec("100")),
+            entry_price_b: Some(dec("50")),
+            entry_size_a: Some(dec("1")),
+            entry_size_b: Some(dec("2")),
+            entry_z: None,
+            entry_beta: Some(1.0),
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        });
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
+        let pair = engine.cfg.universe[0].clone();
+        let prices = HashMap::from([
+            ("AAA".to_string(), SymbolSnapshot::test_at(dec("100"))),
+            ("BBB".to_string(), SymbolSnapshot::test_at(dec("40"))),
+        ]);
+        let position = |engine: &PairTradeEngine| {
+            engine.instances[0].states["AAA/BBB"].position.clone().unwrap()
         };
 
-        let history_path = PathBuf::from(cfg.history_file.as_str());
-        let risk_state_path = risk_state_path_for(&history_path);
+        // A failed order leaves the position for the next tick.
+        connector.order_failures.lock().unwrap().insert("BBB".to_string(), 1);
+        engine
+            .rehedge_position(0, "AAA/BBB", &pair, 1.2, &prices, "[BETA_DRIFT]")
+            .await;
+        assert_eq!(position(&engine).entry_size_b, Some(dec("2")));
+        assert_eq!(position(&engine).entry_beta, Some(1.0));
 
-        Self {
-            cfg,
-            connector: connector.clone(),
-            instances: vec![StrategyInstance {
-                id: "default".to_string(),
-                connector,
-                equity_cache: DEFAULT_EQUITY_USD,
-                last_equity_fetch: None,
-                equity_reference_usd: DEFAULT_EQUITY_USD,
-                states: HashMap::new(),
-                pnl_logger: None,
-                status_reporter: None,
-                consecutive_losses: 0,
-                circuit_breaker_until: None,
-                circuit_breaker_until_ts: None,
-                session_start_equity: 0.0,
-                session_start_ts: 0,
-                realized_pnl_today: 0.0,
-                daily_loss_halted: false,
-                equity_samples: Vec::new(),
-                session_halted: false,
-                session_halt_reason: None,
-                session_halt_ts: None,
-                total_trades: 0,
-                total_wins: 0,
-                total_pnl: 0.0,
-                peak_pnl: 0.0,
-                max_dd: 0.0,
-                pair_params: HashMap::new(),
-                default_pair_params: PairParams::default(),
-            }],
-            history: HashMap::new(),
-            bar_builders: HashMap::new(),
-            last_metrics_log: None,
-            last_ob_warn: HashMap::new(),
-            last_ticker_warn: HashMap::new(),
-            last_position_warn: HashMap::new(),
-            min_order_warned: HashSet::new(),
-            min_tick_warned: HashSet::new(),
-            positions_ready: false,
-            open_positions: HashMap::new(),
-            last_account_rest_call: None,
-            history_path,
-            risk_state_path,
-            kill_switch_active: false,
-            data_dump_writer: None,
-            replay_connector: None,
-            shutdown_pending: false,
+        // Beta 1.2 at 100 / 40 wants 3 BBB: sell one more, not reduce-only,
+        // and average it into the entry price.
+        engine
+            .rehedge_position(0, "AAA/BBB", &pair, 1.2, &prices, "[BETA_DRIFT]")
+            .await;
+        {
+            let calls = connector.calls.lock().unwrap();
+            assert_eq!(calls.len(), 1);
+            assert_eq!((calls[0].0.as_str(), calls[0].1), ("BBB", dec("1")));
+            assert_eq!(calls[0].2, OrderSide::Short);
+            assert!(!calls[0].4);
+        }
+        let pos = position(&engine);
+        assert_eq!(pos.entry_size_b, Some(dec("3")));
+        assert_eq!(pos.entry_price_b, Some(dec("140") / dec("3")));
+        assert_eq!(pos.entry_beta, Some(1.2));
+
+        // Back to beta 0.8 wants 2 BBB: buy one back reduce-only.
+        engine
+            .rehedge_position(0, "AAA/BBB", &pair, 0.8, &prices, "[BETA_DRIFT]")
+            .await;
+        {
+            let calls = connector.calls.lock().unwrap();
+            assert_eq!(calls.len(), 2);
+            assert_eq!((calls[1].0.as_str(), calls[1].1), ("BBB", dec("1")));
+            assert_eq!(calls[1].2, OrderSide::Long);
+            assert!(calls[1].4);
+        }
+        let pos = position(&engine);
+        assert_eq!(pos.entry_size_b, Some(dec("2")));
+        assert_eq!(pos.entry_price_b, Some(dec("140") / dec("3")));
+        assert_eq!(pos.entry_beta, Some(0.8));
+    }
+
+    #[tokio::test]
+    async fn hedge_ratio_beyond_tolerance_rehedges_leg_b() {
+        let connector = Arc::new(DummyConnector::default());
+        let leg = |symbol: &str, size: &str, sign| PositionSnapshot {
+            symbol: symbol.to_string(),
+            size: dec(size),
+            sign,
+            ..Default::default()
+        };
+        // Beta 1 at 100 / 50 wants 2 BBB per AAA; the book holds 2.5.
+        *connector.positions.lock().unwrap() = vec![leg("AAA", "1", 1), leg("BBB", "2.5", -1)];
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        engine.cfg.dry_run = false;
+        engine.cfg.hedge_ratio_tolerance_pct = 5.0;
+        let mut state = PairState::new(10, 2.0);
+        state.beta = 1.0;
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
+        let prices = HashMap::from([
+            ("AAA".to_string(), SymbolSnapshot::test_at(dec("100"))),
+            ("BBB".to_string(), SymbolSnapshot::test_at(dec("50"))),
+        ]);
+
+        engine.sync_positions_from_exchange(0, &prices).await.unwrap();
+        {
+            let calls = connector.calls.lock().unwrap();
+            assert_eq!(calls.len(), 1);
+            // Trim the short leg: buy back 0.5 BBB reduce-only.
+            assert_eq!(calls[0].0, "BBB");
+            assert_eq!(calls[0].1, dec("0.5"));
+            assert_eq!(calls[0].2, OrderSide::Long);
+            assert!(calls[0].4);
+        }
+        let pos = engine.instances[0].states["AAA/BBB"].position.clone().unwrap();
+        assert_eq!(pos.entry_size_b, Some(dec("2.0")));
+
+        // Within tolerance nothing is sent.
+        engine.last_position_warn.clear();
+        *connector.positions.lock().unwrap() = vec![leg("AAA", "1", 1), leg("BBB", "2.05", -1)];
+        engine.sync_positions_from_exchange(0, &prices).await.unwrap();
+        assert_eq!(connector.calls.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn adopted_pair_recovers_entry_time_from_risk_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let connector = Arc::new(DummyConnector::default());
+        let leg = |symbol: &str, sign| PositionSnapshot {
+            symbol: symbol.to_string(),
+            size: dec("1"),
+            sign,
+            ..Default::default()
+        };
+        *connector.positions.lock().unwrap() = vec![leg("AAA", 1), leg("BBB", -1)];
+        let mut engine = PairTradeEngine::test_instance(connector);
+        engine.risk_state_path = dir.path().join("risk_state.json");
+        let mut state = PairState::new(10, 2.0);
+        state.pending_entry = Some(PendingOrders {
+            legs: Vec::new(),
+            direction: PositionDirection::LongSpread,
+            placed_at: engine.clock.now(),
+            hedge_retry_count: 0,
+            post_only_hybrid: false,
+            partial_exit: false,
+            scale_in: false,
+        });
+        state.last_entry_ts = Some(1_000);
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
+        engine.persist_risk_state();
+
+        // Restart: the pending entry is gone, the exchange holds both legs.
+        engine.instances[0]
+            .states
+            .insert("AAA/BBB".to_string(), PairState::new(10, 2.0));
+        engine.load_risk_state();
+        let entered_ts = |engine: &PairTradeEngine| {
+            engine.instances[0].states["AAA/BBB"].position.as_ref().unwrap().entered_ts
+        };
+
+        engine.sync_positions_from_exchange(0, &HashMap::new()).await.unwrap();
+        assert!(entered_ts(&engine) > 1_000);
+
+        engine.cfg.recover_entry_time_from_risk_state = true;
+        engine.instances[0].states.get_mut("AAA/BBB").unwrap().position = None;
+        engine.sync_positions_from_exchange(0, &HashMap::new()).await.unwrap();
+        assert_eq!(entered_ts(&engine), 1_000);
+    }
+
+    #[test]
+    fn status_sizes_use_size_decimals_not_price_dp() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        engine.cfg.status_price_dp = Some(1);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        let mut reporter = StatusReporter::new(&engine.cfg, None, path.clone());
+        reporter.update_size_decimals([("AAA".to_string(), 3)]);
+        let positions = HashMap::from([(
+            "AAA".to_string(),
+            PositionSnapshot {
+                symbol: "AAA".to_string(),
+                size: dec("0.123456"),
+                sign: 1,
+                entry_price: Some(dec("101.26")),
+            },
+        )]);
+
+        reporter.write_snapshot(&positions, true).unwrap();
+        let status: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(status["positions"][0]["size"], "0.123");
+        assert_eq!(status["positions"][0]["entry_price"], "101.3");
+    }
+
+    #[tokio::test]
+    async fn trade_ticks_are_not_folded_twice() {
+        let connector = Arc::new(DummyConnector::default());
+        let engine = PairTradeEngine::test_instance(connector.clone());
+        connector
+            .trades
+            .lock()
+            .unwrap()
+            .insert("AAA".to_string(), vec![dec("100"), dec("0"), dec("101")]);
+        let symbols = ["AAA".to_string(), "BBB".to_string()];
+
+        let ticks = engine.fetch_trade_ticks(symbols.iter()).await;
+        assert_eq!(ticks.len(), 1);
+        assert_eq!(ticks["AAA"], vec![dec("100"), dec("101")]);
+
+        // Already-read prints are gone; only new ones come back.
+        assert!(engine.fetch_trade_ticks(symbols.iter()).await.is_empty());
+        connector.trades.lock().unwrap().insert("AAA".to_string(), vec![dec("102")]);
+        let ticks = engine.fetch_trade_ticks(symbols.iter()).await;
+        assert_eq!(ticks["AAA"], vec![dec("102")]);
+    }
+
+    #[tokio::test]
+    async fn full_sync_keeps_pairs_sharing_a_leg() {
+        let replay = Arc::new(ReplayConnector::empty());
+        let mut engine = PairTradeEngine::test_instance(replay.clone());
+        engine.replay_connector = Some(replay);
+        engine.cfg.backtest_mode = true;
+        engine.cfg.backtest_full_sync = true;
+        let pair = |base: &str, quote: &str| PairSpec {
+            base: base.to_string(),
+            quote: quote.to_string(),
+        };
+        let mut new_cfg = engine.cfg.clone();
+        new_cfg.universe = vec![pair("AAA", "BBB"), pair("AAA", "CCC")];
+        engine.connector_symbols.insert("CCC".to_string());
+        engine.apply_universe_reload(&new_cfg);
+        // Long AAA on one pair, short AAA on the other: AAA nets to zero.
+        for (key, direction) in [
+            ("AAA/BBB", PositionDirection::LongSpread),
+            ("AAA/CCC", PositionDirection::ShortSpread),
+        ] {
+            let state = engine.instances[0]
+                .states
+                .entry(key.to_string())
+                .or_insert_with(|| PairState::new(10, 2.0));
+            state.position = Some(Position {
+                direction,
+                entered_at: Instant::now(),
+                entered_ts: 0,
+                entry_price_a: Some(dec("100")),
+                entry_price_b: Some(dec("50")),
+                entry_size_a: Some(dec("1")),
+                entry_size_b: Some(dec("2")),
+                entry_z: None,
+                entry_beta: Some(1.0),
+                entry_fill_cash: None,
+                partial_taken: false,
+                scaled_in: false,
+                scale_in_add: None,
+                hedge_inverted: false,
+            });
         }
+        let snap = |price: &str| SymbolSnapshot::test_at(dec(price));
+        let prices = HashMap::from([
+            ("AAA".to_string(), snap("100")),
+            ("BBB".to_string(), snap("50")),
+            ("CCC".to_string(), snap("50")),
+        ]);
+
+        engine.step_for_instance(0, &prices, &HashSet::new()).await.unwrap();
+        assert!(engine.positions_ready);
+        for (key, direction) in [
+            ("AAA/BBB", PositionDirection::LongSpread),
+            ("AAA/CCC", PositionDirection::ShortSpread),
+        ] {
+            let state = &engine.instances[0].states[key];
+            let position = state.position.as_ref().expect(key);
+            assert_eq!(position.direction, direction);
+            assert_eq!(position.entry_size_a, Some(dec("1")));
+            assert!(!state.position_guard, "{}", key);
+        }
+        // The connector's own book is still the net one.
+        assert!(!engine.open_positions.contains_key("AAA"));
+        assert_eq!(engine.open_positions.len(), 2);
     }
-}
 
-/// Compute `risk_state.json` path as a sibling of the history file. When
-/// `history_path` is a bare filename (the production default), this falls
-/// back to a relative path resolved against CWD — which is `/opt/debot/`
-/// under the systemd unit.
-fn risk_state_path_for(history_path: &std::path::Path) -> PathBuf {
-    match history_path.parent().filter(|p| !p.as_os_str().is_empty()) {
-        Some(dir) => dir.join("risk_state.json"),
-        None => PathBuf::from("risk_state.json"),
+    #[tokio::test]
+    async fn open_order_audit_keeps_account_call_spacing() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        let clock = Arc::new(clock::ManualClock::new());
+        engine.clock = clock.clone();
+        engine.cfg.dry_run = false;
+        engine.cfg.max_open_orders = 10;
+        let calls = || connector.open_order_calls.load(Ordering::SeqCst);
+
+        // A balance fetch just went out: the audit waits for a later step.
+        engine.last_account_rest_call = Some(clock.now());
+        engine.audit_open_orders(0).await;
+        assert_eq!(calls(), 0);
+
+        clock.advance(Duration::from_secs(6));
+        engine.audit_open_orders(0).await;
+        assert_eq!(calls(), 2);
+        assert_eq!(engine.last_account_rest_call, Some(clock.now()));
+
+        // Then it holds to its own interval.
+        clock.advance(Duration::from_secs(10));
+        engine.audit_open_orders(0).await;
+        assert_eq!(calls(), 2);
     }
-}
 
-/// Bucket a UNIX-seconds timestamp into a "session day" where day
-/// boundaries fall at `reset_hour:00` UTC instead of midnight. Two
-/// timestamps share a session day iff the function returns the same
-/// value for both. `div_euclid` keeps the math correct for non-zero
-/// reset hours near pre-epoch timestamps (impossible in practice but
-/// cheap to preserve). See bot-strategy#185 Phase 2.
-fn session_day(ts_secs: i64, reset_hour: u32) -> i64 {
-    let shift = (reset_hour as i64) * 3600;
-    (ts_secs - shift).div_euclid(86400)
-}
+    #[tokio::test]
+    async fn reissue_partial_entry_missing_price_keeps_pending() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        let pending = PendingOrders {
+            legs: vec![PendingLeg {
+                symbol: "AAA".to_string(),
+                order_id: "leg1".to_string(),
+                exchange_order_id: None,
+                target: dec("0.05"),
+                filled: Decimal::ZERO,
+                side: OrderSide::Long,
+                limit_price: None,
+                placed_price: None,
+            }],
+            direction: PositionDirection::LongSpread,
+            placed_at: Instant::now(),
+            hedge_retry_count: 0,
+            post_only_hybrid: false,
+            partial_exit: false,
+            scale_in: false,
+        };
+        let filled_qtys = HashMap::from([(pending.legs[0].order_id.clone(), dec("0.02"))]);
 
+        let result = engine
+            .reissue_partial_legs(&pending, &filled_qtys, &HashMap::new(), false, false, 0)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.legs.len(), 1);
+        assert_eq!(result.legs[0].target, dec("0.05"));
+        assert_eq!(result.legs[0].filled, dec("0.02"));
+    }
 
-#[derive(Serialize)]
-struct DataDumpEntry<'a> {
-    timestamp: i64,
-    prices: &'a HashMap<String, SymbolSnapshot>,
-}
+    #[tokio::test]
+    async fn refresh_equity_if_needed_skips_when_cache_is_fresh() {
+        let connector = Arc::new(DummyConnector::default());
+        *connector.balance_equity.lock().unwrap() = Some(dec("1234.56"));
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        engine.instances[0].last_equity_fetch = Some(Instant::now());
+        let initial_equity = engine.instances[0].equity_cache;
 
+        engine.refresh_equity_if_needed(0).await.unwrap();
 
+        assert_eq!(connector.balance_calls.load(Ordering::SeqCst), 0);
+        assert_eq!(engine.instances[0].equity_cache, initial_equity);
+    }
 
+    #[tokio::test]
+    async fn refresh_equity_if_needed_fetches_when_cache_is_stale() {
+        let connector = Arc::new(DummyConnector::default());
+        *connector.balance_equity.lock().unwrap() = Some(dec("1234.56"));
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        engine.instances[0].last_equity_fetch = Some(
+            Instant::now() - Duration::from_secs(EQUITY_REFRESH_CACHE_SECS + 1),
+        );
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use super::util::{enforce_post_only_passive, quantize_size_by_step, quantize_size_by_step_ceiling};
-    use rust_decimal::Decimal;
-    use std::str::FromStr;
+        engine.refresh_equity_if_needed(0).await.unwrap();
 
-    fn dec(value: &str) -> Decimal {
-        Decimal::from_str(value).unwrap()
+        assert_eq!(connector.balance_calls.load(Ordering::SeqCst), 1);
+        assert!((engine.instances[0].equity_cache - 1234.56).abs() < 1e-6);
     }
 
-    #[test]
-    fn round_price_by_tick_rounds_long_down() {
-        let price = dec("100.123");
-        let step = dec("0.01");
-        let quantized = round_price_by_tick(price, step, dex_connector::OrderSide::Long);
-        assert_eq!(quantized, dec("100.12"));
-    }
+    #[tokio::test]
+    async fn stale_pending_exit_is_force_flattened_past_max_age() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        let clock = Arc::new(clock::ManualClock::new());
+        engine.clock = clock.clone();
+        let mut state = PairState::new(10, 2.0);
+        state.pending_exit = Some(PendingOrders {
+            legs: vec![PendingLeg {
+                symbol: "AAA".to_string(),
+                order_id: "exit1".to_string(),
+                exchange_order_id: None,
+                target: dec("0.05"),
+                filled: Decimal::ZERO,
+                side: OrderSide::Short,
+                limit_price: None,
+                placed_price: None,
+            }],
+            direction: PositionDirection::LongSpread,
+            placed_at: clock.now(),
+            hedge_retry_count: 0,
+            post_only_hybrid: false,
+            partial_exit: false,
+            scale_in: false,
+        });
+        state.pending_since = Some(clock.now());
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
 
-    #[test]
-    fn round_price_by_tick_rounds_short_up() {
-        let price = dec("100.123");
-        let step = dec("0.01");
-        let quantized = round_price_by_tick(price, step, dex_connector::OrderSide::Short);
-        assert_eq!(quantized, dec("100.13"));
-    }
+        clock.advance(Duration::from_secs(engine.cfg.pending_max_age_secs));
+        engine
+            .reconcile_pending_orders(0, "AAA/BBB", &HashMap::new(), &mut HashMap::new())
+            .await
+            .unwrap();
 
-    #[test]
-    fn round_price_by_tick_enforces_minimum_step() {
-        let price = dec("0.0001");
-        let step = dec("0.005");
-        let quantized = round_price_by_tick(price, step, dex_connector::OrderSide::Long);
-        assert_eq!(quantized, step);
+        let state = &engine.instances[0].states["AAA/BBB"];
+        assert!(state.pending_exit.is_none());
+        assert!(state.pending_since.is_none());
+        let calls = connector.calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].0, "AAA");
+        assert_eq!(calls[0].1, dec("0.05"));
+        assert_eq!(calls[0].3, None);
+        assert!(calls[0].4);
     }
 
-    // bot-strategy#216: post-only passive enforcement
-    #[test]
-    fn post_only_passive_long_extended_btc_at_touch() {
-        // Extended BTC tick=1, ask=77641, ToNegativeInfinity rounding leaves
-        // limit at touch (no-op). Must shift to ask - 1 tick.
-        let rounded = dec("77641");
-        let touch = dec("77641");
-        let tick = dec("1");
-        let limit = enforce_post_only_passive(rounded, touch, tick, dex_connector::OrderSide::Long);
-        assert_eq!(limit, dec("77640"));
+    #[tokio::test]
+    async fn negative_beta_pair_trades_both_legs_on_one_side() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        let pair = PairSpec {
+            base: "AAA".to_string(),
+            quote: "BBB".to_string(),
+        };
+        let snap = |price: &str| SymbolSnapshot {
+            min_order: Some(dec("0.001")),
+            min_tick: Some(dec("0.001")),
+            size_decimals: Some(3),
+            ..SymbolSnapshot::test_at(dec(price))
+        };
+        let price_map = HashMap::from([
+            ("AAA".to_string(), snap("100.0")),
+            ("BBB".to_string(), snap("50.0")),
+        ]);
+
+        // Closing a long spread entered on a negative beta sells both legs.
+        let legs = engine
+            .close_pair_orders(
+                &pair,
+                PositionDirection::LongSpread.exit_sides(true),
+                (dec("0.1"), dec("0.2")),
+                &price_map,
+                true,
+                "exit_z",
+            )
+            .await
+            .unwrap();
+        assert_eq!(legs.len(), 2);
+        {
+            let calls = connector.calls.lock().unwrap();
+            assert_eq!(calls.len(), 2);
+            assert!(calls.iter().all(|c| c.2 == OrderSide::Short && c.4));
+        }
+
+        // A regular hedge still closes the legs on opposite sides.
+        engine
+            .close_pair_orders(
+                &pair,
+                PositionDirection::LongSpread.exit_sides(false),
+                (dec("0.1"), dec("0.2")),
+                &price_map,
+                true,
+                "exit_z",
+            )
+            .await
+            .unwrap();
+        let calls = connector.calls.lock().unwrap();
+        assert_eq!(calls[2].2, OrderSide::Short);
+        assert_eq!(calls[3].2, OrderSide::Long);
     }
 
-    #[test]
-    fn post_only_passive_short_extended_eth_at_touch() {
-        // Extended ETH tick=0.1, bid=2315.5, ToPositiveInfinity rounding
-        // leaves limit at touch. Must shift to bid + 1 tick.
-        let rounded = dec("2315.5");
-        let touch = dec("2315.5");
-        let tick = dec("0.1");
-        let limit = enforce_post_only_passive(rounded, touch, tick, dex_connector::OrderSide::Short);
-        assert_eq!(limit, dec("2315.6"));
+    #[tokio::test]
+    async fn forced_exit_skips_post_only_on_post_only_venue() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        engine.cfg.dex_name = "lighter".to_string();
+        engine.cfg.fee_bps = 2.0;
+        assert!(engine.should_post_only());
+        let pair = PairSpec {
+            base: "AAA".to_string(),
+            quote: "BBB".to_string(),
+        };
+        let snap = |price: &str| SymbolSnapshot {
+            bid_price: Some(dec(price)),
+            ask_price: Some(dec(price)),
+            bid_size: Decimal::ONE,
+            ask_size: Decimal::ONE,
+            min_order: Some(dec("0.001")),
+            min_tick: Some(dec("0.001")),
+            size_decimals: Some(3),
+            ..SymbolSnapshot::test_at(dec(price))
+        };
+        let price_map = HashMap::from([
+            ("AAA".to_string(), snap("100.0")),
+            ("BBB".to_string(), snap("50.0")),
+        ]);
+        let force = engine.cfg.exit_uses_market("stop_loss_z");
+        assert!(force);
+
+        engine
+            .close_pair_orders(
+                &pair,
+                PositionDirection::LongSpread.exit_sides(false),
+                (dec("0.1"), dec("0.2")),
+                &price_map,
+                force,
+                "stop_loss_z",
+            )
+            .await
+            .unwrap();
+        let calls = connector.calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+        // Market orders: no limit price, reduce-only.
+        assert!(calls.iter().all(|c| c.3.is_none() && c.4));
     }
 
-    #[test]
-    fn post_only_passive_long_already_inside_no_op() {
-        // Lighter passive-slippage path: rounded already below ask. Untouched.
-        let rounded = dec("77640.0");
-        let touch = dec("77640.5");
-        let tick = dec("0.1");
-        let limit = enforce_post_only_passive(rounded, touch, tick, dex_connector::OrderSide::Long);
-        assert_eq!(limit, dec("77640.0"));
+    #[tokio::test]
+    async fn force_close_goes_to_market_whatever_the_exit_market_list() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        engine.cfg.dex_name = "lighter".to_string();
+        engine.cfg.fee_bps = 2.0;
+        engine.cfg.exit_market_reasons = vec!["stop_loss_z".to_string()];
+        assert!(engine.should_post_only());
+        assert!(engine.cfg.exit_uses_market("force_close"));
+        let pair = PairSpec {
+            base: "AAA".to_string(),
+            quote: "BBB".to_string(),
+        };
+        let snap = |price: &str| SymbolSnapshot {
+            min_order: Some(dec("0.001")),
+            min_tick: Some(dec("0.001")),
+            size_decimals: Some(3),
+            ..SymbolSnapshot::test_at(dec(price))
+        };
+        let price_map = HashMap::from([
+            ("AAA".to_string(), snap("100.0")),
+            ("BBB".to_string(), snap("50.0")),
+        ]);
+
+        engine
+            .close_pair_orders(
+                &pair,
+                PositionDirection::LongSpread.exit_sides(false),
+                (dec("0.1"), dec("0.2")),
+                &price_map,
+                false,
+                "force_close",
+            )
+            .await
+            .unwrap();
+        let calls = connector.calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert!(calls.iter().all(|c| c.3.is_none() && c.4));
     }
 
     #[test]
-    fn post_only_passive_short_already_inside_no_op() {
-        let rounded = dec("2315.6");
-        let touch = dec("2315.5");
-        let tick = dec("0.1");
-        let limit = enforce_post_only_passive(rounded, touch, tick, dex_connector::OrderSide::Short);
-        assert_eq!(limit, dec("2315.6"));
+    fn metrics_json_carries_pair_detail_when_enabled() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        engine.instances[0]
+            .states
+            .insert("AAA/BBB".to_string(), PairState::new(10, 2.0));
+
+        let metrics = engine.metrics_json(0);
+        assert_eq!(metrics["pairs"][0]["pair"], "AAA/BBB");
+        assert!(metrics["pairs"][0].get("position").is_none());
+        assert!(metrics.get("positions").is_none());
+
+        engine.cfg.metrics_log_detail = true;
+        let metrics = engine.metrics_json(0);
+        assert_eq!(metrics["pairs"][0]["position"], "flat");
+        assert_eq!(metrics["pairs"][0]["pending"], "none");
+        assert_eq!(metrics["positions"]["open"], 0);
     }
 
     #[test]
-    fn post_only_passive_long_above_touch_clamps() {
-        // Defensive: if upstream produced a rounded price above ask (e.g.
-        // aggressive slippage_bps>0 with should_post_only true), clamp it
-        // back inside.
-        let rounded = dec("77642");
-        let touch = dec("77641");
-        let tick = dec("1");
-        let limit = enforce_post_only_passive(rounded, touch, tick, dex_connector::OrderSide::Long);
-        assert_eq!(limit, dec("77640"));
+    fn idle_pair_alert_fires_once_per_idle_stretch() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        engine.cfg.pair_idle_alert_secs = 100;
+        engine.instances[0]
+            .states
+            .insert("AAA/BBB".to_string(), PairState::new(10, 2.0));
+
+        engine.check_idle_pairs(0, 1_000);
+        engine.check_idle_pairs(0, 1_050);
+        assert!(!engine.instances[0].states["AAA/BBB"].idle_alerted);
+        engine.check_idle_pairs(0, 1_100);
+        assert!(engine.instances[0].states["AAA/BBB"].idle_alerted);
+
+        // An entry re-arms the watchdog from its own timestamp.
+        let state = engine.instances[0].states.get_mut("AAA/BBB").unwrap();
+        state.last_entry_ts = Some(1_200);
+        state.idle_alerted = false;
+        engine.check_idle_pairs(0, 1_250);
+        assert!(!engine.instances[0].states["AAA/BBB"].idle_alerted);
     }
 
     #[test]
-    fn post_only_passive_zero_tick_returns_input() {
-        let rounded = dec("100");
-        let touch = dec("100");
-        let tick = dec("0");
-        let limit = enforce_post_only_passive(rounded, touch, tick, dex_connector::OrderSide::Long);
-        assert_eq!(limit, dec("100"));
-    }
+    fn idle_pair_alert_skips_pairs_holding_a_position() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        engine.cfg.pair_idle_alert_secs = 100;
+        let mut state = PairState::new(10, 2.0);
+        state.last_entry_ts = Some(1_000);
+        state.position = Some(Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts: 1_000,
+            entry_price_a: Some(dec("100")),
+            entry_price_b: Some(dec("100")),
+            entry_size_a: Some(dec("1")),
+            entry_size_b: Some(dec("1")),
+            entry_z: None,
+            entry_beta: Some(1.0),
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        });
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
 
-    #[test]
-    fn quantize_size_by_step_uses_size_decimals() {
-        let size = dec("0.0023");
-        let step = dec("0.001");
-        let quantized = quantize_size_by_step(size, step, None);
-        assert_eq!(quantized, dec("0.002"));
-    }
+        engine.check_idle_pairs(0, 5_000);
+        assert!(!engine.instances[0].states["AAA/BBB"].idle_alerted);
 
-    #[test]
-    fn quantize_size_by_step_respects_min_order_floor() {
-        let size = dec("0.0002");
-        let step = dec("0.0001");
-        let quantized = quantize_size_by_step(size, step, Some(dec("0.001")));
-        assert_eq!(quantized, dec("0.001"));
+        // Once flat, the idle stretch runs from the last bar it was held.
+        engine.instances[0].states.get_mut("AAA/BBB").unwrap().position = None;
+        engine.check_idle_pairs(0, 5_050);
+        assert!(!engine.instances[0].states["AAA/BBB"].idle_alerted);
+        engine.check_idle_pairs(0, 5_100);
+        assert!(engine.instances[0].states["AAA/BBB"].idle_alerted);
     }
 
-    #[test]
-    fn quantize_size_by_step_ceiling_rounds_up() {
-        let size = dec("0.0023");
-        let step = dec("0.001");
-        let quantized = quantize_size_by_step_ceiling(size, step, None);
-        assert_eq!(quantized, dec("0.003"));
-    }
+    #[tokio::test]
+    async fn position_audit_closes_untracked_symbol_once_persistent() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        let clock = Arc::new(clock::ManualClock::new());
+        engine.clock = clock.clone();
+        engine.cfg.position_audit_interval_secs = 60;
+        engine.cfg.position_audit_force_close = true;
+        engine.cfg.dry_run = false;
+        engine.positions_ready = true;
+        engine.open_positions.insert(
+            "ZZZ".to_string(),
+            PositionSnapshot {
+                symbol: "ZZZ".to_string(),
+                size: dec("0.5"),
+                sign: 1,
+                ..Default::default()
+            },
+        );
 
-    // 2026-04-23 18:29:50 UTC — 1745432990 — Thu of day 20203 (UNIX/86400)
-    const TS_2026_04_23_18_29: i64 = 1_745_432_990;
-    // 2026-04-24 00:00:05 UTC — 1745452805 — Fri of day 20204
-    const TS_2026_04_24_00_00: i64 = 1_745_452_805;
-    // 2026-04-23 23:59:55 UTC — 1745452795 — still Thu of day 20203
-    const TS_2026_04_23_23_59: i64 = 1_745_452_795;
+        engine.audit_position_count(&HashMap::new()).await;
+        assert_eq!(engine.position_audit_strikes, 1);
+        // Within the interval the audit does not run again.
+        engine.audit_position_count(&HashMap::new()).await;
+        assert_eq!(engine.position_audit_strikes, 1);
+        assert!(connector.calls.lock().unwrap().is_empty());
 
-    #[test]
-    fn session_day_midnight_reset_same_bucket_before_boundary() {
-        let a = session_day(TS_2026_04_23_18_29, 0);
-        let b = session_day(TS_2026_04_23_23_59, 0);
-        assert_eq!(a, b, "timestamps within the same UTC day should share bucket");
-    }
+        clock.advance(Duration::from_secs(60));
+        engine.audit_position_count(&HashMap::new()).await;
+        assert_eq!(engine.position_audit_strikes, 2);
+        {
+            let calls = connector.calls.lock().unwrap();
+            assert_eq!(calls.len(), 1);
+            assert_eq!(calls[0].0, "ZZZ");
+            assert_eq!(calls[0].2, OrderSide::Short);
+            assert!(calls[0].4);
+        }
 
-    #[test]
-    fn session_day_midnight_reset_boundary_crosses() {
-        let before = session_day(TS_2026_04_23_23_59, 0);
-        let after = session_day(TS_2026_04_24_00_00, 0);
-        assert_eq!(after - before, 1, "UTC midnight should advance bucket by exactly 1");
+        engine.open_positions.clear();
+        clock.advance(Duration::from_secs(60));
+        engine.audit_position_count(&HashMap::new()).await;
+        assert_eq!(engine.position_audit_strikes, 0);
     }
 
-    #[test]
-    fn session_day_custom_reset_hour_shifts_boundary() {
-        // reset_hour=6 ⇒ session boundary at UTC 06:00. A timestamp just
-        // before 06:00 and one just after should fall in adjacent buckets.
-        let ts_0400_utc = 1_745_467_200; // 2026-04-24 04:00 UTC
-        let ts_0700_utc = 1_745_478_000; // 2026-04-24 07:00 UTC
-        let bucket_0400 = session_day(ts_0400_utc, 6);
-        let bucket_0700 = session_day(ts_0700_utc, 6);
-        assert_eq!(bucket_0700 - bucket_0400, 1);
-    }
+    #[tokio::test]
+    async fn delta_rebalance_trims_overfilled_hedge_leg() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        engine.cfg.delta_rebalance_enabled = true;
+        engine.cfg.delta_rebalance_interval_secs = 0;
+        engine.cfg.rebalance_threshold_usd = 10.0;
+        engine.cfg.dry_run = false;
+        engine.positions_ready = true;
+        let mut state = PairState::new(10, 2.0);
+        state.position = Some(Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts: 0,
+            entry_price_a: Some(dec("100")),
+            entry_price_b: Some(dec("100")),
+            entry_size_a: Some(dec("1")),
+            entry_size_b: Some(dec("1.5")),
+            entry_z: None,
+            entry_beta: Some(1.25),
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        });
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
+        for (symbol, size, sign) in [("AAA", "1", 1), ("BBB", "1.5", -1)] {
+            engine.open_positions.insert(
+                symbol.to_string(),
+                PositionSnapshot {
+                    symbol: symbol.to_string(),
+                    size: dec(size),
+                    sign,
+                    ..Default::default()
+                },
+            );
+        }
+        let snap = |price: &str| SymbolSnapshot::test_at(dec(price));
+        let prices = HashMap::from([
+            ("AAA".to_string(), snap("100")),
+            ("BBB".to_string(), snap("100")),
+        ]);
 
-    // bot-strategy#185 Phase 3-1: rolling-peak DD calculations.
-    fn sample(ts: i64, equity: f64) -> risk_io::EquitySample {
-        risk_io::EquitySample { ts, equity }
+        // Leg B is short 1.5 against the 1.25 the entry beta calls for.
+        engine.rebalance_net_delta(0, &prices).await;
+        {
+            let calls = connector.calls.lock().unwrap();
+            assert_eq!(calls.len(), 1);
+            assert_eq!(calls[0].0, "BBB");
+            assert_eq!(calls[0].1, dec("0.25"));
+            assert_eq!(calls[0].2, OrderSide::Long);
+            assert!(calls[0].4);
+        }
+        let booked_b = |engine: &PairTradeEngine| {
+            engine.instances[0].states["AAA/BBB"].position.as_ref().unwrap().entry_size_b
+        };
+        let fill = |order_id: &str, size: &str| {
+            connector.filled_orders.lock().unwrap().push(FilledOrder {
+                order_id: order_id.to_string(),
+                filled_size: Some(dec(size)),
+                ..Default::default()
+            });
+        };
+        // Leg B is only booked once the order is confirmed filled, and no
+        // new pass runs until then.
+        assert_eq!(booked_b(&engine), Some(dec("1.5")));
+        engine.rebalance_net_delta(0, &prices).await;
+        assert_eq!(connector.calls.lock().unwrap().len(), 1);
+        assert_eq!(booked_b(&engine), Some(dec("1.5")));
+        fill("test-0", "0.25");
+        engine.open_positions.get_mut("BBB").unwrap().size = dec("1.25");
+        engine.rebalance_net_delta(0, &prices).await;
+        assert_eq!(booked_b(&engine), Some(dec("1.25")));
+        assert!(engine.instances[0].delta_rebalance_orders.is_empty());
+
+        // A guarded pair leaves its symbols alone.
+        engine.instances[0].states.get_mut("AAA/BBB").unwrap().position_guard = true;
+        engine.rebalance_net_delta(0, &prices).await;
+        assert_eq!(connector.calls.lock().unwrap().len(), 1);
+
+        // Leg B entered at 125 implies 1 BBB per AAA, whatever BBB trades at now.
+        let state = engine.instances[0].states.get_mut("AAA/BBB").unwrap();
+        state.position_guard = false;
+        state.position.as_mut().unwrap().entry_price_b = Some(dec("125"));
+        let short_bbb = |size: &str| PositionSnapshot {
+            symbol: "BBB".to_string(),
+            size: dec(size),
+            sign: -1,
+            ..Default::default()
+        };
+        engine.open_positions.insert("BBB".to_string(), short_bbb("1"));
+        engine.rebalance_net_delta(0, &prices).await;
+        assert_eq!(connector.calls.lock().unwrap().len(), 1);
+
+        // Topping up an under-hedged leg adds exposure: the kill switch holds it.
+        engine.open_positions.insert("BBB".to_string(), short_bbb("0.5"));
+        engine.kill_switch_active = true;
+        engine.rebalance_net_delta(0, &prices).await;
+        assert_eq!(connector.calls.lock().unwrap().len(), 1);
+
+        engine.kill_switch_active = false;
+        engine.rebalance_net_delta(0, &prices).await;
+        {
+            let calls = connector.calls.lock().unwrap();
+            assert_eq!(calls.len(), 2);
+            assert_eq!(calls[1].0, "BBB");
+            assert_eq!(calls[1].1, dec("0.5"));
+            assert_eq!(calls[1].2, OrderSide::Short);
+            assert!(!calls[1].4);
+        }
+        fill("test-1", "0.5");
+        engine.open_positions.insert("BBB".to_string(), short_bbb("1"));
+        engine.rebalance_net_delta(0, &prices).await;
+        assert_eq!(booked_b(&engine), Some(dec("1")));
     }
 
-    #[test]
-    fn rolling_peak_no_samples_uses_current_as_peak() {
-        // Bot just started: no samples yet, so peak = current and DD = 0.
-        let (peak, dd) = PairTradeEngine::rolling_peak(&[], 1_000.0).unwrap();
-        assert_eq!(peak, 1_000.0);
-        assert_eq!(dd, 0.0);
+    #[tokio::test]
+    async fn scale_in_unwinds_leg_a_when_leg_b_fails() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        engine.cfg.dry_run = false;
+        engine.cfg.scale_in_fraction = 0.5;
+        let mut state = PairState::new(10, 2.0);
+        state.position = Some(Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts: 0,
+            entry_price_a: Some(dec("100")),
+            entry_price_b: Some(dec("100")),
+            entry_size_a: Some(dec("1")),
+            entry_size_b: Some(dec("1")),
+            entry_z: Some(-2.0),
+            entry_beta: Some(1.0),
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: Some((dec("1"), dec("1"))),
+            hedge_inverted: false,
+        });
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
+        let pair = engine.cfg.universe[0].clone();
+        let snap = |price: &str| SymbolSnapshot::test_at(dec(price));
+        let prices = HashMap::from([
+            ("AAA".to_string(), snap("90")),
+            ("BBB".to_string(), snap("100")),
+        ]);
+
+        connector
+            .order_failures
+            .lock()
+            .unwrap()
+            .insert("BBB".to_string(), LEG_B_ATTEMPTS as usize);
+        engine.scale_in(0, "AAA/BBB", &pair, &prices).await;
+        {
+            let calls = connector.calls.lock().unwrap();
+            assert_eq!(calls.len(), 2);
+            let order = |i: usize| (calls[i].0.as_str(), calls[i].2, calls[i].4);
+            assert_eq!(order(0), ("AAA", OrderSide::Long, false));
+            assert_eq!(order(1), ("AAA", OrderSide::Short, true));
+            assert_eq!(calls[1].1, dec("1"));
+        }
+        let pos = engine.instances[0].states["AAA/BBB"].position.clone().unwrap();
+        assert!(!pos.scaled_in);
+        assert_eq!((pos.entry_size_a, pos.entry_size_b), (Some(dec("1")), Some(dec("1"))));
+        assert_eq!(pos.entry_price_a, Some(dec("100")));
+
+        // A retried leg B goes through: the add waits on its fills.
+        connector.calls.lock().unwrap().clear();
+        connector.order_failures.lock().unwrap().insert("BBB".to_string(), 1);
+        engine.scale_in(0, "AAA/BBB", &pair, &prices).await;
+        assert_eq!(connector.calls.lock().unwrap().len(), 2);
+        let state = &engine.instances[0].states["AAA/BBB"];
+        let pos = state.position.as_ref().unwrap();
+        assert!(pos.scaled_in);
+        assert_eq!((pos.entry_size_a, pos.entry_size_b), (Some(dec("1")), Some(dec("1"))));
+        assert!(state.pending_entry.as_ref().is_some_and(|p| p.scale_in));
     }
 
-    #[test]
-    fn rolling_peak_picks_max_across_samples_and_current() {
-        let s = vec![sample(100, 1_000.0), sample(200, 1_500.0), sample(300, 1_200.0)];
-        let (peak, dd) = PairTradeEngine::rolling_peak(&s, 900.0).unwrap();
-        assert_eq!(peak, 1_500.0);
-        // (1500 - 900)/1500 * 10000 = 4000 bps
-        assert!((dd - 4_000.0).abs() < 1e-6);
-    }
+    #[tokio::test]
+    async fn scale_in_waits_on_the_entry_gates() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        engine.cfg.metrics_window = 5;
+        engine.cfg.scale_in_enabled = true;
+        engine.cfg.scale_in_fraction = 0.5;
+        engine.cfg.scale_in_add_z = 0.1;
+        let pp = &mut engine.instances[0].default_pair_params;
+        pp.exit_z = 0.0;
+        pp.stop_loss_z = 200.0;
+        pp.force_close_secs = 3600;
+        for (symbol, price) in [("AAA", 200.0_f64), ("BBB", 100.0)] {
+            let bars = (0..30)
+                .map(|i| PriceSample {
+                    log_price: price.ln(),
+                    ts: i * 60,
+                    close: None,
+                })
+                .collect();
+            engine.history.insert(symbol.to_string(), bars);
+        }
+        let mut state = PairState::new(5, 2.0);
+        state.eligible = true;
+        // The latest spread (ln 2 at beta 1) sits far above these.
+        for (i, spread) in [0.0, 0.01, -0.01].into_iter().enumerate() {
+            state.push_spread(spread, i as i64 * 60, 5, &engine.cfg);
+        }
+        state.position = Some(Position {
+            direction: PositionDirection::ShortSpread,
+            entered_at: Instant::now(),
+            entered_ts: engine.current_now_ts(),
+            entry_price_a: Some(dec("200")),
+            entry_price_b: Some(dec("100")),
+            entry_size_a: Some(dec("1")),
+            entry_size_b: Some(dec("2")),
+            entry_z: Some(0.5),
+            entry_beta: Some(1.0),
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        });
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
+        for (symbol, size, sign) in [("AAA", "1", -1), ("BBB", "2", 1)] {
+            connector.positions.lock().unwrap().push(PositionSnapshot {
+                symbol: symbol.to_string(),
+                size: dec(size),
+                sign,
+                ..Default::default()
+            });
+        }
+        let snap = |price: &str| SymbolSnapshot::test_at(dec(price));
+        let prices = HashMap::from([
+            ("AAA".to_string(), snap("200")),
+            ("BBB".to_string(), snap("100")),
+        ]);
+        let both = HashSet::from(["AAA".to_string(), "BBB".to_string()]);
+        let scaled_in = |engine: &PairTradeEngine| {
+            engine.instances[0].states["AAA/BBB"]
+                .position
+                .as_ref()
+                .is_some_and(|p| p.scaled_in)
+        };
 
-    #[test]
-    fn rolling_peak_zero_dd_when_current_is_new_peak() {
-        let s = vec![sample(100, 1_000.0)];
-        let (peak, dd) = PairTradeEngine::rolling_peak(&s, 1_500.0).unwrap();
-        assert_eq!(peak, 1_500.0);
-        assert_eq!(dd, 0.0);
-    }
+        engine.kill_switch_active = true;
+        engine.step_for_instance(0, &prices, &both).await.unwrap();
+        assert!(!scaled_in(&engine));
 
-    #[test]
-    fn rolling_peak_returns_none_for_non_positive_equity() {
-        // Pre-funded account / connector hiccup → equity reads 0; can't
-        // define a percent DD, so caller treats it as "no signal".
-        assert!(PairTradeEngine::rolling_peak(&[], 0.0).is_none());
-        assert!(PairTradeEngine::rolling_peak(&[], -10.0).is_none());
+        engine.kill_switch_active = false;
+        engine.step_for_instance(0, &prices, &both).await.unwrap();
+        assert!(scaled_in(&engine));
     }
 
-    #[test]
-    fn risk_state_path_falls_back_to_cwd_for_bare_filename() {
-        let p = risk_state_path_for(std::path::Path::new(
-            "pairtrade_history_BTC_ETH.json",
-        ));
-        assert_eq!(p, std::path::PathBuf::from("risk_state.json"));
+    /// Engine whose AAA/BBB spread sits about 3 sigma above its mean with
+    /// the warm-up and readiness checks met, so a step on
+    /// `entry_step_prices` opens a short-spread entry unless a gate stops it.
+    fn entry_ready_engine(connector: Arc<DummyConnector>) -> PairTradeEngine {
+        let mut engine = PairTradeEngine::test_instance(connector);
+        engine.cfg.metrics_window = 10;
+        engine.cfg.min_symbol_history_bars = 10;
+        engine.positions_ready = true;
+        let pp = &mut engine.instances[0].default_pair_params;
+        pp.entry_z_base = 0.5;
+        pp.stop_loss_z = 200.0;
+        pp.spread_trend_max_slope_sigma = f64::INFINITY;
+        for (symbol, price) in [("AAA", 200.0_f64), ("BBB", 100.0)] {
+            let bars = (0..30)
+                .map(|i| PriceSample {
+                    log_price: price.ln(),
+                    ts: i * 60,
+                    close: None,
+                })
+                .collect();
+            engine.history.insert(symbol.to_string(), bars);
+        }
+        let mut state = PairState::new(10, 2.0);
+        state.eligible = true;
+        state.last_evaluated_ts = Some(engine.current_now_ts());
+        for i in 0..9 {
+            let spread = if i % 2 == 0 { 0.01 } else { -0.01 };
+            state.push_spread(spread, i * 60, 10, &engine.cfg);
+        }
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
+        engine
     }
 
-    #[test]
-    fn risk_state_path_joined_to_parent_dir_when_absolute() {
-        let p = risk_state_path_for(std::path::Path::new(
-            "/opt/debot/pairtrade_history_BTC_ETH.json",
-        ));
-        assert_eq!(p, std::path::PathBuf::from("/opt/debot/risk_state.json"));
+    fn entry_step_prices() -> HashMap<String, SymbolSnapshot> {
+        HashMap::from([
+            ("AAA".to_string(), SymbolSnapshot::test_at(dec("200"))),
+            ("BBB".to_string(), SymbolSnapshot::test_at(dec("100"))),
+        ])
     }
-}
 
-#[cfg(test)]
-mod pending_tests {
-    use super::*;
-    use async_trait::async_trait;
-    use dex_connector::{
-        BalanceResponse, CanceledOrdersResponse, CreateOrderResponse, DexConnector, DexError,
-        FilledOrdersResponse, LastTradesResponse, OpenOrdersResponse, OrderBookSnapshot, OrderSide,
-        PositionSnapshot, TickerResponse, TpSl, TriggerOrderStyle,
-    };
-    use rust_decimal::Decimal;
-    use std::collections::HashMap;
-    use std::str::FromStr;
-    use std::sync::atomic::{AtomicUsize, Ordering};
-    use std::sync::{Arc, Mutex};
-    use std::time::Instant;
+    #[tokio::test]
+    async fn gated_entries_are_recorded_with_their_gate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rejected_entries.jsonl");
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = entry_ready_engine(connector);
+        engine.cfg.rejected_entries_file = Some(path.to_string_lossy().into_owned());
+        engine.kill_switch_active = true;
+        let both = HashSet::from(["AAA".to_string(), "BBB".to_string()]);
 
-    fn dec(value: &str) -> Decimal {
-        Decimal::from_str(value).unwrap()
-    }
+        engine.step_for_instance(0, &entry_step_prices(), &both).await.unwrap();
 
-    #[derive(Default)]
-    struct DummyConnector {
-        calls: Mutex<Vec<(String, Decimal, OrderSide, Option<Decimal>, bool)>>,
-        next_id: AtomicUsize,
-        balance_calls: AtomicUsize,
-        balance_equity: Mutex<Option<Decimal>>,
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["pair"], "AAA/BBB");
+        assert_eq!(lines[0]["reason"], "kill_switch");
+        assert_eq!(lines[0]["direction"], "short_spread");
+        assert!(engine.instances[0].states["AAA/BBB"].position.is_none());
     }
 
-    #[async_trait]
-    impl DexConnector for DummyConnector {
-        async fn start(&self) -> Result<(), DexError> {
-            Ok(())
-        }
-
-        async fn stop(&self) -> Result<(), DexError> {
-            Ok(())
-        }
+    #[tokio::test]
+    async fn min_eligible_pairs_counts_eligible_states() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = entry_ready_engine(connector);
+        engine.cfg.min_eligible_pairs = 2;
+        engine.instances[0]
+            .states
+            .insert("CCC/DDD".to_string(), PairState::new(10, 2.0));
+        let both = HashSet::from(["AAA".to_string(), "BBB".to_string()]);
+        let opened = |engine: &PairTradeEngine| engine.instances[0].states["AAA/BBB"].position.is_some();
+
+        // One eligible pair of the two tracked: entries wait for breadth.
+        engine.step_for_instance(0, &entry_step_prices(), &both).await.unwrap();
+        assert!(!opened(&engine));
+
+        engine.instances[0].states.get_mut("CCC/DDD").unwrap().eligible = true;
+        engine.last_account_rest_call = None;
+        engine.step_for_instance(0, &entry_step_prices(), &both).await.unwrap();
+        assert!(opened(&engine));
+    }
 
-        async fn restart(&self, _max_retries: i32) -> Result<(), DexError> {
-            Ok(())
-        }
+    #[tokio::test]
+    async fn max_active_pairs_caps_entries_outside_single_position_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rejected_entries.jsonl");
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = entry_ready_engine(connector);
+        engine.cfg.rejected_entries_file = Some(path.to_string_lossy().into_owned());
+        engine.cfg.single_position_mode = false;
+        engine.cfg.max_active_pairs = 1;
+        let mut other = PairState::new(10, 2.0);
+        other.position = Some(Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts: engine.current_now_ts(),
+            entry_price_a: Some(dec("10")),
+            entry_price_b: Some(dec("20")),
+            entry_size_a: Some(dec("1")),
+            entry_size_b: Some(dec("0.5")),
+            entry_z: None,
+            entry_beta: Some(1.0),
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        });
+        engine.instances[0].states.insert("CCC/DDD".to_string(), other);
+        let both = HashSet::from(["AAA".to_string(), "BBB".to_string()]);
+        let opened = |engine: &PairTradeEngine| engine.instances[0].states["AAA/BBB"].position.is_some();
+
+        // One pair already open: the cap is reached.
+        engine.step_for_instance(0, &entry_step_prices(), &both).await.unwrap();
+        assert!(!opened(&engine));
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let record: serde_json::Value = serde_json::from_str(contents.lines().next().unwrap()).unwrap();
+        assert_eq!(record["reason"], "max_active_pairs");
+
+        // Room for a second pair: the other open position does not block it.
+        engine.cfg.max_active_pairs = 2;
+        engine.last_account_rest_call = None;
+        engine.step_for_instance(0, &entry_step_prices(), &both).await.unwrap();
+        assert!(opened(&engine));
+    }
 
-        async fn set_leverage(&self, _symbol: &str, _leverage: u32) -> Result<(), DexError> {
-            Ok(())
-        }
+    #[tokio::test]
+    async fn crossed_book_blocks_a_live_entry() {
+        let connector = Arc::new(DummyConnector::default());
+        *connector.balance_equity.lock().unwrap() = Some(dec("10000"));
+        let mut engine = entry_ready_engine(connector.clone());
+        engine.cfg.dry_run = false;
+        engine.cfg.reject_crossed_book = true;
+        // Only the pre-sizing balance fetch runs, so no step waits out the
+        // account-call spacing.
+        engine.instances[0].last_equity_fetch = Some(engine.clock.now());
+        engine.cfg.max_open_orders = 0;
+        let both = HashSet::from(["AAA".to_string(), "BBB".to_string()]);
+        let mut prices = entry_step_prices();
+        let aaa = prices.get_mut("AAA").unwrap();
+        aaa.bid_price = Some(dec("200.5"));
+        aaa.ask_price = Some(dec("199.5"));
+
+        engine.step_for_instance(0, &prices, &both).await.unwrap();
+        assert!(connector.calls.lock().unwrap().is_empty());
+        assert!(engine.instances[0].states["AAA/BBB"].pending_entry.is_none());
+
+        // The same entry goes out once the book uncrosses.
+        let aaa = prices.get_mut("AAA").unwrap();
+        aaa.bid_price = Some(dec("199.5"));
+        aaa.ask_price = Some(dec("200.5"));
+        engine.last_account_rest_call = None;
+        engine.step_for_instance(0, &prices, &both).await.unwrap();
+        assert_eq!(connector.calls.lock().unwrap().len(), 2);
+        assert!(engine.instances[0].states["AAA/BBB"].pending_entry.is_some());
+    }
 
-        async fn get_ticker(
-            &self,
-            _symbol: &str,
-            _test_price: Option<Decimal>,
-        ) -> Result<TickerResponse, DexError> {
-            Err(DexError::Other("not used".to_string()))
-        }
+    #[tokio::test]
+    async fn open_orders_over_the_cap_block_entries() {
+        let connector = Arc::new(DummyConnector::default());
+        *connector.open_orders.lock().unwrap() = 2;
+        let mut engine = entry_ready_engine(connector.clone());
+        engine.cfg.max_open_orders = 3;
+        let both = HashSet::from(["AAA".to_string(), "BBB".to_string()]);
+        let opened = |engine: &PairTradeEngine| engine.instances[0].states["AAA/BBB"].position.is_some();
+        // The audit only runs live; the entry itself stays a dry run.
+        async fn audit(engine: &mut PairTradeEngine) {
+            engine.cfg.dry_run = false;
+            engine.last_account_rest_call = None;
+            engine.instances[0].last_open_order_audit = None;
+            engine.audit_open_orders(0).await;
+            engine.cfg.dry_run = true;
+            engine.last_account_rest_call = None;
+        }
+
+        audit(&mut engine).await;
+        assert_eq!(engine.instances[0].open_order_count, 4);
+        assert!(engine.instances[0].open_orders_blocked);
+        engine.step_for_instance(0, &entry_step_prices(), &both).await.unwrap();
+        assert!(!opened(&engine));
+
+        *connector.open_orders.lock().unwrap() = 1;
+        audit(&mut engine).await;
+        assert!(!engine.instances[0].open_orders_blocked);
+        engine.step_for_instance(0, &entry_step_prices(), &both).await.unwrap();
+        assert!(opened(&engine));
+    }
 
-        async fn get_filled_orders(&self, _symbol: &str) -> Result<FilledOrdersResponse, DexError> {
-            Ok(FilledOrdersResponse::default())
-        }
+    #[tokio::test]
+    async fn partial_take_profit_restores_leg_a_when_leg_b_fails() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        engine.cfg.dry_run = false;
+        engine.cfg.partial_take_profit_fraction = 0.5;
+        let mut state = PairState::new(10, 2.0);
+        state.position = Some(Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts: 0,
+            entry_price_a: Some(dec("100")),
+            entry_price_b: Some(dec("100")),
+            entry_size_a: Some(dec("2")),
+            entry_size_b: Some(dec("3")),
+            entry_z: None,
+            entry_beta: Some(1.5),
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        });
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
+        let pair = engine.cfg.universe[0].clone();
+        let snap = |price: &str| SymbolSnapshot::test_at(dec(price));
+        let prices = HashMap::from([
+            ("AAA".to_string(), snap("110")),
+            ("BBB".to_string(), snap("100")),
+        ]);
+
+        // Leg B fails on every attempt: leg A is bought back, nothing booked.
+        connector.order_failures.lock().unwrap().insert(
+            "BBB".to_string(),
+            LEG_B_ATTEMPTS as usize,
+        );
+        engine.take_partial_profit(0, "AAA/BBB", &pair, &prices).await;
+        {
+            let calls = connector.calls.lock().unwrap();
+            assert_eq!(calls.len(), 2);
+            let order = |i: usize| (calls[i].0.as_str(), calls[i].2, calls[i].4);
+            assert_eq!(order(0), ("AAA", OrderSide::Short, true));
+            assert_eq!(order(1), ("AAA", OrderSide::Long, false));
+            assert_eq!(calls[1].1, dec("1"));
+        }
+        let state = &engine.instances[0].states["AAA/BBB"];
+        let pos = state.position.as_ref().unwrap();
+        assert!(!pos.partial_taken);
+        assert_eq!(pos.entry_size_a, Some(dec("2")));
+        assert!(state.pending_exit.is_none());
+        assert_eq!(engine.instances[0].realized_pnl_today, 0.0);
+
+        // A retried leg B goes through: the slice is tracked as a partial
+        // exit, and nothing is booked until it fills.
+        connector.calls.lock().unwrap().clear();
+        connector.order_failures.lock().unwrap().insert("BBB".to_string(), 1);
+        engine.take_partial_profit(0, "AAA/BBB", &pair, &prices).await;
+        assert_eq!(connector.calls.lock().unwrap().len(), 2);
+        let state = &engine.instances[0].states["AAA/BBB"];
+        let pos = state.position.as_ref().unwrap();
+        assert!(pos.partial_taken);
+        assert_eq!((pos.entry_size_a, pos.entry_size_b), (Some(dec("2")), Some(dec("3"))));
+        let pending = state.pending_exit.as_ref().unwrap();
+        assert!(pending.partial_exit);
+        assert_eq!(pending.legs.len(), 2);
+        assert_eq!(engine.instances[0].realized_pnl_today, 0.0);
+
+        // Once both legs fill, the slice is booked and the remainder stays open.
+        let fills = pending
+            .legs
+            .iter()
+            .map(|leg| (leg.order_id.clone(), leg.target))
+            .collect();
+        let mut prefetched = HashMap::from([(
+            ("AAA/BBB".to_string(), false),
+            PendingStatus {
+                open_remaining: 0,
+                fills,
+                fill_values: HashMap::new(),
+                fill_fees: HashMap::new(),
+                open_ids: HashSet::new(),
+            },
+        )]);
+        engine
+            .reconcile_pending_orders(0, "AAA/BBB", &prices, &mut prefetched)
+            .await
+            .unwrap();
+        let state = &engine.instances[0].states["AAA/BBB"];
+        assert!(state.pending_exit.is_none());
+        let pos = state.position.as_ref().unwrap();
+        assert_eq!((pos.entry_size_a, pos.entry_size_b), (Some(dec("1")), Some(dec("1.5"))));
+        assert!((engine.instances[0].realized_pnl_today - 10.0).abs() < 1e-9);
+    }
 
-        async fn get_canceled_orders(
-            &self,
-            _symbol: &str,
-        ) -> Result<CanceledOrdersResponse, DexError> {
-            Ok(CanceledOrdersResponse::default())
-        }
+    #[tokio::test]
+    async fn scale_in_adds_the_entry_remainder_once_filled() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        engine.cfg.dry_run = false;
+        // A 1 / 1.5 scout of a 2 / 3 target, half of it since taken off.
+        let mut state = PairState::new(10, 2.0);
+        state.position = Some(Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts: 0,
+            entry_price_a: Some(dec("100")),
+            entry_price_b: Some(dec("100")),
+            entry_size_a: Some(dec("0.5")),
+            entry_size_b: Some(dec("0.75")),
+            entry_z: Some(-2.0),
+            entry_beta: Some(1.5),
+            entry_fill_cash: None,
+            partial_taken: true,
+            scaled_in: false,
+            scale_in_add: Some((dec("1"), dec("1.5"))),
+            hedge_inverted: false,
+        });
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
+        let pair = engine.cfg.universe[0].clone();
+        let snap = |price: &str| SymbolSnapshot::test_at(dec(price));
+        let prices = HashMap::from([
+            ("AAA".to_string(), snap("90")),
+            ("BBB".to_string(), snap("100")),
+        ]);
+
+        // The add is the entry remainder, not a multiple of the current size,
+        // and nothing is booked until it fills.
+        engine.scale_in(0, "AAA/BBB", &pair, &prices).await;
+        {
+            let calls = connector.calls.lock().unwrap();
+            let order = |i: usize| (calls[i].0.as_str(), calls[i].1, calls[i].2, calls[i].4);
+            assert_eq!(calls.len(), 2);
+            assert_eq!(order(0), ("AAA", dec("1"), OrderSide::Long, false));
+            assert_eq!(order(1), ("BBB", dec("1.5"), OrderSide::Short, false));
+        }
+        let state = &engine.instances[0].states["AAA/BBB"];
+        let pos = state.position.as_ref().unwrap();
+        assert!(pos.scaled_in);
+        assert_eq!((pos.entry_size_a, pos.entry_size_b), (Some(dec("0.5")), Some(dec("0.75"))));
+        let pending = state.pending_entry.as_ref().unwrap();
+        assert!(pending.scale_in);
+
+        let fills = pending
+            .legs
+            .iter()
+            .map(|leg| (leg.order_id.clone(), leg.target))
+            .collect();
+        let mut prefetched = HashMap::from([(
+            ("AAA/BBB".to_string(), true),
+            PendingStatus {
+                open_remaining: 0,
+                fills,
+                fill_values: HashMap::new(),
+                fill_fees: HashMap::new(),
+                open_ids: HashSet::new(),
+            },
+        )]);
+        engine
+            .reconcile_pending_orders(0, "AAA/BBB", &prices, &mut prefetched)
+            .await
+            .unwrap();
+        let state = &engine.instances[0].states["AAA/BBB"];
+        assert!(state.pending_entry.is_none());
+        let pos = state.position.as_ref().unwrap();
+        assert_eq!((pos.entry_size_a, pos.entry_size_b), (Some(dec("1.5")), Some(dec("2.25"))));
+        assert_eq!(pos.entry_price_a, Some(dec("280") / dec("3")));
+        assert_eq!(pos.entry_price_b, Some(dec("100")));
+    }
 
-        async fn get_open_orders(&self, _symbol: &str) -> Result<OpenOrdersResponse, DexError> {
-            Ok(OpenOrdersResponse::default())
-        }
+    #[tokio::test]
+    async fn timed_out_partial_take_profit_keeps_the_remainder() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        let clock = Arc::new(clock::ManualClock::new());
+        engine.clock = clock.clone();
+        engine.cfg.dry_run = false;
+        engine.cfg.partial_take_profit_fraction = 0.5;
+        let mut state = PairState::new(10, 2.0);
+        state.position = Some(Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: clock.now(),
+            entered_ts: 0,
+            entry_price_a: Some(dec("100")),
+            entry_price_b: Some(dec("100")),
+            entry_size_a: Some(dec("2")),
+            entry_size_b: Some(dec("3")),
+            entry_z: None,
+            entry_beta: Some(1.5),
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        });
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
+        let pair = engine.cfg.universe[0].clone();
+        let snap = |price: &str| SymbolSnapshot::test_at(dec(price));
+        let prices = HashMap::from([
+            ("AAA".to_string(), snap("110")),
+            ("BBB".to_string(), snap("100")),
+        ]);
+        engine.take_partial_profit(0, "AAA/BBB", &pair, &prices).await;
+        let legs = engine.instances[0].states["AAA/BBB"]
+            .pending_exit
+            .as_ref()
+            .unwrap()
+            .legs
+            .clone();
+        connector.calls.lock().unwrap().clear();
+        // Leg A filled half its slice; leg B is still resting.
+        let status = || PendingStatus {
+            open_remaining: 1,
+            fills: HashMap::from([(legs[0].order_id.clone(), dec("0.5"))]),
+            fill_values: HashMap::new(),
+            fill_fees: HashMap::new(),
+            open_ids: HashSet::from([legs[1].order_id.clone()]),
+        };
 
-        async fn get_balance(&self, _symbol: Option<&str>) -> Result<BalanceResponse, DexError> {
-            self.balance_calls.fetch_add(1, Ordering::SeqCst);
-            let equity = self.balance_equity.lock().unwrap().unwrap_or_default();
-            Ok(BalanceResponse {
-                equity,
-                balance: equity,
-                position_entry_price: None,
-                position_sign: None,
-            })
-        }
+        // Before the order timeout the slice keeps waiting.
+        let mut prefetched = HashMap::from([(("AAA/BBB".to_string(), false), status())]);
+        engine
+            .reconcile_pending_orders(0, "AAA/BBB", &prices, &mut prefetched)
+            .await
+            .unwrap();
+        assert!(engine.instances[0].states["AAA/BBB"].pending_exit.is_some());
+
+        // Past it, the rest is cancelled and only the fill is booked; the
+        // position is not flattened.
+        clock.advance(Duration::from_secs(engine.cfg.order_timeout_secs + 1));
+        let mut prefetched = HashMap::from([(("AAA/BBB".to_string(), false), status())]);
+        engine
+            .reconcile_pending_orders(0, "AAA/BBB", &prices, &mut prefetched)
+            .await
+            .unwrap();
+        assert!(connector.calls.lock().unwrap().is_empty());
+        let state = &engine.instances[0].states["AAA/BBB"];
+        assert!(state.pending_exit.is_none());
+        let pos = state.position.as_ref().unwrap();
+        assert!(pos.partial_taken);
+        assert_eq!((pos.entry_size_a, pos.entry_size_b), (Some(dec("1.5")), Some(dec("3"))));
+        assert!((engine.instances[0].realized_pnl_today - 5.0).abs() < 1e-9);
+    }
 
-        async fn get_combined_balance(
-            &self,
-        ) -> Result<dex_connector::CombinedBalanceResponse, DexError> {
-            Ok(dex_connector::CombinedBalanceResponse::default())
-        }
+    #[tokio::test]
+    async fn equity_history_exposure_covers_the_instance_only() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        let mut state = PairState::new(10, 2.0);
+        state.position = Some(Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts: 0,
+            entry_price_a: Some(dec("100")),
+            entry_price_b: Some(dec("50")),
+            entry_size_a: Some(dec("1")),
+            entry_size_b: Some(dec("2")),
+            entry_z: None,
+            entry_beta: Some(1.0),
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        });
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
+        // Another account's position on the shared exchange book.
+        let other = paper_snapshot("ZZZ".to_string(), dec("10"), Some(dec("1000")));
+        engine.open_positions.insert("ZZZ".to_string(), other.unwrap());
+
+        let (gross, count) = engine.instance_exposure_usd(0);
+        assert!((gross - 200.0).abs() < 1e-9);
+        assert_eq!(count, 1);
+    }
 
-        async fn get_positions(&self) -> Result<Vec<PositionSnapshot>, DexError> {
-            Ok(vec![])
-        }
+    #[tokio::test]
+    async fn equity_cache_expires_on_manual_clock() {
+        let connector = Arc::new(DummyConnector::default());
+        *connector.balance_equity.lock().unwrap() = Some(dec("1234.56"));
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        let clock = Arc::new(clock::ManualClock::new());
+        engine.clock = clock.clone();
+        engine.instances[0].last_equity_fetch = Some(clock.now());
 
-        async fn get_last_trades(&self, _symbol: &str) -> Result<LastTradesResponse, DexError> {
-            Ok(LastTradesResponse::default())
-        }
+        engine.refresh_equity_if_needed(0).await.unwrap();
+        assert_eq!(connector.balance_calls.load(Ordering::SeqCst), 0);
 
-        async fn get_order_book(
-            &self,
-            _symbol: &str,
-            _depth: usize,
-        ) -> Result<OrderBookSnapshot, DexError> {
-            Ok(OrderBookSnapshot::default())
-        }
+        clock.advance(Duration::from_secs(EQUITY_REFRESH_CACHE_SECS + 1));
+        engine.refresh_equity_if_needed(0).await.unwrap();
+        assert_eq!(connector.balance_calls.load(Ordering::SeqCst), 1);
+        assert!((engine.instances[0].equity_cache - 1234.56).abs() < 1e-6);
+    }
 
-        async fn clear_filled_order(&self, _symbol: &str, _trade_id: &str) -> Result<(), DexError> {
-            Ok(())
-        }
+    #[tokio::test]
+    async fn fetch_equity_rest_bypasses_cache() {
+        // Pre-entry path must hit REST regardless of cache age so the
+        // about-to-be-placed order is sized against a current value.
+        let connector = Arc::new(DummyConnector::default());
+        *connector.balance_equity.lock().unwrap() = Some(dec("777.0"));
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        engine.instances[0].last_equity_fetch = Some(Instant::now());
 
-        async fn clear_all_filled_orders(&self) -> Result<(), DexError> {
-            Ok(())
-        }
+        engine.fetch_equity_rest(0).await;
 
-        async fn clear_canceled_order(
-            &self,
-            _symbol: &str,
-            _order_id: &str,
-        ) -> Result<(), DexError> {
-            Ok(())
-        }
+        assert_eq!(connector.balance_calls.load(Ordering::SeqCst), 1);
+        assert!((engine.instances[0].equity_cache - 777.0).abs() < 1e-6);
+    }
 
-        async fn clear_all_canceled_orders(&self) -> Result<(), DexError> {
-            Ok(())
-        }
+    #[test]
+    fn funding_window_requires_known_schedule() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        engine.cfg.avoid_funding_window_secs = 300;
+        assert!(!engine.funding_window_active(3_600 - 60));
+        assert!(engine.funding_schedule_warned);
+
+        engine.cfg.funding_interval_secs = 3_600;
+        assert!(engine.funding_window_active(3_600 - 60));
+        assert!(engine.funding_window_active(3_600 - 300));
+        assert!(!engine.funding_window_active(3_600 - 301));
+        assert!(!engine.funding_window_active(3_600));
+
+        // A known venue's schedule wins over the config fallback.
+        engine.cfg.funding_interval_secs = 0;
+        engine.cfg.dex_name = "lighter".to_string();
+        assert!(engine.funding_window_active(7_200 - 60));
+    }
 
-        async fn create_order(
-            &self,
-            symbol: &str,
-            size: Decimal,
-            side: OrderSide,
-            price: Option<Decimal>,
-            _spread: Option<i64>,
-            reduce_only: bool,
-            _expiry_secs: Option<u64>,
-        ) -> Result<CreateOrderResponse, DexError> {
-            let order_id = format!("test-{}", self.next_id.fetch_add(1, Ordering::SeqCst));
-            let ordered_price = price.unwrap_or_else(|| Decimal::ONE);
-            self.calls
-                .lock()
-                .unwrap()
-                .push((symbol.to_string(), size, side, price, reduce_only));
-            Ok(CreateOrderResponse {
-                order_id,
-                exchange_order_id: None,
-                ordered_price,
-                ordered_size: size,
-                client_order_id: None,
-            })
-        }
+    #[test]
+    fn spread_smoothing_feeds_sma_into_history() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        engine.cfg.spread_smoothing_bars = 3;
+        let mut state = PairState::new(10, 2.0);
+        for raw in [1.0, 2.0, 3.0, 7.0] {
+            state.push_spread(raw, 0, 10, &engine.cfg);
+        }
+        let got: Vec<f64> = state.spread_history.iter().copied().collect();
+        assert_eq!(got, vec![1.0, 1.5, 2.0, 4.0]);
+        assert_eq!(state.last_spread, Some(4.0));
+
+        engine.cfg.spread_smoothing_bars = 0;
+        let mut raw_state = PairState::new(10, 2.0);
+        raw_state.push_spread(7.0, 0, 10, &engine.cfg);
+        assert_eq!(raw_state.spread_history.back(), Some(&7.0));
+        assert!(raw_state.raw_spreads.is_empty());
+
+        // Seeding smooths like live pushes and keeps the raw tail; a restore
+        // drops raw spreads that no longer match the restored series.
+        engine.cfg.spread_smoothing_bars = 3;
+        let mut seeded = PairState::new(10, 2.0);
+        seeded.seed_spread_history(&[1.0, 2.0, 3.0, 7.0], &engine.cfg);
+        let got: Vec<f64> = seeded.spread_history.iter().copied().collect();
+        assert_eq!(got, vec![1.0, 1.5, 2.0, 4.0]);
+        assert_eq!(seeded.raw_spreads, VecDeque::from(vec![2.0, 3.0, 7.0]));
+        seeded.restore_spread_history(VecDeque::from(vec![5.0, 6.0]));
+        assert!(seeded.raw_spreads.is_empty());
+        assert_eq!(seeded.last_spread, Some(6.0));
+    }
 
-        async fn create_advanced_trigger_order(
-            &self,
-            _symbol: &str,
-            _size: Decimal,
-            _side: OrderSide,
-            _trigger_px: Decimal,
-            _limit_px: Option<Decimal>,
-            _order_style: TriggerOrderStyle,
-            _slippage_bps: Option<u32>,
-            _tpsl: TpSl,
-            _reduce_only: bool,
-            _expiry_secs: Option<u64>,
-        ) -> Result<CreateOrderResponse, DexError> {
-            Err(DexError::Other("not used".to_string()))
-        }
+    #[test]
+    fn spread_gap_counts_missing_bars() {
+        let connector = Arc::new(DummyConnector::default());
+        let engine = PairTradeEngine::test_instance(connector);
+        let period = engine.cfg.trading_period_secs as i64;
+        let mut state = PairState::new(10, 2.0);
+        for i in 0..3 {
+            state.push_spread(0.1, i * period, 10, &engine.cfg);
+        }
+        assert_eq!(state.max_spread_gap_bars(period as u64), 0);
+        // Downtime: next bar lands five periods later → four missing bars.
+        state.push_spread(0.1, 7 * period, 10, &engine.cfg);
+        assert_eq!(state.max_spread_gap_bars(period as u64), 4);
+        // Rolls out once the window no longer spans it.
+        for i in 8..20 {
+            state.push_spread(0.1, i * period, 10, &engine.cfg);
+        }
+        assert_eq!(state.max_spread_gap_bars(period as u64), 0);
+    }
 
-        async fn cancel_order(&self, _symbol: &str, _order_id: &str) -> Result<(), DexError> {
-            Ok(())
-        }
+    #[test]
+    fn robust_z_ignores_a_spike_bar() {
+        let mut state = PairState::new(10, 2.0);
+        state.spread_history =
+            VecDeque::from(vec![0.0, 0.1, -0.1, 0.1, -0.1, 5.0, 0.0, 0.1, -0.1, 0.2]);
+        let (simple_z, simple_std, _, _) = state.z_score_details().unwrap();
+        state.z_stat_mode = ZStatMode::Robust;
+        let (robust_z, robust_std, center, latest) = state.z_score_details().unwrap();
+        assert_eq!(latest, 0.2);
+        assert!((center - 0.05).abs() < 1e-12);
+        // The spike inflates the std roughly tenfold; the MAD stays put.
+        assert!(simple_std > 1.0 && robust_std < 0.2);
+        assert!(robust_z > 1.0 && simple_z < 0.1);
+    }
 
-        async fn cancel_all_orders(&self, _symbol: Option<String>) -> Result<(), DexError> {
-            Ok(())
-        }
+    #[test]
+    fn log_sampling_emits_one_in_n_per_key() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        let emitted: Vec<bool> = (0..5)
+            .map(|_| engine.log_sampled("zcheck", "AAA/BBB", 2))
+            .collect();
+        assert_eq!(emitted, vec![true, false, true, false, true]);
+        // Keys and categories count independently.
+        assert!(engine.log_sampled("zcheck", "CCC/DDD", 2));
+        assert!(engine.log_sampled("order_params", "AAA/BBB", 2));
+        // Every line is emitted (and nothing tracked) at the default of 1.
+        assert!((0..3).all(|_| engine.log_sampled("price_snapshot", "AAA", 1)));
+        assert!(!engine
+            .log_sample_counts
+            .contains_key(&("price_snapshot", "AAA".to_string())));
+    }
 
-        async fn cancel_orders(
-            &self,
-            _symbol: Option<String>,
-            _order_ids: Vec<String>,
-        ) -> Result<(), DexError> {
-            Ok(())
-        }
+    #[test]
+    fn universe_reload_adds_pairs_and_retires_removed_once_flat() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        let pair = |base: &str, quote: &str| PairSpec {
+            base: base.to_string(),
+            quote: quote.to_string(),
+        };
 
-        async fn close_all_positions(&self, _symbol: Option<String>) -> Result<(), DexError> {
-            Ok(())
-        }
+        let mut new_cfg = engine.cfg.clone();
+        new_cfg.universe = vec![pair("AAA", "BBB"), pair("CCC", "DDD")];
+        // The connector was never told about CCC/DDD.
+        engine.apply_universe_reload(&new_cfg);
+        assert_eq!(engine.cfg.universe.len(), 1);
+        assert!(!engine.instances[0].states.contains_key("CCC/DDD"));
+
+        engine.connector_symbols.extend(["CCC".to_string(), "DDD".to_string()]);
+        engine.apply_universe_reload(&new_cfg);
+        assert_eq!(engine.cfg.universe.len(), 2);
+        assert!(engine.instances[0].states.contains_key("CCC/DDD"));
+        assert!(engine.history.contains_key("CCC"));
+        assert!(engine.bar_builders.contains_key("DDD"));
+
+        engine.instances[0].states.get_mut("CCC/DDD").unwrap().position = Some(Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts: 0,
+            entry_price_a: None,
+            entry_price_b: None,
+            entry_size_a: None,
+            entry_size_b: None,
+            entry_z: None,
+            entry_beta: None,
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        });
+        new_cfg.universe = vec![pair("AAA", "BBB")];
+        engine.apply_universe_reload(&new_cfg);
+        assert!(engine.retiring_pairs.contains("CCC/DDD"));
+        assert_eq!(engine.cfg.universe.len(), 2);
+
+        engine.retire_flat_pairs();
+        assert!(engine.instances[0].states.contains_key("CCC/DDD"));
+
+        engine.instances[0].states.get_mut("CCC/DDD").unwrap().position = None;
+        engine.retire_flat_pairs();
+        assert!(engine.retiring_pairs.is_empty());
+        assert_eq!(engine.cfg.universe.len(), 1);
+        assert!(!engine.instances[0].states.contains_key("CCC/DDD"));
+        assert!(!engine.history.contains_key("CCC"));
+        assert!(!engine.bar_builders.contains_key("DDD"));
+    }
 
-        async fn clear_last_trades(&self, _symbol: &str) -> Result<(), DexError> {
-            Ok(())
-        }
+    #[test]
+    fn universe_reload_seeds_added_pair_from_shared_bars() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        let bars = |drift: f64| -> VecDeque<PriceSample> {
+            (0..20)
+                .map(|i| PriceSample {
+                    log_price: (100.0 + drift * i as f64 + (i % 3) as f64).ln(),
+                    ts: i * 60,
+                    close: None,
+                })
+                .collect()
+        };
+        engine.history.insert("AAA".to_string(), bars(1.0));
+        engine.history.insert("BBB".to_string(), bars(0.5));
+        engine.history.insert("CCC".to_string(), bars(2.0));
+        let added = PairSpec {
+            base: "AAA".to_string(),
+            quote: "CCC".to_string(),
+        };
 
-        async fn is_upcoming_maintenance(&self, _hours_ahead: i64) -> bool {
-            false
-        }
+        engine.connector_symbols.insert("CCC".to_string());
+        let mut new_cfg = engine.cfg.clone();
+        new_cfg.universe.push(added.clone());
+        engine.apply_universe_reload(&new_cfg);
+        assert!(engine.instances[0].states["AAA/CCC"].spread_history.is_empty());
+
+        engine.cfg.universe.retain(|p| p.quote != "CCC");
+        engine.instances[0].states.remove("AAA/CCC");
+        engine.cfg.seed_new_pairs_from_history = true;
+        engine.cfg.metrics_window = 10;
+        engine.apply_universe_reload(&new_cfg);
+        let state = &engine.instances[0].states["AAA/CCC"];
+        assert_eq!(state.spread_history.len(), 10);
+        assert!(state.last_spread.is_some());
+    }
 
-        async fn sign_evm_65b(&self, _message: &str) -> Result<String, DexError> {
-            Ok("signed".to_string())
-        }
+    #[test]
+    fn universe_reload_applies_z_stat_mode_to_existing_pairs() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        engine.instances[0]
+            .states
+            .insert("AAA/BBB".to_string(), PairState::new(10, 2.0));
+        let mut new_cfg = engine.cfg.clone();
+        new_cfg.z_stat_mode = ZStatMode::Robust;
 
-        async fn sign_evm_65b_with_eip191(&self, _message: &str) -> Result<String, DexError> {
-            Ok("signed".to_string())
-        }
+        engine.apply_universe_reload(&new_cfg);
+        assert_eq!(engine.cfg.z_stat_mode, ZStatMode::Robust);
+        assert_eq!(engine.instances[0].states["AAA/BBB"].z_stat_mode, ZStatMode::Robust);
     }
 
     #[tokio::test]
-    async fn reissue_partial_entry_leg_reorders_remaining() {
+    async fn universe_validation_drops_or_aborts_on_bad_symbols() {
         let connector = Arc::new(DummyConnector::default());
+        {
+            let mut tickers = connector.tickers.lock().unwrap();
+            for sym in ["AAA", "BBB", "CCC"] {
+                tickers.insert(sym.to_string(), (dec("10"), Some(dec("0.1"))));
+            }
+            // DDD quotes zero; EEE is missing entirely (unknown symbol).
+            tickers.insert("DDD".to_string(), (dec("0"), Some(dec("0.1"))));
+        }
         let mut engine = PairTradeEngine::test_instance(connector.clone());
+        let pair = |base: &str, quote: &str| PairSpec {
+            base: base.to_string(),
+            quote: quote.to_string(),
+        };
+        let mut new_cfg = engine.cfg.clone();
+        new_cfg.universe = vec![pair("AAA", "BBB"), pair("CCC", "DDD"), pair("AAA", "EEE")];
+        engine
+            .connector_symbols
+            .extend(["CCC", "DDD", "EEE"].map(str::to_string));
+        engine.apply_universe_reload(&new_cfg);
+
+        engine.cfg.strict_universe_validation = true;
+        assert!(engine.validate_universe().await.is_err());
+        assert_eq!(engine.cfg.universe.len(), 3);
+
+        engine.cfg.strict_universe_validation = false;
+        engine.validate_universe().await.unwrap();
+        assert_eq!(engine.cfg.universe.len(), 1);
+        assert_eq!(engine.cfg.universe[0].quote, "BBB");
+        assert!(!engine.instances[0].states.contains_key("CCC/DDD"));
+        assert!(!engine.history.contains_key("DDD"));
+
+        // A 429 is retried instead of dropping the pair.
+        connector.ticker_failures.store(1, Ordering::SeqCst);
+        engine.validate_universe().await.unwrap();
+        assert_eq!(engine.cfg.universe.len(), 1);
+
+        // Still failing after every retry: kept with a warning.
+        connector.ticker_failures.store(usize::MAX, Ordering::SeqCst);
+        engine.validate_universe().await.unwrap();
+        assert_eq!(engine.cfg.universe.len(), 1);
+        connector.ticker_failures.store(0, Ordering::SeqCst);
+
+        // A ticker without min_order is as bad as a zero price.
+        connector.tickers.lock().unwrap().insert("BBB".to_string(), (dec("10"), None));
+        assert!(engine.validate_universe().await.is_err());
+    }
+
+    #[test]
+    fn realized_slippage_blocks_symbol_until_it_recovers() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        engine.cfg.max_realized_slippage_bps = 5.0;
         let pending = PendingOrders {
             legs: vec![PendingLeg {
                 symbol: "AAA".to_string(),
-                order_id: "leg1".to_string(),
+                order_id: "1".to_string(),
                 exchange_order_id: None,
-                target: dec("0.05"),
-                filled: Decimal::ZERO,
+                target: dec("2"),
+                filled: dec("2"),
                 side: OrderSide::Long,
-                limit_price: None,
+                limit_price: Some(dec("100")),
+                placed_price: None,
             }],
             direction: PositionDirection::LongSpread,
             placed_at: Instant::now(),
             hedge_retry_count: 0,
             post_only_hybrid: false,
+            partial_exit: false,
+            scale_in: false,
         };
-        let mut price_map = HashMap::new();
-        price_map.insert(
-            "AAA".to_string(),
-            SymbolSnapshot {
-                price: dec("100.0"),
-                funding_rate: Decimal::ZERO,
-                bid_price: None,
-                ask_price: None,
-                bid_size: Decimal::ZERO,
-                ask_size: Decimal::ZERO,
-                min_order: Some(dec("0.001")),
-                min_tick: Some(dec("0.001")),
-                size_decimals: Some(3),
-                exchange_ts: None,
-            },
-        );
-        let filled_qtys = HashMap::from([(pending.legs[0].order_id.clone(), dec("0.02"))]);
+        let status = |value: &str| PendingStatus {
+            open_remaining: 0,
+            fills: HashMap::from([("1".to_string(), dec("2"))]),
+            fill_values: HashMap::from([("1".to_string(), dec(value))]),
+            fill_fees: HashMap::new(),
+            open_ids: HashSet::new(),
+        };
+        // Bought 2 @ 100.2 against a 100 limit: 20 bps worse each time.
+        for _ in 0..REALIZED_SLIPPAGE_MIN_SAMPLES - 1 {
+            engine.record_realized_slippage(&pending, &status("200.4"));
+        }
+        assert!(engine.slippage_blocked.is_empty());
+        engine.record_realized_slippage(&pending, &status("200.4"));
+        assert!(engine.slippage_blocked.contains_key("AAA"));
 
-        let result = engine
-            .reissue_partial_legs(&pending, &filled_qtys, &price_map, false, false, 0)
-            .await
-            .unwrap()
-            .unwrap();
-        assert_eq!(result.legs.len(), 2);
-        assert!(result
-            .legs
-            .iter()
-            .any(|leg| leg.target == dec("0.02") && leg.filled == dec("0.02")));
-        assert!(result
-            .legs
-            .iter()
-            .any(|leg| leg.target == dec("0.03") && leg.filled == Decimal::ZERO));
-        let calls = connector.calls.lock().unwrap();
-        assert_eq!(calls.len(), 1);
-        assert_eq!(calls[0].0, "AAA");
-        assert_eq!(calls[0].3, Some(dec("100.0")));
-        assert!(!calls[0].4);
+        // Price-improved fills pull the rolling mean back under the cap.
+        for _ in 0..3 {
+            engine.record_realized_slippage(&pending, &status("199.6"));
+        }
+        assert!(engine.slippage_blocked.is_empty());
     }
 
-    #[tokio::test]
-    async fn reissue_partial_entry_missing_price_keeps_pending() {
+    #[test]
+    fn realized_slippage_block_lapses_and_market_legs_use_the_placed_price() {
         let connector = Arc::new(DummyConnector::default());
         let mut engine = PairTradeEngine::test_instance(connector);
+        let clock = Arc::new(clock::ManualClock::new());
+        engine.clock = clock.clone();
+        engine.cfg.max_realized_slippage_bps = 5.0;
+        engine.cfg.realized_slippage_block_secs = 600;
+        // A market buy placed at 100: the fill is judged against that, not
+        // whatever the symbol trades at when the fill is reconciled.
         let pending = PendingOrders {
             legs: vec![PendingLeg {
                 symbol: "AAA".to_string(),
-                order_id: "leg1".to_string(),
+                order_id: "1".to_string(),
                 exchange_order_id: None,
-                target: dec("0.05"),
-                filled: Decimal::ZERO,
+                target: dec("2"),
+                filled: dec("2"),
                 side: OrderSide::Long,
                 limit_price: None,
+                placed_price: Some(dec("100")),
             }],
             direction: PositionDirection::LongSpread,
+            placed_at: clock.now(),
+            hedge_retry_count: 0,
+            post_only_hybrid: false,
+            partial_exit: false,
+            scale_in: false,
+        };
+        let status = PendingStatus {
+            open_remaining: 0,
+            fills: HashMap::from([("1".to_string(), dec("2"))]),
+            fill_values: HashMap::from([("1".to_string(), dec("200.4"))]),
+            fill_fees: HashMap::new(),
+            open_ids: HashSet::new(),
+        };
+        for _ in 0..REALIZED_SLIPPAGE_MIN_SAMPLES {
+            engine.record_realized_slippage(&pending, &status);
+        }
+        let window = &engine.realized_slippage["AAA"];
+        assert!(window.iter().all(|bps| (bps - 20.0).abs() < 1e-6));
+        assert!(engine.slippage_block_active("AAA"));
+
+        // Without fills to recover on, the block lapses on time and the
+        // window starts over.
+        clock.advance(Duration::from_secs(599));
+        assert!(engine.slippage_block_active("AAA"));
+        clock.advance(Duration::from_secs(1));
+        assert!(!engine.slippage_block_active("AAA"));
+        assert!(engine.slippage_blocked.is_empty());
+        assert!(!engine.realized_slippage.contains_key("AAA"));
+    }
+
+    #[test]
+    fn aging_positions_lists_positions_past_warn_age() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        let position = |entered_ts: i64| Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts,
+            entry_price_a: None,
+            entry_price_b: None,
+            entry_size_a: None,
+            entry_size_b: None,
+            entry_z: None,
+            entry_beta: None,
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        };
+        let mut young = PairState::new(10, 2.0);
+        young.position = Some(position(9_500));
+        let mut old = PairState::new(10, 2.0);
+        old.position = Some(position(1_000));
+        engine.instances[0].states.insert("AAA/BBB".to_string(), young);
+        engine.instances[0].states.insert("CCC/DDD".to_string(), old);
+
+        assert!(engine.aging_positions(0, 10_000).is_empty());
+
+        engine.cfg.position_age_warn_secs = 3_600;
+        let aging = engine.aging_positions(0, 10_000);
+        assert_eq!(aging.len(), 1);
+        assert_eq!(aging[0].key, "CCC/DDD");
+        assert_eq!(aging[0].age_secs, 9_000);
+        assert!(engine.last_position_warn.contains_key("aging:CCC/DDD"));
+    }
+
+    #[test]
+    fn dust_uses_per_symbol_notional_floor() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        let snap = |price: &str| SymbolSnapshot {
+            min_order: Some(dec("0.0001")),
+            ..SymbolSnapshot::test_at(dec(price))
+        };
+        let prices = HashMap::from([
+            ("BTC".to_string(), snap("60000")),
+            ("PEPE".to_string(), snap("0.00001")),
+        ]);
+        let position = |symbol: &str, size: &str| PositionSnapshot {
+            symbol: symbol.to_string(),
+            size: dec(size),
+            sign: 1,
+            ..Default::default()
+        };
+        // Exchange minimum only by default: 1000 PEPE is above it.
+        assert!(!engine.is_dust_position(&position("PEPE", "1000"), &prices));
+        assert!(engine.is_dust_position(&position("BTC", "0.00005"), &prices));
+
+        engine.cfg.dust_notional_usd = 100.0;
+        engine.cfg.dust_notional_usd_by_symbol = HashMap::from([("BTC".to_string(), 5.0)]);
+        // $0.01 of PEPE falls under the global floor; $60 of BTC clears its own.
+        assert!(engine.is_dust_position(&position("PEPE", "1000"), &prices));
+        assert!(!engine.is_dust_position(&position("BTC", "0.001"), &prices));
+        assert!(engine.is_dust_position(&position("BTC", "0.00008"), &prices));
+    }
+
+    #[test]
+    fn fill_cash_flow_nets_values_and_fees() {
+        let connector = Arc::new(DummyConnector::default());
+        let engine = PairTradeEngine::test_instance(connector);
+        let leg = |symbol: &str, id: &str, side| PendingLeg {
+            symbol: symbol.to_string(),
+            order_id: id.to_string(),
+            exchange_order_id: None,
+            target: dec("1"),
+            filled: dec("1"),
+            side,
+            limit_price: None,
+            placed_price: None,
+        };
+        let pending = PendingOrders {
+            legs: vec![leg("AAA", "1", OrderSide::Long), leg("BBB", "2", OrderSide::Short)],
+            direction: PositionDirection::LongSpread,
             placed_at: Instant::now(),
             hedge_retry_count: 0,
             post_only_hybrid: false,
+            partial_exit: false,
+            scale_in: false,
         };
-        let filled_qtys = HashMap::from([(pending.legs[0].order_id.clone(), dec("0.02"))]);
+        let mut status = PendingStatus {
+            open_remaining: 0,
+            fills: HashMap::from([("1".to_string(), dec("1")), ("2".to_string(), dec("1"))]),
+            fill_values: HashMap::from([
+                ("1".to_string(), dec("100")),
+                ("2".to_string(), dec("50")),
+            ]),
+            fill_fees: HashMap::from([("1".to_string(), dec("0.05"))]),
+            open_ids: HashSet::new(),
+        };
+        // Bought AAA for 100, sold BBB for 50, paid 0.05 in fees.
+        assert_eq!(engine.fill_cash_flow(&pending, &status), Some(dec("-50.05")));
+        status.fill_values.remove("2");
+        assert_eq!(engine.fill_cash_flow(&pending, &status), None);
+    }
 
-        let result = engine
-            .reissue_partial_legs(&pending, &filled_qtys, &HashMap::new(), false, false, 0)
+    #[tokio::test]
+    async fn equity_guard_blocks_below_min_equity_and_on_fetch_failures() {
+        let connector = Arc::new(DummyConnector::default());
+        *connector.balance_equity.lock().unwrap() = Some(dec("50.0"));
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        assert!(!engine.equity_guard_blocks(&engine.instances[0]));
+
+        engine.cfg.min_equity_usd = 100.0;
+        engine.fetch_equity_rest(0).await;
+        assert!(engine.equity_guard_blocks(&engine.instances[0]));
+        assert!(engine.instances[0].equity_guard_halted);
+
+        *connector.balance_equity.lock().unwrap() = Some(dec("150.0"));
+        engine.last_account_rest_call = None;
+        engine.fetch_equity_rest(0).await;
+        assert!(!engine.equity_guard_blocks(&engine.instances[0]));
+        assert!(!engine.instances[0].equity_guard_halted);
+
+        engine.cfg.max_balance_fetch_failures = 2;
+        engine.instances[0].balance_fetch_failures = 2;
+        assert!(engine.equity_guard_blocks(&engine.instances[0]));
+        engine.last_account_rest_call = None;
+        engine.fetch_equity_rest(0).await;
+        assert_eq!(engine.instances[0].balance_fetch_failures, 0);
+        assert!(!engine.equity_guard_blocks(&engine.instances[0]));
+    }
+
+    #[tokio::test]
+    async fn equity_fallback_cycles_block_entries_until_fetch_succeeds() {
+        let connector = Arc::new(DummyConnector::default());
+        *connector.balance_equity.lock().unwrap() = Some(dec("500.0"));
+        let mut engine = PairTradeEngine::test_instance(connector);
+        engine.cfg.max_equity_fallback_cycles = 3;
+
+        engine.count_equity_fallback_cycle(0);
+        assert_eq!(engine.instances[0].equity_fallback_cycles, 0);
+
+        engine.instances[0].balance_fetch_failures = 1;
+        for _ in 0..2 {
+            engine.count_equity_fallback_cycle(0);
+        }
+        assert!(!engine.equity_guard_blocks(&engine.instances[0]));
+        engine.count_equity_fallback_cycle(0);
+        assert!(engine.equity_guard_blocks(&engine.instances[0]));
+        assert!(engine.instances[0].equity_guard_halted);
+
+        engine.fetch_equity_rest(0).await;
+        assert_eq!(engine.instances[0].equity_fallback_cycles, 0);
+        assert!(!engine.equity_guard_blocks(&engine.instances[0]));
+        assert!(!engine.instances[0].equity_guard_halted);
+    }
+
+    #[test]
+    fn default_equity_fallback_cap_blocks_at_the_bound() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        assert_eq!(engine.cfg.max_equity_fallback_cycles, DEFAULT_MAX_EQUITY_FALLBACK_CYCLES);
+
+        engine.instances[0].balance_fetch_failures = 1;
+        for _ in 1..DEFAULT_MAX_EQUITY_FALLBACK_CYCLES {
+            engine.count_equity_fallback_cycle(0);
+        }
+        assert!(!engine.equity_guard_blocks(&engine.instances[0]));
+        engine.count_equity_fallback_cycle(0);
+        assert!(engine.equity_guard_blocks(&engine.instances[0]));
+    }
+
+    #[tokio::test]
+    async fn connector_restarts_after_consecutive_failures_with_cooldown() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+
+        engine.note_connector_failure("price fetch").await;
+        assert_eq!(engine.connector_failures, 0);
+
+        engine.cfg.auto_restart_after_failures = 2;
+        engine.cfg.auto_restart_cooldown_secs = 600;
+        engine.note_connector_failure("price fetch").await;
+        assert_eq!(connector.restart_calls.load(Ordering::SeqCst), 0);
+        engine.note_connector_failure("position sync").await;
+        assert_eq!(connector.restart_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(engine.connector_failures, 0);
+
+        // Inside the cooldown further failures do not restart again.
+        for _ in 0..3 {
+            engine.note_connector_failure("price fetch").await;
+        }
+        assert_eq!(connector.restart_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(engine.connector_failures, 3);
+
+        engine.cfg.auto_restart_cooldown_secs = 0;
+        engine.note_connector_failure("price fetch").await;
+        assert_eq!(connector.restart_calls.load(Ordering::SeqCst), 2);
+
+        // An instance on its own connector is restarted too.
+        let own = Arc::new(DummyConnector::default());
+        engine.instances[0].connector = own.clone();
+        engine.cfg.auto_restart_after_failures = 1;
+        engine.note_connector_failure("position sync").await;
+        assert_eq!(connector.restart_calls.load(Ordering::SeqCst), 3);
+        assert_eq!(own.restart_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn fixed_pair_notional_overrides_equity_sizing() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector);
+        engine.cfg.max_leverage = 1.0;
+        engine.cfg.risk_pct_per_trade = 0.1;
+        engine.instances[0].equity_reference_usd = 10_000.0;
+        let fixed = PairParams {
+            notional_per_leg_usd: Some(2_000.0),
+            ..engine.instances[0].default_pair_params.clone()
+        };
+        engine.instances[0].pair_params.insert("CCC/DDD".to_string(), fixed);
+        let snap = |price: &str| SymbolSnapshot::test_at(dec(price));
+        let (p1, p2) = (snap("100"), snap("50"));
+        let pair = |base: &str, quote: &str| PairSpec {
+            base: base.to_string(),
+            quote: quote.to_string(),
+        };
+        let long = PositionDirection::LongSpread;
+
+        // Scaled pair: 10k * 10% * 1x / 2 = $500 on leg A.
+        let scaled = engine.hedged_sizes(0, &pair("AAA", "BBB"), long, 1.0, &p1, &p2).unwrap();
+        assert_eq!(scaled, (dec("5"), dec("10")));
+        // Fixed pair: $2000 regardless of equity.
+        let fixed = engine.hedged_sizes(0, &pair("CCC", "DDD"), long, 1.0, &p1, &p2).unwrap();
+        assert_eq!(fixed, (dec("20"), dec("40")));
+
+        // At 3k equity the scaled pair shrinks; the fixed one holds until
+        // leverage binds at 3k / (1 + 1) = $1500 per leg.
+        engine.instances[0].equity_reference_usd = 3_000.0;
+        let scaled = engine.hedged_sizes(0, &pair("AAA", "BBB"), long, 1.0, &p1, &p2).unwrap();
+        assert_eq!(scaled, (dec("1.5"), dec("3")));
+        let fixed = engine.hedged_sizes(0, &pair("CCC", "DDD"), long, 1.0, &p1, &p2).unwrap();
+        assert_eq!(fixed, (dec("15"), dec("30")));
+    }
+
+    #[tokio::test]
+    async fn entry_market_mode_places_both_legs_without_limits() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        let snap = |price: &str| SymbolSnapshot {
+            min_order: Some(dec("0.01")),
+            min_tick: Some(dec("0.01")),
+            ..SymbolSnapshot::test_at(dec(price))
+        };
+        let prices = HashMap::from([
+            ("AAA".to_string(), snap("100")),
+            ("BBB".to_string(), snap("50")),
+        ]);
+        let pair = engine.cfg.universe[0].clone();
+        let qtys = (dec("1"), dec("2"));
+        let direction = PositionDirection::LongSpread;
+
+        let legs = engine
+            .place_pair_orders(0, &pair, direction, false, qtys, &prices)
             .await
-            .unwrap()
             .unwrap();
-        assert_eq!(result.legs.len(), 1);
-        assert_eq!(result.legs[0].target, dec("0.05"));
-        assert_eq!(result.legs[0].filled, dec("0.02"));
+        assert_eq!(legs.len(), 2);
+        assert!(connector.calls.lock().unwrap().iter().all(|call| call.3.is_some()));
+
+        connector.calls.lock().unwrap().clear();
+        engine.cfg.entry_market_mode = true;
+        let legs = engine
+            .place_pair_orders(0, &pair, direction, false, qtys, &prices)
+            .await
+            .unwrap();
+        assert!(legs.iter().all(|leg| leg.limit_price.is_none()));
+        let calls = connector.calls.lock().unwrap().clone();
+        assert_eq!(calls.len(), 2);
+        assert_eq!((calls[0].0.as_str(), calls[0].2), ("AAA", OrderSide::Long));
+        assert_eq!((calls[1].0.as_str(), calls[1].2), ("BBB", OrderSide::Short));
+        assert!(calls.iter().all(|call| call.3.is_none() && !call.4));
     }
 
     #[tokio::test]
-    async fn refresh_equity_if_needed_skips_when_cache_is_fresh() {
+    async fn non_finite_spread_blocks_the_pair_instead_of_trading() {
         let connector = Arc::new(DummyConnector::default());
-        *connector.balance_equity.lock().unwrap() = Some(dec("1234.56"));
         let mut engine = PairTradeEngine::test_instance(connector.clone());
-        engine.instances[0].last_equity_fetch = Some(Instant::now());
-        let initial_equity = engine.instances[0].equity_cache;
-
-        engine.refresh_equity_if_needed(0).await.unwrap();
+        engine.cfg.metrics_window = 10;
+        engine.positions_ready = true;
+        let bars = |last: f64| -> VecDeque<PriceSample> {
+            (0..30)
+                .map(|i| PriceSample {
+                    log_price: if i == 29 { last } else { (100.0 + (i % 3) as f64).ln() },
+                    ts: i * 60,
+                    close: None,
+                })
+                .collect()
+        };
+        engine.history.insert("AAA".to_string(), bars(200f64.ln()));
+        engine.history.insert("BBB".to_string(), bars(f64::NAN));
+        let mut state = PairState::new(10, 2.0);
+        state.spread_history.extend((0..9).map(|i| (i % 3) as f64 * 0.01));
+        state.spread_ts.extend((0..9).map(|i| i * 60));
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
+        let snap = |price: &str| SymbolSnapshot::test_at(dec(price));
+        let prices = HashMap::from([
+            ("AAA".to_string(), snap("200")),
+            ("BBB".to_string(), snap("100")),
+        ]);
+        let updated = HashSet::from(["AAA".to_string(), "BBB".to_string()]);
+
+        engine.step_for_instance(0, &prices, &updated).await.unwrap();
+        let now_ts = engine.current_now_ts();
+        let state = &engine.instances[0].states["AAA/BBB"];
+        assert!(state.stats_blocked(now_ts));
+        assert!(state.position.is_none());
+        assert!(state.pending_entry.is_none());
+        assert!(connector.calls.lock().unwrap().is_empty());
+    }
 
-        assert_eq!(connector.balance_calls.load(Ordering::SeqCst), 0);
-        assert_eq!(engine.instances[0].equity_cache, initial_equity);
+    #[tokio::test]
+    async fn unchanged_closes_skip_flat_pairs_only() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = PairTradeEngine::test_instance(connector.clone());
+        engine.cfg.skip_unchanged_bars = true;
+        for (symbol, price) in [("AAA", 200.0_f64), ("BBB", 100.0)] {
+            // The last two closes are equal.
+            let bars = (0..30)
+                .map(|i: i64| PriceSample {
+                    log_price: (price + (i.min(28) % 3) as f64).ln(),
+                    ts: i * 60,
+                    close: None,
+                })
+                .collect();
+            engine.history.insert(symbol.to_string(), bars);
+        }
+        engine.instances[0]
+            .states
+            .insert("AAA/BBB".to_string(), PairState::new(10, 2.0));
+        let snap = |price: &str| SymbolSnapshot::test_at(dec(price));
+        let prices = HashMap::from([
+            ("AAA".to_string(), snap("200")),
+            ("BBB".to_string(), snap("100")),
+        ]);
+        let evals = |engine: &PairTradeEngine| engine.instances[0].states["AAA/BBB"].eval_ticks;
+
+        let both = HashSet::from(["AAA".to_string(), "BBB".to_string()]);
+        engine.step_for_instance(0, &prices, &both).await.unwrap();
+        assert_eq!(evals(&engine), 0);
+
+        // Only a close from this tick counts as unchanged.
+        engine.cfg.skip_unchanged_bars = false;
+        engine.step_for_instance(0, &prices, &both).await.unwrap();
+        assert_eq!(evals(&engine), 1);
+        engine.cfg.skip_unchanged_bars = true;
+        assert!(engine.bar_unchanged("AAA", &both));
+        assert!(!engine.bar_unchanged("AAA", &HashSet::new()));
+
+        // A pair holding a position keeps being evaluated.
+        for (symbol, size, sign) in [("AAA", "1", 1), ("BBB", "2", -1)] {
+            connector.positions.lock().unwrap().push(PositionSnapshot {
+                symbol: symbol.to_string(),
+                size: dec(size),
+                sign,
+                ..Default::default()
+            });
+        }
+        for h in engine.history.values_mut() {
+            let last = h.back().unwrap().clone();
+            h.push_back(PriceSample { ts: last.ts + 60, ..last });
+        }
+        engine.step_for_instance(0, &prices, &both).await.unwrap();
+        assert_eq!(evals(&engine), 2);
     }
 
     #[tokio::test]
-    async fn refresh_equity_if_needed_fetches_when_cache_is_stale() {
+    async fn warm_check_leaves_exits_of_open_positions_running() {
         let connector = Arc::new(DummyConnector::default());
-        *connector.balance_equity.lock().unwrap() = Some(dec("1234.56"));
         let mut engine = PairTradeEngine::test_instance(connector.clone());
-        engine.instances[0].last_equity_fetch = Some(
-            Instant::now() - Duration::from_secs(EQUITY_REFRESH_CACHE_SECS + 1),
-        );
+        engine.cfg.metrics_window = 5;
+        let pp = &mut engine.instances[0].default_pair_params;
+        pp.exit_z = 100.0;
+        pp.stop_loss_z = 200.0;
+        pp.force_close_secs = 3600;
+        for (symbol, price) in [("AAA", 200.0_f64), ("BBB", 100.0)] {
+            let bars = (0..30)
+                .map(|i| PriceSample {
+                    log_price: (price + (i % 3) as f64).ln(),
+                    ts: i * 60,
+                    close: None,
+                })
+                .collect();
+            engine.history.insert(symbol.to_string(), bars);
+        }
+        let mut state = PairState::new(5, 2.0);
+        state.eligible = true;
+        for (i, spread) in [0.1, 0.3, 0.2].into_iter().enumerate() {
+            state.push_spread(spread, i as i64 * 60, 5, &engine.cfg);
+        }
+        state.position = Some(Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts: engine.current_now_ts(),
+            entry_price_a: Some(dec("200")),
+            entry_price_b: Some(dec("100")),
+            entry_size_a: Some(dec("1")),
+            entry_size_b: Some(dec("2")),
+            entry_z: None,
+            entry_beta: Some(1.0),
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        });
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
+        for (symbol, size, sign) in [("AAA", "1", 1), ("BBB", "2", -1)] {
+            connector.positions.lock().unwrap().push(PositionSnapshot {
+                symbol: symbol.to_string(),
+                size: dec(size),
+                sign,
+                ..Default::default()
+            });
+        }
+        let snap = |price: &str| SymbolSnapshot::test_at(dec(price));
+        let prices = HashMap::from([
+            ("AAA".to_string(), snap("200")),
+            ("BBB".to_string(), snap("100")),
+        ]);
+
+        // Four spread points are short of the 10 an entry would need.
+        let both = HashSet::from(["AAA".to_string(), "BBB".to_string()]);
+        engine.step_for_instance(0, &prices, &both).await.unwrap();
+        let state = &engine.instances[0].states["AAA/BBB"];
+        assert!(state.spread_history.len() < 10);
+        assert_eq!(state.last_exit_reason, Some("exit_z"));
+    }
 
-        engine.refresh_equity_if_needed(0).await.unwrap();
+    #[tokio::test]
+    async fn outlier_bars_give_way_to_a_sustained_level_shift() {
+        let mut engine = PairTradeEngine::test_instance(Arc::new(DummyConnector::default()));
+        let bars = (0..3)
+            .map(|i| PriceSample {
+                log_price: 100f64.ln(),
+                ts: i * 60,
+                close: None,
+            })
+            .collect();
+        engine.history.insert("AAA".to_string(), bars);
+        let reject = |engine: &mut PairTradeEngine, price: &str, ts: i64| {
+            engine.reject_outlier_bar("AAA", dec(price).to_f64().unwrap().ln(), dec(price), ts)
+        };
 
-        assert_eq!(connector.balance_calls.load(Ordering::SeqCst), 1);
-        assert!((engine.instances[0].equity_cache - 1234.56).abs() < 1e-6);
+        // Disabled at zero.
+        engine.cfg.max_bar_log_return = 0.0;
+        assert!(!reject(&mut engine, "300", 180));
+
+        engine.cfg.max_bar_log_return = DEFAULT_MAX_BAR_LOG_RETURN;
+        // A move inside the bound is kept.
+        assert!(!reject(&mut engine, "150", 180));
+        // A lone bad tick is dropped and the streak ends on a normal bar.
+        assert!(reject(&mut engine, "300", 180));
+        assert!(!reject(&mut engine, "101", 180));
+        assert!(!engine.outlier_bar_streaks.contains_key("AAA"));
+
+        // A move that persists is accepted on its third bar.
+        assert!(reject(&mut engine, "300", 240));
+        assert!(reject(&mut engine, "301", 300));
+        assert!(!reject(&mut engine, "302", 360));
+        assert!(!engine.outlier_bar_streaks.contains_key("AAA"));
     }
 
     #[tokio::test]
-    async fn fetch_equity_rest_bypasses_cache() {
-        // Pre-entry path must hit REST regardless of cache age so the
-        // about-to-be-placed order is sized against a current value.
+    async fn one_sided_bar_close_is_tracked_per_streak() {
         let connector = Arc::new(DummyConnector::default());
-        *connector.balance_equity.lock().unwrap() = Some(dec("777.0"));
         let mut engine = PairTradeEngine::test_instance(connector.clone());
-        engine.instances[0].last_equity_fetch = Some(Instant::now());
+        for (symbol, price) in [("AAA", 200.0_f64), ("BBB", 100.0)] {
+            let bars = (0..30)
+                .map(|i| PriceSample {
+                    log_price: (price + (i % 3) as f64).ln(),
+                    ts: i * 60,
+                    close: None,
+                })
+                .collect();
+            engine.history.insert(symbol.to_string(), bars);
+        }
+        engine.instances[0]
+            .states
+            .insert("AAA/BBB".to_string(), PairState::new(10, 2.0));
+        let snap = |price: &str| SymbolSnapshot::test_at(dec(price));
+        let prices = HashMap::from([
+            ("AAA".to_string(), snap("200")),
+            ("BBB".to_string(), snap("100")),
+        ]);
+
+        let base_only = HashSet::from(["AAA".to_string()]);
+        engine.step_for_instance(0, &prices, &base_only).await.unwrap();
+        engine.step_for_instance(0, &prices, &base_only).await.unwrap();
+        assert!(engine.one_sided_bar_pairs.contains("AAA/BBB"));
+
+        let both = HashSet::from(["AAA".to_string(), "BBB".to_string()]);
+        engine.step_for_instance(0, &prices, &both).await.unwrap();
+        assert!(engine.one_sided_bar_pairs.is_empty());
+    }
 
-        engine.fetch_equity_rest(0).await;
+    #[test]
+    fn new_entry_clears_previous_exit_reason() {
+        let mut state = PairState::new(10, 2.0);
+        state.last_exit_reason = Some("stop_loss_z");
+        state.idle_alerted = true;
+        state.mark_entry(1_000);
+        assert_eq!(state.last_exit_reason, None);
+        assert_eq!(state.last_entry_ts, Some(1_000));
+        assert!(!state.idle_alerted);
+    }
 
-        assert_eq!(connector.balance_calls.load(Ordering::SeqCst), 1);
-        assert!((engine.instances[0].equity_cache - 777.0).abs() < 1e-6);
+    #[test]
+    fn losing_exit_blocks_reentry_for_n_ticks() {
+        let mut state = PairState::new(10, 2.0);
+        state.eval_ticks = 40;
+        state.record_exit_pnl(-3.0);
+        assert!(!state.loss_reentry_blocked(0));
+        state.eval_ticks = 45;
+        assert!(state.loss_reentry_blocked(5));
+        state.eval_ticks = 46;
+        assert!(!state.loss_reentry_blocked(5));
+        state.record_exit_pnl(0.0);
+        assert!(!state.loss_reentry_blocked(u64::MAX));
+        state.record_exit_pnl(-1.0);
+        state.record_exit_pnl(1.0);
+        assert!(!state.loss_reentry_blocked(u64::MAX));
+    }
+
+    #[tokio::test]
+    async fn flat_exit_clears_the_loss_reentry_block() {
+        let connector = Arc::new(DummyConnector::default());
+        let mut engine = entry_ready_engine(connector.clone());
+        engine.cfg.loss_reentry_block_ticks = 5;
+        engine.instances[0].default_pair_params.force_close_secs = 1;
+        let leg = |symbol: &str, signed: &str, entry: &str| {
+            paper_snapshot(symbol.to_string(), dec(signed), Some(dec(entry))).unwrap()
+        };
+        let both = HashSet::from(["AAA".to_string(), "BBB".to_string()]);
+        // Short AAA from 190 loses at 200; from 200 it closes flat.
+        for (entry_a, loss_recorded) in [("190", true), ("200", false)] {
+            *connector.positions.lock().unwrap() =
+                vec![leg("AAA", "-1", entry_a), leg("BBB", "1", "100")];
+            engine.instances[0].states.get_mut("AAA/BBB").unwrap().position = Some(Position {
+                direction: PositionDirection::ShortSpread,
+                entered_at: Instant::now(),
+                entered_ts: 0,
+                entry_price_a: Some(dec(entry_a)),
+                entry_price_b: Some(dec("100")),
+                entry_size_a: Some(dec("1")),
+                entry_size_b: Some(dec("1")),
+                entry_z: Some(2.0),
+                entry_beta: Some(1.0),
+                entry_fill_cash: None,
+                partial_taken: false,
+                scaled_in: false,
+                scale_in_add: None,
+                hedge_inverted: false,
+            });
+
+            engine.step_for_instance(0, &entry_step_prices(), &both).await.unwrap();
+
+            let state = &engine.instances[0].states["AAA/BBB"];
+            assert!(state.position.is_none());
+            assert_eq!(state.last_loss_exit_tick.is_some(), loss_recorded);
+        }
+    }
+
+    #[tokio::test]
+    async fn observe_paper_fills_keep_positions_across_exchange_sync() {
+        let mut engine = PairTradeEngine::test_instance(Arc::new(DummyConnector::default()));
+        let paper = || Position {
+            direction: PositionDirection::LongSpread,
+            entered_at: Instant::now(),
+            entered_ts: 0,
+            entry_price_a: Some(dec("100")),
+            entry_price_b: Some(dec("50")),
+            entry_size_a: Some(dec("1")),
+            entry_size_b: Some(dec("2")),
+            entry_z: None,
+            entry_beta: None,
+            entry_fill_cash: None,
+            partial_taken: false,
+            scaled_in: false,
+            scale_in_add: None,
+            hedge_inverted: false,
+        };
+        engine.cfg.dry_run = false;
+        engine.cfg.observe_only = true;
+        engine.cfg.observe_simulate_pnl = true;
+        assert!(engine.cfg.paper_fills());
+        let mut state = PairState::new(10, 2.0);
+        state.position = Some(paper());
+        engine.instances[0].states.insert("AAA/BBB".to_string(), state);
+        engine.sync_positions_from_exchange(0, &HashMap::new()).await.unwrap();
+        assert!(engine.positions_ready);
+        assert!(engine.instances[0].states["AAA/BBB"].position.is_some());
+
+        // Plain observe_only stays a signal observer: the flat exchange wins.
+        engine.cfg.observe_simulate_pnl = false;
+        assert!(!engine.cfg.paper_fills());
+        engine.sync_positions_from_exchange(0, &HashMap::new()).await.unwrap();
+        assert!(engine.instances[0].states["AAA/BBB"].position.is_none());
     }
 }
 
@@ -5742,8 +12821,8 @@ mod shutdown_grace_tests {
     #[test]
     fn validate_rejects_strategy_force_close_exceeding_grace() {
         use std::io::Write;
-        let dir = std::env::temp_dir();
-        let path = dir.join("pairtrade_validate_regression.yaml");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pairtrade_validate_regression.yaml");
         let yaml = r#"
 dex_name: lighter
 rest_endpoint: https://example
@@ -5768,6 +12847,5 @@ strategies:
             msg.contains("shutdown_grace_secs"),
             "error should mention shutdown_grace_secs, got: {msg}"
         );
-        let _ = std::fs::remove_file(&path);
     }
 }
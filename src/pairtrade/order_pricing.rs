@@ -39,6 +39,19 @@ pub(super) fn apply_slippage(
     }
 }
 
+/// Modeled fill for a backtest paper entry: `price` moved against the order
+/// by `cost_bps` (buys pay more, sells receive less).
+pub(super) fn cost_adjusted_fill(price: Decimal, side: dex_connector::OrderSide, cost_bps: f64) -> Decimal {
+    if cost_bps <= 0.0 {
+        return price;
+    }
+    let factor = Decimal::from_f64(cost_bps / 10_000.0).unwrap_or(Decimal::ZERO);
+    match side {
+        dex_connector::OrderSide::Long => price * (Decimal::ONE + factor),
+        dex_connector::OrderSide::Short => price * (Decimal::ONE - factor),
+    }
+}
+
 pub(super) fn quantize_order_size(
     symbol: &str,
     size: Decimal,
@@ -110,6 +123,24 @@ pub(super) fn quantize_order_size_close(
     size
 }
 
+/// Reduce-only close size for a dust remnant: ceiling-quantized to the
+/// symbol's size step. `None` when that is still below `min_order`, i.e.
+/// the exchange would reject it and the dust has to be accepted.
+pub(super) fn dust_sweep_qty(
+    symbol: &str,
+    size: Decimal,
+    prices: &HashMap<String, SymbolSnapshot>,
+) -> Option<Decimal> {
+    let qty = quantize_order_size_close(symbol, size, prices);
+    if qty <= Decimal::ZERO {
+        return None;
+    }
+    match prices.get(symbol).and_then(|s| s.min_order) {
+        Some(min_order) if qty < min_order => None,
+        _ => Some(qty),
+    }
+}
+
 /// Pick the (qty_a, qty_b) pair from {floor, ceil}×{floor, ceil} candidates
 /// that best preserves the requested hedge ratio qtys.0 / qtys.1. Returns the
 /// chosen sizes and the resulting absolute deviation, or `None` when every
@@ -157,6 +188,14 @@ mod tests {
         Decimal::from_str(value).unwrap()
     }
 
+    #[test]
+    fn cost_adjusted_fill_moves_against_the_order() {
+        use dex_connector::OrderSide;
+        assert_eq!(cost_adjusted_fill(dec("100"), OrderSide::Long, 10.0), dec("100.1"));
+        assert_eq!(cost_adjusted_fill(dec("100"), OrderSide::Short, 10.0), dec("99.9"));
+        assert_eq!(cost_adjusted_fill(dec("100"), OrderSide::Long, 0.0), dec("100"));
+    }
+
     #[test]
     fn pick_entry_quantize_eth_011_to_floor() {
         // bot-strategy#211: 0.011 ETH at 0.01 step previously ceiled to 0.02
@@ -228,6 +267,7 @@ mod tests {
             min_tick: None,
             size_decimals,
             exchange_ts: None,
+            depth: Default::default(),
         }
     }
 
@@ -332,4 +372,22 @@ mod tests {
         // Hedge ratio deviation should be ~0 (target ratio == actual ratio).
         assert!(res.2 < 1e-9, "dev should be ~0, got {}", res.2);
     }
+
+    fn dust_snapshot(min_order: &str, size_decimals: Option<u32>) -> SymbolSnapshot {
+        SymbolSnapshot {
+            min_order: Some(dec(min_order)),
+            size_decimals,
+            ..SymbolSnapshot::test_at(dec("100"))
+        }
+    }
+
+    #[test]
+    fn dust_sweep_qty_ceils_or_accepts() {
+        // Step 0.001 == min_order: 0.0004 ceils to 0.001, which is closable.
+        let prices = HashMap::from([("AAA".to_string(), dust_snapshot("0.001", Some(3)))]);
+        assert_eq!(dust_sweep_qty("AAA", dec("0.0004"), &prices), Some(dec("0.001")));
+        // Step finer than min_order: 0.0004 ceils to 0.0004 < 0.01 → accept.
+        let prices = HashMap::from([("AAA".to_string(), dust_snapshot("0.01", Some(4)))]);
+        assert_eq!(dust_sweep_qty("AAA", dec("0.0004"), &prices), None);
+    }
 }
@@ -0,0 +1,146 @@
+//! Rate-limit-aware loop interval. The live loop feeds the process-wide
+//! rate-limit hit count in every tick; new hits double the effective
+//! interval up to `max_interval_secs`, and each clean
+//! `RESTORE_CLEAN_SECS` stretch halves it back towards the configured
+//! interval. `next_tick_after_overrun` schedules the step after one that
+//! ran past its tick.
+
+use std::time::{Duration, Instant};
+
+use super::config::MissedTickBehavior;
+
+/// Quiet period after the last rate-limit hit before the interval is
+/// stepped back down.
+const RESTORE_CLEAN_SECS: u64 = 300;
+
+#[derive(Debug)]
+pub(super) struct AdaptiveInterval {
+    base_secs: u64,
+    max_secs: u64,
+    current_secs: u64,
+    last_hits: u64,
+    clean_since: Instant,
+}
+
+impl AdaptiveInterval {
+    /// `interval_secs` clamped to `[min_secs, max_secs]`; `max_secs == 0`
+    /// disables backing off.
+    pub(super) fn new(
+        interval_secs: u64,
+        min_secs: u64,
+        max_secs: u64,
+        hits: u64,
+        now: Instant,
+    ) -> Self {
+        let mut base_secs = interval_secs.max(min_secs).max(1);
+        if max_secs > 0 {
+            base_secs = base_secs.min(max_secs);
+        }
+        Self {
+            base_secs,
+            max_secs: max_secs.max(base_secs),
+            current_secs: base_secs,
+            last_hits: hits,
+            clean_since: now,
+        }
+    }
+
+    pub(super) fn current_secs(&self) -> u64 {
+        self.current_secs
+    }
+
+    /// Fold in the latest hit count and return the interval for the next
+    /// tick.
+    pub(super) fn observe(&mut self, hits: u64, now: Instant) -> u64 {
+        if hits > self.last_hits {
+            self.last_hits = hits;
+            self.clean_since = now;
+            let next = self.current_secs.saturating_mul(2).min(self.max_secs);
+            if next != self.current_secs {
+                log::warn!(
+                    "[RateLimit] backing off: interval {}s -> {}s",
+                    self.current_secs,
+                    next
+                );
+                self.current_secs = next;
+            }
+        } else if self.current_secs > self.base_secs
+            && now.duration_since(self.clean_since) >= Duration::from_secs(RESTORE_CLEAN_SECS)
+        {
+            let next = (self.current_secs / 2).max(self.base_secs);
+            log::info!(
+                "[RateLimit] {}s without rate limits: interval {}s -> {}s",
+                RESTORE_CLEAN_SECS,
+                self.current_secs,
+                next
+            );
+            self.current_secs = next;
+            self.clean_since = now;
+        }
+        self.current_secs
+    }
+}
+
+/// Deadline of the next step when the one that just finished at `now` ran
+/// past `missed`, the tick scheduled before it started. `next_boundary`
+/// yields the first wall-clock boundary after `now`.
+pub(super) fn next_tick_after_overrun(
+    behavior: MissedTickBehavior,
+    missed: tokio::time::Instant,
+    now: tokio::time::Instant,
+    interval: Duration,
+    next_boundary: impl FnOnce() -> tokio::time::Instant,
+) -> tokio::time::Instant {
+    match behavior {
+        MissedTickBehavior::Skip => next_boundary(),
+        MissedTickBehavior::Delay => now + interval,
+        MissedTickBehavior::Burst => missed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_on_hits_and_restores_after_clean_period() {
+        let t0 = Instant::now();
+        let mut pacing = AdaptiveInterval::new(10, 5, 60, 3, t0);
+        assert_eq!(pacing.observe(3, t0), 10);
+        assert_eq!(pacing.observe(4, t0), 20);
+        assert_eq!(pacing.observe(9, t0), 40);
+        assert_eq!(pacing.observe(10, t0), 60);
+        assert_eq!(pacing.observe(11, t0), 60);
+
+        let later = t0 + Duration::from_secs(RESTORE_CLEAN_SECS);
+        assert_eq!(pacing.observe(11, later), 30);
+        assert_eq!(pacing.observe(11, later + Duration::from_secs(1)), 30);
+        let much_later = later + Duration::from_secs(3 * RESTORE_CLEAN_SECS);
+        assert_eq!(pacing.observe(11, much_later), 15);
+        assert_eq!(
+            pacing.observe(11, much_later + Duration::from_secs(RESTORE_CLEAN_SECS)),
+            10
+        );
+    }
+
+    #[test]
+    fn disabled_without_max_interval() {
+        let t0 = Instant::now();
+        let mut pacing = AdaptiveInterval::new(10, 0, 0, 0, t0);
+        assert_eq!(pacing.observe(5, t0), 10);
+        // The floor still lifts a too-short interval.
+        assert_eq!(AdaptiveInterval::new(2, 5, 0, 0, t0).current_secs(), 5);
+    }
+
+    #[test]
+    fn overrun_schedules_per_missed_tick_behavior() {
+        let missed = tokio::time::Instant::now();
+        let now = missed + Duration::from_secs(7);
+        let boundary = missed + Duration::from_secs(10);
+        let interval = Duration::from_secs(10);
+        let next = |behavior| next_tick_after_overrun(behavior, missed, now, interval, || boundary);
+        assert_eq!(next(MissedTickBehavior::Skip), boundary);
+        assert_eq!(next(MissedTickBehavior::Delay), now + interval);
+        assert_eq!(next(MissedTickBehavior::Burst), missed);
+    }
+}
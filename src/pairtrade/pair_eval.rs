@@ -2,9 +2,11 @@
 
 use std::collections::{HashMap, VecDeque};
 
+use serde::Serialize;
+
 use super::config::{PairSpec, PairTradeConfig, WarmStartMode};
-use super::stats::{regression_beta, tail_samples, PriceSample};
-use super::util::half_life_and_p;
+use super::stats::{hedge_beta, standardize, tail_samples, PriceSample};
+use super::util::{first_non_finite, half_life_and_p};
 
 /// Weight on the short-window beta when blending into `beta_eff`. The
 /// `BETA_EFF_LONG_WEIGHT` companion weights the long-window beta. Kept as
@@ -20,7 +22,7 @@ const SCORE_HALF_LIFE_WEIGHT: f64 = 0.4;
 /// Eligibility threshold on `beta_gap` (relative beta divergence).
 const ELIGIBILITY_BETA_GAP_MAX: f64 = 0.2;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub(super) struct PairEvaluation {
     pub(super) beta_short: f64,
     pub(super) beta_long: f64,
@@ -32,6 +34,71 @@ pub(super) struct PairEvaluation {
     pub(super) beta_gap: f64,
 }
 
+impl PairEvaluation {
+    /// First NaN/inf statistic, if any. An infinite half-life is the "no
+    /// mean reversion" result and passes; only a NaN one is rejected.
+    pub(super) fn non_finite_stat(&self) -> Option<(&'static str, f64)> {
+        first_non_finite(&[
+            ("beta_short", self.beta_short),
+            ("beta_long", self.beta_long),
+            ("beta_eff", self.beta_eff),
+            ("adf_p_value", self.adf_p_value),
+            ("score", self.score),
+            ("beta_gap", self.beta_gap),
+        ])
+        .or_else(|| {
+            self.half_life_hours
+                .is_nan()
+                .then_some(("half_life_hours", self.half_life_hours))
+        })
+    }
+}
+
+/// Bars each leg needs before `evaluate_pair` will run: the full long
+/// lookback in strict mode, `warm_start_min_bars` in relaxed mode.
+pub(super) fn warmup_bars_needed(cfg: &PairTradeConfig, key: &str) -> usize {
+    let pp = cfg.params_for(key);
+    match cfg.warm_start_mode {
+        WarmStartMode::Strict => {
+            ((pp.lookback_hours_long * 3600) / cfg.trading_period_secs).max(1) as usize
+        }
+        WarmStartMode::Relaxed => pp.warm_start_min_bars.max(1),
+    }
+}
+
+/// Whether `key` is warm enough to open a position: its spread history
+/// holds `min_spread_history_points` (default half the metrics window, at
+/// least 10) and both legs hold `min_symbol_history_bars` bars (default
+/// `warmup_bars_needed`). `Err` names the first unmet condition. Exits of
+/// an open position do not wait on it.
+pub(super) fn pair_warm_check(
+    cfg: &PairTradeConfig,
+    key: &str,
+    spread_len: usize,
+    bars_a: usize,
+    bars_b: usize,
+) -> Result<(), String> {
+    let min_spread = match cfg.min_spread_history_points {
+        0 => (cfg.metrics_window / 2).max(10),
+        n => n,
+    };
+    if spread_len < min_spread {
+        return Err(format!("spread history {} < {}", spread_len, min_spread));
+    }
+    let min_bars = match cfg.min_symbol_history_bars {
+        0 => warmup_bars_needed(cfg, key),
+        n => n,
+    };
+    let (base, quote) = key.split_once('/').unwrap_or((key, key));
+    if bars_a < min_bars {
+        return Err(format!("{} bars {} < {}", base, bars_a, min_bars));
+    }
+    if bars_b < min_bars {
+        return Err(format!("{} bars {} < {}", quote, bars_b, min_bars));
+    }
+    Ok(())
+}
+
 pub(super) fn evaluate_pair(
     cfg: &PairTradeConfig,
     history: &HashMap<String, VecDeque<PriceSample>>,
@@ -46,30 +113,43 @@ pub(super) fn evaluate_pair(
         ((pp.lookback_hours_long * 3600) / cfg.trading_period_secs).max(1) as usize;
     let desired_short =
         ((pp.lookback_hours_short * 3600) / cfg.trading_period_secs).max(1) as usize;
+    if available < warmup_bars_needed(cfg, &key) {
+        return None;
+    }
     let (long_len, short_len) = match cfg.warm_start_mode {
-        WarmStartMode::Strict => {
-            if available < desired_long {
-                return None;
-            }
-            (desired_long, desired_short)
-        }
+        WarmStartMode::Strict => (desired_long, desired_short),
         WarmStartMode::Relaxed => {
-            let min_bars = pp.warm_start_min_bars.max(1);
-            if available < min_bars {
-                return None;
-            }
             let long_len = desired_long.min(available);
             let short_len = desired_short.min(long_len);
             (long_len, short_len)
         }
     };
+    // Standardized legs: fit on the `metrics_window` the live spread
+    // standardizes over, so beta relates the same units it is applied to.
+    let (long_len, short_len) = if cfg.normalize_legs {
+        let long_len = cfg.metrics_window.min(available).max(1);
+        (long_len, short_len.min(long_len))
+    } else {
+        (long_len, short_len)
+    };
 
-    let tail_a = tail_samples(hist_a, long_len);
-    let tail_b = tail_samples(hist_b, long_len);
-    let beta_long = regression_beta(&tail_b, &tail_a);
-    let beta_short = regression_beta(
+    let mut tail_a = tail_samples(hist_a, long_len);
+    let mut tail_b = tail_samples(hist_b, long_len);
+    if cfg.normalize_legs {
+        tail_a = standardize(&tail_a);
+        tail_b = standardize(&tail_b);
+    }
+    let beta_long = hedge_beta(
+        &tail_b,
+        &tail_a,
+        cfg.beta_on_returns,
+        cfg.allow_negative_beta,
+    );
+    let beta_short = hedge_beta(
         &tail_b[tail_b.len() - short_len..],
         &tail_a[tail_a.len() - short_len..],
+        cfg.beta_on_returns,
+        cfg.allow_negative_beta,
     );
     let beta_eff = BETA_EFF_SHORT_WEIGHT * beta_short + BETA_EFF_LONG_WEIGHT * beta_long;
 
@@ -81,7 +161,7 @@ pub(super) fn evaluate_pair(
         .collect();
     let (half_life_samples, adf_p_value) = half_life_and_p(&spreads);
     let half_life_hours = half_life_samples * (cfg.trading_period_secs as f64) / 3600.0;
-    let beta_gap = ((beta_short - beta_long) / beta_eff.max(1e-6)).abs();
+    let beta_gap = ((beta_short - beta_long) / beta_eff.abs().max(1e-6)).abs();
     let half_ok = half_life_hours <= pp.half_life_max_hours;
     let adf_ok = adf_p_value <= pp.adf_p_threshold;
     let beta_ok = beta_gap <= ELIGIBILITY_BETA_GAP_MAX;
@@ -102,3 +182,57 @@ pub(super) fn evaluate_pair(
         beta_gap,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warm_check_names_the_unmet_condition() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        cfg.metrics_window = 40;
+        cfg.min_symbol_history_bars = 100;
+        assert_eq!(
+            pair_warm_check(&cfg, "BTC/ETH", 19, 500, 500),
+            Err("spread history 19 < 20".to_string())
+        );
+        assert_eq!(
+            pair_warm_check(&cfg, "BTC/ETH", 20, 500, 99),
+            Err("ETH bars 99 < 100".to_string())
+        );
+        assert_eq!(pair_warm_check(&cfg, "BTC/ETH", 20, 100, 100), Ok(()));
+        cfg.min_spread_history_points = 50;
+        assert!(pair_warm_check(&cfg, "BTC/ETH", 20, 100, 100).is_err());
+    }
+
+    #[test]
+    fn degenerate_history_flags_non_finite_stats() {
+        let mut cfg = PairTradeConfig::from_env().unwrap();
+        cfg.warm_start_mode = WarmStartMode::Relaxed;
+        let pair = PairSpec {
+            base: "AAA".to_string(),
+            quote: "BBB".to_string(),
+        };
+        let len = warmup_bars_needed(&cfg, "AAA/BBB") as i64 + 10;
+        let bars = |f: &dyn Fn(i64) -> f64| -> VecDeque<PriceSample> {
+            (0..len)
+                .map(|i| PriceSample {
+                    log_price: f(i),
+                    ts: i * 60,
+                    close: None,
+                })
+                .collect()
+        };
+        let mut history = HashMap::new();
+        history.insert("AAA".to_string(), bars(&|i| (100.0 + (i % 7) as f64).ln()));
+        history.insert("BBB".to_string(), bars(&|i| (50.0 + (i % 5) as f64).ln()));
+        let eval = evaluate_pair(&cfg, &history, &pair).unwrap();
+        assert_eq!(eval.non_finite_stat(), None);
+
+        // One NaN close poisons the regression.
+        let last = history["BBB"].len() - 1;
+        history.get_mut("BBB").unwrap()[last].log_price = f64::NAN;
+        let eval = evaluate_pair(&cfg, &history, &pair).unwrap();
+        assert!(eval.non_finite_stat().is_some());
+    }
+}
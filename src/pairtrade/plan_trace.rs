@@ -0,0 +1,96 @@
+//! Append-only jsonl trace of every pair's planned action per tick, written
+//! when `plan_trace_file` is set. One record per instance per tick with the
+//! action each pair computed (open/close/none) and its inputs, plus the
+//! entry selection picked and the exits queued. Heavy; meant for replaying
+//! a backtest decision by decision.
+
+use std::io;
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::jsonl;
+
+#[derive(Debug, Serialize)]
+pub(super) struct PlannedActionTrace<'a> {
+    pub(super) pair: &'a str,
+    /// `open`, `close` or `none`.
+    pub(super) action: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) direction: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) z: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) beta: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) force: Option<bool>,
+    pub(super) net_funding_per_hour: f64,
+    pub(super) liquidity_score: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub(super) struct PlanTrace<'a> {
+    /// Replay-aware tick time (unix seconds).
+    pub(super) ts: i64,
+    pub(super) strategy: &'a str,
+    pub(super) actions: Vec<PlannedActionTrace<'a>>,
+    pub(super) selected_entry: Option<&'a str>,
+    pub(super) exits: Vec<&'a str>,
+}
+
+/// Append one tick's trace to `path`.
+pub(super) fn append(path: &Path, trace: &PlanTrace<'_>) -> io::Result<()> {
+    jsonl::append(path, std::slice::from_ref(trace))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn appends_one_record_per_tick() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plan_trace.jsonl");
+        let trace = |ts| PlanTrace {
+            ts,
+            strategy: "default",
+            actions: vec![
+                PlannedActionTrace {
+                    pair: "BTC/ETH",
+                    action: "open",
+                    direction: Some("short_spread"),
+                    z: Some(2.3),
+                    beta: Some(0.9),
+                    force: None,
+                    net_funding_per_hour: 0.0,
+                    liquidity_score: 4.0,
+                },
+                PlannedActionTrace {
+                    pair: "SOL/ETH",
+                    action: "none",
+                    direction: None,
+                    z: None,
+                    beta: None,
+                    force: None,
+                    net_funding_per_hour: 0.0,
+                    liquidity_score: 2.0,
+                },
+            ],
+            selected_entry: Some("BTC/ETH"),
+            exits: Vec::new(),
+        };
+        append(&path, &trace(60)).unwrap();
+        append(&path, &trace(120)).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["ts"], 120);
+        assert_eq!(lines[0]["selected_entry"], "BTC/ETH");
+        assert_eq!(lines[0]["actions"][0]["z"], 2.3);
+        assert!(lines[0]["actions"][1].get("z").is_none());
+    }
+}
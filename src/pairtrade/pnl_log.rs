@@ -3,8 +3,6 @@
 
 use std::env;
 use std::fs;
-use std::fs::OpenOptions;
-use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::time::{Instant, SystemTime};
 
@@ -13,6 +11,7 @@ use serde::{Deserialize, Serialize};
 use tokio::time::Duration;
 
 use super::config::PairTradeConfig;
+use super::jsonl;
 use super::state::PositionDirection;
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -24,6 +23,11 @@ pub(super) struct PnlLogRecord {
     pub(super) direction: String,
     pub(super) pnl: f64,
     pub(super) source: String,
+    /// Strategic reason behind the close (`exit_z`, `stop_loss_z`,
+    /// `force_close`, ...). "unknown" for closes that bypass the exit
+    /// decision and for records written before the field existed.
+    #[serde(default = "unknown_exit_reason")]
+    pub(super) exit_reason: String,
     // Trade log fields for backtest calibration
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(super) entry_price_a: Option<f64>,
@@ -43,6 +47,10 @@ pub(super) struct PnlLogRecord {
     pub(super) hold_secs: Option<f64>,
 }
 
+fn unknown_exit_reason() -> String {
+    "unknown".to_string()
+}
+
 pub(super) struct PnlLogger {
     dir: PathBuf,
     tag: Option<String>,
@@ -124,11 +132,7 @@ impl PnlLogger {
 
     pub(super) fn log(&mut self, record: PnlLogRecord) -> std::io::Result<()> {
         fs::create_dir_all(&self.dir)?;
-        let path = self.log_path();
-        let line = serde_json::to_string(&record)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
-        writeln!(file, "{line}")?;
+        jsonl::append(&self.log_path(), &[record])?;
         self.maybe_cleanup();
         Ok(())
     }
@@ -222,6 +226,7 @@ impl PnlLogRecord {
             direction: direction_label(direction).to_string(),
             pnl,
             source: source.to_string(),
+            exit_reason: unknown_exit_reason(),
             entry_price_a: None,
             entry_price_b: None,
             exit_price_a: None,
@@ -254,4 +259,68 @@ impl PnlLogRecord {
         self.hold_secs = hold_secs;
         self
     }
+
+    pub(super) fn with_exit_reason(mut self, reason: Option<&str>) -> Self {
+        self.exit_reason = reason.map_or_else(unknown_exit_reason, str::to_string);
+        self
+    }
+
+    /// Round prices and PnL for output (`status_price_dp` / `status_pnl_dp`).
+    /// Applied to the logged copy only; stats keep full precision.
+    pub(super) fn rounded(mut self, price_dp: Option<u32>, pnl_dp: Option<u32>) -> Self {
+        self.pnl = round_dp(self.pnl, pnl_dp);
+        for price in [
+            &mut self.entry_price_a,
+            &mut self.entry_price_b,
+            &mut self.exit_price_a,
+            &mut self.exit_price_b,
+        ] {
+            *price = price.map(|v| round_dp(v, price_dp));
+        }
+        self
+    }
+}
+
+/// Round `value` to `dp` decimal places; `None` leaves it untouched.
+pub(super) fn round_dp(value: f64, dp: Option<u32>) -> f64 {
+    match dp {
+        Some(dp) if value.is_finite() => {
+            let scale = 10f64.powi(dp.min(15) as i32);
+            (value * scale).round() / scale
+        }
+        _ => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounded_record_keeps_full_precision_by_default() {
+        let record = PnlLogRecord::new("AAA", "BBB", PositionDirection::LongSpread, 1.23456789, 0, "exit_fill")
+            .with_trade_details(Some(101.123456), None, Some(99.987654), None, None, None, None, None);
+        let same = PnlLogRecord::new("AAA", "BBB", PositionDirection::LongSpread, 1.23456789, 0, "exit_fill")
+            .rounded(None, None);
+        assert_eq!(same.pnl, 1.23456789);
+
+        let rounded = record.rounded(Some(2), Some(4));
+        assert_eq!(rounded.pnl, 1.2346);
+        assert_eq!(rounded.entry_price_a, Some(101.12));
+        assert_eq!(rounded.exit_price_a, Some(99.99));
+        assert_eq!(rounded.entry_price_b, None);
+    }
+
+    #[test]
+    fn exit_reason_defaults_to_unknown() {
+        let legacy: PnlLogRecord = serde_json::from_str(
+            r#"{"ts":0,"pair":"AAA/BBB","base":"AAA","quote":"BBB","direction":"long_spread","pnl":1.0,"source":"exit_fill"}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.exit_reason, "unknown");
+        let record = PnlLogRecord::new("AAA", "BBB", PositionDirection::LongSpread, 1.0, 0, "exit_fill")
+            .with_exit_reason(Some("stop_loss_z"));
+        assert_eq!(record.exit_reason, "stop_loss_z");
+        assert_eq!(record.with_exit_reason(None).exit_reason, "unknown");
+    }
 }
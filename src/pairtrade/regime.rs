@@ -114,6 +114,7 @@ mod tests {
             .map(|(i, &lp)| PriceSample {
                 log_price: lp,
                 ts: i as i64 * 60,
+                close: None,
             })
             .collect()
     }
@@ -0,0 +1,73 @@
+//! Append-only jsonl of entry candidates that reached the selection stage
+//! but were not placed, written when `rejected_entries_file` is set. One
+//! record per rejected candidate per step, with the reason and the inputs
+//! the selection ranked on, so gating and selection can be checked offline
+//! for good trades left on the table.
+
+use std::io;
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::jsonl;
+
+#[derive(Debug, Serialize)]
+pub(super) struct RejectedEntry<'a> {
+    pub(super) ts: i64,
+    pub(super) strategy: &'a str,
+    pub(super) pair: &'a str,
+    pub(super) reason: &'static str,
+    pub(super) direction: &'static str,
+    pub(super) z: f64,
+    pub(super) beta: f64,
+    pub(super) net_funding_per_hour: f64,
+    pub(super) liquidity_score: f64,
+    pub(super) score: f64,
+}
+
+/// Append `entries` to `path`, one line each.
+pub(super) fn append(path: &Path, entries: &[RejectedEntry<'_>]) -> io::Result<()> {
+    jsonl::append(path, entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn appends_one_line_per_rejected_candidate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rejected_entries.jsonl");
+        let entry = |pair, reason| RejectedEntry {
+            ts: 100,
+            strategy: "default",
+            pair,
+            reason,
+            direction: "short_spread",
+            z: 2.4,
+            beta: 0.9,
+            net_funding_per_hour: 0.0001,
+            liquidity_score: 3.5,
+            score: 0.7,
+        };
+        append(
+            &path,
+            &[
+                entry("BTC/ETH", "not_selected"),
+                entry("SOL/ETH", "thin_book"),
+            ],
+        )
+        .unwrap();
+        append(&path, &[]).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["pair"], "BTC/ETH");
+        assert_eq!(lines[1]["reason"], "thin_book");
+        assert_eq!(lines[1]["z"], 2.4);
+    }
+}
@@ -0,0 +1,136 @@
+//! Classification of exchange-side order rejections. `create_order` errors
+//! arrive as free-form `DexError` messages; the variants below are the ones
+//! the engine can act on instead of blindly retrying.
+
+use dex_connector::DexError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum RejectionKind {
+    /// Order size below the venue's minimum. Not retried: resizing one leg
+    /// alone would break the hedge, so the entry is skipped instead.
+    SizeTooSmall,
+    /// Limit price outside the venue's accepted band (stale quote). Refresh
+    /// the snapshot, re-quantize, and retry once.
+    PriceOutOfBounds,
+    /// Not enough free collateral. Retrying cannot help; new entries are
+    /// blocked for a cool-down.
+    InsufficientMargin,
+}
+
+impl RejectionKind {
+    pub(super) fn as_str(&self) -> &'static str {
+        match self {
+            RejectionKind::SizeTooSmall => "size_too_small",
+            RejectionKind::PriceOutOfBounds => "price_out_of_bounds",
+            RejectionKind::InsufficientMargin => "insufficient_margin",
+        }
+    }
+}
+
+/// Map a `create_order` error onto an actionable `RejectionKind`. Returns
+/// `None` for anything unrecognised, which keeps the existing retry path.
+pub(super) fn classify_rejection(err: &DexError) -> Option<RejectionKind> {
+    let msg = match err {
+        DexError::ServerResponse(message) | DexError::Other(message) => message,
+        _ => return None,
+    };
+    let lower = msg.to_ascii_lowercase();
+    if lower.contains("insufficient margin")
+        || lower.contains("insufficient balance")
+        || lower.contains("insufficient collateral")
+        || lower.contains("not enough margin")
+    {
+        return Some(RejectionKind::InsufficientMargin);
+    }
+    if lower.contains("size too small")
+        || lower.contains("below minimum")
+        || lower.contains("min order size")
+        || lower.contains("minimum order size")
+        || lower.contains("order size too low")
+    {
+        return Some(RejectionKind::SizeTooSmall);
+    }
+    if lower.contains("price out of bounds")
+        || lower.contains("price out of range")
+        || lower.contains("price too far")
+        || lower.contains("invalid price")
+    {
+        return Some(RejectionKind::PriceOutOfBounds);
+    }
+    None
+}
+
+/// A ticker/market lookup the venue answered with "no such symbol", as
+/// opposed to a transient failure (rate limit, timeout, lost connection)
+/// that may succeed on retry.
+pub(super) fn is_unknown_symbol_error(err: &DexError) -> bool {
+    let msg = match err {
+        DexError::ServerResponse(message) | DexError::Other(message) => message,
+        _ => return false,
+    };
+    let lower = msg.to_ascii_lowercase();
+    if lower.contains("429") || lower.contains("too many requests") {
+        return false;
+    }
+    lower.contains("unknown symbol")
+        || lower.contains("unknown market")
+        || lower.contains("invalid symbol")
+        || lower.contains("symbol not found")
+        || lower.contains("market not found")
+        || (lower.contains("symbol") && lower.contains("not found"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_common_messages() {
+        let cases = [
+            ("Order size too small", Some(RejectionKind::SizeTooSmall)),
+            (
+                "amount below minimum 0.001",
+                Some(RejectionKind::SizeTooSmall),
+            ),
+            ("Price out of bounds", Some(RejectionKind::PriceOutOfBounds)),
+            (
+                "Insufficient margin to place order",
+                Some(RejectionKind::InsufficientMargin),
+            ),
+            ("timeout", None),
+        ];
+        for (msg, want) in cases {
+            assert_eq!(
+                classify_rejection(&DexError::ServerResponse(msg.to_string())),
+                want,
+                "{}",
+                msg
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_symbol_is_told_apart_from_transient_errors() {
+        let unknown = DexError::Other("Symbol 'XYZ' not found".to_string());
+        assert!(is_unknown_symbol_error(&unknown));
+        assert!(is_unknown_symbol_error(&DexError::ServerResponse(
+            "unknown market".to_string()
+        )));
+        for transient in [
+            DexError::ServerResponse("429 Too Many Requests".to_string()),
+            DexError::Other("request timed out".to_string()),
+            DexError::RateLimited { until_unix: 0 },
+            DexError::NoConnection,
+        ] {
+            assert!(!is_unknown_symbol_error(&transient), "{:?}", transient);
+        }
+    }
+
+    #[test]
+    fn other_variant_is_parsed_too() {
+        assert_eq!(
+            classify_rejection(&DexError::Other("not enough margin".to_string())),
+            Some(RejectionKind::InsufficientMargin)
+        );
+    }
+}
@@ -59,6 +59,12 @@ pub(super) struct InstanceRiskState {
     /// `session_halted`.
     #[serde(default)]
     pub session_halt_ts: Option<i64>,
+    /// UNIX-seconds entry time of each pair with an open position or a
+    /// placed entry, keyed `BASE/QUOTE`. Written when an entry is placed,
+    /// so a pair the exchange still holds after a crash can get its real
+    /// entry time back (`recover_entry_time_from_risk_state`).
+    #[serde(default)]
+    pub entry_ts_by_pair: HashMap<String, i64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
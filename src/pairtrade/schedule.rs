@@ -0,0 +1,171 @@
+//! UTC no-trade windows (`no_trade_windows`). Each window is a string of the
+//! form `[DAYS] [HH:MM-HH:MM]`, e.g. `sat-sun`, `22:00-02:00` or
+//! `mon,fri 13:25-13:45`. DAYS is a comma list of weekday names or ranges
+//! (`fri-mon` wraps); omitted means every day. An omitted time range means
+//! the whole day. A range past midnight belongs to the day it starts on.
+
+use std::fmt;
+
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+const ALL_DAYS: u8 = 0b111_1111;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoTradeWindow {
+    /// Bit `i` set = weekday `i` (Monday = 0).
+    days: u8,
+    start_min: u32,
+    end_min: u32,
+    spec: String,
+}
+
+impl NoTradeWindow {
+    /// True when the UTC timestamp falls inside this window.
+    pub(super) fn contains(&self, ts_secs: i64) -> bool {
+        let day = ts_secs.div_euclid(86_400);
+        let minute = (ts_secs.rem_euclid(86_400) / 60) as u32;
+        // 1970-01-01 was a Thursday.
+        let weekday = (day + 3).rem_euclid(7) as u8;
+        let prev_weekday = (weekday + 6) % 7;
+        let on = |wd: u8| self.days & (1 << wd) != 0;
+        if self.start_min == self.end_min {
+            on(weekday)
+        } else if self.start_min < self.end_min {
+            on(weekday) && minute >= self.start_min && minute < self.end_min
+        } else {
+            (on(weekday) && minute >= self.start_min) || (on(prev_weekday) && minute < self.end_min)
+        }
+    }
+}
+
+impl fmt::Display for NoTradeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.spec)
+    }
+}
+
+impl std::str::FromStr for NoTradeWindow {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let spec = s.trim().to_lowercase();
+        let mut days = None;
+        let mut range = None;
+        for part in spec.split_whitespace() {
+            if part.contains(':') {
+                if range.replace(parse_range(part)?).is_some() {
+                    return Err(format!(
+                        "no_trade_windows '{}': more than one time range",
+                        s
+                    ));
+                }
+            } else if days.replace(parse_days(part)?).is_some() {
+                return Err(format!("no_trade_windows '{}': more than one day list", s));
+            }
+        }
+        if days.is_none() && range.is_none() {
+            return Err("no_trade_windows: empty window".to_string());
+        }
+        let (start_min, end_min) = range.unwrap_or((0, 0));
+        Ok(NoTradeWindow {
+            days: days.unwrap_or(ALL_DAYS),
+            start_min,
+            end_min,
+            spec,
+        })
+    }
+}
+
+/// Parse a list of window specs, failing on the first invalid one.
+pub(super) fn parse_windows<S: AsRef<str>>(specs: &[S]) -> Result<Vec<NoTradeWindow>, String> {
+    specs
+        .iter()
+        .map(|s| s.as_ref())
+        .filter(|s| !s.trim().is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// First window containing `ts_secs`, if any.
+pub(super) fn active_window(windows: &[NoTradeWindow], ts_secs: i64) -> Option<&NoTradeWindow> {
+    windows.iter().find(|w| w.contains(ts_secs))
+}
+
+fn parse_weekday(name: &str) -> Result<u8, String> {
+    WEEKDAYS
+        .iter()
+        .position(|d| name.starts_with(d))
+        .map(|i| i as u8)
+        .ok_or_else(|| format!("no_trade_windows: unknown weekday '{}'", name))
+}
+
+fn parse_days(part: &str) -> Result<u8, String> {
+    let mut mask = 0u8;
+    for item in part.split(',').filter(|i| !i.is_empty()) {
+        match item.split_once('-') {
+            Some((from, to)) => {
+                let (from, to) = (parse_weekday(from)?, parse_weekday(to)?);
+                let mut d = from;
+                loop {
+                    mask |= 1 << d;
+                    if d == to {
+                        break;
+                    }
+                    d = (d + 1) % 7;
+                }
+            }
+            None => mask |= 1 << parse_weekday(item)?,
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_minute(hhmm: &str) -> Result<u32, String> {
+    let err = || format!("no_trade_windows: invalid time '{}'", hhmm);
+    let (h, m) = hhmm.split_once(':').ok_or_else(err)?;
+    let (h, m): (u32, u32) = (h.parse().map_err(|_| err())?, m.parse().map_err(|_| err())?);
+    if h > 24 || m > 59 || (h == 24 && m != 0) {
+        return Err(err());
+    }
+    Ok((h * 60 + m) % (24 * 60))
+}
+
+fn parse_range(part: &str) -> Result<(u32, u32), String> {
+    let (start, end) = part
+        .split_once('-')
+        .ok_or_else(|| format!("no_trade_windows: invalid time range '{}'", part))?;
+    Ok((parse_minute(start)?, parse_minute(end)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2025-04-25 00:00 UTC, a Friday.
+    const FRI: i64 = 1_745_539_200;
+    const H: i64 = 3600;
+
+    #[test]
+    fn weekend_and_overnight_windows() {
+        let windows = parse_windows(&["sat-sun", "mon-fri 22:00-02:00"]).unwrap();
+        let active = |ts| active_window(&windows, ts).map(|w| w.to_string());
+
+        assert_eq!(active(FRI + 12 * H), None);
+        assert_eq!(active(FRI + 23 * H).as_deref(), Some("mon-fri 22:00-02:00"));
+        // Sat 01:00 is the tail of Friday's overnight window (and the weekend).
+        assert!(active(FRI + 25 * H).is_some());
+        assert_eq!(active(FRI + 36 * H).as_deref(), Some("sat-sun"));
+        // Mon 01:00: Sunday does not start an overnight window.
+        assert_eq!(active(FRI + 3 * 24 * H + H), None);
+        assert_eq!(
+            active(FRI + 3 * 24 * H + 22 * H).as_deref(),
+            Some("mon-fri 22:00-02:00")
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_specs() {
+        assert!(parse_windows(&["funday"]).is_err());
+        assert!(parse_windows(&["25:00-26:00"]).is_err());
+        assert!(parse_windows(&["mon 10:00"]).is_err());
+        assert_eq!(parse_windows(&["", "  "]).unwrap(), vec![]);
+    }
+}
@@ -1,11 +1,15 @@
 //! Position-sizing helpers extracted from the monolithic pairtrade module.
 
+use std::collections::HashMap;
+
 use anyhow::{anyhow, Result};
-use rust_decimal::prelude::FromPrimitive;
-use rust_decimal::Decimal;
+use dex_connector::OrderSide;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::{Decimal, RoundingStrategy};
 
-use super::config::PairTradeConfig;
-use super::market::SymbolSnapshot;
+use super::config::{MatchingModel, PairTradeConfig};
+use super::market::{simulated_fill_qty, SymbolSnapshot};
+use super::state::PositionDirection;
 
 /// Apply the `risk.max_notional_usd_per_leg` hard cap to the leg-A target
 /// notional. The intended hedge is `(leg_notional, leg_notional · |beta|)`,
@@ -26,19 +30,131 @@ pub(super) fn cap_leg_notional(leg_notional: f64, beta: f64, cap: f64) -> Option
     }
 }
 
+/// Bound a fixed per-pair `notional_per_leg_usd` by leverage: the gross of
+/// both legs, `leg_notional · (1 + |beta|)`, stays within `equity ·
+/// max_leverage`. Returns `None` when unbounded (no equity or leverage) or
+/// already within it.
+pub(super) fn leverage_cap_leg_notional(
+    leg_notional: f64,
+    beta: f64,
+    equity: f64,
+    max_leverage: f64,
+) -> Option<f64> {
+    let max_gross = equity * max_leverage;
+    if max_gross <= 0.0 {
+        return None;
+    }
+    let allowed = max_gross / (1.0 + beta.abs());
+    (leg_notional > allowed).then_some(allowed)
+}
+
+/// Upper bound on the vol-targeting multiplier when no
+/// `vol_target_max_notional` is set, so a near-zero spread std cannot blow
+/// the notional up.
+const VOL_TARGET_MAX_SCALE: f64 = 4.0;
+
+/// Scale `leg_notional` inversely to the spread's recent std so every
+/// position targets a similar dollar-vol: at `spread_std == vol_target_sigma`
+/// sizing is unchanged, a pair twice as volatile gets half the notional.
+/// The result is clamped to `[min_notional, max_notional]`; `max_notional`
+/// ≤ 0 caps it at `VOL_TARGET_MAX_SCALE` × `leg_notional` instead. Returns
+/// `leg_notional` unchanged when targeting is disabled or the std is
+/// unavailable / degenerate.
+pub(super) fn vol_target_notional(
+    leg_notional: f64,
+    spread_std: Option<f64>,
+    vol_target_sigma: f64,
+    min_notional: f64,
+    max_notional: f64,
+) -> f64 {
+    if vol_target_sigma <= 0.0 {
+        return leg_notional;
+    }
+    let Some(std) = spread_std.filter(|s| s.is_finite() && *s > 0.0) else {
+        return leg_notional;
+    };
+    let cap = if max_notional > 0.0 {
+        max_notional
+    } else {
+        leg_notional * VOL_TARGET_MAX_SCALE
+    };
+    (leg_notional * (vol_target_sigma / std)).min(cap).max(min_notional)
+}
+
+/// Price used to turn a leg notional into a quantity. When the book depth
+/// was fetched this is the estimated fill for `notional` on the side the leg
+/// trades (asks for a buy, bids for a sell), so the qty reflects what the
+/// book actually pays; otherwise the snapshot price, as top-of-book sizing.
+fn sizing_price(p: &SymbolSnapshot, side: OrderSide, notional: Decimal) -> Decimal {
+    p.depth
+        .fill_price(side == OrderSide::Long, notional)
+        .filter(|fill| *fill > Decimal::ZERO)
+        .unwrap_or(p.price)
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(super) fn hedged_sizes(
     cfg: &PairTradeConfig,
     equity: f64,
+    direction: PositionDirection,
     beta: f64,
+    spread_std: Option<f64>,
+    notional_override: Option<f64>,
     p1: &SymbolSnapshot,
     p2: &SymbolSnapshot,
 ) -> Result<(Decimal, Decimal)> {
     // `equity` is the per-instance fixed `equity_reference_usd` so each
     // variant sizes against its own declared capital. Live equity is no
     // longer mixed in here — see StrategyInstance.equity_reference_usd
-    // and bot-strategy#222.
+    // and bot-strategy#222. A pair's `notional_per_leg_usd` replaces it.
     let total_risk = equity * cfg.risk_pct_per_trade * cfg.max_leverage;
-    let mut leg_notional = (total_risk / 2.0).max(10.0);
+    let mut leg_notional = match notional_override {
+        Some(notional) if notional > 0.0 => {
+            match leverage_cap_leg_notional(notional, beta, equity, cfg.max_leverage) {
+                Some(capped) => {
+                    log::warn!(
+                        "[SIZING] fixed leg_notional {:.2} → {:.2} (equity={:.2} max_leverage={:.2} |beta|={:.4})",
+                        notional,
+                        capped,
+                        equity,
+                        cfg.max_leverage,
+                        beta.abs()
+                    );
+                    capped
+                }
+                None => notional,
+            }
+        }
+        _ => (total_risk / 2.0).max(10.0),
+    };
+    if cfg.vol_target_sigma > 0.0 {
+        let scaled = vol_target_notional(
+            leg_notional,
+            spread_std,
+            cfg.vol_target_sigma,
+            cfg.vol_target_min_notional,
+            cfg.vol_target_max_notional,
+        );
+        log::debug!(
+            "[VOL_TARGET] leg_notional {:.2} → {:.2} (spread_std={:?}, target={:.6})",
+            leg_notional,
+            scaled,
+            spread_std,
+            cfg.vol_target_sigma
+        );
+        leg_notional = scaled;
+        if let Some(capped) = leverage_cap_leg_notional(leg_notional, beta, equity, cfg.max_leverage) {
+            log::warn!(
+                "[VOL_TARGET] leg_notional {:.2} → {:.2} (equity={:.2} max_leverage={:.2} |beta|={:.4})",
+                leg_notional,
+                capped,
+                equity,
+                cfg.max_leverage,
+                beta.abs()
+            );
+            leg_notional = capped;
+        }
+    }
     if let Some(capped) = cap_leg_notional(leg_notional, beta, cfg.risk.max_notional_usd_per_leg) {
         log::warn!(
             "[RISK_NOTIONAL_CAP] leg_notional {:.2} → {:.2} (cap={:.2}, |beta|={:.4})",
@@ -49,12 +165,33 @@ pub(super) fn hedged_sizes(
         );
         leg_notional = capped;
     }
+    let factor = cfg.max_min_order_oversizing_factor;
+    if factor > 0.0 {
+        for (p, intended) in [(p1, leg_notional), (p2, leg_notional * beta.abs())] {
+            let Some(ratio) = min_order_oversizing(p, intended) else {
+                continue;
+            };
+            if ratio > factor {
+                log::warn!(
+                    "[SIZING] min_order notional is {:.1}x the intended leg notional {:.2} (> {:.1}x allowed, min_order={:?} price={}); sizing to zero",
+                    ratio,
+                    intended,
+                    factor,
+                    p.min_order,
+                    p.price
+                );
+                return Ok((Decimal::ZERO, Decimal::ZERO));
+            }
+        }
+    }
     let notional = Decimal::from_f64(leg_notional).ok_or_else(|| anyhow!("invalid notional"))?;
+    let (side_a, side_b) = direction.entry_sides(beta < 0.0);
 
+    let price_a = sizing_price(p1, side_a, notional);
     let qty_a = if p1.price == Decimal::ZERO {
         Decimal::ZERO
     } else {
-        let mut qty = notional / p1.price;
+        let mut qty = notional / price_a;
         if let Some(decimals) = p1.size_decimals {
             qty = qty.round_dp(decimals);
         }
@@ -66,13 +203,15 @@ pub(super) fn hedged_sizes(
         qty
     };
     // Compute qty_b from the actual notional of leg A (after min_order adjustment)
-    // so that the hedge ratio matches beta: notional_b = notional_a * beta
-    let actual_notional_a = qty_a * p1.price;
+    // so that the hedge ratio matches beta: notional_b = notional_a * beta.
+    // Leg A's notional uses the same price its qty was sized at.
+    let actual_notional_a = qty_a * price_a;
     let qty_b = if p2.price == Decimal::ZERO {
         Decimal::ZERO
     } else {
         let beta_dec = Decimal::from_f64(beta.abs()).unwrap_or(Decimal::ONE);
-        let mut qty = (actual_notional_a * beta_dec) / p2.price;
+        let notional_b = actual_notional_a * beta_dec;
+        let mut qty = notional_b / sizing_price(p2, side_b, notional_b);
         if let Some(decimals) = p2.size_decimals {
             qty = qty.round_dp(decimals);
         }
@@ -86,10 +225,206 @@ pub(super) fn hedged_sizes(
     Ok((qty_a, qty_b))
 }
 
+/// Leg-B size that re-hedges `qty_a` of leg A at `beta`
+/// (`notional_b = notional_a · |beta|`). `None` without prices.
+pub(super) fn rehedge_qty_b(
+    qty_a: Decimal,
+    price_a: Decimal,
+    price_b: Decimal,
+    beta: f64,
+) -> Option<Decimal> {
+    if price_a <= Decimal::ZERO || price_b <= Decimal::ZERO {
+        return None;
+    }
+    let beta_dec = Decimal::from_f64(beta.abs())?;
+    Some(qty_a * price_a * beta_dec / price_b)
+}
+
+/// How many times the leg's min-order notional exceeds `intended_notional`
+/// (the leg size before the min_order floor). `None` without a min_order,
+/// a price, or a positive intended notional.
+pub(super) fn min_order_oversizing(p: &SymbolSnapshot, intended_notional: f64) -> Option<f64> {
+    let min_notional = (p.min_order? * p.price).to_f64()?;
+    if min_notional <= 0.0 || intended_notional <= 0.0 {
+        return None;
+    }
+    Some(min_notional / intended_notional)
+}
+
+/// Relative deviation of the realized hedge ratio (leg-B notional over
+/// leg-A notional) from `|beta|`, e.g. 0.05 = 5% off. `None` when either
+/// notional or beta is zero.
+pub(super) fn realized_hedge_deviation(
+    size_a: Decimal,
+    price_a: Decimal,
+    size_b: Decimal,
+    price_b: Decimal,
+    beta: f64,
+) -> Option<f64> {
+    let notional_a = (size_a * price_a).abs().to_f64()?;
+    let notional_b = (size_b * price_b).abs().to_f64()?;
+    let beta = beta.abs();
+    if notional_a <= 0.0 || notional_b <= 0.0 || beta <= 0.0 {
+        return None;
+    }
+    Some((notional_b / notional_a / beta - 1.0).abs())
+}
+
+/// Paper entry sizes under `matching_model`. Each leg's simulated fill is
+/// turned into a fill ratio and both legs are cut to the smaller one
+/// (rounded down at the order's own precision) so the paper position keeps
+/// its hedge ratio. Zero on either leg means the entry would not fill.
+pub(super) fn paper_entry_qtys(
+    model: MatchingModel,
+    direction: PositionDirection,
+    hedge_inverted: bool,
+    qtys: (Decimal, Decimal),
+    p1: &SymbolSnapshot,
+    p2: &SymbolSnapshot,
+) -> (Decimal, Decimal) {
+    if model == MatchingModel::Full || qtys.0 <= Decimal::ZERO || qtys.1 <= Decimal::ZERO {
+        return qtys;
+    }
+    let (side_a, side_b) = direction.entry_sides(hedge_inverted);
+    let ratio_a = simulated_fill_qty(model, p1, side_a, qtys.0) / qtys.0;
+    let ratio_b = simulated_fill_qty(model, p2, side_b, qtys.1) / qtys.1;
+    let ratio = ratio_a.min(ratio_b);
+    let scale = |q: Decimal| (q * ratio).round_dp_with_strategy(q.scale(), RoundingStrategy::ToZero);
+    (scale(qtys.0), scale(qtys.1))
+}
+
+/// `shared_symbol_policy: net_exposure`: the first leg, among symbols
+/// already in `existing` (signed USD per symbol), whose net exposure would
+/// exceed `cap` once the candidate's signed leg notional is added, with
+/// that net. `None` when every shared symbol stays within the cap.
+pub(super) fn net_exposure_breach(
+    existing: &HashMap<String, f64>,
+    legs: &[(&str, f64)],
+    cap: f64,
+) -> Option<(String, f64)> {
+    legs.iter().find_map(|(symbol, signed_usd)| {
+        let net = existing.get(*symbol)? + signed_usd;
+        (net.abs() > cap).then(|| (symbol.to_string(), net))
+    })
+}
+
+/// `delta_rebalance_enabled`: symbols whose book exposure (`actual`, signed
+/// USD) is more than `threshold` away from what the open pairs call for
+/// (`target`), with the signed gap `actual - target`, in symbol order. A
+/// symbol missing from `actual` is flat.
+pub(super) fn delta_rebalance_gaps(
+    actual: &HashMap<String, f64>,
+    target: &HashMap<String, f64>,
+    threshold: f64,
+) -> Vec<(String, f64)> {
+    let mut gaps: Vec<(String, f64)> = target
+        .iter()
+        .map(|(symbol, want)| {
+            let have = actual.get(symbol).copied().unwrap_or(0.0);
+            (symbol.clone(), have - want)
+        })
+        .filter(|(_, gap)| gap.abs() > threshold)
+        .collect();
+    gaps.sort_by(|a, b| a.0.cmp(&b.0));
+    gaps
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn delta_rebalance_only_flags_gaps_past_threshold() {
+        let actual = HashMap::from([
+            ("BTC".to_string(), 1_000.0),
+            ("ETH".to_string(), -1_180.0),
+            ("SOL".to_string(), -960.0),
+        ]);
+        let target = HashMap::from([
+            ("BTC".to_string(), 1_000.0),
+            ("ETH".to_string(), -1_000.0),
+            ("SOL".to_string(), -1_000.0),
+            ("AVAX".to_string(), 70.0),
+        ]);
+        assert_eq!(
+            delta_rebalance_gaps(&actual, &target, 50.0),
+            vec![("AVAX".to_string(), -70.0), ("ETH".to_string(), -180.0)]
+        );
+        assert!(delta_rebalance_gaps(&actual, &target, 200.0).is_empty());
+    }
+
+    #[test]
+    fn net_exposure_only_caps_shared_symbols() {
+        let existing = HashMap::from([("BTC".to_string(), 1_000.0)]);
+        // Short BTC offsets the existing long; SOL is not shared.
+        assert_eq!(net_exposure_breach(&existing, &[("BTC", -900.0), ("SOL", 5_000.0)], 500.0), None);
+        assert_eq!(
+            net_exposure_breach(&existing, &[("BTC", 800.0), ("SOL", -800.0)], 1_500.0),
+            Some(("BTC".to_string(), 1_800.0))
+        );
+    }
+
+    #[test]
+    fn realized_hedge_deviation_vs_beta() {
+        let d = |v: &str| v.parse::<Decimal>().unwrap();
+        // 1 × 100 vs 2.1 × 50 = 105 notional → 5% over beta 1.0.
+        let dev = realized_hedge_deviation(d("1"), d("100"), d("2.1"), d("50"), 1.0).unwrap();
+        assert!((dev - 0.05).abs() < 1e-9);
+        // Sign of beta is irrelevant; 105 / 100 matches beta -1.05 exactly.
+        let dev = realized_hedge_deviation(d("1"), d("100"), d("2.1"), d("50"), -1.05).unwrap();
+        assert!(dev < 1e-9);
+        assert_eq!(realized_hedge_deviation(d("0"), d("100"), d("1"), d("50"), 1.0), None);
+    }
+
+    #[test]
+    fn vol_target_disabled_keeps_notional() {
+        assert_eq!(vol_target_notional(500.0, Some(0.02), 0.0, 10.0, 0.0), 500.0);
+        assert_eq!(vol_target_notional(500.0, None, 0.01, 10.0, 0.0), 500.0);
+        assert_eq!(vol_target_notional(500.0, Some(0.0), 0.01, 10.0, 0.0), 500.0);
+    }
+
+    #[test]
+    fn vol_target_high_vol_pair_gets_smaller_size() {
+        let quiet = vol_target_notional(500.0, Some(0.005), 0.01, 10.0, 0.0);
+        let wild = vol_target_notional(500.0, Some(0.04), 0.01, 10.0, 0.0);
+        assert!((quiet - 1_000.0).abs() < 1e-9);
+        assert!((wild - 125.0).abs() < 1e-9);
+        assert!(wild < quiet);
+    }
+
+    #[test]
+    fn vol_target_respects_notional_bounds() {
+        // Extremely quiet pair would scale 100x; upper bound clamps it.
+        assert_eq!(vol_target_notional(500.0, Some(0.0001), 0.01, 10.0, 2_000.0), 2_000.0);
+        // Extremely wild pair would scale to 5; lower bound lifts it.
+        assert_eq!(vol_target_notional(500.0, Some(1.0), 0.01, 10.0, 2_000.0), 10.0);
+    }
+
+    #[test]
+    fn vol_target_stays_finite_as_std_goes_to_zero() {
+        for std in [1e-6, 1e-12, f64::MIN_POSITIVE] {
+            let scaled = vol_target_notional(500.0, Some(std), 0.01, 10.0, 0.0);
+            assert_eq!(scaled, 500.0 * VOL_TARGET_MAX_SCALE);
+        }
+    }
+
+    #[test]
+    fn min_order_oversizing_ratio() {
+        let snap = SymbolSnapshot {
+            min_order: Some(Decimal::new(1, 1)),
+            size_decimals: Some(2),
+            ..SymbolSnapshot::test_at(Decimal::from(2_000))
+        };
+        // 0.1 × 2000 = $200 minimum against a $5 intended leg → 40x.
+        assert!((min_order_oversizing(&snap, 5.0).unwrap() - 40.0).abs() < 1e-9);
+        assert_eq!(min_order_oversizing(&snap, 0.0), None);
+        let no_min = SymbolSnapshot {
+            min_order: None,
+            ..snap
+        };
+        assert_eq!(min_order_oversizing(&no_min, 5.0), None);
+    }
+
     #[test]
     fn cap_disabled_returns_none() {
         assert_eq!(cap_leg_notional(100_000.0, 1.0, 0.0), None);
@@ -128,4 +463,58 @@ mod tests {
         // leg_notional == allowed → no clamp (use > comparison, not ≥).
         assert_eq!(cap_leg_notional(50_000.0, 1.0, 50_000.0), None);
     }
+
+    #[test]
+    fn leverage_cap_bounds_gross_of_both_legs() {
+        assert_eq!(leverage_cap_leg_notional(5_000.0, 1.0, 0.0, 2.0), None);
+        // 10k equity at 1x with beta 1.5: gross 2.5 * leg must stay <= 10k.
+        assert_eq!(leverage_cap_leg_notional(3_000.0, 1.5, 10_000.0, 1.0), None);
+        assert_eq!(leverage_cap_leg_notional(5_000.0, -1.5, 10_000.0, 1.0), Some(4_000.0));
+    }
+
+    #[test]
+    fn paper_entry_cuts_both_legs_to_thinner_fill() {
+        let d = |v: &str| v.parse::<Decimal>().unwrap();
+        let snap = |bid: &str, ask: &str| SymbolSnapshot {
+            bid_price: Some(d("99")),
+            ask_price: Some(d("101")),
+            bid_size: d(bid),
+            ask_size: d(ask),
+            ..SymbolSnapshot::test_at(d("100"))
+        };
+        // LongSpread: buy A (queue 1, contra 3 → 2 of 4), sell B (fills 8 of 8).
+        let (p1, p2) = (snap("1", "3"), snap("50", "0"));
+        let qtys = (d("4.00"), d("8.00"));
+        let dir = PositionDirection::LongSpread;
+        assert_eq!(paper_entry_qtys(MatchingModel::Full, dir, false, qtys, &p1, &p2), qtys);
+        assert_eq!(
+            paper_entry_qtys(MatchingModel::PriceTime, dir, false, qtys, &p1, &p2),
+            (d("2.00"), d("4.00"))
+        );
+        // Same-side hedge: B is bought too and its queue of 50 never clears.
+        assert_eq!(
+            paper_entry_qtys(MatchingModel::PriceTime, dir, true, qtys, &p1, &p2),
+            (d("0.00"), d("0.00"))
+        );
+    }
+
+    #[test]
+    fn hedged_sizes_price_each_leg_on_its_own_side() {
+        use super::super::market::BookDepth;
+        let d = |v: &str| v.parse::<Decimal>().unwrap();
+        let cfg = PairTradeConfig::from_env().unwrap();
+        let snap = |price: &str, bid: &str, ask: &str| SymbolSnapshot {
+            size_decimals: Some(2),
+            depth: BookDepth::from_levels(vec![(d(bid), d("100"))], vec![(d(ask), d("100"))], 0.0),
+            ..SymbolSnapshot::test_at(d(price))
+        };
+        let (p1, p2) = (snap("100", "99", "101"), snap("50", "49", "51"));
+        let sizes = |dir| hedged_sizes(&cfg, 1e6, dir, 1.0, None, Some(1_000.0), &p1, &p2).unwrap();
+
+        // Buy A at the 101 ask, then sell B at the 49 bid against the
+        // 9.90 × 101 = 999.90 actually sized on A.
+        assert_eq!(sizes(PositionDirection::LongSpread), (d("9.90"), d("20.41")));
+        // Sell A at the 99 bid (10.10 × 99 = 999.90), buy B at the 51 ask.
+        assert_eq!(sizes(PositionDirection::ShortSpread), (d("10.10"), d("19.61")));
+    }
 }
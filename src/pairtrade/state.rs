@@ -10,9 +10,9 @@ use std::time::Instant;
 use dex_connector::DexError;
 use rust_decimal::Decimal;
 
-use super::config::PairTradeConfig;
+use super::config::{PairTradeConfig, ZStatMode};
 use super::kalman::KalmanBeta;
-use super::util::mean_std;
+use super::util::{mean_std, median_mad};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(super) enum PositionDirection {
@@ -20,6 +20,40 @@ pub(super) enum PositionDirection {
     ShortSpread,
 }
 
+impl PositionDirection {
+    /// Entry order sides for (leg A, leg B). Long spread buys A and sells
+    /// B; with `hedge_inverted` (negative beta) B is traded on the same side
+    /// as A, since the spread `a − β·b` then rises with b.
+    pub(super) fn entry_sides(
+        self,
+        hedge_inverted: bool,
+    ) -> (dex_connector::OrderSide, dex_connector::OrderSide) {
+        use dex_connector::OrderSide::{Long, Short};
+        let (a, b) = match self {
+            PositionDirection::LongSpread => (Long, Short),
+            PositionDirection::ShortSpread => (Short, Long),
+        };
+        if hedge_inverted {
+            (a, a)
+        } else {
+            (a, b)
+        }
+    }
+
+    /// Closing order sides for (leg A, leg B): the reverse of `entry_sides`.
+    pub(super) fn exit_sides(
+        self,
+        hedge_inverted: bool,
+    ) -> (dex_connector::OrderSide, dex_connector::OrderSide) {
+        let flip = |side| match side {
+            dex_connector::OrderSide::Long => dex_connector::OrderSide::Short,
+            dex_connector::OrderSide::Short => dex_connector::OrderSide::Long,
+        };
+        let (a, b) = self.entry_sides(hedge_inverted);
+        (flip(a), flip(b))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(super) struct Position {
     pub(super) direction: PositionDirection,
@@ -35,6 +69,27 @@ pub(super) struct Position {
     pub(super) entry_size_a: Option<Decimal>,
     pub(super) entry_size_b: Option<Decimal>,
     pub(super) entry_z: Option<f64>,
+    /// Hedge ratio the legs were sized with; `beta_drift_exit_pct` compares
+    /// the current beta against it. `None` when adopted from an exchange
+    /// snapshot without a known entry.
+    pub(super) entry_beta: Option<f64>,
+    /// Net cash flow of the entry fills (sell value minus buy value minus
+    /// fees) from venue fill data; `reconcile_fill_pnl` adds the exit's to
+    /// it for realized PnL. `None` when any leg lacked fill data.
+    pub(super) entry_fill_cash: Option<Decimal>,
+    /// `partial_take_profit_fraction` already closed part of the legs; the
+    /// recorded sizes are the remainder.
+    pub(super) partial_taken: bool,
+    /// The `scale_in_*` add already went in; the recorded sizes and entry
+    /// prices cover both phases.
+    pub(super) scaled_in: bool,
+    /// Rest of the full entry size that `scale_in` adds to the scout,
+    /// fixed from the scout orders at entry so a later partial take-profit
+    /// doesn't change it. `None` without scale-in or for a position adopted
+    /// from the exchange.
+    pub(super) scale_in_add: Option<(Decimal, Decimal)>,
+    /// Entered on a negative beta: both legs are on the same side.
+    pub(super) hedge_inverted: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -51,6 +106,9 @@ pub(super) struct PendingLeg {
     /// ([ORDER_FALLBACK_DETAIL], bot-strategy#165) to compare the posted
     /// price against the book at timeout.
     pub(super) limit_price: Option<Decimal>,
+    /// Snapshot price of the symbol when the leg was placed. Realized
+    /// slippage of a leg without a limit is measured against it.
+    pub(super) placed_price: Option<Decimal>,
 }
 
 #[derive(Debug)]
@@ -60,12 +118,23 @@ pub(super) struct PendingOrders {
     pub(super) placed_at: Instant,
     pub(super) hedge_retry_count: u32,
     pub(super) post_only_hybrid: bool,
+    /// Reduce-only orders of a `partial_take_profit_fraction` slice: the
+    /// position shrinks by what fills and keeps the remainder.
+    pub(super) partial_exit: bool,
+    /// Orders adding the `scale_in_*` remainder to an open scout: the
+    /// position grows by what fills and is otherwise left as it was.
+    pub(super) scale_in: bool,
 }
 
 #[derive(Debug)]
 pub(super) struct PendingStatus {
     pub(super) open_remaining: usize,
     pub(super) fills: HashMap<String, Decimal>,
+    /// Filled notional per order id, when the venue reports it. Divided by
+    /// the matching `fills` size it gives the realized fill price.
+    pub(super) fill_values: HashMap<String, Decimal>,
+    /// Fees charged per order id, when the venue reports them.
+    pub(super) fill_fees: HashMap<String, Decimal>,
     pub(super) open_ids: HashSet<String>,
 }
 
@@ -102,6 +171,15 @@ pub(super) struct PairState {
     pub(super) beta: f64,
     pub(super) z_entry: f64,
     pub(super) spread_history: VecDeque<f64>,
+    /// Statistic `z_score_details` uses over `spread_history`.
+    pub(super) z_stat_mode: ZStatMode,
+    /// Last `spread_smoothing_bars` raw (unsmoothed) spreads. Empty when
+    /// smoothing is disabled.
+    pub(super) raw_spreads: VecDeque<f64>,
+    /// Bar timestamps of the most recent `spread_history` entries, aligned
+    /// to its tail. Shorter than `spread_history` after a restore from disk
+    /// or warm start, whose spreads carry no timestamps.
+    pub(super) spread_ts: VecDeque<i64>,
     pub(super) last_spread: Option<f64>,
     pub(super) last_velocity_sigma_per_min: f64,
     pub(super) position: Option<Position>,
@@ -110,19 +188,41 @@ pub(super) struct PairState {
     /// cooldown and unhedged-close cooldown so they fire correctly under
     /// backtest replay.
     pub(super) last_exit_ts: Option<i64>,
+    /// Reason tag of the most recent close decision (`exit_reason` labels
+    /// plus `force_close` / `ineligible` / ...). A `stop_loss_z` exit
+    /// switches should_enter to `stop_loss_cooldown_secs`.
+    pub(super) last_exit_reason: Option<&'static str>,
+    /// When `flatten_mismatched_legs` last submitted closes for this pair;
+    /// spaces out re-submission while the fills land.
+    pub(super) last_mismatch_flatten_at: Option<Instant>,
     pub(super) beta_short: f64,
     pub(super) beta_long: f64,
     pub(super) half_life_hours: f64,
     pub(super) adf_p_value: f64,
     pub(super) eligible: bool,
+    /// Direction-specific eligibility, refreshed every tick: `eligible`
+    /// and that direction's net carry clears the floor. Gates entries
+    /// under `directional_eligibility`.
+    pub(super) eligible_long_spread: bool,
+    pub(super) eligible_short_spread: bool,
     pub(super) last_evaluated: Option<Instant>,
     /// Replay-aware companion to `last_evaluated`. Drives the periodic
-    /// pair re-evaluation interval (PAIR_SELECTION_INTERVAL_SECS).
+    /// pair re-evaluation interval (`pair_eval_interval_secs`).
     pub(super) last_evaluated_ts: Option<i64>,
     pub(super) p_value_weighted_score: f64,
     pub(super) beta_gap: f64,
     pub(super) pending_entry: Option<PendingOrders>,
     pub(super) pending_exit: Option<PendingOrders>,
+    /// When this pair last went from no pending order to one. Unlike
+    /// `PendingOrders::placed_at` it survives reissues; `pending_max_age_secs`
+    /// is measured from it.
+    pub(super) pending_since: Option<Instant>,
+    /// Replay-aware time of the last entry, seeded with the first bar the
+    /// pair is seen on so a pair that never trades also ages. Drives the
+    /// `pair_idle_alert_secs` watchdog.
+    pub(super) last_entry_ts: Option<i64>,
+    /// The `[IDLE]` warning has fired since `last_entry_ts`.
+    pub(super) idle_alerted: bool,
     pub(super) position_guard: bool,
     pub(super) kalman: Option<KalmanBeta>,
     /// Rolling history of the most recent full-window spread std values, one
@@ -137,6 +237,15 @@ pub(super) struct PairState {
     /// position still held (blocking new entries). The PnL is already computed
     /// and stored here so it can be logged when the deferred exit resolves.
     pub(super) bt_deferred_exit: Option<BtDeferredExit>,
+    /// Replay-aware time until which entries stay blocked after a NaN/inf
+    /// statistic (`non_finite_stats_block_secs`).
+    pub(super) stats_blocked_until_ts: Option<i64>,
+    /// Spread updates this pair has had; the tick clock for
+    /// `loss_reentry_block_ticks`.
+    pub(super) eval_ticks: u64,
+    /// `eval_ticks` at the last exit if it lost money. Cleared by a
+    /// profitable exit.
+    pub(super) last_loss_exit_tick: Option<u64>,
 }
 
 /// Deferred exit info for BT fill-delay simulation.
@@ -152,34 +261,127 @@ impl PairState {
             beta: 1.0,
             z_entry,
             spread_history: VecDeque::with_capacity(window),
+            z_stat_mode: ZStatMode::Simple,
+            raw_spreads: VecDeque::new(),
+            spread_ts: VecDeque::with_capacity(window),
             last_spread: None,
             last_velocity_sigma_per_min: 0.0,
             position: None,
             last_exit_at: None,
             last_exit_ts: None,
+            last_exit_reason: None,
+            last_mismatch_flatten_at: None,
             beta_short: 1.0,
             beta_long: 1.0,
             half_life_hours: 0.0,
             adf_p_value: 1.0,
             eligible: false,
+            eligible_long_spread: false,
+            eligible_short_spread: false,
             last_evaluated: None,
             last_evaluated_ts: None,
             p_value_weighted_score: 0.0,
             beta_gap: 0.0,
             pending_entry: None,
             pending_exit: None,
+            pending_since: None,
+            last_entry_ts: None,
+            idle_alerted: false,
             position_guard: false,
             kalman: None,
             std_history: VecDeque::new(),
             bt_deferred_exit: None,
+            stats_blocked_until_ts: None,
+            eval_ticks: 0,
+            last_loss_exit_tick: None,
         }
     }
 
-    pub(super) fn push_spread(&mut self, spread: f64, window: usize, config: &PairTradeConfig) {
+    pub(super) fn stats_blocked(&self, now_ts: i64) -> bool {
+        self.stats_blocked_until_ts.is_some_and(|until| now_ts < until)
+    }
+
+    /// A new entry was placed or filled: re-arm the idle watchdog and drop
+    /// the previous trade's exit reason so it can't label this one's close.
+    pub(super) fn mark_entry(&mut self, now_ts: i64) {
+        self.last_entry_ts = Some(now_ts);
+        self.idle_alerted = false;
+        self.last_exit_reason = None;
+    }
+
+    /// Remember whether a closed trade lost money, for the loss re-entry
+    /// guard. A flat or profitable exit clears an earlier loss.
+    pub(super) fn record_exit_pnl(&mut self, pnl: f64) {
+        self.last_loss_exit_tick = (pnl < 0.0).then_some(self.eval_ticks);
+    }
+
+    /// The last exit lost money no more than `block_ticks` evaluations ago.
+    pub(super) fn loss_reentry_blocked(&self, block_ticks: u64) -> bool {
+        block_ticks > 0
+            && self
+                .last_loss_exit_tick
+                .is_some_and(|tick| self.eval_ticks.saturating_sub(tick) <= block_ticks)
+    }
+
+    /// Replace `spread_history` with a persisted, already-smoothed series.
+    /// Its raw inputs are gone, so smoothing restarts from the next spread.
+    pub(super) fn restore_spread_history(&mut self, spreads: VecDeque<f64>) {
+        self.last_spread = spreads.back().copied();
+        self.spread_history = spreads;
+        self.spread_ts.clear();
+        self.raw_spreads.clear();
+    }
+
+    /// Seed `spread_history` from raw spreads recomputed off bar history,
+    /// smoothed the same way `push_spread` would have and leaving
+    /// `raw_spreads` holding their tail.
+    pub(super) fn seed_spread_history(&mut self, raw: &[f64], config: &PairTradeConfig) {
+        let smoothing = config.spread_smoothing_bars;
+        self.raw_spreads.clear();
+        let spreads: VecDeque<f64> = raw
+            .iter()
+            .map(|&spread| {
+                if smoothing > 1 {
+                    if self.raw_spreads.len() >= smoothing {
+                        self.raw_spreads.pop_front();
+                    }
+                    self.raw_spreads.push_back(spread);
+                    self.raw_spreads.iter().sum::<f64>() / self.raw_spreads.len() as f64
+                } else {
+                    spread
+                }
+            })
+            .collect();
+        self.last_spread = spreads.back().copied();
+        self.spread_history = spreads;
+        self.spread_ts.clear();
+    }
+
+    pub(super) fn push_spread(
+        &mut self,
+        spread: f64,
+        ts: i64,
+        window: usize,
+        config: &PairTradeConfig,
+    ) {
+        let smoothing = config.spread_smoothing_bars;
+        let spread = if smoothing > 1 {
+            if self.raw_spreads.len() >= smoothing {
+                self.raw_spreads.pop_front();
+            }
+            self.raw_spreads.push_back(spread);
+            self.raw_spreads.iter().sum::<f64>() / self.raw_spreads.len() as f64
+        } else {
+            spread
+        };
         if self.spread_history.len() >= window {
             self.spread_history.pop_front();
         }
+        if self.spread_ts.len() >= window {
+            self.spread_ts.pop_front();
+        }
         self.spread_history.push_back(spread);
+        self.spread_ts.push_back(ts);
         self.last_spread = Some(spread);
 
         // Record the current full-window std for the std-collapse guard
@@ -215,6 +417,23 @@ impl PairState {
         }
     }
 
+    /// Largest number of missing bars between consecutive timestamped
+    /// spreads in the window.
+    pub(super) fn max_spread_gap_bars(&self, period_secs: u64) -> usize {
+        if period_secs == 0 {
+            return 0;
+        }
+        self.spread_ts
+            .iter()
+            .zip(self.spread_ts.iter().skip(1))
+            .map(|(prev, next)| {
+                let bars = next.saturating_sub(*prev).max(0) as u64 / period_secs;
+                bars.saturating_sub(1) as usize
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
     pub(super) fn z_score(&self) -> Option<(f64, f64)> {
         self.z_score_details().map(|(z, std, _, _)| (z, std))
     }
@@ -223,7 +442,10 @@ impl PairState {
         if self.spread_history.len() < 2 {
             return None;
         }
-        let (mean, std) = mean_std(&self.spread_history)?;
+        let (mean, std) = match self.z_stat_mode {
+            ZStatMode::Simple => mean_std(&self.spread_history)?,
+            ZStatMode::Robust => median_mad(&self.spread_history)?,
+        };
         let latest = *self.spread_history.back().unwrap();
         let z = if std < 1e-9 {
             0.0
@@ -3,10 +3,16 @@
 
 use std::collections::VecDeque;
 
+use rust_decimal::Decimal;
+
 #[derive(Debug, Clone)]
 pub(super) struct PriceSample {
     pub(super) log_price: f64,
     pub(super) ts: i64,
+    /// Raw bar close the `log_price` was derived from, when known. Lets the
+    /// `decimal` history format persist the exact price instead of the
+    /// f64 log. `None` for samples loaded from an f64-format file.
+    pub(super) close: Option<Decimal>,
 }
 
 pub(super) fn tail_samples(history: &VecDeque<PriceSample>, len: usize) -> Vec<PriceSample> {
@@ -16,7 +22,18 @@ pub(super) fn tail_samples(history: &VecDeque<PriceSample>, len: usize) -> Vec<P
     v
 }
 
-pub(super) fn regression_beta(x: &[PriceSample], y: &[PriceSample]) -> f64 {
+/// Clamp a fitted beta into the tradable range. Positive-only by default;
+/// with `allow_negative` the sign is kept and only the magnitude is clamped,
+/// so an inverted relationship is never forced through zero.
+pub(super) fn clamp_beta(beta: f64, allow_negative: bool) -> f64 {
+    if allow_negative && beta < 0.0 {
+        -(-beta).clamp(0.1, 10.0)
+    } else {
+        beta.clamp(0.1, 10.0)
+    }
+}
+
+pub(super) fn regression_beta(x: &[PriceSample], y: &[PriceSample], allow_negative: bool) -> f64 {
     let n = x.len().min(y.len());
     if n < 2 {
         return 1.0;
@@ -39,8 +56,112 @@ pub(super) fn regression_beta(x: &[PriceSample], y: &[PriceSample]) -> f64 {
     if var_x.abs() < 1e-9 {
         1.0
     } else {
-        (cov / var_x).clamp(0.1, 10.0)
+        clamp_beta(cov / var_x, allow_negative)
+    }
+}
+
+/// OLS beta of y on x over first-differenced log prices (bar returns).
+/// Immune to a common trend, which only shifts the return means. Same
+/// fallback and clamp as `regression_beta`.
+pub(super) fn regression_beta_on_returns(
+    x: &[PriceSample],
+    y: &[PriceSample],
+    allow_negative: bool,
+) -> f64 {
+    let n = x.len().min(y.len());
+    if n < 3 {
+        return 1.0;
+    }
+    let rx: Vec<f64> = x[..n].windows(2).map(|w| w[1].log_price - w[0].log_price).collect();
+    let ry: Vec<f64> = y[..n].windows(2).map(|w| w[1].log_price - w[0].log_price).collect();
+    let m = rx.len() as f64;
+    let mean_x = rx.iter().sum::<f64>() / m;
+    let mean_y = ry.iter().sum::<f64>() / m;
+    let mut cov = 0.0;
+    let mut var_x = 0.0;
+    for (dx, dy) in rx.iter().zip(ry.iter()) {
+        cov += (dx - mean_x) * (dy - mean_y);
+        var_x += (dx - mean_x) * (dx - mean_x);
+    }
+    if var_x.abs() < 1e-12 {
+        1.0
+    } else {
+        clamp_beta(cov / var_x, allow_negative)
+    }
+}
+
+/// Hedge beta per the `beta_on_returns` and `allow_negative_beta` settings.
+pub(super) fn hedge_beta(
+    x: &[PriceSample],
+    y: &[PriceSample],
+    on_returns: bool,
+    allow_negative: bool,
+) -> f64 {
+    if on_returns {
+        regression_beta_on_returns(x, y, allow_negative)
+    } else {
+        regression_beta(x, y, allow_negative)
+    }
+}
+
+/// Mean and divisor `standardize` uses for the slice: the log-price std,
+/// or 1 for a flat slice.
+fn standardize_params(samples: &[PriceSample]) -> (f64, f64) {
+    let n = samples.len() as f64;
+    let mean = samples.iter().map(|s| s.log_price).sum::<f64>() / n;
+    let var = samples
+        .iter()
+        .map(|s| (s.log_price - mean) * (s.log_price - mean))
+        .sum::<f64>()
+        / n;
+    let std = var.sqrt();
+    (mean, if std < 1e-12 { 1.0 } else { std })
+}
+
+/// Standardize log prices over the slice: `(x - mean) / std`. A flat
+/// slice is only demeaned. Used by `normalize_legs`.
+pub(super) fn standardize(samples: &[PriceSample]) -> Vec<PriceSample> {
+    if samples.is_empty() {
+        return Vec::new();
     }
+    let (mean, scale) = standardize_params(samples);
+    samples
+        .iter()
+        .map(|s| PriceSample {
+            log_price: (s.log_price - mean) / scale,
+            ..s.clone()
+        })
+        .collect()
+}
+
+/// Divisor `standardize` applies to the trailing `window` samples. A beta
+/// fitted on standardized legs times `scale_a / scale_b` is back on the
+/// log-price scale.
+pub(super) fn standardize_scale(history: &VecDeque<PriceSample>, window: usize) -> Option<f64> {
+    let tail = tail_samples(history, window);
+    (!tail.is_empty()).then(|| standardize_params(&tail).1)
+}
+
+/// Latest log price standardized against the trailing `window` samples.
+pub(super) fn standardized_latest(history: &VecDeque<PriceSample>, window: usize) -> Option<f64> {
+    standardize(&tail_samples(history, window))
+        .last()
+        .map(|s| s.log_price)
+}
+
+/// Log return of `log_price` against the newest sample when its magnitude
+/// exceeds `bound` (`max_bar_log_return`); `None` when within bounds, with no
+/// prior sample, or with the check disabled (`bound <= 0`).
+pub(super) fn outlier_log_return(
+    history: &VecDeque<PriceSample>,
+    log_price: f64,
+    bound: f64,
+) -> Option<f64> {
+    if bound <= 0.0 {
+        return None;
+    }
+    let ret = log_price - history.back()?.log_price;
+    (ret.abs() > bound).then_some(ret)
 }
 
 pub(super) fn spread_slope_sigma(history: &VecDeque<f64>, window: usize) -> Option<f64> {
@@ -77,3 +198,96 @@ pub(super) fn spread_slope_sigma(history: &VecDeque<f64>, window: usize) -> Opti
     }
     Some((slope / std).abs())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(f: impl Fn(f64) -> f64, n: usize) -> Vec<PriceSample> {
+        (0..n)
+            .map(|i| PriceSample {
+                log_price: f(i as f64),
+                ts: i as i64 * 60,
+                close: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn outlier_log_return_flags_jumps_past_the_bound() {
+        let history: VecDeque<PriceSample> = series(|_| 100f64.ln(), 3).into();
+        assert_eq!(outlier_log_return(&VecDeque::new(), 0.0, 0.5), None);
+        assert_eq!(outlier_log_return(&history, 120f64.ln(), 0.5), None);
+        let ret = outlier_log_return(&history, 1f64.ln(), 0.5).unwrap();
+        assert!((ret + 100f64.ln()).abs() < 1e-12);
+        assert_eq!(outlier_log_return(&history, 1f64.ln(), 0.0), None);
+    }
+
+    #[test]
+    fn returns_beta_ignores_common_trend() {
+        // y co-moves with x at 0.5 bar-to-bar, but both share a strong
+        // drift (y's twice x's). Levels regress onto the trend ratio.
+        let noise = |t: f64| (t * 1.7).sin() * 0.01;
+        let x = series(|t| 0.01 * t + noise(t), 240);
+        let y = series(|t| 0.02 * t + 0.5 * noise(t), 240);
+
+        let level = regression_beta(&x, &y, false);
+        let returns = regression_beta_on_returns(&x, &y, false);
+        assert!(level > 1.9, "level beta should track the trend, got {}", level);
+        assert!((returns - 0.5).abs() < 1e-9, "returns beta {}", returns);
+        assert_eq!(hedge_beta(&x, &y, false, false), level);
+        assert_eq!(hedge_beta(&x, &y, true, false), returns);
+    }
+
+    #[test]
+    fn standardized_legs_are_scale_invariant() {
+        let a = series(|t| (t * 0.3).sin(), 50);
+        let b = series(|t| 100.0 * (t * 0.3).sin() + 7.0, 50);
+        let (za, zb) = (standardize(&a), standardize(&b));
+        for (x, y) in za.iter().zip(zb.iter()) {
+            assert!((x.log_price - y.log_price).abs() < 1e-9);
+        }
+        let flat = standardize(&series(|_| 2.0, 5));
+        assert!(flat.iter().all(|s| s.log_price == 0.0));
+        let hist: VecDeque<PriceSample> = b.into_iter().collect();
+        assert!((standardized_latest(&hist, 50).unwrap() - zb[49].log_price).abs() < 1e-12);
+    }
+
+    #[test]
+    fn standardized_beta_scales_back_to_log_prices() {
+        // b moves 4x as much as a: standardized they move 1:1, and the
+        // standardized beta of 1 times std_a / std_b recovers 0.25.
+        let a = series(|t| 0.01 * (t * 0.3).sin(), 60);
+        let b = series(|t| 0.04 * (t * 0.3).sin() + 3.0, 60);
+        let beta_std = regression_beta(&standardize(&b), &standardize(&a), false);
+        assert!((beta_std - 1.0).abs() < 1e-9);
+        let hist = |s: Vec<PriceSample>| s.into_iter().collect::<VecDeque<_>>();
+        let scale_a = standardize_scale(&hist(a.clone()), 60).unwrap();
+        let scale_b = standardize_scale(&hist(b.clone()), 60).unwrap();
+        assert!((beta_std * scale_a / scale_b - regression_beta(&b, &a, false)).abs() < 1e-9);
+        assert!((beta_std * scale_a / scale_b - 0.25).abs() < 1e-9);
+        assert!(standardize_scale(&VecDeque::new(), 60).is_none());
+    }
+
+    #[test]
+    fn negative_beta_only_when_allowed() {
+        // y moves against x at -0.8: the positive-only clamp pins it at the
+        // 0.1 floor, the signed clamp keeps the inverse relationship.
+        let x = series(|t| (t * 0.4).sin() * 0.05, 120);
+        let y = series(|t| 3.0 - 0.8 * (t * 0.4).sin() * 0.05, 120);
+        assert_eq!(hedge_beta(&x, &y, false, false), 0.1);
+        assert!((hedge_beta(&x, &y, false, true) + 0.8).abs() < 1e-9);
+        assert!((hedge_beta(&x, &y, true, true) + 0.8).abs() < 1e-9);
+        assert_eq!(clamp_beta(-0.01, true), -0.1);
+        assert_eq!(clamp_beta(-25.0, true), -10.0);
+        assert_eq!(clamp_beta(0.0, true), 0.1);
+    }
+
+    #[test]
+    fn returns_beta_falls_back_on_short_or_flat_input() {
+        let flat = series(|_| 1.0, 10);
+        assert_eq!(regression_beta_on_returns(&flat, &flat, false), 1.0);
+        let short = series(|t| t, 2);
+        assert_eq!(regression_beta_on_returns(&short, &short, false), 1.0);
+    }
+}
@@ -2,7 +2,7 @@
 //! from the monolithic pairtrade module. The reporter writes a JSON status
 //! file consumed by the dashboard, plus an equity history JSONL.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::cmp::Ordering;
 use std::fs::{self, OpenOptions};
 use std::io::Write;
@@ -15,7 +15,8 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 use super::config::PairTradeConfig;
-use super::pnl_log::sanitize_pnl_tag;
+use super::pnl_log::{round_dp, sanitize_pnl_tag};
+use super::trade_stats::TradeStatsBook;
 use crate::error_counter::{self, ErrorSummary};
 
 use std::env;
@@ -30,6 +31,12 @@ pub(super) struct EquityBaseline {
 pub(super) struct EquityHistoryPoint {
     pub(super) ts: i64,
     pub(super) equity: f64,
+    /// Only written with `equity_history_detailed`: the gross notional and
+    /// open pair count of this instance's own positions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) gross_notional: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) position_count: Option<usize>,
 }
 
 #[derive(Debug)]
@@ -47,6 +54,10 @@ pub(super) struct StatusReporter {
     pub(super) pnl_today_date: NaiveDate,
     pub(super) equity_day_start: f64,
     pub(super) equity_day_start_set: bool,
+    /// Readings needed before the day's baseline is fixed
+    /// (`equity_baseline_samples`), and those collected so far.
+    pub(super) equity_baseline_samples: usize,
+    pub(super) equity_baseline_pending: Vec<f64>,
     pub(super) equity_baseline_path: PathBuf,
     pub(super) equity_history_path: PathBuf,
     pub(super) last_equity_history_ts: Option<i64>,
@@ -62,6 +73,28 @@ pub(super) struct StatusReporter {
     /// Phase 3-1 session-DD snapshot. None until the threshold is
     /// enabled and the first equity sample is taken.
     pub(super) session_risk: Option<SessionRiskSnapshot>,
+    /// Per-pair / aggregate trade stats. Set by the engine every tick via
+    /// `set_pair_stats`.
+    pub(super) pair_stats: Option<TradeStatsBook>,
+    /// Startup force-close progress while it runs; cleared once every
+    /// position is confirmed closed, kept when some stay open. See
+    /// `set_startup_close`.
+    pub(super) startup_close: Option<StartupCloseStatus>,
+    /// Positions past `position_age_warn_secs`. See `set_aging`.
+    pub(super) aging: Vec<AgingPosition>,
+    /// Exchange size adjustments per symbol, with
+    /// `status_size_adjustments`. See `set_size_adjustments`.
+    pub(super) size_adjustments: BTreeMap<String, SizeAdjustment>,
+    /// `(gross_notional, position_count)` attached to equity history points
+    /// with `equity_history_detailed`. See `set_exposure`.
+    pub(super) exposure: Option<(f64, usize)>,
+    /// Venue `size_decimals` by symbol, from the latest price snapshots;
+    /// position sizes are written at that precision.
+    pub(super) size_decimals: HashMap<String, u32>,
+    /// Output rounding (`status_price_dp` / `status_pnl_dp`); None keeps
+    /// full precision.
+    pub(super) price_dp: Option<u32>,
+    pub(super) pnl_dp: Option<u32>,
 }
 
 /// Per-instance realized daily-DD view emitted in `status.json` so the
@@ -134,6 +167,14 @@ pub(super) struct StatusSnapshot {
     pub(super) daily_risk: Option<DailyRiskSnapshot>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(super) session_risk: Option<SessionRiskSnapshot>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) pair_stats: Option<TradeStatsBook>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) startup_close: Option<StartupCloseStatus>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(super) aging: Vec<AgingPosition>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub(super) size_adjustments: BTreeMap<String, SizeAdjustment>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -160,6 +201,55 @@ pub(super) struct ShutdownStatus {
     pub(super) positions: Vec<ShutdownPosition>,
 }
 
+/// Progress of the startup force-close loop, so a supervisor can tell a slow
+/// flatten from a stuck one.
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct StartupCloseStatus {
+    pub(super) attempt: u32,
+    pub(super) attempts: u32,
+    /// Open positions seen at the start of `attempt`; `None` when
+    /// `get_positions` failed.
+    pub(super) remaining_positions: Option<usize>,
+    pub(super) done: bool,
+}
+
+/// A position held past `position_age_warn_secs`, listed so operators can
+/// look at it before `force_close_secs` flushes it.
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct AgingPosition {
+    pub(super) key: String,
+    pub(super) entered_ts: i64,
+    pub(super) age_secs: i64,
+    pub(super) force_close_eta_ts: i64,
+}
+
+/// Running tally, per symbol, of orders whose size the connector reported
+/// differently from what the engine asked for (rounding, min-size bumps).
+#[derive(Debug, Clone, Default, Serialize)]
+pub(super) struct SizeAdjustment {
+    pub(super) orders: u64,
+    pub(super) adjusted: u64,
+    /// Sum of |ordered - requested| over adjusted orders, in base units.
+    pub(super) abs_diff: f64,
+    /// Largest |ordered - requested| / requested seen, in percent.
+    pub(super) max_diff_pct: f64,
+}
+
+impl SizeAdjustment {
+    pub(super) fn record(&mut self, requested: f64, ordered: f64) {
+        self.orders += 1;
+        let diff = (ordered - requested).abs();
+        if diff <= 0.0 {
+            return;
+        }
+        self.adjusted += 1;
+        self.abs_diff += diff;
+        if requested > 0.0 {
+            self.max_diff_pct = self.max_diff_pct.max(diff / requested * 100.0);
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub(super) struct ShutdownPosition {
     pub(super) key: String,
@@ -270,6 +360,20 @@ impl StatusReporter {
             })
             .unwrap_or_else(|| PathBuf::from("status.json"));
 
+        let mut reporter = Self::new(cfg, id, path);
+        reporter.load_equity_baseline();
+        if let Err(err) = reporter.ensure_status_file() {
+            log::warn!(
+                "[STATUS] failed to create status file {}: {:?}",
+                reporter.path.display(),
+                err
+            );
+        }
+        Some(reporter)
+    }
+
+    /// Reporter writing to `path`, with nothing loaded from disk yet.
+    pub(super) fn new(cfg: &PairTradeConfig, id: Option<String>, path: PathBuf) -> Self {
         let equity_baseline_path = path.with_extension("equity.json");
         let equity_history_path = path.with_extension("equity_history.jsonl");
         let interval_secs = cfg.interval_secs.max(1);
@@ -279,7 +383,7 @@ impl StatusReporter {
             Duration::from_secs(interval_secs.saturating_mul(n).max(1))
         };
 
-        let mut reporter = Self {
+        Self {
             path,
             id,
             agent: cfg.agent_name.clone(),
@@ -293,6 +397,8 @@ impl StatusReporter {
             pnl_today_date: Utc::now().date_naive(),
             equity_day_start: 0.0,
             equity_day_start_set: false,
+            equity_baseline_samples: cfg.equity_baseline_samples.max(1),
+            equity_baseline_pending: Vec::new(),
             equity_baseline_path,
             equity_history_path,
             last_equity_history_ts: None,
@@ -308,16 +414,15 @@ impl StatusReporter {
             shutdown: None,
             daily_risk: None,
             session_risk: None,
-        };
-        reporter.load_equity_baseline();
-        if let Err(err) = reporter.ensure_status_file() {
-            log::warn!(
-                "[STATUS] failed to create status file {}: {:?}",
-                reporter.path.display(),
-                err
-            );
+            pair_stats: None,
+            startup_close: None,
+            aging: Vec::new(),
+            size_adjustments: BTreeMap::new(),
+            exposure: None,
+            size_decimals: HashMap::new(),
+            price_dp: cfg.status_price_dp,
+            pnl_dp: cfg.status_pnl_dp,
         }
-        Some(reporter)
     }
 
     pub(super) fn ensure_status_file(&self) -> std::io::Result<()> {
@@ -380,7 +485,12 @@ impl StatusReporter {
             return;
         }
         self.last_equity_history_ts = Some(ts);
-        let point = EquityHistoryPoint { ts, equity };
+        let point = EquityHistoryPoint {
+            ts,
+            equity,
+            gross_notional: self.exposure.map(|(gross, _)| gross),
+            position_count: self.exposure.map(|(_, count)| count),
+        };
         let line = match serde_json::to_string(&point) {
             Ok(v) => v,
             Err(err) => {
@@ -413,11 +523,18 @@ impl StatusReporter {
     pub(super) fn update_equity(&mut self, equity: f64) {
         let today = Utc::now().date_naive();
         self.pnl_total = equity;
-        if !self.equity_day_start_set || self.pnl_today_date != today {
+        if self.equity_day_start_set && self.pnl_today_date != today {
+            self.start_baseline_sampling();
+        }
+        if !self.equity_day_start_set {
             self.pnl_today_date = today;
-            self.equity_day_start = equity;
-            self.equity_day_start_set = true;
-            self.persist_equity_baseline();
+            self.equity_baseline_pending.push(equity);
+            if self.equity_baseline_pending.len() >= self.equity_baseline_samples {
+                self.equity_day_start = median(&self.equity_baseline_pending);
+                self.equity_day_start_set = true;
+                self.equity_baseline_pending.clear();
+                self.persist_equity_baseline();
+            }
         }
         if self.equity_day_start_set {
             self.pnl_today = equity - self.equity_day_start;
@@ -441,6 +558,30 @@ impl StatusReporter {
         self.session_risk = risk;
     }
 
+    pub(super) fn set_pair_stats(&mut self, stats: Option<TradeStatsBook>) {
+        self.pair_stats = stats;
+    }
+
+    pub(super) fn set_startup_close(&mut self, status: Option<StartupCloseStatus>) {
+        self.startup_close = status;
+    }
+
+    pub(super) fn set_aging(&mut self, aging: Vec<AgingPosition>) {
+        self.aging = aging;
+    }
+
+    pub(super) fn set_exposure(&mut self, exposure: Option<(f64, usize)>) {
+        self.exposure = exposure;
+    }
+
+    pub(super) fn set_size_adjustments(&mut self, adjustments: BTreeMap<String, SizeAdjustment>) {
+        self.size_adjustments = adjustments;
+    }
+
+    pub(super) fn update_size_decimals(&mut self, decimals: impl IntoIterator<Item = (String, u32)>) {
+        self.size_decimals.extend(decimals);
+    }
+
     pub(super) fn write_snapshot(
         &mut self,
         open_positions: &HashMap<String, PositionSnapshot>,
@@ -457,8 +598,13 @@ impl StatusReporter {
                     Ordering::Less => "SHORT".to_string(),
                     Ordering::Equal => "FLAT".to_string(),
                 },
-                size: pos.size.to_string(),
-                entry_price: pos.entry_price.map(|v| v.to_string()),
+                size: match self.size_decimals.get(&pos.symbol) {
+                    Some(dp) => pos.size.round_dp(*dp).to_string(),
+                    None => pos.size.to_string(),
+                },
+                entry_price: pos
+                    .entry_price
+                    .map(|v| round_decimal(v, self.price_dp).to_string()),
             })
             .collect();
         let snapshot = StatusSnapshot {
@@ -474,8 +620,8 @@ impl StatusReporter {
             position_count: positions.len(),
             has_position: !positions.is_empty(),
             positions,
-            pnl_total: self.pnl_total,
-            pnl_today: self.pnl_today,
+            pnl_total: round_dp(self.pnl_total, self.pnl_dp),
+            pnl_today: round_dp(self.pnl_today, self.pnl_dp),
             pnl_source: "equity".to_string(),
             trade_stats: self.trade_stats.clone(),
             maintenance: self.maintenance.clone(),
@@ -483,6 +629,10 @@ impl StatusReporter {
             error_summary: error_counter::global().map(|h| h.snapshot()),
             daily_risk: self.daily_risk.clone(),
             session_risk: self.session_risk.clone(),
+            pair_stats: self.pair_stats.clone(),
+            startup_close: self.startup_close.clone(),
+            aging: self.aging.clone(),
+            size_adjustments: self.size_adjustments.clone(),
         };
         let payload = serde_json::to_string(&snapshot)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
@@ -518,10 +668,35 @@ impl StatusReporter {
         }
         let today = Utc::now().date_naive();
         if today != self.pnl_today_date {
+            if self.equity_baseline_samples > 1 {
+                // Wait for the new day's readings in `update_equity`.
+                self.start_baseline_sampling();
+                return;
+            }
             self.pnl_today_date = today;
             self.equity_day_start = self.pnl_total;
             self.persist_equity_baseline();
         }
         self.pnl_today = self.pnl_total - self.equity_day_start;
     }
+
+    /// Drop the current baseline and collect fresh readings for a new day.
+    fn start_baseline_sampling(&mut self) {
+        self.equity_day_start_set = false;
+        self.equity_baseline_pending.clear();
+        self.pnl_today = 0.0;
+    }
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    sorted[sorted.len() / 2]
+}
+
+fn round_decimal(value: Decimal, dp: Option<u32>) -> Decimal {
+    match dp {
+        Some(dp) => value.round_dp(dp).normalize(),
+        None => value,
+    }
 }
@@ -0,0 +1,234 @@
+//! Running trade-level statistics (count, wins, losses, gross PnL, average
+//! hold time), kept per pair and in aggregate. Fed from the same
+//! `PnlLogRecord` that goes to the pnl log on every exit, so the numbers
+//! match the journal. Distinct from the equity-based `pnl_today`: this is
+//! per-trade expectancy, used to judge whether a pair is worth keeping.
+//!
+//! With `persist_trade_stats` the books are mirrored to a versioned sidecar
+//! next to `risk_state.json` after every trade and reloaded on start, so an
+//! intraday restart does not zero them.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::config::TradeStatsReset;
+use super::session_day;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TradeStats {
+    pub trades: u64,
+    pub wins: u64,
+    pub losses: u64,
+    pub gross_pnl: f64,
+    /// Mean hold time over trades that reported one.
+    pub avg_hold_secs: f64,
+    /// Trades behind `avg_hold_secs`; kept so a reloaded book continues the
+    /// running mean.
+    #[serde(default)]
+    hold_samples: u64,
+}
+
+impl TradeStats {
+    fn record(&mut self, pnl: f64, hold_secs: Option<f64>) {
+        self.trades += 1;
+        if pnl > 0.0 {
+            self.wins += 1;
+        } else if pnl < 0.0 {
+            self.losses += 1;
+        }
+        self.gross_pnl += pnl;
+        if let Some(hold) = hold_secs {
+            self.hold_samples += 1;
+            self.avg_hold_secs += (hold - self.avg_hold_secs) / self.hold_samples as f64;
+        }
+    }
+
+    /// Average PnL per trade; 0.0 before the first trade.
+    pub fn expectancy(&self) -> f64 {
+        if self.trades == 0 {
+            0.0
+        } else {
+            self.gross_pnl / self.trades as f64
+        }
+    }
+
+    fn compact(&self) -> String {
+        format!(
+            "n={} w={} l={} pnl={:.4} hold={:.0}s",
+            self.trades, self.wins, self.losses, self.gross_pnl, self.avg_hold_secs
+        )
+    }
+}
+
+/// Per-pair and aggregate stats since `since_ts` (process start, or the
+/// last daily rollover when `trade_stats_reset: daily`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TradeStatsBook {
+    pub since_ts: i64,
+    pub aggregate: TradeStats,
+    pub pairs: BTreeMap<String, TradeStats>,
+}
+
+impl TradeStatsBook {
+    pub(super) fn record(&mut self, pair: &str, pnl: f64, hold_secs: Option<f64>) {
+        self.aggregate.record(pnl, hold_secs);
+        self.pairs
+            .entry(pair.to_string())
+            .or_default()
+            .record(pnl, hold_secs);
+    }
+
+    /// Start the window on first call and, in daily mode, clear the book
+    /// when `now_ts` crosses into a new session day. Returns true on reset.
+    pub(super) fn roll(&mut self, mode: TradeStatsReset, now_ts: i64, reset_hour: u32) -> bool {
+        if self.since_ts == 0 {
+            self.since_ts = now_ts;
+            return false;
+        }
+        if mode == TradeStatsReset::Daily
+            && session_day(now_ts, reset_hour) != session_day(self.since_ts, reset_hour)
+        {
+            *self = TradeStatsBook {
+                since_ts: now_ts,
+                ..Default::default()
+            };
+            return true;
+        }
+        false
+    }
+
+    /// One-line summary for the periodic `[METRICS]` log.
+    pub(super) fn summary_line(&self) -> String {
+        let mut parts = vec![format!("all {}", self.aggregate.compact())];
+        parts.extend(
+            self.pairs
+                .iter()
+                .map(|(pair, stats)| format!("{} {}", pair, stats.compact())),
+        );
+        parts.join(" | ")
+    }
+}
+
+const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Default)]
+struct TradeStatsSnapshot {
+    #[serde(rename = "_v")]
+    version: u32,
+    #[serde(default)]
+    instances: HashMap<String, TradeStatsBook>,
+}
+
+/// Write the per-instance books to `path` (tmpfile + rename).
+pub(super) fn persist_trade_stats(path: &Path, books: HashMap<String, TradeStatsBook>) {
+    let snapshot = TradeStatsSnapshot {
+        version: SNAPSHOT_VERSION,
+        instances: books,
+    };
+    let Ok(json) = serde_json::to_string(&snapshot) else {
+        log::warn!("[TRADE_STATS] serialize failed");
+        return;
+    };
+    let tmp = path.with_extension(format!("json.tmp.{}", std::process::id()));
+    if let Err(e) = fs::write(&tmp, json) {
+        log::warn!("[TRADE_STATS] tmp write failed: {:?}", e);
+        return;
+    }
+    if let Err(e) = fs::rename(&tmp, path) {
+        log::warn!("[TRADE_STATS] rename failed: {:?}", e);
+        let _ = fs::remove_file(&tmp);
+    }
+}
+
+/// Read the books written by `persist_trade_stats`. A missing file, a parse
+/// failure or a newer version all yield an empty map (fresh stats).
+pub(super) fn load_trade_stats(path: &Path) -> HashMap<String, TradeStatsBook> {
+    let content = match fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return HashMap::new(),
+        Err(e) => {
+            log::warn!("[TRADE_STATS] read failed ({}): {:?}", path.display(), e);
+            return HashMap::new();
+        }
+    };
+    match serde_json::from_str::<TradeStatsSnapshot>(&content) {
+        Ok(snap) if snap.version <= SNAPSHOT_VERSION => snap.instances,
+        Ok(snap) => {
+            log::warn!(
+                "[TRADE_STATS] {} has unsupported version {}; starting fresh",
+                path.display(),
+                snap.version
+            );
+            HashMap::new()
+        }
+        Err(e) => {
+            log::warn!("[TRADE_STATS] parse failed ({}): {:?}", path.display(), e);
+            HashMap::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_per_pair_and_aggregate() {
+        let mut book = TradeStatsBook::default();
+        book.record("BTC/ETH", 2.0, Some(600.0));
+        book.record("BTC/ETH", -1.0, Some(1200.0));
+        book.record("SOL/ETH", 0.5, None);
+
+        assert_eq!(book.aggregate.trades, 3);
+        assert_eq!(book.aggregate.wins, 2);
+        assert_eq!(book.aggregate.losses, 1);
+        assert!((book.aggregate.gross_pnl - 1.5).abs() < 1e-12);
+        assert!((book.aggregate.avg_hold_secs - 900.0).abs() < 1e-9);
+
+        let btc = &book.pairs["BTC/ETH"];
+        assert_eq!((btc.trades, btc.wins, btc.losses), (2, 1, 1));
+        assert!((btc.expectancy() - 0.5).abs() < 1e-12);
+        assert_eq!(book.pairs["SOL/ETH"].avg_hold_secs, 0.0);
+    }
+
+    #[test]
+    fn daily_mode_resets_on_rollover_session_mode_does_not() {
+        let day0 = 1_745_452_800; // 2025-04-24 00:00 UTC
+        for (mode, want_reset) in [
+            (TradeStatsReset::Daily, true),
+            (TradeStatsReset::Session, false),
+        ] {
+            let mut book = TradeStatsBook::default();
+            assert!(!book.roll(mode, day0 + 60, 0));
+            book.record("BTC/ETH", 1.0, None);
+            assert!(!book.roll(mode, day0 + 3600, 0));
+            assert_eq!(book.roll(mode, day0 + 86_400 + 60, 0), want_reset);
+            assert_eq!(book.aggregate.trades, if want_reset { 0 } else { 1 });
+        }
+    }
+
+    #[test]
+    fn persisted_books_round_trip_and_keep_running_mean() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trade_stats.json");
+        let mut book = TradeStatsBook {
+            since_ts: 100,
+            ..Default::default()
+        };
+        book.record("BTC/ETH", 2.0, Some(600.0));
+        persist_trade_stats(&path, HashMap::from([("default".to_string(), book)]));
+
+        let mut loaded = load_trade_stats(&path).remove("default").unwrap();
+        assert_eq!(loaded.since_ts, 100);
+        assert_eq!(loaded.pairs["BTC/ETH"].trades, 1);
+        loaded.record("BTC/ETH", -1.0, Some(1200.0));
+        assert!((loaded.aggregate.avg_hold_secs - 900.0).abs() < 1e-9);
+        assert!((loaded.aggregate.gross_pnl - 1.0).abs() < 1e-12);
+
+        fs::write(&path, r#"{"_v":99,"instances":{}}"#).unwrap();
+        assert!(load_trade_stats(&path).is_empty());
+    }
+}
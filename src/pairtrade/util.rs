@@ -22,6 +22,33 @@ pub(super) fn mean_std(window: &VecDeque<f64>) -> Option<(f64, f64)> {
     Some((mean, var.sqrt()))
 }
 
+/// First `(name, value)` whose value is NaN or infinite.
+pub(super) fn first_non_finite(values: &[(&'static str, f64)]) -> Option<(&'static str, f64)> {
+    values.iter().copied().find(|(_, v)| !v.is_finite())
+}
+
+/// Median and MAD scaled by 1.4826, which matches the standard deviation
+/// for normal data but ignores a minority of outliers.
+pub(super) fn median_mad(window: &VecDeque<f64>) -> Option<(f64, f64)> {
+    fn median(sorted: &[f64]) -> f64 {
+        let mid = sorted.len() / 2;
+        if sorted.len().is_multiple_of(2) {
+            (sorted[mid - 1] + sorted[mid]) / 2.0
+        } else {
+            sorted[mid]
+        }
+    }
+    if window.is_empty() {
+        return None;
+    }
+    let mut values: Vec<f64> = window.iter().copied().collect();
+    values.sort_by(|a, b| a.total_cmp(b));
+    let center = median(&values);
+    let mut deviations: Vec<f64> = values.iter().map(|v| (v - center).abs()).collect();
+    deviations.sort_by(|a, b| a.total_cmp(b));
+    Some((center, median(&deviations) * 1.4826))
+}
+
 pub(super) fn half_life_and_p(spreads: &[f64]) -> (f64, f64) {
     // ADF-style AR(1) on levels: dY_t = phi * Y_{t-1} + eps
     if spreads.len() < 5 {
@@ -6,6 +6,7 @@ use dex_connector::{
     OrderBookLevel, OrderBookSnapshot, OrderSide, PositionSnapshot, TickerResponse, TpSl,
     TriggerOrderStyle,
 };
+use flate2::read::MultiGzDecoder;
 use rand;
 use rust_decimal::Decimal;
 use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
@@ -13,7 +14,9 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::Mutex;
 
 // Data structures that mirror the JSONL dump file
 #[derive(Debug, Clone, Deserialize)]
@@ -130,19 +133,22 @@ impl From<BincodeDataEntry> for DumpedDataEntry {
     }
 }
 
+// Consecutive `from_files` inputs further apart than this are reported as a
+// gap in the replay stream.
+const FILE_GAP_WARN_MS: i64 = 10 * 60 * 1000;
+
 #[derive(Debug)]
 pub struct ReplayConnector {
     data: Vec<DumpedDataEntry>,
     cursor: AtomicUsize,
+    // Paper position book reported by `get_positions`; the engine mirrors
+    // its own positions here when `backtest_full_sync` is on.
+    paper_positions: Mutex<Vec<PositionSnapshot>>,
 }
 
 impl ReplayConnector {
     pub fn new(path: &str) -> Result<Self, DexError> {
-        let data = if path.ends_with(".bin") {
-            Self::load_bincode(path)?
-        } else {
-            Self::load_jsonl(path)?
-        };
+        let data = Self::load_path(path)?;
 
         if data.is_empty() {
             return Err(DexError::Other(
@@ -153,13 +159,98 @@ impl ReplayConnector {
         Ok(Self {
             data,
             cursor: AtomicUsize::new(0),
+            paper_positions: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Replay several dumps back to back as one stream. Entries may use `*`
+    /// in the file name; matches are taken in name order. A file starting
+    /// before the previous one started is an error; gaps and overlaps
+    /// between consecutive files are logged, and records not after the
+    /// stream's last timestamp are dropped.
+    pub fn from_files(paths: &[String]) -> Result<Self, DexError> {
+        let files = expand_replay_paths(paths)?;
+        let mut data: Vec<DumpedDataEntry> = Vec::new();
+        let mut prev: Option<(String, i64, i64)> = None;
+        for path in &files {
+            let entries = Self::load_path(path)?;
+            let (Some(first), Some(last)) = (
+                entries.first().map(|e| e.timestamp),
+                entries.last().map(|e| e.timestamp),
+            ) else {
+                log::warn!("[REPLAY] {} is empty; skipped", path);
+                continue;
+            };
+            if let Some((prev_path, prev_first, prev_last)) = &prev {
+                if first < *prev_first {
+                    return Err(DexError::Other(format!(
+                        "replay files out of order: {} starts before {}",
+                        path, prev_path
+                    )));
+                }
+                if first <= *prev_last {
+                    let dropped = entries.iter().filter(|e| e.timestamp <= *prev_last).count();
+                    log::warn!(
+                        "[REPLAY] {} overlaps {} by {}s; dropping {} records",
+                        path,
+                        prev_path,
+                        (prev_last - first) / 1000,
+                        dropped
+                    );
+                } else if first - prev_last > FILE_GAP_WARN_MS {
+                    log::warn!(
+                        "[REPLAY] gap of {}s between {} and {}",
+                        (first - prev_last) / 1000,
+                        prev_path,
+                        path
+                    );
+                }
+            }
+            let cutoff = data.last().map(|e| e.timestamp);
+            data.extend(
+                entries
+                    .into_iter()
+                    .filter(|e| cutoff.is_none_or(|c| e.timestamp > c)),
+            );
+            let stream_last = data.last().map_or(last, |e| e.timestamp);
+            prev = Some((path.clone(), first, stream_last));
+        }
+
+        if data.is_empty() {
+            return Err(DexError::Other(
+                anyhow!("Data dump files are empty or invalid").to_string(),
+            ));
+        }
+        log::info!(
+            "[REPLAY] loaded {} records from {} files",
+            data.len(),
+            files.len()
+        );
+
+        Ok(Self {
+            data,
+            cursor: AtomicUsize::new(0),
+            paper_positions: Mutex::new(Vec::new()),
         })
     }
 
+    fn load_path(path: &str) -> Result<Vec<DumpedDataEntry>, DexError> {
+        if path.ends_with(".bin") {
+            Self::load_bincode(path)
+        } else {
+            Self::load_jsonl(path)
+        }
+    }
+
     fn load_jsonl(path: &str) -> Result<Vec<DumpedDataEntry>, DexError> {
         let file = File::open(path)
             .map_err(|e| DexError::Other(format!("failed to open replay file: {}", e)))?;
-        let reader = BufReader::new(file);
+        // Compressed dumps may hold several gzip members (one per run).
+        let reader: Box<dyn BufRead> = if path.ends_with(".gz") {
+            Box::new(BufReader::new(MultiGzDecoder::new(file)))
+        } else {
+            Box::new(BufReader::new(file))
+        };
         let mut data = Vec::new();
 
         for line in reader.lines() {
@@ -250,6 +341,11 @@ impl ReplayConnector {
         }
     }
 
+    /// Replace the paper position book served by `get_positions`.
+    pub fn set_paper_positions(&self, positions: Vec<PositionSnapshot>) {
+        *self.paper_positions.lock().unwrap() = positions;
+    }
+
     pub fn current_timestamp_secs(&self) -> Option<i64> {
         let current_cursor = self.cursor.load(AtomicOrdering::SeqCst);
         self.data.get(current_cursor).map(|e| e.timestamp / 1000) // stored as ms
@@ -260,8 +356,71 @@ impl ReplayConnector {
         Self {
             data,
             cursor: AtomicUsize::new(0),
+            paper_positions: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// A connector with no market data, for engine tests that only need the
+    /// paper position book.
+    #[cfg(test)]
+    pub(crate) fn empty() -> Self {
+        Self::from_entries(Vec::new())
+    }
+}
+
+/// Expand `*` in the file-name part of each path, in name order. Paths
+/// without a wildcard pass through; a pattern matching nothing is an error.
+fn expand_replay_paths(paths: &[String]) -> Result<Vec<String>, DexError> {
+    let mut files = Vec::new();
+    for path in paths {
+        let path_ref = Path::new(path);
+        let name = path_ref.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if !name.contains('*') {
+            files.push(path.clone());
+            continue;
+        }
+        let dir = match path_ref.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut matched: Vec<String> = std::fs::read_dir(dir)
+            .map_err(|e| DexError::Other(format!("failed to list {}: {}", dir.display(), e)))?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|n| wildcard_match(name, n))
+            })
+            .map(|entry| entry.path().to_string_lossy().into_owned())
+            .collect();
+        if matched.is_empty() {
+            return Err(DexError::Other(format!("no replay files match {}", path)));
         }
+        matched.sort();
+        files.extend(matched);
     }
+    Ok(files)
+}
+
+/// `*`-only glob match over a whole file name.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if parts.len() == 1 {
+        return pattern == name;
+    }
+    if name.len() < first.len() + last.len() || !name.starts_with(first) || !name.ends_with(last) {
+        return false;
+    }
+    let mut rest = &name[first.len()..name.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    true
 }
 
 #[async_trait]
@@ -356,7 +515,7 @@ impl DexConnector for ReplayConnector {
     }
 
     async fn get_positions(&self) -> Result<Vec<PositionSnapshot>, DexError> {
-        Ok(Vec::new())
+        Ok(self.paper_positions.lock().unwrap().clone())
     }
 
     async fn get_last_trades(&self, _symbol: &str) -> Result<LastTradesResponse, DexError> {
@@ -596,4 +755,65 @@ mod tests {
             "must use per-symbol exchange_ts, not top-level timestamp/1000",
         );
     }
+
+    #[tokio::test]
+    async fn get_positions_serves_the_paper_book() {
+        let r = ReplayConnector::from_entries(vec![mk_entry(1_776_229_320_000, 71_000.0, None)]);
+        assert!(r.get_positions().await.unwrap().is_empty());
+
+        r.set_paper_positions(vec![PositionSnapshot {
+            symbol: "BTC".to_string(),
+            size: Decimal::ONE,
+            sign: -1,
+            ..Default::default()
+        }]);
+        let positions = r.get_positions().await.unwrap();
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].symbol, "BTC");
+        assert_eq!(positions[0].sign, -1);
+    }
+
+    fn write_dump(dir: &Path, name: &str, timestamps_ms: &[i64]) {
+        let lines: Vec<String> = timestamps_ms
+            .iter()
+            .map(|ts| {
+                format!(
+                    r#"{{"timestamp":{},"prices":{{"BTC":{{"price":"100","funding_rate":"0","bid_size":"0","ask_size":"0"}}}}}}"#,
+                    ts
+                )
+            })
+            .collect();
+        std::fs::write(dir.join(name), lines.join("\n")).unwrap();
+    }
+
+    #[test]
+    fn from_files_concatenates_in_order_and_drops_overlap() {
+        let dir = tempfile::tempdir().unwrap();
+        write_dump(dir.path(), "dump_2025-01.jsonl", &[1_000, 2_000, 3_000]);
+        // Overlaps the previous file by one record.
+        write_dump(dir.path(), "dump_2025-02.jsonl", &[3_000, 4_000]);
+        write_dump(dir.path(), "other.jsonl", &[9_000]);
+
+        let pattern = dir.path().join("dump_*.jsonl").to_string_lossy().into_owned();
+        let r = ReplayConnector::from_files(&[pattern]).unwrap();
+        let ts: Vec<i64> = r.data.iter().map(|e| e.timestamp).collect();
+        assert_eq!(ts, vec![1_000, 2_000, 3_000, 4_000]);
+
+        let out_of_order = [
+            dir.path().join("dump_2025-02.jsonl").to_string_lossy().into_owned(),
+            dir.path().join("dump_2025-01.jsonl").to_string_lossy().into_owned(),
+        ];
+        assert!(ReplayConnector::from_files(&out_of_order).is_err());
+        let missing = dir.path().join("none_*.jsonl").to_string_lossy().into_owned();
+        assert!(ReplayConnector::from_files(&[missing]).is_err());
+    }
+
+    #[test]
+    fn wildcard_matches_whole_name() {
+        assert!(wildcard_match("dump_*.bin", "dump_2025-01.bin"));
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("a*b*c", "axxbyyc"));
+        assert!(!wildcard_match("dump_*.bin", "dump_2025-01.jsonl"));
+        assert!(!wildcard_match("a*a", "a"));
+    }
 }
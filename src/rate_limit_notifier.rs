@@ -1,10 +1,23 @@
-use crate::email_client::EmailClient;
+use crate::notifier::{self, Severity};
 use once_cell::sync::Lazy;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 static RATE_LIMIT_NOTIFIER: Lazy<RateLimitNotifier> = Lazy::new(RateLimitNotifier::new);
 
+/// Process-wide count of rate-limit responses seen by any connector. The
+/// pairtrade live loop polls it to back its interval off.
+static RATE_LIMIT_HITS: AtomicU64 = AtomicU64::new(0);
+
+pub fn record_rate_limit_hit() {
+    RATE_LIMIT_HITS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn rate_limit_hit_count() -> u64 {
+    RATE_LIMIT_HITS.load(Ordering::Relaxed)
+}
+
 /// Host-shared dedup file for the Lighter WAF cooldown email. Sibling of
 /// `/tmp/lighter_waf_cooldown` (the cooldown deadline file written by
 /// dex-connector). Multiple bot processes on the same host check this so that
@@ -75,9 +88,9 @@ impl RateLimitNotifier {
             context, detail
         );
 
-        EmailClient::new().send(&subject, &body);
+        notifier::notify(Severity::Warning, &subject, &body);
         log::info!(
-            "📧 [RateLimit] Email notification sent for '{}' (detail: {})",
+            "📧 [RateLimit] Alert sent for '{}' (detail: {})",
             context,
             detail
         );
@@ -104,9 +117,9 @@ impl RateLimitNotifier {
             context, until_unix
         );
 
-        EmailClient::new().send(&subject, &body);
+        notifier::notify(Severity::Warning, &subject, &body);
         log::info!(
-            "📧 [RateLimit] Lighter WAF cooldown email sent (until_unix={}, context={})",
+            "📧 [RateLimit] Lighter WAF cooldown alert sent (until_unix={}, context={})",
             until_unix,
             context
         );
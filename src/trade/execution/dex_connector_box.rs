@@ -16,9 +16,14 @@ use crate::config::get_lighter_config_from_env;
 #[cfg(feature = "extended-sdk")]
 use crate::config::get_extended_config_from_env;
 use crate::config::{get_hyperliquid_config_from_env, RunMode};
-use crate::rate_limit_notifier::{notify_lighter_waf_cooldown, notify_rate_limit};
+use crate::rate_limit_notifier::{
+    notify_lighter_waf_cooldown, notify_rate_limit, record_rate_limit_hit,
+};
 use lazy_static::lazy_static;
+use std::collections::HashMap;
 use std::env;
+use std::sync::Mutex;
+use std::time::Instant;
 
 lazy_static! {
     static ref FILLED_PROBABILITY_IN_EMULATION: Decimal = {
@@ -31,21 +36,87 @@ lazy_static! {
 
 pub struct DexConnectorBox {
     pub inner: Box<dyn DexConnector>,
+    order_limiter: Option<OrderRateLimiter>,
+}
+
+/// Per-symbol token bucket over opening order placement. Each
+/// symbol holds up to `max_per_min` tokens and refills continuously at
+/// `max_per_min / 60` per second; a call without a token is rejected rather
+/// than queued, so a runaway reissue/chase loop surfaces as order errors
+/// instead of an exchange-side rate-limit ban.
+struct OrderRateLimiter {
+    max_per_min: u32,
+    buckets: Mutex<HashMap<String, (f64, Instant)>>,
+}
+
+impl OrderRateLimiter {
+    fn new(max_per_min: u32) -> Self {
+        Self {
+            max_per_min,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn try_acquire(&self, symbol: &str, now: Instant) -> bool {
+        let capacity = self.max_per_min as f64;
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let (tokens, last) = buckets
+            .entry(symbol.to_string())
+            .or_insert((capacity, now));
+        let refill = now.saturating_duration_since(*last).as_secs_f64() * capacity / 60.0;
+        *tokens = (*tokens + refill).min(capacity);
+        *last = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
 }
 
 impl DexConnectorBox {
+    /// Cap opening `create_order` calls at `max_per_min` per symbol per
+    /// minute (0 = unlimited). Cancels and reduce-only orders only take risk
+    /// off, so they are never held back by the limiter.
+    pub fn with_order_rate_limit(mut self, max_per_min: u32) -> Self {
+        self.order_limiter = (max_per_min > 0).then(|| OrderRateLimiter::new(max_per_min));
+        self
+    }
+
+    fn check_order_rate(&self, operation: &str, symbol: &str) -> Result<(), DexError> {
+        let Some(limiter) = &self.order_limiter else {
+            return Ok(());
+        };
+        if limiter.try_acquire(symbol, Instant::now()) {
+            return Ok(());
+        }
+        log::warn!(
+            "[RateLimit] {} {} rejected locally: over {} orders/min for this symbol",
+            operation,
+            symbol,
+            limiter.max_per_min
+        );
+        Err(DexError::Other(format!(
+            "order rate limit exceeded for {}: max {} per minute",
+            symbol, limiter.max_per_min
+        )))
+    }
+
     fn report_rate_limit(&self, operation: &str, detail: &str, err: &DexError) {
         // New structured form of the Lighter WAF cooldown (HTTP 405 +
         // x-amzn-waf-action: captcha or HTTP 429). Send a single deduped email
         // per engagement event across all bot processes on this host. See
         // bot-strategy#35.
         if let DexError::RateLimited { until_unix } = err {
+            record_rate_limit_hit();
             let context = format!("{} ({})", operation, detail);
             notify_lighter_waf_cooldown(*until_unix, &context);
             return;
         }
         let err_text = err.to_string();
         if err_text.contains("429") || err_text.contains("Too Many Requests") {
+            record_rate_limit_hit();
             let context = format!("{} ({})", operation, detail);
             notify_rate_limit(&context, &err_text);
         }
@@ -97,6 +168,7 @@ impl DexConnectorBox {
 
                 Ok(DexConnectorBox {
                     inner: Box::new(connector),
+                    order_limiter: None,
                 })
             }
             #[cfg(feature = "lighter-sdk")]
@@ -157,10 +229,14 @@ impl DexConnectorBox {
                     let connector = LighterConnector::new(connector_config)?;
                     Ok(DexConnectorBox {
                         inner: Box::new(connector),
+                        order_limiter: None,
                     })
                 } else {
                     let connector = create_lighter_connector(connector_config)?;
-                    Ok(DexConnectorBox { inner: connector })
+                    Ok(DexConnectorBox {
+                        inner: connector,
+                        order_limiter: None,
+                    })
                 }
             }
             #[cfg(feature = "extended-sdk")]
@@ -180,7 +256,10 @@ impl DexConnectorBox {
                 )
                 .await?;
 
-                Ok(DexConnectorBox { inner: connector })
+                Ok(DexConnectorBox {
+                    inner: connector,
+                    order_limiter: None,
+                })
             }
             _ => Err(DexError::Other("Unsupported dex".to_owned())),
         }
@@ -344,6 +423,9 @@ impl DexConnector for DexConnectorBox {
         reduce_only: bool,
         expiry_secs: Option<u64>,
     ) -> Result<CreateOrderResponse, DexError> {
+        if !reduce_only {
+            self.check_order_rate("create_order", symbol)?;
+        }
         let result = self
             .inner
             .create_order(symbol, size, side, price, spread, reduce_only, expiry_secs)
@@ -497,3 +579,23 @@ impl DexConnector for DexConnectorBox {
         self.inner.get_positions().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn order_rate_limiter_refills_per_symbol() {
+        let limiter = OrderRateLimiter::new(2);
+        let t0 = Instant::now();
+        assert!(limiter.try_acquire("BTC", t0));
+        assert!(limiter.try_acquire("BTC", t0));
+        assert!(!limiter.try_acquire("BTC", t0));
+        // Other symbols have their own bucket.
+        assert!(limiter.try_acquire("ETH", t0));
+        // 2/min refills one token every 30s.
+        assert!(!limiter.try_acquire("BTC", t0 + Duration::from_secs(20)));
+        assert!(limiter.try_acquire("BTC", t0 + Duration::from_secs(31)));
+    }
+}